@@ -0,0 +1,224 @@
+//! Criterion benchmarks for the core transforms, on synthetic frames from
+//! `mlprep::bench`.
+//!
+//! ```text
+//! cargo bench --bench transforms
+//! MLPREP_BENCH_ROWS=1000000,10000000 cargo bench --bench transforms -- group_by
+//! ```
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use mlprep::bench::{parse_schema, synthetic_frame, DEFAULT_SCHEMA};
+use mlprep::builder::PipelineBuilder;
+use mlprep::dsl::{CheckConfig, ColumnCheck, Pipeline, Step, ValidationMode, Window, WindowOp};
+use mlprep::features::{self, FeatureConfig, FeatureSpec, FeatureTransform};
+use polars::prelude::*;
+use std::sync::OnceLock;
+
+/// Frame sizes from `MLPREP_BENCH_ROWS` (comma-separated), 1M rows by default.
+fn sizes() -> Vec<usize> {
+    std::env::var("MLPREP_BENCH_ROWS")
+        .ok()
+        .map(|rows| {
+            rows.split(',')
+                .map(|n| {
+                    n.trim()
+                        .parse()
+                        .expect("MLPREP_BENCH_ROWS must be row counts")
+                })
+                .collect()
+        })
+        .unwrap_or_else(|| vec![1_000_000])
+}
+
+/// Frames are generated once and shared by every benchmark.
+fn frames() -> &'static [(usize, DataFrame)] {
+    static FRAMES: OnceLock<Vec<(usize, DataFrame)>> = OnceLock::new();
+    FRAMES.get_or_init(|| {
+        // `region` keeps one-hot output small enough for 10M-row frames.
+        let columns = parse_schema(&format!("{},region:str:16", DEFAULT_SCHEMA)).unwrap();
+        sizes()
+            .into_iter()
+            .map(|rows| (rows, synthetic_frame(&columns, rows, 42).unwrap()))
+            .collect()
+    })
+}
+
+fn bench_pipeline(c: &mut Criterion, name: &str, pipeline: &Pipeline) {
+    let mut group = c.benchmark_group(name);
+    group.sample_size(10);
+    for (rows, df) in frames() {
+        let rows = *rows;
+        group.throughput(Throughput::Elements(rows as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(rows), df, |b, df| {
+            b.iter(|| mlprep::engine::run_in_memory(df.clone().lazy(), pipeline).unwrap())
+        });
+    }
+    group.finish();
+}
+
+fn filter(c: &mut Criterion) {
+    let pipeline = PipelineBuilder::new()
+        .filter("price > 500 AND active AND quantity < 250000")
+        .build();
+    bench_pipeline(c, "filter", &pipeline);
+}
+
+fn group_by(c: &mut Criterion) {
+    let pipeline = PipelineBuilder::new()
+        .group_by(["category"], [("price", "mean"), ("quantity", "sum")])
+        .build();
+    bench_pipeline(c, "group_by", &pipeline);
+}
+
+fn window(c: &mut Criterion) {
+    let op = |column: &str, func: &str| WindowOp {
+        column: column.to_string(),
+        func: func.to_string(),
+        alias: format!("{}_{}", column, func),
+    };
+    let pipeline = PipelineBuilder::new()
+        .step(Step::Window(Window {
+            partition_by: vec!["category".to_string()],
+            order_by: None,
+            ops: vec![op("price", "mean"), op("quantity", "cumsum")],
+        }))
+        .build();
+    bench_pipeline(c, "window", &pipeline);
+}
+
+fn one_hot(c: &mut Criterion) {
+    let spec = FeatureSpec::new("region", FeatureTransform::OneHotEncode);
+    let config = FeatureConfig {
+        features: vec![spec.clone()],
+    };
+    let mut group = c.benchmark_group("one_hot");
+    group.sample_size(10);
+    for (rows, df) in frames() {
+        let rows = *rows;
+        group.throughput(Throughput::Elements(rows as u64));
+        group.bench_with_input(BenchmarkId::new("fit", rows), df, |b, df| {
+            b.iter(|| features::fit_features_lazy(df.clone().lazy(), &config, false).unwrap())
+        });
+        let state = features::fit_features_lazy(df.clone().lazy(), &config, false).unwrap();
+        let entry = state.get_entry("region", &spec.transform).unwrap();
+        group.bench_with_input(BenchmarkId::new("transform", rows), df, |b, df| {
+            b.iter(|| {
+                let exprs = features::exprs_from_state(&spec, entry).unwrap();
+                df.clone().lazy().with_columns(exprs).collect().unwrap()
+            })
+        });
+    }
+    group.finish();
+}
+
+/// The eager transform used by `fit_transform`, on a 500-category column.
+fn one_hot_wide(c: &mut Criterion) {
+    let columns = parse_schema("sku:str:500").unwrap();
+    let mut group = c.benchmark_group("one_hot_wide");
+    group.sample_size(10);
+    for rows in sizes() {
+        let df = synthetic_frame(&columns, rows, 42).unwrap();
+        let vocab = features::fit_onehot(&df, "sku").unwrap();
+        group.throughput(Throughput::Elements(rows as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(rows), &df, |b, df| {
+            b.iter(|| features::transform_onehot(df, "sku", &vocab, None).unwrap())
+        });
+    }
+    group.finish();
+}
+
+/// Not-null, range, regex and enum checks over three columns.
+fn checks() -> CheckConfig {
+    let check = |name: &str| ColumnCheck {
+        name: name.to_string(),
+        not_null: true,
+        unique: false,
+        range: None,
+        min: None,
+        max: None,
+        regex: None,
+        allowed_values: None,
+        nulls: None,
+        unique_nulls: None,
+        on_violation: None,
+        severity: None,
+    };
+    CheckConfig {
+        columns: vec![
+            ColumnCheck {
+                range: Some((0.0, 900.0)),
+                ..check("price")
+            },
+            ColumnCheck {
+                regex: Some("^category_[0-9]+$".to_string()),
+                ..check("category")
+            },
+            ColumnCheck {
+                allowed_values: Some((0..12).map(|k| format!("region_{}", k).into()).collect()),
+                nulls: None,
+                unique_nulls: None,
+                on_violation: None,
+                ..check("region")
+            },
+        ],
+        dataset: None,
+        stats: Vec::new(),
+        unique_keys: Vec::new(),
+        conditions: Vec::new(),
+    }
+}
+
+fn validation_mask(c: &mut Criterion) {
+    let checks = checks();
+    let mut group = c.benchmark_group("validation_mask");
+    group.sample_size(10);
+    for (rows, df) in frames() {
+        let rows = *rows;
+        group.throughput(Throughput::Elements(rows as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(rows), df, |b, df| {
+            b.iter(|| {
+                let mask = mlprep::validate::violation_mask_expr(&checks)
+                    .unwrap()
+                    .unwrap();
+                df.clone().lazy().select([mask.sum()]).collect().unwrap()
+            })
+        });
+    }
+    group.finish();
+}
+
+/// Eager `run_validation` in quarantine mode: per-check counts plus the row split.
+fn validation_eager(c: &mut Criterion) {
+    let checks = checks();
+    let masker = mlprep::security::Masker::new(vec![]);
+    let mut group = c.benchmark_group("validation_eager");
+    group.sample_size(10);
+    for (rows, df) in frames() {
+        let rows = *rows;
+        group.throughput(Throughput::Elements(rows as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(rows), df, |b, df| {
+            b.iter(|| {
+                mlprep::validate::run_validation(
+                    df.clone(),
+                    &checks,
+                    &ValidationMode::Quarantine,
+                    &masker,
+                )
+                .unwrap()
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    filter,
+    group_by,
+    window,
+    one_hot,
+    one_hot_wide,
+    validation_mask,
+    validation_eager
+);
+criterion_main!(benches);
@@ -0,0 +1,66 @@
+"""Shared helpers for the Airflow and Prefect integrations."""
+
+import logging
+
+import mlprep
+
+
+def progress_logger(logger: logging.Logger):
+    """Return a run_pipeline progress callback that logs stages and steps."""
+
+    def on_progress(event):
+        if event["event"] == "stage":
+            logger.info("mlprep stage: %s", event["stage"])
+        elif event["event"] == "step":
+            logger.info(
+                "mlprep step %d/%d: %s", event["index"], event["total"], event["step"]
+            )
+
+    return on_progress
+
+
+def run_logged(pipeline, logger, streaming=None, memory_limit=None, udfs=None):
+    """Run a pipeline file, logging progress and the final summary."""
+    logger.info("Running mlprep pipeline %s", pipeline)
+    result = mlprep.run_pipeline(
+        str(pipeline),
+        streaming=streaming,
+        memory_limit=memory_limit,
+        udfs=udfs,
+        progress=progress_logger(logger),
+    )
+    logger.info(
+        "mlprep run %s finished: %d rows read, %d rows written, %d violations "
+        "in %d ms",
+        result.run_id,
+        result.rows_read,
+        result.rows_written,
+        result.violations,
+        result.duration_ms,
+    )
+    if result.lineage:
+        logger.info("Lineage written to %s", result.lineage)
+    return result
+
+
+def summary_markdown(result) -> str:
+    """Render a run result as a Markdown table (used for Prefect artifacts)."""
+    rows = [
+        ("run_id", result.run_id),
+        ("pipeline", result.pipeline),
+        ("status", result.status),
+        ("rows_read", result.rows_read),
+        ("rows_written", result.rows_written),
+        ("violations", result.violations),
+        ("attempts", result.attempts),
+        ("duration_ms", result.duration_ms),
+        ("outputs", ", ".join(result.outputs)),
+        ("lineage", result.lineage or ""),
+    ]
+    rows += [
+        (f"step:{name}", f"{ms} ms")
+        for name, ms in sorted(result.step_durations_ms.items())
+    ]
+    lines = ["| metric | value |", "|---|---|"]
+    lines += [f"| {name} | {value} |" for name, value in rows]
+    return "\n".join(lines)
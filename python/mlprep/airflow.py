@@ -0,0 +1,69 @@
+"""Airflow operator for running mlprep pipelines.
+
+Requires ``apache-airflow`` (``pip install mlprep-rust[airflow]``)::
+
+    from mlprep.airflow import MlPrepOperator
+
+    prep = MlPrepOperator(task_id="prep", pipeline="pipelines/train.yaml")
+
+The run summary (``RunResult.to_dict()``) is the task's return value, so it lands in
+the ``return_value`` XCom. The lineage path, run id and row counts are also pushed
+under their own keys for downstream tasks.
+"""
+
+try:
+    from airflow.sdk import BaseOperator
+except ImportError:  # Airflow 2.x
+    from airflow.models import BaseOperator
+
+from ._orchestration import run_logged
+
+
+class MlPrepOperator(BaseOperator):
+    """Run an mlprep pipeline YAML file as an Airflow task.
+
+    Args:
+        pipeline: Path to the pipeline YAML file (templated).
+        streaming: Override the pipeline's streaming setting.
+        memory_limit: Override the memory limit, e.g. ``"4GB"`` (templated).
+        udfs: Python UDFs referenced by ``python_udf`` steps.
+        push_xcom_metrics: Push ``run_id``, ``lineage``, ``rows_written`` and
+            ``violations`` as separate XComs.
+    """
+
+    template_fields = ("pipeline", "memory_limit")
+    template_ext = ()
+    ui_color = "#f0d9b5"
+
+    def __init__(
+        self,
+        *,
+        pipeline,
+        streaming=None,
+        memory_limit=None,
+        udfs=None,
+        push_xcom_metrics=True,
+        **kwargs,
+    ):
+        super().__init__(**kwargs)
+        self.pipeline = pipeline
+        self.streaming = streaming
+        self.memory_limit = memory_limit
+        self.udfs = udfs
+        self.push_xcom_metrics = push_xcom_metrics
+
+    def execute(self, context):
+        result = run_logged(
+            self.pipeline,
+            self.log,
+            streaming=self.streaming,
+            memory_limit=self.memory_limit,
+            udfs=self.udfs,
+        )
+        if self.push_xcom_metrics:
+            ti = context["ti"]
+            ti.xcom_push(key="run_id", value=result.run_id)
+            ti.xcom_push(key="lineage", value=result.lineage)
+            ti.xcom_push(key="rows_written", value=result.rows_written)
+            ti.xcom_push(key="violations", value=result.violations)
+        return result.to_dict()
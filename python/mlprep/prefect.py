@@ -0,0 +1,53 @@
+"""Prefect task for running mlprep pipelines.
+
+Requires ``prefect`` (``pip install mlprep-rust[prefect]``)::
+
+    from prefect import flow
+    from mlprep.prefect import run_pipeline_task
+
+    @flow
+    def prep():
+        summary = run_pipeline_task("pipelines/train.yaml")
+
+The task returns the run summary (``RunResult.to_dict()``) and publishes a Markdown
+artifact with the run's metrics and lineage path.
+"""
+
+from prefect import get_run_logger, task
+from prefect.artifacts import create_markdown_artifact
+
+from ._orchestration import run_logged, summary_markdown
+
+
+@task(name="mlprep-run-pipeline")
+def run_pipeline_task(
+    pipeline,
+    streaming=None,
+    memory_limit=None,
+    udfs=None,
+    artifact_key="mlprep-run",
+):
+    """Run an mlprep pipeline YAML file and publish its metrics as an artifact.
+
+    Args:
+        pipeline: Path to the pipeline YAML file.
+        streaming: Override the pipeline's streaming setting.
+        memory_limit: Override the memory limit, e.g. ``"4GB"``.
+        udfs: Python UDFs referenced by ``python_udf`` steps.
+        artifact_key: Key of the published artifact (lowercase letters, digits and
+            dashes); ``None`` skips publishing.
+    """
+    result = run_logged(
+        pipeline,
+        get_run_logger(),
+        streaming=streaming,
+        memory_limit=memory_limit,
+        udfs=udfs,
+    )
+    if artifact_key is not None:
+        create_markdown_artifact(
+            key=artifact_key,
+            markdown=summary_markdown(result),
+            description=f"mlprep run {result.run_id}",
+        )
+    return result.to_dict()
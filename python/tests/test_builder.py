@@ -0,0 +1,88 @@
+"""Test suite for the fluent mlprep.Pipeline builder."""
+
+import os
+import tempfile
+
+import mlprep
+import polars as pl
+import pytest
+
+
+def _frame():
+    return pl.DataFrame({"g": ["x", "y", "x"], "a": [1, 2, 3], "b": [10, 20, 30]})
+
+
+class TestPipelineBuilder:
+    """Tests for building, serializing and running mlprep.Pipeline."""
+
+    def test_methods_return_new_builders(self):
+        """Each call returns a new builder and leaves the original untouched."""
+        base = mlprep.Pipeline().filter("a > 1")
+        extended = base.select(["a"])
+        assert len(base) == 1
+        assert len(extended) == 2
+
+    def test_collect(self):
+        """Steps are applied to an in-memory DataFrame."""
+        out = (
+            mlprep.Pipeline()
+            .filter("a > 1")
+            .group_by("g", {"b": "sum", "a": {"func": "max", "alias": "a_max"}})
+            .sort("g")
+            .collect(_frame())
+        )
+        assert out["g"].to_list() == ["x", "y"]
+        assert out["b"].to_list() == [30, 20]
+        assert out["a_max"].to_list() == [3, 2]
+
+    def test_yaml_roundtrip(self):
+        """to_yaml produces pipeline YAML that parses back to the same steps."""
+        pipeline = (
+            mlprep.Pipeline()
+            .input("data.csv")
+            .cast({"a": "float64"})
+            .fill_null("a", "literal", value=0)
+            .limit(10)
+            .output("out.parquet", mode="append")
+        )
+        yaml = pipeline.to_yaml()
+        assert "null" not in yaml
+        assert "type: fill_null" in yaml
+        assert "mode: append" in yaml
+        assert mlprep.Pipeline.from_yaml(yaml).to_yaml() == yaml
+
+    def test_generic_step(self):
+        """Steps without a dedicated method can be added as dicts."""
+        pipeline = mlprep.Pipeline().step(
+            {"type": "features", "config": {"features": []}}
+        )
+        assert "type: features" in pipeline.to_yaml()
+
+    def test_invalid_step(self):
+        """Invalid steps are rejected when they are added."""
+        with pytest.raises(ValueError, match="Invalid step"):
+            mlprep.Pipeline().step({"type": "unknown"})
+        with pytest.raises(ValueError, match="Invalid output mode"):
+            mlprep.Pipeline().output("out.csv", mode="sometimes")
+
+    def test_run_saves_yaml(self):
+        """run() writes the YAML it executes, keeping notebook and file in sync."""
+        with tempfile.TemporaryDirectory() as tmpdir:
+            input_path = os.path.join(tmpdir, "input.csv")
+            output_path = os.path.join(tmpdir, "output.parquet")
+            config_path = os.path.join(tmpdir, "pipeline.yaml")
+            _frame().write_csv(input_path)
+
+            pipeline = (
+                mlprep.Pipeline().input(input_path).select(["a"]).output(output_path)
+            )
+            pipeline.run(config_path)
+
+            assert pl.read_parquet(output_path).columns == ["a"]
+            with open(config_path) as f:
+                assert f.read() == pipeline.to_yaml()
+
+    def test_run_pipeline_on_accepts_builder(self):
+        """run_pipeline_on takes a builder in place of YAML."""
+        out = mlprep.run_pipeline_on(_frame(), mlprep.Pipeline().drop_null("a"))
+        assert out.height == 3
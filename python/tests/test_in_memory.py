@@ -0,0 +1,74 @@
+"""Test suite for running pipelines on in-memory DataFrames."""
+
+import mlprep
+import polars as pl
+import pytest
+
+
+def _frame():
+    return pl.DataFrame({"a": [1, 2, 3], "b": [10, 20, 30]})
+
+
+class TestRunPipelineOn:
+    """Tests for mlprep.run_pipeline_on."""
+
+    def test_yaml_string(self):
+        """A YAML pipeline is applied and a Polars DataFrame is returned."""
+        out = mlprep.run_pipeline_on(
+            _frame(),
+            'steps:\n  - type: filter\n    condition: "a > 1"\n'
+            "  - type: select\n    columns: [b]\n",
+        )
+        assert isinstance(out, pl.DataFrame)
+        assert out.to_dict(as_series=False) == {"b": [20, 30]}
+
+    def test_dict_pipeline(self):
+        """A dict with the YAML structure is accepted as well."""
+        out = mlprep.run_pipeline_on(
+            _frame(), {"steps": [{"type": "select", "columns": ["a"]}]}
+        )
+        assert out.columns == ["a"]
+
+    def test_inputs_and_outputs_ignored(self, tmp_path):
+        """File inputs/outputs in the pipeline are neither read nor written."""
+        output_path = tmp_path / "out.parquet"
+        out = mlprep.run_pipeline_on(
+            _frame(),
+            {
+                "inputs": [{"path": str(tmp_path / "missing.csv")}],
+                "steps": [],
+                "outputs": [{"path": str(output_path)}],
+            },
+        )
+        assert out.height == 3
+        assert not output_path.exists()
+
+    def test_udf(self):
+        """python_udf steps can use callables passed through udfs."""
+        out = mlprep.run_pipeline_on(
+            _frame(),
+            "steps:\n  - type: python_udf\n    function: double\n",
+            udfs={"double": lambda df: df.with_columns(pl.col("b") * 2)},
+        )
+        assert out["b"].to_list() == [20, 40, 60]
+
+    def test_pandas_roundtrip(self):
+        """A pandas DataFrame comes back as a pandas DataFrame."""
+        pd = pytest.importorskip("pandas")
+        pytest.importorskip("pyarrow")
+        out = mlprep.run_pipeline_on(
+            pd.DataFrame({"a": [1, 2, 3]}),
+            'steps:\n  - type: filter\n    condition: "a >= 2"\n',
+        )
+        assert isinstance(out, pd.DataFrame)
+        assert out["a"].tolist() == [2, 3]
+
+    def test_invalid_pipeline(self):
+        """Malformed pipelines raise ValueError."""
+        with pytest.raises(ValueError, match="Invalid pipeline"):
+            mlprep.run_pipeline_on(_frame(), "steps: 3")
+
+    def test_invalid_frame(self):
+        """Non-DataFrame inputs raise TypeError."""
+        with pytest.raises(TypeError):
+            mlprep.run_pipeline_on([1, 2, 3], "steps: []")
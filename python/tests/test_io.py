@@ -144,3 +144,47 @@ class TestToPolars:
             assert filtered.shape == (1, 2)
         finally:
             os.unlink(csv_path)
+
+
+class TestPandas:
+    """Tests for pandas interchange (converted through Arrow)."""
+
+    @pytest.fixture(autouse=True)
+    def _requires_pandas(self):
+        pytest.importorskip("pandas")
+        pytest.importorskip("pyarrow")
+
+    def test_to_pandas(self):
+        """to_pandas() returns a pandas DataFrame with the same data."""
+        import pandas as pd
+
+        with tempfile.NamedTemporaryFile(mode="w", suffix=".csv", delete=False) as f:
+            f.write("a,b\n1,x\n2,y\n")
+            csv_path = f.name
+
+        try:
+            pdf = mlprep.read_csv(csv_path).to_pandas()
+            assert isinstance(pdf, pd.DataFrame)
+            assert pdf["a"].tolist() == [1, 2]
+            assert pdf["b"].tolist() == ["x", "y"]
+        finally:
+            os.unlink(csv_path)
+
+    def test_read_accepts_pandas(self):
+        """read_csv/read_parquet take a pandas DataFrame in place of a path."""
+        import pandas as pd
+
+        pdf = pd.DataFrame({"a": [1, 2, 3], "b": [0.5, None, 1.5]})
+        for read in (mlprep.read_csv, mlprep.read_parquet):
+            pl_df = read(pdf).to_polars()
+            assert pl_df.columns == ["a", "b"]
+            assert pl_df["b"].null_count() == 1
+
+    def test_write_parquet_from_pandas(self):
+        """write_parquet accepts pandas (and Polars) frames directly."""
+        import pandas as pd
+
+        with tempfile.TemporaryDirectory() as tmpdir:
+            parquet_path = os.path.join(tmpdir, "out.parquet")
+            mlprep.write_parquet(pd.DataFrame({"a": [1, 2]}), parquet_path)
+            assert pl.read_parquet(parquet_path)["a"].to_list() == [1, 2]
@@ -0,0 +1,104 @@
+"""Tests for the Airflow and Prefect integrations."""
+
+import logging
+import os
+import tempfile
+
+import pytest
+
+from mlprep._orchestration import run_logged, summary_markdown
+
+
+def _write_pipeline(tmpdir):
+    input_path = os.path.join(tmpdir, "input.csv")
+    output_path = os.path.join(tmpdir, "output.csv")
+    config_path = os.path.join(tmpdir, "pipeline.yaml")
+    with open(input_path, "w") as f:
+        f.write("a,b\n1,2\n3,4\n")
+    with open(config_path, "w") as f:
+        f.write(
+            f'inputs:\n  - path: "{input_path}"\n'
+            "steps:\n  - type: select\n    columns: [a]\n"
+            f'outputs:\n  - path: "{output_path}"\n    format: csv\n'
+        )
+    return config_path, output_path
+
+
+class TestRunLogged:
+    """Tests for the shared run helper."""
+
+    def test_logs_progress_and_summary(self, caplog):
+        """Stages and the final summary are logged."""
+        logger = logging.getLogger("mlprep.test")
+        with tempfile.TemporaryDirectory() as tmpdir:
+            config_path, output_path = _write_pipeline(tmpdir)
+            with caplog.at_level(logging.INFO, logger="mlprep.test"):
+                result = run_logged(config_path, logger)
+
+            assert result.rows_written == 2
+            assert os.path.exists(output_path)
+            assert "mlprep stage: execution" in caplog.text
+            assert f"mlprep run {result.run_id} finished" in caplog.text
+            assert "Lineage written to" in caplog.text
+
+    def test_summary_markdown(self):
+        """The summary table lists metrics and step durations."""
+        with tempfile.TemporaryDirectory() as tmpdir:
+            config_path, _ = _write_pipeline(tmpdir)
+            result = run_logged(config_path, logging.getLogger("mlprep.test"))
+            markdown = summary_markdown(result)
+
+            assert markdown.startswith("| metric | value |")
+            assert f"| run_id | {result.run_id} |" in markdown
+            assert "| rows_written | 2 |" in markdown
+            assert "| step:execution |" in markdown
+
+
+class TestAirflowOperator:
+    """Tests for MlPrepOperator."""
+
+    def test_execute_pushes_xcoms(self):
+        """execute returns the summary and pushes lineage and metrics."""
+        pytest.importorskip("airflow")
+        from mlprep.airflow import MlPrepOperator
+
+        class FakeTaskInstance:
+            def __init__(self):
+                self.xcoms = {}
+
+            def xcom_push(self, key, value):
+                self.xcoms[key] = value
+
+        with tempfile.TemporaryDirectory() as tmpdir:
+            config_path, _ = _write_pipeline(tmpdir)
+            operator = MlPrepOperator(task_id="prep", pipeline=config_path)
+            ti = FakeTaskInstance()
+            summary = operator.execute({"ti": ti})
+
+            assert summary["rows_written"] == 2
+            assert ti.xcoms["run_id"] == summary["run_id"]
+            assert ti.xcoms["lineage"] == summary["lineage"]
+            assert ti.xcoms["rows_written"] == 2
+            assert ti.xcoms["violations"] == 0
+
+
+class TestPrefectTask:
+    """Tests for run_pipeline_task."""
+
+    def test_task_in_flow(self):
+        """The task returns the run summary inside a flow."""
+        pytest.importorskip("prefect")
+        from prefect import flow
+
+        from mlprep.prefect import run_pipeline_task
+
+        with tempfile.TemporaryDirectory() as tmpdir:
+            config_path, _ = _write_pipeline(tmpdir)
+
+            @flow
+            def prep():
+                return run_pipeline_task(config_path, artifact_key=None)
+
+            summary = prep()
+            assert summary["status"] == "success"
+            assert summary["rows_written"] == 2
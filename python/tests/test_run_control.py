@@ -0,0 +1,72 @@
+"""Test suite for progress reporting and cancellation in mlprep.run_pipeline."""
+
+import _thread
+import os
+import tempfile
+import time
+
+import mlprep
+import polars as pl
+import pytest
+
+
+def _write_pipeline(tmpdir, steps):
+    input_path = os.path.join(tmpdir, "input.csv")
+    output_path = os.path.join(tmpdir, "output.parquet")
+    config_path = os.path.join(tmpdir, "pipeline.yaml")
+    with open(input_path, "w") as f:
+        f.write("a,b\n1,10\n2,20\n3,30\n")
+    with open(config_path, "w") as f:
+        f.write(
+            f'inputs:\n  - path: "{input_path}"\n'
+            f"steps:\n{steps}"
+            f'outputs:\n  - path: "{output_path}"\n'
+        )
+    return config_path, output_path
+
+
+class TestRunControl:
+    """Tests for the progress callback and KeyboardInterrupt handling."""
+
+    def test_progress_callback(self):
+        """Stage, step and row events are delivered to the callback."""
+        with tempfile.TemporaryDirectory() as tmpdir:
+            config_path, output_path = _write_pipeline(
+                tmpdir, "  - type: select\n    columns: [a]\n"
+            )
+            events = []
+            mlprep.run_pipeline(config_path, progress=events.append)
+
+            assert pl.read_parquet(output_path).columns == ["a"]
+            assert {"event": "step", "index": 1, "total": 1, "step": "select"} in events
+            assert {"event": "stage", "stage": "write_output"} in events
+            assert sum(e["rows"] for e in events if e["event"] == "rows") == 3
+
+    def test_progress_callback_error_cancels(self):
+        """An exception in the callback aborts the run and propagates."""
+        with tempfile.TemporaryDirectory() as tmpdir:
+            config_path, _ = _write_pipeline(tmpdir, "  - type: limit\n    n: 1\n")
+
+            def fail(event):
+                raise ValueError("stop")
+
+            with pytest.raises(ValueError, match="stop"):
+                mlprep.run_pipeline(config_path, progress=fail)
+
+    def test_keyboard_interrupt_cancels(self):
+        """Ctrl-C raises KeyboardInterrupt and the run stops before writing."""
+        with tempfile.TemporaryDirectory() as tmpdir:
+            config_path, output_path = _write_pipeline(
+                tmpdir, "  - type: python_udf\n    function: slow\n"
+            )
+
+            def slow(df):
+                _thread.interrupt_main()
+                time.sleep(0.5)
+                return df
+
+            with pytest.raises(KeyboardInterrupt):
+                mlprep.run_pipeline(config_path, udfs={"slow": slow})
+            # The worker has stopped by the time the interrupt propagates
+            assert not os.path.exists(output_path)
+            assert mlprep.run_pipeline(config_path, udfs={"slow": lambda df: df})
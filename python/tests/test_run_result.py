@@ -0,0 +1,75 @@
+"""Test suite for the RunResult returned by mlprep.run_pipeline."""
+
+import json
+import os
+import tempfile
+
+import mlprep
+import pytest
+
+
+def _write_pipeline(tmpdir, steps):
+    input_path = os.path.join(tmpdir, "input.csv")
+    output_path = os.path.join(tmpdir, "output.parquet")
+    config_path = os.path.join(tmpdir, "pipeline.yaml")
+    with open(input_path, "w") as f:
+        f.write("a,b\n1,10\n2,\n3,30\n")
+    with open(config_path, "w") as f:
+        f.write(
+            f'inputs:\n  - path: "{input_path}"\n'
+            f"steps:\n{steps}"
+            f'outputs:\n  - path: "{output_path}"\n'
+        )
+    return config_path, output_path
+
+
+class TestRunResult:
+    """Tests for the structured result of run_pipeline."""
+
+    def test_result_fields(self):
+        """Counts, durations, outputs and lineage are reported."""
+        with tempfile.TemporaryDirectory() as tmpdir:
+            config_path, output_path = _write_pipeline(
+                tmpdir,
+                "  - type: validate\n    mode: warn\n"
+                "    checks:\n      columns:\n"
+                "        - name: b\n          not_null: true\n",
+            )
+            result = mlprep.run_pipeline(config_path)
+
+            assert result.status == "success"
+            assert len(result.run_id) == 36
+            assert result.rows_written == 3
+            assert result.violations == 1
+            assert result.attempts == 1
+            assert result.outputs == [output_path]
+            assert "execution" in result.step_durations_ms
+            [warning] = result.warnings
+            assert warning["step"] == 1
+            assert warning["kind"] == "validate"
+            assert warning["code"] == "validate.not_null"
+            assert warning["count"] == 1
+            # One of three rows fails the only check
+            assert result.quality_score == pytest.approx(2 / 3)
+            assert os.path.exists(result.lineage)
+            with open(result.lineage) as f:
+                assert json.load(f)["run_id"] == result.run_id
+
+    def test_to_dict(self):
+        """to_dict returns a JSON-compatible summary."""
+        with tempfile.TemporaryDirectory() as tmpdir:
+            config_path, _ = _write_pipeline(tmpdir, "  - type: limit\n    n: 2\n")
+            summary = mlprep.run_pipeline(config_path).to_dict()
+            assert summary["rows_written"] == 2
+            assert summary["status"] == "success"
+            assert "quality_score" not in summary
+            json.dumps(summary)
+
+    def test_failure_raises(self):
+        """Failed runs raise instead of returning a result."""
+        with tempfile.TemporaryDirectory() as tmpdir:
+            config_path, _ = _write_pipeline(
+                tmpdir, "  - type: select\n    columns: [missing]\n"
+            )
+            with pytest.raises(RuntimeError, match="Pipeline execution failed"):
+                mlprep.run_pipeline(config_path)
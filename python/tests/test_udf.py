@@ -0,0 +1,67 @@
+"""Test suite for python_udf pipeline steps."""
+
+import os
+import tempfile
+
+import mlprep
+import polars as pl
+import pytest
+
+
+def _write_pipeline(tmpdir, steps):
+    input_path = os.path.join(tmpdir, "input.csv")
+    output_path = os.path.join(tmpdir, "output.parquet")
+    config_path = os.path.join(tmpdir, "pipeline.yaml")
+    with open(input_path, "w") as f:
+        f.write("a,b\n1,10\n2,20\n3,30\n")
+    with open(config_path, "w") as f:
+        f.write(
+            f'inputs:\n  - path: "{input_path}"\n'
+            f"steps:\n{steps}"
+            f'outputs:\n  - path: "{output_path}"\n'
+        )
+    return config_path, output_path
+
+
+class TestPythonUdf:
+    """Tests for python_udf steps run through mlprep.run_pipeline."""
+
+    def test_udf_on_selected_columns(self):
+        """The callable sees only the selected columns; its output is merged back."""
+        with tempfile.TemporaryDirectory() as tmpdir:
+            config_path, output_path = _write_pipeline(
+                tmpdir,
+                "  - type: python_udf\n    function: log_b\n    columns: [b]\n",
+            )
+
+            def log_b(df: pl.DataFrame) -> pl.DataFrame:
+                assert df.columns == ["b"]
+                return df.with_columns(pl.col("b").log10().alias("b_log"))
+
+            mlprep.run_pipeline(config_path, udfs={"log_b": log_b})
+
+            out = pl.read_parquet(output_path)
+            assert out.columns == ["a", "b", "b_log"]
+            assert out["b_log"].to_list() == pytest.approx([1.0, 1.30103, 1.47712], rel=1e-4)
+
+    def test_udf_not_registered(self):
+        """A python_udf step without a matching callable fails the run."""
+        with tempfile.TemporaryDirectory() as tmpdir:
+            config_path, _ = _write_pipeline(
+                tmpdir, "  - type: python_udf\n    function: missing\n"
+            )
+            with pytest.raises(RuntimeError, match="not registered"):
+                mlprep.run_pipeline(config_path)
+
+    def test_udf_exception_propagates(self):
+        """Errors raised by the callable surface as a pipeline failure."""
+        with tempfile.TemporaryDirectory() as tmpdir:
+            config_path, _ = _write_pipeline(
+                tmpdir, "  - type: python_udf\n    function: boom\n"
+            )
+
+            def boom(df):
+                raise ValueError("bad data")
+
+            with pytest.raises(RuntimeError, match="bad data"):
+                mlprep.run_pipeline(config_path, udfs={"boom": boom})
@@ -0,0 +1,69 @@
+"""Test suite for mlprep.validate."""
+
+import mlprep
+import polars as pl
+import pytest
+
+
+def _frame():
+    return pl.DataFrame(
+        {"id": [1, 2, 2, 4], "age": [30, -1, 40, None], "city": ["a", "b", "x", "a"]}
+    )
+
+
+CHECKS = {
+    "columns": [
+        {"name": "id", "unique": True},
+        {"name": "age", "range": [0, 120]},
+        {"name": "age", "not_null": True},
+        {"name": "city", "enum": ["a", "b"]},
+    ]
+}
+
+
+class TestValidate:
+    """Tests for running validate-step checks from Python."""
+
+    def test_per_check_results(self):
+        """Every configured check is reported, passing or not."""
+        result = mlprep.validate(_frame(), CHECKS)
+        assert not result.passed
+        assert [(c.column, c.check) for c in result.checks] == [
+            ("id", "unique"),
+            ("age", "range"),
+            ("age", "not_null"),
+            ("city", "enum"),
+        ]
+        assert [c.count for c in result.checks] == [2, 1, 1, 1]
+        assert result.total_violations == 5
+        assert result.checks[0].message is not None
+        # warn mode keeps every row
+        assert result.valid.height == 4
+        assert result.quarantine is None
+
+    def test_quarantine(self):
+        """Quarantine mode splits off violating rows."""
+        result = mlprep.validate(_frame(), CHECKS, mode="quarantine")
+        assert result.valid["id"].to_list() == [1]
+        assert result.quarantine.height == 3
+
+    def test_strict_raises(self):
+        """Strict mode raises when a check fails, like the pipeline step."""
+        with pytest.raises(ValueError, match="Validation failed"):
+            mlprep.validate(_frame(), CHECKS, mode="strict")
+
+    def test_yaml_and_list_checks(self):
+        """Checks can be given as YAML or as a plain list of column checks."""
+        from_yaml = mlprep.validate(
+            _frame(), "columns:\n  - name: city\n    enum: [a, b, x]\n"
+        )
+        from_list = mlprep.validate(
+            _frame(), [{"name": "city", "enum": ["a", "b", "x"]}]
+        )
+        assert from_yaml.passed and from_list.passed
+        assert bool(from_yaml)
+
+    def test_invalid_mode(self):
+        """Unknown modes are rejected."""
+        with pytest.raises(ValueError, match="Invalid validation mode"):
+            mlprep.validate(_frame(), CHECKS, mode="lenient")
@@ -0,0 +1,452 @@
+//! `mlprep bench`: synthetic data generation and per-phase pipeline benchmarks.
+//!
+//! Generated data is deterministic for a given schema, row count and seed, so results
+//! from different builds (or Polars versions) can be compared run against run.
+
+use crate::dsl::{Input, Pipeline, RuntimeConfig};
+use crate::engine::DataPipeline;
+use crate::errors::{MlPrepError, MlPrepResult};
+use crate::io;
+use crate::memory::format_bytes;
+use polars::prelude::*;
+use serde::de::Error;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use tracing::info;
+
+/// Columns generated when no schema is given.
+pub const DEFAULT_SCHEMA: &str =
+    "id:id,price:float,quantity:int,category:str:100,active:bool,day:date";
+
+/// Rows generated (and written) per batch, bounding memory for large files.
+const GENERATE_BATCH: usize = 1_000_000;
+
+/// 2020-01-01 as days since the Unix epoch; generated dates span five years from it.
+pub(crate) const FIRST_DAY: i32 = 18262;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ColumnKind {
+    /// Row number, starting at 0.
+    Id,
+    /// Uniform integers in `[0, 1_000_000)`.
+    Int,
+    /// Uniform floats in `[0, 1000)`.
+    Float,
+    /// Strings `<name>_<k>` with `k` uniform in `[0, cardinality)`.
+    Str {
+        cardinality: usize,
+    },
+    Bool,
+    Date,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ColumnSpec {
+    pub name: String,
+    pub kind: ColumnKind,
+}
+
+/// Parse a comma-separated list of `name:type` pairs, e.g. `"id:id,city:str:50,x:float"`.
+/// Types are `id`, `int`, `float`, `str[:cardinality]` (default 100), `bool` and `date`.
+pub fn parse_schema(spec: &str) -> MlPrepResult<Vec<ColumnSpec>> {
+    let invalid = |msg: String| MlPrepError::ConfigError(serde_yaml::Error::custom(msg), None);
+    spec.split(',')
+        .map(str::trim)
+        .filter(|part| !part.is_empty())
+        .map(|part| {
+            let fields: Vec<&str> = part.split(':').collect();
+            let kind = match fields.as_slice() {
+                [_, "id"] => ColumnKind::Id,
+                [_, "int"] => ColumnKind::Int,
+                [_, "float"] => ColumnKind::Float,
+                [_, "str"] => ColumnKind::Str { cardinality: 100 },
+                [_, "str", n] => ColumnKind::Str {
+                    cardinality: n
+                        .parse()
+                        .ok()
+                        .filter(|n| *n > 0)
+                        .ok_or_else(|| invalid(format!("Invalid cardinality in '{}'", part)))?,
+                },
+                [_, "bool"] => ColumnKind::Bool,
+                [_, "date"] => ColumnKind::Date,
+                _ => {
+                    return Err(invalid(format!(
+                        "Invalid column '{}': expected name:type with type id, int, float, str[:cardinality], bool or date",
+                        part
+                    )))
+                }
+            };
+            Ok(ColumnSpec {
+                name: fields[0].to_string(),
+                kind,
+            })
+        })
+        .collect()
+}
+
+/// SplitMix64: tiny, fast and good enough for benchmark data.
+pub(crate) struct Rng(pub(crate) u64);
+
+impl Rng {
+    pub(crate) fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    pub(crate) fn below(&mut self, n: u64) -> u64 {
+        self.next() % n
+    }
+
+    pub(crate) fn unit(&mut self) -> f64 {
+        (self.next() >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+fn synthetic_batch(
+    columns: &[ColumnSpec],
+    first_row: usize,
+    rows: usize,
+    rng: &mut Rng,
+) -> MlPrepResult<DataFrame> {
+    let series = columns
+        .iter()
+        .map(|column| {
+            let name = column.name.as_str().into();
+            let series = match &column.kind {
+                ColumnKind::Id => Series::new(
+                    name,
+                    (first_row as i64..(first_row + rows) as i64).collect::<Vec<_>>(),
+                ),
+                ColumnKind::Int => Series::new(
+                    name,
+                    (0..rows)
+                        .map(|_| rng.below(1_000_000) as i64)
+                        .collect::<Vec<_>>(),
+                ),
+                ColumnKind::Float => Series::new(
+                    name,
+                    (0..rows).map(|_| rng.unit() * 1000.0).collect::<Vec<_>>(),
+                ),
+                ColumnKind::Str { cardinality } => {
+                    let values: Vec<String> = (0..*cardinality)
+                        .map(|k| format!("{}_{}", column.name, k))
+                        .collect();
+                    Series::new(
+                        name,
+                        (0..rows)
+                            .map(|_| values[rng.below(*cardinality as u64) as usize].as_str())
+                            .collect::<Vec<_>>(),
+                    )
+                }
+                ColumnKind::Bool => Series::new(
+                    name,
+                    (0..rows).map(|_| rng.next() & 1 == 1).collect::<Vec<_>>(),
+                ),
+                ColumnKind::Date => Series::new(
+                    name,
+                    (0..rows)
+                        .map(|_| FIRST_DAY + rng.below(5 * 365) as i32)
+                        .collect::<Vec<_>>(),
+                )
+                .cast(&DataType::Date)
+                .map_err(MlPrepError::PolarsError)?,
+            };
+            Ok(series.into())
+        })
+        .collect::<MlPrepResult<Vec<Column>>>()?;
+    DataFrame::new(series).map_err(MlPrepError::PolarsError)
+}
+
+/// Generate `rows` rows of `columns` in memory.
+pub fn synthetic_frame(columns: &[ColumnSpec], rows: usize, seed: u64) -> MlPrepResult<DataFrame> {
+    synthetic_batch(columns, 0, rows, &mut Rng(seed))
+}
+
+/// Write `rows` rows of `columns` to `path` (Parquet or CSV, by extension) in batches,
+/// so files larger than memory can be generated. Returns the file size in bytes.
+pub fn generate<P: AsRef<Path>>(
+    path: P,
+    columns: &[ColumnSpec],
+    rows: usize,
+    seed: u64,
+) -> MlPrepResult<u64> {
+    let path = path.as_ref();
+    let parquet = match path.extension().and_then(|ext| ext.to_str()) {
+        Some("parquet") => true,
+        Some("csv") => false,
+        _ => {
+            return Err(MlPrepError::ConfigError(
+                serde_yaml::Error::custom(format!(
+                    "Unsupported format for generated data: {} (use .csv or .parquet)",
+                    path.display()
+                )),
+                None,
+            ))
+        }
+    };
+    if columns.is_empty() {
+        return Err(MlPrepError::ConfigError(
+            serde_yaml::Error::custom("Generated data needs at least one column"),
+            None,
+        ));
+    }
+
+    let mut rng = Rng(seed);
+    let mut offset = 0;
+    let mut started = false;
+    let mut next_batch = || -> MlPrepResult<Option<DataFrame>> {
+        // An empty request still yields one (empty) batch, so the file gets a schema.
+        if started && offset >= rows {
+            return Ok(None);
+        }
+        started = true;
+        let n = GENERATE_BATCH.min(rows - offset);
+        let batch = synthetic_batch(columns, offset, n, &mut rng)?;
+        offset += n;
+        Ok(Some(batch))
+    };
+    io::write_atomic(path, |file| {
+        crate::chunked::write_chunks(file, parquet, &Default::default(), &mut next_batch)
+            .map(|_| ())
+    })?;
+    Ok(std::fs::metadata(path).map_err(MlPrepError::IoError)?.len())
+}
+
+/// Timings of one phase over all iterations.
+#[derive(Debug, Clone, Serialize)]
+pub struct PhaseResult {
+    pub phase: String,
+    pub rows: usize,
+    /// Bytes read from or written to disk (read/write) or held in memory (transform).
+    pub bytes: u64,
+    pub best_ms: f64,
+    pub mean_ms: f64,
+    /// Throughput of the fastest iteration.
+    pub rows_per_sec: f64,
+    pub mb_per_sec: f64,
+}
+
+impl PhaseResult {
+    fn new(phase: &str, rows: usize, bytes: u64, timings: &[Duration]) -> Self {
+        let best = timings.iter().min().copied().unwrap_or_default();
+        let mean = timings.iter().sum::<Duration>() / timings.len().max(1) as u32;
+        let secs = best.as_secs_f64().max(f64::EPSILON);
+        Self {
+            phase: phase.to_string(),
+            rows,
+            bytes,
+            best_ms: best.as_secs_f64() * 1000.0,
+            mean_ms: mean.as_secs_f64() * 1000.0,
+            rows_per_sec: rows as f64 / secs,
+            mb_per_sec: bytes as f64 / (1024.0 * 1024.0) / secs,
+        }
+    }
+}
+
+/// Result of `mlprep bench`: read, transform and write timings for one pipeline.
+#[derive(Debug, Clone, Serialize)]
+pub struct BenchReport {
+    pub pipeline: String,
+    pub input: String,
+    pub iterations: usize,
+    pub phases: Vec<PhaseResult>,
+}
+
+impl std::fmt::Display for BenchReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "Benchmark: {} (input {}, best of {})",
+            self.pipeline, self.input, self.iterations
+        )?;
+        for phase in &self.phases {
+            writeln!(
+                f,
+                "  {:<9} {:>10} rows {:>10} {:>10.1} ms (mean {:>10.1} ms) {:>12.0} rows/s {:>9.1} MB/s",
+                phase.phase,
+                phase.rows,
+                format_bytes(phase.bytes),
+                phase.best_ms,
+                phase.mean_ms,
+                phase.rows_per_sec,
+                phase.mb_per_sec
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Time the read, transform and write phases of the pipeline at `path`, running each
+/// `iterations` times. `input` replaces the pipeline's first input (e.g. with generated
+/// data). The result is written to a temporary file in the format of the pipeline's
+/// first output (Parquet if it has none), never to the output itself.
+pub fn bench_pipeline(
+    path: &PathBuf,
+    input: Option<&Path>,
+    iterations: usize,
+    security_config: crate::security::SecurityConfig,
+    runtime_override: Option<RuntimeConfig>,
+) -> MlPrepResult<BenchReport> {
+    let security_context = crate::security::SecurityContext::new(security_config)?;
+    security_context.validate_path(path)?;
+    let mut pipeline = Pipeline::from_path(path)?;
+    crate::runner::load_check_files(&mut pipeline, &security_context)?;
+    if let Some(input) = input {
+        let input = Input {
+            path: input.to_path_buf(),
+            format: None,
+            schema: None,
+            infer_rows: None,
+            null_values: None,
+            contract: None,
+            manifest: None,
+            iceberg: None,
+            kafka: None,
+        };
+        match pipeline.inputs.first_mut() {
+            Some(first) => *first = input,
+            None => pipeline.inputs.push(input),
+        }
+    }
+    let (input_path, input_format) = pipeline
+        .inputs
+        .first()
+        .map(|input| (input.path.clone(), input.format.clone()))
+        .ok_or_else(|| {
+            MlPrepError::ConfigError(
+                serde_yaml::Error::custom("No inputs specified in pipeline"),
+                None,
+            )
+        })?;
+    security_context.validate_path(&input_path)?;
+
+    let runtime = crate::runner::resolve_runtime(&pipeline, runtime_override);
+    let effective = crate::runner::init_runtime(&runtime)?;
+    let csv_output = pipeline
+        .outputs
+        .first()
+        .is_some_and(|output| io::has_extension(&output.path, "csv"));
+    let scratch = std::env::temp_dir().join(format!(
+        "mlprep-bench-{}.{}",
+        uuid::Uuid::new_v4(),
+        if csv_output { "csv" } else { "parquet" }
+    ));
+    let input_bytes = std::fs::metadata(&input_path)
+        .map_err(MlPrepError::IoError)?
+        .len();
+
+    let iterations = iterations.max(1);
+    let mut timings: [Vec<Duration>; 3] = Default::default();
+    let (mut rows_read, mut rows_out, mut frame_bytes, mut output_bytes) = (0, 0, 0, 0);
+    for iteration in 1..=iterations {
+        info!("Benchmark iteration {}/{}", iteration, iterations);
+
+        let start = Instant::now();
+        let df = io::read_detected(&input_path, input_format.as_deref())?
+            .collect()
+            .map_err(MlPrepError::PolarsError)?;
+        timings[0].push(start.elapsed());
+        rows_read = df.height();
+
+        let start = Instant::now();
+        let out = DataPipeline::new(df.lazy())
+            .apply_transforms(pipeline.clone(), &runtime, &security_context)?
+            .with_runtime(&effective)
+            .collect(runtime.streaming)?;
+        timings[1].push(start.elapsed());
+        rows_out = out.height();
+        frame_bytes = out.estimated_size() as u64;
+
+        let start = Instant::now();
+        let written = if csv_output {
+            io::write_csv(out, &scratch)
+        } else {
+            io::write_parquet(out, &scratch)
+        };
+        timings[2].push(start.elapsed());
+        let size = std::fs::metadata(&scratch).map(|m| m.len());
+        let _ = std::fs::remove_file(&scratch);
+        written?;
+        output_bytes = size.map_err(MlPrepError::IoError)?;
+    }
+
+    Ok(BenchReport {
+        pipeline: path.display().to_string(),
+        input: input_path.display().to_string(),
+        iterations,
+        phases: vec![
+            PhaseResult::new("read", rows_read, input_bytes, &timings[0]),
+            PhaseResult::new("transform", rows_out, frame_bytes, &timings[1]),
+            PhaseResult::new("write", rows_out, output_bytes, &timings[2]),
+        ],
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_parse_schema() {
+        let columns = parse_schema(DEFAULT_SCHEMA).unwrap();
+        assert_eq!(columns.len(), 6);
+        assert_eq!(columns[3].kind, ColumnKind::Str { cardinality: 100 });
+        assert_eq!(
+            parse_schema("a:str:7").unwrap()[0].kind,
+            ColumnKind::Str { cardinality: 7 }
+        );
+        assert!(parse_schema("a:decimal").is_err());
+        assert!(parse_schema("a").is_err());
+        assert!(parse_schema("a:str:0").is_err());
+    }
+
+    #[test]
+    fn test_synthetic_frame_is_deterministic() {
+        let columns = parse_schema(DEFAULT_SCHEMA).unwrap();
+        let a = synthetic_frame(&columns, 1000, 7).unwrap();
+        let b = synthetic_frame(&columns, 1000, 7).unwrap();
+        assert!(a.equals(&b));
+        assert!(!a.equals(&synthetic_frame(&columns, 1000, 8).unwrap()));
+        assert_eq!(a.column("day").unwrap().dtype(), &DataType::Date);
+        assert_eq!(
+            a.column("category").unwrap().n_unique().unwrap(),
+            100,
+            "1000 draws should hit every category"
+        );
+    }
+
+    #[test]
+    fn test_generate_and_bench() {
+        let dir = tempdir().unwrap();
+        let data = dir.path().join("data.parquet");
+        let columns = parse_schema("id:id,price:float,city:str:3").unwrap();
+        let size = generate(&data, &columns, 2500, 42).unwrap();
+        assert!(size > 0);
+        assert_eq!(io::parquet_row_count(&data).unwrap(), 2500);
+        let csv = dir.path().join("data.csv");
+        generate(&csv, &columns, 10, 42).unwrap();
+        assert_eq!(std::fs::read_to_string(&csv).unwrap().lines().count(), 11);
+        assert!(generate(dir.path().join("data.json"), &columns, 10, 42).is_err());
+
+        let pipeline = dir.path().join("pipeline.yaml");
+        std::fs::write(
+            &pipeline,
+            "inputs:\n  - path: unused.csv\nsteps:\n  - type: filter\n    condition: \"price >= 500\"\noutputs:\n  - path: out.csv\n",
+        )
+        .unwrap();
+        let report = bench_pipeline(&pipeline, Some(&data), 2, Default::default(), None).unwrap();
+        assert_eq!(report.iterations, 2);
+        let phases: Vec<&str> = report.phases.iter().map(|p| p.phase.as_str()).collect();
+        assert_eq!(phases, ["read", "transform", "write"]);
+        assert_eq!(report.phases[0].rows, 2500);
+        assert_eq!(report.phases[0].bytes, size);
+        assert!(report.phases[1].rows > 0 && report.phases[1].rows < 2500);
+        assert!(report.phases[2].bytes > 0);
+        assert!(!dir.path().join("out.csv").exists());
+    }
+}
@@ -0,0 +1,344 @@
+//! Fluent construction of [`Pipeline`]s for Rust code embedding mlprep.
+//!
+//! ```no_run
+//! use mlprep::builder::PipelineBuilder;
+//! use polars::prelude::*;
+//!
+//! let pipeline = PipelineBuilder::new()
+//!     .filter("price > 0")
+//!     .select(["price", "city"])
+//!     .sort(["price"], [true])
+//!     .limit(100)
+//!     .build();
+//! let lf = df!("price" => [1.0, -1.0], "city" => ["a", "b"]).unwrap().lazy();
+//! let out = mlprep::engine::run_in_memory(lf, &pipeline).unwrap();
+//! ```
+
+use crate::dsl::{
+    Agg, Cast, CheckConfig, DropNull, FillNull, FillNullStrategy, Filter, GroupBy, Input, Join,
+    Limit, Output, OutputMode, Pipeline, Predicate, RuntimeConfig, ScalarValue, Select, Slice,
+    Sort, Sql, Step, Validate, ValidationMode,
+};
+use std::path::PathBuf;
+
+fn strings<I, S>(items: I) -> Vec<String>
+where
+    I: IntoIterator<Item = S>,
+    S: Into<String>,
+{
+    items.into_iter().map(Into::into).collect()
+}
+
+/// Builds a [`Pipeline`] step by step; the result is identical to parsing the
+/// equivalent YAML.
+#[derive(Debug, Clone, Default)]
+pub struct PipelineBuilder {
+    pipeline: Pipeline,
+}
+
+impl PipelineBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Read an input file; the format is inferred from the extension.
+    pub fn input(mut self, path: impl Into<PathBuf>) -> Self {
+        self.pipeline.inputs.push(Input {
+            path: path.into(),
+            format: None,
+            schema: None,
+            infer_rows: None,
+            null_values: None,
+            contract: None,
+            manifest: None,
+            iceberg: None,
+            kafka: None,
+        });
+        self
+    }
+
+    /// Write the result to `path` (overwriting); the format is inferred from the extension.
+    pub fn output(self, path: impl Into<PathBuf>) -> Self {
+        self.output_with_mode(path, OutputMode::Overwrite)
+    }
+
+    pub fn output_with_mode(mut self, path: impl Into<PathBuf>, mode: OutputMode) -> Self {
+        self.pipeline.outputs.push(Output {
+            path: path.into(),
+            format: None,
+            compression: None,
+            partition_by: None,
+            mode,
+            feast: None,
+            max_rows_per_file: None,
+            max_file_size: None,
+        });
+        self
+    }
+
+    pub fn runtime(mut self, runtime: RuntimeConfig) -> Self {
+        self.pipeline.runtime = Some(runtime);
+        self
+    }
+
+    pub fn streaming(mut self, streaming: bool) -> Self {
+        self.pipeline
+            .runtime
+            .get_or_insert_with(RuntimeConfig::default)
+            .streaming = streaming;
+        self
+    }
+
+    /// Append an arbitrary step (for step types without a dedicated method).
+    pub fn step(mut self, step: Step) -> Self {
+        self.pipeline.steps.push(step);
+        self
+    }
+
+    pub fn select<I, S>(self, columns: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.step(Step::Select(Select {
+            columns: strings(columns),
+        }))
+    }
+
+    /// Keep rows matching a SQL condition, e.g. `"price > 0 AND city = 'tokyo'"`.
+    pub fn filter(self, condition: impl Into<String>) -> Self {
+        self.step(Step::Filter(Filter {
+            condition: Some(condition.into()),
+            predicate: None,
+        }))
+    }
+
+    /// Keep rows matching a structured predicate (no SQL parsing).
+    pub fn filter_predicate(self, predicate: Predicate) -> Self {
+        self.step(Step::Filter(Filter {
+            condition: None,
+            predicate: Some(predicate),
+        }))
+    }
+
+    /// Cast columns to the given dtypes (`"int"`, `"float"`, `"str"`, ...).
+    pub fn cast<I, K, V>(self, columns: I) -> Self
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: Into<String>,
+        V: Into<String>,
+    {
+        self.step(Step::Cast(Cast {
+            columns: columns
+                .into_iter()
+                .map(|(k, v)| (k.into(), v.into()))
+                .collect(),
+        }))
+    }
+
+    pub fn sort<I, S, D>(self, by: I, descending: D) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+        D: IntoIterator<Item = bool>,
+    {
+        self.step(Step::Sort(Sort {
+            by: strings(by),
+            descending: descending.into_iter().collect(),
+        }))
+    }
+
+    /// Join against the file at `right_path` (`how`: inner, left, outer, cross, semi, anti).
+    pub fn join<L, R, S, T>(
+        self,
+        right_path: impl Into<PathBuf>,
+        left_on: L,
+        right_on: R,
+        how: impl Into<String>,
+    ) -> Self
+    where
+        L: IntoIterator<Item = S>,
+        S: Into<String>,
+        R: IntoIterator<Item = T>,
+        T: Into<String>,
+    {
+        self.step(Step::Join(Join {
+            right_path: Some(right_path.into()),
+            right: None,
+            left_on: strings(left_on),
+            right_on: strings(right_on),
+            how: how.into(),
+            expect: None,
+            warn_only: false,
+            strategy: None,
+        }))
+    }
+
+    /// Group by `by` and aggregate each `(column, func)` pair (`sum`, `mean`, `count`, ...).
+    pub fn group_by<I, S, A, K, F>(self, by: I, aggs: A) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+        A: IntoIterator<Item = (K, F)>,
+        K: Into<String>,
+        F: Into<String>,
+    {
+        let aggs: Vec<Agg> = aggs
+            .into_iter()
+            .map(|(column, func)| Agg {
+                column: column.into(),
+                func: func.into(),
+                alias: None,
+                condition: None,
+            })
+            .collect();
+        self.step(Step::GroupBy(GroupBy {
+            by: strings(by),
+            aggs,
+            ..Default::default()
+        }))
+    }
+
+    pub fn fill_null<I, S>(self, columns: I, strategy: FillNullStrategy) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.step(Step::FillNull(FillNull {
+            columns: strings(columns),
+            strategy,
+            value: None,
+            over: Vec::new(),
+            order_by: None,
+        }))
+    }
+
+    /// Fill nulls with a literal, cast to each column's dtype.
+    pub fn fill_null_with<I, S>(self, columns: I, value: impl Into<ScalarValue>) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.step(Step::FillNull(FillNull {
+            columns: strings(columns),
+            strategy: FillNullStrategy::Literal,
+            value: Some(value.into()),
+            over: Vec::new(),
+            order_by: None,
+        }))
+    }
+
+    pub fn drop_null<I, S>(self, columns: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.step(Step::DropNull(DropNull {
+            columns: strings(columns),
+        }))
+    }
+
+    pub fn limit(self, n: usize) -> Self {
+        self.step(Step::Limit(Limit { n }))
+    }
+
+    pub fn slice(self, offset: i64, length: Option<usize>) -> Self {
+        self.step(Step::Slice(Slice { offset, length }))
+    }
+
+    /// Run a SELECT over the current frame, registered as `df`.
+    pub fn sql(self, query: impl Into<String>) -> Self {
+        self.step(Step::Sql(Sql {
+            query: query.into(),
+        }))
+    }
+
+    pub fn validate(self, checks: CheckConfig, mode: ValidationMode) -> Self {
+        self.step(Step::Validate(Validate {
+            checks,
+            checks_path: None,
+            mode,
+            violations_path: None,
+            sample_fraction: None,
+            sample_rows: None,
+        }))
+    }
+
+    pub fn build(self) -> Pipeline {
+        self.pipeline
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use polars::prelude::*;
+
+    #[test]
+    fn test_builder_matches_yaml() {
+        let built = PipelineBuilder::new()
+            .input("data.csv")
+            .filter("a > 1")
+            .select(["a", "b"])
+            .cast([("a", "float")])
+            .sort(["a"], [true])
+            .group_by(["b"], [("a", "sum")])
+            .limit(10)
+            .output("out.parquet")
+            .build();
+        let parsed = Pipeline::from_reader(
+            r#"
+inputs:
+  - path: data.csv
+steps:
+  - type: filter
+    condition: "a > 1"
+  - type: select
+    columns: [a, b]
+  - type: cast
+    columns: {a: float}
+  - type: sort
+    by: [a]
+    descending: [true]
+  - type: group_by
+    by: [b]
+    aggs:
+      a: {func: sum}
+  - type: limit
+    n: 10
+outputs:
+  - path: out.parquet
+"#
+            .as_bytes(),
+        )
+        .unwrap();
+        assert_eq!(built, parsed);
+    }
+
+    #[test]
+    fn test_builder_run_in_memory() {
+        let lf = df!(
+            "a" => [3i64, 1, 2, 4],
+            "b" => [Some("x"), None, Some("y"), Some("z")]
+        )
+        .unwrap()
+        .lazy();
+        let pipeline = PipelineBuilder::new()
+            .fill_null_with(["b"], "missing")
+            .filter("a > 1")
+            .sort(["a"], [false])
+            .limit(2)
+            .build();
+
+        let out = crate::engine::run_in_memory(lf, &pipeline).unwrap();
+        assert_eq!(out.height(), 2);
+        let a: Vec<i64> = out
+            .column("a")
+            .unwrap()
+            .i64()
+            .unwrap()
+            .into_no_null_iter()
+            .collect();
+        assert_eq!(a, vec![2, 3]);
+    }
+}
@@ -0,0 +1,521 @@
+//! `mlprep docs`: a Markdown feature catalog of a pipeline's output columns, listing for
+//! each column its dtype, the docs from the `schema` block, the input columns it derives
+//! from, the steps that write it, the statistics fitted by `features` steps and the
+//! checks `validate` steps apply to it.
+
+use crate::dsl::{Agg, ColumnCheck, Pipeline, Step};
+use crate::errors::MlPrepResult;
+use crate::features::{FeatureSpec, FeatureState, FeatureStateEntry, FeatureTransform};
+use crate::lineage::{self, ColumnSource};
+use serde::Serialize;
+use std::fmt::Write;
+use std::path::Path;
+
+/// Categories listed for a fitted one-hot vocabulary before it is elided.
+const MAX_LISTED_CATEGORIES: usize = 10;
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ColumnEntry {
+    pub name: String,
+    pub dtype: String,
+    #[serde(flatten)]
+    pub doc: crate::io::ColumnDoc,
+    pub sources: Vec<ColumnSource>,
+    /// Steps writing the column, in pipeline order
+    pub transforms: Vec<String>,
+    /// Statistics fitted for the column by `features` steps with a saved state
+    pub statistics: Vec<String>,
+    /// Checks of `validate` steps on the column
+    pub validation: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FeatureCatalog {
+    pub pipeline: String,
+    pub columns: Vec<ColumnEntry>,
+}
+
+/// Columns a step writes: by exact name, by name prefix (columns only known after
+/// fitting) or every column (opaque steps).
+enum Target {
+    Column(String),
+    Prefix(String),
+    All,
+}
+
+impl Target {
+    fn matches(&self, column: &str) -> bool {
+        match self {
+            Target::Column(name) => name == column,
+            Target::Prefix(prefix) => {
+                column == prefix || column.starts_with(&format!("{}_", prefix))
+            }
+            Target::All => true,
+        }
+    }
+}
+
+fn agg_description(agg: &Agg, context: &str) -> String {
+    let mut description = format!("{}({}) {}", agg.func, agg.column, context);
+    if agg.condition.is_some() {
+        description.push_str(" on matching rows");
+    }
+    description
+}
+
+/// Output name of a feature spec: its alias, else its column.
+fn feature_output(spec: &FeatureSpec) -> &str {
+    spec.alias.as_deref().unwrap_or(&spec.column)
+}
+
+fn feature_target(spec: &FeatureSpec, column: &str) -> Target {
+    let name = match (
+        &spec.alias,
+        spec.columns.is_empty() && spec.columns_regex.is_none(),
+    ) {
+        (Some(alias), true) => alias.clone(),
+        _ => column.to_string(),
+    };
+    match spec.transform {
+        FeatureTransform::OneHotEncode | FeatureTransform::Interactions | FeatureTransform::Pca => {
+            Target::Prefix(name)
+        }
+        _ => Target::Column(name),
+    }
+}
+
+/// The columns `step` writes, each with a short description of what it does.
+fn step_effects(step: &Step) -> Vec<(Target, String)> {
+    match step {
+        Step::Cast(cast) => cast
+            .columns
+            .iter()
+            .map(|(name, dtype)| (Target::Column(name.clone()), format!("cast to {}", dtype)))
+            .collect(),
+        Step::FillNull(fill) => fill
+            .columns
+            .iter()
+            .map(|name| {
+                let strategy = format!("{:?}", fill.strategy).to_lowercase();
+                (
+                    Target::Column(name.clone()),
+                    format!("nulls filled ({})", strategy),
+                )
+            })
+            .collect(),
+        Step::GroupBy(group_by) => {
+            let context = format!("by [{}]", group_by.by.join(", "));
+            group_by
+                .aggs
+                .iter()
+                .map(|agg| {
+                    (
+                        Target::Column(agg.output_name(&group_by.aggs)),
+                        agg_description(agg, &context),
+                    )
+                })
+                .collect()
+        }
+        Step::Resample(resample) => {
+            let context = format!("per {} of {}", resample.every, resample.time_column);
+            resample
+                .aggs
+                .iter()
+                .map(|agg| {
+                    (
+                        Target::Column(agg.output_name(&resample.aggs)),
+                        agg_description(agg, &context),
+                    )
+                })
+                .collect()
+        }
+        Step::Window(window) => window
+            .ops
+            .iter()
+            .map(|op| {
+                (
+                    Target::Column(op.alias.clone()),
+                    format!(
+                        "{}({}) over [{}]",
+                        op.func,
+                        op.column,
+                        window.partition_by.join(", ")
+                    ),
+                )
+            })
+            .collect(),
+        Step::DatetimeOps(ops) => vec![(
+            Target::Column(ops.alias.clone().unwrap_or_else(|| ops.column.clone())),
+            format!("datetime ops on {}", ops.column),
+        )],
+        Step::Join(join) => {
+            let source = join
+                .right_input()
+                .map(|(path, _)| path.display().to_string())
+                .unwrap_or_default();
+            vec![(Target::All, format!("{} join with {}", join.how, source))]
+        }
+        Step::Features(features) => features
+            .config
+            .features
+            .iter()
+            .map(|spec| {
+                let description = format!("{:?}", spec.transform);
+                (feature_target(spec, feature_output(spec)), description)
+            })
+            .collect(),
+        Step::Sql(_) => vec![(Target::All, "sql".to_string())],
+        Step::PythonUdf(udf) => match &udf.columns {
+            Some(columns) => columns
+                .iter()
+                .map(|name| {
+                    (
+                        Target::Column(name.clone()),
+                        format!("python_udf {}", udf.function),
+                    )
+                })
+                .collect(),
+            None => vec![(Target::All, format!("python_udf {}", udf.function))],
+        },
+        _ => Vec::new(),
+    }
+}
+
+fn entry_column(entry: &FeatureStateEntry) -> &str {
+    match entry {
+        FeatureStateEntry::MinMax { column, .. }
+        | FeatureStateEntry::Standard { column, .. }
+        | FeatureStateEntry::OneHot { column, .. }
+        | FeatureStateEntry::Count { column, .. }
+        | FeatureStateEntry::Interactions { column, .. }
+        | FeatureStateEntry::Pca { column, .. }
+        | FeatureStateEntry::Cluster { column, .. } => column,
+    }
+}
+
+fn describe_entry(entry: &FeatureStateEntry) -> String {
+    match entry {
+        FeatureStateEntry::MinMax { stats, .. } => {
+            format!("min {}, max {}", stats.min, stats.max)
+        }
+        FeatureStateEntry::Standard { stats, .. } => {
+            format!("mean {}, std {}", stats.mean, stats.std)
+        }
+        FeatureStateEntry::OneHot { vocab, .. } => {
+            let mut listed: Vec<&str> = vocab
+                .categories
+                .iter()
+                .take(MAX_LISTED_CATEGORIES)
+                .map(String::as_str)
+                .collect();
+            if vocab.categories.len() > MAX_LISTED_CATEGORIES {
+                listed.push("...");
+            }
+            format!(
+                "{} categories: {}",
+                vocab.categories.len(),
+                listed.join(", ")
+            )
+        }
+        FeatureStateEntry::Count { stats, .. } => format!(
+            "{} categories counted over {} rows",
+            stats.counts.len(),
+            stats.total
+        ),
+        FeatureStateEntry::Interactions { terms, .. } => {
+            format!("{} interaction terms", terms.terms.len())
+        }
+        FeatureStateEntry::Pca { stats, .. } => format!(
+            "{} components of [{}], explained variance ratio {:?}",
+            stats.components.len(),
+            stats.inputs.join(", "),
+            stats.explained_variance_ratio
+        ),
+        FeatureStateEntry::Cluster { stats, .. } => format!(
+            "{} clusters of [{}], inertia {}",
+            stats.centroids.len(),
+            stats.inputs.join(", "),
+            stats.inertia
+        ),
+    }
+}
+
+/// Fitted statistics of every saved `features` state, with the columns they describe.
+fn fitted_statistics(pipeline: &Pipeline) -> MlPrepResult<Vec<(Target, String)>> {
+    let mut statistics = Vec::new();
+    for step in &pipeline.steps {
+        let Step::Features(features) = step else {
+            continue;
+        };
+        let Some(state_path) = &features.state_path else {
+            continue;
+        };
+        if !Path::new(state_path).exists() {
+            continue;
+        }
+        let state = FeatureState::load(state_path)
+            .map_err(|e| crate::errors::MlPrepError::FeatureError(e.to_string()))?;
+        for entry in &state.entries {
+            let column = entry_column(entry);
+            let spec = features
+                .config
+                .features
+                .iter()
+                .find(|spec| spec.column == column || spec.columns.iter().any(|c| c == column));
+            let target = match spec {
+                Some(spec) => feature_target(spec, column),
+                None => Target::Column(column.to_string()),
+            };
+            statistics.push((target, describe_entry(entry)));
+        }
+    }
+    Ok(statistics)
+}
+
+fn describe_check(check: &ColumnCheck) -> Vec<String> {
+    let mut rules = Vec::new();
+    if check.not_null {
+        rules.push("not null".to_string());
+    }
+    if check.unique && check.unique_nulls_fail() {
+        rules.push("unique".to_string());
+    } else if check.unique {
+        rules.push("unique (ignoring nulls)".to_string());
+    }
+    match check.bounds() {
+        Some((Some(min), Some(max))) => rules.push(format!("in [{}, {}]", min, max)),
+        Some((Some(min), None)) => rules.push(format!(">= {}", min)),
+        Some((None, Some(max))) => rules.push(format!("<= {}", max)),
+        _ => {}
+    }
+    if let Some(regex) = &check.regex {
+        rules.push(format!("matches `{}`", regex));
+    }
+    if let Some(values) = &check.allowed_values {
+        let values: Vec<String> = values.iter().map(ToString::to_string).collect();
+        rules.push(format!("one of {}", values.join(", ")));
+    }
+    if check.nulls_fail() {
+        rules.push("nulls fail".to_string());
+    }
+    if let Some(repair) = &check.on_violation {
+        rules.push(format!("on violation: {}", repair));
+    }
+    rules
+}
+
+/// Build the catalog of the pipeline at `path`; its output schema is resolved as by
+/// `mlprep run --dry-run`, reading only the input schema.
+pub fn build(
+    path: &Path,
+    security_config: crate::security::SecurityConfig,
+) -> MlPrepResult<FeatureCatalog> {
+    let mut pipeline = Pipeline::from_path(path)?;
+    let report = crate::runner::dry_run(&path.to_path_buf(), security_config.clone(), None, None)?;
+    let security_context = crate::security::SecurityContext::new(security_config)?;
+    crate::runner::load_check_files(&mut pipeline, &security_context)?;
+    let names = |columns: &[(String, String)]| -> Vec<String> {
+        columns.iter().map(|(name, _)| name.clone()).collect()
+    };
+    let input_columns = report
+        .steps
+        .first()
+        .map(|s| names(&s.columns))
+        .unwrap_or_default();
+    let output = report
+        .steps
+        .last()
+        .map(|s| s.columns.clone())
+        .unwrap_or_default();
+    let input = pipeline
+        .inputs
+        .first()
+        .map(|input| input.path.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let lineage = lineage::column_lineage(
+        &input,
+        &input_columns,
+        &pipeline.steps,
+        Some(&names(&output)),
+    )?;
+
+    let effects: Vec<(Target, String)> = pipeline.steps.iter().flat_map(step_effects).collect();
+    let statistics = fitted_statistics(&pipeline)?;
+    let checks: Vec<&ColumnCheck> = pipeline
+        .steps
+        .iter()
+        .filter_map(|step| match step {
+            Step::Validate(validate) => Some(&validate.checks.columns),
+            _ => None,
+        })
+        .flatten()
+        .collect();
+    let mut docs = pipeline.column_docs();
+
+    let columns = output
+        .into_iter()
+        .zip(lineage)
+        .map(|((name, dtype), lineage)| {
+            let matching = |targets: &[(Target, String)]| -> Vec<String> {
+                targets
+                    .iter()
+                    .filter(|(target, _)| target.matches(&name))
+                    .map(|(_, description)| description.clone())
+                    .collect()
+            };
+            ColumnEntry {
+                doc: docs.remove(&name).unwrap_or_default(),
+                sources: lineage.sources,
+                transforms: matching(&effects),
+                statistics: matching(&statistics),
+                validation: checks
+                    .iter()
+                    .filter(|check| check.name == name)
+                    .flat_map(|check| describe_check(check))
+                    .collect(),
+                name,
+                dtype,
+            }
+        })
+        .collect();
+    Ok(FeatureCatalog {
+        pipeline: path.display().to_string(),
+        columns,
+    })
+}
+
+impl FeatureCatalog {
+    pub fn to_markdown(&self) -> String {
+        let mut md = String::new();
+        let _ = writeln!(md, "# Features of `{}`\n", self.pipeline);
+        let _ = writeln!(md, "| Column | Type | Description |");
+        let _ = writeln!(md, "|--------|------|-------------|");
+        for column in &self.columns {
+            let _ = writeln!(
+                md,
+                "| [`{}`](#{}) | {} | {} |",
+                column.name,
+                column.name.to_lowercase(),
+                column.dtype,
+                column.doc.description.as_deref().unwrap_or("")
+            );
+        }
+        for column in &self.columns {
+            let _ = writeln!(md, "\n## {}\n", column.name);
+            let _ = writeln!(md, "- **Type:** {}", column.dtype);
+            if let Some(description) = &column.doc.description {
+                let _ = writeln!(md, "- **Description:** {}", description);
+            }
+            if let Some(unit) = &column.doc.unit {
+                let _ = writeln!(md, "- **Unit:** {}", unit);
+            }
+            if !column.sources.is_empty() {
+                let sources: Vec<String> = column
+                    .sources
+                    .iter()
+                    .map(|s| format!("`{}:{}`", s.input, s.column))
+                    .collect();
+                let _ = writeln!(md, "- **Sources:** {}", sources.join(", "));
+            }
+            for (label, items) in [
+                ("Transforms", &column.transforms),
+                ("Fitted statistics", &column.statistics),
+                ("Validation", &column.validation),
+            ] {
+                if !items.is_empty() {
+                    let _ = writeln!(md, "- **{}:** {}", label, items.join("; "));
+                }
+            }
+        }
+        md
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_build_catalog() {
+        let dir = tempdir().unwrap();
+        let input = dir.path().join("orders.csv");
+        let state = dir.path().join("state.json");
+        let pipeline_path = dir.path().join("pipeline.yaml");
+        fs::write(
+            &input,
+            "id,amount,city\n1,10,tokyo\n2,30,osaka\n3,20,tokyo\n",
+        )
+        .unwrap();
+        fs::write(
+            &pipeline_path,
+            format!(
+                r#"
+inputs:
+  - path: "{}"
+schema:
+  amount: {{dtype: Float64, description: "Order total", unit: JPY}}
+steps:
+  - type: validate
+    checks:
+      columns:
+        - {{name: amount, not_null: true, range: [0, 1000]}}
+  - type: features
+    state_path: "{}"
+    config:
+      features:
+        - {{column: amount, transform: min_max_scale, alias: amount_scaled}}
+        - {{column: city, transform: one_hot_encode}}
+"#,
+                input.display(),
+                state.display()
+            ),
+        )
+        .unwrap();
+
+        // Fit the state so the catalog can report its statistics
+        crate::runner::execution_pipeline(
+            &pipeline_path,
+            uuid::Uuid::new_v4(),
+            Default::default(),
+            None,
+        )
+        .unwrap();
+
+        let catalog = build(&pipeline_path, Default::default()).unwrap();
+        let column = |name: &str| catalog.columns.iter().find(|c| c.name == name).unwrap();
+
+        let amount = column("amount");
+        assert_eq!(amount.dtype, "f64");
+        assert_eq!(amount.doc.unit.as_deref(), Some("JPY"));
+        assert_eq!(amount.validation, vec!["not null", "in [0, 1000]"]);
+        assert_eq!(amount.sources[0].column, "amount");
+
+        let scaled = column("amount_scaled");
+        assert_eq!(scaled.transforms, vec!["MinMaxScale"]);
+        assert_eq!(scaled.statistics, vec!["min 10, max 30"]);
+
+        let tokyo = column("city_tokyo");
+        assert_eq!(tokyo.transforms, vec!["OneHotEncode"]);
+        // The fitted vocabulary is not ordered
+        assert!(
+            tokyo.statistics[0] == "2 categories: osaka, tokyo"
+                || tokyo.statistics[0] == "2 categories: tokyo, osaka",
+            "{:?}",
+            tokyo.statistics
+        );
+
+        let md = catalog.to_markdown();
+        assert!(
+            md.contains("| [`amount`](#amount) | f64 | Order total |"),
+            "{}",
+            md
+        );
+        assert!(
+            md.contains("- **Fitted statistics:** min 10, max 30"),
+            "{}",
+            md
+        );
+    }
+}
@@ -0,0 +1,587 @@
+//! Chunked execution: run a row-wise pipeline over fixed-size row chunks of its input.
+//!
+//! Enabled with `runtime.chunk_size`. Steps that materialize their input (`validate`
+//! summaries, `python_udf` with `row_wise: true`) then only ever hold one chunk in memory.
+//! Feature states are fitted once over the whole input (streaming) before the first chunk
+//! and reused for every chunk; validation counts are summed across chunks and checked
+//! once at the end. Inputs that cannot be sliced cheaply are first copied into a
+//! temporary Parquet file, so each chunk reads only its own rows.
+
+use crate::compute;
+use crate::control::{self, Progress};
+use crate::dsl::{FillNullStrategy, Output, OutputMode, Pipeline, RuntimeConfig, Step};
+use crate::errors::{MlPrepError, MlPrepResult};
+use crate::features::FeatureState;
+use crate::observability::Metrics;
+use crate::validate::{count_violations_lazy, report_from_counts};
+use polars::prelude::*;
+use std::cell::RefCell;
+
+/// Why `pipeline` cannot run chunked, or `None` if it can.
+pub fn unsupported_reason(pipeline: &Pipeline) -> Option<String> {
+    let mut seen_udf = false;
+    for (i, step) in pipeline.steps.iter().enumerate() {
+        let row_wise = match step {
+            Step::Select(_) | Step::Filter(_) | Step::Cast(_) | Step::DropNull(_) => true,
+            Step::DatetimeOps(_) => true,
+            // A key repeated across chunks would pass a per-chunk cardinality check.
+            Step::Join(j) => {
+                matches!(j.how.to_lowercase().as_str(), "inner" | "left") && j.expect.is_none()
+            }
+            Step::FillNull(f) => {
+                matches!(
+                    f.strategy,
+                    FillNullStrategy::Literal | FillNullStrategy::Zero
+                )
+            }
+            // Uniqueness, column statistics, samples and row indices span every chunk.
+            Step::Validate(v) => {
+                v.sample_fraction.is_none()
+                    && v.violations_path.is_none()
+                    && v.sample_rows.is_none()
+                    && !v.checks.columns.iter().any(|c| c.unique)
+                    && v.checks.stats.is_empty()
+                    && v.checks.unique_keys.is_empty()
+            }
+            // Fitting happens before the first chunk, on a plan that cannot include a UDF.
+            Step::Features(_) => !seen_udf,
+            // Any DataFrame callable is allowed, so only one declared row-wise is split up.
+            Step::PythonUdf(u) => {
+                seen_udf = true;
+                u.row_wise
+            }
+            _ => false,
+        };
+        if !row_wise {
+            return Some(format!(
+                "step {} ({}) needs the whole frame",
+                i + 1,
+                step.kind()
+            ));
+        }
+    }
+    if let Some(output) = pipeline.outputs.first() {
+        if output.mode == OutputMode::Append || crate::io::is_dataset_dir(&output.path) {
+            return Some(format!("output {} is appended to", output.path.display()));
+        }
+        if output.is_split() {
+            return Some(format!(
+                "output {} is split into part files",
+                output.path.display()
+            ));
+        }
+        if !(crate::io::has_extension(&output.path, "parquet")
+            || crate::io::has_extension(&output.path, "csv"))
+        {
+            return Some(format!(
+                "output {} is not Parquet or CSV",
+                output.path.display()
+            ));
+        }
+    }
+    None
+}
+
+/// Run `pipeline`'s steps over `source` in chunks of `chunk_size` rows and write the
+/// result to `output`, if any. Rows read and written and validation violations are
+/// recorded in `metrics`. The pipeline must pass [`unsupported_reason`].
+pub fn execute_chunked(
+    source: LazyFrame,
+    pipeline: &Pipeline,
+    runtime: &RuntimeConfig,
+    chunk_size: usize,
+    output: Option<&Output>,
+    metrics: &mut Metrics,
+) -> MlPrepResult<()> {
+    if chunk_size == 0 {
+        return Err(MlPrepError::TransformError(
+            "chunk_size must be at least 1".to_string(),
+        ));
+    }
+    let source = match &pipeline.schema {
+        Some(schema) => compute::apply_schema(source, schema.clone())?,
+        None => source,
+    };
+    let (source, _spool) = sliceable(source, chunk_size)?;
+    let states = fit_states(&source, &pipeline.steps, runtime)?;
+    // Warnings of row-wise steps, raised per chunk
+    let chunk_metrics = RefCell::new(Metrics::new());
+    let counts: RefCell<Vec<Vec<usize>>> = RefCell::new(
+        pipeline
+            .steps
+            .iter()
+            .map(|step| match step {
+                // One count per row-wise check, then the rows checked
+                Step::Validate(v) => {
+                    vec![0; v.checks.columns.len() + v.checks.conditions.len() + 1]
+                }
+                _ => Vec::new(),
+            })
+            .collect(),
+    );
+
+    let mut offset = 0usize;
+    let mut done = false;
+    let mut next_chunk = || -> MlPrepResult<Option<DataFrame>> {
+        if done {
+            return Ok(None);
+        }
+        control::check_cancelled()?;
+        let chunk = source
+            .clone()
+            .slice(offset as i64, chunk_size as IdxSize)
+            .collect()
+            .map_err(MlPrepError::PolarsError)?;
+        offset += chunk.height();
+        done = chunk.height() < chunk_size;
+        // An empty input still yields one (empty) chunk, so the output gets a schema.
+        if chunk.height() == 0 && offset > 0 {
+            return Ok(None);
+        }
+        control::report(Progress::Rows(chunk.height() as u64));
+        let counts = &mut counts.borrow_mut();
+        let chunk_metrics = &mut chunk_metrics.borrow_mut();
+        run_chunk(
+            chunk,
+            &pipeline.steps,
+            &states,
+            counts,
+            runtime,
+            chunk_metrics,
+        )
+        .map(Some)
+    };
+    // Runs before the output is moved into place, so a strict failure leaves none behind.
+    let finish_validation = |metrics: &mut Metrics| -> MlPrepResult<()> {
+        for (i, (step, counts)) in pipeline
+            .steps
+            .iter()
+            .zip(counts.borrow().iter())
+            .enumerate()
+        {
+            if let Step::Validate(v) = step {
+                metrics.enter_step(i + 1, step.kind());
+                let report = report_from_counts(&v.checks, counts);
+                compute::finish_validation(&report, v, metrics)?;
+            }
+        }
+        Ok(())
+    };
+
+    let docs = pipeline.column_docs();
+    match output {
+        Some(output) => crate::io::write_atomic(&output.path, |file| {
+            metrics.rows_written = write_chunks(
+                file,
+                crate::io::has_extension(&output.path, "parquet"),
+                &docs,
+                &mut next_chunk,
+            )?;
+            finish_validation(metrics)
+        })?,
+        None => {
+            while next_chunk()?.is_some() {}
+            finish_validation(metrics)?;
+        }
+    }
+    metrics.rows_read = offset;
+    metrics.warnings.extend(chunk_metrics.into_inner().warnings);
+    Ok(())
+}
+
+/// `source`, or a copy of it that each chunk can be sliced from without reading the rows
+/// before it. Slicing a Parquet scan or an in-memory frame skips straight to the chunk;
+/// anything else (a CSV scan re-parses the file from the start for every slice) is
+/// streamed once into a temporary Parquet file with one row group per chunk, which is
+/// deleted when the returned [`Spool`] drops.
+fn sliceable(source: LazyFrame, chunk_size: usize) -> MlPrepResult<(LazyFrame, Option<Spool>)> {
+    let scans = crate::io::explain_scans(source.clone())?;
+    if scans.iter().all(|scan| scan.format == "parquet") {
+        return Ok((source, None));
+    }
+    control::check_cancelled()?;
+    let spool =
+        Spool(std::env::temp_dir().join(format!("mlprep-chunks-{}.parquet", uuid::Uuid::new_v4())));
+    let options = ParquetWriteOptions {
+        row_group_size: Some(chunk_size),
+        ..Default::default()
+    };
+    match source
+        .clone()
+        .sink_parquet(&spool.0, options, None)
+        .map_err(MlPrepError::PolarsError)
+    {
+        Ok(()) => {}
+        // Only the input and its schema casts are planned here, so this is rare; such a
+        // source is held in memory anyway.
+        Err(e) if crate::io::is_not_streamable(&e) => {
+            let df = source.collect().map_err(MlPrepError::PolarsError)?;
+            return Ok((df.lazy(), None));
+        }
+        Err(e) => return Err(e),
+    }
+    let spooled = crate::io::read_parquet(&spool.0)?;
+    Ok((spooled, Some(spool)))
+}
+
+/// A temporary copy of a chunked run's input, removed on drop.
+struct Spool(std::path::PathBuf);
+
+impl Drop for Spool {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.0);
+    }
+}
+
+/// Fitted state for each `features` step (by step index), fitted over the whole input.
+fn fit_states(
+    source: &LazyFrame,
+    steps: &[Step],
+    runtime: &RuntimeConfig,
+) -> MlPrepResult<Vec<Option<FeatureState>>> {
+    let mut states = vec![None; steps.len()];
+    let Some(last) = steps.iter().rposition(|s| matches!(s, Step::Features(_))) else {
+        return Ok(states);
+    };
+    let mut lf = source.clone();
+    // Fitting only plans the steps; their warnings are raised again by the chunks.
+    let metrics = &mut Metrics::new();
+    for (i, step) in steps[..=last].iter().enumerate() {
+        lf = match step {
+            Step::Features(f) => {
+                let state = compute::resolve_feature_state(&lf, f, true, runtime.seed)?;
+                let lf = compute::apply_feature_state(lf, f, &state)?;
+                states[i] = Some(state);
+                lf
+            }
+            Step::Validate(v) => quarantine(lf, v)?,
+            other => compute::apply_row_step(lf, other.clone(), runtime, metrics)?,
+        };
+    }
+    Ok(states)
+}
+
+fn run_chunk(
+    chunk: DataFrame,
+    steps: &[Step],
+    states: &[Option<FeatureState>],
+    counts: &mut [Vec<usize>],
+    runtime: &RuntimeConfig,
+    metrics: &mut Metrics,
+) -> MlPrepResult<DataFrame> {
+    let mut lf = chunk.lazy();
+    for (i, step) in steps.iter().enumerate() {
+        metrics.enter_step(i + 1, step.kind());
+        lf = match step {
+            Step::Features(f) => {
+                let state = states[i].as_ref().ok_or_else(|| {
+                    MlPrepError::FeatureError("Missing fitted feature state".to_string())
+                })?;
+                compute::apply_feature_state(lf, f, state)?
+            }
+            Step::Validate(v) => {
+                let df = lf.collect().map_err(MlPrepError::PolarsError)?;
+                let chunk_counts = count_violations_lazy(df.clone().lazy(), &v.checks, false)
+                    .map_err(|e| {
+                        MlPrepError::ValidationError(format!("Validation execution failed: {}", e))
+                    })?;
+                for (total, n) in counts[i].iter_mut().zip(chunk_counts) {
+                    *total += n;
+                }
+                quarantine(df.lazy(), v)?
+            }
+            other => compute::apply_row_step(lf, other.clone(), runtime, metrics)?,
+        };
+    }
+    lf.collect().map_err(MlPrepError::PolarsError)
+}
+
+/// Repair violating rows and drop the rest for quarantine-mode validation; other modes
+/// keep every row.
+fn quarantine(lf: LazyFrame, validate: &crate::dsl::Validate) -> MlPrepResult<LazyFrame> {
+    compute::validated_rows(lf, validate)
+}
+
+/// Write every chunk produced by `next_chunk` to `file`; returns the rows written.
+/// Parquet files record `docs` in their metadata.
+pub(crate) fn write_chunks(
+    file: &mut std::fs::File,
+    parquet: bool,
+    docs: &crate::io::ColumnDocs,
+    next_chunk: &mut dyn FnMut() -> MlPrepResult<Option<DataFrame>>,
+) -> MlPrepResult<usize> {
+    let Some(mut first) = next_chunk()? else {
+        return Ok(0);
+    };
+    let schema = first.schema().clone();
+    let mut rows = first.height();
+    let check_schema = |df: &DataFrame| {
+        if *df.schema() == schema {
+            Ok(())
+        } else {
+            Err(MlPrepError::TransformError(
+                "Chunks produced different output schemas".to_string(),
+            ))
+        }
+    };
+
+    if parquet {
+        let mut writer = ParquetWriter::new(file)
+            .batched(&schema)
+            .map_err(MlPrepError::PolarsError)?;
+        writer
+            .write_batch(&first)
+            .map_err(MlPrepError::PolarsError)?;
+        while let Some(df) = next_chunk()? {
+            if df.height() == 0 {
+                continue;
+            }
+            check_schema(&df)?;
+            rows += df.height();
+            writer.write_batch(&df).map_err(MlPrepError::PolarsError)?;
+        }
+        let metadata = crate::io::column_docs_metadata(docs, &schema)?;
+        crate::io::finish_parquet(&writer, metadata)?;
+    } else {
+        CsvWriter::new(&mut *file)
+            .finish(&mut first)
+            .map_err(MlPrepError::PolarsError)?;
+        while let Some(mut df) = next_chunk()? {
+            if df.height() == 0 {
+                continue;
+            }
+            check_schema(&df)?;
+            rows += df.height();
+            CsvWriter::new(&mut *file)
+                .include_header(false)
+                .finish(&mut df)
+                .map_err(MlPrepError::PolarsError)?;
+        }
+    }
+    Ok(rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    const STEPS: &str = r#"
+steps:
+  - type: fill_null
+    columns: [price]
+    strategy: literal
+    value: 0
+  - type: validate
+    mode: quarantine
+    checks:
+      columns:
+        - name: price
+          range: [0, 100]
+  - type: validate
+    mode: warn
+    checks:
+      columns:
+        - name: city
+          not_null: true
+      conditions:
+        - when: "city = 'tokyo'"
+          require: "price < 50"
+  - type: features
+    config:
+      features:
+        - column: price
+          transform: standard_scale
+        - column: city
+          transform: one_hot_encode
+"#;
+
+    fn input() -> DataFrame {
+        df!(
+            "price" => [Some(10.0), None, Some(500.0), Some(30.0), Some(40.0), Some(50.0), Some(60.0)],
+            "city" => [Some("tokyo"), Some("osaka"), Some("tokyo"), None, Some("osaka"), Some("kyoto"), Some("tokyo")]
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_chunked_matches_whole_frame() {
+        let dir = tempdir().unwrap();
+        let output_path = dir.path().join("out.parquet");
+        let mut pipeline = Pipeline::from_reader(STEPS.as_bytes()).unwrap();
+        assert_eq!(unsupported_reason(&pipeline), None);
+        let runtime = RuntimeConfig::default();
+
+        let mut whole_metrics = Metrics::new();
+        let security = crate::security::SecurityContext::new(Default::default()).unwrap();
+        let expected = compute::apply_pipeline_tracked(
+            input().lazy(),
+            pipeline.clone(),
+            &runtime,
+            &security,
+            &indicatif::ProgressBar::hidden(),
+            &mut whole_metrics,
+        )
+        .unwrap()
+        .collect()
+        .unwrap();
+
+        pipeline.outputs.push(Output {
+            path: output_path.clone(),
+            format: None,
+            compression: None,
+            partition_by: None,
+            mode: OutputMode::Overwrite,
+            feast: None,
+            max_rows_per_file: None,
+            max_file_size: None,
+        });
+        let mut metrics = Metrics::new();
+        execute_chunked(
+            input().lazy(),
+            &pipeline,
+            &runtime,
+            2,
+            pipeline.outputs.first(),
+            &mut metrics,
+        )
+        .unwrap();
+
+        let written = crate::io::read_parquet(&output_path)
+            .unwrap()
+            .collect()
+            .unwrap();
+        // One-hot columns are named by category, in the order the fit saw them.
+        let expected = expected.select(written.get_column_names_str()).unwrap();
+        // The scaler is fitted in streaming mode, so means may differ in the last bits.
+        let price = |df: &DataFrame| -> Vec<f64> {
+            df.column("price")
+                .unwrap()
+                .f64()
+                .unwrap()
+                .into_no_null_iter()
+                .collect()
+        };
+        for (w, e) in price(&written).iter().zip(price(&expected)) {
+            assert!((w - e).abs() < 1e-9, "{written}\n{expected}");
+        }
+        let rest = |df: &DataFrame| df.drop("price").unwrap();
+        assert!(
+            rest(&written).equals_missing(&rest(&expected)),
+            "{written}\n{expected}"
+        );
+        assert_eq!(metrics.rows_read, 7);
+        assert_eq!(metrics.rows_written, 6);
+        assert_eq!(metrics.violations, whole_metrics.violations);
+        // A null city and one tokyo price over 50
+        assert_eq!(metrics.violations, 3);
+    }
+
+    #[test]
+    fn test_chunked_csv_and_strict_validation() {
+        let dir = tempdir().unwrap();
+        let output_path = dir.path().join("out.csv");
+        let output = Output {
+            path: output_path.clone(),
+            format: None,
+            compression: None,
+            partition_by: None,
+            mode: OutputMode::Overwrite,
+            feast: None,
+            max_rows_per_file: None,
+            max_file_size: None,
+        };
+        let select =
+            Pipeline::from_reader("steps:\n  - type: select\n    columns: [city]\n".as_bytes())
+                .unwrap();
+        let mut metrics = Metrics::new();
+        execute_chunked(
+            input().lazy(),
+            &select,
+            &RuntimeConfig::default(),
+            3,
+            Some(&output),
+            &mut metrics,
+        )
+        .unwrap();
+        let text = std::fs::read_to_string(&output_path).unwrap();
+        assert_eq!(text.lines().filter(|l| *l == "city").count(), 1);
+        assert_eq!(text.lines().count(), 8);
+
+        std::fs::remove_file(&output_path).unwrap();
+        let strict = Pipeline::from_reader(
+            "steps:\n  - type: validate\n    checks:\n      columns:\n        - name: city\n          not_null: true\n"
+                .as_bytes(),
+        )
+        .unwrap();
+        let err = execute_chunked(
+            input().lazy(),
+            &strict,
+            &RuntimeConfig::default(),
+            3,
+            Some(&output),
+            &mut Metrics::new(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, MlPrepError::ValidationError(_)));
+        assert!(!output_path.exists());
+    }
+
+    #[test]
+    fn test_sliceable_spools_csv_input() {
+        let dir = tempdir().unwrap();
+        let csv = dir.path().join("in.csv");
+        std::fs::write(&csv, "x\n1\n2\n3\n4\n5\n").unwrap();
+        let (lf, spool) = sliceable(crate::io::read_csv(&csv).unwrap(), 2).unwrap();
+        let spool_path = spool.as_ref().unwrap().0.clone();
+        assert_eq!(
+            crate::io::explain_scans(lf.clone()).unwrap()[0].format,
+            "parquet"
+        );
+        let chunk = lf.slice(2, 2).collect().unwrap();
+        let x: Vec<_> = chunk
+            .column("x")
+            .unwrap()
+            .i64()
+            .unwrap()
+            .into_no_null_iter()
+            .collect();
+        assert_eq!(x, [3, 4]);
+        drop(spool);
+        assert!(!spool_path.exists());
+
+        assert!(sliceable(input().lazy(), 2).unwrap().1.is_none());
+    }
+
+    #[test]
+    fn test_unsupported_reason() {
+        let reason =
+            |yaml: &str| unsupported_reason(&Pipeline::from_reader(yaml.as_bytes()).unwrap());
+        assert_eq!(
+            reason("steps:\n  - type: sort\n    by: [a]\n").as_deref(),
+            Some("step 1 (sort) needs the whole frame")
+        );
+        assert!(reason(
+            "steps:\n  - type: validate\n    checks:\n      columns:\n        - name: a\n          unique: true\n"
+        )
+        .is_some());
+        assert!(reason("steps:\n  - type: python_udf\n    function: f\n    row_wise: true\n  - type: features\n    config:\n      features: []\n").is_some());
+        assert_eq!(
+            reason("steps:\n  - type: python_udf\n    function: f\n").as_deref(),
+            Some("step 1 (python_udf) needs the whole frame")
+        );
+        assert_eq!(
+            reason("steps:\n  - type: python_udf\n    function: f\n    row_wise: true\n"),
+            None
+        );
+        assert!(reason(
+            "steps:\n  - type: join\n    right_path: r.csv\n    left_on: [id]\n    right_on: [id]\n    how: left\n    expect: one_to_one\n"
+        )
+        .is_some());
+        assert!(reason("steps: []\noutputs:\n  - path: out.parquet\n    mode: append\n").is_some());
+        assert_eq!(
+            reason("steps:\n  - type: drop_null\n    columns: [a]\n"),
+            None
+        );
+    }
+}
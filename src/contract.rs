@@ -0,0 +1,439 @@
+//! Schema contracts: the columns, dtypes, nullability and value ranges an input must
+//! have. `mlprep contract generate` writes one from a sample file; an input's
+//! `contract:` is checked before any step runs, and a mismatch fails the run with a
+//! diff of contract against input.
+
+use crate::errors::{MlPrepError, MlPrepResult};
+use polars::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::path::Path;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Contract {
+    /// Accept input columns the contract does not list
+    #[serde(default)]
+    pub allow_extra_columns: bool,
+    pub columns: Vec<ColumnContract>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ColumnContract {
+    pub name: String,
+    /// Polars dtype name without parameters, e.g. `Int64`, `Float64`, `String`, `Datetime`
+    pub dtype: String,
+    #[serde(default = "default_nullable")]
+    pub nullable: bool,
+    /// Inclusive bounds for numeric columns
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max: Option<f64>,
+}
+
+fn default_nullable() -> bool {
+    true
+}
+
+/// Name of `dtype` as written in contracts: its Polars name without parameters.
+pub fn dtype_name(dtype: &DataType) -> String {
+    let name = format!("{:?}", dtype);
+    match name.split_once('(') {
+        Some((base, _)) => base.to_string(),
+        None => name,
+    }
+}
+
+/// One way an input differs from its contract.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ContractDifference {
+    MissingColumn {
+        column: String,
+        dtype: String,
+    },
+    UnexpectedColumn {
+        column: String,
+        dtype: String,
+    },
+    Dtype {
+        column: String,
+        expected: String,
+        actual: String,
+    },
+    Nulls {
+        column: String,
+        count: u64,
+    },
+    OutOfRange {
+        column: String,
+        count: u64,
+        min: Option<f64>,
+        max: Option<f64>,
+        observed: (Option<f64>, Option<f64>),
+    },
+}
+
+fn bound(value: Option<f64>) -> String {
+    value.map_or_else(|| "..".to_string(), |v| v.to_string())
+}
+
+/// Diff lines: `-` for what the contract expects, `+` for what the input has.
+impl fmt::Display for ContractDifference {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ContractDifference::MissingColumn { column, dtype } => {
+                write!(f, "- {}: {}", column, dtype)
+            }
+            ContractDifference::UnexpectedColumn { column, dtype } => {
+                write!(f, "+ {}: {}", column, dtype)
+            }
+            ContractDifference::Dtype {
+                column,
+                expected,
+                actual,
+            } => write!(f, "- {}: {}\n+ {}: {}", column, expected, column, actual),
+            ContractDifference::Nulls { column, count } => {
+                write!(
+                    f,
+                    "- {}: not nullable\n+ {}: {} nulls",
+                    column, column, count
+                )
+            }
+            ContractDifference::OutOfRange {
+                column,
+                count,
+                min,
+                max,
+                observed,
+            } => write!(
+                f,
+                "- {}: range [{}, {}]\n+ {}: {} values outside (observed [{}, {}])",
+                column,
+                bound(*min),
+                bound(*max),
+                column,
+                count,
+                bound(observed.0),
+                bound(observed.1)
+            ),
+        }
+    }
+}
+
+/// First-row value of `column` in `row` as f64, `None` if null.
+fn scalar(row: &DataFrame, column: &str) -> MlPrepResult<Option<f64>> {
+    let value = row
+        .column(column)
+        .map_err(MlPrepError::PolarsError)?
+        .get(0)
+        .map_err(MlPrepError::PolarsError)?;
+    Ok(value.extract::<f64>())
+}
+
+impl Contract {
+    pub fn load<P: AsRef<Path>>(path: P) -> MlPrepResult<Self> {
+        let text = std::fs::read_to_string(path).map_err(MlPrepError::IoError)?;
+        serde_yaml::from_str(&text).map_err(|e| MlPrepError::ConfigError(e, None))
+    }
+
+    /// Write the contract as YAML, under a comment naming the input it describes.
+    pub fn save<P: AsRef<Path>>(&self, path: P, source: &str) -> MlPrepResult<()> {
+        std::fs::write(path, self.to_yaml(source)?).map_err(MlPrepError::IoError)
+    }
+
+    pub fn to_yaml(&self, source: &str) -> MlPrepResult<String> {
+        let body = serde_yaml::to_string(self).map_err(|e| MlPrepError::ConfigError(e, None))?;
+        Ok(format!(
+            "# Generated by `mlprep contract generate` from {}; edit bounds as needed.\n{}",
+            source, body
+        ))
+    }
+
+    /// Describe `lf` as it is: every column with its dtype, whether it has nulls, and
+    /// the observed range of numeric columns. Reads the data once.
+    pub fn generate(mut lf: LazyFrame, streaming: bool) -> MlPrepResult<Self> {
+        let schema = lf.collect_schema().map_err(MlPrepError::PolarsError)?;
+        let mut exprs = Vec::new();
+        for (i, (name, dtype)) in schema.iter().enumerate() {
+            let column = col(name.clone());
+            exprs.push(column.clone().null_count().alias(format!("__nulls{}", i)));
+            if dtype.is_primitive_numeric() {
+                let values = column.cast(DataType::Float64);
+                exprs.push(values.clone().min().alias(format!("__min{}", i)));
+                exprs.push(values.max().alias(format!("__max{}", i)));
+            }
+        }
+        let stats = lf
+            .select(exprs)
+            .with_streaming(streaming)
+            .collect()
+            .map_err(MlPrepError::PolarsError)?;
+
+        let mut columns = Vec::with_capacity(schema.len());
+        for (i, (name, dtype)) in schema.iter().enumerate() {
+            let nulls = scalar(&stats, &format!("__nulls{}", i))?.unwrap_or(0.0);
+            let (min, max) = if dtype.is_primitive_numeric() {
+                (
+                    scalar(&stats, &format!("__min{}", i))?,
+                    scalar(&stats, &format!("__max{}", i))?,
+                )
+            } else {
+                (None, None)
+            };
+            columns.push(ColumnContract {
+                name: name.to_string(),
+                dtype: dtype_name(dtype),
+                nullable: nulls > 0.0,
+                min,
+                max,
+            });
+        }
+        Ok(Contract {
+            allow_extra_columns: false,
+            columns,
+        })
+    }
+
+    /// Every difference between `lf` and this contract. Column names and dtypes come
+    /// from the schema; nulls and ranges are counted in one pass over the data, only
+    /// for columns whose dtype matches.
+    pub fn check(
+        &self,
+        mut lf: LazyFrame,
+        streaming: bool,
+    ) -> MlPrepResult<Vec<ContractDifference>> {
+        let schema = lf.collect_schema().map_err(MlPrepError::PolarsError)?;
+        let mut differences = Vec::new();
+        let mut exprs = Vec::new();
+        let mut counted = Vec::new();
+        for (i, spec) in self.columns.iter().enumerate() {
+            let Some(dtype) = schema.get(spec.name.as_str()) else {
+                differences.push(ContractDifference::MissingColumn {
+                    column: spec.name.clone(),
+                    dtype: spec.dtype.clone(),
+                });
+                continue;
+            };
+            let actual = dtype_name(dtype);
+            if !actual.eq_ignore_ascii_case(&spec.dtype) {
+                differences.push(ContractDifference::Dtype {
+                    column: spec.name.clone(),
+                    expected: spec.dtype.clone(),
+                    actual,
+                });
+                continue;
+            }
+            let column = col(spec.name.as_str());
+            if !spec.nullable {
+                exprs.push(column.clone().null_count().alias(format!("__nulls{}", i)));
+            }
+            if spec.min.is_some() || spec.max.is_some() {
+                let values = column.cast(DataType::Float64);
+                let below = spec
+                    .min
+                    .map_or(lit(false), |min| values.clone().lt(lit(min)));
+                let above = spec
+                    .max
+                    .map_or(lit(false), |max| values.clone().gt(lit(max)));
+                exprs.push(
+                    below
+                        .or(above)
+                        .fill_null(false)
+                        .cast(DataType::UInt64)
+                        .sum()
+                        .alias(format!("__outside{}", i)),
+                );
+                exprs.push(values.clone().min().alias(format!("__min{}", i)));
+                exprs.push(values.max().alias(format!("__max{}", i)));
+            }
+            counted.push(i);
+        }
+        if !self.allow_extra_columns {
+            for (name, dtype) in schema.iter() {
+                if !self.columns.iter().any(|spec| spec.name == name.as_str()) {
+                    differences.push(ContractDifference::UnexpectedColumn {
+                        column: name.to_string(),
+                        dtype: dtype_name(dtype),
+                    });
+                }
+            }
+        }
+        if exprs.is_empty() {
+            return Ok(differences);
+        }
+
+        let stats = lf
+            .select(exprs)
+            .with_streaming(streaming)
+            .collect()
+            .map_err(MlPrepError::PolarsError)?;
+        for i in counted {
+            let spec = &self.columns[i];
+            if !spec.nullable {
+                let count = scalar(&stats, &format!("__nulls{}", i))?.unwrap_or(0.0) as u64;
+                if count > 0 {
+                    differences.push(ContractDifference::Nulls {
+                        column: spec.name.clone(),
+                        count,
+                    });
+                }
+            }
+            if spec.min.is_some() || spec.max.is_some() {
+                let count = scalar(&stats, &format!("__outside{}", i))?.unwrap_or(0.0) as u64;
+                if count > 0 {
+                    differences.push(ContractDifference::OutOfRange {
+                        column: spec.name.clone(),
+                        count,
+                        min: spec.min,
+                        max: spec.max,
+                        observed: (
+                            scalar(&stats, &format!("__min{}", i))?,
+                            scalar(&stats, &format!("__max{}", i))?,
+                        ),
+                    });
+                }
+            }
+        }
+        Ok(differences)
+    }
+}
+
+/// Fail with a diff-style report unless the input `lf` (read from `input`) matches the
+/// contract at `contract_path`.
+pub fn enforce<I: AsRef<Path>, C: AsRef<Path>>(
+    lf: LazyFrame,
+    input: I,
+    contract_path: C,
+    streaming: bool,
+) -> MlPrepResult<()> {
+    let contract = Contract::load(&contract_path)?;
+    let differences = contract.check(lf, streaming)?;
+    if differences.is_empty() {
+        return Ok(());
+    }
+    let lines: Vec<String> = differences.iter().map(ToString::to_string).collect();
+    let (input, contract_path) = (input.as_ref().display(), contract_path.as_ref().display());
+    Err(MlPrepError::ContractError(format!(
+        "{} does not match {} ({} differences)\n--- {}\n+++ {}\n{}",
+        input,
+        contract_path,
+        differences.len(),
+        contract_path,
+        input,
+        lines.join("\n")
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> LazyFrame {
+        df!(
+            "id" => [1i64, 2, 3],
+            "price" => [Some(9.5), None, Some(20.0)],
+            "city" => ["a", "b", "c"]
+        )
+        .unwrap()
+        .lazy()
+    }
+
+    #[test]
+    fn test_generate_contract() {
+        let contract = Contract::generate(sample(), false).unwrap();
+        assert!(!contract.allow_extra_columns);
+        assert_eq!(
+            contract.columns,
+            vec![
+                ColumnContract {
+                    name: "id".to_string(),
+                    dtype: "Int64".to_string(),
+                    nullable: false,
+                    min: Some(1.0),
+                    max: Some(3.0),
+                },
+                ColumnContract {
+                    name: "price".to_string(),
+                    dtype: "Float64".to_string(),
+                    nullable: true,
+                    min: Some(9.5),
+                    max: Some(20.0),
+                },
+                ColumnContract {
+                    name: "city".to_string(),
+                    dtype: "String".to_string(),
+                    nullable: false,
+                    min: None,
+                    max: None,
+                },
+            ]
+        );
+        assert!(contract.check(sample(), false).unwrap().is_empty());
+
+        let yaml = contract.to_yaml("sample.csv").unwrap();
+        assert!(yaml.starts_with("# Generated by `mlprep contract generate` from sample.csv"));
+        let parsed: Contract = serde_yaml::from_str(&yaml).unwrap();
+        assert_eq!(parsed, contract);
+    }
+
+    #[test]
+    fn test_check_reports_every_difference() {
+        let contract: Contract = serde_yaml::from_str(
+            r#"
+columns:
+  - {name: id, dtype: Int64, nullable: false}
+  - {name: price, dtype: Float64, nullable: false, min: 0, max: 10}
+  - {name: city, dtype: Int64}
+  - {name: country, dtype: String}
+"#,
+        )
+        .unwrap();
+        let lf = sample().with_column(lit(1i32).alias("extra"));
+
+        let differences = contract.check(lf, false).unwrap();
+        assert_eq!(
+            differences,
+            vec![
+                ContractDifference::Dtype {
+                    column: "city".to_string(),
+                    expected: "Int64".to_string(),
+                    actual: "String".to_string(),
+                },
+                ContractDifference::MissingColumn {
+                    column: "country".to_string(),
+                    dtype: "String".to_string(),
+                },
+                ContractDifference::UnexpectedColumn {
+                    column: "extra".to_string(),
+                    dtype: "Int32".to_string(),
+                },
+                ContractDifference::Nulls {
+                    column: "price".to_string(),
+                    count: 1,
+                },
+                ContractDifference::OutOfRange {
+                    column: "price".to_string(),
+                    count: 1,
+                    min: Some(0.0),
+                    max: Some(10.0),
+                    observed: (Some(9.5), Some(20.0)),
+                },
+            ]
+        );
+        assert_eq!(
+            differences[4].to_string(),
+            "- price: range [0, 10]\n+ price: 1 values outside (observed [9.5, 20])"
+        );
+
+        let lenient = Contract {
+            allow_extra_columns: true,
+            ..contract
+        };
+        let extra = sample().with_column(lit(1i32).alias("extra"));
+        assert!(!lenient
+            .check(extra, false)
+            .unwrap()
+            .iter()
+            .any(|d| matches!(d, ContractDifference::UnexpectedColumn { .. })));
+    }
+}
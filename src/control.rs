@@ -0,0 +1,134 @@
+//! Cooperative cancellation and progress reporting for a run.
+//!
+//! The host (the Python module) installs a [`RunControl`] on the thread executing the
+//! pipeline. The runner checks it between stages and reports progress through it;
+//! plans built on that thread capture it so streaming execution can stop between chunks.
+
+use crate::errors::{MlPrepError, MlPrepResult};
+use std::cell::RefCell;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::Sender;
+use std::sync::Arc;
+
+/// Progress notifications emitted while a pipeline runs.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Progress {
+    /// A runner stage started (`build_graph`, `execution`, `write_output`)
+    Stage(&'static str),
+    /// Step `index` (1-based) of `total` is being planned
+    Step {
+        index: usize,
+        total: usize,
+        kind: &'static str,
+    },
+    /// `rows` more rows came out of the plan
+    Rows(u64),
+}
+
+#[derive(Debug, Default)]
+pub struct RunControl {
+    cancelled: AtomicBool,
+    progress: Option<Sender<Progress>>,
+}
+
+impl RunControl {
+    pub fn new(progress: Option<Sender<Progress>>) -> Arc<Self> {
+        Arc::new(Self {
+            cancelled: AtomicBool::new(false),
+            progress,
+        })
+    }
+
+    /// Ask the run to stop at the next check.
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+    }
+
+    pub fn check(&self) -> MlPrepResult<()> {
+        if self.is_cancelled() {
+            Err(MlPrepError::Cancelled)
+        } else {
+            Ok(())
+        }
+    }
+
+    pub fn report(&self, event: Progress) {
+        if let Some(sender) = &self.progress {
+            // The receiver going away only means nobody is listening any more.
+            let _ = sender.send(event);
+        }
+    }
+}
+
+thread_local! {
+    static CURRENT: RefCell<Option<Arc<RunControl>>> = const { RefCell::new(None) };
+}
+
+/// Uninstalls the control when dropped.
+pub struct ControlGuard {
+    previous: Option<Arc<RunControl>>,
+}
+
+impl Drop for ControlGuard {
+    fn drop(&mut self) {
+        let previous = self.previous.take();
+        CURRENT.with(|c| *c.borrow_mut() = previous);
+    }
+}
+
+/// Make `control` the current control of this thread until the guard is dropped.
+pub fn install(control: Arc<RunControl>) -> ControlGuard {
+    let previous = CURRENT.with(|c| c.borrow_mut().replace(control));
+    ControlGuard { previous }
+}
+
+pub fn current() -> Option<Arc<RunControl>> {
+    CURRENT.with(|c| c.borrow().clone())
+}
+
+/// Fail with [`MlPrepError::Cancelled`] if the current run was cancelled.
+pub fn check_cancelled() -> MlPrepResult<()> {
+    current().map_or(Ok(()), |control| control.check())
+}
+
+/// Replace an execution error with [`MlPrepError::Cancelled`] when it was caused by
+/// cancelling the current run (streaming plans abort with a compute error).
+pub fn or_cancelled(error: MlPrepError) -> MlPrepError {
+    match current() {
+        Some(control) if control.is_cancelled() => MlPrepError::Cancelled,
+        _ => error,
+    }
+}
+
+/// Report progress to the current control, if any.
+pub fn report(event: Progress) {
+    if let Some(control) = current() {
+        control.report(event);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_install_cancel_and_report() {
+        assert!(check_cancelled().is_ok());
+        let (tx, rx) = std::sync::mpsc::channel();
+        let control = RunControl::new(Some(tx));
+        {
+            let _guard = install(control.clone());
+            report(Progress::Stage("execution"));
+            assert!(check_cancelled().is_ok());
+            control.cancel();
+            assert!(matches!(check_cancelled(), Err(MlPrepError::Cancelled)));
+        }
+        // Uninstalled with the guard.
+        assert!(check_cancelled().is_ok());
+        assert_eq!(rx.try_recv(), Ok(Progress::Stage("execution")));
+    }
+}
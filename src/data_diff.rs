@@ -0,0 +1,402 @@
+//! Row-level comparison of two datasets matched on key columns (`mlprep diff`): rows
+//! only in one dataset, rows whose values changed, and per-column change statistics.
+//! Used to check that a refactored pipeline still produces the same output.
+
+use crate::errors::{MlPrepError, MlPrepResult};
+use crate::observability::SchemaField;
+use polars::prelude::*;
+use serde::Serialize;
+use std::fmt;
+
+/// Keys listed per category of changed rows.
+const SAMPLE_KEYS: IdxSize = 5;
+
+/// Value changes in one column present in both datasets.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ColumnDiff {
+    pub column: String,
+    pub dtype_a: String,
+    pub dtype_b: String,
+    /// Matched rows whose value differs (a null on one side only counts as a change)
+    pub changed: usize,
+    /// Largest absolute difference over matched rows, for numeric columns
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_abs_diff: Option<f64>,
+    /// Mean absolute difference over changed rows, for numeric columns
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mean_abs_diff: Option<f64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DataDiff {
+    pub keys: Vec<String>,
+    pub rows_a: usize,
+    pub rows_b: usize,
+    /// Keys present in both datasets
+    pub matched: usize,
+    /// Rows only in B
+    pub added: usize,
+    /// Rows only in A
+    pub removed: usize,
+    /// Matched rows with at least one changed value
+    pub changed: usize,
+    pub only_in_a: Vec<SchemaField>,
+    pub only_in_b: Vec<SchemaField>,
+    pub columns: Vec<ColumnDiff>,
+    /// Up to five keys of added, removed and changed rows
+    pub added_keys: Vec<String>,
+    pub removed_keys: Vec<String>,
+    pub changed_keys: Vec<String>,
+}
+
+impl DataDiff {
+    /// Same rows, same columns with the same dtypes, same values.
+    pub fn is_equivalent(&self) -> bool {
+        self.added == 0
+            && self.removed == 0
+            && self.changed == 0
+            && self.only_in_a.is_empty()
+            && self.only_in_b.is_empty()
+            && self.columns.iter().all(|c| c.dtype_a == c.dtype_b)
+    }
+}
+
+fn count(lf: LazyFrame, streaming: bool) -> MlPrepResult<usize> {
+    let df = lf
+        .select([len()])
+        .with_streaming(streaming)
+        .collect()
+        .map_err(MlPrepError::PolarsError)?;
+    Ok(df.get_columns()[0].get(0)?.extract::<usize>().unwrap_or(0))
+}
+
+/// Key values of the rows of `lf`, formatted for the report.
+fn sample_keys(lf: LazyFrame, keys: &[Expr]) -> MlPrepResult<Vec<String>> {
+    let df = lf
+        .select(keys)
+        .limit(SAMPLE_KEYS)
+        .collect()
+        .map_err(MlPrepError::PolarsError)?;
+    Ok((0..df.height())
+        .filter_map(|i| df.get(i))
+        .map(|row| {
+            let values: Vec<String> = row
+                .iter()
+                .map(|value| match value {
+                    AnyValue::String(s) => s.to_string(),
+                    other => other.to_string(),
+                })
+                .collect();
+            values.join(", ")
+        })
+        .collect())
+}
+
+/// Rows of `lf` whose key has no match in `other`.
+fn unmatched(lf: LazyFrame, other: LazyFrame, keys: &[Expr]) -> LazyFrame {
+    let present = other.select(keys).with_column(lit(true).alias("__present"));
+    lf.select(keys)
+        .join(
+            present,
+            keys.to_vec(),
+            keys.to_vec(),
+            JoinArgs::new(JoinType::Left),
+        )
+        .filter(col("__present").is_null())
+}
+
+/// Compare `a` and `b`, matching rows on `keys`, which must be unique in both. Numeric
+/// values within `tolerance` of each other count as equal.
+pub fn diff_data(
+    mut a: LazyFrame,
+    mut b: LazyFrame,
+    keys: &[String],
+    tolerance: f64,
+    streaming: bool,
+) -> MlPrepResult<DataDiff> {
+    if keys.is_empty() {
+        return Err(MlPrepError::TransformError(
+            "diff needs at least one key column".to_string(),
+        ));
+    }
+    let schema_a = a.collect_schema().map_err(MlPrepError::PolarsError)?;
+    let schema_b = b.collect_schema().map_err(MlPrepError::PolarsError)?;
+    for (side, schema) in [("A", &schema_a), ("B", &schema_b)] {
+        if let Some(missing) = keys.iter().find(|key| !schema.contains(key)) {
+            return Err(MlPrepError::TransformError(format!(
+                "Key column '{}' not found in {}",
+                missing, side
+            )));
+        }
+    }
+    let key_exprs: Vec<Expr> = keys.iter().map(col).collect();
+
+    let rows_a = count(a.clone(), streaming)?;
+    let rows_b = count(b.clone(), streaming)?;
+    for (side, lf, rows) in [("A", &a, rows_a), ("B", &b, rows_b)] {
+        let distinct = count(
+            lf.clone()
+                .select(key_exprs.clone())
+                .unique(None, UniqueKeepStrategy::Any),
+            streaming,
+        )?;
+        if distinct < rows {
+            return Err(MlPrepError::TransformError(format!(
+                "Keys [{}] are not unique in {}: {} duplicate rows",
+                keys.join(", "),
+                side,
+                rows - distinct
+            )));
+        }
+    }
+
+    let field = |name: &PlSmallStr, dtype: &DataType| SchemaField {
+        name: name.to_string(),
+        dtype: format!("{:?}", dtype),
+    };
+    let only_in_a = schema_a
+        .iter()
+        .filter(|(name, _)| !schema_b.contains(name))
+        .map(|(name, dtype)| field(name, dtype))
+        .collect();
+    let only_in_b = schema_b
+        .iter()
+        .filter(|(name, _)| !schema_a.contains(name))
+        .map(|(name, dtype)| field(name, dtype))
+        .collect();
+    let common: Vec<(&PlSmallStr, &DataType, &DataType)> = schema_a
+        .iter()
+        .filter(|(name, _)| !keys.iter().any(|key| key == name.as_str()))
+        .filter_map(|(name, dtype_a)| schema_b.get(name).map(|dtype_b| (name, dtype_a, dtype_b)))
+        .collect();
+
+    // One pass over the matched rows computes every column's statistics.
+    let joined = a.clone().join(
+        b.clone(),
+        key_exprs.clone(),
+        key_exprs.clone(),
+        JoinArgs::new(JoinType::Inner).with_suffix(Some("__b".into())),
+    );
+    let mut changed_exprs = Vec::with_capacity(common.len());
+    let mut stats = vec![len().alias("__matched")];
+    for (i, (name, dtype_a, dtype_b)) in common.iter().enumerate() {
+        let value_a = col(name.as_str());
+        let value_b = col(format!("{}__b", name));
+        let changed = if dtype_a.is_primitive_numeric() && dtype_b.is_primitive_numeric() {
+            let diff = (value_a.clone().cast(DataType::Float64)
+                - value_b.clone().cast(DataType::Float64))
+            .abs();
+            stats.push(diff.clone().max().alias(format!("__max{}", i)));
+            let changed = value_a
+                .is_null()
+                .neq(value_b.is_null())
+                .or(diff.clone().gt(lit(tolerance)))
+                .fill_null(lit(false));
+            stats.push(
+                diff.filter(changed.clone())
+                    .mean()
+                    .alias(format!("__mean{}", i)),
+            );
+            changed
+        } else if dtype_a == dtype_b {
+            value_a.neq_missing(value_b)
+        } else {
+            value_a
+                .cast(DataType::String)
+                .neq_missing(value_b.cast(DataType::String))
+        };
+        stats.push(
+            changed
+                .clone()
+                .cast(DataType::UInt32)
+                .sum()
+                .alias(format!("__changed{}", i)),
+        );
+        changed_exprs.push(changed);
+    }
+    let any_changed = changed_exprs
+        .into_iter()
+        .reduce(|acc, changed| acc.or(changed))
+        .unwrap_or_else(|| lit(false));
+    stats.push(
+        any_changed
+            .clone()
+            .cast(DataType::UInt32)
+            .sum()
+            .alias("__rows_changed"),
+    );
+    let stats = joined
+        .clone()
+        .select(stats)
+        .with_streaming(streaming)
+        .collect()
+        .map_err(MlPrepError::PolarsError)?;
+
+    let value = |name: &str| -> MlPrepResult<AnyValue<'_>> {
+        stats
+            .column(name)
+            .map_err(MlPrepError::PolarsError)?
+            .get(0)
+            .map_err(MlPrepError::PolarsError)
+    };
+    let matched = value("__matched")?.extract::<usize>().unwrap_or(0);
+    let changed = value("__rows_changed")?.extract::<usize>().unwrap_or(0);
+    let mut columns = Vec::with_capacity(common.len());
+    for (i, (name, dtype_a, dtype_b)) in common.iter().enumerate() {
+        let numeric = dtype_a.is_primitive_numeric() && dtype_b.is_primitive_numeric();
+        let float = |stat: &str| -> MlPrepResult<Option<f64>> {
+            Ok(if numeric {
+                value(&format!("{}{}", stat, i))?.extract::<f64>()
+            } else {
+                None
+            })
+        };
+        columns.push(ColumnDiff {
+            column: name.to_string(),
+            dtype_a: format!("{:?}", dtype_a),
+            dtype_b: format!("{:?}", dtype_b),
+            changed: value(&format!("__changed{}", i))?
+                .extract::<usize>()
+                .unwrap_or(0),
+            max_abs_diff: float("__max")?,
+            mean_abs_diff: float("__mean")?,
+        });
+    }
+
+    Ok(DataDiff {
+        keys: keys.to_vec(),
+        rows_a,
+        rows_b,
+        matched,
+        added: rows_b - matched,
+        removed: rows_a - matched,
+        changed,
+        only_in_a,
+        only_in_b,
+        columns,
+        added_keys: sample_keys(unmatched(b.clone(), a.clone(), &key_exprs), &key_exprs)?,
+        removed_keys: sample_keys(unmatched(a, b, &key_exprs), &key_exprs)?,
+        changed_keys: sample_keys(joined.filter(any_changed), &key_exprs)?,
+    })
+}
+
+impl fmt::Display for DataDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Keys: {}", self.keys.join(", "))?;
+        writeln!(
+            f,
+            "Rows: A {}, B {} ({} matched)",
+            self.rows_a, self.rows_b, self.matched
+        )?;
+        let keys =
+            |f: &mut fmt::Formatter<'_>, sign: &str, what: &str, n: usize, keys: &[String]| {
+                if n == 0 {
+                    return Ok(());
+                }
+                write!(f, "  {} {} {}", sign, n, what)?;
+                if !keys.is_empty() {
+                    write!(f, ": [{}]", keys.join("], ["))?;
+                    if n > keys.len() {
+                        write!(f, ", ...")?;
+                    }
+                }
+                writeln!(f)
+            };
+        keys(f, "+", "added (only in B)", self.added, &self.added_keys)?;
+        keys(
+            f,
+            "-",
+            "removed (only in A)",
+            self.removed,
+            &self.removed_keys,
+        )?;
+        keys(f, "~", "changed", self.changed, &self.changed_keys)?;
+        for (title, fields) in [
+            ("Columns only in A", &self.only_in_a),
+            ("Columns only in B", &self.only_in_b),
+        ] {
+            if !fields.is_empty() {
+                let names: Vec<String> = fields
+                    .iter()
+                    .map(|field| format!("{} ({})", field.name, field.dtype))
+                    .collect();
+                writeln!(f, "{}: {}", title, names.join(", "))?;
+            }
+        }
+        let changed: Vec<&ColumnDiff> = self
+            .columns
+            .iter()
+            .filter(|c| c.changed > 0 || c.dtype_a != c.dtype_b)
+            .collect();
+        if !changed.is_empty() {
+            writeln!(f, "Changed columns:")?;
+            for column in changed {
+                write!(f, "  {}:", column.column)?;
+                if column.dtype_a != column.dtype_b {
+                    write!(f, " {} -> {},", column.dtype_a, column.dtype_b)?;
+                }
+                write!(f, " {} values changed", column.changed)?;
+                if let (Some(max), Some(mean)) = (column.max_abs_diff, column.mean_abs_diff) {
+                    write!(f, " (max |diff| {}, mean {:.6})", max, mean)?;
+                }
+                writeln!(f)?;
+            }
+        }
+        if self.is_equivalent() {
+            writeln!(f, "Datasets are equivalent")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_diff_data() {
+        let a = df!(
+            "id" => [1i64, 2, 3, 4],
+            "price" => [Some(1.0), Some(2.0), None, Some(4.0)],
+            "city" => ["a", "b", "c", "d"],
+            "old" => [0i64, 0, 0, 0]
+        )
+        .unwrap()
+        .lazy();
+        let b = df!(
+            "id" => [2i64, 3, 4, 5],
+            "price" => [Some(2.5), None, Some(4.0000001), Some(5.0)],
+            "city" => ["b", "c", "x", "e"],
+            "new" => [true, true, true, true]
+        )
+        .unwrap()
+        .lazy();
+
+        let diff = diff_data(a.clone(), b.clone(), &["id".to_string()], 1e-3, false).unwrap();
+        assert_eq!((diff.rows_a, diff.rows_b, diff.matched), (4, 4, 3));
+        assert_eq!((diff.added, diff.removed, diff.changed), (1, 1, 2));
+        assert_eq!(diff.added_keys, vec!["5"]);
+        assert_eq!(diff.removed_keys, vec!["1"]);
+        let mut changed_keys = diff.changed_keys.clone();
+        changed_keys.sort();
+        assert_eq!(changed_keys, vec!["2", "4"]);
+        assert_eq!(diff.only_in_a[0].name, "old");
+        assert_eq!(diff.only_in_b[0].name, "new");
+
+        let price = &diff.columns[0];
+        assert_eq!((price.column.as_str(), price.changed), ("price", 1));
+        assert_eq!(price.mean_abs_diff, Some(0.5));
+        let city = &diff.columns[1];
+        assert_eq!((city.column.as_str(), city.changed), ("city", 1));
+        assert_eq!(city.max_abs_diff, None);
+        assert!(!diff.is_equivalent());
+
+        let same = diff_data(a.clone(), a, &["id".to_string()], 0.0, false).unwrap();
+        assert!(same.is_equivalent());
+        assert!(same.to_string().contains("Datasets are equivalent"));
+
+        let dupes = df!("id" => [1i64, 1]).unwrap().lazy();
+        let err = diff_data(dupes, b, &["id".to_string()], 0.0, false).unwrap_err();
+        assert!(err.to_string().contains("not unique in A"));
+    }
+}
@@ -1,9 +1,15 @@
 use serde::de::Error;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 
-#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+/// DSL version written by `mlprep migrate`; pipelines declaring a newer one are rejected.
+pub const DSL_VERSION: u32 = 2;
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone, Default)]
 pub struct Pipeline {
+    /// DSL version the pipeline is written for; unversioned pipelines are version 1
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<u32>,
     #[serde(default)]
     pub inputs: Vec<Input>,
     pub steps: Vec<Step>,
@@ -11,18 +17,25 @@ pub struct Pipeline {
     pub outputs: Vec<Output>,
     #[serde(default)]
     pub runtime: Option<RuntimeConfig>,
+    /// Per-column dtype casts applied to the input, and docs written to Parquet outputs
+    #[serde(default)]
+    pub schema: Option<HashMap<String, ColumnSchema>>,
+    /// Actions run by the runner when the pipeline fails
     #[serde(default)]
-    pub schema: Option<HashMap<String, String>>,
+    pub on_failure: Vec<FailureHook>,
+    /// Inline test cases run by `mlprep test`
+    #[serde(default)]
+    pub tests: Vec<TestCase>,
 }
 
 use crate::errors::{MlPrepError, MlPrepResult};
 use miette::SourceSpan;
 use std::io::Read;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 impl Pipeline {
     pub fn from_reader<R: Read>(reader: R) -> MlPrepResult<Self> {
-        serde_yaml::from_reader(reader).map_err(|e| {
+        let pipeline: Self = serde_yaml::from_reader(reader).map_err(|e| {
             // Serde YAML error locations are 0-indexed line/col
             let _range = e.location().map(|loc| {
                 let _line = loc.line();
@@ -31,7 +44,19 @@ impl Pipeline {
                 SourceSpan::new(0.into(), 0.into())
             });
             MlPrepError::ConfigError(e, _range)
-        })
+        })?;
+        match pipeline.version {
+            Some(version) if version == 0 || version > DSL_VERSION => {
+                Err(MlPrepError::ConfigError(
+                    serde_yaml::Error::custom(format!(
+                        "Pipeline version {} is not supported; this mlprep reads versions 1 to {}",
+                        version, DSL_VERSION
+                    )),
+                    None,
+                ))
+            }
+            _ => Ok(pipeline),
+        }
     }
 
     pub fn from_path<P: AsRef<Path>>(path: P) -> MlPrepResult<Self> {
@@ -42,29 +67,335 @@ impl Pipeline {
                 None,
             ));
         }
-        let file = std::fs::File::open(path).map_err(MlPrepError::IoError)?;
+        let file = std::fs::File::open(path.as_ref()).map_err(MlPrepError::IoError)?;
         let reader = std::io::BufReader::new(file);
-        Self::from_reader(reader)
+        let mut pipeline = Self::from_reader(reader)?;
+        let base = path.as_ref().parent().unwrap_or(Path::new(""));
+        for step in &mut pipeline.steps {
+            if let Step::Validate(Validate {
+                checks_path: Some(checks_path),
+                ..
+            }) = step
+            {
+                *checks_path = base.join(&*checks_path);
+            }
+        }
+        Ok(pipeline)
+    }
+
+    /// Serialize back to pipeline YAML, omitting unset options and empty top-level lists.
+    pub fn to_yaml(&self) -> MlPrepResult<String> {
+        let to_config_error = |e: serde_yaml::Error| MlPrepError::ConfigError(e, None);
+        let mut value = serde_yaml::to_value(self).map_err(to_config_error)?;
+        strip_nulls(&mut value);
+        if let serde_yaml::Value::Mapping(map) = &mut value {
+            map.retain(|key, value| {
+                key.as_str() == Some("steps")
+                    || !matches!(value, serde_yaml::Value::Sequence(s) if s.is_empty())
+            });
+        }
+        serde_yaml::to_string(&value).map_err(to_config_error)
+    }
+
+    /// Descriptions and units declared in the `schema` block.
+    pub fn column_docs(&self) -> crate::io::ColumnDocs {
+        self.schema
+            .iter()
+            .flatten()
+            .filter_map(|(name, column)| match column {
+                ColumnSchema::Dtype(_) => None,
+                ColumnSchema::Spec(spec) => (spec.description.is_some() || spec.unit.is_some())
+                    .then(|| {
+                        let doc = crate::io::ColumnDoc {
+                            description: spec.description.clone(),
+                            unit: spec.unit.clone(),
+                        };
+                        (name.clone(), doc)
+                    }),
+            })
+            .collect()
+    }
+}
+
+/// Byte spans of the items of the top-level `steps:` list in pipeline YAML text, in
+/// order; empty when the list is not written in block form.
+pub fn step_spans(text: &str) -> Vec<SourceSpan> {
+    let mut spans = Vec::new();
+    let mut in_steps = false;
+    let mut item_indent = None;
+    // Start of the current item and end of its last non-blank line
+    let mut current: Option<(usize, usize)> = None;
+    let mut offset = 0;
+    for line in text.split_inclusive('\n') {
+        let start = offset;
+        offset += line.len();
+        let content = line.trim_end();
+        let trimmed = content.trim_start();
+        if !in_steps {
+            in_steps = content.strip_prefix("steps:").is_some_and(|rest| {
+                let rest = rest.trim_start();
+                rest.is_empty() || rest.starts_with('#')
+            });
+            continue;
+        }
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+        let indent = content.len() - trimmed.len();
+        let is_item = trimmed == "-" || trimmed.starts_with("- ");
+        let item = *item_indent.get_or_insert(indent);
+        if indent < item || (indent == item && !is_item) {
+            break;
+        }
+        if indent == item {
+            spans.extend(current.map(|(from, to)| SourceSpan::from(from..to)));
+            current = Some((start + indent, start + content.len()));
+        } else if let Some((_, end)) = &mut current {
+            *end = start + content.len();
+        }
+    }
+    spans.extend(current.map(|(from, to)| SourceSpan::from(from..to)));
+    spans
+}
+
+/// A `schema` entry: a dtype name, or `{dtype, description, unit}`
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+#[serde(untagged)]
+pub enum ColumnSchema {
+    Dtype(String),
+    Spec(ColumnSpec),
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct ColumnSpec {
+    /// Cast the input column to this dtype; left as read when omitted
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dtype: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub unit: Option<String>,
+}
+
+impl ColumnSchema {
+    pub fn dtype(&self) -> Option<&str> {
+        match self {
+            ColumnSchema::Dtype(dtype) => Some(dtype),
+            ColumnSchema::Spec(spec) => spec.dtype.as_deref(),
+        }
+    }
+}
+
+fn strip_nulls(value: &mut serde_yaml::Value) {
+    match value {
+        serde_yaml::Value::Mapping(map) => {
+            map.retain(|_, v| !v.is_null());
+            map.values_mut().for_each(strip_nulls);
+        }
+        serde_yaml::Value::Sequence(items) => items.iter_mut().for_each(strip_nulls),
+        _ => {}
     }
 }
 
 #[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
 pub struct Input {
-    pub path: String,
+    pub path: PathBuf,
     #[serde(default)]
     pub format: Option<String>,
     pub schema: Option<String>,
     pub infer_rows: Option<usize>,
     pub null_values: Option<Vec<String>>,
+    /// Schema contract the input must match before any step runs
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub contract: Option<PathBuf>,
+    /// Treat `path` as a manifest listing the files to read
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub manifest: Option<Manifest>,
+    /// Read `path` as an Apache Iceberg table
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub iceberg: Option<Iceberg>,
+    /// Read one batch of messages from the Kafka topic at `path` (experimental)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub kafka: Option<Kafka>,
+}
+
+/// Iceberg table input: `path` is the table location (or one of its `*.metadata.json`
+/// files) and the data files come from the selected snapshot (see [`crate::iceberg`])
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone, Default)]
+pub struct Iceberg {
+    /// Read this snapshot instead of the current one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub snapshot_id: Option<i64>,
+    /// Read the snapshot that was current at this time (RFC 3339, or
+    /// `YYYY-MM-DD[ HH:MM:SS]` in UTC)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub as_of: Option<String>,
+}
+
+/// Kafka input: `path` is `kafka://<broker>[,<broker>...]/<topic>` and each run reads one
+/// bounded batch of messages (see [`crate::kafka`])
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone, Default)]
+pub struct Kafka {
+    /// Consumer group the batch starts from and commits its offsets to once the outputs
+    /// are written; without one, every run starts at `start`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub group: Option<String>,
+    /// Where to start without committed offsets: `earliest` (default) or `latest`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub start: Option<String>,
+    /// End the batch after this many messages
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_messages: Option<usize>,
+    /// End the batch after consuming for this long, e.g. "30s" (default)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_wait: Option<String>,
+    /// Avro schema (`.avsc`) of `format: avro` payloads that are bare records rather
+    /// than container files
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub avro_schema: Option<PathBuf>,
+}
+
+/// Manifest input: `path` is a CSV/JSON file with one row per file to process; its other
+/// columns are per-file metadata (see [`crate::manifest`])
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct Manifest {
+    /// Manifest column holding the file paths; relative paths start at the manifest
+    #[serde(default = "default_path_column")]
+    pub path_column: String,
+    /// Format of the listed files; detected per file when unset
+    #[serde(default)]
+    pub format: Option<String>,
+    /// Run the steps on each file separately and write one output per file
+    #[serde(default)]
+    pub per_file: bool,
+    /// Where `per_file` runs record finished files; defaults to
+    /// `<pipeline stem>.progress.json` next to the pipeline
+    #[serde(default)]
+    pub state_path: Option<PathBuf>,
+}
+
+fn default_path_column() -> String {
+    "path".to_string()
 }
 
 #[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
 pub struct Output {
-    pub path: String,
+    pub path: PathBuf,
     #[serde(default)]
     pub format: Option<String>,
     pub compression: Option<String>,
     pub partition_by: Option<Vec<String>>,
+    #[serde(default)]
+    pub mode: OutputMode,
+    /// Also write a Feast feature repository definition for this output
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub feast: Option<FeastOutput>,
+    /// Split the output into numbered part files of at most this many rows
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_rows_per_file: Option<usize>,
+    /// Split the output into numbered part files of at most this size, e.g. "256MB"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_file_size: Option<String>,
+}
+
+impl Output {
+    /// Whether the output is written as numbered part files (see [`Output::part_path`]).
+    pub fn is_split(&self) -> bool {
+        self.max_rows_per_file.is_some() || self.max_file_size.is_some()
+    }
+
+    pub fn max_file_bytes(&self) -> MlPrepResult<Option<u64>> {
+        self.max_file_size
+            .as_deref()
+            .map(parse_memory_size)
+            .transpose()
+    }
+
+    /// Path of part `index` of a split output: `<stem>-00000.<ext>` next to `path`, or
+    /// `part-00000.<format>` inside a dataset directory.
+    pub fn part_path(&self, index: usize) -> PathBuf {
+        if crate::io::is_dataset_dir(&self.path) {
+            let ext = self.format.as_deref().unwrap_or("parquet");
+            return self.path.join(format!("part-{:05}.{}", index, ext));
+        }
+        let stem = self
+            .path
+            .file_stem()
+            .map(|s| s.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let name = match self.path.extension() {
+            Some(ext) => format!("{}-{:05}.{}", stem, index, ext.to_string_lossy()),
+            None => format!("{}-{:05}", stem, index),
+        };
+        self.path.with_file_name(name)
+    }
+
+    /// The files the output consists of: its existing part files when split, else `path`.
+    pub fn files(&self) -> Vec<PathBuf> {
+        if !self.is_split() {
+            return vec![self.path.clone()];
+        }
+        (0..)
+            .map(|index| self.part_path(index))
+            .take_while(|part| part.exists())
+            .collect()
+    }
+}
+
+/// Feast FeatureView generated for an output (see [`crate::feast`])
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct FeastOutput {
+    /// FeatureView name; defaults to the output file stem
+    #[serde(default)]
+    pub name: Option<String>,
+    /// Entity join key columns
+    pub entities: Vec<String>,
+    /// Event-time column
+    pub timestamp_field: String,
+    /// How long features stay valid, e.g. `24h`; unlimited when unset
+    #[serde(default)]
+    pub ttl: Option<String>,
+    /// Python file to write; defaults to `<output stem>_feast.py` next to the output
+    #[serde(default)]
+    pub definition: Option<PathBuf>,
+}
+
+/// What to do when an output path already exists
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone, Copy, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum OutputMode {
+    /// Replace the existing file
+    #[default]
+    Overwrite,
+    /// Add rows to an existing file, or a new part file when the path is a directory
+    Append,
+    /// Refuse to run if the output already exists
+    ErrorIfExists,
+}
+
+/// Failure hook executed after the final failed attempt of a run
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum FailureHook {
+    /// Write a JSON marker file describing the failure
+    WriteMarker { path: String },
+    /// Remove the output files the failed attempt wrote; files from earlier runs are kept
+    DeleteOutputs,
+    /// POST a JSON failure payload to a URL
+    Webhook { url: String },
+}
+
+/// A test case: the steps applied to `given` rows must produce `expect`
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct TestCase {
+    pub name: String,
+    /// Rows standing in for the first input
+    pub given: Vec<serde_yaml::Mapping>,
+    /// Expected output rows; only the columns they mention are compared
+    pub expect: Vec<serde_yaml::Mapping>,
+    /// Compare rows regardless of their order
+    #[serde(default)]
+    pub ignore_order: bool,
 }
 
 #[derive(Debug, Serialize, Deserialize, PartialEq, Clone, Default)]
@@ -74,6 +405,152 @@ pub struct RuntimeConfig {
     pub memory_limit: Option<String>,
     #[serde(default)]
     pub streaming: bool,
+    /// Show progress bars on stderr (defaults to on when stderr is a terminal)
+    #[serde(default)]
+    pub progress: Option<bool>,
+    /// Re-run the pipeline up to this many times on transient I/O errors
+    #[serde(default)]
+    pub retries: Option<u32>,
+    /// Delay before the first retry (e.g. "500ms", "30s", "2m"); doubles on each attempt
+    #[serde(default)]
+    pub retry_backoff: Option<String>,
+    /// Report the columns and predicates pushed down to each file scan
+    #[serde(default)]
+    pub explain_io: Option<bool>,
+    /// Process the input in chunks of this many rows (row-wise pipelines only)
+    #[serde(default)]
+    pub chunk_size: Option<usize>,
+    /// Seed for every random operation; also keeps group order stable, so re-runs on
+    /// the same input write byte-identical outputs
+    #[serde(default)]
+    pub seed: Option<u64>,
+    /// Forget the progress of an interrupted `per_file` manifest run and process every
+    /// file again
+    #[serde(default)]
+    pub restart: bool,
+    /// Webhooks told about failed runs and runs with validation problems
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub notifications: Vec<Notification>,
+    /// Values of the `:name` parameters of filter conditions; `MLPREP_PARAM_<NAME>`
+    /// environment variables and `--param name=value` override them
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub params: BTreeMap<String, ScalarValue>,
+    /// Directory of the local copies of remote inputs (default `~/.cache/mlprep/inputs`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub input_cache_dir: Option<PathBuf>,
+    /// Size limit of the input cache (e.g. "20GB"); least recently used copies are evicted
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub input_cache_size: Option<String>,
+    /// Read remote inputs directly instead of through the input cache
+    #[serde(default)]
+    pub no_input_cache: bool,
+    /// Callables of the run's `python_udf` steps, passed in by the Python module
+    #[serde(skip)]
+    pub udfs: crate::udf::Udfs,
+    /// Input cache copies the run is reading, which other runs must not evict
+    #[serde(skip)]
+    pub input_pins: crate::input_cache::Pins,
+}
+
+/// A webhook POSTed a summary when a run fails, or succeeds with violations or a low
+/// quality score.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct Notification {
+    pub url: String,
+    #[serde(default)]
+    pub format: NotificationFormat,
+    /// Also notify successful runs whose checks found violations
+    #[serde(default = "default_true")]
+    pub on_violations: bool,
+    /// Also notify successful runs whose quality score is below this
+    #[serde(default)]
+    pub min_quality_score: Option<f64>,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+/// Shape of a notification payload
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone, Copy, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum NotificationFormat {
+    /// The run summary fields as a JSON object
+    #[default]
+    Json,
+    /// A `{"text": ...}` message for a Slack incoming webhook
+    Slack,
+}
+
+impl RuntimeConfig {
+    pub fn retry_backoff_duration(&self) -> MlPrepResult<std::time::Duration> {
+        match &self.retry_backoff {
+            Some(value) => parse_duration(value),
+            None => Ok(std::time::Duration::from_secs(1)),
+        }
+    }
+
+    /// `memory_limit` in bytes, if set.
+    pub fn memory_limit_bytes(&self) -> MlPrepResult<Option<u64>> {
+        self.memory_limit
+            .as_deref()
+            .map(parse_memory_size)
+            .transpose()
+    }
+}
+
+/// Parse a human-readable memory size such as "512MB", "4GB" or "1.5GiB" (1 KB = 1024 bytes).
+pub fn parse_memory_size(value: &str) -> MlPrepResult<u64> {
+    let value = value.trim();
+    let split = value
+        .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .unwrap_or(value.len());
+    let (number, unit) = value.split_at(split);
+    let invalid = || {
+        MlPrepError::ConfigError(
+            serde_yaml::Error::custom(format!(
+                "Invalid memory size '{}': expected a number followed by B, KB, MB, GB or TB",
+                value
+            )),
+            None,
+        )
+    };
+    let n: f64 = number.parse().map_err(|_| invalid())?;
+    let scale: u64 = match unit.trim().to_ascii_uppercase().trim_end_matches("IB") {
+        "" | "B" => 1,
+        "K" | "KB" => 1 << 10,
+        "M" | "MB" => 1 << 20,
+        "G" | "GB" => 1 << 30,
+        "T" | "TB" => 1 << 40,
+        _ => return Err(invalid()),
+    };
+    Ok((n * scale as f64) as u64)
+}
+
+/// Parse a human-readable duration such as "250ms", "30s", "5m" or "1h".
+pub fn parse_duration(value: &str) -> MlPrepResult<std::time::Duration> {
+    let value = value.trim();
+    let split = value
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(value.len());
+    let (number, unit) = value.split_at(split);
+    let invalid = || {
+        MlPrepError::ConfigError(
+            serde_yaml::Error::custom(format!(
+                "Invalid duration '{}': expected a number followed by ms, s, m or h",
+                value
+            )),
+            None,
+        )
+    };
+    let n: u64 = number.parse().map_err(|_| invalid())?;
+    match unit.trim() {
+        "ms" => Ok(std::time::Duration::from_millis(n)),
+        "" | "s" => Ok(std::time::Duration::from_secs(n)),
+        "m" => Ok(std::time::Duration::from_secs(n * 60)),
+        "h" => Ok(std::time::Duration::from_secs(n * 3600)),
+        _ => Err(invalid()),
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
@@ -90,6 +567,39 @@ pub enum Step {
     DropNull(DropNull),
     Validate(Validate),
     Features(Features),
+    FeatureSelect(FeatureSelect),
+    Limit(Limit),
+    Slice(Slice),
+    Sql(Sql),
+    PythonUdf(PythonUdf),
+    DatetimeOps(DatetimeOps),
+    Resample(Resample),
+}
+
+impl Step {
+    /// The `type` tag used for this step in pipeline YAML.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Step::Select(_) => "select",
+            Step::Filter(_) => "filter",
+            Step::Cast(_) => "cast",
+            Step::Sort(_) => "sort",
+            Step::Join(_) => "join",
+            Step::GroupBy(_) => "group_by",
+            Step::Window(_) => "window",
+            Step::FillNull(_) => "fill_null",
+            Step::DropNull(_) => "drop_null",
+            Step::Validate(_) => "validate",
+            Step::Features(_) => "features",
+            Step::FeatureSelect(_) => "feature_select",
+            Step::Limit(_) => "limit",
+            Step::Slice(_) => "slice",
+            Step::Sql(_) => "sql",
+            Step::PythonUdf(_) => "python_udf",
+            Step::DatetimeOps(_) => "datetime_ops",
+            Step::Resample(_) => "resample",
+        }
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
@@ -97,9 +607,153 @@ pub struct Select {
     pub columns: Vec<String>,
 }
 
+/// Sql: run an arbitrary SELECT over the current frame, registered as `df`
 #[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct Sql {
+    pub query: String,
+}
+
+/// PythonUdf: apply a callable registered by the Python module (`run_pipeline(..., udfs=...)`)
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct PythonUdf {
+    /// Name the callable was registered under
+    pub function: String,
+    /// Pass only these columns to the callable and merge its result back into the frame
+    #[serde(default)]
+    pub columns: Option<Vec<String>>,
+    /// Each output row depends only on its own input row, so `chunk_size` may run the
+    /// callable on one chunk at a time
+    #[serde(default)]
+    pub row_wise: bool,
+}
+
+/// Filter: keep rows matching either a SQL `condition` or a structured `predicate`
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone, Default)]
 pub struct Filter {
-    pub condition: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub condition: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub predicate: Option<Predicate>,
+}
+
+/// Structured filter tree compiled directly to Polars expressions (no SQL parsing).
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+#[serde(untagged)]
+pub enum Predicate {
+    Compare(Comparison),
+    And { and: Vec<Predicate> },
+    Or { or: Vec<Predicate> },
+    Not { not: Box<Predicate> },
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct Comparison {
+    pub column: String,
+    pub op: CompareOp,
+    /// Scalar for comparisons, list for `in`/`not_in`, omitted for null checks
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub value: Option<CompareValue>,
+}
+
+/// The right-hand side of a [`Comparison`].
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+#[serde(untagged)]
+pub enum CompareValue {
+    Scalar(ScalarValue),
+    List(Vec<ScalarValue>),
+}
+
+/// A literal in the DSL, kept with its YAML type so that `1`, `1.0`, `true` and `"1"`
+/// stay distinct. Dates are tagged as `{date: 2024-01-31}`, since a bare date is a
+/// string in YAML.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+#[serde(untagged)]
+pub enum ScalarValue {
+    Null,
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    Date { date: chrono::NaiveDate },
+    String(String),
+}
+
+impl std::fmt::Display for ScalarValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ScalarValue::Null => write!(f, "null"),
+            ScalarValue::Bool(b) => write!(f, "{}", b),
+            ScalarValue::Int(i) => write!(f, "{}", i),
+            ScalarValue::Float(x) => write!(f, "{}", x),
+            ScalarValue::Date { date } => write!(f, "{}", date),
+            ScalarValue::String(s) => write!(f, "{}", s),
+        }
+    }
+}
+
+impl From<bool> for ScalarValue {
+    fn from(value: bool) -> Self {
+        ScalarValue::Bool(value)
+    }
+}
+
+impl From<i64> for ScalarValue {
+    fn from(value: i64) -> Self {
+        ScalarValue::Int(value)
+    }
+}
+
+impl From<i32> for ScalarValue {
+    fn from(value: i32) -> Self {
+        ScalarValue::Int(value.into())
+    }
+}
+
+impl From<f64> for ScalarValue {
+    fn from(value: f64) -> Self {
+        ScalarValue::Float(value)
+    }
+}
+
+impl From<chrono::NaiveDate> for ScalarValue {
+    fn from(date: chrono::NaiveDate) -> Self {
+        ScalarValue::Date { date }
+    }
+}
+
+impl From<&str> for ScalarValue {
+    fn from(value: &str) -> Self {
+        ScalarValue::String(value.to_string())
+    }
+}
+
+impl From<String> for ScalarValue {
+    fn from(value: String) -> Self {
+        ScalarValue::String(value)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone, Copy)]
+pub enum CompareOp {
+    #[serde(rename = "==", alias = "eq")]
+    Eq,
+    #[serde(rename = "!=", alias = "ne")]
+    Ne,
+    #[serde(rename = ">", alias = "gt")]
+    Gt,
+    #[serde(rename = ">=", alias = "ge")]
+    Ge,
+    #[serde(rename = "<", alias = "lt")]
+    Lt,
+    #[serde(rename = "<=", alias = "le")]
+    Le,
+    #[serde(rename = "in")]
+    In,
+    #[serde(rename = "not_in")]
+    NotIn,
+    #[serde(rename = "is_null")]
+    IsNull,
+    #[serde(rename = "is_not_null")]
+    IsNotNull,
 }
 
 #[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
@@ -118,11 +772,113 @@ pub struct Sort {
 /// Join: Combine two DataFrames
 #[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
 pub struct Join {
-    pub right_path: String,
+    /// File holding the right side; alternatively `right`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub right_path: Option<PathBuf>,
+    /// Right side built by an inline pipeline, so the lookup table can be filtered or
+    /// aggregated before the join
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub right: Option<Box<JoinSource>>,
     pub left_on: Vec<String>,
     pub right_on: Vec<String>,
     #[serde(default = "default_join_how")]
     pub how: String,
+    /// Key cardinality the join must have; checked before joining
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expect: Option<JoinCardinality>,
+    /// Log a warning instead of failing when `expect` does not hold
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub warn_only: bool,
+    /// Join algorithm; small right sides are broadcast when unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub strategy: Option<JoinStrategy>,
+}
+
+/// How a join is executed
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum JoinStrategy {
+    /// Load the right side into memory once and probe it from the left side
+    Broadcast,
+    /// Partitioned hash join (the Polars default)
+    Hash,
+    /// Sort both sides on their keys so the join can merge them
+    SortMerge,
+}
+
+impl JoinStrategy {
+    /// The name used in pipeline YAML.
+    pub fn name(&self) -> &'static str {
+        match self {
+            JoinStrategy::Broadcast => "broadcast",
+            JoinStrategy::Hash => "hash",
+            JoinStrategy::SortMerge => "sort_merge",
+        }
+    }
+}
+
+/// Expected cardinality of a join: which sides must have unique keys
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum JoinCardinality {
+    OneToOne,
+    OneToMany,
+    ManyToOne,
+    ManyToMany,
+}
+
+impl JoinCardinality {
+    /// The name used in pipeline YAML.
+    pub fn name(&self) -> &'static str {
+        match self {
+            JoinCardinality::OneToOne => "one_to_one",
+            JoinCardinality::OneToMany => "one_to_many",
+            JoinCardinality::ManyToOne => "many_to_one",
+            JoinCardinality::ManyToMany => "many_to_many",
+        }
+    }
+
+    pub fn left_unique(&self) -> bool {
+        matches!(self, JoinCardinality::OneToOne | JoinCardinality::OneToMany)
+    }
+
+    pub fn right_unique(&self) -> bool {
+        matches!(self, JoinCardinality::OneToOne | JoinCardinality::ManyToOne)
+    }
+}
+
+/// Inline right side of a join: `input` is read and `steps` applied to it
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct JoinSource {
+    pub input: Input,
+    #[serde(default)]
+    pub steps: Vec<Step>,
+}
+
+impl Join {
+    /// Path and explicit format of the file the right side is read from.
+    pub fn right_input(&self) -> MlPrepResult<(&Path, Option<&str>)> {
+        match (&self.right_path, &self.right) {
+            (Some(path), None) => Ok((path, None)),
+            (None, Some(right)) if right.input.manifest.is_some() => Err(MlPrepError::ConfigError(
+                serde_yaml::Error::custom("a join's right side cannot be a manifest input"),
+                None,
+            )),
+            (None, Some(right)) if right.input.iceberg.is_some() => Err(MlPrepError::ConfigError(
+                serde_yaml::Error::custom("a join's right side cannot be an Iceberg input"),
+                None,
+            )),
+            (None, Some(right)) if right.input.kafka.is_some() => Err(MlPrepError::ConfigError(
+                serde_yaml::Error::custom("a join's right side cannot be a Kafka input"),
+                None,
+            )),
+            (None, Some(right)) => Ok((&right.input.path, right.input.format.as_deref())),
+            _ => Err(MlPrepError::ConfigError(
+                serde_yaml::Error::custom("join needs exactly one of right_path or right"),
+                None,
+            )),
+        }
+    }
 }
 
 fn default_join_how() -> String {
@@ -130,17 +886,170 @@ fn default_join_how() -> String {
 }
 
 /// GroupBy: Aggregate data by groups
-#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone, Default)]
 pub struct GroupBy {
     pub by: Vec<String>,
-    pub aggs: HashMap<String, Agg>,
+    #[serde(deserialize_with = "deserialize_aggs")]
+    pub aggs: Vec<Agg>,
+    /// Emit groups in order of first appearance instead of hash order
+    #[serde(default)]
+    pub maintain_order: bool,
+    /// The input is already sorted by the `by` columns (ascending), enabling Polars'
+    /// sorted-key group-by. Groups are wrong if the input is not actually sorted.
+    #[serde(default)]
+    pub keys_sorted: bool,
+    /// Keep only the groups whose aggregated row matches this condition
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub having: Option<Condition>,
 }
 
-/// Aggregation function specification
+/// Aggregation function specification: `func` applied to `column`
 #[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
 pub struct Agg {
+    pub column: String,
     pub func: String,
+    #[serde(default)]
     pub alias: Option<String>,
+    /// Aggregate only the rows of each group matching this condition
+    #[serde(default, rename = "where", skip_serializing_if = "Option::is_none")]
+    pub condition: Option<Condition>,
+}
+
+/// A row condition: a SQL expression (e.g. `status = 'error'`) or a structured predicate
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+#[serde(untagged)]
+pub enum Condition {
+    Sql(String),
+    Predicate(Predicate),
+}
+
+impl Agg {
+    /// Name of the aggregated column: the alias, else the column name, or
+    /// `<column>_<func>` when `aggs` holds several aggregations of the column.
+    pub fn output_name(&self, aggs: &[Agg]) -> String {
+        match &self.alias {
+            Some(alias) => alias.clone(),
+            None if aggs.iter().filter(|a| a.column == self.column).count() > 1 => {
+                let func = self.func.to_lowercase();
+                match quantile_level(&func) {
+                    Some(Ok(q)) => format!("{}_p{}", self.column, (q * 1e6).round() / 1e4),
+                    _ => format!("{}_{}", self.column, func),
+                }
+            }
+            None => self.column.clone(),
+        }
+    }
+}
+
+/// The level `p` of a `quantile(p)` aggregation, or `None` for other functions.
+pub fn quantile_level(func: &str) -> Option<Result<f64, String>> {
+    let arg = func.strip_prefix("quantile(")?.strip_suffix(')')?;
+    Some(match arg.trim().parse::<f64>() {
+        Ok(q) if (0.0..=1.0).contains(&q) => Ok(q),
+        _ => Err(format!(
+            "Invalid quantile level '{}' in {}: expected a number between 0 and 1",
+            arg, func
+        )),
+    })
+}
+
+/// `aggs` is a list of `{column, func, alias}`, or the older map of column to
+/// `{func, alias}` (or to a list of them).
+fn deserialize_aggs<'de, D>(deserializer: D) -> Result<Vec<Agg>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    struct AggFunc {
+        func: String,
+        #[serde(default)]
+        alias: Option<String>,
+        #[serde(default, rename = "where")]
+        condition: Option<Condition>,
+    }
+
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum AggFuncs {
+        One(AggFunc),
+        Many(Vec<AggFunc>),
+    }
+
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Aggs {
+        List(Vec<Agg>),
+        Map(serde_yaml::Mapping),
+    }
+
+    let map = match Aggs::deserialize(deserializer).map_err(|_| {
+        D::Error::custom(
+            "aggs must be a list of {column, func, alias} or a map of column to {func, alias}",
+        )
+    })? {
+        Aggs::List(aggs) => return Ok(aggs),
+        Aggs::Map(map) => map,
+    };
+    let mut aggs = Vec::with_capacity(map.len());
+    for (column, funcs) in map {
+        let column: String = serde_yaml::from_value(column).map_err(D::Error::custom)?;
+        let funcs = match serde_yaml::from_value(funcs)
+            .map_err(|e| D::Error::custom(format!("invalid aggregation for '{}': {}", column, e)))?
+        {
+            AggFuncs::One(func) => vec![func],
+            AggFuncs::Many(funcs) => funcs,
+        };
+        aggs.extend(funcs.into_iter().map(|func| Agg {
+            column: column.clone(),
+            func: func.func,
+            alias: func.alias,
+            condition: func.condition,
+        }));
+    }
+    Ok(aggs)
+}
+
+/// Resample: aggregate rows into fixed time buckets of `time_column`
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct Resample {
+    pub time_column: String,
+    /// Bucket spacing, e.g. "1h", "15m", "1d"
+    pub every: String,
+    /// Bucket length (defaults to `every`)
+    #[serde(default)]
+    pub period: Option<String>,
+    /// Shift bucket boundaries, e.g. "30m"
+    #[serde(default)]
+    pub offset: Option<String>,
+    #[serde(default)]
+    pub closed: ClosedSide,
+    #[serde(default)]
+    pub label: BucketLabel,
+    /// Resample each group separately
+    #[serde(default)]
+    pub by: Vec<String>,
+    #[serde(deserialize_with = "deserialize_aggs")]
+    pub aggs: Vec<Agg>,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone, Copy, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum ClosedSide {
+    #[default]
+    Left,
+    Right,
+    Both,
+    None,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone, Copy, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum BucketLabel {
+    /// Label each bucket with its start
+    #[default]
+    Left,
+    /// Label each bucket with its end
+    Right,
 }
 
 /// Window: Window/rolling functions
@@ -159,12 +1068,44 @@ pub struct WindowOp {
     pub alias: String,
 }
 
+/// DatetimeOps: apply timezone/truncation/offset operations to a Datetime column, in order
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct DatetimeOps {
+    pub column: String,
+    /// Each op is a single-key map, e.g. `- truncate: hour`
+    #[serde(with = "serde_yaml::with::singleton_map_recursive")]
+    pub ops: Vec<DatetimeOp>,
+    /// Write the result to a new column instead of replacing `column`
+    #[serde(default)]
+    pub alias: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+#[serde(rename_all = "snake_case")]
+pub enum DatetimeOp {
+    /// Convert to another timezone (the instant is unchanged)
+    ConvertTimeZone(String),
+    /// Attach a timezone to naive timestamps (wall-clock time is unchanged)
+    ReplaceTimeZone(String),
+    /// Truncate to `hour`, `day`, `week` or a duration such as "15m"
+    Truncate(String),
+    /// Shift by a duration such as "1h", "-2d" or "1mo"
+    OffsetBy(String),
+}
+
 /// FillNull: Strategy to fill missing values
 #[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
 pub struct FillNull {
     pub columns: Vec<String>,
     pub strategy: FillNullStrategy,
-    pub value: Option<String>, // For "literal" strategy
+    /// For the "literal" strategy: a value cast to each column's dtype
+    pub value: Option<ScalarValue>,
+    /// Compute the fill within each group of these columns (e.g. per user) instead of globally
+    #[serde(default, alias = "partition_by")]
+    pub over: Vec<String>,
+    /// Interpolate relative to this (e.g. time) column instead of row position
+    #[serde(default)]
+    pub order_by: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
@@ -178,6 +1119,24 @@ pub enum FillNullStrategy {
     Min,
     Max,
     Zero,
+    /// Linear interpolation between the surrounding non-null values
+    Interpolate,
+}
+
+/// Limit: Keep only the first `n` rows
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct Limit {
+    pub n: usize,
+}
+
+/// Slice: Keep `length` rows starting at `offset` (negative offsets count from the end)
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct Slice {
+    #[serde(default)]
+    pub offset: i64,
+    /// Rows to keep; all remaining rows when omitted
+    #[serde(default)]
+    pub length: Option<usize>,
 }
 
 /// DropNull: Remove rows with nulls in specified columns
@@ -209,12 +1168,129 @@ pub struct ColumnCheck {
     pub not_null: bool,
     #[serde(default)]
     pub unique: bool,
+    /// Inclusive `[min, max]`; give `min` or `max` alone for an open-ended range
     #[serde(default)]
     pub range: Option<(f64, f64)>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max: Option<f64>,
     #[serde(default)]
     pub regex: Option<String>,
     #[serde(default, rename = "enum")]
-    pub allowed_values: Option<Vec<String>>,
+    pub allowed_values: Option<Vec<ScalarValue>>,
+    /// Whether a null violates the `range`, `regex` and `enum` checks; defaults to `pass`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub nulls: Option<NullPolicy>,
+    /// Whether repeated nulls violate the `unique` check; defaults to `fail`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub unique_nulls: Option<NullPolicy>,
+    /// Repair violating values instead of failing or quarantining on them
+    #[serde(
+        default,
+        deserialize_with = "on_violation_or_null",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub on_violation: Option<OnViolation>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub severity: Option<Severity>,
+}
+
+impl ColumnCheck {
+    /// Bounds of the range check: `range` if given, else `min` and `max`, either of
+    /// which may be open. `None` when the check has no range.
+    pub fn bounds(&self) -> Option<(Option<f64>, Option<f64>)> {
+        match self.range {
+            Some((min, max)) => Some((Some(min), Some(max))),
+            None if self.min.is_some() || self.max.is_some() => Some((self.min, self.max)),
+            None => None,
+        }
+    }
+
+    /// Whether nulls count as violations of the `range`, `regex` and `enum` checks.
+    pub fn nulls_fail(&self) -> bool {
+        self.nulls == Some(NullPolicy::Fail)
+    }
+
+    /// Whether repeated nulls count as duplicates in the `unique` check.
+    pub fn unique_nulls_fail(&self) -> bool {
+        self.unique_nulls != Some(NullPolicy::Pass)
+    }
+}
+
+/// Repair applied to the rows violating a column check.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+#[serde(untagged)]
+pub enum OnViolation {
+    /// `fill: <value>`: replace the violating values
+    Fill {
+        fill: ScalarValue,
+    },
+    Action(RepairAction),
+}
+
+impl std::fmt::Display for OnViolation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            OnViolation::Fill { fill } => write!(f, "fill {}", fill),
+            OnViolation::Action(RepairAction::Drop) => write!(f, "drop"),
+            OnViolation::Action(RepairAction::Clamp) => write!(f, "clamp"),
+            OnViolation::Action(RepairAction::Null) => write!(f, "null"),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum RepairAction {
+    /// Drop the violating rows
+    Drop,
+    /// Move out-of-range values to the nearest bound of the `range` check
+    Clamp,
+    /// Replace the violating values with null
+    Null,
+}
+
+/// `on_violation: null` reads as YAML null, so a present null is the `null` repair.
+fn on_violation_or_null<'de, D>(deserializer: D) -> Result<Option<OnViolation>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let repair = Option::<OnViolation>::deserialize(deserializer)?;
+    Ok(Some(
+        repair.unwrap_or(OnViolation::Action(RepairAction::Null)),
+    ))
+}
+
+/// Whether a null value passes or fails a check.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum NullPolicy {
+    Pass,
+    Fail,
+}
+
+/// How much a check weighs in the quality score of a run; checks default to `medium`.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone, Copy, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum Severity {
+    Low,
+    #[default]
+    Medium,
+    High,
+    Critical,
+}
+
+impl Severity {
+    /// Each level counts twice as much as the one below it.
+    pub fn weight(self) -> f64 {
+        match self {
+            Severity::Low => 1.0,
+            Severity::Medium => 2.0,
+            Severity::High => 4.0,
+            Severity::Critical => 8.0,
+        }
+    }
 }
 
 /// Dataset-level checks
@@ -228,13 +1304,80 @@ pub struct DatasetCheck {
     pub duplicate_rate_max: Option<f64>,
 }
 
-/// Validation configuration (checks.yaml structure)
+/// Expected summary statistics of a column. Unlike the row-level column checks they
+/// catch whole-column shifts, such as an upstream extract switching units.
 #[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct StatsCheck {
+    pub name: String,
+    #[serde(default)]
+    pub mean: Option<StatExpectation>,
+    /// Sample standard deviation
+    #[serde(default)]
+    pub std: Option<StatExpectation>,
+    #[serde(default)]
+    pub min: Option<StatExpectation>,
+    #[serde(default)]
+    pub max: Option<StatExpectation>,
+    /// Applies to each of the statistics
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub severity: Option<Severity>,
+}
+
+/// Expected value of a statistic and how far the actual value may deviate from it.
+/// Without a tolerance the value must match exactly; with both, the wider one applies.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct StatExpectation {
+    pub expected: f64,
+    /// Allowed deviation as a fraction of `expected`, e.g. 0.1 for ±10%
+    #[serde(default)]
+    pub rel_tolerance: Option<f64>,
+    /// Allowed deviation in the column's units
+    #[serde(default)]
+    pub abs_tolerance: Option<f64>,
+}
+
+impl StatExpectation {
+    /// Largest allowed distance between the actual and the expected value.
+    pub fn tolerance(&self) -> f64 {
+        let relative = self.rel_tolerance.map_or(0.0, |r| r * self.expected.abs());
+        relative.max(self.abs_tolerance.unwrap_or(0.0))
+    }
+}
+
+/// Validation configuration (checks.yaml structure)
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone, Default)]
 pub struct CheckConfig {
     #[serde(default)]
     pub columns: Vec<ColumnCheck>,
     #[serde(default)]
     pub dataset: Option<DatasetCheck>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub stats: Vec<StatsCheck>,
+    /// Column sets that must identify rows, e.g. `[[user_id, date]]` for a composite
+    /// primary key
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub unique_keys: Vec<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub conditions: Vec<ConditionalCheck>,
+}
+
+/// A business invariant over several columns: rows matching the SQL condition `when`
+/// must also satisfy `require`, e.g. closed tickets need a `closed_at`.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct ConditionalCheck {
+    /// Label used in reports; defaults to the `require` condition
+    #[serde(default)]
+    pub name: Option<String>,
+    pub when: String,
+    pub require: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub severity: Option<Severity>,
+}
+
+impl ConditionalCheck {
+    pub fn label(&self) -> &str {
+        self.name.as_deref().unwrap_or(&self.require)
+    }
 }
 
 /// Validation execution mode
@@ -253,9 +1396,41 @@ pub enum ValidationMode {
 /// Validate step for pipeline
 #[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
 pub struct Validate {
+    #[serde(default)]
     pub checks: CheckConfig,
+    /// A shared checks file, relative to the pipeline; its checks run before the inline
+    /// `checks`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub checks_path: Option<PathBuf>,
     #[serde(default)]
     pub mode: ValidationMode,
+    /// In `quarantine` mode, also write one row per violation (row, column, check and
+    /// value) to this Parquet or CSV file
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub violations_path: Option<PathBuf>,
+    /// Check a random fraction of the rows, in (0, 1]; not with `quarantine` mode
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sample_fraction: Option<f64>,
+    /// Check this many randomly drawn rows; not with `quarantine` mode
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sample_rows: Option<usize>,
+}
+
+impl CheckConfig {
+    pub fn load<P: AsRef<Path>>(path: P) -> MlPrepResult<Self> {
+        let file = std::fs::File::open(path.as_ref())?;
+        serde_yaml::from_reader(std::io::BufReader::new(file))
+            .map_err(|e| MlPrepError::ConfigError(e, None))
+    }
+
+    /// Add the checks of `other` after these; its dataset limits replace any set here.
+    pub fn extend(&mut self, other: CheckConfig) {
+        self.columns.extend(other.columns);
+        self.dataset = other.dataset.or(self.dataset.take());
+        self.stats.extend(other.stats);
+        self.unique_keys.extend(other.unique_keys);
+        self.conditions.extend(other.conditions);
+    }
 }
 
 /// Feature engineering step
@@ -267,6 +1442,23 @@ pub struct Features {
     pub state_path: Option<String>,
 }
 
+/// Feature selection: drop near-constant and highly correlated numeric columns
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone, Default)]
+pub struct FeatureSelect {
+    /// Candidate columns (defaults to every numeric column)
+    #[serde(default)]
+    pub columns: Option<Vec<String>>,
+    /// Drop candidates whose sample variance is below this value
+    #[serde(default)]
+    pub variance_threshold: Option<f64>,
+    /// Drop the later column of each pair whose |Pearson correlation| exceeds this value
+    #[serde(default)]
+    pub correlation_threshold: Option<f64>,
+    /// Path to load/save the selection (optional)
+    #[serde(default)]
+    pub state_path: Option<String>,
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -294,11 +1486,201 @@ steps:
 "#;
         let pipeline: Pipeline = serde_yaml::from_str(yaml).unwrap();
         match &pipeline.steps[0] {
-            Step::Filter(f) => assert_eq!(f.condition, "col('a') > 10"),
+            Step::Filter(f) => assert_eq!(f.condition.as_deref(), Some("col('a') > 10")),
             _ => panic!("Expected Filter step"),
         }
     }
 
+    #[test]
+    fn test_deserialize_datetime_ops() {
+        let yaml = r#"
+steps:
+  - type: datetime_ops
+    column: ts
+    alias: ts_hour
+    ops:
+      - replace_time_zone: "Asia/Tokyo"
+      - convert_time_zone: "UTC"
+      - truncate: hour
+      - offset_by: "-1d"
+"#;
+        let pipeline: Pipeline = serde_yaml::from_str(yaml).unwrap();
+        match &pipeline.steps[0] {
+            Step::DatetimeOps(d) => {
+                assert_eq!(d.alias.as_deref(), Some("ts_hour"));
+                assert_eq!(d.ops.len(), 4);
+                assert_eq!(d.ops[2], DatetimeOp::Truncate("hour".to_string()));
+            }
+            _ => panic!("Expected DatetimeOps step"),
+        }
+    }
+
+    #[test]
+    fn test_deserialize_resample() {
+        let yaml = r#"
+steps:
+  - type: resample
+    time_column: ts
+    every: "1h"
+    closed: right
+    by: [sensor]
+    aggs:
+      value:
+        func: mean
+        alias: value_mean
+"#;
+        let pipeline: Pipeline = serde_yaml::from_str(yaml).unwrap();
+        match &pipeline.steps[0] {
+            Step::Resample(r) => {
+                assert_eq!(r.every, "1h");
+                assert_eq!(r.closed, ClosedSide::Right);
+                assert_eq!(r.label, BucketLabel::Left);
+                assert!(r.period.is_none());
+                assert_eq!(r.aggs[0].column, "value");
+                assert_eq!(r.aggs[0].func, "mean");
+            }
+            _ => panic!("Expected Resample step"),
+        }
+    }
+
+    #[test]
+    fn test_deserialize_sql() {
+        let yaml = r#"
+steps:
+  - type: sql
+    query: "SELECT a, SUM(b) AS total FROM df GROUP BY a"
+"#;
+        let pipeline: Pipeline = serde_yaml::from_str(yaml).unwrap();
+        assert_eq!(pipeline.steps[0].kind(), "sql");
+        match &pipeline.steps[0] {
+            Step::Sql(s) => assert!(s.query.starts_with("SELECT a")),
+            _ => panic!("Expected Sql step"),
+        }
+    }
+
+    #[test]
+    fn test_deserialize_filter_predicate() {
+        let yaml = r#"
+steps:
+  - type: filter
+    predicate:
+      and:
+        - { column: a, op: ">=", value: 2 }
+        - or:
+            - { column: b, op: is_null }
+            - { column: c, op: in, value: [x, y] }
+"#;
+        let pipeline: Pipeline = serde_yaml::from_str(yaml).unwrap();
+        let Step::Filter(f) = &pipeline.steps[0] else {
+            panic!("Expected Filter step");
+        };
+        assert!(f.condition.is_none());
+        let Some(Predicate::And { and }) = &f.predicate else {
+            panic!("Expected and predicate");
+        };
+        assert_eq!(and.len(), 2);
+        match &and[0] {
+            Predicate::Compare(c) => {
+                assert_eq!(c.column, "a");
+                assert_eq!(c.op, CompareOp::Ge);
+            }
+            other => panic!("Expected comparison, got {:?}", other),
+        }
+        assert!(matches!(&and[1], Predicate::Or { or } if or.len() == 2));
+    }
+
+    #[test]
+    fn test_on_violation() {
+        let config: CheckConfig = serde_yaml::from_str(
+            r#"
+columns:
+  - { name: a, range: [0, 1], on_violation: clamp }
+  - { name: b, enum: [x], on_violation: null }
+  - { name: c, regex: "^x", on_violation: { fill: "x" } }
+  - { name: d, not_null: true, on_violation: drop }
+  - { name: e, not_null: true }
+"#,
+        )
+        .unwrap();
+        let repairs: Vec<Option<OnViolation>> = config
+            .columns
+            .iter()
+            .map(|check| check.on_violation.clone())
+            .collect();
+        assert_eq!(
+            repairs,
+            [
+                Some(OnViolation::Action(RepairAction::Clamp)),
+                Some(OnViolation::Action(RepairAction::Null)),
+                Some(OnViolation::Fill { fill: "x".into() }),
+                Some(OnViolation::Action(RepairAction::Drop)),
+                None,
+            ]
+        );
+
+        // `null` is written quoted, so it reads back as the repair
+        let yaml = serde_yaml::to_string(&config).unwrap();
+        assert_eq!(serde_yaml::from_str::<CheckConfig>(&yaml).unwrap(), config);
+    }
+
+    #[test]
+    fn test_scalar_values_keep_their_type() {
+        let yaml = r#"
+steps:
+  - type: filter
+    predicate:
+      or:
+        - { column: a, op: "==", value: 1 }
+        - { column: a, op: "==", value: 1.5 }
+        - { column: b, op: "==", value: "1" }
+        - { column: c, op: "==", value: true }
+        - { column: d, op: ">=", value: { date: 2024-01-31 } }
+        - { column: e, op: in, value: [x, null, 2] }
+  - type: fill_null
+    columns: [f]
+    strategy: literal
+    value: "true"
+"#;
+        let pipeline = Pipeline::from_reader(yaml.as_bytes()).unwrap();
+        let Step::Filter(Filter {
+            predicate: Some(Predicate::Or { or }),
+            ..
+        }) = &pipeline.steps[0]
+        else {
+            panic!("Expected an or predicate");
+        };
+        let values: Vec<&CompareValue> = or
+            .iter()
+            .map(|p| match p {
+                Predicate::Compare(c) => c.value.as_ref().unwrap(),
+                other => panic!("Expected comparison, got {:?}", other),
+            })
+            .collect();
+        let date = chrono::NaiveDate::from_ymd_opt(2024, 1, 31).unwrap();
+        assert_eq!(
+            values,
+            [
+                &CompareValue::Scalar(ScalarValue::Int(1)),
+                &CompareValue::Scalar(ScalarValue::Float(1.5)),
+                &CompareValue::Scalar(ScalarValue::String("1".to_string())),
+                &CompareValue::Scalar(ScalarValue::Bool(true)),
+                &CompareValue::Scalar(ScalarValue::Date { date }),
+                &CompareValue::List(vec!["x".into(), ScalarValue::Null, 2.into()]),
+            ]
+        );
+        let Step::FillNull(fill) = &pipeline.steps[1] else {
+            panic!("Expected FillNull step");
+        };
+        assert_eq!(fill.value, Some(ScalarValue::String("true".to_string())));
+
+        let rendered = pipeline.to_yaml().unwrap();
+        assert!(rendered.contains("date: 2024-01-31"), "{}", rendered);
+        assert_eq!(
+            Pipeline::from_reader(rendered.as_bytes()).unwrap(),
+            pipeline
+        );
+    }
+
     #[test]
     fn test_deserialize_cast() {
         let yaml = r#"
@@ -366,7 +1748,7 @@ steps:
         let pipeline: Pipeline = serde_yaml::from_str(yaml).unwrap();
         match &pipeline.steps[0] {
             Step::Join(j) => {
-                assert_eq!(j.right_path, "lookup.csv");
+                assert_eq!(j.right_path.as_deref(), Some(Path::new("lookup.csv")));
                 assert_eq!(j.left_on, vec!["id"]);
                 assert_eq!(j.right_on, vec!["user_id"]);
                 assert_eq!(j.how, "left");
@@ -375,6 +1757,36 @@ steps:
         }
     }
 
+    #[test]
+    fn test_deserialize_join_inline_right() {
+        let yaml = r#"
+steps:
+  - type: join
+    right:
+      input: {path: users.parquet}
+      steps:
+        - type: filter
+          condition: "active"
+    left_on: ["user_id"]
+    right_on: ["id"]
+  - type: join
+    left_on: ["id"]
+    right_on: ["id"]
+"#;
+        let pipeline: Pipeline = serde_yaml::from_str(yaml).unwrap();
+        match &pipeline.steps[0] {
+            Step::Join(j) => {
+                assert_eq!(j.right_input().unwrap(), (Path::new("users.parquet"), None));
+                assert_eq!(j.right.as_ref().unwrap().steps.len(), 1);
+            }
+            _ => panic!("Expected Join step"),
+        }
+        match &pipeline.steps[1] {
+            Step::Join(j) => assert!(j.right_input().is_err()),
+            _ => panic!("Expected Join step"),
+        }
+    }
+
     #[test]
     fn test_deserialize_join_default_inner() {
         let yaml = r#"
@@ -410,10 +1822,10 @@ steps:
         match &pipeline.steps[0] {
             Step::GroupBy(g) => {
                 assert_eq!(g.by, vec!["category"]);
-                let value_agg = g.aggs.get("value").unwrap();
+                let value_agg = g.aggs.iter().find(|a| a.column == "value").unwrap();
                 assert_eq!(value_agg.func, "sum");
                 assert_eq!(value_agg.alias, Some("total_value".to_string()));
-                let count_agg = g.aggs.get("count").unwrap();
+                let count_agg = g.aggs.iter().find(|a| a.column == "count").unwrap();
                 assert_eq!(count_agg.func, "count");
                 assert_eq!(count_agg.alias, None);
             }
@@ -421,6 +1833,43 @@ steps:
         }
     }
 
+    #[test]
+    fn test_deserialize_groupby_agg_list() {
+        let yaml = r#"
+steps:
+  - type: group_by
+    by: [category]
+    aggs:
+      - {column: amount, func: sum}
+      - {column: amount, func: mean, alias: avg_amount}
+      - {column: amount, func: max}
+  - type: group_by
+    by: [category]
+    aggs:
+      amount: [{func: sum}, {func: max}]
+"#;
+        let pipeline: Pipeline = serde_yaml::from_str(yaml).unwrap();
+        let (Step::GroupBy(list), Step::GroupBy(map)) = (&pipeline.steps[0], &pipeline.steps[1])
+        else {
+            panic!("Expected GroupBy steps");
+        };
+        let names: Vec<String> = list
+            .aggs
+            .iter()
+            .map(|a| a.output_name(&list.aggs))
+            .collect();
+        assert_eq!(names, vec!["amount_sum", "avg_amount", "amount_max"]);
+        assert_eq!(map.aggs.len(), 2);
+        assert_eq!(map.aggs[1].column, "amount");
+        assert_eq!(map.aggs[1].func, "max");
+
+        let err = serde_yaml::from_str::<Pipeline>(
+            "steps:\n  - type: group_by\n    by: [a]\n    aggs: sum\n",
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("aggs must be a list"), "{}", err);
+    }
+
     #[test]
     fn test_deserialize_window() {
         let yaml = r#"
@@ -481,6 +1930,36 @@ steps:
         }
     }
 
+    #[test]
+    fn test_deserialize_limit_and_slice() {
+        let yaml = r#"
+steps:
+  - type: limit
+    n: 100
+  - type: slice
+    offset: 20
+    length: 10
+  - type: slice
+    offset: -5
+"#;
+        let pipeline: Pipeline = serde_yaml::from_str(yaml).unwrap();
+        assert_eq!(pipeline.steps[0], Step::Limit(Limit { n: 100 }));
+        assert_eq!(
+            pipeline.steps[1],
+            Step::Slice(Slice {
+                offset: 20,
+                length: Some(10)
+            })
+        );
+        assert_eq!(
+            pipeline.steps[2],
+            Step::Slice(Slice {
+                offset: -5,
+                length: None
+            })
+        );
+    }
+
     #[test]
     fn test_deserialize_schema() {
         let yaml = r#"
@@ -491,7 +1970,112 @@ steps: []
 "#;
         let pipeline: Pipeline = serde_yaml::from_str(yaml).unwrap();
         let schema = pipeline.schema.unwrap();
-        assert_eq!(schema.get("col_a").unwrap(), "Int64");
+        assert_eq!(schema.get("col_a").unwrap().dtype(), Some("Int64"));
+    }
+
+    #[test]
+    fn test_deserialize_schema_column_docs() {
+        let yaml = r#"
+schema:
+    price: {dtype: Float64, description: "Unit price", unit: EUR}
+    note: {description: "Free text"}
+    id: Int64
+steps: []
+"#;
+        let pipeline: Pipeline = serde_yaml::from_str(yaml).unwrap();
+        let schema = pipeline.schema.as_ref().unwrap();
+        assert_eq!(schema["price"].dtype(), Some("Float64"));
+        assert_eq!(schema["note"].dtype(), None);
+
+        let docs = pipeline.column_docs();
+        assert_eq!(docs.keys().collect::<Vec<_>>(), vec!["note", "price"]);
+        assert_eq!(docs["price"].unit.as_deref(), Some("EUR"));
+        assert_eq!(docs["note"].description.as_deref(), Some("Free text"));
+    }
+
+    #[test]
+    fn test_deserialize_output_mode() {
+        let yaml = r#"
+steps: []
+outputs:
+  - path: "a.parquet"
+  - path: "dataset/"
+    mode: append
+  - path: "prod.parquet"
+    mode: error_if_exists
+"#;
+        let pipeline: Pipeline = serde_yaml::from_str(yaml).unwrap();
+        let modes: Vec<OutputMode> = pipeline.outputs.iter().map(|o| o.mode).collect();
+        assert_eq!(
+            modes,
+            vec![
+                OutputMode::Overwrite,
+                OutputMode::Append,
+                OutputMode::ErrorIfExists
+            ]
+        );
+    }
+
+    #[test]
+    fn test_deserialize_on_failure() {
+        let yaml = r#"
+steps: []
+on_failure:
+  - type: write_marker
+    path: "out/_FAILED"
+  - type: delete_outputs
+  - type: webhook
+    url: "http://localhost:9000/hooks/mlprep"
+"#;
+        let pipeline: Pipeline = serde_yaml::from_str(yaml).unwrap();
+        assert_eq!(
+            pipeline.on_failure,
+            vec![
+                FailureHook::WriteMarker {
+                    path: "out/_FAILED".to_string()
+                },
+                FailureHook::DeleteOutputs,
+                FailureHook::Webhook {
+                    url: "http://localhost:9000/hooks/mlprep".to_string()
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_output_part_paths() {
+        let output: Output =
+            serde_yaml::from_str("path: out/orders.csv\nmax_rows_per_file: 10\n").unwrap();
+        assert!(output.is_split());
+        assert_eq!(output.part_path(3), Path::new("out/orders-00003.csv"));
+        let output: Output =
+            serde_yaml::from_str("path: out/\nformat: csv\nmax_file_size: 1GB\n").unwrap();
+        assert_eq!(output.part_path(0), Path::new("out/part-00000.csv"));
+        assert_eq!(output.max_file_bytes().unwrap(), Some(1 << 30));
+        let output: Output = serde_yaml::from_str("path: out/orders.csv\n").unwrap();
+        assert!(!output.is_split());
+        assert_eq!(output.files(), [PathBuf::from("out/orders.csv")]);
+    }
+
+    #[test]
+    fn test_parse_duration() {
+        use std::time::Duration;
+        assert_eq!(parse_duration("250ms").unwrap(), Duration::from_millis(250));
+        assert_eq!(parse_duration("30s").unwrap(), Duration::from_secs(30));
+        assert_eq!(parse_duration("2m").unwrap(), Duration::from_secs(120));
+        assert_eq!(parse_duration("10").unwrap(), Duration::from_secs(10));
+        assert!(parse_duration("soon").is_err());
+        assert!(parse_duration("5d").is_err());
+    }
+
+    #[test]
+    fn test_parse_memory_size() {
+        assert_eq!(parse_memory_size("512MB").unwrap(), 512 << 20);
+        assert_eq!(parse_memory_size("4GB").unwrap(), 4 << 30);
+        assert_eq!(parse_memory_size("1.5 GiB").unwrap(), 3 << 29);
+        assert_eq!(parse_memory_size("2048").unwrap(), 2048);
+        assert!(parse_memory_size("lots").is_err());
+        assert!(parse_memory_size("4PB").is_err());
     }
 
     #[test]
@@ -500,11 +2084,70 @@ steps: []
 runtime:
   streaming: true
   memory_limit: "4GB"
+  progress: false
 steps: []
 "#;
         let pipeline: Pipeline = serde_yaml::from_str(yaml).unwrap();
         let runtime = pipeline.runtime.unwrap();
         assert!(runtime.streaming);
         assert_eq!(runtime.memory_limit, Some("4GB".to_string()));
+        assert_eq!(runtime.progress, Some(false));
+    }
+
+    #[test]
+    fn test_to_yaml_roundtrip() {
+        let yaml = r#"
+inputs:
+  - path: data.csv
+steps:
+  - type: filter
+    condition: "a > 1"
+  - type: slice
+    offset: 2
+outputs:
+  - path: out.parquet
+"#;
+        let pipeline = Pipeline::from_reader(yaml.as_bytes()).unwrap();
+        let rendered = pipeline.to_yaml().unwrap();
+        assert!(!rendered.contains("null"));
+        assert!(!rendered.contains("on_failure"));
+        assert!(rendered.contains("- type: filter"));
+        assert_eq!(
+            Pipeline::from_reader(rendered.as_bytes()).unwrap(),
+            pipeline
+        );
+
+        let empty = Pipeline::default().to_yaml().unwrap();
+        assert_eq!(empty.trim(), "steps: []");
+    }
+
+    #[test]
+    fn test_step_spans() {
+        let yaml = "\
+inputs:
+  - path: data.csv
+steps:
+  # drop refunds
+  - type: filter
+    condition: \"a > 1\"
+
+  - {type: limit, n: 5}
+outputs:
+  - path: out.parquet
+";
+        let spans = step_spans(yaml);
+        let items: Vec<&str> = spans
+            .iter()
+            .map(|span| &yaml[span.offset()..span.offset() + span.len()])
+            .collect();
+        assert_eq!(
+            items,
+            [
+                "- type: filter\n    condition: \"a > 1\"",
+                "- {type: limit, n: 5}"
+            ]
+        );
+        assert_eq!(step_spans("steps:\n- type: limit\n  n: 1\n").len(), 1);
+        assert!(step_spans("steps: []\n").is_empty());
     }
 }
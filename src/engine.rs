@@ -21,6 +21,15 @@ impl DataPipeline {
         }
     }
 
+    /// Apply the per-plan runtime settings (plan caching) to the query.
+    pub fn with_runtime(self, runtime: &crate::observability::EffectiveRuntime) -> Self {
+        let df = self
+            .df
+            .with_comm_subplan_elim(runtime.cache)
+            .with_comm_subexpr_elim(runtime.cache);
+        Self { df }
+    }
+
     pub fn get_df(&self) -> &LazyFrame {
         &self.df
     }
@@ -34,4 +43,77 @@ impl DataPipeline {
         let new_lf = crate::compute::apply_pipeline(self.df, pipeline, runtime, security_context)?;
         Ok(Self { df: new_lf })
     }
+
+    pub fn apply_transforms_tracked(
+        self,
+        pipeline: crate::dsl::Pipeline,
+        runtime: &crate::dsl::RuntimeConfig,
+        security_context: &crate::security::SecurityContext,
+        progress: &indicatif::ProgressBar,
+        metrics: &mut crate::observability::Metrics,
+    ) -> MlPrepResult<Self> {
+        let new_lf = crate::compute::apply_pipeline_tracked(
+            self.df,
+            pipeline,
+            runtime,
+            security_context,
+            progress,
+            metrics,
+        )?;
+        Ok(Self { df: new_lf })
+    }
+
+    /// Count input rows as they enter the plan; call before the steps are applied. In
+    /// streaming mode the counter is updated per chunk. Filters and limits pushed into the
+    /// scan pass through the counter, so it then counts only the rows they keep.
+    pub fn with_input_counter(self, progress: indicatif::ProgressBar) -> Self {
+        let df = self.df.map(
+            move |df| {
+                progress.inc(df.height() as u64);
+                Ok(df)
+            },
+            AllowedOptimizations::default() | OptFlags::STREAMING,
+            None,
+            Some("input_counter"),
+        );
+        Self { df }
+    }
+
+    /// Count output rows as they flow out of the plan. In streaming mode the counter
+    /// is updated per chunk; otherwise it is updated once, when the frame is materialized.
+    /// A run control installed on this thread is captured, so a cancelled streaming
+    /// run stops at the next chunk.
+    pub fn with_row_counter(self, progress: indicatif::ProgressBar) -> Self {
+        let control = crate::control::current();
+        let df = self.df.map(
+            move |df| {
+                if let Some(control) = &control {
+                    if control.is_cancelled() {
+                        polars_bail!(ComputeError: "run cancelled");
+                    }
+                    control.report(crate::control::Progress::Rows(df.height() as u64));
+                }
+                progress.inc(df.height() as u64);
+                Ok(df)
+            },
+            AllowedOptimizations::default() | OptFlags::STREAMING,
+            None,
+            Some("row_counter"),
+        );
+        Self { df }
+    }
+}
+
+/// Apply `pipeline`'s steps to `lf` and collect the result, without reading inputs or
+/// writing outputs. The pipeline's `runtime` block is honored (see
+/// [`crate::runner::init_runtime`]); no path restrictions apply.
+pub fn run_in_memory(lf: LazyFrame, pipeline: &crate::dsl::Pipeline) -> MlPrepResult<DataFrame> {
+    let runtime = pipeline.runtime.clone().unwrap_or_default();
+    let effective = crate::runner::init_runtime(&runtime)?;
+    let security_context =
+        crate::security::SecurityContext::new(crate::security::SecurityConfig::default())?;
+    DataPipeline::new(lf)
+        .apply_transforms(pipeline.clone(), &runtime, &security_context)?
+        .with_runtime(&effective)
+        .collect(runtime.streaming)
 }
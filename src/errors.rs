@@ -1,4 +1,4 @@
-use miette::{Diagnostic, SourceSpan};
+use miette::{Diagnostic, NamedSource, SourceSpan};
 use thiserror::Error;
 
 #[derive(Error, Diagnostic, Debug)]
@@ -39,9 +39,329 @@ pub enum MlPrepError {
     )]
     FeatureError(String),
 
+    #[error("Run cancelled")]
+    #[diagnostic(
+        code("MLPREP-007"),
+        help("The run was interrupted before it finished.")
+    )]
+    Cancelled,
+
+    #[error("Input does not match its contract: {0}")]
+    #[diagnostic(
+        code("MLPREP-008"),
+        help("Fix the input, or regenerate the contract with `mlprep contract generate` if the change is intended.")
+    )]
+    ContractError(String),
+
+    #[error("Pipeline test failed: {0}")]
+    #[diagnostic(
+        code("MLPREP-009"),
+        help("Review the differences; rerun `mlprep test` with `--update` if a snapshot change is intended.")
+    )]
+    TestFailure(String),
+
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    StepError(Box<StepError>),
+
     #[error(transparent)]
     #[diagnostic(code("MLPREP-000"))]
     Unknown(#[from] anyhow::Error),
 }
 
+/// A pipeline step that failed, with the columns of the frame it was applied to and,
+/// once attached with [`MlPrepError::with_pipeline_source`], its location in the YAML.
+#[derive(Error, Diagnostic, Debug)]
+#[error("Step {index} ({kind}) failed: {reason}")]
+#[diagnostic(code("MLPREP-010"))]
+pub struct StepError {
+    /// 1-based position in the pipeline's `steps`
+    pub index: usize,
+    pub kind: String,
+    /// The column the step referenced but the frame does not have
+    pub column: Option<String>,
+    /// The available column closest to `column`, when one is close enough to be meant
+    pub suggestion: Option<String>,
+    /// Name and dtype of each column of the frame the step was applied to
+    pub available: Vec<(String, String)>,
+    reason: String,
+    /// The error the step raised; not reported as the cause, since the engine's message
+    /// repeats the whole plan
+    pub cause: MlPrepError,
+    #[source_code]
+    pipeline: NamedSource,
+    #[label("step {index}")]
+    span: Option<SourceSpan>,
+    #[help]
+    help: Option<String>,
+}
+
+impl StepError {
+    /// Wrap `cause`, raised by step `index`, when it is an error of the step itself
+    /// (a missing column, a bad expression); validation failures, cancellation and I/O
+    /// errors are returned unchanged.
+    pub fn wrap(
+        index: usize,
+        kind: &str,
+        schema: Option<&polars::prelude::Schema>,
+        cause: MlPrepError,
+    ) -> MlPrepError {
+        if !matches!(
+            cause,
+            MlPrepError::PolarsError(_)
+                | MlPrepError::TransformError(_)
+                | MlPrepError::FeatureError(_)
+                | MlPrepError::ConfigError(..)
+                | MlPrepError::Unknown(_)
+        ) || matches!(&cause, MlPrepError::PolarsError(e) if is_io(e))
+        {
+            return cause;
+        }
+        let available: Vec<(String, String)> = schema
+            .map(|schema| {
+                schema
+                    .iter()
+                    .map(|(name, dtype)| (name.to_string(), dtype.to_string()))
+                    .collect()
+            })
+            .unwrap_or_default();
+        let column = missing_column(&cause)
+            .filter(|column| !available.iter().any(|(name, _)| name == column));
+        let suggestion = column.as_deref().and_then(|column| {
+            closest_name(column, available.iter().map(|(name, _)| name.as_str()))
+                .map(str::to_string)
+        });
+        let reason = match &column {
+            Some(column) => format!(
+                "column '{}' not found{}",
+                column,
+                did_you_mean(suggestion.as_deref())
+            ),
+            None => first_line(&cause.to_string()).to_string(),
+        };
+        let help = (!available.is_empty()).then(|| {
+            let columns: Vec<String> = available
+                .iter()
+                .map(|(name, dtype)| format!("{} ({})", name, dtype))
+                .collect();
+            format!("available columns: {}", columns.join(", "))
+        });
+        MlPrepError::StepError(Box::new(StepError {
+            index,
+            kind: kind.to_string(),
+            column,
+            suggestion,
+            available,
+            reason,
+            cause,
+            pipeline: NamedSource::new("", String::new()),
+            span: None,
+            help,
+        }))
+    }
+}
+
+/// The name among `candidates` closest to `name`, if it is close enough to be a typo
+/// of it: a case-insensitive edit distance of at most a third of the name's length
+/// (and at least one). Ties go to the earlier candidate.
+pub fn closest_name<'a>(
+    name: &str,
+    candidates: impl IntoIterator<Item = &'a str>,
+) -> Option<&'a str> {
+    let target: Vec<char> = name.to_lowercase().chars().collect();
+    let limit = (target.len() / 3).max(1);
+    candidates
+        .into_iter()
+        .filter(|candidate| *candidate != name)
+        .map(|candidate| {
+            let chars: Vec<char> = candidate.to_lowercase().chars().collect();
+            (edit_distance(&target, &chars), candidate)
+        })
+        .filter(|(distance, _)| *distance <= limit)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, candidate)| candidate)
+}
+
+/// Levenshtein distance between `a` and `b`.
+fn edit_distance(a: &[char], b: &[char]) -> usize {
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, x) in a.iter().enumerate() {
+        let mut current = vec![i + 1; b.len() + 1];
+        for (j, y) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(x != y);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+fn did_you_mean(suggestion: Option<&str>) -> String {
+    suggestion
+        .map(|name| format!("; did you mean '{}'?", name))
+        .unwrap_or_default()
+}
+
+/// "Column 'x' not found", with a suggestion when one of `available` is a likely
+/// intended name. Every step reports missing columns with this message.
+pub fn column_not_found<'a>(name: &str, available: impl IntoIterator<Item = &'a str>) -> String {
+    format!(
+        "Column '{}' not found{}",
+        name,
+        did_you_mean(closest_name(name, available))
+    )
+}
+
+/// Column `name` of `df`, or a [`column_not_found`] error.
+pub fn find_column<'a>(
+    df: &'a polars::prelude::DataFrame,
+    name: &str,
+) -> anyhow::Result<&'a polars::prelude::Column> {
+    df.column(name).map_err(|_| {
+        anyhow::anyhow!(column_not_found(
+            name,
+            df.get_column_names().into_iter().map(|c| c.as_str())
+        ))
+    })
+}
+
+fn is_io(error: &polars::error::PolarsError) -> bool {
+    match error {
+        polars::error::PolarsError::IO { .. } => true,
+        polars::error::PolarsError::Context { error, .. } => is_io(error),
+        _ => false,
+    }
+}
+
+/// Polars appends the resolved plan to its messages; the first line is the error.
+fn first_line(message: &str) -> &str {
+    message.lines().next().unwrap_or(message)
+}
+
+/// The column named by a column-not-found error, from Polars or from mlprep's own
+/// checks (`Column 'x' not found`).
+fn missing_column(error: &MlPrepError) -> Option<String> {
+    fn quoted(text: &str, open: char) -> Option<String> {
+        let (_, rest) = text.split_once(open)?;
+        let (name, _) = rest.split_once(open)?;
+        Some(name.to_string())
+    }
+    fn polars(error: &polars::error::PolarsError) -> Option<String> {
+        match error {
+            polars::error::PolarsError::ColumnNotFound(message) => {
+                let message = first_line(message);
+                if let Some((_, rest)) = message.split_once("column: ") {
+                    quoted(rest, '\'')
+                } else if message.starts_with("unable to find column")
+                    || message.ends_with(" not found")
+                {
+                    quoted(message, '"')
+                } else {
+                    Some(message.to_string())
+                }
+            }
+            polars::error::PolarsError::Context { error, .. } => polars(error),
+            _ => None,
+        }
+    }
+    match error {
+        MlPrepError::PolarsError(e) => polars(e),
+        MlPrepError::StepError(e) => e.column.clone(),
+        other => {
+            let message = other.to_string();
+            let (before, _) = message.split_once("' not found")?;
+            let (_, name) = before.rsplit_once("olumn '")?;
+            Some(name.to_string())
+        }
+    }
+}
+
+impl MlPrepError {
+    /// Whether retrying the run may succeed (network storage hiccups, eventual consistency).
+    pub fn is_transient(&self) -> bool {
+        match self {
+            MlPrepError::IoError(e) => is_transient_io(e.kind()),
+            MlPrepError::PolarsError(polars::error::PolarsError::IO { error, .. }) => {
+                is_transient_io(error.kind())
+            }
+            MlPrepError::StepError(e) => e.cause.is_transient(),
+            _ => false,
+        }
+    }
+
+    /// Point a [`StepError`] at its step in the pipeline YAML at `path`, so the report
+    /// shows the step's source; other errors, or an unreadable file, are left as they are.
+    pub fn with_pipeline_source(self, path: &std::path::Path) -> Self {
+        match self {
+            MlPrepError::StepError(mut e) => {
+                if let Ok(text) = std::fs::read_to_string(path) {
+                    e.span = crate::dsl::step_spans(&text).get(e.index - 1).copied();
+                    e.pipeline = NamedSource::new(path.display().to_string(), text);
+                }
+                MlPrepError::StepError(e)
+            }
+            other => other,
+        }
+    }
+
+    /// Renumber a [`StepError`] raised by a pipeline of one step to its `index` in the
+    /// full pipeline.
+    pub fn at_step(self, index: usize) -> Self {
+        match self {
+            MlPrepError::StepError(mut e) => {
+                e.index = index;
+                MlPrepError::StepError(e)
+            }
+            other => other,
+        }
+    }
+}
+
+fn is_transient_io(kind: std::io::ErrorKind) -> bool {
+    use std::io::ErrorKind;
+    matches!(
+        kind,
+        ErrorKind::TimedOut
+            | ErrorKind::Interrupted
+            | ErrorKind::WouldBlock
+            | ErrorKind::UnexpectedEof
+            | ErrorKind::ConnectionReset
+            | ErrorKind::ConnectionAborted
+            | ErrorKind::ConnectionRefused
+            | ErrorKind::BrokenPipe
+    )
+}
+
 pub type MlPrepResult<T> = Result<T, MlPrepError>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_closest_name() {
+        let columns = ["amount", "user_id", "created_at", "id"];
+        assert_eq!(closest_name("amout", columns), Some("amount"));
+        assert_eq!(closest_name("Amount", columns), Some("amount"));
+        assert_eq!(closest_name("userid", columns), Some("user_id"));
+        assert_eq!(closest_name("create_at", columns), Some("created_at"));
+        assert_eq!(closest_name("ix", columns), Some("id"));
+        assert_eq!(closest_name("price", columns), None);
+        assert_eq!(closest_name("amount", columns), None);
+        assert_eq!(
+            column_not_found("amuont", columns),
+            "Column 'amuont' not found; did you mean 'amount'?"
+        );
+    }
+
+    #[test]
+    fn test_is_transient() {
+        use std::io::{Error, ErrorKind};
+        let io = |kind| MlPrepError::IoError(Error::new(kind, "x"));
+        assert!(io(ErrorKind::TimedOut).is_transient());
+        assert!(io(ErrorKind::ConnectionRefused).is_transient());
+        // A mistyped input path fails at once instead of being retried
+        assert!(!io(ErrorKind::NotFound).is_transient());
+        assert!(!io(ErrorKind::PermissionDenied).is_transient());
+    }
+}
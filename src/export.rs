@@ -0,0 +1,412 @@
+//! Export fitted feature transforms to formats common serving stacks can read
+//! without mlprep: scikit-learn style JSON parameters and PMML 4.4.
+
+use crate::features::{FeatureConfig, FeatureState, FeatureStateEntry};
+use anyhow::{anyhow, Result};
+use serde_json::{json, Value};
+use std::collections::HashSet;
+use std::fmt::Write;
+
+/// Current layout version of the `sklearn_json` document.
+pub const SKLEARN_JSON_VERSION: u32 = 1;
+
+/// Describe each fitted transform with scikit-learn attribute names (`mean_`, `scale_`,
+/// `categories_`, ...) so it can be re-created with `set_params`/attribute assignment or
+/// applied by hand.
+///
+/// Scalers are exact: `MinMaxScaler` maps constant columns to 0.5 through `min_`, and
+/// `StandardScaler` uses mlprep's sample standard deviation as `scale_`.
+pub fn export_sklearn_json(config: &FeatureConfig, state: &FeatureState) -> Result<String> {
+    let mut transformers = Vec::new();
+    for (spec, entry) in state.fitted_specs(config)? {
+        let prefix = spec.alias.as_deref().unwrap_or(&spec.column).to_string();
+        let transformer = match entry {
+            FeatureStateEntry::MinMax { stats, .. } => {
+                let range = stats.max - stats.min;
+                let (scale, min) = if range.abs() < f64::EPSILON {
+                    (0.0, 0.5)
+                } else {
+                    (1.0 / range, -stats.min / range)
+                };
+                json!({
+                    "class": "sklearn.preprocessing.MinMaxScaler",
+                    "columns": [spec.column],
+                    "outputs": [prefix],
+                    "params": {
+                        "feature_range": [0.0, 1.0],
+                        "data_min_": [stats.min],
+                        "data_max_": [stats.max],
+                        "data_range_": [range],
+                        "scale_": [scale],
+                        "min_": [min],
+                    },
+                })
+            }
+            FeatureStateEntry::Standard { stats, .. } => {
+                // Constant columns map to 0 either way; sklearn uses 1.0 to avoid dividing by 0.
+                let scale = if stats.std.abs() < f64::EPSILON {
+                    1.0
+                } else {
+                    stats.std
+                };
+                json!({
+                    "class": "sklearn.preprocessing.StandardScaler",
+                    "columns": [spec.column],
+                    "outputs": [prefix],
+                    "params": {
+                        "mean_": [stats.mean],
+                        "var_": [stats.std * stats.std],
+                        "scale_": [scale],
+                    },
+                })
+            }
+            FeatureStateEntry::OneHot { vocab, .. } => json!({
+                "class": "sklearn.preprocessing.OneHotEncoder",
+                "columns": [spec.column],
+                "outputs": vocab
+                    .categories
+                    .iter()
+                    .map(|c| format!("{}_{}", prefix, c))
+                    .collect::<Vec<_>>(),
+                "params": {
+                    "categories_": [vocab.categories],
+                    "handle_unknown": "ignore",
+                },
+            }),
+            FeatureStateEntry::Count { stats, .. } => {
+                let mut mapping: Vec<(&String, &u64)> = stats.counts.iter().collect();
+                mapping.sort();
+                let mapping: serde_json::Map<String, Value> = mapping
+                    .into_iter()
+                    .map(|(value, count)| {
+                        let freq = if stats.total == 0 {
+                            0.0
+                        } else {
+                            *count as f64 / stats.total as f64
+                        };
+                        (value.clone(), json!(freq))
+                    })
+                    .collect();
+                json!({
+                    "class": "category_encoders.CountEncoder",
+                    "columns": [spec.column],
+                    "outputs": [prefix],
+                    "params": {
+                        "normalize": true,
+                        "handle_unknown": 0.0,
+                        "handle_missing": 0.0,
+                        "mapping": { spec.column.as_str(): mapping },
+                    },
+                })
+            }
+            FeatureStateEntry::Interactions { terms, .. } => json!({
+                // PolynomialFeatures would also emit the raw inputs; list the exact products instead.
+                "class": "mlprep.InteractionTerms",
+                "columns": spec.inputs,
+                "outputs": terms.terms.iter().map(|t| &t.name).collect::<Vec<_>>(),
+                "params": {
+                    "terms": terms.terms.iter().map(|t| [&t.left, &t.right]).collect::<Vec<_>>(),
+                },
+            }),
+            FeatureStateEntry::Pca { stats, .. } => json!({
+                "class": "sklearn.decomposition.PCA",
+                "columns": stats.inputs,
+                "outputs": (1..=stats.components.len())
+                    .map(|i| format!("{}_pc{}", prefix, i))
+                    .collect::<Vec<_>>(),
+                "params": {
+                    "n_components_": stats.components.len(),
+                    "mean_": stats.means,
+                    "components_": stats.components,
+                    "explained_variance_": stats.explained_variance,
+                    "explained_variance_ratio_": stats.explained_variance_ratio,
+                },
+            }),
+            FeatureStateEntry::Cluster { stats, .. } => json!({
+                "class": "sklearn.cluster.KMeans",
+                "columns": stats.inputs,
+                "outputs": [format!("{}_cluster", prefix), format!("{}_distance", prefix)],
+                "params": {
+                    "n_clusters": stats.centroids.len(),
+                    "cluster_centers_": stats.centroids,
+                    "inertia_": stats.inertia,
+                },
+            }),
+        };
+        transformers.push(transformer);
+    }
+
+    let document = json!({
+        "format": "mlprep-sklearn",
+        "version": SKLEARN_JSON_VERSION,
+        "transformers": transformers,
+    });
+    Ok(serde_json::to_string_pretty(&document)?)
+}
+
+fn xml_escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+/// Collects PMML `DataField`s and `DerivedField`s.
+#[derive(Default)]
+struct PmmlBuilder {
+    data_fields: Vec<(String, bool)>,
+    seen: HashSet<String>,
+    derived: String,
+}
+
+impl PmmlBuilder {
+    fn field(&mut self, name: &str, numeric: bool) -> String {
+        if self.seen.insert(name.to_string()) {
+            self.data_fields.push((name.to_string(), numeric));
+        }
+        xml_escape(name)
+    }
+
+    /// Derived fields may not reuse a DataField name (in-place scaling gets `_out`).
+    fn derived(&mut self, name: &str, data_type: &str, body: &str) -> Result<()> {
+        let name = if self.seen.contains(name) {
+            format!("{}_out", name)
+        } else {
+            name.to_string()
+        };
+        let optype = if data_type == "integer" {
+            "categorical"
+        } else {
+            "continuous"
+        };
+        writeln!(
+            self.derived,
+            "    <DerivedField name=\"{}\" optype=\"{}\" dataType=\"{}\">\n      {}\n    </DerivedField>",
+            xml_escape(&name),
+            optype,
+            data_type,
+            body
+        )?;
+        Ok(())
+    }
+}
+
+/// Emit a PMML 4.4 document whose `TransformationDictionary` reproduces the fitted
+/// transforms (`NormContinuous`, `NormDiscrete`, `MapValues`, `Apply`).
+pub fn export_pmml(config: &FeatureConfig, state: &FeatureState) -> Result<String> {
+    let mut b = PmmlBuilder::default();
+    for (spec, entry) in state.fitted_specs(config)? {
+        let prefix = spec.alias.as_deref().unwrap_or(&spec.column).to_string();
+        match entry {
+            FeatureStateEntry::MinMax { stats, .. } => {
+                let field = b.field(&spec.column, true);
+                let range = stats.max - stats.min;
+                let body = if range.abs() < f64::EPSILON {
+                    "<Constant dataType=\"double\">0.5</Constant>".to_string()
+                } else {
+                    format!(
+                        "<NormContinuous field=\"{}\"><LinearNorm orig=\"{}\" norm=\"0\"/><LinearNorm orig=\"{}\" norm=\"1\"/></NormContinuous>",
+                        field, stats.min, stats.max
+                    )
+                };
+                b.derived(&prefix, "double", &body)?;
+            }
+            FeatureStateEntry::Standard { stats, .. } => {
+                let field = b.field(&spec.column, true);
+                let body = if stats.std.abs() < f64::EPSILON {
+                    "<Constant dataType=\"double\">0</Constant>".to_string()
+                } else {
+                    format!(
+                        "<NormContinuous field=\"{}\"><LinearNorm orig=\"{}\" norm=\"0\"/><LinearNorm orig=\"{}\" norm=\"1\"/></NormContinuous>",
+                        field,
+                        stats.mean,
+                        stats.mean + stats.std
+                    )
+                };
+                b.derived(&prefix, "double", &body)?;
+            }
+            FeatureStateEntry::OneHot { vocab, .. } => {
+                let field = b.field(&spec.column, false);
+                for category in &vocab.categories {
+                    let body = format!(
+                        "<NormDiscrete field=\"{}\" value=\"{}\" mapMissingTo=\"0\"/>",
+                        field,
+                        xml_escape(category)
+                    );
+                    b.derived(&format!("{}_{}", prefix, category), "integer", &body)?;
+                }
+            }
+            FeatureStateEntry::Count { stats, .. } => {
+                let field = b.field(&spec.column, false);
+                let mut rows: Vec<(&String, &u64)> = stats.counts.iter().collect();
+                rows.sort();
+                let mut body = format!(
+                    "<MapValues outputColumn=\"out\" dataType=\"double\" defaultValue=\"0\" mapMissingTo=\"0\">\n        <FieldColumnPair field=\"{}\" column=\"in\"/>\n        <InlineTable>\n",
+                    field
+                );
+                for (value, count) in rows {
+                    let freq = if stats.total == 0 {
+                        0.0
+                    } else {
+                        *count as f64 / stats.total as f64
+                    };
+                    writeln!(
+                        body,
+                        "          <row><in>{}</in><out>{}</out></row>",
+                        xml_escape(value),
+                        freq
+                    )?;
+                }
+                body.push_str("        </InlineTable>\n      </MapValues>");
+                b.derived(&prefix, "double", &body)?;
+            }
+            FeatureStateEntry::Interactions { terms, .. } => {
+                for term in &terms.terms {
+                    let left = b.field(&term.left, true);
+                    let right = b.field(&term.right, true);
+                    let body = format!(
+                        "<Apply function=\"*\"><FieldRef field=\"{}\"/><FieldRef field=\"{}\"/></Apply>",
+                        left, right
+                    );
+                    b.derived(&term.name, "double", &body)?;
+                }
+            }
+            FeatureStateEntry::Pca { stats, .. } => {
+                let fields: Vec<String> = stats.inputs.iter().map(|c| b.field(c, true)).collect();
+                for (i, component) in stats.components.iter().enumerate() {
+                    let terms: String = fields
+                        .iter()
+                        .zip(&stats.means)
+                        .zip(component)
+                        .map(|((field, mean), weight)| {
+                            format!(
+                                "<Apply function=\"*\"><Apply function=\"-\"><FieldRef field=\"{}\"/><Constant dataType=\"double\">{}</Constant></Apply><Constant dataType=\"double\">{}</Constant></Apply>",
+                                field, mean, weight
+                            )
+                        })
+                        .collect();
+                    let body = format!("<Apply function=\"sum\">{}</Apply>", terms);
+                    b.derived(&format!("{}_pc{}", prefix, i + 1), "double", &body)?;
+                }
+            }
+            FeatureStateEntry::Cluster { .. } => {
+                return Err(anyhow!(
+                    "cluster_encode ('{}') cannot be exported to PMML; use sklearn_json",
+                    spec.column
+                ));
+            }
+        }
+    }
+
+    let mut pmml = String::new();
+    writeln!(pmml, "<?xml version=\"1.0\" encoding=\"UTF-8\"?>")?;
+    writeln!(
+        pmml,
+        "<PMML version=\"4.4\" xmlns=\"http://www.dmg.org/PMML-4_4\">"
+    )?;
+    writeln!(
+        pmml,
+        "  <Header description=\"mlprep feature transforms\"><Application name=\"mlprep\" version=\"{}\"/></Header>",
+        env!("CARGO_PKG_VERSION")
+    )?;
+    writeln!(
+        pmml,
+        "  <DataDictionary numberOfFields=\"{}\">",
+        b.data_fields.len()
+    )?;
+    for (name, numeric) in &b.data_fields {
+        let (optype, data_type) = if *numeric {
+            ("continuous", "double")
+        } else {
+            ("categorical", "string")
+        };
+        writeln!(
+            pmml,
+            "    <DataField name=\"{}\" optype=\"{}\" dataType=\"{}\"/>",
+            xml_escape(name),
+            optype,
+            data_type
+        )?;
+    }
+    writeln!(pmml, "  </DataDictionary>")?;
+    writeln!(pmml, "  <TransformationDictionary>")?;
+    pmml.push_str(&b.derived);
+    writeln!(pmml, "  </TransformationDictionary>")?;
+    writeln!(pmml, "</PMML>")?;
+    Ok(pmml)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::features::{fit_features, FeatureSpec, FeatureTransform};
+    use polars::prelude::*;
+
+    fn fitted() -> (FeatureConfig, FeatureState) {
+        let df = df! {
+            "price" => &[10.0, 20.0, 30.0],
+            "city" => &["tokyo", "osaka", "tokyo"],
+        }
+        .unwrap();
+        let config = FeatureConfig {
+            features: vec![
+                FeatureSpec::new("price", FeatureTransform::MinMaxScale),
+                FeatureSpec::new("city", FeatureTransform::OneHotEncode),
+                FeatureSpec {
+                    alias: Some("city_freq".to_string()),
+                    ..FeatureSpec::new("city", FeatureTransform::CountEncode)
+                },
+            ],
+        };
+        let state = fit_features(&df, &config).unwrap();
+        (config, state)
+    }
+
+    #[test]
+    fn test_export_sklearn_json() {
+        let (config, state) = fitted();
+        let doc: Value =
+            serde_json::from_str(&export_sklearn_json(&config, &state).unwrap()).unwrap();
+
+        assert_eq!(doc["version"], SKLEARN_JSON_VERSION);
+        let minmax = &doc["transformers"][0];
+        assert_eq!(minmax["class"], "sklearn.preprocessing.MinMaxScaler");
+        assert_eq!(minmax["params"]["scale_"][0], 0.05);
+        assert_eq!(minmax["params"]["min_"][0], -0.5);
+
+        let onehot = &doc["transformers"][1];
+        assert_eq!(
+            onehot["params"]["categories_"][0],
+            json!(["osaka", "tokyo"])
+        );
+        assert_eq!(onehot["outputs"], json!(["city_osaka", "city_tokyo"]));
+
+        let count = &doc["transformers"][2];
+        let tokyo = count["params"]["mapping"]["city"]["tokyo"]
+            .as_f64()
+            .unwrap();
+        assert!((tokyo - 2.0 / 3.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_export_pmml() {
+        let (config, state) = fitted();
+        let pmml = export_pmml(&config, &state).unwrap();
+
+        assert!(
+            pmml.contains("<DataField name=\"price\" optype=\"continuous\" dataType=\"double\"/>")
+        );
+        assert!(
+            pmml.contains("<DataField name=\"city\" optype=\"categorical\" dataType=\"string\"/>")
+        );
+        // In-place scaling can't reuse the DataField name
+        assert!(pmml.contains("<DerivedField name=\"price_out\""));
+        assert!(pmml
+            .contains("<LinearNorm orig=\"10\" norm=\"0\"/><LinearNorm orig=\"30\" norm=\"1\"/>"));
+        assert!(pmml.contains("<NormDiscrete field=\"city\" value=\"tokyo\" mapMissingTo=\"0\"/>"));
+        assert!(pmml.contains("<DerivedField name=\"city_freq\""));
+        assert!(pmml.contains("<row><in>osaka</in>"));
+    }
+}
@@ -0,0 +1,244 @@
+//! Feast feature repository definitions for pipeline outputs: an output with a `feast:`
+//! block also gets a Python file declaring its entities, a `FileSource` over the written
+//! file and a `FeatureView` whose fields mirror the output schema, ready for `feast apply`.
+
+use crate::dsl::{FeastOutput, Output};
+use crate::errors::{MlPrepError, MlPrepResult};
+use polars::prelude::*;
+use serde::de::Error;
+use std::collections::BTreeSet;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+fn invalid(msg: String) -> MlPrepError {
+    MlPrepError::ConfigError(serde_yaml::Error::custom(msg), None)
+}
+
+/// The `feast.types` name of a polars dtype, if Feast has one.
+fn feast_type(dtype: &DataType) -> Option<String> {
+    Some(match dtype {
+        DataType::Boolean => "Bool".to_string(),
+        DataType::Int8 | DataType::Int16 | DataType::Int32 | DataType::UInt8 | DataType::UInt16 => {
+            "Int32".to_string()
+        }
+        DataType::Int64 | DataType::UInt32 | DataType::UInt64 => "Int64".to_string(),
+        DataType::Float32 => "Float32".to_string(),
+        DataType::Float64 => "Float64".to_string(),
+        DataType::String | DataType::Categorical(_, _) | DataType::Enum(_, _) => {
+            "String".to_string()
+        }
+        DataType::Binary => "Bytes".to_string(),
+        DataType::Date | DataType::Datetime(_, _) => "UnixTimestamp".to_string(),
+        DataType::List(inner) => match inner.as_ref() {
+            DataType::List(_) => return None,
+            inner => format!("Array({})", feast_type(inner)?),
+        },
+        _ => return None,
+    })
+}
+
+/// A valid Python identifier derived from `name`.
+fn identifier(name: &str) -> String {
+    let ident: String = name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    if ident.is_empty() || ident.starts_with(|c: char| c.is_ascii_digit()) {
+        format!("_{}", ident)
+    } else {
+        ident
+    }
+}
+
+fn file_stem(path: &Path) -> String {
+    path.file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "features".to_string())
+}
+
+/// Where the definition of `output` is written: `definition:`, or
+/// `<output stem>_feast.py` next to the output.
+pub fn definition_path(output: &Output, feast: &FeastOutput) -> PathBuf {
+    match &feast.definition {
+        Some(path) => path.clone(),
+        None => output
+            .path
+            .with_file_name(format!("{}_feast.py", file_stem(&output.path))),
+    }
+}
+
+/// Render the Python definition of a FeatureView over `source` with the given schema.
+pub fn render(feast: &FeastOutput, source: &str, schema: &Schema) -> MlPrepResult<String> {
+    if feast.entities.is_empty() {
+        return Err(invalid("feast needs at least one entity".to_string()));
+    }
+    for entity in &feast.entities {
+        if schema.get(entity).is_none() {
+            return Err(invalid(format!(
+                "feast entity '{}' is not an output column",
+                entity
+            )));
+        }
+    }
+    match schema.get(&feast.timestamp_field) {
+        Some(DataType::Date | DataType::Datetime(_, _)) => {}
+        Some(other) => {
+            return Err(invalid(format!(
+                "feast timestamp_field '{}' must be a Date or Datetime column, got {:?}",
+                feast.timestamp_field, other
+            )))
+        }
+        None => {
+            return Err(invalid(format!(
+                "feast timestamp_field '{}' is not an output column",
+                feast.timestamp_field
+            )))
+        }
+    }
+    let ttl = feast
+        .ttl
+        .as_deref()
+        .map(crate::dsl::parse_duration)
+        .transpose()?;
+    let name = feast
+        .name
+        .clone()
+        .unwrap_or_else(|| file_stem(Path::new(source)));
+
+    let mut fields = Vec::new();
+    for (column, dtype) in schema.iter() {
+        if feast.entities.iter().any(|e| e == column.as_str()) || column == &feast.timestamp_field {
+            continue;
+        }
+        let ty = feast_type(dtype).ok_or_else(|| {
+            invalid(format!(
+                "column '{}' of type {:?} has no Feast type",
+                column, dtype
+            ))
+        })?;
+        fields.push((column.to_string(), ty));
+    }
+    let types: BTreeSet<&str> = fields
+        .iter()
+        .flat_map(|(_, ty)| ty.split(['(', ')']))
+        .filter(|part| !part.is_empty())
+        .collect();
+
+    let mut py = String::new();
+    py.push_str("# Generated by mlprep; regenerated on every run.\n");
+    py.push_str("from datetime import timedelta\n\n");
+    py.push_str("from feast import Entity, FeatureView, Field, FileSource\n");
+    if !types.is_empty() {
+        py.push_str(&format!(
+            "from feast.types import {}\n",
+            types.into_iter().collect::<Vec<_>>().join(", ")
+        ));
+    }
+    for entity in &feast.entities {
+        py.push_str(&format!(
+            "\n{} = Entity(name={:?}, join_keys=[{:?}])\n",
+            identifier(entity),
+            entity,
+            entity
+        ));
+    }
+    let source_var = format!("{}_source", identifier(&name));
+    py.push_str(&format!(
+        "\n{} = FileSource(\n    name={:?},\n    path={:?},\n    timestamp_field={:?},\n)\n",
+        source_var,
+        format!("{}_source", name),
+        source,
+        feast.timestamp_field
+    ));
+    py.push_str(&format!(
+        "\n{} = FeatureView(\n    name={:?},\n    entities=[{}],\n",
+        identifier(&name),
+        name,
+        feast
+            .entities
+            .iter()
+            .map(|e| identifier(e))
+            .collect::<Vec<_>>()
+            .join(", ")
+    ));
+    if let Some(ttl) = ttl {
+        py.push_str(&format!("    ttl=timedelta(seconds={}),\n", ttl.as_secs()));
+    }
+    py.push_str("    schema=[\n");
+    for (column, ty) in &fields {
+        py.push_str(&format!(
+            "        Field(name={:?}, dtype={}),\n",
+            column, ty
+        ));
+    }
+    py.push_str(&format!("    ],\n    source={},\n)\n", source_var));
+    Ok(py)
+}
+
+/// Write the Feast definition of an already written output; returns where it went.
+pub fn write_definition(output: &Output, feast: &FeastOutput) -> MlPrepResult<PathBuf> {
+    let schema = crate::io::read_detected(&output.path, output.format.as_deref())?
+        .collect_schema()
+        .map_err(MlPrepError::PolarsError)?;
+    let source = std::path::absolute(&output.path)?;
+    let py = render(feast, &source.to_string_lossy(), &schema)?;
+    let path = definition_path(output, feast);
+    crate::io::write_atomic(&path, |file| {
+        file.write_all(py.as_bytes()).map_err(MlPrepError::IoError)
+    })?;
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_feature_view() {
+        let feast: FeastOutput = serde_yaml::from_str(
+            "entities: [user_id]\ntimestamp_field: event_time\nttl: 24h\nname: user-stats\n",
+        )
+        .unwrap();
+        let schema = Schema::from_iter([
+            Field::new("user_id".into(), DataType::Int64),
+            Field::new(
+                "event_time".into(),
+                DataType::Datetime(TimeUnit::Microseconds, None),
+            ),
+            Field::new("spend".into(), DataType::Float64),
+            Field::new("visits".into(), DataType::UInt32),
+            Field::new("country".into(), DataType::String),
+            Field::new(
+                "embedding".into(),
+                DataType::List(Box::new(DataType::Float32)),
+            ),
+        ]);
+        let py = render(&feast, "/data/user_stats.parquet", &schema).unwrap();
+        assert!(
+            py.contains("from feast.types import Array, Float32, Float64, Int64, String\n"),
+            "{}",
+            py
+        );
+        assert!(py.contains("user_id = Entity(name=\"user_id\", join_keys=[\"user_id\"])"));
+        assert!(py.contains(
+            "    path=\"/data/user_stats.parquet\",\n    timestamp_field=\"event_time\","
+        ));
+        assert!(py.contains(
+            "user_stats = FeatureView(\n    name=\"user-stats\",\n    entities=[user_id],"
+        ));
+        assert!(py.contains("ttl=timedelta(seconds=86400)"));
+        assert!(py.contains("Field(name=\"visits\", dtype=Int64)"));
+        assert!(py.contains("Field(name=\"embedding\", dtype=Array(Float32))"));
+        assert!(!py.contains("Field(name=\"user_id\""));
+        assert!(!py.contains("Field(name=\"event_time\""));
+
+        let mut bad = feast.clone();
+        bad.timestamp_field = "spend".to_string();
+        let err = render(&bad, "x.parquet", &schema).unwrap_err().to_string();
+        assert!(err.contains("must be a Date or Datetime column"), "{}", err);
+        bad.timestamp_field = "event_time".to_string();
+        bad.entities = vec!["account".to_string()];
+        let err = render(&bad, "x.parquet", &schema).unwrap_err().to_string();
+        assert!(err.contains("'account' is not an output column"), "{}", err);
+    }
+}
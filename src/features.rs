@@ -1,15 +1,17 @@
 //! Feature Engineering module
 //!
 //! Implements fit/transform pattern for reproducible feature generation.
-//! Supports scaling (MinMax, Standard) and encoding (OneHot, Count).
+//! Supports scaling (MinMax, Standard), encoding (OneHot, Count),
+//! pairwise interaction terms, PCA projection and k-means cluster encoding.
 
+use crate::errors::{column_not_found, find_column};
 use anyhow::{anyhow, Result};
 use polars::prelude::UniqueKeepStrategy;
 use polars::prelude::*;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::fs::File;
-use std::io::{BufReader, BufWriter};
+use std::io::{BufRead, BufReader, BufWriter};
 use std::path::Path;
 
 /// Feature transformation types
@@ -20,15 +22,58 @@ pub enum FeatureTransform {
     StandardScale,
     OneHotEncode,
     CountEncode,
+    Interactions,
+    Pca,
+    ClusterEncode,
 }
 
 /// Specification for a single feature transformation
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct FeatureSpec {
+    /// Column to transform (or the feature-set name for multi-input transforms)
+    #[serde(default)]
     pub column: String,
     pub transform: FeatureTransform,
     #[serde(default)]
     pub alias: Option<String>,
+    /// Apply the same transform to each of these columns (instead of `column`)
+    #[serde(default)]
+    pub columns: Vec<String>,
+    /// Apply the same transform to every column whose name matches this regex
+    #[serde(default)]
+    pub columns_regex: Option<String>,
+    /// Input columns for multi-column transforms (`interactions`, `pca`, `cluster_encode`); `column` then names the feature set
+    #[serde(default)]
+    pub inputs: Vec<String>,
+    /// `interactions`: also emit the square of each input
+    #[serde(default)]
+    pub include_squares: bool,
+    /// `pca`: number of components to keep (defaults to the number of inputs)
+    #[serde(default)]
+    pub n_components: Option<usize>,
+    /// `cluster_encode`: number of clusters
+    #[serde(default)]
+    pub k: Option<usize>,
+    /// `cluster_encode`: seed for centroid initialisation
+    #[serde(default)]
+    pub seed: Option<u64>,
+}
+
+impl FeatureSpec {
+    pub fn new(column: impl Into<String>, transform: FeatureTransform) -> Self {
+        Self {
+            column: column.into(),
+            transform,
+            alias: None,
+            columns: Vec::new(),
+            columns_regex: None,
+            inputs: Vec::new(),
+            include_squares: false,
+            n_components: None,
+            k: None,
+            seed: None,
+        }
+    }
 }
 
 /// Configuration for feature engineering pipeline
@@ -37,6 +82,101 @@ pub struct FeatureConfig {
     pub features: Vec<FeatureSpec>,
 }
 
+impl FeatureConfig {
+    /// Load a feature config (`features: [...]`) from a YAML file
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let file = File::open(path.as_ref())
+            .map_err(|e| anyhow!("Failed to open feature config: {}", e))?;
+        serde_yaml::from_reader(BufReader::new(file))
+            .map_err(|e| anyhow!("Failed to parse feature config: {}", e))
+    }
+
+    /// The config with `seed` filled in for specs that don't set their own.
+    pub fn with_default_seed(&self, seed: Option<u64>) -> FeatureConfig {
+        let mut config = self.clone();
+        for spec in &mut config.features {
+            spec.seed = spec.seed.or(seed);
+        }
+        config
+    }
+
+    /// Expand `columns` / `columns_regex` specs into one spec per concrete column of `schema`,
+    /// so fitted state is stored (and looked up) per column.
+    pub fn resolve(&self, schema: &Schema) -> Result<FeatureConfig> {
+        let mut features = Vec::with_capacity(self.features.len());
+        for spec in &self.features {
+            if spec.columns.is_empty() && spec.columns_regex.is_none() {
+                if spec.column.is_empty() {
+                    return Err(anyhow!(
+                        "Feature spec for {:?} needs `column`, `columns` or `columns_regex`",
+                        spec.transform
+                    ));
+                }
+                features.push(spec.clone());
+                continue;
+            }
+
+            if !spec.column.is_empty() {
+                return Err(anyhow!(
+                    "Feature spec '{}' sets both `column` and `columns`/`columns_regex`",
+                    spec.column
+                ));
+            }
+            if spec.alias.is_some() {
+                return Err(anyhow!(
+                    "`alias` cannot be used with `columns`/`columns_regex` ({:?})",
+                    spec.transform
+                ));
+            }
+            if matches!(
+                spec.transform,
+                FeatureTransform::Interactions
+                    | FeatureTransform::Pca
+                    | FeatureTransform::ClusterEncode
+            ) {
+                return Err(anyhow!(
+                    "{:?} combines several columns; list them in `inputs` instead of `columns`",
+                    spec.transform
+                ));
+            }
+
+            let mut columns = spec.columns.clone();
+            if let Some(pattern) = &spec.columns_regex {
+                let re = regex::Regex::new(pattern)
+                    .map_err(|e| anyhow!("Invalid columns_regex '{}': {}", pattern, e))?;
+                // Scalers only make sense on numeric columns, so `.*` means "every numeric column".
+                let numeric_only = matches!(
+                    spec.transform,
+                    FeatureTransform::MinMaxScale | FeatureTransform::StandardScale
+                );
+                for (name, dtype) in schema.iter() {
+                    if re.is_match(name)
+                        && (!numeric_only || dtype.is_primitive_numeric())
+                        && !columns.iter().any(|c| c == name.as_str())
+                    {
+                        columns.push(name.to_string());
+                    }
+                }
+            }
+            if columns.is_empty() {
+                return Err(anyhow!(
+                    "`columns_regex` {:?} matched no columns for {:?}",
+                    spec.columns_regex.as_deref().unwrap_or_default(),
+                    spec.transform
+                ));
+            }
+
+            features.extend(columns.into_iter().map(|column| FeatureSpec {
+                column,
+                columns: Vec::new(),
+                columns_regex: None,
+                ..spec.clone()
+            }));
+        }
+        Ok(FeatureConfig { features })
+    }
+}
+
 /// Statistics for MinMax scaling
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct MinMaxStats {
@@ -64,6 +204,40 @@ pub struct CountStats {
     pub total: u64,
 }
 
+/// A generated product feature `left * right`
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct InteractionTerm {
+    pub left: String,
+    pub right: String,
+    pub name: String,
+}
+
+/// Interaction terms fixed at fit time, so inference emits the same feature set
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct InteractionTerms {
+    pub terms: Vec<InteractionTerm>,
+}
+
+/// Fitted PCA projection: `pc_i = sum_j (x_j - means[j]) * components[i][j]`
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct PcaStats {
+    pub inputs: Vec<String>,
+    pub means: Vec<f64>,
+    /// Unit component vectors, ordered by decreasing explained variance
+    pub components: Vec<Vec<f64>>,
+    pub explained_variance: Vec<f64>,
+    pub explained_variance_ratio: Vec<f64>,
+}
+
+/// Fitted k-means centroids (one row per cluster, in `inputs` order)
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct KMeansStats {
+    pub inputs: Vec<String>,
+    pub centroids: Vec<Vec<f64>>,
+    /// Sum of squared distances to the assigned centroid at fit time
+    pub inertia: f64,
+}
+
 /// State for a single feature (stores fit statistics)
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(tag = "type", rename_all = "snake_case")]
@@ -84,38 +258,219 @@ pub enum FeatureStateEntry {
         column: String,
         stats: CountStats,
     },
+    Interactions {
+        column: String,
+        terms: InteractionTerms,
+    },
+    Pca {
+        column: String,
+        stats: PcaStats,
+    },
+    Cluster {
+        column: String,
+        stats: KMeansStats,
+    },
+}
+
+/// Current on-disk FeatureState format version.
+///
+/// 1: entries only (files without a `version` field); 2: adds the fitted schema snapshot.
+pub const FEATURE_STATE_VERSION: u32 = 2;
+
+/// On-disk encoding of a FeatureState
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum StateFormat {
+    /// Pretty-printed JSON, easy to inspect and diff
+    #[default]
+    Json,
+    /// zstd-compressed MessagePack, for large vocabularies and count tables
+    Binary,
+}
+
+const ZSTD_MAGIC: [u8; 4] = [0x28, 0xB5, 0x2F, 0xFD];
+
+fn legacy_state_version() -> u32 {
+    1
+}
+
+/// Name and dtype of an input column at fit time
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct FittedColumn {
+    pub name: String,
+    pub dtype: String,
 }
 
 /// Complete feature state for persistence
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct FeatureState {
+    #[serde(default = "legacy_state_version")]
+    pub version: u32,
+    /// Input columns the state was fitted on (empty for version 1 states)
+    #[serde(default)]
+    pub schema: Vec<FittedColumn>,
     pub entries: Vec<FeatureStateEntry>,
 }
 
+impl Default for FeatureState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl FeatureState {
     pub fn new() -> Self {
         Self {
+            version: FEATURE_STATE_VERSION,
+            schema: Vec::new(),
             entries: Vec::new(),
         }
     }
 
-    /// Save feature state to JSON file
+    /// Snapshot the dtypes of the columns `config` reads from `schema`.
+    pub fn record_schema(&mut self, schema: &Schema, config: &FeatureConfig) {
+        self.schema.clear();
+        for spec in &config.features {
+            let inputs = if spec.inputs.is_empty() {
+                std::slice::from_ref(&spec.column)
+            } else {
+                spec.inputs.as_slice()
+            };
+            for name in inputs {
+                if self.schema.iter().any(|c| &c.name == name) {
+                    continue;
+                }
+                if let Some(dtype) = schema.get(name) {
+                    self.schema.push(FittedColumn {
+                        name: name.clone(),
+                        dtype: dtype.to_string(),
+                    });
+                }
+            }
+        }
+    }
+
+    /// Schema rebuilt from the snapshot (numeric columns as f64, everything else as strings),
+    /// enough to resolve `columns_regex` specs without the original data.
+    pub fn fitted_schema(&self) -> Schema {
+        self.schema
+            .iter()
+            .map(|c| {
+                let dtype = if is_numeric_dtype_name(&c.dtype) {
+                    DataType::Float64
+                } else {
+                    DataType::String
+                };
+                Field::new(c.name.as_str().into(), dtype)
+            })
+            .collect()
+    }
+
+    /// Pair every (resolved) spec of `config` with its fitted entry, for exporters
+    /// that only have the state and not the original data.
+    pub fn fitted_specs(
+        &self,
+        config: &FeatureConfig,
+    ) -> Result<Vec<(FeatureSpec, &FeatureStateEntry)>> {
+        config
+            .resolve(&self.fitted_schema())?
+            .features
+            .into_iter()
+            .map(|spec| {
+                let entry = self
+                    .get_entry(&spec.column, &spec.transform)
+                    .ok_or_else(|| {
+                        anyhow!(
+                            "No fitted state for column '{}' with transform {:?}",
+                            spec.column,
+                            spec.transform
+                        )
+                    })?;
+                Ok((spec, entry))
+            })
+            .collect()
+    }
+
+    /// Check that `schema` still has every fitted column with a compatible dtype.
+    ///
+    /// Numeric columns may change width (e.g. i32 -> i64) since transforms read them as f64.
+    pub fn check_compatible(&self, schema: &Schema) -> Result<()> {
+        let problems: Vec<String> = self
+            .schema
+            .iter()
+            .filter_map(|fitted| match schema.get(&fitted.name) {
+                None => Some(format!("column '{}' is missing", fitted.name)),
+                Some(dtype) => {
+                    let current = dtype.to_string();
+                    let both_numeric =
+                        dtype.is_primitive_numeric() && is_numeric_dtype_name(&fitted.dtype);
+                    (current != fitted.dtype && !both_numeric).then(|| {
+                        format!(
+                            "column '{}' was fitted as {} but is now {}",
+                            fitted.name, fitted.dtype, current
+                        )
+                    })
+                }
+            })
+            .collect();
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(anyhow!(
+                "Feature state (version {}) does not match the input data: {}",
+                self.version,
+                problems.join("; ")
+            ))
+        }
+    }
+
+    /// Save feature state, as compressed binary when `path` ends in `.bin` and JSON otherwise
     pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let format = if path.as_ref().extension().is_some_and(|ext| ext == "bin") {
+            StateFormat::Binary
+        } else {
+            StateFormat::Json
+        };
+        self.save_as(path, format)
+    }
+
+    /// Save feature state in the given format
+    pub fn save_as<P: AsRef<Path>>(&self, path: P, format: StateFormat) -> Result<()> {
         let file = File::create(path.as_ref())
             .map_err(|e| anyhow!("Failed to create feature state file: {}", e))?;
         let writer = BufWriter::new(file);
-        serde_json::to_writer_pretty(writer, self)
-            .map_err(|e| anyhow!("Failed to write feature state: {}", e))?;
+        match format {
+            StateFormat::Json => serde_json::to_writer_pretty(writer, self)
+                .map_err(|e| anyhow!("Failed to write feature state: {}", e))?,
+            StateFormat::Binary => {
+                let mut encoder = zstd::Encoder::new(writer, 0)?;
+                rmp_serde::encode::write_named(&mut encoder, self)
+                    .map_err(|e| anyhow!("Failed to write feature state: {}", e))?;
+                encoder.finish()?;
+            }
+        }
         Ok(())
     }
 
-    /// Load feature state from JSON file
+    /// Load feature state from a JSON or binary file (detected from its contents)
     pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
         let file = File::open(path.as_ref())
             .map_err(|e| anyhow!("Failed to open feature state file: {}", e))?;
-        let reader = BufReader::new(file);
-        let state: FeatureState = serde_json::from_reader(reader)
-            .map_err(|e| anyhow!("Failed to parse feature state: {}", e))?;
+        let mut reader = BufReader::new(file);
+        let is_binary = reader.fill_buf()?.starts_with(&ZSTD_MAGIC);
+        let state: FeatureState = if is_binary {
+            rmp_serde::from_read(zstd::Decoder::with_buffer(reader)?)
+                .map_err(|e| anyhow!("Failed to parse binary feature state: {}", e))?
+        } else {
+            serde_json::from_reader(reader)
+                .map_err(|e| anyhow!("Failed to parse feature state: {}", e))?
+        };
+        if state.version > FEATURE_STATE_VERSION {
+            return Err(anyhow!(
+                "Feature state version {} is newer than the supported version {}; upgrade mlprep",
+                state.version,
+                FEATURE_STATE_VERSION
+            ));
+        }
         Ok(state)
     }
 
@@ -141,16 +496,60 @@ impl FeatureState {
             (FeatureStateEntry::Count { column: c, .. }, FeatureTransform::CountEncode) => {
                 c == column
             }
+            (FeatureStateEntry::Interactions { column: c, .. }, FeatureTransform::Interactions) => {
+                c == column
+            }
+            (FeatureStateEntry::Pca { column: c, .. }, FeatureTransform::Pca) => c == column,
+            (FeatureStateEntry::Cluster { column: c, .. }, FeatureTransform::ClusterEncode) => {
+                c == column
+            }
             _ => false,
         })
     }
 }
 
+/// Columns kept and dropped by a `feature_select` step
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+pub struct FeatureSelection {
+    pub kept: Vec<String>,
+    pub dropped: Vec<DroppedFeature>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct DroppedFeature {
+    pub column: String,
+    pub reason: String,
+}
+
+impl FeatureSelection {
+    /// Save the selection to a JSON file
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let file = File::create(path.as_ref())
+            .map_err(|e| anyhow!("Failed to create feature selection file: {}", e))?;
+        serde_json::to_writer_pretty(BufWriter::new(file), self)
+            .map_err(|e| anyhow!("Failed to write feature selection: {}", e))?;
+        Ok(())
+    }
+
+    /// Load a selection from a JSON file
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let file = File::open(path.as_ref())
+            .map_err(|e| anyhow!("Failed to open feature selection file: {}", e))?;
+        serde_json::from_reader(BufReader::new(file))
+            .map_err(|e| anyhow!("Failed to parse feature selection: {}", e))
+    }
+}
+
+fn is_numeric_dtype_name(name: &str) -> bool {
+    matches!(
+        name,
+        "i8" | "i16" | "i32" | "i64" | "i128" | "u8" | "u16" | "u32" | "u64" | "f32" | "f64"
+    )
+}
+
 /// Fit MinMax scaler on a column
 pub fn fit_minmax(df: &DataFrame, column: &str) -> Result<MinMaxStats> {
-    let col = df
-        .column(column)
-        .map_err(|e| anyhow!("Column '{}' not found: {}", column, e))?;
+    let col = find_column(df, column)?;
 
     let float_col = col
         .cast(&DataType::Float64)
@@ -199,9 +598,7 @@ pub fn transform_minmax(
 
 /// Fit Standard scaler on a column
 pub fn fit_standard(df: &DataFrame, column: &str) -> Result<StandardStats> {
-    let col = df
-        .column(column)
-        .map_err(|e| anyhow!("Column '{}' not found: {}", column, e))?;
+    let col = find_column(df, column)?;
 
     let float_col = col
         .cast(&DataType::Float64)
@@ -248,9 +645,7 @@ pub fn transform_standard(
 
 /// Fit OneHot encoder on a column
 pub fn fit_onehot(df: &DataFrame, column: &str) -> Result<OneHotVocab> {
-    let col = df
-        .column(column)
-        .map_err(|e| anyhow!("Column '{}' not found: {}", column, e))?;
+    let col = find_column(df, column)?;
 
     let str_col = col
         .str()
@@ -267,48 +662,67 @@ pub fn fit_onehot(df: &DataFrame, column: &str) -> Result<OneHotVocab> {
     Ok(OneHotVocab { categories })
 }
 
-/// Transform column using OneHot encoding
+/// Transform column using OneHot encoding: one `i32` column per fitted category.
+/// Nulls and categories outside the vocabulary are 0 in every column.
 pub fn transform_onehot(
     df: &DataFrame,
     column: &str,
     vocab: &OneHotVocab,
     _alias: Option<&str>,
 ) -> Result<DataFrame> {
-    let mut result = df.clone();
-
-    let col = df
-        .column(column)
-        .map_err(|e| anyhow!("Column '{}' not found: {}", column, e))?;
-
-    let str_col = col
+    let col_ref = find_column(df, column)?;
+    col_ref
         .str()
         .map_err(|e| anyhow!("Column '{}' is not a string type: {}", column, e))?;
 
-    for category in &vocab.categories {
-        let col_name = format!("{}_{}", column, category);
-        let mut values: Vec<i32> = Vec::with_capacity(str_col.len());
-
-        for opt_val in str_col.into_iter() {
-            match opt_val {
-                Some(val) if val == category => values.push(1),
-                _ => values.push(0),
+    // Map each value to its vocabulary index (null when unseen) in one pass, then let
+    // `to_dummies` scatter the ones: dummy columns are named by index, so neither nulls
+    // nor unseen values can collide with a category.
+    let categories = Series::new(PlSmallStr::EMPTY, vocab.categories.as_slice());
+    let indices = Series::new(
+        PlSmallStr::EMPTY,
+        (0..vocab.categories.len() as u32).collect::<Vec<_>>(),
+    );
+    let codes = df
+        .clone()
+        .lazy()
+        .select([col(column)
+            .replace_strict(
+                lit(categories),
+                lit(indices),
+                Some(lit(LiteralValue::Null).cast(DataType::UInt32)),
+                Some(DataType::UInt32),
+            )
+            .alias("code")])
+        .collect()
+        .map_err(|e| anyhow!("Failed to encode '{}': {}", column, e))?;
+    let dummies = codes
+        .column("code")
+        .and_then(|c| c.as_materialized_series().to_dummies(None, false))
+        .map_err(|e| anyhow!("Failed to one-hot encode '{}': {}", column, e))?;
+
+    let columns = vocab
+        .categories
+        .iter()
+        .enumerate()
+        .map(|(i, category)| {
+            let name = PlSmallStr::from(format!("{}_{}", column, category));
+            match dummies.column(&format!("code_{}", i)) {
+                Ok(dummy) => dummy
+                    .cast(&DataType::Int32)
+                    .map(|c| c.with_name(name))
+                    .map_err(|e| anyhow!("Failed to one-hot encode '{}': {}", column, e)),
+                Err(_) => Ok(Int32Chunked::full(name, 0, df.height()).into_column()),
             }
-        }
-
-        let series = Series::new(col_name.into(), values);
-        result = result
-            .hstack(&[series.into()])
-            .map_err(|e| anyhow!("Failed to add one-hot column: {}", e))?;
-    }
-
-    Ok(result)
+        })
+        .collect::<Result<Vec<_>>>()?;
+    df.hstack(&columns)
+        .map_err(|e| anyhow!("Failed to add one-hot columns: {}", e))
 }
 
 /// Fit Count encoder on a column
 pub fn fit_count(df: &DataFrame, column: &str) -> Result<CountStats> {
-    let col = df
-        .column(column)
-        .map_err(|e| anyhow!("Column '{}' not found: {}", column, e))?;
+    let col = find_column(df, column)?;
 
     let str_col = col
         .str()
@@ -332,9 +746,7 @@ pub fn transform_count(
     stats: &CountStats,
     alias: Option<&str>,
 ) -> Result<DataFrame> {
-    let col = df
-        .column(column)
-        .map_err(|e| anyhow!("Column '{}' not found: {}", column, e))?;
+    let col = find_column(df, column)?;
 
     let str_col = col
         .str()
@@ -370,8 +782,357 @@ pub fn transform_count(
     Ok(result)
 }
 
+/// Fix the pairwise products (and optional squares) of `spec.inputs`, checking they are numeric.
+pub fn fit_interactions(schema: &Schema, spec: &FeatureSpec) -> Result<InteractionTerms> {
+    if spec.inputs.is_empty() || (spec.inputs.len() < 2 && !spec.include_squares) {
+        return Err(anyhow!(
+            "Interactions '{}' needs at least two inputs (or include_squares)",
+            spec.column
+        ));
+    }
+    for input in &spec.inputs {
+        let dtype = schema.get(input).ok_or_else(|| {
+            anyhow!(column_not_found(
+                input,
+                schema.iter_names().map(|n| n.as_str())
+            ))
+        })?;
+        if !dtype.is_primitive_numeric() {
+            return Err(anyhow!(
+                "Interaction input '{}' must be numeric, found {}",
+                input,
+                dtype
+            ));
+        }
+    }
+
+    let prefix = spec
+        .alias
+        .as_ref()
+        .map(|a| format!("{}_", a))
+        .unwrap_or_default();
+    let mut terms = Vec::new();
+    for (i, left) in spec.inputs.iter().enumerate() {
+        if spec.include_squares {
+            terms.push(InteractionTerm {
+                left: left.clone(),
+                right: left.clone(),
+                name: format!("{}{}_sq", prefix, left),
+            });
+        }
+        for right in &spec.inputs[i + 1..] {
+            terms.push(InteractionTerm {
+                left: left.clone(),
+                right: right.clone(),
+                name: format!("{}{}_x_{}", prefix, left, right),
+            });
+        }
+    }
+    Ok(InteractionTerms { terms })
+}
+
+/// Collect `inputs` as a Float64 row-major matrix, skipping rows with any null.
+fn collect_input_matrix(
+    lf: LazyFrame,
+    inputs: &[String],
+    streaming: bool,
+) -> Result<nalgebra::DMatrix<f64>> {
+    let df = lf
+        .with_streaming(streaming)
+        .select(
+            inputs
+                .iter()
+                .map(|c| col(c.as_str()).cast(DataType::Float64))
+                .collect::<Vec<_>>(),
+        )
+        .drop_nulls(None)
+        .collect()
+        .map_err(|e| anyhow!("Failed to collect inputs {:?}: {}", inputs, e))?;
+
+    let mut data = nalgebra::DMatrix::<f64>::zeros(df.height(), inputs.len());
+    for (j, name) in inputs.iter().enumerate() {
+        for (i, value) in df.column(name)?.f64()?.into_no_null_iter().enumerate() {
+            data[(i, j)] = value;
+        }
+    }
+    Ok(data)
+}
+
+/// Fit PCA on `spec.inputs` (rows with any null input are skipped).
+pub fn fit_pca(lf: LazyFrame, spec: &FeatureSpec, streaming: bool) -> Result<PcaStats> {
+    let n_features = spec.inputs.len();
+    if n_features == 0 {
+        return Err(anyhow!("PCA '{}' requires at least one input", spec.column));
+    }
+    let n_components = spec.n_components.unwrap_or(n_features);
+    if n_components == 0 || n_components > n_features {
+        return Err(anyhow!(
+            "PCA '{}': n_components must be between 1 and {}",
+            spec.column,
+            n_features
+        ));
+    }
+
+    let mut data = collect_input_matrix(lf, &spec.inputs, streaming)?;
+    let n_rows = data.nrows();
+    if n_rows < 2 {
+        return Err(anyhow!(
+            "PCA '{}' needs at least two complete rows",
+            spec.column
+        ));
+    }
+
+    let means: Vec<f64> = (0..n_features).map(|j| data.column(j).mean()).collect();
+    for (j, mean) in means.iter().enumerate() {
+        data.column_mut(j).add_scalar_mut(-mean);
+    }
+    let covariance = (data.transpose() * &data) / (n_rows as f64 - 1.0);
+    let eigen = nalgebra::SymmetricEigen::new(covariance);
+
+    let mut order: Vec<usize> = (0..n_features).collect();
+    order.sort_by(|&a, &b| eigen.eigenvalues[b].total_cmp(&eigen.eigenvalues[a]));
+    let total_variance: f64 = eigen.eigenvalues.iter().map(|v| v.max(0.0)).sum();
+
+    let mut components = Vec::with_capacity(n_components);
+    let mut explained_variance = Vec::with_capacity(n_components);
+    for &idx in order.iter().take(n_components) {
+        let mut vector: Vec<f64> = eigen.eigenvectors.column(idx).iter().copied().collect();
+        // Eigenvector signs are arbitrary; make the largest loading positive for stable output.
+        let pivot = vector
+            .iter()
+            .copied()
+            .fold(0.0f64, |acc, v| if v.abs() > acc.abs() { v } else { acc });
+        if pivot < 0.0 {
+            vector.iter_mut().for_each(|v| *v = -*v);
+        }
+        components.push(vector);
+        explained_variance.push(eigen.eigenvalues[idx].max(0.0));
+    }
+    let explained_variance_ratio = explained_variance
+        .iter()
+        .map(|v| {
+            if total_variance > 0.0 {
+                v / total_variance
+            } else {
+                0.0
+            }
+        })
+        .collect();
+
+    Ok(PcaStats {
+        inputs: spec.inputs.clone(),
+        means,
+        components,
+        explained_variance,
+        explained_variance_ratio,
+    })
+}
+
+const KMEANS_MAX_ITER: usize = 100;
+
+/// Fit k-means (k-means++ initialisation, Lloyd iterations) on `spec.inputs`.
+///
+/// Rows with any null input are skipped. The same `seed` always yields the same centroids.
+pub fn fit_kmeans(lf: LazyFrame, spec: &FeatureSpec, streaming: bool) -> Result<KMeansStats> {
+    if spec.inputs.is_empty() {
+        return Err(anyhow!(
+            "cluster_encode '{}' requires at least one input",
+            spec.column
+        ));
+    }
+    let k = spec
+        .k
+        .filter(|k| *k > 0)
+        .ok_or_else(|| anyhow!("cluster_encode '{}' requires k >= 1", spec.column))?;
+
+    let data = collect_input_matrix(lf, &spec.inputs, streaming)?;
+    let n_rows = data.nrows();
+    if n_rows < k {
+        return Err(anyhow!(
+            "cluster_encode '{}': k = {} exceeds the {} complete rows",
+            spec.column,
+            k,
+            n_rows
+        ));
+    }
+    let rows: Vec<Vec<f64>> = data
+        .row_iter()
+        .map(|r| r.iter().copied().collect())
+        .collect();
+    let sq_dist =
+        |a: &[f64], b: &[f64]| -> f64 { a.iter().zip(b).map(|(x, y)| (x - y) * (x - y)).sum() };
+
+    // xorshift64*: small deterministic generator so state is reproducible without extra deps
+    let mut rng_state = spec.seed.unwrap_or(0) ^ 0x9E37_79B9_7F4A_7C15;
+    let mut next_unit = || {
+        rng_state ^= rng_state >> 12;
+        rng_state ^= rng_state << 25;
+        rng_state ^= rng_state >> 27;
+        (rng_state.wrapping_mul(0x2545_F491_4F6C_DD1D) >> 11) as f64 / (1u64 << 53) as f64
+    };
+
+    // k-means++ seeding
+    let mut centroids = vec![rows[(next_unit() * n_rows as f64) as usize % n_rows].clone()];
+    let mut nearest: Vec<f64> = rows.iter().map(|r| sq_dist(r, &centroids[0])).collect();
+    while centroids.len() < k {
+        let total: f64 = nearest.iter().sum();
+        let idx = if total > 0.0 {
+            let mut target = next_unit() * total;
+            nearest
+                .iter()
+                .position(|d| {
+                    target -= d;
+                    target <= 0.0
+                })
+                .unwrap_or(n_rows - 1)
+        } else {
+            // All remaining points coincide with a centroid; fall back to the next row
+            centroids.len()
+        };
+        let centroid = rows[idx].clone();
+        for (d, row) in nearest.iter_mut().zip(&rows) {
+            *d = d.min(sq_dist(row, &centroid));
+        }
+        centroids.push(centroid);
+    }
+
+    let assign = |centroids: &[Vec<f64>], row: &[f64]| -> (usize, f64) {
+        centroids
+            .iter()
+            .enumerate()
+            .map(|(i, c)| (i, sq_dist(row, c)))
+            .fold(
+                (0, f64::INFINITY),
+                |best, cur| if cur.1 < best.1 { cur } else { best },
+            )
+    };
+
+    let mut labels = vec![usize::MAX; n_rows];
+    for _ in 0..KMEANS_MAX_ITER {
+        let mut changed = false;
+        for (label, row) in labels.iter_mut().zip(&rows) {
+            let (best, _) = assign(&centroids, row);
+            if *label != best {
+                *label = best;
+                changed = true;
+            }
+        }
+        if !changed {
+            break;
+        }
+        let n_features = spec.inputs.len();
+        let mut sums = vec![vec![0.0; n_features]; k];
+        let mut counts = vec![0usize; k];
+        for (label, row) in labels.iter().zip(&rows) {
+            counts[*label] += 1;
+            for (s, v) in sums[*label].iter_mut().zip(row) {
+                *s += v;
+            }
+        }
+        for ((centroid, sum), count) in centroids.iter_mut().zip(sums).zip(counts) {
+            // Empty clusters keep their previous centroid
+            if count > 0 {
+                *centroid = sum.into_iter().map(|s| s / count as f64).collect();
+            }
+        }
+    }
+
+    let inertia = rows.iter().map(|row| assign(&centroids, row).1).sum();
+    Ok(KMeansStats {
+        inputs: spec.inputs.clone(),
+        centroids,
+        inertia,
+    })
+}
+
+/// Select among `columns`: first drop those with variance below `variance_threshold`,
+/// then walk the rest in order and drop any whose |correlation| with an already kept
+/// column exceeds `correlation_threshold`. Correlations use rows where all survivors are non-null.
+pub fn fit_feature_selection(
+    lf: LazyFrame,
+    columns: &[String],
+    variance_threshold: Option<f64>,
+    correlation_threshold: Option<f64>,
+    streaming: bool,
+) -> Result<FeatureSelection> {
+    let mut selection = FeatureSelection::default();
+
+    let mut survivors = columns.to_vec();
+    if let Some(threshold) = variance_threshold {
+        let variances = lf
+            .clone()
+            .with_streaming(streaming)
+            .select(
+                columns
+                    .iter()
+                    .map(|c| col(c.as_str()).cast(DataType::Float64).var(1))
+                    .collect::<Vec<_>>(),
+            )
+            .collect()
+            .map_err(|e| anyhow!("Failed to compute variances: {}", e))?;
+        survivors.clear();
+        for column in columns {
+            // Columns with fewer than two values have no variance; treat them as constant.
+            let variance = variances.column(column)?.f64()?.get(0).unwrap_or(0.0);
+            if variance < threshold {
+                selection.dropped.push(DroppedFeature {
+                    column: column.clone(),
+                    reason: format!("variance {} below {}", variance, threshold),
+                });
+            } else {
+                survivors.push(column.clone());
+            }
+        }
+    }
+
+    match correlation_threshold {
+        Some(threshold) if survivors.len() > 1 => {
+            let mut data = collect_input_matrix(lf, &survivors, streaming)?;
+            let n_rows = data.nrows();
+            for mut column in data.column_iter_mut() {
+                let mean = column.mean();
+                column.add_scalar_mut(-mean);
+                let norm = column.norm();
+                if norm > 0.0 {
+                    column /= norm;
+                }
+            }
+            let corr = if n_rows > 1 {
+                data.transpose() * &data
+            } else {
+                nalgebra::DMatrix::zeros(survivors.len(), survivors.len())
+            };
+
+            let mut kept_idx: Vec<usize> = Vec::new();
+            for (i, column) in survivors.iter().enumerate() {
+                let correlated = kept_idx
+                    .iter()
+                    .find(|&&j| corr[(i, j)].abs() > threshold)
+                    .copied();
+                match correlated {
+                    Some(j) => selection.dropped.push(DroppedFeature {
+                        column: column.clone(),
+                        reason: format!(
+                            "correlation {:.4} with '{}' above {}",
+                            corr[(i, j)],
+                            survivors[j],
+                            threshold
+                        ),
+                    }),
+                    None => kept_idx.push(i),
+                }
+            }
+            selection.kept = kept_idx.into_iter().map(|i| survivors[i].clone()).collect();
+        }
+        _ => selection.kept = survivors,
+    }
+
+    Ok(selection)
+}
+
 /// Fit all features in config and return combined state
 pub fn fit_features(df: &DataFrame, config: &FeatureConfig) -> Result<FeatureState> {
+    let config = &config.resolve(df.schema())?;
     let mut state = FeatureState::new();
 
     for spec in &config.features {
@@ -404,10 +1165,23 @@ pub fn fit_features(df: &DataFrame, config: &FeatureConfig) -> Result<FeatureSta
                     stats,
                 }
             }
+            FeatureTransform::Interactions => FeatureStateEntry::Interactions {
+                column: spec.column.clone(),
+                terms: fit_interactions(df.schema(), spec)?,
+            },
+            FeatureTransform::Pca => FeatureStateEntry::Pca {
+                column: spec.column.clone(),
+                stats: fit_pca(df.clone().lazy(), spec, false)?,
+            },
+            FeatureTransform::ClusterEncode => FeatureStateEntry::Cluster {
+                column: spec.column.clone(),
+                stats: fit_kmeans(df.clone().lazy(), spec, false)?,
+            },
         };
         state.add_entry(entry);
     }
 
+    state.record_schema(df.schema(), config);
     Ok(state)
 }
 
@@ -417,6 +1191,8 @@ pub fn transform_features(
     config: &FeatureConfig,
     state: &FeatureState,
 ) -> Result<DataFrame> {
+    let config = &config.resolve(df.schema())?;
+    state.check_compatible(df.schema())?;
     let mut result = df.clone();
 
     for spec in &config.features {
@@ -443,6 +1219,13 @@ pub fn transform_features(
             FeatureStateEntry::Count { stats, .. } => {
                 transform_count(&result, &spec.column, stats, spec.alias.as_deref())?
             }
+            FeatureStateEntry::Interactions { .. }
+            | FeatureStateEntry::Pca { .. }
+            | FeatureStateEntry::Cluster { .. } => result
+                .lazy()
+                .with_columns(exprs_from_state(spec, entry)?)
+                .collect()
+                .map_err(|e| anyhow!("Failed to apply {:?} transform: {}", spec.transform, e))?,
         };
     }
 
@@ -455,6 +1238,11 @@ pub fn fit_features_lazy(
     config: &FeatureConfig,
     streaming: bool,
 ) -> Result<FeatureState> {
+    let schema = lf
+        .clone()
+        .collect_schema()
+        .map_err(|e| anyhow!("Failed to resolve schema: {}", e))?;
+    let config = &config.resolve(&schema)?;
     let mut state = FeatureState::new();
 
     // Collect numeric stats together to minimize scans.
@@ -592,9 +1380,7 @@ pub fn fit_features_lazy(
 
                 let mut counts = HashMap::new();
                 let mut total: u64 = 0;
-                for (value_opt, count_opt) in
-                    values_series.into_iter().zip(counts_series.into_iter())
-                {
+                for (value_opt, count_opt) in values_series.into_iter().zip(counts_series) {
                     if let Some(count) = count_opt {
                         total += count as u64;
                         if let Some(value) = value_opt {
@@ -608,9 +1394,114 @@ pub fn fit_features_lazy(
                     stats: CountStats { counts, total },
                 });
             }
+            FeatureTransform::Interactions => {
+                let schema = lf
+                    .clone()
+                    .collect_schema()
+                    .map_err(|e| anyhow!("Failed to resolve schema: {}", e))?;
+                state.add_entry(FeatureStateEntry::Interactions {
+                    column: spec.column.clone(),
+                    terms: fit_interactions(&schema, spec)?,
+                });
+            }
+            FeatureTransform::Pca => {
+                state.add_entry(FeatureStateEntry::Pca {
+                    column: spec.column.clone(),
+                    stats: fit_pca(lf.clone(), spec, streaming)?,
+                });
+            }
+            FeatureTransform::ClusterEncode => {
+                state.add_entry(FeatureStateEntry::Cluster {
+                    column: spec.column.clone(),
+                    stats: fit_kmeans(lf.clone(), spec, streaming)?,
+                });
+            }
         }
     }
 
+    state.record_schema(&schema, config);
+    Ok(state)
+}
+
+/// A state with neutral statistics for every spec of `config`, fitted on no rows, for
+/// resolving the output schema without reading data (`mlprep run --dry-run` without
+/// `--sample`). Scalers get an identity range, `one_hot_encode` has no categories and so
+/// adds no columns, and `pca` and `cluster_encode` get zero projections and centroids.
+pub fn unfitted_features(schema: &Schema, config: &FeatureConfig) -> Result<FeatureState> {
+    let config = &config.resolve(schema)?;
+    let mut state = FeatureState::new();
+    for spec in &config.features {
+        let column = spec.column.clone();
+        let n_inputs = spec.inputs.len();
+        let entry = match spec.transform {
+            FeatureTransform::MinMaxScale => FeatureStateEntry::MinMax {
+                column,
+                stats: MinMaxStats { min: 0.0, max: 1.0 },
+            },
+            FeatureTransform::StandardScale => FeatureStateEntry::Standard {
+                column,
+                stats: StandardStats {
+                    mean: 0.0,
+                    std: 1.0,
+                },
+            },
+            FeatureTransform::OneHotEncode => FeatureStateEntry::OneHot {
+                column,
+                vocab: OneHotVocab {
+                    categories: Vec::new(),
+                },
+            },
+            FeatureTransform::CountEncode => FeatureStateEntry::Count {
+                column,
+                stats: CountStats {
+                    counts: HashMap::new(),
+                    total: 0,
+                },
+            },
+            FeatureTransform::Interactions => FeatureStateEntry::Interactions {
+                column,
+                terms: fit_interactions(schema, spec)?,
+            },
+            FeatureTransform::Pca => {
+                let n_components = spec.n_components.unwrap_or(n_inputs);
+                if n_inputs == 0 || n_components == 0 || n_components > n_inputs {
+                    return Err(anyhow!(
+                        "PCA '{}': n_components must be between 1 and {}",
+                        spec.column,
+                        n_inputs
+                    ));
+                }
+                FeatureStateEntry::Pca {
+                    column,
+                    stats: PcaStats {
+                        inputs: spec.inputs.clone(),
+                        means: vec![0.0; n_inputs],
+                        components: (0..n_components)
+                            .map(|i| (0..n_inputs).map(|j| f64::from(i == j)).collect())
+                            .collect(),
+                        explained_variance: vec![0.0; n_components],
+                        explained_variance_ratio: vec![0.0; n_components],
+                    },
+                }
+            }
+            FeatureTransform::ClusterEncode => {
+                let k = spec
+                    .k
+                    .filter(|k| *k > 0)
+                    .ok_or_else(|| anyhow!("cluster_encode '{}' requires k >= 1", spec.column))?;
+                FeatureStateEntry::Cluster {
+                    column,
+                    stats: KMeansStats {
+                        inputs: spec.inputs.clone(),
+                        centroids: vec![vec![0.0; n_inputs]; k],
+                        inertia: 0.0,
+                    },
+                }
+            }
+        };
+        state.add_entry(entry);
+    }
+    state.record_schema(schema, config);
     Ok(state)
 }
 
@@ -657,20 +1548,112 @@ pub fn exprs_from_state(spec: &FeatureSpec, entry: &FeatureStateEntry) -> Result
         }
         (FeatureTransform::CountEncode, FeatureStateEntry::Count { stats, .. }) => {
             let output_name = spec.alias.clone().unwrap_or_else(|| spec.column.clone());
-            let base = col(&spec.column).cast(DataType::String);
-            let mut expr = lit(0.0);
-            for (value, count) in &stats.counts {
-                let freq = if stats.total == 0 {
-                    0.0
-                } else {
-                    *count as f64 / stats.total as f64
-                };
-                expr = when(base.clone().eq(lit(value.as_str())))
-                    .then(lit(freq))
-                    .otherwise(expr);
-            }
+            // One hash lookup rather than a when/otherwise branch per category, so plan
+            // size stays constant for high-cardinality columns.
+            let (values, freqs): (Vec<&str>, Vec<f64>) = stats
+                .counts
+                .iter()
+                .map(|(value, count)| {
+                    let freq = if stats.total == 0 {
+                        0.0
+                    } else {
+                        *count as f64 / stats.total as f64
+                    };
+                    (value.as_str(), freq)
+                })
+                .unzip();
+            let expr = col(&spec.column)
+                .cast(DataType::String)
+                .replace_strict(
+                    lit(Series::new(PlSmallStr::EMPTY, values)),
+                    lit(Series::new(PlSmallStr::EMPTY, freqs)),
+                    Some(lit(0.0)),
+                    Some(DataType::Float64),
+                )
+                .fill_null(lit(0.0));
             Ok(vec![expr.alias(output_name)])
         }
+        (FeatureTransform::Interactions, FeatureStateEntry::Interactions { terms, .. }) => {
+            Ok(terms
+                .terms
+                .iter()
+                .map(|t| {
+                    (col(t.left.as_str()).cast(DataType::Float64)
+                        * col(t.right.as_str()).cast(DataType::Float64))
+                    .alias(t.name.as_str())
+                })
+                .collect())
+        }
+        (FeatureTransform::Pca, FeatureStateEntry::Pca { stats, .. }) => {
+            let prefix = spec.alias.as_deref().unwrap_or(&spec.column);
+            Ok(stats
+                .components
+                .iter()
+                .enumerate()
+                .map(|(i, component)| {
+                    stats
+                        .inputs
+                        .iter()
+                        .zip(&stats.means)
+                        .zip(component)
+                        .map(|((input, mean), weight)| {
+                            (col(input.as_str()).cast(DataType::Float64) - lit(*mean))
+                                * lit(*weight)
+                        })
+                        .reduce(|acc, term| acc + term)
+                        .unwrap_or_else(|| lit(0.0))
+                        .alias(format!("{}_pc{}", prefix, i + 1))
+                })
+                .collect())
+        }
+        (FeatureTransform::ClusterEncode, FeatureStateEntry::Cluster { stats, .. }) => {
+            let prefix = spec.alias.as_deref().unwrap_or(&spec.column);
+            let sq_dists: Vec<Expr> = stats
+                .centroids
+                .iter()
+                .map(|centroid| {
+                    stats
+                        .inputs
+                        .iter()
+                        .zip(centroid)
+                        .map(|(input, c)| {
+                            let diff = col(input.as_str()).cast(DataType::Float64) - lit(*c);
+                            diff.clone() * diff
+                        })
+                        .reduce(|acc, term| acc + term)
+                        .unwrap_or_else(|| lit(0.0))
+                })
+                .collect();
+            // Centroid i is nearest when no other centroid is closer; walking the chain in
+            // id order resolves ties to the lowest id, matching fit-time assignment.
+            let is_nearest: Vec<Expr> = sq_dists
+                .iter()
+                .enumerate()
+                .map(|(i, d)| {
+                    sq_dists
+                        .iter()
+                        .enumerate()
+                        .filter(|(j, _)| *j != i)
+                        .fold(d.clone().is_not_null(), |acc, (_, other)| {
+                            acc.and(d.clone().lt_eq(other.clone()))
+                        })
+                })
+                .collect();
+            let pick = |value: &dyn Fn(usize) -> Expr, dtype: DataType| {
+                is_nearest
+                    .iter()
+                    .enumerate()
+                    .rev()
+                    .fold(lit(NULL).cast(dtype), |acc, (i, cond)| {
+                        when(cond.clone()).then(value(i)).otherwise(acc)
+                    })
+            };
+            Ok(vec![
+                pick(&|i| lit(i as u32), DataType::UInt32).alias(format!("{}_cluster", prefix)),
+                pick(&|i| sq_dists[i].clone().sqrt(), DataType::Float64)
+                    .alias(format!("{}_distance", prefix)),
+            ])
+        }
         _ => Err(anyhow!(
             "State {:?} does not match requested transform {:?}",
             entry,
@@ -828,6 +1811,43 @@ mod tests {
         assert_eq!(cat_col.get(2), Some(0)); // "bird"
     }
 
+    #[test]
+    fn test_transform_onehot_restricted_to_vocab() {
+        let df = df! {
+            "category" => &[Some("cat"), None, Some("fish"), Some("null"), Some("cat")]
+        }
+        .unwrap();
+        let vocab = OneHotVocab {
+            categories: vec!["cat".to_string(), "dog".to_string(), "null".to_string()],
+        };
+        let result = transform_onehot(&df, "category", &vocab, None).unwrap();
+
+        // Only fitted categories get a column, in vocabulary order.
+        let names: Vec<&str> = result
+            .get_column_names()
+            .into_iter()
+            .map(|n| n.as_str())
+            .collect();
+        assert_eq!(
+            names,
+            ["category", "category_cat", "category_dog", "category_null"]
+        );
+        let values = |name: &str| -> Vec<i32> {
+            result
+                .column(name)
+                .unwrap()
+                .i32()
+                .unwrap()
+                .into_no_null_iter()
+                .collect()
+        };
+        assert_eq!(values("category_cat"), [1, 0, 0, 0, 1]);
+        // Absent from the data: all zeros.
+        assert_eq!(values("category_dog"), [0, 0, 0, 0, 0]);
+        // The "null" category is not confused with null values.
+        assert_eq!(values("category_null"), [0, 0, 0, 1, 0]);
+    }
+
     // ============================================================================
     // Count Encoder Tests
     // ============================================================================
@@ -885,6 +1905,49 @@ mod tests {
         assert!((encoded.get(1).unwrap() - 0.0).abs() < 1e-10); // unknown = 0
     }
 
+    #[test]
+    fn test_count_exprs_match_eager_transform() {
+        // High cardinality: 5000 fitted categories, plus nulls and unseen values.
+        let values: Vec<Option<String>> = (0..20_000)
+            .map(|i| match i % 7 {
+                0 => None,
+                1 => Some(format!("unseen_{}", i)),
+                _ => Some(format!("v{}", i % 5000)),
+            })
+            .collect();
+        let df = DataFrame::new(vec![Column::new("category".into(), &values)]).unwrap();
+        let mut counts = HashMap::new();
+        for k in 0..5000u64 {
+            counts.insert(format!("v{}", k), k + 1);
+        }
+        let stats = CountStats {
+            counts,
+            total: 5000 * 5001 / 2,
+        };
+        let spec = FeatureSpec::new("category", FeatureTransform::CountEncode);
+        let entry = FeatureStateEntry::Count {
+            column: "category".to_string(),
+            stats: stats.clone(),
+        };
+
+        let eager = transform_count(&df, "category", &stats, None).unwrap();
+        let exprs = exprs_from_state(&spec, &entry).unwrap();
+        let lazy = df.clone().lazy().with_columns(exprs).collect().unwrap();
+        assert!(lazy.equals_missing(&eager));
+
+        let empty = FeatureStateEntry::Count {
+            column: "category".to_string(),
+            stats: CountStats {
+                counts: HashMap::new(),
+                total: 0,
+            },
+        };
+        let exprs = exprs_from_state(&spec, &empty).unwrap();
+        let lazy = df.lazy().with_columns(exprs).collect().unwrap();
+        let encoded = lazy.column("category").unwrap().f64().unwrap();
+        assert!(encoded.into_iter().all(|v| v == Some(0.0)));
+    }
+
     // ============================================================================
     // Feature State Persistence Tests
     // ============================================================================
@@ -916,6 +1979,41 @@ mod tests {
         assert_eq!(state, loaded);
     }
 
+    #[test]
+    fn test_feature_state_binary_round_trip() {
+        let dir = tempdir().unwrap();
+        let counts: HashMap<String, u64> = (0..5_000).map(|i| (format!("cat_{i}"), i)).collect();
+        let mut state = FeatureState::new();
+        state.add_entry(FeatureStateEntry::Count {
+            column: "category".to_string(),
+            stats: CountStats {
+                counts,
+                total: 5_000,
+            },
+        });
+        state.add_entry(FeatureStateEntry::OneHot {
+            column: "city".to_string(),
+            vocab: OneHotVocab {
+                categories: vec!["a".to_string(), "b".to_string()],
+            },
+        });
+
+        let json_path = dir.path().join("state.json");
+        let bin_path = dir.path().join("state.bin");
+        state.save(&json_path).unwrap();
+        state.save(&bin_path).unwrap();
+
+        let json_size = std::fs::metadata(&json_path).unwrap().len();
+        let bin_size = std::fs::metadata(&bin_path).unwrap().len();
+        assert!(bin_size * 4 < json_size, "{bin_size} vs {json_size}");
+
+        // Detection is by content, not extension
+        let renamed = dir.path().join("state.json.bak");
+        std::fs::rename(&bin_path, &renamed).unwrap();
+        assert_eq!(FeatureState::load(&renamed).unwrap(), state);
+        assert_eq!(FeatureState::load(&json_path).unwrap(), state);
+    }
+
     // ============================================================================
     // Fit/Transform Integration Tests
     // ============================================================================
@@ -930,16 +2028,8 @@ mod tests {
 
         let config = FeatureConfig {
             features: vec![
-                FeatureSpec {
-                    column: "value".to_string(),
-                    transform: FeatureTransform::MinMaxScale,
-                    alias: None,
-                },
-                FeatureSpec {
-                    column: "category".to_string(),
-                    transform: FeatureTransform::CountEncode,
-                    alias: None,
-                },
+                FeatureSpec::new("value", FeatureTransform::MinMaxScale),
+                FeatureSpec::new("category", FeatureTransform::CountEncode),
             ],
         };
 
@@ -947,6 +2037,41 @@ mod tests {
         assert_eq!(state.entries.len(), 2);
     }
 
+    #[test]
+    fn test_unfitted_features() {
+        let schema = Schema::from_iter([
+            Field::new("x".into(), DataType::Float64),
+            Field::new("y".into(), DataType::Float64),
+        ]);
+        let multi = |transform| FeatureSpec {
+            inputs: vec!["x".to_string(), "y".to_string()],
+            n_components: Some(1),
+            k: Some(3),
+            ..FeatureSpec::new("xy", transform)
+        };
+        let config = FeatureConfig {
+            features: vec![
+                FeatureSpec::new("x", FeatureTransform::StandardScale),
+                multi(FeatureTransform::Pca),
+                multi(FeatureTransform::ClusterEncode),
+            ],
+        };
+        let state = unfitted_features(&schema, &config).unwrap();
+        assert_eq!(state.entries.len(), 3);
+        let FeatureStateEntry::Pca { stats, .. } = &state.entries[1] else {
+            panic!("expected a PCA entry");
+        };
+        assert_eq!(stats.components, vec![vec![1.0, 0.0]]);
+
+        let bad = FeatureConfig {
+            features: vec![FeatureSpec {
+                k: Some(0),
+                ..multi(FeatureTransform::ClusterEncode)
+            }],
+        };
+        assert!(unfitted_features(&schema, &bad).is_err());
+    }
+
     #[test]
     fn test_transform_features() {
         let train_df = df! {
@@ -962,11 +2087,7 @@ mod tests {
         .unwrap();
 
         let config = FeatureConfig {
-            features: vec![FeatureSpec {
-                column: "value".to_string(),
-                transform: FeatureTransform::MinMaxScale,
-                alias: None,
-            }],
+            features: vec![FeatureSpec::new("value", FeatureTransform::MinMaxScale)],
         };
 
         let state = fit_features(&train_df, &config).unwrap();
@@ -995,15 +2116,10 @@ mod tests {
         let config = FeatureConfig {
             features: vec![
                 FeatureSpec {
-                    column: "age".to_string(),
-                    transform: FeatureTransform::StandardScale,
                     alias: Some("age_scaled".to_string()),
+                    ..FeatureSpec::new("age", FeatureTransform::StandardScale)
                 },
-                FeatureSpec {
-                    column: "city".to_string(),
-                    transform: FeatureTransform::OneHotEncode,
-                    alias: None,
-                },
+                FeatureSpec::new("city", FeatureTransform::OneHotEncode),
             ],
         };
 
@@ -1021,4 +2137,369 @@ mod tests {
         assert!(test_result.column("city_LA").is_ok());
         assert!(test_result.column("city_NYC").is_ok());
     }
+
+    // ============================================================================
+    // Interaction Tests
+    // ============================================================================
+
+    #[test]
+    fn test_interactions_fit_and_transform() {
+        let df = df! {
+            "a" => &[1.0, 2.0],
+            "b" => &[3i64, 4],
+            "c" => &[5.0, 6.0],
+        }
+        .unwrap();
+        let spec = FeatureSpec {
+            inputs: vec!["a".to_string(), "b".to_string(), "c".to_string()],
+            include_squares: true,
+            ..FeatureSpec::new("poly", FeatureTransform::Interactions)
+        };
+        let config = FeatureConfig {
+            features: vec![spec],
+        };
+
+        let state = fit_features(&df, &config).unwrap();
+        let result = transform_features(&df, &config, &state).unwrap();
+
+        let names: Vec<&str> = match &state.entries[0] {
+            FeatureStateEntry::Interactions { terms, .. } => {
+                terms.terms.iter().map(|t| t.name.as_str()).collect()
+            }
+            other => panic!("Unexpected entry {:?}", other),
+        };
+        assert_eq!(
+            names,
+            vec!["a_sq", "a_x_b", "a_x_c", "b_sq", "b_x_c", "c_sq"]
+        );
+        let a_x_b = result.column("a_x_b").unwrap().f64().unwrap();
+        assert_eq!(a_x_b.get(1), Some(8.0));
+        let c_sq = result.column("c_sq").unwrap().f64().unwrap();
+        assert_eq!(c_sq.get(0), Some(25.0));
+    }
+
+    #[test]
+    fn test_interactions_follow_fitted_state() {
+        let train = df! { "a" => &[1.0], "b" => &[2.0] }.unwrap();
+        let spec = FeatureSpec {
+            inputs: vec!["a".to_string(), "b".to_string()],
+            ..FeatureSpec::new("poly", FeatureTransform::Interactions)
+        };
+        let state = fit_features(
+            &train,
+            &FeatureConfig {
+                features: vec![spec],
+            },
+        )
+        .unwrap();
+
+        // At inference the spec's input list is ignored in favour of the persisted terms.
+        let inference_spec = FeatureSpec {
+            inputs: vec!["a".to_string()],
+            ..FeatureSpec::new("poly", FeatureTransform::Interactions)
+        };
+        let entry = state
+            .get_entry("poly", &FeatureTransform::Interactions)
+            .unwrap();
+        let exprs = exprs_from_state(&inference_spec, entry).unwrap();
+        let out = df! { "a" => &[3.0], "b" => &[4.0] }
+            .unwrap()
+            .lazy()
+            .with_columns(exprs)
+            .collect()
+            .unwrap();
+        assert_eq!(
+            out.column("a_x_b").unwrap().f64().unwrap().get(0),
+            Some(12.0)
+        );
+    }
+
+    #[test]
+    fn test_interactions_reject_non_numeric() {
+        let df = df! { "a" => &[1.0], "s" => &["x"] }.unwrap();
+        let spec = FeatureSpec {
+            inputs: vec!["a".to_string(), "s".to_string()],
+            ..FeatureSpec::new("poly", FeatureTransform::Interactions)
+        };
+        assert!(fit_interactions(df.schema(), &spec).is_err());
+    }
+
+    // ============================================================================
+    // PCA Tests
+    // ============================================================================
+
+    #[test]
+    fn test_pca_fit_and_transform() {
+        // y = 2x exactly, so one component explains all the variance.
+        let df = df! {
+            "x" => &[1.0, 2.0, 3.0, 4.0],
+            "y" => &[2.0, 4.0, 6.0, 8.0],
+        }
+        .unwrap();
+        let spec = FeatureSpec {
+            inputs: vec!["x".to_string(), "y".to_string()],
+            n_components: Some(1),
+            ..FeatureSpec::new("emb", FeatureTransform::Pca)
+        };
+        let config = FeatureConfig {
+            features: vec![spec],
+        };
+
+        let state = fit_features(&df, &config).unwrap();
+        let FeatureStateEntry::Pca { stats, .. } = &state.entries[0] else {
+            panic!("Expected PCA entry");
+        };
+        assert_eq!(stats.means, vec![2.5, 5.0]);
+        assert_eq!(stats.components.len(), 1);
+        assert!((stats.explained_variance_ratio[0] - 1.0).abs() < 1e-9);
+        let norm = 5f64.sqrt();
+        assert!((stats.components[0][0] - 1.0 / norm).abs() < 1e-9);
+        assert!((stats.components[0][1] - 2.0 / norm).abs() < 1e-9);
+
+        let result = transform_features(&df, &config, &state).unwrap();
+        let pc1 = result.column("emb_pc1").unwrap().f64().unwrap();
+        // Row 0 is (-1.5, -3) from the mean: projection = -1.5 * sqrt(5)
+        assert!((pc1.get(0).unwrap() + 1.5 * norm).abs() < 1e-9);
+        assert!(result.column("emb_pc2").is_err());
+    }
+
+    #[test]
+    fn test_pca_rejects_too_many_components() {
+        let df = df! { "x" => &[1.0, 2.0], "y" => &[3.0, 1.0] }.unwrap();
+        let spec = FeatureSpec {
+            inputs: vec!["x".to_string(), "y".to_string()],
+            n_components: Some(3),
+            ..FeatureSpec::new("emb", FeatureTransform::Pca)
+        };
+        assert!(fit_pca(df.lazy(), &spec, false).is_err());
+    }
+
+    // ============================================================================
+    // Cluster Encode Tests
+    // ============================================================================
+
+    fn two_blobs() -> DataFrame {
+        df! {
+            "x" => &[0.0, 0.1, -0.1, 10.0, 10.1, 9.9],
+            "y" => &[0.0, -0.1, 0.1, 10.0, 9.9, 10.1],
+        }
+        .unwrap()
+    }
+
+    #[test]
+    fn test_cluster_encode_fit_and_transform() {
+        let df = two_blobs();
+        let spec = FeatureSpec {
+            inputs: vec!["x".to_string(), "y".to_string()],
+            k: Some(2),
+            seed: Some(7),
+            ..FeatureSpec::new("blob", FeatureTransform::ClusterEncode)
+        };
+        let config = FeatureConfig {
+            features: vec![spec],
+        };
+
+        let state = fit_features(&df, &config).unwrap();
+        let FeatureStateEntry::Cluster { stats, .. } = &state.entries[0] else {
+            panic!("Expected cluster entry");
+        };
+        assert_eq!(stats.centroids.len(), 2);
+        let mut centroids = stats.centroids.clone();
+        centroids.sort_by(|a, b| a[0].total_cmp(&b[0]));
+        assert!(centroids[0][0].abs() < 1e-9 && centroids[0][1].abs() < 1e-9);
+        assert!((centroids[1][0] - 10.0).abs() < 1e-9);
+
+        let result = transform_features(&df, &config, &state).unwrap();
+        let cluster = result.column("blob_cluster").unwrap().u32().unwrap();
+        assert_eq!(cluster.get(0), cluster.get(2));
+        assert_eq!(cluster.get(3), cluster.get(5));
+        assert_ne!(cluster.get(0), cluster.get(3));
+        let distance = result.column("blob_distance").unwrap().f64().unwrap();
+        assert!(distance.get(0).unwrap().abs() < 1e-9);
+
+        // Same seed, same centroids
+        let refit = fit_features_lazy(df.lazy(), &config, false).unwrap();
+        assert_eq!(refit, state);
+    }
+
+    #[test]
+    fn test_cluster_encode_requires_k() {
+        let spec = FeatureSpec {
+            inputs: vec!["x".to_string()],
+            ..FeatureSpec::new("blob", FeatureTransform::ClusterEncode)
+        };
+        assert!(fit_kmeans(two_blobs().lazy(), &spec, false).is_err());
+
+        let spec = FeatureSpec {
+            k: Some(10),
+            ..spec
+        };
+        assert!(fit_kmeans(two_blobs().lazy(), &spec, false).is_err());
+    }
+
+    // ============================================================================
+    // Feature Selection Tests
+    // ============================================================================
+
+    #[test]
+    fn test_feature_selection_variance_and_correlation() {
+        let df = df! {
+            "constant" => &[1.0, 1.0, 1.0, 1.0],
+            "a" => &[1.0, 2.0, 3.0, 4.0],
+            "a_twice" => &[2.0, 4.0, 6.0, 8.0],
+            "b" => &[1.0, -1.0, -1.0, 1.0],
+        }
+        .unwrap();
+        let columns: Vec<String> = ["constant", "a", "a_twice", "b"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+
+        let selection =
+            fit_feature_selection(df.lazy(), &columns, Some(1e-8), Some(0.95), false).unwrap();
+        assert_eq!(selection.kept, vec!["a", "b"]);
+        let dropped: Vec<&str> = selection
+            .dropped
+            .iter()
+            .map(|d| d.column.as_str())
+            .collect();
+        assert_eq!(dropped, vec!["constant", "a_twice"]);
+        assert!(selection.dropped[1].reason.contains("'a'"));
+
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("selection.json");
+        selection.save(&path).unwrap();
+        assert_eq!(FeatureSelection::load(&path).unwrap(), selection);
+    }
+
+    // ============================================================================
+    // Multi-column Spec Tests
+    // ============================================================================
+
+    #[test]
+    fn test_columns_and_regex_expand_per_column() {
+        let df = df! {
+            "num_a" => &[1.0, 2.0, 3.0],
+            "num_b" => &[10, 20, 30],
+            "num_label" => &["x", "y", "z"],
+            "other" => &[5.0, 6.0, 7.0],
+        }
+        .unwrap();
+        let config = FeatureConfig {
+            features: vec![
+                FeatureSpec {
+                    columns_regex: Some("^num_".to_string()),
+                    ..FeatureSpec::new("", FeatureTransform::StandardScale)
+                },
+                FeatureSpec {
+                    columns: vec!["other".to_string()],
+                    ..FeatureSpec::new("", FeatureTransform::MinMaxScale)
+                },
+            ],
+        };
+
+        let resolved = config.resolve(df.schema()).unwrap();
+        let columns: Vec<&str> = resolved
+            .features
+            .iter()
+            .map(|s| s.column.as_str())
+            .collect();
+        // num_label is skipped: scalers only match numeric columns
+        assert_eq!(columns, vec!["num_a", "num_b", "other"]);
+
+        let state = fit_features(&df, &config).unwrap();
+        assert_eq!(state.entries.len(), 3);
+        assert!(state
+            .get_entry("num_b", &FeatureTransform::StandardScale)
+            .is_some());
+
+        let result = transform_features(&df, &config, &state).unwrap();
+        let scaled = result.column("other").unwrap().f64().unwrap();
+        assert_eq!(scaled.get(2), Some(1.0));
+
+        let lazy_state = fit_features_lazy(df.lazy(), &config, false).unwrap();
+        assert_eq!(lazy_state.entries.len(), 3);
+    }
+
+    #[test]
+    fn test_resolve_rejects_ambiguous_specs() {
+        let schema = Schema::from_iter([Field::new("a".into(), DataType::Float64)]);
+        let both = FeatureConfig {
+            features: vec![FeatureSpec {
+                columns: vec!["a".to_string()],
+                ..FeatureSpec::new("a", FeatureTransform::MinMaxScale)
+            }],
+        };
+        assert!(both.resolve(&schema).is_err());
+
+        let no_match = FeatureConfig {
+            features: vec![FeatureSpec {
+                columns_regex: Some("^zzz".to_string()),
+                ..FeatureSpec::new("", FeatureTransform::MinMaxScale)
+            }],
+        };
+        assert!(no_match.resolve(&schema).is_err());
+    }
+
+    // ============================================================================
+    // State Versioning Tests
+    // ============================================================================
+
+    #[test]
+    fn test_state_records_schema_and_checks_compatibility() {
+        let df = df! {
+            "price" => &[1i32, 2, 3],
+            "city" => &["a", "b", "a"],
+        }
+        .unwrap();
+        let config = FeatureConfig {
+            features: vec![
+                FeatureSpec::new("price", FeatureTransform::StandardScale),
+                FeatureSpec::new("city", FeatureTransform::CountEncode),
+            ],
+        };
+        let state = fit_features(&df, &config).unwrap();
+        assert_eq!(state.version, FEATURE_STATE_VERSION);
+        assert_eq!(
+            state.schema,
+            vec![
+                FittedColumn {
+                    name: "price".to_string(),
+                    dtype: "i32".to_string()
+                },
+                FittedColumn {
+                    name: "city".to_string(),
+                    dtype: "str".to_string()
+                },
+            ]
+        );
+
+        // Widening a numeric column is fine
+        let widened = df! { "price" => &[1i64], "city" => &["a"] }.unwrap();
+        assert!(transform_features(&widened, &config, &state).is_ok());
+
+        let retyped = df! { "price" => &[1i64], "city" => &[1i64] }.unwrap();
+        let err = state.check_compatible(retyped.schema()).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("column 'city' was fitted as str but is now i64"));
+
+        let missing = df! { "price" => &[1.0] }.unwrap();
+        let err = state.check_compatible(missing.schema()).unwrap_err();
+        assert!(err.to_string().contains("column 'city' is missing"));
+    }
+
+    #[test]
+    fn test_state_version_handling() {
+        let dir = tempdir().unwrap();
+        let legacy = dir.path().join("legacy.json");
+        std::fs::write(&legacy, r#"{"entries": []}"#).unwrap();
+        let state = FeatureState::load(&legacy).unwrap();
+        assert_eq!(state.version, 1);
+        assert!(state.schema.is_empty());
+
+        let future = dir.path().join("future.json");
+        std::fs::write(&future, r#"{"version": 99, "entries": []}"#).unwrap();
+        assert!(FeatureState::load(&future).is_err());
+    }
 }
@@ -0,0 +1,849 @@
+//! Apache Iceberg table inputs. The table metadata selects a snapshot, whose manifest list
+//! and manifests (Avro files) name the data files to read. Files whose column bounds rule
+//! out the pipeline's leading `filter` conditions are skipped without being opened; the
+//! filters still run on the rows of the files that are read.
+
+use crate::dsl::{Iceberg, Input, Manifest, RuntimeConfig, Step};
+use crate::errors::{MlPrepError, MlPrepResult};
+use crate::manifest::ManifestEntry;
+use chrono::{NaiveDate, NaiveDateTime, TimeZone, Utc};
+use polars::io::avro::AvroReader;
+use polars::prelude::*;
+use serde::de::Error;
+use serde::Deserialize;
+use sqlparser::ast::{BinaryOperator, Expr, UnaryOperator, Value};
+use sqlparser::dialect::GenericDialect;
+use sqlparser::parser::Parser;
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+fn invalid(msg: String) -> MlPrepError {
+    MlPrepError::ConfigError(serde_yaml::Error::custom(msg), None)
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+struct TableMetadata {
+    #[serde(default)]
+    current_snapshot_id: Option<i64>,
+    #[serde(default)]
+    snapshots: Vec<Snapshot>,
+    #[serde(default)]
+    snapshot_log: Vec<SnapshotLogEntry>,
+    #[serde(default)]
+    schemas: Vec<TableSchema>,
+    #[serde(default)]
+    current_schema_id: Option<i32>,
+    /// Format version 1 tables may only have this
+    #[serde(default)]
+    schema: Option<TableSchema>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+struct Snapshot {
+    snapshot_id: i64,
+    timestamp_ms: i64,
+    #[serde(default)]
+    manifest_list: Option<String>,
+    /// Format version 1 snapshots may list their manifests inline
+    #[serde(default)]
+    manifests: Vec<String>,
+    #[serde(default)]
+    schema_id: Option<i32>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+struct SnapshotLogEntry {
+    snapshot_id: i64,
+    timestamp_ms: i64,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+struct TableSchema {
+    #[serde(default)]
+    schema_id: i32,
+    fields: Vec<SchemaField>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SchemaField {
+    id: i32,
+    name: String,
+    /// A primitive type name, or an object for nested types
+    #[serde(rename = "type")]
+    ty: serde_json::Value,
+}
+
+/// The manifest settings data files are scanned with: every Iceberg data file handled
+/// here is Parquet.
+pub fn manifest() -> Manifest {
+    Manifest {
+        path_column: "path".to_string(),
+        format: Some("parquet".to_string()),
+        per_file: false,
+        state_path: None,
+    }
+}
+
+/// The `*.metadata.json` file describing the table at `path`: `path` itself, the version
+/// named by `metadata/version-hint.text`, or the highest-numbered metadata file.
+pub fn metadata_path(path: &Path, runtime: &RuntimeConfig) -> MlPrepResult<PathBuf> {
+    if path.to_string_lossy().ends_with(".metadata.json") {
+        return local_copy(path, runtime);
+    }
+    let dir = path.join("metadata");
+    let hint = dir.join("version-hint.text");
+    if crate::input_cache::is_remote(path) || hint.is_file() {
+        let hint = local_copy(&hint, runtime)?;
+        let version = std::fs::read_to_string(&hint).map_err(MlPrepError::IoError)?;
+        return local_copy(
+            &dir.join(format!("v{}.metadata.json", version.trim())),
+            runtime,
+        );
+    }
+    let entries = std::fs::read_dir(&dir).map_err(|e| {
+        invalid(format!(
+            "{} is not an Iceberg table: cannot list {} ({})",
+            path.display(),
+            dir.display(),
+            e
+        ))
+    })?;
+    entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter_map(|file| {
+            let name = file.file_name()?.to_str()?.to_string();
+            let stem = name.strip_suffix(".metadata.json")?;
+            // `v3.metadata.json` or `00003-<uuid>.metadata.json`
+            let digits: String = stem
+                .trim_start_matches('v')
+                .chars()
+                .take_while(char::is_ascii_digit)
+                .collect();
+            Some((digits.parse::<u64>().ok()?, file))
+        })
+        .max_by_key(|(version, _)| *version)
+        .map(|(_, file)| file)
+        .ok_or_else(|| {
+            invalid(format!(
+                "{} is not an Iceberg table: {} has no metadata files",
+                path.display(),
+                dir.display()
+            ))
+        })
+}
+
+/// A local file to read table metadata from: `path`, or a remote file's cached copy.
+fn local_copy(path: &Path, runtime: &RuntimeConfig) -> MlPrepResult<PathBuf> {
+    let local = crate::input_cache::localize(path, runtime)?;
+    if crate::input_cache::is_remote(&local) {
+        return Err(invalid(format!(
+            "The metadata of Iceberg table {} is read through the input cache, which is disabled by --no-cache",
+            path.display()
+        )));
+    }
+    Ok(local)
+}
+
+/// A location recorded in table metadata as a path: `file:` URIs become local paths.
+fn location(uri: &str) -> PathBuf {
+    match uri.strip_prefix("file://") {
+        Some(path) => PathBuf::from(path),
+        None => PathBuf::from(uri.strip_prefix("file:").unwrap_or(uri)),
+    }
+}
+
+/// The data files of `input`'s table at the selected snapshot that may hold rows
+/// passing the leading SQL `filter` steps of `steps`.
+pub fn data_files(
+    input: &Input,
+    iceberg: &Iceberg,
+    steps: &[Step],
+    runtime: &RuntimeConfig,
+) -> MlPrepResult<Vec<ManifestEntry>> {
+    if input.manifest.is_some() {
+        return Err(invalid(format!(
+            "Input {} sets both iceberg and manifest",
+            input.path.display()
+        )));
+    }
+    let metadata_file = metadata_path(&input.path, runtime)?;
+    let text = std::fs::read_to_string(&metadata_file).map_err(MlPrepError::IoError)?;
+    let metadata: TableMetadata = serde_json::from_str(&text).map_err(|e| {
+        invalid(format!(
+            "{} is not Iceberg table metadata: {}",
+            metadata_file.display(),
+            e
+        ))
+    })?;
+    let snapshot = select_snapshot(&metadata, iceberg)?;
+    let schema = snapshot
+        .schema_id
+        .or(metadata.current_schema_id)
+        .and_then(|id| metadata.schemas.iter().find(|s| s.schema_id == id))
+        .or(metadata.schema.as_ref())
+        .or(metadata.schemas.last());
+    let pruner = Pruner::new(schema, &conditions(steps, runtime));
+
+    let manifests = match &snapshot.manifest_list {
+        Some(list) => manifest_paths(&local_copy(&location(list), runtime)?)?,
+        None => snapshot.manifests.clone(),
+    };
+    let mut files = Vec::new();
+    let mut skipped = Vec::new();
+    for manifest in manifests {
+        for file in read_manifest(&local_copy(&location(&manifest), runtime)?)? {
+            let entry = ManifestEntry {
+                path: location(&file.path),
+                metadata: Vec::new(),
+            };
+            if pruner.may_match(&file) {
+                files.push(entry);
+            } else {
+                skipped.push(entry);
+            }
+        }
+    }
+    tracing::info!(
+        "Iceberg snapshot {} of {}: reading {} data files, {} skipped by their column bounds",
+        snapshot.snapshot_id,
+        input.path.display(),
+        files.len(),
+        skipped.len()
+    );
+    if files.is_empty() {
+        // One file still gives the filters an empty result with the table's columns
+        match skipped.into_iter().next() {
+            Some(entry) => files.push(entry),
+            None => {
+                return Err(invalid(format!(
+                    "Snapshot {} of Iceberg table {} has no data files",
+                    snapshot.snapshot_id,
+                    input.path.display()
+                )))
+            }
+        }
+    }
+    Ok(files)
+}
+
+/// The snapshot `iceberg` asks for: by id, the one current at `as_of`, or the current one.
+fn select_snapshot<'a>(
+    metadata: &'a TableMetadata,
+    iceberg: &Iceberg,
+) -> MlPrepResult<&'a Snapshot> {
+    let by_id = |id: i64| {
+        metadata
+            .snapshots
+            .iter()
+            .find(|s| s.snapshot_id == id)
+            .ok_or_else(|| invalid(format!("The Iceberg table has no snapshot {}", id)))
+    };
+    match (iceberg.snapshot_id, &iceberg.as_of) {
+        (Some(_), Some(_)) => Err(invalid(
+            "An Iceberg input takes snapshot_id or as_of, not both".to_string(),
+        )),
+        (Some(id), None) => by_id(id),
+        (None, Some(as_of)) => {
+            let at = parse_timestamp_ms(as_of).ok_or_else(|| {
+                invalid(format!(
+                    "Invalid as_of '{}': expected RFC 3339 or YYYY-MM-DD[ HH:MM:SS]",
+                    as_of
+                ))
+            })?;
+            // The snapshot log is the history of the current snapshot; tables without
+            // one fall back to the snapshots' own timestamps.
+            let history: Vec<(i64, i64)> = if metadata.snapshot_log.is_empty() {
+                metadata
+                    .snapshots
+                    .iter()
+                    .map(|s| (s.timestamp_ms, s.snapshot_id))
+                    .collect()
+            } else {
+                metadata
+                    .snapshot_log
+                    .iter()
+                    .map(|s| (s.timestamp_ms, s.snapshot_id))
+                    .collect()
+            };
+            let id = history
+                .into_iter()
+                .filter(|(timestamp, _)| *timestamp <= at)
+                .max_by_key(|(timestamp, _)| *timestamp)
+                .map(|(_, id)| id)
+                .ok_or_else(|| {
+                    invalid(format!("The Iceberg table has no snapshot as of {}", as_of))
+                })?;
+            by_id(id)
+        }
+        (None, None) => match metadata.current_snapshot_id.filter(|id| *id >= 0) {
+            Some(id) => by_id(id),
+            None => Err(invalid("The Iceberg table has no snapshots".to_string())),
+        },
+    }
+}
+
+fn parse_timestamp_ms(text: &str) -> Option<i64> {
+    if let Ok(at) = chrono::DateTime::parse_from_rfc3339(text) {
+        return Some(at.timestamp_millis());
+    }
+    parse_naive(text).map(|at| Utc.from_utc_datetime(&at).timestamp_millis())
+}
+
+fn parse_naive(text: &str) -> Option<NaiveDateTime> {
+    ["%Y-%m-%d %H:%M:%S%.f", "%Y-%m-%dT%H:%M:%S%.f"]
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(text, format).ok())
+        .or_else(|| {
+            NaiveDate::parse_from_str(text, "%Y-%m-%d")
+                .ok()
+                .and_then(|date| date.and_hms_opt(0, 0, 0))
+        })
+}
+
+fn read_avro(path: &Path) -> MlPrepResult<DataFrame> {
+    let file = std::fs::File::open(path).map_err(MlPrepError::IoError)?;
+    AvroReader::new(file).finish().map_err(|e| {
+        invalid(format!(
+            "Cannot read Iceberg manifest {}: {}",
+            path.display(),
+            e
+        ))
+    })
+}
+
+/// The data manifests listed in a manifest list.
+fn manifest_paths(path: &Path) -> MlPrepResult<Vec<String>> {
+    let df = read_avro(path)?;
+    let paths = df.column("manifest_path")?.str()?;
+    // Format version 2 lists delete manifests too (content 1)
+    let content = df.column("content").ok().map(|c| c.i32()).transpose()?;
+    let mut manifests = Vec::new();
+    for row in 0..df.height() {
+        if content.and_then(|c| c.get(row)).unwrap_or(0) != 0 {
+            return Err(invalid(format!(
+                "Iceberg manifest list {} has delete files, which are not supported",
+                path.display()
+            )));
+        }
+        if let Some(path) = paths.get(row) {
+            manifests.push(path.to_string());
+        }
+    }
+    Ok(manifests)
+}
+
+/// A live data file listed in a manifest, with its column bounds by field id.
+#[derive(Debug, Clone, Default)]
+struct DataFile {
+    path: String,
+    lower: HashMap<i32, Vec<u8>>,
+    upper: HashMap<i32, Vec<u8>>,
+}
+
+fn read_manifest(path: &Path) -> MlPrepResult<Vec<DataFile>> {
+    let df = read_avro(path)?;
+    let status = df.column("status")?.i32()?;
+    let data_file = df.column("data_file")?.struct_()?;
+    let field = |name: &str| data_file.field_by_name(name).ok();
+    let file_paths = data_file.field_by_name("file_path")?;
+    let file_paths = file_paths.str()?;
+    let formats = data_file.field_by_name("file_format")?;
+    let formats = formats.str()?;
+    let contents = field("content");
+    let lower_bounds = field("lower_bounds");
+    let upper_bounds = field("upper_bounds");
+
+    let mut files = Vec::new();
+    for row in 0..df.height() {
+        // 2 marks a file deleted by this snapshot
+        if status.get(row) == Some(2) {
+            continue;
+        }
+        let Some(file_path) = file_paths.get(row) else {
+            continue;
+        };
+        let content = contents
+            .as_ref()
+            .and_then(|c| c.i32().ok()?.get(row))
+            .unwrap_or(0);
+        if content != 0 {
+            return Err(invalid(format!(
+                "Iceberg manifest {} has delete files, which are not supported",
+                path.display()
+            )));
+        }
+        let format = formats.get(row).unwrap_or_default();
+        if !format.eq_ignore_ascii_case("parquet") {
+            return Err(invalid(format!(
+                "Iceberg data file {} is {}; only Parquet data files are supported",
+                file_path, format
+            )));
+        }
+        files.push(DataFile {
+            path: file_path.to_string(),
+            lower: bounds(lower_bounds.as_ref(), row)?,
+            upper: bounds(upper_bounds.as_ref(), row)?,
+        });
+    }
+    Ok(files)
+}
+
+/// A `lower_bounds` / `upper_bounds` map (a list of `{key, value}` records) at `row`.
+fn bounds(column: Option<&Series>, row: usize) -> MlPrepResult<HashMap<i32, Vec<u8>>> {
+    let Some(entries) = column.and_then(|c| c.list().ok()?.get_as_series(row)) else {
+        return Ok(HashMap::new());
+    };
+    let entries = entries.struct_()?;
+    let keys = entries.field_by_name("key")?;
+    let values = entries.field_by_name("value")?;
+    Ok(keys
+        .i32()?
+        .into_iter()
+        .zip(values.binary()?)
+        .filter_map(|(key, value)| Some((key?, value?.to_vec())))
+        .collect())
+}
+
+/// The SQL conditions of the `filter` steps the pipeline starts with, with parameters
+/// bound. Conditions that fail to bind are left to the step to report.
+fn conditions(steps: &[Step], runtime: &RuntimeConfig) -> Vec<Expr> {
+    steps
+        .iter()
+        .map_while(|step| match step {
+            Step::Filter(filter) => Some(filter.condition.as_deref()),
+            _ => None,
+        })
+        .flatten()
+        .filter_map(|condition| {
+            let bound = crate::sql_condition::bind(condition, &runtime.params).ok()?;
+            Parser::new(&GenericDialect {})
+                .try_with_sql(&bound)
+                .ok()?
+                .parse_expr()
+                .ok()
+        })
+        .collect()
+}
+
+/// A value of a column bound or of a literal it is compared with.
+#[derive(Debug, Clone, PartialEq)]
+enum Datum {
+    Int(i64),
+    Float(f64),
+    Str(String),
+    Bool(bool),
+}
+
+impl Datum {
+    fn compare(&self, other: &Datum) -> Option<Ordering> {
+        match (self, other) {
+            (Datum::Int(a), Datum::Int(b)) => Some(a.cmp(b)),
+            (Datum::Float(a), Datum::Float(b)) => a.partial_cmp(b),
+            (Datum::Int(a), Datum::Float(b)) => (*a as f64).partial_cmp(b),
+            (Datum::Float(a), Datum::Int(b)) => a.partial_cmp(&(*b as f64)),
+            (Datum::Str(a), Datum::Str(b)) => Some(a.cmp(b)),
+            (Datum::Bool(a), Datum::Bool(b)) => Some(a.cmp(b)),
+            _ => None,
+        }
+    }
+
+    /// A bound in Iceberg's single-value binary encoding, for the types it can be
+    /// compared as.
+    fn decode(ty: &str, bytes: &[u8]) -> Option<Datum> {
+        Some(match ty {
+            "boolean" => Datum::Bool(*bytes.first()? != 0),
+            "int" | "date" => Datum::Int(i32::from_le_bytes(bytes.try_into().ok()?) as i64),
+            "long" | "time" | "timestamp" | "timestamptz" => {
+                Datum::Int(i64::from_le_bytes(bytes.try_into().ok()?))
+            }
+            "float" => Datum::Float(f32::from_le_bytes(bytes.try_into().ok()?) as f64),
+            "double" => Datum::Float(f64::from_le_bytes(bytes.try_into().ok()?)),
+            "string" => Datum::Str(String::from_utf8(bytes.to_vec()).ok()?),
+            _ => return None,
+        })
+    }
+
+    /// A SQL literal as a value of a column of type `ty`.
+    fn literal(ty: &str, expr: &Expr) -> Option<Datum> {
+        match expr {
+            Expr::Nested(inner) => Datum::literal(ty, inner),
+            Expr::Cast { expr, .. } => Datum::literal(ty, expr),
+            Expr::TypedString { value, .. } => Datum::from_text(ty, value),
+            Expr::UnaryOp {
+                op: UnaryOperator::Minus,
+                expr,
+            } => match Datum::literal(ty, expr)? {
+                Datum::Int(i) => Some(Datum::Int(-i)),
+                Datum::Float(f) => Some(Datum::Float(-f)),
+                _ => None,
+            },
+            Expr::Value(Value::Number(number, _)) => match ty {
+                "int" | "long" => number
+                    .parse()
+                    .map(Datum::Int)
+                    .or_else(|_| number.parse().map(Datum::Float))
+                    .ok(),
+                "float" | "double" => number.parse().map(Datum::Float).ok(),
+                _ => None,
+            },
+            Expr::Value(Value::Boolean(b)) if ty == "boolean" => Some(Datum::Bool(*b)),
+            Expr::Value(Value::SingleQuotedString(text)) => Datum::from_text(ty, text),
+            _ => None,
+        }
+    }
+
+    fn from_text(ty: &str, text: &str) -> Option<Datum> {
+        match ty {
+            "string" => Some(Datum::Str(text.to_string())),
+            "date" => {
+                let date = NaiveDate::parse_from_str(text, "%Y-%m-%d").ok()?;
+                Some(Datum::Int(
+                    (date - NaiveDate::from_ymd_opt(1970, 1, 1)?).num_days(),
+                ))
+            }
+            "timestamp" | "timestamptz" => {
+                let at = parse_naive(text)?;
+                Some(Datum::Int(Utc.from_utc_datetime(&at).timestamp_micros()))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Decides from a data file's column bounds whether any of its rows may pass the
+/// conditions. Anything it cannot evaluate may match.
+struct Pruner {
+    /// Field id and primitive type by column name
+    fields: HashMap<String, (i32, String)>,
+    conditions: Vec<Expr>,
+}
+
+impl Pruner {
+    fn new(schema: Option<&TableSchema>, conditions: &[Expr]) -> Self {
+        let fields = schema
+            .map(|schema| {
+                schema
+                    .fields
+                    .iter()
+                    .filter_map(|field| {
+                        Some((
+                            field.name.clone(),
+                            (field.id, field.ty.as_str()?.to_string()),
+                        ))
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        Self {
+            fields,
+            conditions: conditions.to_vec(),
+        }
+    }
+
+    fn may_match(&self, file: &DataFile) -> bool {
+        self.conditions
+            .iter()
+            .all(|condition| self.expr_may_match(condition, file))
+    }
+
+    fn expr_may_match(&self, expr: &Expr, file: &DataFile) -> bool {
+        match expr {
+            Expr::Nested(inner) => self.expr_may_match(inner, file),
+            Expr::BinaryOp {
+                left,
+                op: BinaryOperator::And,
+                right,
+            } => self.expr_may_match(left, file) && self.expr_may_match(right, file),
+            Expr::BinaryOp {
+                left,
+                op: BinaryOperator::Or,
+                right,
+            } => self.expr_may_match(left, file) || self.expr_may_match(right, file),
+            Expr::BinaryOp { left, op, right } => match (self.column(left), self.column(right)) {
+                (Some(column), None) => self.compare_may_match(column, op.clone(), right, file),
+                (None, Some(column)) => match flip(op) {
+                    Some(op) => self.compare_may_match(column, op, left, file),
+                    None => true,
+                },
+                _ => true,
+            },
+            Expr::Between {
+                expr,
+                negated: false,
+                low,
+                high,
+            } => self.column(expr).is_none_or(|column| {
+                self.compare_may_match(column, BinaryOperator::GtEq, low, file)
+                    && self.compare_may_match(column, BinaryOperator::LtEq, high, file)
+            }),
+            Expr::InList {
+                expr,
+                list,
+                negated: false,
+            } => self.column(expr).is_none_or(|column| {
+                list.iter()
+                    .any(|value| self.compare_may_match(column, BinaryOperator::Eq, value, file))
+            }),
+            _ => true,
+        }
+    }
+
+    /// The column an identifier names, if the table schema has it.
+    fn column<'a>(&'a self, expr: &Expr) -> Option<&'a (i32, String)> {
+        let name = match expr {
+            Expr::Identifier(ident) => &ident.value,
+            Expr::CompoundIdentifier(parts) => &parts.last()?.value,
+            Expr::Nested(inner) => return self.column(inner),
+            _ => return None,
+        };
+        self.fields.get(name)
+    }
+
+    /// Whether `column op value` may hold for a row of `file`.
+    fn compare_may_match(
+        &self,
+        (id, ty): &(i32, String),
+        op: BinaryOperator,
+        value: &Expr,
+        file: &DataFile,
+    ) -> bool {
+        let Some(value) = Datum::literal(ty, value) else {
+            return true;
+        };
+        let lower = file.lower.get(id).and_then(|b| Datum::decode(ty, b));
+        let upper = file.upper.get(id).and_then(|b| Datum::decode(ty, b));
+        let lower_cmp = lower.and_then(|lower| lower.compare(&value));
+        let upper_cmp = upper.and_then(|upper| upper.compare(&value));
+        match op {
+            BinaryOperator::Eq => {
+                lower_cmp != Some(Ordering::Greater) && upper_cmp != Some(Ordering::Less)
+            }
+            BinaryOperator::Lt => !matches!(lower_cmp, Some(Ordering::Greater | Ordering::Equal)),
+            BinaryOperator::LtEq => lower_cmp != Some(Ordering::Greater),
+            BinaryOperator::Gt => !matches!(upper_cmp, Some(Ordering::Less | Ordering::Equal)),
+            BinaryOperator::GtEq => upper_cmp != Some(Ordering::Less),
+            BinaryOperator::NotEq => {
+                !(lower_cmp == Some(Ordering::Equal) && upper_cmp == Some(Ordering::Equal))
+            }
+            _ => true,
+        }
+    }
+}
+
+/// `op` with its operands swapped: `5 < x` is `x > 5`.
+fn flip(op: &BinaryOperator) -> Option<BinaryOperator> {
+    Some(match op {
+        BinaryOperator::Eq => BinaryOperator::Eq,
+        BinaryOperator::NotEq => BinaryOperator::NotEq,
+        BinaryOperator::Lt => BinaryOperator::Gt,
+        BinaryOperator::LtEq => BinaryOperator::GtEq,
+        BinaryOperator::Gt => BinaryOperator::Lt,
+        BinaryOperator::GtEq => BinaryOperator::LtEq,
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dsl::ScalarValue;
+    use polars::io::avro::AvroWriter;
+    use tempfile::tempdir;
+
+    fn write_avro(path: &Path, mut df: DataFrame) {
+        let file = std::fs::File::create(path).unwrap();
+        AvroWriter::new(file).finish(&mut df).unwrap();
+    }
+
+    /// `{key, value}` records of a long column's bound.
+    fn long_bound(value: i64) -> Series {
+        let key = Series::new("key".into(), [1i32]);
+        let value = Series::new("value".into(), [value.to_le_bytes().to_vec()]);
+        StructChunked::from_series("entry".into(), 1, [key, value].iter())
+            .unwrap()
+            .into_series()
+    }
+
+    /// A manifest listing Parquet data files with `x` in the given ranges.
+    fn write_manifest(path: &Path, files: &[(&Path, i64, i64)]) {
+        let fields = [
+            Series::new("content".into(), vec![0i32; files.len()]),
+            Series::new(
+                "file_path".into(),
+                files
+                    .iter()
+                    .map(|(file, _, _)| format!("file://{}", file.display()))
+                    .collect::<Vec<_>>(),
+            ),
+            Series::new("file_format".into(), vec!["PARQUET"; files.len()]),
+            Series::new(
+                "lower_bounds".into(),
+                files
+                    .iter()
+                    .map(|(_, low, _)| long_bound(*low))
+                    .collect::<Vec<_>>(),
+            ),
+            Series::new(
+                "upper_bounds".into(),
+                files
+                    .iter()
+                    .map(|(_, _, high)| long_bound(*high))
+                    .collect::<Vec<_>>(),
+            ),
+        ];
+        let data_file = StructChunked::from_series("data_file".into(), files.len(), fields.iter())
+            .unwrap()
+            .into_series();
+        let status = Series::new("status".into(), vec![1i32; files.len()]);
+        write_avro(
+            path,
+            DataFrame::new(vec![status.into(), data_file.into()]).unwrap(),
+        );
+    }
+
+    fn write_manifest_list(path: &Path, manifests: &[&Path]) {
+        let paths: Vec<String> = manifests.iter().map(|m| m.display().to_string()).collect();
+        let df = df! {
+            "manifest_path" => paths,
+            "content" => vec![0i32; manifests.len()],
+        }
+        .unwrap();
+        write_avro(path, df);
+    }
+
+    /// A table whose first snapshot (at 1000 ms) holds `x` 1..=3 and whose second (at
+    /// 2000 ms) adds `x` 10..=12.
+    fn write_table(dir: &Path) -> Input {
+        let data = dir.join("data");
+        let metadata = dir.join("metadata");
+        std::fs::create_dir_all(&data).unwrap();
+        std::fs::create_dir_all(&metadata).unwrap();
+        let a = data.join("a.parquet");
+        let b = data.join("b.parquet");
+        crate::io::write_parquet(df!("x" => [1i64, 2, 3]).unwrap(), &a).unwrap();
+        crate::io::write_parquet(df!("x" => [10i64, 11, 12]).unwrap(), &b).unwrap();
+        write_manifest(&metadata.join("m1.avro"), &[(&a, 1, 3)]);
+        write_manifest(&metadata.join("m2.avro"), &[(&b, 10, 12)]);
+        write_manifest_list(&metadata.join("snap-1.avro"), &[&metadata.join("m1.avro")]);
+        write_manifest_list(
+            &metadata.join("snap-2.avro"),
+            &[&metadata.join("m1.avro"), &metadata.join("m2.avro")],
+        );
+        let table = serde_json::json!({
+            "format-version": 2,
+            "current-snapshot-id": 2,
+            "current-schema-id": 0,
+            "schemas": [{
+                "schema-id": 0,
+                "type": "struct",
+                "fields": [{"id": 1, "name": "x", "required": false, "type": "long"}],
+            }],
+            "snapshots": [
+                {"snapshot-id": 1, "timestamp-ms": 1000,
+                 "manifest-list": metadata.join("snap-1.avro")},
+                {"snapshot-id": 2, "timestamp-ms": 2000,
+                 "manifest-list": format!("file:{}", metadata.join("snap-2.avro").display())},
+            ],
+            "snapshot-log": [
+                {"snapshot-id": 1, "timestamp-ms": 1000},
+                {"snapshot-id": 2, "timestamp-ms": 2000},
+            ],
+        });
+        std::fs::write(metadata.join("v1.metadata.json"), "{}").unwrap();
+        std::fs::write(metadata.join("v2.metadata.json"), table.to_string()).unwrap();
+        serde_yaml::from_str(&format!("path: \"{}\"\niceberg: {{}}\n", dir.display())).unwrap()
+    }
+
+    fn file_names(entries: &[ManifestEntry]) -> Vec<String> {
+        entries
+            .iter()
+            .map(|e| e.path.file_name().unwrap().to_string_lossy().into_owned())
+            .collect()
+    }
+
+    #[test]
+    fn test_snapshot_selection() {
+        let dir = tempdir().unwrap();
+        let input = write_table(dir.path());
+        let runtime = RuntimeConfig::default();
+        let files = |snapshot_id: Option<i64>, as_of: Option<&str>| {
+            let table = Iceberg {
+                snapshot_id,
+                as_of: as_of.map(str::to_string),
+            };
+            data_files(&input, &table, &[], &runtime).map(|entries| file_names(&entries))
+        };
+
+        // Without a version hint the highest-numbered metadata file is current
+        assert_eq!(
+            metadata_path(dir.path(), &runtime).unwrap(),
+            dir.path().join("metadata/v2.metadata.json")
+        );
+        assert_eq!(files(None, None).unwrap(), ["a.parquet", "b.parquet"]);
+        assert_eq!(files(Some(1), None).unwrap(), ["a.parquet"]);
+        assert_eq!(
+            files(None, Some("1970-01-01T00:00:01.500Z")).unwrap(),
+            ["a.parquet"]
+        );
+        assert_eq!(
+            files(None, Some("2024-01-01")).unwrap(),
+            ["a.parquet", "b.parquet"]
+        );
+        assert!(files(None, Some("1970-01-01")).is_err());
+        assert!(files(Some(3), None).is_err());
+        assert!(files(Some(1), Some("2024-01-01")).is_err());
+
+        std::fs::write(dir.path().join("metadata/version-hint.text"), "1\n").unwrap();
+        assert_eq!(
+            metadata_path(dir.path(), &runtime).unwrap(),
+            dir.path().join("metadata/v1.metadata.json")
+        );
+    }
+
+    #[test]
+    fn test_bound_pruning() {
+        let dir = tempdir().unwrap();
+        let input = write_table(dir.path());
+        let mut runtime = RuntimeConfig::default();
+        runtime
+            .params
+            .insert("min".to_string(), ScalarValue::Int(5));
+        let table = Iceberg::default();
+        let files = |conditions: &[&str]| {
+            let steps: Vec<Step> = conditions
+                .iter()
+                .map(|c| {
+                    serde_yaml::from_str(&format!("type: filter\ncondition: \"{}\"\n", c)).unwrap()
+                })
+                .collect();
+            file_names(&data_files(&input, &table, &steps, &runtime).unwrap())
+        };
+
+        assert_eq!(files(&["x > :min"]), ["b.parquet"]);
+        assert_eq!(files(&["5 >= x"]), ["a.parquet"]);
+        assert_eq!(files(&["x = 2 OR x = 11"]), ["a.parquet", "b.parquet"]);
+        assert_eq!(
+            files(&["x BETWEEN 4 AND 9 OR x IN (12, 20)"]),
+            ["b.parquet"]
+        );
+        assert_eq!(files(&["x > 1", "x < 10"]), ["a.parquet"]);
+        // Conditions on unknown columns or with functions cannot prune
+        assert_eq!(files(&["y > 100"]), ["a.parquet", "b.parquet"]);
+        assert_eq!(files(&["abs(x) > 100"]), ["a.parquet", "b.parquet"]);
+        // With every file ruled out, one is still read for the table's columns
+        assert_eq!(files(&["x > 100"]), ["a.parquet"]);
+
+        // The rows of the files read are still filtered by the steps
+        let security = crate::security::SecurityContext::new(Default::default()).unwrap();
+        let df = crate::manifest::scan_input(&input, &security, &runtime)
+            .unwrap()
+            .collect()
+            .unwrap();
+        assert_eq!(df.height(), 6);
+    }
+}
@@ -0,0 +1,559 @@
+//! Local copies of remote inputs (`s3://`, `gs://`, `az://` and `http(s)://`), so runs
+//! against the same remote dataset download each object once.
+//!
+//! Copies are stored by content hash under `objects/<sha256>/<file name>`, keeping the
+//! name so format detection sees the original extension. `index.json` maps each URI to
+//! the version (ETag, or modification time and size) it was downloaded at; a URI whose
+//! version changed is downloaded again. Least recently used copies are evicted once the
+//! cache outgrows its size limit.
+//!
+//! Runs sharing the cache (such as the workers of `--jobs`) update the index while holding
+//! a lock on `index.lock`, and keep a shared lock on each copy they read until they
+//! finish; eviction skips copies that are locked.
+
+use crate::dsl::{parse_memory_size, RuntimeConfig};
+use crate::errors::{MlPrepError, MlPrepResult};
+use crate::io::{self, RemoteObject};
+use crate::observability::compute_file_hash;
+use chrono::{DateTime, Utc};
+use serde::de::Error;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs::{File, TryLockError};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+/// Size limit of the cache when `runtime.input_cache_size` is unset.
+pub const DEFAULT_MAX_SIZE: &str = "10GB";
+
+const INDEX_FILE: &str = "index.json";
+const LOCK_FILE: &str = "index.lock";
+
+/// Whether `path` is read from a remote store: an object store URI or an HTTP(S) URL.
+pub fn is_remote(path: &Path) -> bool {
+    io::is_cloud_uri(path) || is_http_url(path)
+}
+
+fn is_http_url(path: &Path) -> bool {
+    path.to_str().is_some_and(|url| {
+        let url = url.to_ascii_lowercase();
+        url.starts_with("http://") || url.starts_with("https://")
+    })
+}
+
+/// Fetch the version and size of the remote object at `uri`.
+pub fn head(uri: &Path) -> MlPrepResult<RemoteObject> {
+    let text = uri.to_str().unwrap_or_default();
+    if is_http_url(uri) {
+        let response = ureq::head(text).call().map_err(|e| http_error(uri, e))?;
+        let size = response
+            .header("Content-Length")
+            .and_then(|value| value.parse().ok())
+            .unwrap_or(0);
+        let version = match (response.header("ETag"), response.header("Last-Modified")) {
+            (Some(etag), _) => etag.to_string(),
+            (None, Some(modified)) => format!("{}-{}", modified, size),
+            (None, None) => {
+                return Err(remote_error(
+                    uri,
+                    "the server sends neither an ETag nor a Last-Modified header",
+                ))
+            }
+        };
+        return Ok(RemoteObject { version, size });
+    }
+
+    io::head_object(uri)
+}
+
+/// Download the remote object at `uri` into the local file `dest`.
+fn download(uri: &Path, dest: &Path) -> MlPrepResult<()> {
+    let text = uri.to_str().unwrap_or_default();
+    let mut file = std::fs::File::create(dest).map_err(MlPrepError::IoError)?;
+    if is_http_url(uri) {
+        let response = ureq::get(text).call().map_err(|e| http_error(uri, e))?;
+        std::io::copy(&mut response.into_reader(), &mut file).map_err(MlPrepError::IoError)?;
+        return file.sync_all().map_err(MlPrepError::IoError);
+    }
+
+    use polars::io::cloud::{build_object_store, object_path_from_str};
+    use polars::io::pl_async::get_runtime;
+
+    let options = io::cloud_options(uri)?;
+    let mut file = tokio::fs::File::from_std(file);
+    get_runtime()
+        .block_on_potential_spawn(async {
+            let (location, store) = build_object_store(text, options.as_ref(), false).await?;
+            store
+                .download(&object_path_from_str(&location.prefix)?, &mut file)
+                .await
+        })
+        .map_err(MlPrepError::PolarsError)
+}
+
+/// A failed HTTP request. A server that cannot be reached is reported as a refused
+/// connection, which is transient, so the run can be retried.
+fn http_error(uri: &Path, error: ureq::Error) -> MlPrepError {
+    let kind = match &error {
+        ureq::Error::Transport(transport)
+            if transport.kind() == ureq::ErrorKind::ConnectionFailed =>
+        {
+            std::io::ErrorKind::ConnectionRefused
+        }
+        _ => std::io::ErrorKind::Other,
+    };
+    MlPrepError::IoError(std::io::Error::new(
+        kind,
+        format!("Cannot read {}: {}", uri.display(), error),
+    ))
+}
+
+fn remote_error(uri: &Path, error: impl std::fmt::Display) -> MlPrepError {
+    MlPrepError::IoError(std::io::Error::other(format!(
+        "Cannot read {}: {}",
+        uri.display(),
+        error
+    )))
+}
+
+/// A cached copy of one URI.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct Entry {
+    version: String,
+    sha256: String,
+    file_name: String,
+    size: u64,
+    last_used: DateTime<Utc>,
+}
+
+/// The input cache in a directory, holding at most `max_bytes` of copies.
+#[derive(Debug, Clone)]
+pub struct InputCache {
+    dir: PathBuf,
+    max_bytes: u64,
+}
+
+impl InputCache {
+    pub fn new(dir: impl Into<PathBuf>, max_bytes: u64) -> Self {
+        Self {
+            dir: dir.into(),
+            max_bytes,
+        }
+    }
+
+    /// The cache configured by `runtime`; `None` with `no_input_cache`.
+    pub fn for_runtime(runtime: &RuntimeConfig) -> MlPrepResult<Option<Self>> {
+        if runtime.no_input_cache {
+            return Ok(None);
+        }
+        let dir = runtime.input_cache_dir.clone().unwrap_or_else(default_dir);
+        let max_bytes = parse_memory_size(
+            runtime
+                .input_cache_size
+                .as_deref()
+                .unwrap_or(DEFAULT_MAX_SIZE),
+        )?;
+        Ok(Some(Self::new(dir, max_bytes)))
+    }
+
+    /// The local copy of the remote object at `uri`, downloaded unless a copy of its
+    /// current version is cached. The copy stays pinned by `pins`.
+    pub fn fetch(&self, uri: &Path, pins: &Pins) -> MlPrepResult<PathBuf> {
+        let object = head(uri)?;
+        let key = uri.to_string_lossy().to_string();
+
+        {
+            let _lock = self.lock_index()?;
+            let mut index = self.load_index()?;
+            let cached = index
+                .get(&key)
+                .filter(|entry| entry.version == object.version)
+                .map(|entry| self.object_path(entry))
+                .filter(|path| path.is_file());
+            if let Some(path) = cached {
+                tracing::debug!(uri = %uri.display(), "input cache hit");
+                pins.pin(&path)?;
+                if let Some(entry) = index.get_mut(&key) {
+                    entry.last_used = Utc::now();
+                }
+                self.save_index(&index)?;
+                return Ok(path);
+            }
+        }
+
+        // Download without holding the lock, so other runs can use the cache meanwhile
+        tracing::info!("Downloading {} into the input cache", uri.display());
+        let (tmp_path, entry) = self.store(uri, object.version)?;
+        let _lock = self.lock_index()?;
+        let result = (|| {
+            let path = self.place(&tmp_path, &entry)?;
+            pins.pin(&path)?;
+            let mut index = self.load_index()?;
+            index.insert(key.clone(), entry);
+            self.evict(&mut index, &key)?;
+            self.save_index(&index)?;
+            Ok(path)
+        })();
+        if result.is_err() {
+            let _ = std::fs::remove_file(&tmp_path);
+        }
+        result
+    }
+
+    /// Download `uri` into the cache's `tmp` directory, returning the file and its entry.
+    fn store(&self, uri: &Path, version: String) -> MlPrepResult<(PathBuf, Entry)> {
+        let tmp_dir = self.dir.join("tmp");
+        std::fs::create_dir_all(&tmp_dir).map_err(MlPrepError::IoError)?;
+        let tmp_path = tmp_dir.join(uuid::Uuid::new_v4().to_string());
+        let result = (|| {
+            download(uri, &tmp_path)?;
+            let sha256 = compute_file_hash(&tmp_path).map_err(MlPrepError::IoError)?;
+            let size = std::fs::metadata(&tmp_path)
+                .map_err(MlPrepError::IoError)?
+                .len();
+            let file_name = uri
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .filter(|name| !name.is_empty())
+                .unwrap_or_else(|| "data".to_string());
+            Ok(Entry {
+                version,
+                sha256,
+                file_name,
+                size,
+                last_used: Utc::now(),
+            })
+        })();
+        match result {
+            Ok(entry) => Ok((tmp_path, entry)),
+            Err(e) => {
+                let _ = std::fs::remove_file(&tmp_path);
+                Err(e)
+            }
+        }
+    }
+
+    /// Move a downloaded file to its content-addressed place.
+    fn place(&self, tmp_path: &Path, entry: &Entry) -> MlPrepResult<PathBuf> {
+        let path = self.object_path(entry);
+        if path.is_file() {
+            // Another URI already brought in the same content
+            std::fs::remove_file(tmp_path).map_err(MlPrepError::IoError)?;
+        } else {
+            std::fs::create_dir_all(path.parent().unwrap_or(&self.dir))
+                .map_err(MlPrepError::IoError)?;
+            std::fs::rename(tmp_path, &path).map_err(MlPrepError::IoError)?;
+        }
+        Ok(path)
+    }
+
+    /// Drop the least recently used entries other than `keep` until the copies fit in
+    /// `max_bytes`, deleting copies no remaining entry refers to. Copies pinned by a run
+    /// are kept, and so is an object larger than the limit on its own, for the run that
+    /// needs it.
+    fn evict(&self, index: &mut BTreeMap<String, Entry>, keep: &str) -> MlPrepResult<()> {
+        let stored = |index: &BTreeMap<String, Entry>| -> BTreeMap<String, u64> {
+            index
+                .values()
+                .map(|entry| (entry.sha256.clone(), entry.size))
+                .collect()
+        };
+        let mut in_use = Vec::new();
+        while stored(index).values().sum::<u64>() > self.max_bytes {
+            let Some(oldest) = index
+                .iter()
+                .filter(|(key, _)| key.as_str() != keep && !in_use.contains(*key))
+                .min_by_key(|(_, entry)| entry.last_used)
+                .map(|(key, _)| key.clone())
+            else {
+                break;
+            };
+            if is_pinned(&self.object_path(&index[&oldest]))? {
+                tracing::debug!(uri = %oldest, "not evicting an input another run is reading");
+                in_use.push(oldest);
+                continue;
+            }
+            let entry = index.remove(&oldest).expect("key taken from the index");
+            if !stored(index).contains_key(&entry.sha256) {
+                let dir = self.dir.join("objects").join(&entry.sha256);
+                if dir.exists() {
+                    std::fs::remove_dir_all(dir).map_err(MlPrepError::IoError)?;
+                }
+            }
+            tracing::debug!(uri = %oldest, "evicted from the input cache");
+        }
+        Ok(())
+    }
+
+    /// Hold the lock on the index until the returned file is dropped.
+    fn lock_index(&self) -> MlPrepResult<File> {
+        std::fs::create_dir_all(&self.dir).map_err(MlPrepError::IoError)?;
+        let file = File::create(self.dir.join(LOCK_FILE)).map_err(MlPrepError::IoError)?;
+        file.lock().map_err(MlPrepError::IoError)?;
+        Ok(file)
+    }
+
+    fn object_path(&self, entry: &Entry) -> PathBuf {
+        self.dir
+            .join("objects")
+            .join(&entry.sha256)
+            .join(&entry.file_name)
+    }
+
+    fn load_index(&self) -> MlPrepResult<BTreeMap<String, Entry>> {
+        let path = self.dir.join(INDEX_FILE);
+        if !path.exists() {
+            return Ok(BTreeMap::new());
+        }
+        let text = std::fs::read_to_string(&path).map_err(MlPrepError::IoError)?;
+        // A corrupt index only costs downloads; it is rebuilt as inputs are fetched.
+        Ok(serde_json::from_str(&text).unwrap_or_else(|e| {
+            tracing::warn!(
+                "Ignoring unreadable input cache index {}: {}",
+                path.display(),
+                e
+            );
+            BTreeMap::new()
+        }))
+    }
+
+    fn save_index(&self, index: &BTreeMap<String, Entry>) -> MlPrepResult<()> {
+        std::fs::create_dir_all(&self.dir).map_err(MlPrepError::IoError)?;
+        io::write_atomic(self.dir.join(INDEX_FILE), |file| {
+            serde_json::to_writer_pretty(file, index).map_err(|e| MlPrepError::Unknown(e.into()))
+        })
+    }
+}
+
+/// Whether a run holds a pin on the cached copy at `path`.
+fn is_pinned(path: &Path) -> MlPrepResult<bool> {
+    let Ok(file) = File::open(path) else {
+        return Ok(false);
+    };
+    match file.try_lock() {
+        Ok(()) => Ok(false),
+        Err(TryLockError::WouldBlock) => Ok(true),
+        Err(TryLockError::Error(e)) => Err(MlPrepError::IoError(e)),
+    }
+}
+
+/// The cached copies one run reads, each held under a shared lock so that other runs do
+/// not evict it. The locks are released when the last clone is dropped.
+#[derive(Clone, Default)]
+pub struct Pins(Arc<Mutex<Vec<File>>>);
+
+impl Pins {
+    fn pin(&self, path: &Path) -> MlPrepResult<()> {
+        let file = File::open(path).map_err(MlPrepError::IoError)?;
+        file.lock_shared().map_err(MlPrepError::IoError)?;
+        self.0.lock().unwrap_or_else(|e| e.into_inner()).push(file);
+        Ok(())
+    }
+}
+
+impl fmt::Debug for Pins {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let count = self.0.lock().map_or(0, |files| files.len());
+        f.debug_tuple("Pins").field(&count).finish()
+    }
+}
+
+/// Pins are not part of a run's configuration, so any two compare equal.
+impl PartialEq for Pins {
+    fn eq(&self, _: &Self) -> bool {
+        true
+    }
+}
+
+/// `$XDG_CACHE_HOME/mlprep/inputs`, else `~/.cache/mlprep/inputs`.
+fn default_dir() -> PathBuf {
+    std::env::var_os("XDG_CACHE_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".cache")))
+        .unwrap_or_else(std::env::temp_dir)
+        .join("mlprep")
+        .join("inputs")
+}
+
+/// `path` itself, or for a remote input the local copy to read instead. Without a cache
+/// object store URIs are scanned directly, while HTTP(S) inputs need the cache.
+pub fn localize(path: &Path, runtime: &RuntimeConfig) -> MlPrepResult<PathBuf> {
+    if !is_remote(path) {
+        return Ok(path.to_path_buf());
+    }
+    match InputCache::for_runtime(runtime)? {
+        Some(cache) => cache.fetch(path, &runtime.input_pins),
+        None if is_http_url(path) => Err(MlPrepError::ConfigError(
+            serde_yaml::Error::custom(format!(
+                "HTTP input {} is read through the input cache, which is disabled by --no-cache",
+                path.display()
+            )),
+            None,
+        )),
+        None => Ok(path.to_path_buf()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    /// Serve `body` (with `etag`) over HTTP, counting the GET requests.
+    fn serve(body: &'static str, etag: &'static str) -> (String, Arc<AtomicUsize>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        let gets = Arc::new(AtomicUsize::new(0));
+        let counter = gets.clone();
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut stream = stream.unwrap();
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut request = String::new();
+                reader.read_line(&mut request).unwrap();
+                let mut line = String::new();
+                while reader.read_line(&mut line).unwrap() > 2 {
+                    line.clear();
+                }
+                let is_get = request.starts_with("GET");
+                if is_get {
+                    counter.fetch_add(1, Ordering::SeqCst);
+                }
+                let _ = write!(
+                    stream,
+                    "HTTP/1.1 200 OK\r\nETag: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    etag,
+                    body.len(),
+                    if is_get { body } else { "" }
+                );
+            }
+        });
+        (base, gets)
+    }
+
+    #[test]
+    fn test_fetch_caches_by_version() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = InputCache::new(dir.path(), 1 << 20);
+        let (base, gets) = serve("id,v\n1,a\n2,b\n", "\"v1\"");
+        let uri = PathBuf::from(format!("{}/data/events.csv", base));
+        let pins = Pins::default();
+
+        let first = cache.fetch(&uri, &pins).unwrap();
+        assert_eq!(std::fs::read_to_string(&first).unwrap(), "id,v\n1,a\n2,b\n");
+        assert_eq!(first.file_name().unwrap(), "events.csv");
+        let second = cache.fetch(&uri, &pins).unwrap();
+        assert_eq!(first, second);
+        assert_eq!(gets.load(Ordering::SeqCst), 1);
+
+        // Another URI with the same content shares the copy
+        let other = PathBuf::from(format!("{}/mirror/events.csv", base));
+        assert_eq!(cache.fetch(&other, &pins).unwrap(), first);
+
+        // A changed version is downloaded again
+        let mut index = cache.load_index().unwrap();
+        index
+            .get_mut(&uri.to_string_lossy().to_string())
+            .unwrap()
+            .version = "old".into();
+        cache.save_index(&index).unwrap();
+        cache.fetch(&uri, &pins).unwrap();
+        assert_eq!(gets.load(Ordering::SeqCst), 3);
+    }
+
+    #[test]
+    fn test_eviction_keeps_cache_under_limit() {
+        let dir = tempfile::tempdir().unwrap();
+        let (base_a, _) = serve("a,b\n1,2\n", "\"a\"");
+        let (base_b, _) = serve("c,d\n3,4\n", "\"b\"");
+        // Room for one of the 8-byte files only
+        let cache = InputCache::new(dir.path(), 10);
+        let a = cache
+            .fetch(
+                &PathBuf::from(format!("{}/a.csv", base_a)),
+                &Pins::default(),
+            )
+            .unwrap();
+        let b = cache
+            .fetch(
+                &PathBuf::from(format!("{}/b.csv", base_b)),
+                &Pins::default(),
+            )
+            .unwrap();
+        assert!(!a.exists());
+        assert!(b.exists());
+        assert_eq!(cache.load_index().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_eviction_skips_pinned_copies() {
+        let dir = tempfile::tempdir().unwrap();
+        let (base_a, _) = serve("a,b\n1,2\n", "\"a\"");
+        let (base_b, _) = serve("c,d\n3,4\n", "\"b\"");
+        let (base_c, _) = serve("e,f\n5,6\n", "\"c\"");
+        let cache = InputCache::new(dir.path(), 10);
+
+        // Another run is still reading a
+        let reading = Pins::default();
+        let a = cache
+            .fetch(&PathBuf::from(format!("{}/a.csv", base_a)), &reading)
+            .unwrap();
+        let b = cache
+            .fetch(
+                &PathBuf::from(format!("{}/b.csv", base_b)),
+                &Pins::default(),
+            )
+            .unwrap();
+        assert!(a.exists());
+        assert!(b.exists());
+        assert_eq!(cache.load_index().unwrap().len(), 2);
+
+        // Once it finishes, a can go
+        drop(reading);
+        let c = cache
+            .fetch(
+                &PathBuf::from(format!("{}/c.csv", base_c)),
+                &Pins::default(),
+            )
+            .unwrap();
+        assert!(!a.exists());
+        assert!(!b.exists());
+        assert!(c.exists());
+    }
+
+    #[test]
+    fn test_concurrent_fetches_keep_every_entry() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = InputCache::new(dir.path(), 1 << 20);
+        let (base, _) = serve("id\n1\n", "\"v1\"");
+        let threads: Vec<_> = (0..8)
+            .map(|i| {
+                let cache = cache.clone();
+                let uri = PathBuf::from(format!("{}/{}.csv", base, i));
+                std::thread::spawn(move || cache.fetch(&uri, &Pins::default()).unwrap())
+            })
+            .collect();
+        for thread in threads {
+            thread.join().unwrap();
+        }
+        assert_eq!(cache.load_index().unwrap().len(), 8);
+    }
+
+    #[test]
+    fn test_localize() {
+        let local = Path::new("data/events.csv");
+        let runtime = RuntimeConfig {
+            no_input_cache: true,
+            ..Default::default()
+        };
+        assert_eq!(localize(local, &runtime).unwrap(), local);
+        let uri = Path::new("s3://lake/events.parquet");
+        assert_eq!(localize(uri, &runtime).unwrap(), uri);
+        let err = localize(Path::new("https://example.com/a.csv"), &runtime).unwrap_err();
+        assert!(err.to_string().contains("--no-cache"), "{}", err);
+    }
+}
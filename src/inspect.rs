@@ -0,0 +1,253 @@
+//! `mlprep head` and `mlprep describe`: the first rows and per-column summary statistics
+//! of a data file, for quick sanity checks in terminals and CI logs. Masked columns show
+//! `***` rows and no values in their statistics.
+
+use crate::contract::dtype_name;
+use crate::errors::{MlPrepError, MlPrepResult};
+use crate::io;
+use crate::security::SecurityContext;
+use polars::prelude::*;
+use serde::Serialize;
+use serde_json::Value;
+use std::path::Path;
+
+/// The first `n` rows of the file at `path`.
+pub fn head(
+    path: &Path,
+    format: Option<&str>,
+    n: usize,
+    security_context: &SecurityContext,
+) -> MlPrepResult<DataFrame> {
+    security_context.validate_path(path)?;
+    let lf = io::read_detected(path, format)?.limit(n as IdxSize);
+    security_context
+        .masker()
+        .mask(lf)?
+        .collect()
+        .map_err(MlPrepError::PolarsError)
+}
+
+/// Summary statistics of one column. `mean` and `std` are given for numeric columns,
+/// `min` and `max` for numeric, string and temporal ones.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ColumnSummary {
+    pub name: String,
+    pub dtype: String,
+    pub nulls: usize,
+    /// Distinct values, nulls included; not counted for nested columns
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub unique: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mean: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub std: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max: Option<Value>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FileSummary {
+    pub rows: usize,
+    pub columns: Vec<ColumnSummary>,
+}
+
+/// `value` with at most four decimals and no trailing zeros.
+fn format_number(value: f64) -> String {
+    let text = format!("{:.4}", value);
+    text.trim_end_matches('0').trim_end_matches('.').to_string()
+}
+
+fn format_value(value: &Option<Value>) -> String {
+    match value {
+        Some(Value::Number(n)) => n.as_f64().map(format_number).unwrap_or_default(),
+        Some(Value::String(s)) => s.clone(),
+        Some(other) => other.to_string(),
+        None => String::new(),
+    }
+}
+
+impl std::fmt::Display for FileSummary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "rows: {}", self.rows)?;
+        let mut table = vec![[
+            "column", "dtype", "nulls", "unique", "mean", "std", "min", "max",
+        ]
+        .map(String::from)
+        .to_vec()];
+        for column in &self.columns {
+            table.push(vec![
+                column.name.clone(),
+                column.dtype.clone(),
+                column.nulls.to_string(),
+                column.unique.map(|u| u.to_string()).unwrap_or_default(),
+                column.mean.map(format_number).unwrap_or_default(),
+                column.std.map(format_number).unwrap_or_default(),
+                format_value(&column.min),
+                format_value(&column.max),
+            ]);
+        }
+        let widths: Vec<usize> = (0..table[0].len())
+            .map(|i| {
+                table
+                    .iter()
+                    .map(|row| row[i].chars().count())
+                    .max()
+                    .unwrap_or(0)
+            })
+            .collect();
+        for row in &table {
+            let line: Vec<String> = row
+                .iter()
+                .zip(&widths)
+                .map(|(cell, width)| format!("{:width$}", cell, width = width))
+                .collect();
+            writeln!(f, "{}", line.join("  ").trim_end())?;
+        }
+        Ok(())
+    }
+}
+
+fn stat(stats: &DataFrame, name: &str) -> MlPrepResult<AnyValue<'static>> {
+    Ok(stats
+        .column(name)
+        .map_err(MlPrepError::PolarsError)?
+        .get(0)
+        .map_err(MlPrepError::PolarsError)?
+        .into_static())
+}
+
+/// Summary statistics of every column of `lf`, computed in one pass over the data.
+pub fn describe(
+    mut lf: LazyFrame,
+    security_context: &SecurityContext,
+) -> MlPrepResult<FileSummary> {
+    let schema = lf.collect_schema().map_err(MlPrepError::PolarsError)?;
+    let masker = security_context.masker();
+    let mut exprs = vec![len().alias("__rows")];
+    for (i, (name, dtype)) in schema.iter().enumerate() {
+        let column = col(name.clone());
+        exprs.push(column.clone().null_count().alias(format!("__nulls{}", i)));
+        if !dtype.is_nested() {
+            exprs.push(column.clone().n_unique().alias(format!("__unique{}", i)));
+        }
+        if masker.is_masked(name) {
+            continue;
+        }
+        if dtype.is_primitive_numeric() {
+            let values = column.cast(DataType::Float64);
+            exprs.push(values.clone().mean().alias(format!("__mean{}", i)));
+            exprs.push(values.clone().std(1).alias(format!("__std{}", i)));
+            exprs.push(values.clone().min().alias(format!("__min{}", i)));
+            exprs.push(values.max().alias(format!("__max{}", i)));
+        } else if dtype.is_string() || dtype.is_temporal() {
+            exprs.push(
+                column
+                    .clone()
+                    .min()
+                    .cast(DataType::String)
+                    .alias(format!("__min{}", i)),
+            );
+            exprs.push(
+                column
+                    .max()
+                    .cast(DataType::String)
+                    .alias(format!("__max{}", i)),
+            );
+        }
+    }
+    let stats = lf
+        .select(exprs)
+        .collect()
+        .map_err(MlPrepError::PolarsError)?;
+
+    let optional = |name: String| -> MlPrepResult<Option<AnyValue<'static>>> {
+        if stats.column(&name).is_err() {
+            return Ok(None);
+        }
+        Ok(Some(stat(&stats, &name)?).filter(|v| !v.is_null()))
+    };
+    let json = |value: AnyValue| match value.get_str() {
+        Some(s) => Value::from(s),
+        None => value.extract::<f64>().map(Value::from).unwrap_or_default(),
+    };
+    let rows = stat(&stats, "__rows")?.extract::<usize>().unwrap_or(0);
+    let mut columns = Vec::with_capacity(schema.len());
+    for (i, (name, dtype)) in schema.iter().enumerate() {
+        columns.push(ColumnSummary {
+            name: name.to_string(),
+            dtype: dtype_name(dtype),
+            nulls: stat(&stats, &format!("__nulls{}", i))?
+                .extract::<usize>()
+                .unwrap_or(0),
+            unique: optional(format!("__unique{}", i))?.and_then(|v| v.extract::<usize>()),
+            mean: optional(format!("__mean{}", i))?.and_then(|v| v.extract::<f64>()),
+            std: optional(format!("__std{}", i))?.and_then(|v| v.extract::<f64>()),
+            min: optional(format!("__min{}", i))?.map(json),
+            max: optional(format!("__max{}", i))?.map(json),
+        });
+    }
+    Ok(FileSummary { rows, columns })
+}
+
+/// Summary statistics of the file at `path`.
+pub fn describe_file(
+    path: &Path,
+    format: Option<&str>,
+    security_context: &SecurityContext,
+) -> MlPrepResult<FileSummary> {
+    security_context.validate_path(path)?;
+    describe(io::read_detected(path, format)?, security_context)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_head_and_describe() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("users.csv");
+        std::fs::write(
+            &path,
+            "id,name,score,joined,ssn\n1,ann,1.5,2024-01-02,111\n2,bob,,2024-03-01,222\n3,ann,4.5,2023-12-31,333\n",
+        )
+        .unwrap();
+        let security_context = SecurityContext::new(crate::security::SecurityConfig {
+            allowed_paths: None,
+            mask_columns: Some(vec!["ssn".to_string()]),
+        })
+        .unwrap();
+
+        let df = head(&path, None, 2, &security_context).unwrap();
+        assert_eq!(df.height(), 2);
+        assert_eq!(df.column("ssn").unwrap().str().unwrap().get(0), Some("***"));
+
+        let lf = io::read_detected(&path, None)
+            .unwrap()
+            .with_column(col("joined").str().to_date(Default::default()));
+        let summary = describe(lf, &security_context).unwrap();
+        assert_eq!(summary.rows, 3);
+        let score = &summary.columns[2];
+        assert_eq!((score.nulls, score.unique), (1, Some(3)));
+        assert_eq!(score.mean, Some(3.0));
+        assert_eq!(score.min, Some(Value::from(1.5)));
+        let joined = &summary.columns[3];
+        assert_eq!(joined.dtype, "Date");
+        assert_eq!(joined.min, Some(Value::from("2023-12-31")));
+        let ssn = &summary.columns[4];
+        assert_eq!((ssn.unique, &ssn.min, ssn.mean), (Some(3), &None, None));
+
+        assert_eq!(
+            summary.to_string(),
+            "rows: 3\n\
+             column  dtype    nulls  unique  mean  std     min         max\n\
+             id      Int64    0      3       2     1       1           3\n\
+             name    String   0      2                     ann         bob\n\
+             score   Float64  1      3       3     2.1213  1.5         4.5\n\
+             joined  Date     0      3                     2023-12-31  2024-03-01\n\
+             ssn     Int64    0      3\n"
+        );
+    }
+}
@@ -1,25 +1,676 @@
 use crate::errors::{MlPrepError, MlPrepResult};
+use polars::io::cloud::CloudOptions;
+use polars::io::parquet::write::BatchedWriter;
 use polars::prelude::*;
+use polars_parquet::write::KeyValue;
+use serde::de::Error;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::io::Read;
 use std::path::Path;
 
+/// Input file formats, resolved by [`detect_format`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FileFormat {
+    Csv,
+    Parquet,
+    /// Newline-delimited records or a single array of records
+    Json,
+}
+
+impl FileFormat {
+    /// Parse an input's `format:` value.
+    pub fn parse(name: &str) -> MlPrepResult<Self> {
+        match name.trim().to_lowercase().as_str() {
+            "csv" => Ok(FileFormat::Csv),
+            "parquet" => Ok(FileFormat::Parquet),
+            "json" | "ndjson" | "jsonl" => Ok(FileFormat::Json),
+            other => Err(MlPrepError::ConfigError(
+                serde_yaml::Error::custom(format!(
+                    "Unsupported input format: {} (expected csv, parquet or json)",
+                    other
+                )),
+                None,
+            )),
+        }
+    }
+
+    fn from_extension(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?.to_lowercase();
+        match extension.as_str() {
+            "csv" => Some(FileFormat::Csv),
+            "parquet" | "pq" => Some(FileFormat::Parquet),
+            "json" | "ndjson" | "jsonl" => Some(FileFormat::Json),
+            _ => None,
+        }
+    }
+}
+
+/// Bytes read from the start of a file to detect its format.
+const SNIFF_BYTES: usize = 4096;
+
+/// The first bytes of the file at `path`, empty if it cannot be opened (e.g. a glob
+/// or a directory, which the reader then resolves itself).
+fn read_head(path: &Path) -> Vec<u8> {
+    let mut head = Vec::with_capacity(SNIFF_BYTES);
+    if let Ok(file) = std::fs::File::open(path) {
+        let _ = file.take(SNIFF_BYTES as u64).read_to_end(&mut head);
+    }
+    head
+}
+
+/// Decompress as much of a gzip or zstd `head` as it holds; `None` if it is not compressed.
+fn decompress_head(head: &[u8]) -> Option<Vec<u8>> {
+    let mut text = Vec::new();
+    // A truncated stream fails after yielding what it could decode, which is all we need.
+    if head.starts_with(&[0x1f, 0x8b]) {
+        let decoder = flate2::read::MultiGzDecoder::new(head);
+        let _ = decoder.take(SNIFF_BYTES as u64).read_to_end(&mut text);
+    } else if head.starts_with(&[0x28, 0xb5, 0x2f, 0xfd]) {
+        let decoder = zstd::stream::read::Decoder::new(head).ok()?;
+        let _ = decoder.take(SNIFF_BYTES as u64).read_to_end(&mut text);
+    } else {
+        return None;
+    }
+    Some(text)
+}
+
+/// The first non-whitespace byte of text content, after any UTF-8 byte order mark.
+fn first_text_byte(text: &[u8]) -> Option<u8> {
+    let text = text.strip_prefix(b"\xef\xbb\xbf").unwrap_or(text);
+    text.iter().copied().find(|b| !b.is_ascii_whitespace())
+}
+
+/// Whether the file name of `path` ends in `.<extension>`.
+pub fn has_extension(path: &Path, extension: &str) -> bool {
+    path.extension().is_some_and(|ext| ext == extension)
+}
+
+/// URI schemes read and written through an object store: S3, Google Cloud Storage and
+/// Azure Blob Storage / ADLS Gen2.
+const CLOUD_SCHEMES: &[&str] = &["s3", "s3a", "gs", "gcs", "az", "azure", "abfs", "abfss"];
+
+/// Whether `path` is an object store URI such as `s3://bucket/key`, `gs://bucket/key` or
+/// `az://container/key`, rather than a local path.
+pub fn is_cloud_uri(path: &Path) -> bool {
+    path.to_str()
+        .and_then(|uri| uri.split_once("://"))
+        .is_some_and(|(scheme, _)| CLOUD_SCHEMES.contains(&scheme.to_lowercase().as_str()))
+}
+
+/// Polars cloud options for an object store URI; `None` for local paths. Credentials come
+/// from each provider's standard chain: `AWS_*` variables and profiles for S3,
+/// `GOOGLE_APPLICATION_CREDENTIALS` or the metadata server for GCS, and `AZURE_STORAGE_*`
+/// variables or a managed identity for Azure.
+pub fn cloud_options(path: &Path) -> MlPrepResult<Option<CloudOptions>> {
+    if !is_cloud_uri(path) {
+        return Ok(None);
+    }
+    let uri = path.to_str().unwrap_or_default();
+    CloudOptions::from_untyped_config(uri, std::iter::empty::<(&str, String)>())
+        .map(Some)
+        .map_err(MlPrepError::PolarsError)
+}
+
+/// Version and size of a remote object.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RemoteObject {
+    /// The ETag, or the modification time and size when the store reports none
+    pub version: String,
+    pub size: u64,
+}
+
+/// Fetch the version and size of the object at the object store URI `uri`, without
+/// downloading it.
+pub fn head_object(uri: &Path) -> MlPrepResult<RemoteObject> {
+    use polars::io::cloud::{build_object_store, object_path_from_str};
+    use polars::io::pl_async::get_runtime;
+
+    let text = uri.to_str().unwrap_or_default();
+    let options = cloud_options(uri)?;
+    get_runtime()
+        .block_on_potential_spawn(async {
+            let (location, store) = build_object_store(text, options.as_ref(), false).await?;
+            let meta = store.head(&object_path_from_str(&location.prefix)?).await?;
+            let version = meta.e_tag.unwrap_or_else(|| {
+                format!("{}-{}", meta.last_modified.timestamp_millis(), meta.size)
+            });
+            Ok(RemoteObject {
+                version,
+                size: meta.size as u64,
+            })
+        })
+        .map_err(MlPrepError::PolarsError)
+}
+
+/// Whether `path` names a dataset directory: an existing directory, or a path spelled
+/// with a trailing separator.
+pub fn is_dataset_dir(path: &Path) -> bool {
+    path.is_dir()
+        || path
+            .as_os_str()
+            .as_encoded_bytes()
+            .last()
+            .is_some_and(|&b| std::path::is_separator(b as char))
+}
+
+/// Resolve the format of the input at `path`. An explicit `format:` wins; otherwise
+/// the Parquet magic bytes, then the extension (ignoring a `.gz` / `.zst` suffix),
+/// then the first character of the (decompressed) text: `{` or `[` is JSON, anything
+/// else CSV. Extension-less files from object stores are handled by content alone.
+pub fn detect_format<P: AsRef<Path>>(path: P, explicit: Option<&str>) -> MlPrepResult<FileFormat> {
+    if let Some(name) = explicit {
+        return FileFormat::parse(name);
+    }
+    let path = path.as_ref();
+    let head = read_head(path);
+    if head.starts_with(b"PAR1") {
+        return Ok(FileFormat::Parquet);
+    }
+    let uncompressed_name = match path.extension().and_then(|ext| ext.to_str()) {
+        Some("gz" | "gzip" | "zst" | "zstd") => path.with_extension(""),
+        _ => path.to_path_buf(),
+    };
+    if let Some(format) = FileFormat::from_extension(&uncompressed_name) {
+        return Ok(format);
+    }
+    let text = decompress_head(&head).unwrap_or(head);
+    Ok(match first_text_byte(&text) {
+        Some(b'{') | Some(b'[') => FileFormat::Json,
+        _ => FileFormat::Csv,
+    })
+}
+
+/// Scan the input at `path` in the given format.
+pub fn read_input<P: AsRef<Path>>(path: P, format: FileFormat) -> MlPrepResult<LazyFrame> {
+    match format {
+        FileFormat::Csv => read_csv(path),
+        FileFormat::Parquet => read_parquet(path),
+        FileFormat::Json => read_json(path),
+    }
+}
+
+/// Scan the input at `path`, detecting its format (see [`detect_format`]).
+pub fn read_detected<P: AsRef<Path>>(path: P, explicit: Option<&str>) -> MlPrepResult<LazyFrame> {
+    let path = path.as_ref();
+    read_input(path, detect_format(path, explicit)?)
+}
+
+/// Newline-delimited JSON is scanned lazily; a JSON array is parsed up front.
+/// Objects in a bucket cannot be sniffed, so there only newline-delimited JSON is read.
+pub fn read_json<P: AsRef<Path>>(path: P) -> MlPrepResult<LazyFrame> {
+    let path = path.as_ref();
+    let head = read_head(path);
+    let text = decompress_head(&head).unwrap_or(head);
+    if first_text_byte(&text) == Some(b'[') {
+        let file = std::fs::File::open(path).map_err(MlPrepError::IoError)?;
+        return JsonReader::new(file)
+            .finish()
+            .map(DataFrame::lazy)
+            .map_err(MlPrepError::PolarsError);
+    }
+    if let Some(file) = open_non_utf8(path)? {
+        return JsonReader::new(file)
+            .with_json_format(JsonFormat::JsonLines)
+            .finish()
+            .map(DataFrame::lazy)
+            .map_err(MlPrepError::PolarsError);
+    }
+    LazyJsonLineReader::new(path)
+        .with_cloud_options(cloud_options(path)?)
+        .finish()
+        .map_err(MlPrepError::PolarsError)
+}
+
+/// Polars' scanners only take UTF-8 paths, so a file whose path is not valid UTF-8 is
+/// opened here and read eagerly instead; `None` for every other path.
+fn open_non_utf8(path: &Path) -> MlPrepResult<Option<std::fs::File>> {
+    if path.to_str().is_some() {
+        return Ok(None);
+    }
+    Ok(Some(
+        std::fs::File::open(path).map_err(MlPrepError::IoError)?,
+    ))
+}
+
 pub fn read_csv<P: AsRef<Path>>(path: P) -> MlPrepResult<LazyFrame> {
+    let path = path.as_ref();
+    if let Some(file) = open_non_utf8(path)? {
+        return CsvReader::new(file)
+            .finish()
+            .map(DataFrame::lazy)
+            .map_err(MlPrepError::PolarsError);
+    }
     LazyCsvReader::new(path)
+        .with_cloud_options(cloud_options(path)?)
         .finish()
         .map_err(MlPrepError::PolarsError)
 }
 
 pub fn read_parquet<P: AsRef<Path>>(path: P) -> MlPrepResult<LazyFrame> {
-    LazyFrame::scan_parquet(path, Default::default()).map_err(MlPrepError::PolarsError)
+    let path = path.as_ref();
+    if let Some(file) = open_non_utf8(path)? {
+        return ParquetReader::new(file)
+            .finish()
+            .map(DataFrame::lazy)
+            .map_err(MlPrepError::PolarsError);
+    }
+    let args = ScanArgsParquet {
+        cloud_options: cloud_options(path)?,
+        ..Default::default()
+    };
+    LazyFrame::scan_parquet(path, args).map_err(MlPrepError::PolarsError)
+}
+
+/// Row count from the Parquet footer, without reading any data pages.
+pub fn parquet_row_count<P: AsRef<Path>>(path: P) -> MlPrepResult<usize> {
+    let path = path.as_ref();
+    if is_cloud_uri(path) {
+        // The scan of a `len()` only fetches the footer too.
+        let counted = read_parquet(path)?
+            .select([len()])
+            .collect()
+            .map_err(MlPrepError::PolarsError)?;
+        return counted[0]
+            .get(0)
+            .and_then(|value| value.try_extract::<u64>())
+            .map(|rows| rows as usize)
+            .map_err(MlPrepError::PolarsError);
+    }
+    let file = std::fs::File::open(path).map_err(MlPrepError::IoError)?;
+    ParquetReader::new(file)
+        .num_rows()
+        .map_err(MlPrepError::PolarsError)
+}
+
+/// Row count of an input file without parsing it: the Parquet footer, or Polars'
+/// `COUNT(*)` fast path for CSV, which counts records without building columns.
+pub fn count_rows<P: AsRef<Path>>(path: P, format: FileFormat) -> MlPrepResult<usize> {
+    let path = path.as_ref();
+    if format == FileFormat::Parquet {
+        return parquet_row_count(path);
+    }
+    let counted = read_input(path, format)?
+        .select([len()])
+        .collect()
+        .map_err(MlPrepError::PolarsError)?;
+    let rows = counted[0]
+        .get(0)
+        .and_then(|value| value.try_extract::<u64>())
+        .map_err(MlPrepError::PolarsError)?;
+    Ok(rows as usize)
 }
 
 pub fn write_parquet<P: AsRef<Path>>(df: DataFrame, path: P) -> MlPrepResult<()> {
-    let file = std::fs::File::create(path).map_err(MlPrepError::IoError)?;
-    ParquetWriter::new(file)
-        .finish(&mut df.clone())
+    write_parquet_documented(df, path, &ColumnDocs::new())
+}
+
+/// Parquet key-value metadata key holding column descriptions and units, as JSON.
+pub const COLUMN_DOCS_KEY: &str = "mlprep.columns";
+
+/// Description and unit of an output column.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct ColumnDoc {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub unit: Option<String>,
+}
+
+/// Column docs by column name.
+pub type ColumnDocs = BTreeMap<String, ColumnDoc>;
+
+/// Key-value metadata recording the `docs` of the columns in `schema`; `None` when none
+/// of them is documented.
+pub(crate) fn column_docs_metadata(
+    docs: &ColumnDocs,
+    schema: &Schema,
+) -> MlPrepResult<Option<Vec<KeyValue>>> {
+    let present: ColumnDocs = docs
+        .iter()
+        .filter(|(name, _)| schema.contains(name))
+        .map(|(name, doc)| (name.clone(), doc.clone()))
+        .collect();
+    if present.is_empty() {
+        return Ok(None);
+    }
+    let json =
+        serde_json::to_string(&present).map_err(|e| MlPrepError::TransformError(e.to_string()))?;
+    Ok(Some(vec![KeyValue::new(COLUMN_DOCS_KEY.to_string(), json)]))
+}
+
+/// Write `df` as Parquet, recording `docs` in the file's key-value metadata.
+pub fn write_parquet_documented<P: AsRef<Path>>(
+    df: DataFrame,
+    path: P,
+    docs: &ColumnDocs,
+) -> MlPrepResult<()> {
+    let metadata = column_docs_metadata(docs, df.schema())?;
+    write_atomic(path, |file| {
+        let Some(metadata) = metadata else {
+            ParquetWriter::new(file)
+                .finish(&mut df.clone())
+                .map_err(MlPrepError::PolarsError)?;
+            return Ok(());
+        };
+        let mut writer = ParquetWriter::new(file)
+            .batched(df.schema())
+            .map_err(MlPrepError::PolarsError)?;
+        writer.write_batch(&df).map_err(MlPrepError::PolarsError)?;
+        finish_parquet(&writer, Some(metadata))
+    })
+}
+
+/// Write the footer of a batched Parquet file, with extra key-value metadata.
+pub(crate) fn finish_parquet<W: std::io::Write>(
+    writer: &BatchedWriter<W>,
+    metadata: Option<Vec<KeyValue>>,
+) -> MlPrepResult<()> {
+    writer
+        .get_writer()
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .end(metadata)
         .map_err(MlPrepError::PolarsError)?;
     Ok(())
 }
 
+/// A column of a data file with its docs, as shown by `mlprep schema show`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DescribedColumn {
+    pub name: String,
+    pub dtype: String,
+    #[serde(flatten)]
+    pub doc: ColumnDoc,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FileSchema {
+    pub columns: Vec<DescribedColumn>,
+}
+
+impl std::fmt::Display for FileSchema {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name_width = self.columns.iter().map(|c| c.name.len()).max().unwrap_or(0);
+        let dtype_width = self
+            .columns
+            .iter()
+            .map(|c| c.dtype.len())
+            .max()
+            .unwrap_or(0);
+        for column in &self.columns {
+            let mut line = format!("{:name_width$}  {:dtype_width$}", column.name, column.dtype);
+            if let Some(description) = &column.doc.description {
+                line.push_str("  ");
+                line.push_str(description);
+            }
+            if let Some(unit) = &column.doc.unit {
+                line.push_str(&format!("  [{}]", unit));
+            }
+            writeln!(f, "{}", line.trim_end())?;
+        }
+        Ok(())
+    }
+}
+
+/// Columns and dtypes of the file at `path`, with the docs recorded in Parquet metadata.
+pub fn describe_schema<P: AsRef<Path>>(
+    path: P,
+    explicit: Option<&str>,
+) -> MlPrepResult<FileSchema> {
+    let path = path.as_ref();
+    let format = detect_format(path, explicit)?;
+    let schema = read_input(path, format)?
+        .collect_schema()
+        .map_err(MlPrepError::PolarsError)?;
+    let mut docs = if format == FileFormat::Parquet && path.is_file() {
+        read_column_docs(path)?
+    } else {
+        ColumnDocs::new()
+    };
+    let columns = schema
+        .iter()
+        .map(|(name, dtype)| DescribedColumn {
+            name: name.to_string(),
+            dtype: format!("{:?}", dtype),
+            doc: docs.remove(name.as_str()).unwrap_or_default(),
+        })
+        .collect();
+    Ok(FileSchema { columns })
+}
+
+/// Column docs recorded in the metadata of the Parquet file at `path`; empty for files
+/// written without them.
+pub fn read_column_docs<P: AsRef<Path>>(path: P) -> MlPrepResult<ColumnDocs> {
+    let file = std::fs::File::open(path.as_ref()).map_err(MlPrepError::IoError)?;
+    let metadata = ParquetReader::new(file)
+        .get_metadata()
+        .map_err(MlPrepError::PolarsError)?
+        .clone();
+    let Some(entry) = metadata
+        .key_value_metadata()
+        .iter()
+        .flatten()
+        .find(|kv| kv.key == COLUMN_DOCS_KEY)
+    else {
+        return Ok(ColumnDocs::new());
+    };
+    serde_json::from_str(entry.value.as_deref().unwrap_or("{}")).map_err(|e| {
+        MlPrepError::TransformError(format!(
+            "Invalid {} metadata in {}: {}",
+            COLUMN_DOCS_KEY,
+            path.as_ref().display(),
+            e
+        ))
+    })
+}
+
+pub fn write_csv<P: AsRef<Path>>(mut df: DataFrame, path: P) -> MlPrepResult<()> {
+    write_atomic(path, |file| {
+        CsvWriter::new(file)
+            .finish(&mut df)
+            .map_err(MlPrepError::PolarsError)
+    })
+}
+
+/// Write to a temporary file next to `path` and rename it into place only once `write`
+/// succeeded, so readers never observe a truncated file. The temp file is removed on failure.
+/// For an object store URI the temp file is local and uploaded once complete; the object
+/// only becomes visible when the upload finishes.
+pub fn write_atomic<P, F>(path: P, write: F) -> MlPrepResult<()>
+where
+    P: AsRef<Path>,
+    F: FnOnce(&mut std::fs::File) -> MlPrepResult<()>,
+{
+    let path = path.as_ref();
+    let tmp_path = temp_path_for(path)?;
+
+    let result = (|| {
+        let mut file = std::fs::File::create(&tmp_path).map_err(MlPrepError::IoError)?;
+        write(&mut file)?;
+        file.sync_all().map_err(MlPrepError::IoError)?;
+        move_into_place(&tmp_path, path)
+    })();
+    if result.is_err() {
+        let _ = std::fs::remove_file(&tmp_path);
+    }
+    result
+}
+
+/// Rename the complete `tmp_path` to `path`, or upload it there and remove it when `path`
+/// is an object store URI.
+pub fn move_into_place(tmp_path: &Path, path: &Path) -> MlPrepResult<()> {
+    if !is_cloud_uri(path) {
+        return std::fs::rename(tmp_path, path).map_err(MlPrepError::IoError);
+    }
+    upload(tmp_path, path)?;
+    std::fs::remove_file(tmp_path).map_err(MlPrepError::IoError)
+}
+
+/// Copy the local file at `local` to the object store URI `uri`.
+fn upload(local: &Path, uri: &Path) -> MlPrepResult<()> {
+    use polars::io::cloud::CloudWriter;
+    use polars::io::pl_async::get_runtime;
+
+    let options = cloud_options(uri)?;
+    let uri = uri.to_str().unwrap_or_default();
+    let mut writer = get_runtime()
+        .block_on_potential_spawn(CloudWriter::new(uri, options.as_ref()))
+        .map_err(MlPrepError::PolarsError)?;
+    let mut file = std::fs::File::open(local).map_err(MlPrepError::IoError)?;
+    std::io::copy(&mut file, &mut writer).map_err(MlPrepError::IoError)?;
+    writer.close().map_err(MlPrepError::PolarsError)
+}
+
+/// Hidden temporary file next to `path`, renamed into place once fully written. Uploads
+/// to an object store are staged in the system temp directory.
+fn temp_path_for(path: &Path) -> MlPrepResult<std::path::PathBuf> {
+    let file_name = path.file_name().ok_or_else(|| {
+        MlPrepError::IoError(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!("Output path has no file name: {:?}", path),
+        ))
+    })?;
+    let tmp_name = format!(
+        ".{}.{}.tmp",
+        file_name.to_string_lossy(),
+        uuid::Uuid::new_v4()
+    );
+    if is_cloud_uri(path) {
+        return Ok(std::env::temp_dir().join(tmp_name));
+    }
+    Ok(path.with_file_name(tmp_name))
+}
+
+/// Like [`temp_path_for`], but keeping the extension of `path`, for files written in
+/// their final format under a temporary name and later passed to [`move_into_place`].
+pub fn staging_path_for(path: &Path) -> MlPrepResult<std::path::PathBuf> {
+    let tmp_path = temp_path_for(path)?;
+    Ok(match path.extension() {
+        Some(ext) => tmp_path.with_extension(format!("tmp.{}", ext.to_string_lossy())),
+        None => tmp_path,
+    })
+}
+
+/// Stream `lf` into a Parquet or CSV file chunk by chunk, without materializing the
+/// frame. Like [`write_atomic`], the file only appears at `path` once complete.
+/// Fails with [`is_not_streamable`] errors, before writing anything, when the plan
+/// cannot run in streaming order.
+pub fn sink_atomic<P: AsRef<Path>>(lf: LazyFrame, path: P) -> MlPrepResult<()> {
+    let path = path.as_ref();
+    let tmp_path = temp_path_for(path)?;
+    let result = if has_extension(path, "parquet") {
+        lf.sink_parquet(&tmp_path, Default::default(), None)
+    } else {
+        lf.sink_csv(&tmp_path, Default::default(), None)
+    }
+    .map_err(MlPrepError::PolarsError)
+    .and_then(|()| move_into_place(&tmp_path, path));
+    if result.is_err() {
+        let _ = std::fs::remove_file(&tmp_path);
+    }
+    result
+}
+
+/// Whether `error` is the sink's refusal of a plan that cannot be streamed.
+pub fn is_not_streamable(error: &MlPrepError) -> bool {
+    let MlPrepError::PolarsError(e) = error else {
+        return false;
+    };
+    let message = e.to_string();
+    message.contains("cannot run the whole query in a streaming order")
+        || message.contains("not yet supported in standard engine")
+}
+
+/// What the optimizer pushed down into one file scan of a plan.
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct ScanPushdown {
+    pub source: String,
+    pub format: String,
+    /// Columns read from the file; `None` means every column is read.
+    pub columns: Option<Vec<String>>,
+    pub total_columns: usize,
+    /// Predicate evaluated during the scan (used for Parquet row-group pruning).
+    pub predicate: Option<String>,
+    pub slice: Option<(i64, usize)>,
+}
+
+impl std::fmt::Display for ScanPushdown {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} scan {}: ", self.format, self.source)?;
+        match &self.columns {
+            Some(cols) => write!(
+                f,
+                "{}/{} columns [{}]",
+                cols.len(),
+                self.total_columns,
+                cols.join(", ")
+            )?,
+            None => write!(f, "all {} columns", self.total_columns)?,
+        }
+        match &self.predicate {
+            Some(p) => write!(f, "; predicate {}", p)?,
+            None => write!(f, "; no predicate")?,
+        }
+        if let Some((offset, len)) = self.slice {
+            write!(f, "; slice offset {} length {}", offset, len)?;
+        }
+        Ok(())
+    }
+}
+
+/// Optimize `lf` and report the projection, predicate and slice pushed into each file scan.
+pub fn explain_scans(lf: LazyFrame) -> MlPrepResult<Vec<ScanPushdown>> {
+    use polars_plan::plans::{FileScan, IR};
+
+    let plan = lf.to_alp_optimized().map_err(MlPrepError::PolarsError)?;
+    let mut scans = Vec::new();
+    let mut stack = vec![plan.lp_top];
+    while let Some(node) = stack.pop() {
+        let ir = plan.lp_arena.get(node);
+        if let IR::Scan {
+            sources,
+            file_info,
+            predicate,
+            scan_type,
+            file_options,
+            ..
+        } = ir
+        {
+            let format = match scan_type {
+                FileScan::Csv { .. } => "csv",
+                FileScan::Parquet { .. } => "parquet",
+                _ => "other",
+            };
+            scans.push(ScanPushdown {
+                source: sources
+                    .as_paths()
+                    .map(|paths| {
+                        paths
+                            .iter()
+                            .map(|p| p.display().to_string())
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    })
+                    .unwrap_or_else(|| "<memory>".to_string()),
+                format: format.to_string(),
+                columns: file_options
+                    .with_columns
+                    .as_ref()
+                    .map(|cols| cols.iter().map(|c| c.to_string()).collect()),
+                total_columns: file_info.schema.len(),
+                predicate: predicate
+                    .as_ref()
+                    .map(|p| p.display(&plan.expr_arena).to_string()),
+                slice: file_options.slice,
+            });
+        }
+        // Push inputs in reverse so scans are reported left to right.
+        stack.extend(ir.get_inputs_vec().into_iter().rev());
+    }
+    Ok(scans)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -41,6 +692,223 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_count_rows() -> MlPrepResult<()> {
+        let dir = tempfile::tempdir()?;
+        let csv = dir.path().join("data.csv");
+        // A quoted newline is part of the record, and there is no trailing newline.
+        fs::write(&csv, "a,b\n1,\"x\ny\"\n2,z\n3,w")?;
+        assert_eq!(count_rows(csv.to_str().unwrap(), FileFormat::Csv)?, 3);
+
+        let parquet = dir.path().join("data.parquet");
+        let df = read_csv(&csv)?
+            .collect()
+            .map_err(MlPrepError::PolarsError)?;
+        write_parquet(df, &parquet)?;
+        assert_eq!(
+            count_rows(parquet.to_str().unwrap(), FileFormat::Parquet)?,
+            3
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_parquet_column_docs() -> MlPrepResult<()> {
+        let dir = tempfile::tempdir()?;
+        let parquet = dir.path().join("prices.parquet");
+        let df = df!("id" => [1i64, 2], "price" => [1.5, 2.5]).map_err(MlPrepError::PolarsError)?;
+        let docs = ColumnDocs::from([
+            (
+                "price".to_string(),
+                ColumnDoc {
+                    description: Some("Unit price".to_string()),
+                    unit: Some("EUR".to_string()),
+                },
+            ),
+            ("dropped".to_string(), ColumnDoc::default()),
+        ]);
+        write_parquet_documented(df.clone(), &parquet, &docs)?;
+
+        let read = read_parquet(&parquet)?
+            .collect()
+            .map_err(MlPrepError::PolarsError)?;
+        assert!(read.equals(&df));
+        let stored = read_column_docs(&parquet)?;
+        assert_eq!(stored.keys().collect::<Vec<_>>(), vec!["price"]);
+
+        let schema = describe_schema(parquet.to_str().unwrap(), None)?;
+        assert_eq!(schema.columns[1].doc, docs["price"]);
+        assert_eq!(
+            schema.to_string(),
+            "id     Int64\nprice  Float64  Unit price  [EUR]\n"
+        );
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_non_utf8_paths() -> MlPrepResult<()> {
+        use std::os::unix::ffi::OsStrExt;
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join(std::ffi::OsStr::from_bytes(b"caf\xe9.csv"));
+        assert!(path.to_str().is_none());
+        fs::write(&path, "a,b\n1,x\n2,y\n")?;
+
+        assert!(has_extension(&path, "csv"));
+        assert_eq!(detect_format(&path, None)?, FileFormat::Csv);
+        assert_eq!(count_rows(&path, FileFormat::Csv)?, 2);
+        let df = read_detected(&path, None)?
+            .collect()
+            .map_err(MlPrepError::PolarsError)?;
+        assert_eq!(df.shape(), (2, 2));
+
+        let out = dir
+            .path()
+            .join(std::ffi::OsStr::from_bytes(b"\xff.parquet"));
+        write_parquet(df.clone(), &out)?;
+        let back = read_detected(&out, None)?
+            .collect()
+            .map_err(MlPrepError::PolarsError)?;
+        assert!(back.equals(&df));
+        let sunk = dir.path().join(std::ffi::OsStr::from_bytes(b"\xfe.csv"));
+        sink_atomic(df.clone().lazy(), &sunk)?;
+        assert_eq!(count_rows(&sunk, FileFormat::Csv)?, 2);
+
+        let security = crate::security::SecurityContext::new(crate::security::SecurityConfig {
+            allowed_paths: Some(vec![dir.path().to_path_buf()]),
+            mask_columns: None,
+        })?;
+        security.validate_path(&path)?;
+        security.validate_path(&out)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_detect_format_by_content() -> MlPrepResult<()> {
+        use std::io::Write;
+        let dir = tempfile::tempdir()?;
+        let path = |name: &str| dir.path().join(name);
+        let detect = |name: &str| detect_format(path(name), None).unwrap();
+
+        let df =
+            df!("a" => [1i64, 2, 3], "b" => ["x", "y", "z"]).map_err(MlPrepError::PolarsError)?;
+        write_parquet(df.clone(), path("blob"))?;
+        // Magic bytes beat a misleading extension.
+        write_parquet(df, path("mislabeled.csv"))?;
+        fs::write(path("table"), "\u{feff}a,b\n1,x\n2,y\n3,z\n")?;
+        fs::write(
+            path("events"),
+            "{\"a\": 1, \"b\": \"x\"}\n{\"a\": 2, \"b\": \"y\"}\n",
+        )?;
+        fs::write(path("records"), "  [{\"a\": 1}, {\"a\": 2}]")?;
+        let mut gz = flate2::write::GzEncoder::new(
+            fs::File::create(path("download"))?,
+            flate2::Compression::default(),
+        );
+        gz.write_all(b"{\"a\": 1}\n{\"a\": 2}\n")?;
+        gz.finish()?;
+        let mut gz = flate2::write::GzEncoder::new(
+            fs::File::create(path("table.csv.gz"))?,
+            flate2::Compression::default(),
+        );
+        gz.write_all(b"a,b\n1,x\n2,y\n")?;
+        gz.finish()?;
+
+        assert_eq!(detect("blob"), FileFormat::Parquet);
+        assert_eq!(detect("mislabeled.csv"), FileFormat::Parquet);
+        assert_eq!(detect("table"), FileFormat::Csv);
+        assert_eq!(detect("events"), FileFormat::Json);
+        assert_eq!(detect("records"), FileFormat::Json);
+        assert_eq!(detect("download"), FileFormat::Json);
+        assert_eq!(detect("table.csv.gz"), FileFormat::Csv);
+        assert_eq!(detect_format(path("events"), Some("CSV"))?, FileFormat::Csv);
+        assert!(detect_format(path("events"), Some("xlsx")).is_err());
+
+        let rows = |name: &str| {
+            read_detected(path(name), None)
+                .unwrap()
+                .collect()
+                .unwrap()
+                .height()
+        };
+        assert_eq!(rows("blob"), 3);
+        assert_eq!(rows("table"), 3);
+        assert_eq!(rows("events"), 2);
+        assert_eq!(rows("records"), 2);
+        assert_eq!(rows("download"), 2);
+        assert_eq!(rows("table.csv.gz"), 2);
+        assert_eq!(count_rows(path("table.csv.gz"), FileFormat::Csv)?, 2);
+        Ok(())
+    }
+
+    #[test]
+    fn test_cloud_uris() -> MlPrepResult<()> {
+        for uri in [
+            "s3://lake/raw/events.parquet",
+            "gs://lake/raw/events.csv",
+            "GS://lake/raw/events.csv",
+            "az://container/events.parquet",
+            "abfss://container@account.dfs.core.windows.net/events.parquet",
+        ] {
+            let path = Path::new(uri);
+            assert!(is_cloud_uri(path), "{}", uri);
+            assert!(cloud_options(path)?.is_some(), "{}", uri);
+            // Uploads are staged locally
+            assert!(temp_path_for(path)?.starts_with(std::env::temp_dir()));
+        }
+        for path in [
+            "data/events.csv",
+            "/tmp/s3://x",
+            "https://example.com/a.csv",
+        ] {
+            assert!(!is_cloud_uri(Path::new(path)), "{}", path);
+            assert!(cloud_options(Path::new(path))?.is_none());
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_write_atomic_cleans_up_on_failure() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("out.parquet");
+        fs::write(&path, "previous").unwrap();
+
+        let result = write_atomic(&path, |file| {
+            use std::io::Write;
+            file.write_all(b"partial")?;
+            Err(MlPrepError::TransformError("boom".to_string()))
+        });
+
+        assert!(result.is_err());
+        // The previous file is untouched and no temp file is left behind.
+        assert_eq!(fs::read_to_string(&path).unwrap(), "previous");
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn test_explain_scans_reports_pushdown() -> MlPrepResult<()> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("data.parquet");
+        let df = df!("a" => [1, 2, 3], "b" => ["x", "y", "z"], "c" => [1.0, 2.0, 3.0])
+            .map_err(MlPrepError::PolarsError)?;
+        write_parquet(df, &path)?;
+
+        let lf = read_parquet(&path)?
+            .filter(col("a").gt(lit(1)))
+            .select([col("b")]);
+        let scans = explain_scans(lf)?;
+
+        assert_eq!(scans.len(), 1);
+        let scan = &scans[0];
+        assert_eq!(scan.format, "parquet");
+        assert_eq!(scan.total_columns, 3);
+        let mut cols = scan.columns.clone().unwrap();
+        cols.sort();
+        assert_eq!(cols, vec!["a", "b"]);
+        assert!(scan.predicate.as_deref().unwrap().contains("col(\"a\")"));
+        Ok(())
+    }
+
     #[test]
     fn test_parquet_io() -> MlPrepResult<()> {
         let csv_path = "test_p.csv";
@@ -0,0 +1,73 @@
+//! Parsed right sides of broadcast joins, shared by every pipeline run in the process.
+//!
+//! Entries are keyed by the file's content hash, so pipelines of one invocation that
+//! join against the same lookup table read and parse it once, while a file edited
+//! between runs (e.g. in watch mode) is read again.
+
+use crate::errors::{MlPrepError, MlPrepResult};
+use crate::io::{self, FileFormat};
+use crate::observability::compute_file_hash;
+use polars::prelude::*;
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::{LazyLock, Mutex};
+
+/// Tables kept at once; the cache is emptied when it would grow past this.
+const MAX_TABLES: usize = 32;
+
+static TABLES: LazyLock<Mutex<HashMap<(String, FileFormat), DataFrame>>> =
+    LazyLock::new(Default::default);
+
+/// Read and parse the file at `path`, reusing the table parsed by an earlier join of a
+/// file with the same content. Paths that cannot be hashed (directories, globs) are
+/// read without caching.
+pub fn read(path: &Path, format: Option<&str>) -> MlPrepResult<DataFrame> {
+    let format = io::detect_format(path, format)?;
+    let Ok(hash) = compute_file_hash(path) else {
+        return io::read_input(path, format)?
+            .collect()
+            .map_err(MlPrepError::PolarsError);
+    };
+    let key = (hash, format);
+    if let Some(df) = lock().get(&key) {
+        tracing::debug!(path = %path.display(), "join table cache hit");
+        return Ok(df.clone());
+    }
+
+    let df = io::read_input(path, format)?
+        .collect()
+        .map_err(MlPrepError::PolarsError)?;
+    let mut tables = lock();
+    if tables.len() >= MAX_TABLES {
+        tables.clear();
+    }
+    tables.insert(key, df.clone());
+    Ok(df)
+}
+
+fn lock() -> std::sync::MutexGuard<'static, HashMap<(String, FileFormat), DataFrame>> {
+    TABLES.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_read_shares_tables_by_content() {
+        let dir = tempdir().unwrap();
+        let first = dir.path().join("users.csv");
+        let copy = dir.path().join("users_copy.csv");
+        std::fs::write(&first, "id,name\n1,a\n2,b\n").unwrap();
+        std::fs::copy(&first, &copy).unwrap();
+
+        let df = read(&first, None).unwrap();
+        assert_eq!(df.height(), 2);
+        assert!(read(&copy, None).unwrap().equals_missing(&df));
+
+        // An edited file is parsed again
+        std::fs::write(&first, "id,name\n1,a\n2,b\n3,c\n").unwrap();
+        assert_eq!(read(&first, None).unwrap().height(), 3);
+    }
+}
@@ -0,0 +1,395 @@
+//! Experimental Kafka inputs. Each run consumes one bounded batch of a topic (a message
+//! count or a time window), decodes the JSON or Avro payloads into a frame and runs the
+//! pipeline on it as if it were a file. With a consumer group the batch's offsets are
+//! committed once the run has written its outputs, so a failed run reads the same
+//! messages again.
+//!
+//! Consuming needs the `kafka` cargo feature; decoding does not.
+
+use crate::dsl::Kafka;
+use crate::errors::{MlPrepError, MlPrepResult};
+use polars::io::avro::AvroReader;
+use polars::prelude::*;
+use serde::de::Error;
+use std::io::Cursor;
+use std::path::{Path, PathBuf};
+
+#[cfg(feature = "kafka")]
+use ::kafka::consumer::Consumer;
+/// Stands in for the consumer when built without the `kafka` feature
+#[cfg(not(feature = "kafka"))]
+type Consumer = std::convert::Infallible;
+
+const SCHEME: &str = "kafka://";
+const DEFAULT_MAX_WAIT: &str = "30s";
+/// Magic bytes of an Avro object container file
+const AVRO_MAGIC: &[u8] = b"Obj\x01";
+
+fn invalid(msg: String) -> MlPrepError {
+    MlPrepError::ConfigError(serde_yaml::Error::custom(msg), None)
+}
+
+pub fn is_kafka_uri(path: &Path) -> bool {
+    path.to_string_lossy().starts_with(SCHEME)
+}
+
+/// The brokers and topic of a `kafka://<broker>[,<broker>...]/<topic>` URI.
+pub fn parse_uri(path: &Path) -> MlPrepResult<(Vec<String>, String)> {
+    let uri = path.to_string_lossy();
+    let parsed = uri.strip_prefix(SCHEME).and_then(|rest| {
+        let (brokers, topic) = rest.split_once('/')?;
+        let brokers: Vec<String> = brokers
+            .split(',')
+            .filter(|b| !b.is_empty())
+            .map(str::to_string)
+            .collect();
+        (!brokers.is_empty() && !topic.is_empty() && !topic.contains('/'))
+            .then(|| (brokers, topic.to_string()))
+    });
+    parsed.ok_or_else(|| {
+        invalid(format!(
+            "Invalid Kafka input '{}': expected kafka://<broker>[,<broker>...]/<topic>",
+            uri
+        ))
+    })
+}
+
+/// One consumed batch, decoded into a local Parquet file that is removed when the batch
+/// is dropped.
+pub struct Batch {
+    pub path: PathBuf,
+    pub messages: usize,
+    /// Set when a consumer group commits the batch's offsets
+    consumer: Option<Consumer>,
+}
+
+impl Batch {
+    /// Commit the batch's offsets to its consumer group, if it has one.
+    pub fn commit(mut self) -> MlPrepResult<()> {
+        match &mut self.consumer {
+            Some(consumer) => commit_consumed(consumer, self.messages),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Drop for Batch {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+/// Consume one batch of the topic at `path` and write it to a local Parquet file in
+/// `dir`. `format` is the payload format: `json` (default) or `avro`.
+pub fn consume(
+    path: &Path,
+    options: &Kafka,
+    format: Option<&str>,
+    dir: &Path,
+) -> MlPrepResult<Batch> {
+    let (df, messages, consumer) = fetch(path, options, format)?;
+    let batch = Batch {
+        path: dir.join(format!("mlprep-kafka-{}.parquet", uuid::Uuid::new_v4())),
+        messages,
+        consumer,
+    };
+    crate::io::write_parquet(df, &batch.path)?;
+    Ok(batch)
+}
+
+/// Read one batch of the topic at `path` without committing its offsets, e.g. to
+/// preview a pipeline.
+pub fn read(path: &Path, options: &Kafka, format: Option<&str>) -> MlPrepResult<DataFrame> {
+    fetch(path, options, format).map(|(df, _, _)| df)
+}
+
+fn fetch(
+    path: &Path,
+    options: &Kafka,
+    format: Option<&str>,
+) -> MlPrepResult<(DataFrame, usize, Option<Consumer>)> {
+    let (brokers, topic) = parse_uri(path)?;
+    let max_wait =
+        crate::dsl::parse_duration(options.max_wait.as_deref().unwrap_or(DEFAULT_MAX_WAIT))?;
+    let avro_schema = options
+        .avro_schema
+        .as_ref()
+        .map(|schema| std::fs::read_to_string(schema).map_err(MlPrepError::IoError))
+        .transpose()?;
+    // Check the options before connecting
+    let format = format.unwrap_or("json");
+    decode(&[], format, avro_schema.as_deref())?;
+
+    let (payloads, consumer) = poll(&brokers, &topic, options, max_wait)?;
+    tracing::info!(
+        "Consumed {} messages from Kafka topic {}",
+        payloads.len(),
+        topic
+    );
+    if payloads.is_empty() {
+        return Err(MlPrepError::IoError(std::io::Error::new(
+            std::io::ErrorKind::TimedOut,
+            format!(
+                "No messages arrived on Kafka topic {} within {:?}",
+                topic, max_wait
+            ),
+        )));
+    }
+    let df = decode(&payloads, format, avro_schema.as_deref())?;
+    Ok((df, payloads.len(), consumer))
+}
+
+#[cfg(feature = "kafka")]
+fn kafka_error(e: ::kafka::Error) -> MlPrepError {
+    match e {
+        ::kafka::Error::Io(e) => MlPrepError::IoError(e),
+        e => MlPrepError::IoError(std::io::Error::other(format!("Kafka: {}", e))),
+    }
+}
+
+#[cfg(feature = "kafka")]
+fn commit_consumed(consumer: &mut Consumer, messages: usize) -> MlPrepResult<()> {
+    consumer.commit_consumed().map_err(kafka_error)?;
+    tracing::info!(
+        "Committed {} Kafka messages for group {}",
+        messages,
+        consumer.group()
+    );
+    Ok(())
+}
+
+#[cfg(not(feature = "kafka"))]
+fn commit_consumed(consumer: &mut Consumer, _messages: usize) -> MlPrepResult<()> {
+    match *consumer {}
+}
+
+/// Read messages until `max_messages` arrived or `max_wait` passed. The consumer is
+/// returned when a group commits the offsets of what was read.
+#[cfg(feature = "kafka")]
+fn poll(
+    brokers: &[String],
+    topic: &str,
+    options: &Kafka,
+    max_wait: std::time::Duration,
+) -> MlPrepResult<(Vec<Vec<u8>>, Option<Consumer>)> {
+    use ::kafka::consumer::{FetchOffset, GroupOffsetStorage};
+
+    let start = match options.start.as_deref().unwrap_or("earliest") {
+        "earliest" => FetchOffset::Earliest,
+        "latest" => FetchOffset::Latest,
+        other => {
+            return Err(invalid(format!(
+                "Invalid Kafka start '{}': expected earliest or latest",
+                other
+            )))
+        }
+    };
+    let mut builder = Consumer::from_hosts(brokers.to_vec())
+        .with_topic(topic.to_string())
+        .with_fallback_offset(start)
+        .with_client_id("mlprep".to_string());
+    if let Some(group) = &options.group {
+        builder = builder
+            .with_group(group.clone())
+            .with_offset_storage(Some(GroupOffsetStorage::Kafka));
+    }
+    let mut consumer = builder.create().map_err(kafka_error)?;
+
+    let limit = options.max_messages.unwrap_or(usize::MAX);
+    let deadline = std::time::Instant::now() + max_wait;
+    let mut payloads = Vec::new();
+    'poll: while payloads.len() < limit && std::time::Instant::now() < deadline {
+        let sets = consumer.poll().map_err(kafka_error)?;
+        for set in sets.iter() {
+            for message in set.messages() {
+                if payloads.len() == limit {
+                    break 'poll;
+                }
+                payloads.push(message.value.to_vec());
+                consumer
+                    .consume_message(set.topic(), set.partition(), message.offset)
+                    .map_err(kafka_error)?;
+            }
+        }
+    }
+    Ok((payloads, options.group.is_some().then_some(consumer)))
+}
+
+#[cfg(not(feature = "kafka"))]
+fn poll(
+    _brokers: &[String],
+    _topic: &str,
+    _options: &Kafka,
+    _max_wait: std::time::Duration,
+) -> MlPrepResult<(Vec<Vec<u8>>, Option<Consumer>)> {
+    Err(MlPrepError::FeatureError(
+        "Kafka inputs are unavailable: mlprep was built without the `kafka` feature".to_string(),
+    ))
+}
+
+/// Decode message payloads into a frame, in message order: one JSON object per message,
+/// or Avro container files or bare records of `avro_schema`.
+pub fn decode(
+    payloads: &[Vec<u8>],
+    format: &str,
+    avro_schema: Option<&str>,
+) -> MlPrepResult<DataFrame> {
+    match format {
+        "json" => {
+            if payloads.is_empty() {
+                return Ok(DataFrame::empty());
+            }
+            let mut lines = Vec::new();
+            for payload in payloads {
+                lines.extend(payload.trim_ascii());
+                lines.push(b'\n');
+            }
+            JsonReader::new(Cursor::new(lines))
+                .with_json_format(JsonFormat::JsonLines)
+                .finish()
+                .map_err(MlPrepError::PolarsError)
+        }
+        "avro" => {
+            // Runs of bare records are decoded together, keeping the messages' order
+            let mut frames = Vec::new();
+            for run in payloads.chunk_by(|a, b| is_container(a) == is_container(b)) {
+                if is_container(&run[0]) {
+                    for file in run {
+                        frames.push(read_avro(file.clone())?);
+                    }
+                    continue;
+                }
+                let schema = avro_schema.ok_or_else(|| {
+                    invalid(
+                        "Kafka messages with bare Avro records need kafka.avro_schema".to_string(),
+                    )
+                })?;
+                let records: Vec<&Vec<u8>> = run.iter().collect();
+                frames.push(read_avro(container(schema, &records))?);
+            }
+            let mut frames = frames.into_iter();
+            let Some(mut df) = frames.next() else {
+                return Ok(DataFrame::empty());
+            };
+            for frame in frames {
+                df.vstack_mut(&frame).map_err(MlPrepError::PolarsError)?;
+            }
+            Ok(df)
+        }
+        other => Err(invalid(format!(
+            "Unsupported Kafka payload format '{}': expected json or avro",
+            other
+        ))),
+    }
+}
+
+fn is_container(payload: &[u8]) -> bool {
+    payload.starts_with(AVRO_MAGIC)
+}
+
+fn read_avro(bytes: Vec<u8>) -> MlPrepResult<DataFrame> {
+    AvroReader::new(Cursor::new(bytes))
+        .finish()
+        .map_err(MlPrepError::PolarsError)
+}
+
+/// An Avro object container file holding `records`, each encoded with `schema`.
+fn container(schema: &str, records: &[&Vec<u8>]) -> Vec<u8> {
+    fn long(out: &mut Vec<u8>, n: i64) {
+        let mut z = ((n << 1) ^ (n >> 63)) as u64;
+        while z >= 0x80 {
+            out.push((z as u8 & 0x7f) | 0x80);
+            z >>= 7;
+        }
+        out.push(z as u8);
+    }
+    fn bytes(out: &mut Vec<u8>, value: &[u8]) {
+        long(out, value.len() as i64);
+        out.extend(value);
+    }
+
+    let sync = [0x5au8; 16];
+    let mut out = AVRO_MAGIC.to_vec();
+    long(&mut out, 1);
+    bytes(&mut out, b"avro.schema");
+    bytes(&mut out, schema.as_bytes());
+    long(&mut out, 0);
+    out.extend(sync);
+    long(&mut out, records.len() as i64);
+    long(
+        &mut out,
+        records.iter().map(|record| record.len()).sum::<usize>() as i64,
+    );
+    for record in records {
+        out.extend(record.iter());
+    }
+    out.extend(sync);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_uri() {
+        let (brokers, topic) = parse_uri(Path::new("kafka://a:9092,b:9092/orders")).unwrap();
+        assert_eq!(brokers, ["a:9092", "b:9092"]);
+        assert_eq!(topic, "orders");
+        assert!(is_kafka_uri(Path::new("kafka://a:9092/orders")));
+        assert!(!is_kafka_uri(Path::new("s3://bucket/orders")));
+        assert!(parse_uri(Path::new("kafka://a:9092")).is_err());
+        assert!(parse_uri(Path::new("kafka:///orders")).is_err());
+        assert!(parse_uri(Path::new("kafka://a:9092/orders/x")).is_err());
+    }
+
+    #[test]
+    fn test_decode_json() {
+        let payloads = vec![
+            br#"{"id": 1, "amount": 2.5}"#.to_vec(),
+            b" {\"id\": 2, \"amount\": 4.0}\n".to_vec(),
+        ];
+        let df = decode(&payloads, "json", None).unwrap();
+        assert_eq!(df.height(), 2);
+        assert_eq!(df.column("id").unwrap().i64().unwrap().get(1), Some(2));
+        assert!(decode(&payloads, "csv", None).is_err());
+    }
+
+    #[test]
+    fn test_decode_avro() {
+        let schema = r#"{"type": "record", "name": "order", "fields": [
+            {"name": "id", "type": "long"}, {"name": "country", "type": "string"}]}"#;
+        // Avro longs are zigzag varints and strings are length-prefixed
+        let record = |id: u8, country: &str| {
+            let mut bytes = vec![id * 2, country.len() as u8 * 2];
+            bytes.extend(country.as_bytes());
+            bytes
+        };
+        let records = vec![record(1, "JP"), record(2, "US")];
+        let df = decode(&records, "avro", Some(schema)).unwrap();
+        assert_eq!(df.height(), 2);
+        assert_eq!(
+            df.column("country").unwrap().str().unwrap().get(1),
+            Some("US")
+        );
+        assert!(decode(&records, "avro", None).is_err());
+
+        // Messages may also be container files of their own, between bare records
+        let mut file = Vec::new();
+        polars::io::avro::AvroWriter::new(&mut file)
+            .finish(&mut df!("id" => [2i64], "country" => ["DE"]).unwrap())
+            .unwrap();
+        let payloads = vec![record(1, "JP"), file, record(3, "US")];
+        let df = decode(&payloads, "avro", Some(schema)).unwrap();
+        assert_eq!(df.height(), 3);
+        assert_eq!(
+            df.column("id")
+                .unwrap()
+                .i64()
+                .unwrap()
+                .into_iter()
+                .collect::<Vec<_>>(),
+            [Some(1), Some(2), Some(3)]
+        );
+    }
+}
@@ -1,19 +1,58 @@
+pub mod bench;
+pub mod builder;
+pub mod catalog;
+pub mod chunked;
 pub mod compute;
+pub mod contract;
+pub mod control;
+pub mod data_diff;
 pub mod dsl;
 pub mod engine;
 pub mod errors;
+pub mod export;
+pub mod feast;
 pub mod features;
+pub mod iceberg;
+pub mod input_cache;
+pub mod inspect;
 pub mod io;
+pub mod join_cache;
+pub mod kafka;
+pub mod lineage;
+pub mod manifest;
+pub mod memory;
+pub mod migrate;
+pub mod notify;
 pub mod observability;
+pub mod onnx;
+pub mod repl;
+pub mod run_diff;
 pub mod runner;
+pub mod scaffold;
 pub mod security;
+pub mod serve;
+pub mod snapshot;
+pub mod sql_condition;
+pub mod sql_query;
+pub mod synth;
+pub mod testing;
+pub mod udf;
 pub mod validate;
+pub mod view;
+pub mod watch;
+
+mod optimizer;
 
 use polars::prelude::*;
-use pyo3::exceptions::{PyIOError, PyRuntimeError};
+use pyo3::exceptions::{PyIOError, PyRuntimeError, PyTypeError, PyValueError};
 use pyo3::prelude::*;
+use pyo3::types::PyDict;
 use pyo3_polars::PyDataFrame;
+use serde_json::json;
+use std::collections::HashMap;
 use std::path::PathBuf;
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::time::Duration;
 use uuid::Uuid;
 
 /// Wrapper for DataFrame that exposes it to Python
@@ -31,6 +70,11 @@ impl MlPrepDataFrame {
         Ok(py_df.into_pyobject(py)?.into_any().unbind())
     }
 
+    /// Convert to a pandas DataFrame (through Arrow; requires pandas and pyarrow)
+    fn to_pandas(&self, py: Python<'_>) -> PyResult<PyObject> {
+        to_pandas(py, self.inner.clone())
+    }
+
     fn __len__(&self) -> usize {
         self.inner.height()
     }
@@ -44,9 +88,16 @@ impl MlPrepDataFrame {
     }
 }
 
-/// Read a CSV file and return a DataFrame
+/// Read a CSV file and return a DataFrame. A pandas DataFrame is accepted in place
+/// of a path and converted through Arrow.
 #[pyfunction]
-fn read_csv(path: &str) -> PyResult<MlPrepDataFrame> {
+fn read_csv(source: &Bound<'_, PyAny>) -> PyResult<MlPrepDataFrame> {
+    if is_pandas(source) {
+        return Ok(MlPrepDataFrame {
+            inner: extract_frame(source)?,
+        });
+    }
+    let path: PathBuf = source.extract()?;
     let lf =
         io::read_csv(path).map_err(|e| PyIOError::new_err(format!("Failed to read CSV: {}", e)))?;
     let df = lf
@@ -55,9 +106,16 @@ fn read_csv(path: &str) -> PyResult<MlPrepDataFrame> {
     Ok(MlPrepDataFrame { inner: df })
 }
 
-/// Read a Parquet file and return a DataFrame
+/// Read a Parquet file and return a DataFrame. A pandas DataFrame is accepted in
+/// place of a path and converted through Arrow.
 #[pyfunction]
-fn read_parquet(path: &str) -> PyResult<MlPrepDataFrame> {
+fn read_parquet(source: &Bound<'_, PyAny>) -> PyResult<MlPrepDataFrame> {
+    if is_pandas(source) {
+        return Ok(MlPrepDataFrame {
+            inner: extract_frame(source)?,
+        });
+    }
+    let path: PathBuf = source.extract()?;
     let lf = io::read_parquet(path)
         .map_err(|e| PyIOError::new_err(format!("Failed to read Parquet: {}", e)))?;
     let df = lf
@@ -66,40 +124,763 @@ fn read_parquet(path: &str) -> PyResult<MlPrepDataFrame> {
     Ok(MlPrepDataFrame { inner: df })
 }
 
-/// Write a DataFrame to a Parquet file
+/// Write a DataFrame (mlprep, Polars or pandas) to a Parquet file
 #[pyfunction]
-fn write_parquet(df: &MlPrepDataFrame, path: &str) -> PyResult<()> {
-    io::write_parquet(df.inner.clone(), path)
+fn write_parquet(df: &Bound<'_, PyAny>, path: PathBuf) -> PyResult<()> {
+    io::write_parquet(extract_frame(df)?, path)
         .map_err(|e| PyIOError::new_err(format!("Failed to write Parquet: {}", e)))?;
     Ok(())
 }
 
-/// Run a pipeline from a YAML configuration file path
-#[pyfunction(signature = (path, streaming=None, memory_limit=None))]
+fn is_pandas(obj: &Bound<'_, PyAny>) -> bool {
+    obj.get_type()
+        .module()
+        .is_ok_and(|m| m.to_string_lossy().starts_with("pandas"))
+}
+
+/// Accept an mlprep, Polars or pandas DataFrame; pandas frames are converted through Arrow.
+fn extract_frame(obj: &Bound<'_, PyAny>) -> PyResult<DataFrame> {
+    if let Ok(df) = obj.downcast::<MlPrepDataFrame>() {
+        return Ok(df.borrow().inner.clone());
+    }
+    let polars_df = if is_pandas(obj) {
+        obj.py()
+            .import("polars")?
+            .call_method1("from_pandas", (obj,))?
+    } else {
+        obj.clone()
+    };
+    polars_df
+        .extract::<PyDataFrame>()
+        .map(|df| df.0)
+        .map_err(|_| {
+            PyTypeError::new_err(format!(
+                "Expected a Polars or pandas DataFrame, got {}",
+                obj.get_type()
+            ))
+        })
+}
+
+fn to_pandas(py: Python<'_>, df: DataFrame) -> PyResult<PyObject> {
+    let polars_df = PyDataFrame(df).into_pyobject(py)?;
+    Ok(polars_df.call_method0("to_pandas")?.unbind())
+}
+
+/// Outcome of `run_pipeline`: what was read and written, how long each stage took
+/// and where the lineage file went.
+#[pyclass(name = "RunResult")]
+pub struct PyRunResult {
+    #[pyo3(get)]
+    run_id: String,
+    #[pyo3(get)]
+    pipeline: String,
+    #[pyo3(get)]
+    status: String,
+    #[pyo3(get)]
+    rows_read: usize,
+    #[pyo3(get)]
+    rows_written: usize,
+    #[pyo3(get)]
+    violations: usize,
+    #[pyo3(get)]
+    attempts: u32,
+    #[pyo3(get)]
+    duration_ms: u64,
+    #[pyo3(get)]
+    step_durations_ms: HashMap<String, u64>,
+    #[pyo3(get)]
+    outputs: Vec<String>,
+    #[pyo3(get)]
+    lineage: Option<String>,
+    /// Weighted pass rate of the validation checks, from 0 to 1; None without checks
+    #[pyo3(get)]
+    quality_score: Option<f64>,
+    summary: observability::RunSummary,
+}
+
+impl From<observability::RunSummary> for PyRunResult {
+    fn from(summary: observability::RunSummary) -> Self {
+        Self {
+            run_id: summary.run_id.clone(),
+            pipeline: summary.pipeline.clone(),
+            status: match summary.status {
+                observability::RunStatus::Success => "success",
+                observability::RunStatus::Failed => "failed",
+            }
+            .to_string(),
+            rows_read: summary.rows_read,
+            rows_written: summary.rows_written,
+            violations: summary.violations,
+            attempts: summary.attempts,
+            duration_ms: summary.duration_ms,
+            step_durations_ms: summary.step_durations_ms.clone(),
+            outputs: summary.outputs.clone(),
+            lineage: summary.lineage.clone(),
+            quality_score: summary.quality_score,
+            summary,
+        }
+    }
+}
+
+fn to_py_json<'py, T: serde::Serialize>(py: Python<'py>, value: &T) -> PyResult<Bound<'py, PyAny>> {
+    let json = serde_json::to_string(value)
+        .map_err(|e| PyRuntimeError::new_err(format!("Failed to serialize result: {}", e)))?;
+    py.import("json")?.call_method1("loads", (json,))
+}
+
+#[pymethods]
+impl PyRunResult {
+    /// The run summary as a JSON-compatible dict (same shape as `--output-format json`)
+    fn to_dict<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        to_py_json(py, &self.summary)
+    }
+
+    /// Problems that did not stop the run, as dicts with `step`, `kind`, `code`,
+    /// `message` and `count`
+    #[getter]
+    fn warnings<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        to_py_json(py, &self.summary.warnings)
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "RunResult(run_id={:?}, status={:?}, rows_written={}, violations={}, duration_ms={})",
+            self.run_id, self.status, self.rows_written, self.violations, self.duration_ms
+        )
+    }
+}
+
+/// Run a pipeline from a YAML configuration file path.
+///
+/// `udfs` maps names used by `python_udf` steps to callables taking and returning a
+/// Polars DataFrame. `progress` is called with a dict per progress event
+/// (`{"event": "stage" | "step" | "rows", ...}`) on the calling thread.
+/// Returns a `RunResult`; failed runs raise `RuntimeError`.
+///
+/// The pipeline runs on a worker thread while the GIL is released; Ctrl-C
+/// (KeyboardInterrupt) cancels the run at the next stage, step or streamed chunk.
+#[pyfunction(signature = (path, streaming=None, memory_limit=None, udfs=None, progress=None))]
 fn run_pipeline(
+    py: Python<'_>,
     path: String,
     streaming: Option<bool>,
     memory_limit: Option<String>,
-) -> PyResult<()> {
+    udfs: Option<HashMap<String, PyObject>>,
+    progress: Option<PyObject>,
+) -> PyResult<PyRunResult> {
     let path_buf = PathBuf::from(path);
     let run_id = Uuid::new_v4();
-    // Default security config for Python usage (no restrictions for now)
-    let security_config = crate::security::SecurityConfig {
+    let runtime = runtime_override(streaming, memory_limit, udfs);
+
+    let (events_tx, events_rx) = mpsc::channel();
+    let control = control::RunControl::new(progress.is_some().then_some(events_tx));
+    let (done_tx, mut done_rx) = mpsc::channel();
+    let worker_control = control.clone();
+    let worker = std::thread::spawn(move || {
+        let _guard = control::install(worker_control);
+        let result =
+            runner::execution_pipeline(&path_buf, run_id, python_security_config(), runtime);
+        let _ = done_tx.send(result);
+    });
+
+    let error = loop {
+        let done = &mut done_rx;
+        let received = py.allow_threads(move || done.recv_timeout(Duration::from_millis(100)));
+        if let Some(callback) = &progress {
+            let delivered = events_rx
+                .try_iter()
+                .try_for_each(|event| callback.call1(py, (progress_dict(py, &event)?,)).map(drop));
+            if let Err(e) = delivered {
+                break e;
+            }
+        }
+        match received {
+            Ok(result) => {
+                let summary = result.map_err(|e| {
+                    PyRuntimeError::new_err(format!("Pipeline execution failed: {}", e))
+                })?;
+                return Ok(summary.into());
+            }
+            Err(RecvTimeoutError::Timeout) => {
+                if let Err(e) = py.check_signals() {
+                    break e;
+                }
+            }
+            Err(RecvTimeoutError::Disconnected) => {
+                return Err(PyRuntimeError::new_err(
+                    "Pipeline execution failed: worker thread panicked",
+                ));
+            }
+        }
+    };
+    // Wait for the cancelled worker to stop before returning, so nothing is written and
+    // no UDF is called once Python has moved on.
+    control.cancel();
+    let _ = py.allow_threads(|| worker.join());
+    Err(error)
+}
+
+fn progress_dict<'py>(py: Python<'py>, event: &control::Progress) -> PyResult<Bound<'py, PyDict>> {
+    let dict = PyDict::new(py);
+    match event {
+        control::Progress::Stage(stage) => {
+            dict.set_item("event", "stage")?;
+            dict.set_item("stage", stage)?;
+        }
+        control::Progress::Step { index, total, kind } => {
+            dict.set_item("event", "step")?;
+            dict.set_item("index", index)?;
+            dict.set_item("total", total)?;
+            dict.set_item("step", kind)?;
+        }
+        control::Progress::Rows(rows) => {
+            dict.set_item("event", "rows")?;
+            dict.set_item("rows", rows)?;
+        }
+    }
+    Ok(dict)
+}
+
+/// Apply a pipeline to an in-memory Polars or pandas DataFrame and return the result
+/// as the same kind of frame. No files are read or written: `inputs` and `outputs`
+/// in the pipeline are ignored.
+///
+/// `pipeline` is a YAML string, a dict with the same structure or an `mlprep.Pipeline`.
+#[pyfunction(signature = (df, pipeline, streaming=None, memory_limit=None, udfs=None))]
+fn run_pipeline_on(
+    py: Python<'_>,
+    df: &Bound<'_, PyAny>,
+    pipeline: &Bound<'_, PyAny>,
+    streaming: Option<bool>,
+    memory_limit: Option<String>,
+    udfs: Option<HashMap<String, PyObject>>,
+) -> PyResult<PyObject> {
+    let pipeline = if let Ok(builder) = pipeline.downcast::<MlPrepPipeline>() {
+        builder.borrow().inner.clone()
+    } else if let Ok(yaml) = pipeline.extract::<String>() {
+        parse_pipeline(&yaml)?
+    } else if pipeline.is_instance_of::<PyDict>() {
+        // JSON is valid YAML, so a dict goes through the same parser as a YAML string.
+        parse_pipeline(&to_json_string(pipeline)?)?
+    } else {
+        return Err(PyTypeError::new_err(
+            "pipeline must be a YAML string, a dict or an mlprep.Pipeline",
+        ));
+    };
+    run_on_frame(py, df, pipeline, streaming, memory_limit, udfs)
+}
+
+fn run_on_frame(
+    py: Python<'_>,
+    df: &Bound<'_, PyAny>,
+    pipeline: dsl::Pipeline,
+    streaming: Option<bool>,
+    memory_limit: Option<String>,
+    udfs: Option<HashMap<String, PyObject>>,
+) -> PyResult<PyObject> {
+    let return_pandas = is_pandas(df);
+    let input = extract_frame(df)?;
+
+    let runtime = runtime_override(streaming, memory_limit, udfs);
+    let output = py
+        .allow_threads(|| {
+            runner::execute_in_memory(input, pipeline, python_security_config(), runtime)
+        })
+        .map_err(|e| PyRuntimeError::new_err(format!("Pipeline execution failed: {}", e)))?;
+
+    if return_pandas {
+        to_pandas(py, output)
+    } else {
+        Ok(PyDataFrame(output).into_pyobject(py)?.into_any().unbind())
+    }
+}
+
+fn parse_pipeline(yaml: &str) -> PyResult<dsl::Pipeline> {
+    dsl::Pipeline::from_reader(yaml.as_bytes())
+        .map_err(|e| PyValueError::new_err(format!("Invalid pipeline: {}", e)))
+}
+
+fn to_json_string(obj: &Bound<'_, PyAny>) -> PyResult<String> {
+    obj.py()
+        .import("json")?
+        .call_method1("dumps", (obj,))?
+        .extract::<String>()
+}
+
+fn to_json(obj: &Bound<'_, PyAny>) -> PyResult<serde_json::Value> {
+    serde_json::from_str(&to_json_string(obj)?)
+        .map_err(|e| PyValueError::new_err(format!("Invalid value: {}", e)))
+}
+
+/// Accept a single column name or a list of names.
+fn column_list(obj: &Bound<'_, PyAny>) -> PyResult<Vec<String>> {
+    match obj.extract::<String>() {
+        Ok(column) => Ok(vec![column]),
+        Err(_) => obj.extract::<Vec<String>>(),
+    }
+}
+
+/// Fluent builder producing the same structure as a pipeline YAML file.
+///
+/// Every method returns a new builder, so partial pipelines can be reused:
+/// `Pipeline().input("data.csv").filter("a > 1").select(["a", "b"])`.
+#[pyclass(name = "Pipeline")]
+#[derive(Clone, Default)]
+pub struct MlPrepPipeline {
+    inner: dsl::Pipeline,
+}
+
+impl MlPrepPipeline {
+    fn with_step(&self, step: serde_json::Value) -> PyResult<Self> {
+        let step: dsl::Step = serde_json::from_value(step)
+            .map_err(|e| PyValueError::new_err(format!("Invalid step: {}", e)))?;
+        let mut next = self.clone();
+        next.inner.steps.push(step);
+        Ok(next)
+    }
+}
+
+#[pymethods]
+impl MlPrepPipeline {
+    #[new]
+    fn new() -> Self {
+        Self::default()
+    }
+
+    /// Build a pipeline from YAML text
+    #[staticmethod]
+    fn from_yaml(yaml: &str) -> PyResult<Self> {
+        Ok(Self {
+            inner: parse_pipeline(yaml)?,
+        })
+    }
+
+    /// Add an input file
+    #[pyo3(signature = (path, format=None))]
+    fn input(&self, path: String, format: Option<String>) -> Self {
+        let mut next = self.clone();
+        next.inner.inputs.push(dsl::Input {
+            path: path.into(),
+            format,
+            schema: None,
+            infer_rows: None,
+            null_values: None,
+            contract: None,
+            manifest: None,
+            iceberg: None,
+            kafka: None,
+        });
+        next
+    }
+
+    /// Add an output file; `mode` is `overwrite`, `append` or `error_if_exists`
+    #[pyo3(signature = (path, format=None, mode=None))]
+    fn output(&self, path: String, format: Option<String>, mode: Option<String>) -> PyResult<Self> {
+        let mode = match mode {
+            Some(mode) => serde_json::from_value(serde_json::Value::String(mode))
+                .map_err(|e| PyValueError::new_err(format!("Invalid output mode: {}", e)))?,
+            None => dsl::OutputMode::default(),
+        };
+        let mut next = self.clone();
+        next.inner.outputs.push(dsl::Output {
+            path: path.into(),
+            format,
+            compression: None,
+            partition_by: None,
+            mode,
+            feast: None,
+            max_rows_per_file: None,
+            max_file_size: None,
+        });
+        Ok(next)
+    }
+
+    fn select(&self, columns: &Bound<'_, PyAny>) -> PyResult<Self> {
+        self.with_step(json!({ "type": "select", "columns": column_list(columns)? }))
+    }
+
+    /// Keep rows matching a SQL condition, e.g. `"a > 1 AND b IS NOT NULL"`
+    fn filter(&self, condition: String) -> PyResult<Self> {
+        self.with_step(json!({ "type": "filter", "condition": condition }))
+    }
+
+    /// Cast columns, e.g. `{"a": "float64"}`
+    fn cast(&self, columns: HashMap<String, String>) -> PyResult<Self> {
+        self.with_step(json!({ "type": "cast", "columns": columns }))
+    }
+
+    #[pyo3(signature = (by, descending=None))]
+    fn sort(&self, by: &Bound<'_, PyAny>, descending: Option<&Bound<'_, PyAny>>) -> PyResult<Self> {
+        let descending: Vec<bool> = match descending {
+            Some(d) => match d.extract::<bool>() {
+                Ok(flag) => vec![flag],
+                Err(_) => d.extract()?,
+            },
+            None => Vec::new(),
+        };
+        self.with_step(json!({
+            "type": "sort",
+            "by": column_list(by)?,
+            "descending": descending,
+        }))
+    }
+
+    /// Join with another file; `right_on` defaults to `left_on`
+    #[pyo3(signature = (right_path, left_on, right_on=None, how="inner"))]
+    fn join(
+        &self,
+        right_path: String,
+        left_on: &Bound<'_, PyAny>,
+        right_on: Option<&Bound<'_, PyAny>>,
+        how: &str,
+    ) -> PyResult<Self> {
+        let left_on = column_list(left_on)?;
+        let right_on = match right_on {
+            Some(r) => column_list(r)?,
+            None => left_on.clone(),
+        };
+        self.with_step(json!({
+            "type": "join",
+            "right_path": right_path,
+            "left_on": left_on,
+            "right_on": right_on,
+            "how": how,
+        }))
+    }
+
+    /// Aggregate per group; `aggs` is a list of `{"column": ..., "func": ..., "alias": ...}`,
+    /// or maps a column to a function name, `{"func": ..., "alias": ...}` or a list of these
+    fn group_by(&self, by: &Bound<'_, PyAny>, aggs: &Bound<'_, PyAny>) -> PyResult<Self> {
+        let mut aggs = to_json(aggs)?;
+        let func = |agg: &mut serde_json::Value| {
+            if let serde_json::Value::String(func) = agg {
+                *agg = json!({ "func": func });
+            }
+        };
+        if let Some(map) = aggs.as_object_mut() {
+            for agg in map.values_mut() {
+                match agg {
+                    serde_json::Value::Array(funcs) => funcs.iter_mut().for_each(func),
+                    agg => func(agg),
+                }
+            }
+        }
+        self.with_step(json!({ "type": "group_by", "by": column_list(by)?, "aggs": aggs }))
+    }
+
+    #[pyo3(signature = (columns, strategy, value=None))]
+    fn fill_null(
+        &self,
+        columns: &Bound<'_, PyAny>,
+        strategy: String,
+        value: Option<&Bound<'_, PyAny>>,
+    ) -> PyResult<Self> {
+        let value = value.map(to_json).transpose()?;
+        self.with_step(json!({
+            "type": "fill_null",
+            "columns": column_list(columns)?,
+            "strategy": strategy,
+            "value": value,
+        }))
+    }
+
+    fn drop_null(&self, columns: &Bound<'_, PyAny>) -> PyResult<Self> {
+        self.with_step(json!({ "type": "drop_null", "columns": column_list(columns)? }))
+    }
+
+    fn limit(&self, n: usize) -> PyResult<Self> {
+        self.with_step(json!({ "type": "limit", "n": n }))
+    }
+
+    #[pyo3(signature = (offset, length=None))]
+    fn slice(&self, offset: i64, length: Option<usize>) -> PyResult<Self> {
+        self.with_step(json!({ "type": "slice", "offset": offset, "length": length }))
+    }
+
+    /// Run a SELECT over the current frame, registered as `df`
+    fn sql(&self, query: String) -> PyResult<Self> {
+        self.with_step(json!({ "type": "sql", "query": query }))
+    }
+
+    #[pyo3(signature = (function, columns=None))]
+    fn python_udf(&self, function: String, columns: Option<&Bound<'_, PyAny>>) -> PyResult<Self> {
+        let columns = columns.map(column_list).transpose()?;
+        self.with_step(json!({ "type": "python_udf", "function": function, "columns": columns }))
+    }
+
+    /// Append any step given as a dict with the YAML structure, e.g.
+    /// `{"type": "features", "config": {...}}`
+    fn step(&self, step: &Bound<'_, PyAny>) -> PyResult<Self> {
+        self.with_step(to_json(step)?)
+    }
+
+    /// Serialize to pipeline YAML (unset options are omitted)
+    fn to_yaml(&self) -> PyResult<String> {
+        self.inner
+            .to_yaml()
+            .map_err(|e| PyValueError::new_err(format!("Failed to serialize pipeline: {}", e)))
+    }
+
+    /// Write the pipeline YAML to `path`
+    fn save(&self, path: PathBuf) -> PyResult<()> {
+        std::fs::write(&path, self.to_yaml()?)
+            .map_err(|e| PyIOError::new_err(format!("Failed to write pipeline: {}", e)))
+    }
+
+    /// Apply the steps to an in-memory Polars or pandas DataFrame (see `run_pipeline_on`)
+    #[pyo3(signature = (df, streaming=None, memory_limit=None, udfs=None))]
+    fn collect(
+        &self,
+        py: Python<'_>,
+        df: &Bound<'_, PyAny>,
+        streaming: Option<bool>,
+        memory_limit: Option<String>,
+        udfs: Option<HashMap<String, PyObject>>,
+    ) -> PyResult<PyObject> {
+        run_on_frame(py, df, self.inner.clone(), streaming, memory_limit, udfs)
+    }
+
+    /// Save the pipeline to `path` and run it like `run_pipeline(path)`, so the YAML
+    /// that produced the outputs (and lineage next to it) is kept
+    #[pyo3(signature = (path, streaming=None, memory_limit=None, udfs=None, progress=None))]
+    fn run(
+        &self,
+        py: Python<'_>,
+        path: String,
+        streaming: Option<bool>,
+        memory_limit: Option<String>,
+        udfs: Option<HashMap<String, PyObject>>,
+        progress: Option<PyObject>,
+    ) -> PyResult<PyRunResult> {
+        self.save(PathBuf::from(&path))?;
+        run_pipeline(py, path, streaming, memory_limit, udfs, progress)
+    }
+
+    fn __len__(&self) -> usize {
+        self.inner.steps.len()
+    }
+
+    fn __repr__(&self) -> PyResult<String> {
+        self.to_yaml()
+    }
+}
+
+/// Outcome of one configured column check.
+#[pyclass(name = "CheckResult", get_all)]
+#[derive(Clone)]
+pub struct PyCheckResult {
+    column: String,
+    check: String,
+    passed: bool,
+    count: usize,
+    message: Option<String>,
+}
+
+#[pymethods]
+impl PyCheckResult {
+    fn __repr__(&self) -> String {
+        format!(
+            "CheckResult(column={:?}, check={:?}, passed={}, count={})",
+            self.column,
+            self.check,
+            if self.passed { "True" } else { "False" },
+            self.count
+        )
+    }
+}
+
+/// Result of `mlprep.validate`: per-check outcomes plus the rows a pipeline step
+/// would pass on (`valid`) and, in quarantine mode, the violating rows.
+#[pyclass(name = "ValidationResult", get_all)]
+pub struct PyValidationResult {
+    passed: bool,
+    total_violations: usize,
+    checks: Vec<PyCheckResult>,
+    valid: PyObject,
+    quarantine: Option<PyObject>,
+}
+
+#[pymethods]
+impl PyValidationResult {
+    fn __bool__(&self) -> bool {
+        self.passed
+    }
+
+    fn __repr__(&self) -> String {
+        let failed = self.checks.iter().filter(|c| !c.passed).count();
+        format!(
+            "ValidationResult(passed={}, checks={}, failed_checks={}, total_violations={})",
+            if self.passed { "True" } else { "False" },
+            self.checks.len(),
+            failed,
+            self.total_violations
+        )
+    }
+}
+
+/// Run the checks of a `validate` step on a Polars or pandas DataFrame.
+///
+/// `checks` is the step's `checks` block as a dict or YAML string
+/// (`{"columns": [{"name": "age", "range": [0, 120]}]}`); a list is taken as the
+/// `columns` entry. `mode` follows the step: `warn` (default here) only reports,
+/// `quarantine` also splits off violating rows, and `strict` raises `ValueError`
+/// when any check fails.
+#[pyfunction(name = "validate", signature = (df, checks, mode="warn", streaming=false))]
+fn validate_frame(
+    py: Python<'_>,
+    df: &Bound<'_, PyAny>,
+    checks: &Bound<'_, PyAny>,
+    mode: &str,
+    streaming: bool,
+) -> PyResult<PyValidationResult> {
+    let return_pandas = is_pandas(df);
+    let frame = extract_frame(df)?;
+
+    let yaml = match checks.extract::<String>() {
+        Ok(yaml) => yaml,
+        Err(_) => to_json_string(checks)?,
+    };
+    let mut value: serde_yaml::Value = serde_yaml::from_str(&yaml)
+        .map_err(|e| PyValueError::new_err(format!("Invalid checks: {}", e)))?;
+    if value.is_sequence() {
+        let mut map = serde_yaml::Mapping::new();
+        map.insert("columns".into(), value);
+        value = serde_yaml::Value::Mapping(map);
+    }
+    let config: dsl::CheckConfig = serde_yaml::from_value(value)
+        .map_err(|e| PyValueError::new_err(format!("Invalid checks: {}", e)))?;
+    let mode: dsl::ValidationMode = serde_yaml::from_str(mode)
+        .map_err(|e| PyValueError::new_err(format!("Invalid validation mode: {}", e)))?;
+
+    let lf = frame.lazy();
+    let report = validate::summarize_violations_lazy(lf.clone(), &config, streaming)
+        .map_err(|e| PyValueError::new_err(format!("Validation execution failed: {}", e)))?;
+    let labels = config
+        .columns
+        .iter()
+        .map(|check| {
+            (
+                check.name.clone(),
+                validate::check_label_suffix(check).to_string(),
+            )
+        })
+        .chain(
+            config
+                .conditions
+                .iter()
+                .map(|check| (check.label().to_string(), "condition".to_string())),
+        )
+        .chain(
+            validate::stat_checks(&config)
+                .into_iter()
+                .map(|(check, stat, _)| (check.name.clone(), format!("stats.{}", stat))),
+        )
+        .chain(
+            config
+                .unique_keys
+                .iter()
+                .map(|keys| (keys.join(", "), "unique_keys".to_string())),
+        );
+    let checks: Vec<PyCheckResult> = labels
+        .zip(&report.results)
+        .map(|((column, check), result)| {
+            let violation = result.violations.first();
+            PyCheckResult {
+                column,
+                check,
+                passed: result.passed,
+                count: violation.map_or(0, |v| v.count),
+                message: violation.map(|v| v.message.clone()),
+            }
+        })
+        .collect();
+
+    if mode == dsl::ValidationMode::Strict && !report.passed {
+        let details: Vec<String> = checks.iter().filter_map(|c| c.message.clone()).collect();
+        return Err(PyValueError::new_err(format!(
+            "Validation failed with {} violations: {}",
+            report.total_violations,
+            details.join("; ")
+        )));
+    }
+
+    let to_py = |df: DataFrame| -> PyResult<PyObject> {
+        if return_pandas {
+            to_pandas(py, df)
+        } else {
+            Ok(PyDataFrame(df).into_pyobject(py)?.into_any().unbind())
+        }
+    };
+    let collect = |lf: LazyFrame| -> PyResult<DataFrame> {
+        lf.with_streaming(streaming)
+            .collect()
+            .map_err(|e| PyRuntimeError::new_err(format!("Validation execution failed: {}", e)))
+    };
+    let mask = validate::violation_mask_expr(&config)
+        .map_err(|e| PyValueError::new_err(format!("Invalid checks: {}", e)))?;
+    let (valid, quarantine) = match (mode, mask) {
+        (dsl::ValidationMode::Quarantine, Some(mask)) => (
+            collect(lf.clone().filter(mask.clone().not()))?,
+            Some(to_py(collect(lf.filter(mask))?)?),
+        ),
+        _ => (collect(lf)?, None),
+    };
+
+    Ok(PyValidationResult {
+        passed: report.passed,
+        total_violations: report.total_violations,
+        checks,
+        valid: to_py(valid)?,
+        quarantine,
+    })
+}
+
+/// Default security config for Python usage (no restrictions for now)
+fn python_security_config() -> security::SecurityConfig {
+    security::SecurityConfig {
         allowed_paths: None,
         mask_columns: None,
-    };
-    let runtime_override = if streaming.unwrap_or(false) || memory_limit.is_some() {
-        Some(crate::dsl::RuntimeConfig {
+    }
+}
+
+fn runtime_override(
+    streaming: Option<bool>,
+    memory_limit: Option<String>,
+    udfs: Option<HashMap<String, PyObject>>,
+) -> Option<dsl::RuntimeConfig> {
+    let udfs = python_udfs(udfs);
+    if streaming.unwrap_or(false) || memory_limit.is_some() || !udfs.is_empty() {
+        Some(dsl::RuntimeConfig {
             streaming: streaming.unwrap_or(false),
             memory_limit,
+            udfs,
             ..Default::default()
         })
     } else {
         None
-    };
-    runner::execution_pipeline(&path_buf, run_id, security_config, runtime_override)
-        .map_err(|e| PyRuntimeError::new_err(format!("Pipeline execution failed: {}", e)))?;
-    Ok(())
+    }
+}
+
+/// The `udfs` of one run, wrapped for its `python_udf` steps.
+fn python_udfs(udfs: Option<HashMap<String, PyObject>>) -> udf::Udfs {
+    let mut wrapped = udf::Udfs::default();
+    for (name, callable) in udfs.unwrap_or_default() {
+        wrapped.insert(name.clone(), python_udf(name, callable));
+    }
+    wrapped
+}
+
+/// Wrap a Python callable as a UDF; it is invoked with the GIL held.
+fn python_udf(name: String, callable: PyObject) -> udf::UdfFn {
+    std::sync::Arc::new(move |df: DataFrame| {
+        Python::with_gil(|py| {
+            let result = callable.call1(py, (PyDataFrame(df),))?;
+            result.extract::<PyDataFrame>(py).map(|df| df.0)
+        })
+        .map_err(|e: PyErr| {
+            errors::MlPrepError::TransformError(format!("python_udf '{}' failed: {}", name, e))
+        })
+    })
 }
 
 /// A Python module implemented in Rust.
@@ -107,9 +888,15 @@ fn run_pipeline(
 fn mlprep(m: &Bound<'_, PyModule>) -> PyResult<()> {
     m.add("__version__", "0.3.0")?;
     m.add_class::<MlPrepDataFrame>()?;
+    m.add_class::<MlPrepPipeline>()?;
+    m.add_class::<PyCheckResult>()?;
+    m.add_class::<PyValidationResult>()?;
+    m.add_class::<PyRunResult>()?;
     m.add_function(wrap_pyfunction!(read_csv, m)?)?;
     m.add_function(wrap_pyfunction!(read_parquet, m)?)?;
     m.add_function(wrap_pyfunction!(write_parquet, m)?)?;
     m.add_function(wrap_pyfunction!(run_pipeline, m)?)?;
+    m.add_function(wrap_pyfunction!(run_pipeline_on, m)?)?;
+    m.add_function(wrap_pyfunction!(validate_frame, m)?)?;
     Ok(())
 }
@@ -0,0 +1,468 @@
+//! Column-level lineage: which input columns each output column is derived from.
+//!
+//! The provenance map is derived from the pipeline's steps alone, without running
+//! them: every step maps the sources of the columns it reads onto the columns it
+//! writes. Steps that only drop or reorder rows (filter, sort, limit, ...) leave the
+//! map unchanged. Columns whose names are only known after fitting (one-hot
+//! categories, PCA components) are tracked by name prefix and resolved against the
+//! output schema; `sql` and `python_udf` steps are opaque, so every column after them
+//! is attributed to every column they could read.
+
+use crate::dsl::{Agg, Join, Step};
+use crate::errors::{MlPrepError, MlPrepResult};
+use crate::features::{FeatureSpec, FeatureTransform};
+use crate::io;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+
+/// An input column, identified by the file it was read from.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct ColumnSource {
+    pub input: String,
+    pub column: String,
+}
+
+/// Provenance of one output column.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ColumnLineage {
+    pub column: String,
+    pub sources: Vec<ColumnSource>,
+}
+
+type Sources = BTreeSet<ColumnSource>;
+
+#[derive(Debug, Default)]
+struct Provenance {
+    /// Current columns, in frame order
+    columns: Vec<(String, Sources)>,
+    /// Columns whose exact names are unknown until the step runs, by name prefix
+    families: Vec<(String, Sources)>,
+}
+
+impl Provenance {
+    fn lookup(&self, name: &str) -> Sources {
+        if let Some((_, sources)) = self.columns.iter().find(|(column, _)| column == name) {
+            return sources.clone();
+        }
+        self.families
+            .iter()
+            .filter(|(prefix, _)| name.starts_with(prefix.as_str()))
+            .max_by_key(|(prefix, _)| prefix.len())
+            .map(|(_, sources)| sources.clone())
+            .unwrap_or_default()
+    }
+
+    fn lookup_all<'a>(&self, names: impl IntoIterator<Item = &'a String>) -> Sources {
+        names
+            .into_iter()
+            .flat_map(|name| self.lookup(name))
+            .collect()
+    }
+
+    fn set(&mut self, name: &str, sources: Sources) {
+        match self.columns.iter_mut().find(|(column, _)| column == name) {
+            Some(entry) => entry.1 = sources,
+            None => self.columns.push((name.to_string(), sources)),
+        }
+    }
+
+    fn names(&self) -> Vec<String> {
+        self.columns.iter().map(|(name, _)| name.clone()).collect()
+    }
+
+    /// Every source any current column could derive from.
+    fn all_sources(&self) -> Sources {
+        self.columns
+            .iter()
+            .chain(&self.families)
+            .flat_map(|(_, sources)| sources.iter().cloned())
+            .collect()
+    }
+
+    /// Replace the frame with `columns`, each derived from the named columns.
+    fn project(&mut self, columns: Vec<(String, Sources)>) {
+        self.columns = columns;
+        self.families.clear();
+    }
+
+    /// After an opaque step every column, known or not, may derive from `sources`.
+    fn opaque(&mut self, sources: Sources) {
+        self.columns.clear();
+        self.families = vec![(String::new(), sources)];
+    }
+}
+
+/// Derive the provenance of every output column of `steps` applied to `input`, whose
+/// columns are `input_columns`. `output_columns` (the resolved output schema) names
+/// columns created by fitted transforms; without it, the columns the steps name are
+/// reported.
+pub fn column_lineage(
+    input: &str,
+    input_columns: &[String],
+    steps: &[Step],
+    output_columns: Option<&[String]>,
+) -> MlPrepResult<Vec<ColumnLineage>> {
+    let mut provenance = Provenance::default();
+    for column in input_columns {
+        let source = ColumnSource {
+            input: input.to_string(),
+            column: column.clone(),
+        };
+        provenance.set(column, BTreeSet::from([source]));
+    }
+
+    for step in steps {
+        match step {
+            Step::Select(select) => {
+                let columns = select
+                    .columns
+                    .iter()
+                    .map(|name| (name.clone(), provenance.lookup(name)))
+                    .collect();
+                provenance.project(columns);
+            }
+            Step::Join(join) => apply_join(&mut provenance, join)?,
+            Step::GroupBy(group_by) => {
+                aggregate(&mut provenance, group_by.by.iter(), &group_by.aggs)
+            }
+            Step::Resample(resample) => aggregate(
+                &mut provenance,
+                std::iter::once(&resample.time_column).chain(&resample.by),
+                &resample.aggs,
+            ),
+            Step::Window(window) => {
+                let context =
+                    provenance.lookup_all(window.partition_by.iter().chain(&window.order_by));
+                for op in &window.ops {
+                    let mut sources = provenance.lookup(&op.column);
+                    sources.extend(context.iter().cloned());
+                    provenance.set(&op.alias, sources);
+                }
+            }
+            Step::FillNull(fill) => {
+                // Group-wise and interpolated fills also read the grouping/order columns.
+                let context = provenance.lookup_all(fill.over.iter().chain(&fill.order_by));
+                for column in &fill.columns {
+                    let mut sources = provenance.lookup(column);
+                    sources.extend(context.iter().cloned());
+                    provenance.set(column, sources);
+                }
+            }
+            Step::DatetimeOps(ops) => {
+                let sources = provenance.lookup(&ops.column);
+                provenance.set(ops.alias.as_ref().unwrap_or(&ops.column), sources);
+            }
+            Step::Features(features) => {
+                for spec in &features.config.features {
+                    apply_feature(&mut provenance, spec)?;
+                }
+            }
+            Step::PythonUdf(udf) => match &udf.columns {
+                // Only the listed columns reach the callable; its result is merged back.
+                Some(columns) => {
+                    let sources = provenance.lookup_all(columns);
+                    for column in columns {
+                        provenance.set(column, sources.clone());
+                    }
+                    provenance.families.push((String::new(), sources));
+                }
+                None => provenance.opaque(provenance.all_sources()),
+            },
+            Step::Sql(_) => provenance.opaque(provenance.all_sources()),
+            Step::Filter(_)
+            | Step::Cast(_)
+            | Step::Sort(_)
+            | Step::DropNull(_)
+            | Step::Validate(_)
+            | Step::FeatureSelect(_)
+            | Step::Limit(_)
+            | Step::Slice(_) => {}
+        }
+    }
+
+    let names = match output_columns {
+        Some(columns) => columns.to_vec(),
+        None => provenance.names(),
+    };
+    Ok(names
+        .into_iter()
+        .map(|column| ColumnLineage {
+            sources: provenance.lookup(&column).into_iter().collect(),
+            column,
+        })
+        .collect())
+}
+
+fn aggregate<'a>(
+    provenance: &mut Provenance,
+    keys: impl Iterator<Item = &'a String>,
+    aggs: &[Agg],
+) {
+    let mut columns: Vec<(String, Sources)> = keys
+        .map(|key| (key.clone(), provenance.lookup(key)))
+        .collect();
+    for agg in aggs {
+        columns.push((agg.output_name(aggs), provenance.lookup(&agg.column)));
+    }
+    provenance.project(columns);
+}
+
+/// Right-side columns join the frame as Polars names them: key columns are merged
+/// into the left keys (kept separately for full joins), and other names already on
+/// the left get a `_right` suffix.
+fn apply_join(provenance: &mut Provenance, join: &Join) -> MlPrepResult<()> {
+    let right = right_lineage(join)?;
+    let how = join.how.to_lowercase();
+    let source = |column: &str| -> Sources {
+        right
+            .iter()
+            .filter(|lineage| lineage.column == column)
+            .flat_map(|lineage| lineage.sources.iter().cloned())
+            .collect()
+    };
+
+    let left_names = provenance.names();
+    let keys_merged = how != "full" && how != "outer" && how != "cross";
+    if keys_merged {
+        for (left, right) in join.left_on.iter().zip(&join.right_on) {
+            let mut sources = provenance.lookup(left);
+            sources.extend(source(right));
+            provenance.set(left, sources);
+        }
+    }
+    for name in right.iter().map(|lineage| lineage.column.as_str()) {
+        if keys_merged && join.right_on.iter().any(|key| key == name) {
+            continue;
+        }
+        let output = if left_names.iter().any(|left| left == name) {
+            format!("{}_right", name)
+        } else {
+            name.to_string()
+        };
+        provenance.set(&output, source(name));
+    }
+    Ok(())
+}
+
+/// Columns of a join's right side, traced through the steps of an inline `right` pipeline.
+fn right_lineage(join: &Join) -> MlPrepResult<Vec<ColumnLineage>> {
+    let (path, format) = join.right_input()?;
+    let mut input = io::read_detected(path, format)?;
+    let input_columns: Vec<String> = input
+        .collect_schema()
+        .map_err(MlPrepError::PolarsError)?
+        .iter_names()
+        .map(|name| name.to_string())
+        .collect();
+    match &join.right {
+        Some(right) if !right.steps.is_empty() => {
+            let output_columns: Vec<String> =
+                crate::compute::join_right(join, &Default::default(), false)?
+                    .collect_schema()
+                    .map_err(MlPrepError::PolarsError)?
+                    .iter_names()
+                    .map(|name| name.to_string())
+                    .collect();
+            column_lineage(
+                &path.to_string_lossy(),
+                &input_columns,
+                &right.steps,
+                Some(&output_columns),
+            )
+        }
+        _ => column_lineage(&path.to_string_lossy(), &input_columns, &[], None),
+    }
+}
+
+fn apply_feature(provenance: &mut Provenance, spec: &FeatureSpec) -> MlPrepResult<()> {
+    let mut columns = spec.columns.clone();
+    if let Some(pattern) = &spec.columns_regex {
+        let re = regex::Regex::new(pattern).map_err(|e| {
+            MlPrepError::FeatureError(format!("Invalid columns_regex '{}': {}", pattern, e))
+        })?;
+        for name in provenance.names() {
+            if re.is_match(&name) && !columns.contains(&name) {
+                columns.push(name);
+            }
+        }
+    }
+    if columns.is_empty() {
+        columns.push(spec.column.clone());
+    }
+    let prefix = spec.alias.as_deref().unwrap_or(&spec.column);
+    let inputs = provenance.lookup_all(&spec.inputs);
+
+    match spec.transform {
+        FeatureTransform::MinMaxScale
+        | FeatureTransform::StandardScale
+        | FeatureTransform::CountEncode => {
+            for column in &columns {
+                let sources = provenance.lookup(column);
+                provenance.set(spec.alias.as_ref().unwrap_or(column), sources);
+            }
+        }
+        FeatureTransform::OneHotEncode => {
+            for column in &columns {
+                let sources = provenance.lookup(column);
+                provenance.families.push((format!("{}_", column), sources));
+            }
+        }
+        FeatureTransform::Interactions => {
+            let prefix = spec
+                .alias
+                .as_ref()
+                .map(|alias| format!("{}_", alias))
+                .unwrap_or_default();
+            for (i, left) in spec.inputs.iter().enumerate() {
+                if spec.include_squares {
+                    let sources = provenance.lookup(left);
+                    provenance.set(&format!("{}{}_sq", prefix, left), sources);
+                }
+                for right in &spec.inputs[i + 1..] {
+                    let sources = provenance.lookup_all([left, right]);
+                    provenance.set(&format!("{}{}_x_{}", prefix, left, right), sources);
+                }
+            }
+        }
+        FeatureTransform::Pca => {
+            provenance.families.push((format!("{}_pc", prefix), inputs));
+        }
+        FeatureTransform::ClusterEncode => {
+            provenance.set(&format!("{}_cluster", prefix), inputs.clone());
+            provenance.set(&format!("{}_distance", prefix), inputs);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dsl::Pipeline;
+    use std::fs;
+    use tempfile::tempdir;
+
+    fn sources(lineage: &[ColumnLineage], column: &str) -> Vec<String> {
+        lineage
+            .iter()
+            .find(|c| c.column == column)
+            .unwrap_or_else(|| panic!("no lineage for {}", column))
+            .sources
+            .iter()
+            .map(|s| format!("{}:{}", s.input, s.column))
+            .collect()
+    }
+
+    fn columns(names: &[&str]) -> Vec<String> {
+        names.iter().map(|n| n.to_string()).collect()
+    }
+
+    #[test]
+    fn test_column_lineage_through_join_and_group_by() {
+        let dir = tempdir().unwrap();
+        let right = dir.path().join("users.csv");
+        fs::write(&right, "user_id,country,amount\n1,de,3\n").unwrap();
+        let right = right.to_str().unwrap();
+
+        let pipeline = Pipeline::from_reader(
+            format!(
+                r#"
+steps:
+  - type: join
+    right_path: "{right}"
+    left_on: [uid]
+    right_on: [user_id]
+  - type: window
+    partition_by: [country]
+    order_by: ts
+    ops:
+      - {{column: amount, func: cum_sum, alias: running}}
+  - type: group_by
+    by: [country]
+    aggs:
+      amount: {{func: sum, alias: total}}
+      running: {{func: max}}
+"#
+            )
+            .as_bytes(),
+        )
+        .unwrap();
+
+        let lineage = column_lineage(
+            "orders.csv",
+            &columns(&["uid", "ts", "amount"]),
+            &pipeline.steps,
+            None,
+        )
+        .unwrap();
+        let names: Vec<&str> = lineage.iter().map(|c| c.column.as_str()).collect();
+        assert_eq!(names, vec!["country", "total", "running"]);
+        assert_eq!(
+            sources(&lineage, "country"),
+            vec![format!("{}:country", right)]
+        );
+        assert_eq!(sources(&lineage, "total"), vec!["orders.csv:amount"]);
+        assert_eq!(
+            sources(&lineage, "running"),
+            vec![
+                format!("{}:country", right),
+                "orders.csv:amount".to_string(),
+                "orders.csv:ts".to_string()
+            ]
+        );
+    }
+
+    #[test]
+    fn test_column_lineage_resolves_fitted_feature_columns() {
+        let pipeline = Pipeline::from_reader(
+            r#"
+steps:
+  - type: features
+    config:
+      features:
+        - {column: city, transform: one_hot_encode}
+        - {column: price, transform: standard_scale, alias: price_z}
+        - {column: geo, transform: pca, inputs: [lat, lon], n_components: 2}
+        - {column: xs, transform: interactions, inputs: [lat, price]}
+  - type: select
+    columns: [city_berlin, price_z, geo_pc1, lat_x_price]
+  - type: sql
+    query: "SELECT *, price_z * 2 AS doubled FROM df"
+"#
+            .as_bytes(),
+        )
+        .unwrap();
+
+        let output = columns(&["city_berlin", "doubled"]);
+        let lineage = column_lineage(
+            "in.csv",
+            &columns(&["city", "price", "lat", "lon"]),
+            &pipeline.steps[..2],
+            None,
+        )
+        .unwrap();
+        assert_eq!(sources(&lineage, "city_berlin"), vec!["in.csv:city"]);
+        assert_eq!(sources(&lineage, "price_z"), vec!["in.csv:price"]);
+        assert_eq!(
+            sources(&lineage, "geo_pc1"),
+            vec!["in.csv:lat", "in.csv:lon"]
+        );
+        assert_eq!(
+            sources(&lineage, "lat_x_price"),
+            vec!["in.csv:lat", "in.csv:price"]
+        );
+
+        // Columns after a sql step may derive from anything it could read.
+        let lineage = column_lineage(
+            "in.csv",
+            &columns(&["city", "price", "lat", "lon"]),
+            &pipeline.steps,
+            Some(&output),
+        )
+        .unwrap();
+        assert_eq!(
+            sources(&lineage, "doubled"),
+            vec!["in.csv:city", "in.csv:lat", "in.csv:lon", "in.csv:price"]
+        );
+    }
+}
@@ -1,8 +1,14 @@
-use clap::{Parser, Subcommand, ValueEnum};
-use miette::Result;
+use clap::{CommandFactory, Parser, Subcommand, ValueEnum};
+use miette::{IntoDiagnostic, Result};
+use mlprep::observability::RunSummary;
 use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
 use tracing::Level;
-use tracing_subscriber::EnvFilter;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{EnvFilter, Layer};
 use uuid::Uuid;
 
 #[cfg(not(target_env = "msvc"))]
@@ -18,6 +24,40 @@ enum LogFormat {
     Json,
 }
 
+#[derive(Clone, ValueEnum, Debug, PartialEq)]
+enum OutputFormat {
+    Text,
+    Json,
+}
+
+#[derive(Clone, ValueEnum, Debug, PartialEq)]
+enum ExportFormat {
+    /// ONNX-ML preprocessing graph
+    Onnx,
+    /// scikit-learn style JSON parameters
+    #[value(name = "sklearn_json", alias = "sklearn-json")]
+    SklearnJson,
+    /// PMML 4.4 TransformationDictionary
+    Pmml,
+}
+
+#[derive(Clone, Copy, ValueEnum, Debug, PartialEq)]
+enum ServeFormat {
+    /// One JSON object (record) or array (batch) per line
+    Ndjson,
+    /// Consecutive Arrow IPC streams, one per batch
+    Ipc,
+}
+
+impl From<ServeFormat> for mlprep::serve::ServeFormat {
+    fn from(format: ServeFormat) -> Self {
+        match format {
+            ServeFormat::Ndjson => mlprep::serve::ServeFormat::Ndjson,
+            ServeFormat::Ipc => mlprep::serve::ServeFormat::Ipc,
+        }
+    }
+}
+
 #[derive(Parser)]
 #[command(name = "mlprep")]
 #[command(version = "0.3.0")]
@@ -54,11 +94,31 @@ struct Cli {
     #[arg(long, global = true)]
     memory_limit: Option<String>,
 
-    /// Maximum threads for Polars (overrides POLARS_MAX_THREADS)
+    /// Process the input in chunks of N rows (row-wise pipelines only)
+    #[arg(long, value_name = "N", global = true)]
+    chunk_size: Option<usize>,
+
+    /// Polars thread pool size (overrides POLARS_MAX_THREADS)
     #[arg(long, value_name = "N", global = true)]
     threads: Option<String>,
 
-    /// Enable/disable plan cache (maps to POLARS_CACHE)
+    /// Retry a pipeline up to N times on transient I/O errors
+    #[arg(long, value_name = "N", global = true)]
+    retries: Option<u32>,
+
+    /// Delay before the first retry, doubled on each attempt (e.g. "500ms", "30s")
+    #[arg(long, value_name = "DURATION", global = true)]
+    retry_backoff: Option<String>,
+
+    /// Disable progress bars (they are already hidden when stderr is not a terminal)
+    #[arg(long, global = true)]
+    no_progress: bool,
+
+    /// Export tracing spans to an OTLP/HTTP collector (requires the `otel` feature)
+    #[arg(long, value_name = "URL", global = true)]
+    otel_endpoint: Option<String>,
+
+    /// Enable/disable common subplan and subexpression caching
     #[arg(
         long,
         value_name = "true|false",
@@ -76,6 +136,372 @@ enum Commands {
         /// One or more pipeline YAML files to execute sequentially
         #[arg(value_name = "PIPELINE_FILE", num_args = 1..)]
         pipelines: Vec<PathBuf>,
+
+        /// Print a run summary on stdout (json: one object per pipeline, logs stay on stderr)
+        #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+        output_format: OutputFormat,
+
+        /// Re-run whenever a pipeline file or one of its inputs changes
+        #[arg(long)]
+        watch: bool,
+
+        /// Resolve the output schema of every step without writing outputs
+        #[arg(long)]
+        dry_run: bool,
+
+        /// With --dry-run, also execute each step on the first N input rows
+        #[arg(long, value_name = "N", requires = "dry_run")]
+        sample: Option<usize>,
+
+        /// Report the columns and predicates pushed down to each Parquet/CSV scan
+        #[arg(long)]
+        explain_io: bool,
+
+        /// Number of pipelines to execute concurrently
+        #[arg(short, long, value_name = "N", default_value_t = 1)]
+        jobs: usize,
+
+        /// Seed for random operations; makes re-runs write byte-identical outputs
+        #[arg(long, value_name = "N")]
+        seed: Option<u64>,
+
+        /// Process every file of a per-file manifest again instead of resuming an
+        /// interrupted run
+        #[arg(long)]
+        restart: bool,
+
+        /// Value of a `:name` parameter of filter conditions (repeatable)
+        #[arg(long = "param", value_name = "NAME=VALUE", value_parser = mlprep::sql_condition::parse_param)]
+        params: Vec<(String, mlprep::dsl::ScalarValue)>,
+
+        /// Read remote inputs directly instead of through the local input cache
+        #[arg(long)]
+        no_cache: bool,
+    },
+    /// Benchmark the read, transform and write phases of a pipeline, optionally on generated data
+    Bench {
+        /// Pipeline to benchmark (omit to only generate data)
+        #[arg(value_name = "PIPELINE_FILE", required_unless_present = "generate")]
+        pipeline: Option<PathBuf>,
+
+        /// Write synthetic data to FILE (.csv or .parquet); it replaces the pipeline's input
+        #[arg(long, value_name = "FILE")]
+        generate: Option<PathBuf>,
+
+        /// Number of rows to generate
+        #[arg(long, value_name = "N", default_value_t = 1_000_000)]
+        rows: usize,
+
+        /// Columns to generate as name:type pairs (id, int, float, str[:cardinality], bool, date)
+        #[arg(long, value_name = "SPEC", default_value = mlprep::bench::DEFAULT_SCHEMA)]
+        schema: String,
+
+        /// Seed for generated data (the same seed always yields the same data)
+        #[arg(long, default_value_t = 42)]
+        seed: u64,
+
+        /// Times each phase is run; throughput is reported for the fastest run
+        #[arg(long, value_name = "N", default_value_t = 3)]
+        iterations: usize,
+
+        /// Print the report as text or json
+        #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+        output_format: OutputFormat,
+    },
+    /// Generate or inspect schema contracts for pipeline inputs
+    Contract {
+        #[command(subcommand)]
+        action: ContractCommand,
+    },
+    /// Inspect the schema of data files
+    Schema {
+        #[command(subcommand)]
+        action: SchemaCommand,
+    },
+    /// Compare two datasets row by row, matching rows on key columns
+    Diff {
+        /// First dataset (CSV, Parquet or JSON)
+        #[arg(value_name = "DATA_A")]
+        data_a: PathBuf,
+
+        /// Second dataset (CSV, Parquet or JSON)
+        #[arg(value_name = "DATA_B")]
+        data_b: PathBuf,
+
+        /// Key columns identifying a row (comma-separated); must be unique in both datasets
+        #[arg(long, value_name = "COL", value_delimiter = ',', required = true)]
+        keys: Vec<String>,
+
+        /// Numeric values closer than this count as equal
+        #[arg(long, default_value_t = 0.0)]
+        tolerance: f64,
+
+        /// Print the report as text or json
+        #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+        output_format: OutputFormat,
+
+        /// Exit with status 1 when the datasets differ
+        #[arg(long)]
+        exit_code: bool,
+    },
+    /// Compare two runs from their lineage files: inputs, step parameters, row counts, schema
+    DiffRuns {
+        /// Lineage file of the first run (or a directory holding exactly one)
+        #[arg(value_name = "RUN_A")]
+        run_a: PathBuf,
+
+        /// Lineage file of the second run (or a directory holding exactly one)
+        #[arg(value_name = "RUN_B")]
+        run_b: PathBuf,
+
+        /// Print the report as text or json
+        #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+        output_format: OutputFormat,
+    },
+    /// Export a fitted feature state so it can be applied without mlprep
+    ExportState {
+        /// Feature config YAML (`features: [...]`) the state was fitted with
+        #[arg(long, value_name = "FILE")]
+        config: PathBuf,
+
+        /// Fitted feature state (JSON or binary)
+        #[arg(long, value_name = "FILE")]
+        state: PathBuf,
+
+        /// Export format
+        #[arg(long, value_enum, default_value_t = ExportFormat::Onnx)]
+        format: ExportFormat,
+
+        /// File to write
+        #[arg(short, long, value_name = "FILE")]
+        output: PathBuf,
+    },
+    /// Apply a fitted feature state to record batches read from stdin (or a Unix socket)
+    Serve {
+        /// Feature config YAML (`features: [...]`) the state was fitted with
+        #[arg(long, value_name = "FILE")]
+        config: PathBuf,
+
+        /// Fitted feature state (JSON or binary)
+        #[arg(long, value_name = "FILE")]
+        state: PathBuf,
+
+        /// Wire format of incoming and outgoing batches
+        #[arg(long, value_enum, default_value_t = ServeFormat::Ndjson)]
+        format: ServeFormat,
+
+        /// Listen on this Unix socket instead of stdin/stdout
+        #[arg(long, value_name = "PATH")]
+        socket: Option<PathBuf>,
+    },
+    /// Serve POST /transform and GET /healthz over HTTP (requires the `serve-http` feature)
+    ServeHttp {
+        /// Feature config YAML (`features: [...]`) the state was fitted with
+        #[arg(long, value_name = "FILE")]
+        config: PathBuf,
+
+        /// Fitted feature state (JSON or binary)
+        #[arg(long, value_name = "FILE")]
+        state: PathBuf,
+
+        /// Address to listen on
+        #[arg(long, value_name = "HOST:PORT", default_value = "127.0.0.1:8080")]
+        addr: String,
+
+        /// Number of request worker threads
+        #[arg(long, value_name = "N", default_value_t = 4)]
+        workers: usize,
+    },
+    /// Write a Markdown catalog of a pipeline's output columns: sources, transforms,
+    /// fitted statistics and validation rules
+    Docs {
+        /// Pipeline YAML file
+        #[arg(value_name = "PIPELINE_FILE")]
+        pipeline: PathBuf,
+
+        /// File to write the catalog to (default: stdout)
+        #[arg(short, long, value_name = "FILE")]
+        output: Option<PathBuf>,
+
+        /// Print the catalog as markdown (text) or json
+        #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+        output_format: OutputFormat,
+    },
+    /// Generate fake data shaped like a schema contract or checks file
+    Synth {
+        /// Contract or checks YAML describing the columns
+        #[arg(long, value_name = "FILE")]
+        schema: PathBuf,
+
+        /// Number of rows to generate
+        #[arg(long, value_name = "N", default_value_t = 1000)]
+        rows: usize,
+
+        /// Seed for generated data (the same seed always yields the same data)
+        #[arg(long, default_value_t = 42)]
+        seed: u64,
+
+        /// File to write (.csv or .parquet); CSV on stdout when omitted
+        #[arg(short, long, value_name = "FILE")]
+        output: Option<PathBuf>,
+    },
+    /// Run a pipeline's inline `tests:` cases and/or compare its output on a fixture
+    /// input with a stored snapshot
+    Test {
+        /// Pipeline YAML to test
+        #[arg(value_name = "PIPELINE_FILE")]
+        pipeline: PathBuf,
+
+        /// Directory holding output snapshots; a missing snapshot is created
+        #[arg(long, value_name = "DIR")]
+        snapshot: Option<PathBuf>,
+
+        /// Fixture input to run on instead of the pipeline's first input
+        #[arg(long, value_name = "FILE")]
+        input: Option<PathBuf>,
+
+        /// Overwrite the snapshot with the current output
+        #[arg(long)]
+        update: bool,
+    },
+    /// Rewrite a pipeline written for an older DSL version to the current one
+    Migrate {
+        /// Pipeline YAML to migrate
+        #[arg(value_name = "PIPELINE_FILE")]
+        pipeline: PathBuf,
+
+        /// File to write the migrated pipeline to (default: stdout)
+        #[arg(short, long, value_name = "FILE", conflicts_with = "in_place")]
+        output: Option<PathBuf>,
+
+        /// Overwrite the pipeline file with the migrated version
+        #[arg(long)]
+        in_place: bool,
+    },
+    /// Build a pipeline interactively: enter steps one at a time and preview their effect
+    Repl {
+        /// Input file (CSV, Parquet or JSON)
+        #[arg(value_name = "INPUT_FILE")]
+        input: PathBuf,
+
+        /// Input format (csv, parquet or json); detected from the file when omitted
+        #[arg(long)]
+        format: Option<String>,
+
+        /// Rows previewed after each step
+        #[arg(long, value_name = "N", default_value_t = mlprep::repl::DEFAULT_PREVIEW_ROWS)]
+        rows: usize,
+
+        /// File to write the pipeline to when the session ends (default: stdout)
+        #[arg(short, long, value_name = "FILE")]
+        output: Option<PathBuf>,
+    },
+    /// Print the first rows of a data file
+    Head {
+        /// Data file (CSV, Parquet or JSON)
+        #[arg(value_name = "FILE")]
+        file: PathBuf,
+
+        /// Number of rows to print
+        #[arg(short = 'n', long, value_name = "N", default_value_t = 10)]
+        rows: usize,
+
+        /// Input format (csv, parquet or json); detected from the file when omitted
+        #[arg(long)]
+        format: Option<String>,
+
+        /// Print the rows as a text table or a json array
+        #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+        output_format: OutputFormat,
+    },
+    /// Print summary statistics of every column of a data file
+    Describe {
+        /// Data file (CSV, Parquet or JSON)
+        #[arg(value_name = "FILE")]
+        file: PathBuf,
+
+        /// Input format (csv, parquet or json); detected from the file when omitted
+        #[arg(long)]
+        format: Option<String>,
+
+        /// Print the statistics as text or json
+        #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+        output_format: OutputFormat,
+    },
+    /// Scaffold a new project: pipeline.yaml, checks.yaml, data/ and output/
+    Init {
+        /// Project directory (created if missing)
+        #[arg(value_name = "DIR", default_value = ".")]
+        dir: PathBuf,
+
+        /// Sample input the pipeline reads; checks are generated from its columns
+        #[arg(long, value_name = "FILE")]
+        input: Option<PathBuf>,
+
+        /// Overwrite existing project files
+        #[arg(long)]
+        force: bool,
+    },
+    /// Print a shell completion script
+    Completions {
+        /// Shell to complete in
+        #[arg(value_enum)]
+        shell: clap_complete::Shell,
+    },
+    /// Page through a data file in a terminal table viewer
+    View {
+        /// Data file (CSV, Parquet or JSON)
+        #[arg(value_name = "FILE")]
+        file: PathBuf,
+
+        /// Input format (csv, parquet or json); detected from the file when omitted
+        #[arg(long)]
+        format: Option<String>,
+
+        /// Columns to show (default: all)
+        #[arg(long, value_delimiter = ',', value_name = "COLUMNS")]
+        columns: Vec<String>,
+
+        /// Rows printed when stdout is not a terminal
+        #[arg(long, value_name = "N", default_value_t = 20)]
+        rows: usize,
+    },
+}
+
+#[derive(Subcommand)]
+enum ContractCommand {
+    /// Write a contract describing the columns, dtypes, nulls and ranges of an input file
+    Generate {
+        /// Input file (CSV, Parquet or JSON)
+        #[arg(value_name = "INPUT_FILE")]
+        input: PathBuf,
+
+        /// File to write the contract to (default: stdout)
+        #[arg(short, long, value_name = "FILE")]
+        output: Option<PathBuf>,
+
+        /// Input format (csv, parquet or json); detected from the file when omitted
+        #[arg(long)]
+        format: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+enum SchemaCommand {
+    /// List the columns and dtypes of a file, with the descriptions and units recorded
+    /// in Parquet outputs
+    Show {
+        /// Data file (CSV, Parquet or JSON)
+        #[arg(value_name = "FILE")]
+        file: PathBuf,
+
+        /// Input format (csv, parquet or json); detected from the file when omitted
+        #[arg(long)]
+        format: Option<String>,
+
+        /// Print the schema as text or json
+        #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+        output_format: OutputFormat,
     },
 }
 
@@ -98,20 +524,41 @@ fn main() -> Result<()> {
         .with_env_var("MLPREP_LOG")
         .from_env_lossy();
 
-    match cli.log_format {
-        LogFormat::Json => {
-            tracing_subscriber::fmt()
-                .with_env_filter(filter)
-                .json()
-                .with_span_list(false)
-                .with_current_span(false)
+    let fmt_layer = match cli.log_format {
+        LogFormat::Json => tracing_subscriber::fmt::layer()
+            .json()
+            .with_writer(std::io::stderr)
+            .with_span_list(false)
+            .with_current_span(false)
+            .with_filter(filter)
+            .boxed(),
+        LogFormat::Text => tracing_subscriber::fmt::layer()
+            .with_writer(std::io::stderr)
+            .with_filter(filter)
+            .boxed(),
+    };
+    let registry = tracing_subscriber::registry().with(fmt_layer);
+
+    // Spans are exported independently of the console log level.
+    #[cfg(feature = "otel")]
+    let _otel_guard = match cli.otel_endpoint.as_deref() {
+        Some(endpoint) => {
+            let (layer, guard) = mlprep::observability::otel_layer(endpoint)?;
+            registry
+                .with(layer.with_filter(tracing_subscriber::filter::LevelFilter::INFO))
                 .init();
+            Some(guard)
         }
-        LogFormat::Text => {
-            tracing_subscriber::fmt()
-                .with_env_filter(filter)
-                .with_writer(std::io::stderr)
-                .init();
+        None => {
+            registry.init();
+            None
+        }
+    };
+    #[cfg(not(feature = "otel"))]
+    {
+        registry.init();
+        if cli.otel_endpoint.is_some() {
+            tracing::warn!("--otel-endpoint ignored: mlprep was built without the `otel` feature");
         }
     }
 
@@ -120,7 +567,19 @@ fn main() -> Result<()> {
     let _span = tracing::info_span!("root", run_id = %run_id).entered();
 
     match &cli.command {
-        Commands::Run { pipelines } => {
+        Commands::Run {
+            pipelines,
+            output_format,
+            watch,
+            jobs,
+            dry_run,
+            sample,
+            explain_io,
+            seed,
+            restart,
+            params,
+            no_cache,
+        } => {
             // miette::Result handles returning errors nicely
             let security_config = mlprep::security::SecurityConfig {
                 allowed_paths: cli.allowed_paths,
@@ -131,19 +590,770 @@ fn main() -> Result<()> {
                 memory_limit: cli.memory_limit,
                 threads: cli.threads.clone(),
                 cache: cli.cache,
+                // Concurrent progress bars would overwrite each other on the terminal.
+                progress: (cli.no_progress || *jobs > 1).then_some(false),
+                retries: cli.retries,
+                retry_backoff: cli.retry_backoff.clone(),
+                explain_io: explain_io.then_some(true),
+                chunk_size: cli.chunk_size,
+                seed: *seed,
+                restart: *restart,
+                notifications: Vec::new(),
+                params: params.iter().cloned().collect(),
+                no_input_cache: *no_cache,
+                ..Default::default()
             };
 
-            for pipeline in pipelines {
-                let pipeline_run = Uuid::new_v4();
-                mlprep::runner::execution_pipeline(
+            if *dry_run {
+                for pipeline in pipelines {
+                    let report = mlprep::runner::dry_run(
+                        pipeline,
+                        security_config.clone(),
+                        Some(runtime_override.clone()),
+                        *sample,
+                    )?;
+                    match output_format {
+                        OutputFormat::Json => {
+                            println!("{}", serde_json::to_string(&report).into_diagnostic()?)
+                        }
+                        OutputFormat::Text => print!("{}", report),
+                    }
+                }
+                return Ok(());
+            }
+
+            loop {
+                let outcomes = run_pipelines(
+                    pipelines,
+                    (*jobs).max(1),
+                    &security_config,
+                    &runtime_override,
+                );
+                let total = outcomes.len();
+                let mut failures = Vec::new();
+                for outcome in outcomes {
+                    if *output_format == OutputFormat::Json {
+                        let summary = match &outcome.result {
+                            Ok(summary) => summary.clone(),
+                            Err(e) => RunSummary::failed(
+                                outcome.run_id,
+                                &outcome.pipeline,
+                                outcome.elapsed,
+                                outcome.attempts,
+                                e.to_string(),
+                            ),
+                        };
+                        println!("{}", serde_json::to_string(&summary).into_diagnostic()?);
+                    } else if let Ok(summary) = &outcome.result {
+                        if !summary.scans.is_empty() {
+                            println!("{}:", summary.pipeline);
+                            for scan in &summary.scans {
+                                println!("  {}", scan);
+                            }
+                        }
+                    }
+                    if let Err(e) = outcome.result {
+                        failures.push(e);
+                    }
+                }
+
+                if *watch {
+                    // In watch mode a failed run is reported and we wait for the next edit.
+                    for e in failures {
+                        tracing::error!("{:?}", miette::Report::new(e));
+                    }
+                } else if failures.len() == 1 {
+                    return Err(failures.remove(0).into());
+                } else if !failures.is_empty() {
+                    let failed = failures.len();
+                    for e in failures {
+                        tracing::error!("{:?}", miette::Report::new(e));
+                    }
+                    return Err(miette::miette!("{} of {} pipelines failed", failed, total));
+                }
+
+                if !*watch {
+                    break;
+                }
+                let files: Vec<PathBuf> = pipelines
+                    .iter()
+                    .flat_map(|p| mlprep::watch::watched_files(p))
+                    .collect();
+                tracing::info!(
+                    "Watching {} files for changes (Ctrl-C to stop)",
+                    files.len()
+                );
+                let changed = mlprep::watch::wait_for_change(&files, Duration::from_millis(500));
+                tracing::info!("Change detected in {:?}, re-running", changed);
+            }
+        }
+        Commands::Bench {
+            pipeline,
+            generate,
+            rows,
+            schema,
+            seed,
+            iterations,
+            output_format,
+        } => {
+            let security_config = mlprep::security::SecurityConfig {
+                allowed_paths: cli.allowed_paths,
+                mask_columns: cli.mask_columns,
+            };
+            if let Some(path) = generate {
+                mlprep::security::SecurityContext::new(security_config.clone())?
+                    .validate_path(path)?;
+                let columns = mlprep::bench::parse_schema(schema)?;
+                let start = Instant::now();
+                let bytes = mlprep::bench::generate(path, &columns, *rows, *seed)?;
+                tracing::info!(
+                    "Generated {} rows ({}) to {} in {:?}",
+                    rows,
+                    mlprep::memory::format_bytes(bytes),
+                    path.display(),
+                    start.elapsed()
+                );
+            }
+            if let Some(pipeline) = pipeline {
+                let runtime_override = mlprep::dsl::RuntimeConfig {
+                    streaming: cli.streaming,
+                    memory_limit: cli.memory_limit,
+                    threads: cli.threads.clone(),
+                    cache: cli.cache,
+                    progress: Some(false),
+                    ..Default::default()
+                };
+                let report = mlprep::bench::bench_pipeline(
                     pipeline,
-                    pipeline_run,
-                    security_config.clone(),
-                    Some(runtime_override.clone()),
+                    generate.as_deref(),
+                    *iterations,
+                    security_config,
+                    Some(runtime_override),
                 )?;
+                match output_format {
+                    OutputFormat::Json => {
+                        println!("{}", serde_json::to_string(&report).into_diagnostic()?)
+                    }
+                    OutputFormat::Text => print!("{}", report),
+                }
+            }
+        }
+        Commands::Contract {
+            action:
+                ContractCommand::Generate {
+                    input,
+                    output,
+                    format,
+                },
+        } => {
+            let security =
+                mlprep::security::SecurityContext::new(mlprep::security::SecurityConfig {
+                    allowed_paths: cli.allowed_paths,
+                    mask_columns: cli.mask_columns,
+                })?;
+            for path in std::iter::once(input).chain(output) {
+                security.validate_path(path)?;
+            }
+            let source = input.to_string_lossy();
+            let lf = mlprep::io::read_detected(input, format.as_deref())?;
+            let contract = mlprep::contract::Contract::generate(lf, cli.streaming)?;
+            match output {
+                Some(path) => {
+                    contract.save(path, &source)?;
+                    tracing::info!(
+                        "Wrote contract for {} columns to {}",
+                        contract.columns.len(),
+                        path.display()
+                    );
+                }
+                None => print!("{}", contract.to_yaml(&source)?),
+            }
+        }
+        Commands::Schema {
+            action:
+                SchemaCommand::Show {
+                    file,
+                    format,
+                    output_format,
+                },
+        } => {
+            let security =
+                mlprep::security::SecurityContext::new(mlprep::security::SecurityConfig {
+                    allowed_paths: cli.allowed_paths,
+                    mask_columns: cli.mask_columns,
+                })?;
+            security.validate_path(file)?;
+            let schema = mlprep::io::describe_schema(file, format.as_deref())?;
+            match output_format {
+                OutputFormat::Json => {
+                    println!("{}", serde_json::to_string(&schema).into_diagnostic()?)
+                }
+                OutputFormat::Text => print!("{}", schema),
+            }
+        }
+        Commands::Diff {
+            data_a,
+            data_b,
+            keys,
+            tolerance,
+            output_format,
+            exit_code,
+        } => {
+            let security =
+                mlprep::security::SecurityContext::new(mlprep::security::SecurityConfig {
+                    allowed_paths: cli.allowed_paths,
+                    mask_columns: cli.mask_columns,
+                })?;
+            for path in [data_a, data_b] {
+                security.validate_path(path)?;
+            }
+            let diff = mlprep::data_diff::diff_data(
+                mlprep::io::read_detected(data_a, None)?,
+                mlprep::io::read_detected(data_b, None)?,
+                keys,
+                *tolerance,
+                cli.streaming,
+            )?;
+            match output_format {
+                OutputFormat::Json => {
+                    println!("{}", serde_json::to_string(&diff).into_diagnostic()?)
+                }
+                OutputFormat::Text => print!("{}", diff),
+            }
+            if *exit_code && !diff.is_equivalent() {
+                std::process::exit(1);
+            }
+        }
+        Commands::DiffRuns {
+            run_a,
+            run_b,
+            output_format,
+        } => {
+            let security =
+                mlprep::security::SecurityContext::new(mlprep::security::SecurityConfig {
+                    allowed_paths: cli.allowed_paths,
+                    mask_columns: cli.mask_columns,
+                })?;
+            for path in [run_a, run_b] {
+                security.validate_path(path)?;
+            }
+            let diff = mlprep::run_diff::diff_runs(
+                &mlprep::run_diff::load_run(run_a)?,
+                &mlprep::run_diff::load_run(run_b)?,
+            );
+            match output_format {
+                OutputFormat::Json => {
+                    println!("{}", serde_json::to_string(&diff).into_diagnostic()?)
+                }
+                OutputFormat::Text => print!("{}", diff),
+            }
+        }
+        Commands::ExportState {
+            config,
+            state,
+            format,
+            output,
+        } => {
+            let security =
+                mlprep::security::SecurityContext::new(mlprep::security::SecurityConfig {
+                    allowed_paths: cli.allowed_paths,
+                    mask_columns: cli.mask_columns,
+                })?;
+            for path in [config, state, output] {
+                security.validate_path(path)?;
+            }
+            export_state(config, state, format, output)?;
+            tracing::info!("Exported {:?} to {}", format, output.display());
+        }
+        Commands::Serve {
+            config,
+            state,
+            format,
+            socket,
+        } => {
+            let security =
+                mlprep::security::SecurityContext::new(mlprep::security::SecurityConfig {
+                    allowed_paths: cli.allowed_paths,
+                    mask_columns: cli.mask_columns,
+                })?;
+            for path in [Some(config), Some(state), socket.as_ref()]
+                .into_iter()
+                .flatten()
+            {
+                security.validate_path(path)?;
+            }
+            serve(config, state, *format, socket.as_ref())?;
+        }
+        Commands::ServeHttp {
+            config,
+            state,
+            addr,
+            workers,
+        } => {
+            let security =
+                mlprep::security::SecurityContext::new(mlprep::security::SecurityConfig {
+                    allowed_paths: cli.allowed_paths,
+                    mask_columns: cli.mask_columns,
+                })?;
+            for path in [config, state] {
+                security.validate_path(path)?;
+            }
+            serve_http(config, state, addr, *workers)?;
+        }
+        Commands::Docs {
+            pipeline,
+            output,
+            output_format,
+        } => {
+            let security_config = mlprep::security::SecurityConfig {
+                allowed_paths: cli.allowed_paths,
+                mask_columns: cli.mask_columns,
+            };
+            let security = mlprep::security::SecurityContext::new(security_config.clone())?;
+            for path in std::iter::once(pipeline).chain(output) {
+                security.validate_path(path)?;
+            }
+            let catalog = mlprep::catalog::build(pipeline, security_config)?;
+            let rendered = match output_format {
+                OutputFormat::Json => serde_json::to_string(&catalog).into_diagnostic()? + "\n",
+                OutputFormat::Text => catalog.to_markdown(),
+            };
+            match output {
+                Some(path) => {
+                    std::fs::write(path, rendered).into_diagnostic()?;
+                    tracing::info!(
+                        "Wrote {} columns to {}",
+                        catalog.columns.len(),
+                        path.display()
+                    );
+                }
+                None => print!("{}", rendered),
+            }
+        }
+        Commands::Synth {
+            schema,
+            rows,
+            seed,
+            output,
+        } => {
+            let security =
+                mlprep::security::SecurityContext::new(mlprep::security::SecurityConfig {
+                    allowed_paths: cli.allowed_paths,
+                    mask_columns: cli.mask_columns,
+                })?;
+            for path in std::iter::once(schema).chain(output) {
+                security.validate_path(path)?;
+            }
+            let schema = mlprep::synth::SynthSchema::load(schema)?;
+            let mut df = mlprep::synth::synthesize(&schema, *rows, *seed)?;
+            match output {
+                Some(path) => match path.extension().and_then(|ext| ext.to_str()) {
+                    Some("parquet") => mlprep::io::write_parquet(df, path)?,
+                    Some("csv") => mlprep::io::write_csv(df, path)?,
+                    _ => {
+                        return Err(miette::miette!(
+                            "Unsupported format for generated data: {} (use .csv or .parquet)",
+                            path.display()
+                        ))
+                    }
+                },
+                None => {
+                    use polars::prelude::{CsvWriter, SerWriter};
+                    CsvWriter::new(std::io::stdout().lock())
+                        .finish(&mut df)
+                        .into_diagnostic()?;
+                }
+            }
+        }
+        Commands::Test {
+            pipeline,
+            snapshot,
+            input,
+            update,
+        } => {
+            let security_config = mlprep::security::SecurityConfig {
+                allowed_paths: cli.allowed_paths,
+                mask_columns: cli.mask_columns,
+            };
+            let security = mlprep::security::SecurityContext::new(security_config.clone())?;
+            for path in std::iter::once(pipeline).chain(snapshot) {
+                security.validate_path(path)?;
+            }
+            let definition = mlprep::dsl::Pipeline::from_path(pipeline)?;
+            if definition.tests.is_empty() && snapshot.is_none() {
+                return Err(miette::miette!(
+                    "Nothing to test: {} has no `tests:` cases and no --snapshot was given",
+                    pipeline.display()
+                ));
+            }
+
+            let outcomes = mlprep::testing::run_cases(&definition, security_config.clone())?;
+            for outcome in &outcomes {
+                println!("{}", outcome);
+            }
+            if let Some(snapshot) = snapshot {
+                let output =
+                    mlprep::runner::execute_fixture(pipeline, input.as_deref(), security_config)?;
+                let path = mlprep::snapshot::snapshot_path(snapshot, pipeline);
+                let status = mlprep::snapshot::check(&output, &path, *update)?;
+                println!(
+                    "{}: snapshot {} ({}, {} rows)",
+                    pipeline.display(),
+                    status,
+                    path.display(),
+                    output.height()
+                );
+            }
+            let failed = outcomes.iter().filter(|o| !o.passed()).count();
+            if failed > 0 {
+                return Err(mlprep::errors::MlPrepError::TestFailure(format!(
+                    "{} of {} test cases failed",
+                    failed,
+                    outcomes.len()
+                ))
+                .into());
+            }
+        }
+        Commands::Migrate {
+            pipeline,
+            output,
+            in_place,
+        } => {
+            let security =
+                mlprep::security::SecurityContext::new(mlprep::security::SecurityConfig {
+                    allowed_paths: cli.allowed_paths,
+                    mask_columns: cli.mask_columns,
+                })?;
+            let target = if *in_place {
+                Some(pipeline)
+            } else {
+                output.as_ref()
+            };
+            for path in std::iter::once(pipeline).chain(target) {
+                security.validate_path(path)?;
+            }
+            let text = std::fs::read_to_string(pipeline).into_diagnostic()?;
+            let migration = mlprep::migrate::migrate(&text)?;
+            for change in &migration.changes {
+                tracing::info!("{}: {}", pipeline.display(), change);
+            }
+            if migration.reformatted {
+                tracing::warn!(
+                    "{}: rewrote the whole file; comments and layout were not kept",
+                    pipeline.display()
+                );
+            }
+            match target {
+                Some(path) => {
+                    std::fs::write(path, &migration.yaml).into_diagnostic()?;
+                    tracing::info!(
+                        "Wrote {} (version {}) to {}",
+                        pipeline.display(),
+                        mlprep::dsl::DSL_VERSION,
+                        path.display()
+                    );
+                }
+                None => print!("{}", migration.yaml),
+            }
+        }
+        Commands::Repl {
+            input,
+            format,
+            rows,
+            output,
+        } => {
+            let security =
+                mlprep::security::SecurityContext::new(mlprep::security::SecurityConfig {
+                    allowed_paths: cli.allowed_paths,
+                    mask_columns: cli.mask_columns,
+                })?;
+            if let Some(path) = output {
+                security.validate_path(path)?;
+            }
+            let input = mlprep::dsl::Input {
+                path: input.clone(),
+                format: format.clone(),
+                schema: None,
+                infer_rows: None,
+                null_values: None,
+                contract: None,
+                manifest: None,
+                iceberg: None,
+                kafka: None,
+            };
+            let mut repl = mlprep::repl::Repl::new(input, security)?.with_preview_rows(*rows);
+            repl.session(std::io::stdin().lock(), std::io::stdout())?;
+            let yaml = repl.pipeline().to_yaml()?;
+            match output {
+                Some(path) => {
+                    std::fs::write(path, yaml).into_diagnostic()?;
+                    tracing::info!(
+                        "Wrote {} steps to {}",
+                        repl.pipeline().steps.len(),
+                        path.display()
+                    );
+                }
+                None => print!("{}", yaml),
+            }
+        }
+        Commands::Head {
+            file,
+            rows,
+            format,
+            output_format,
+        } => {
+            let security =
+                mlprep::security::SecurityContext::new(mlprep::security::SecurityConfig {
+                    allowed_paths: cli.allowed_paths,
+                    mask_columns: cli.mask_columns,
+                })?;
+            let mut df = mlprep::inspect::head(file, format.as_deref(), *rows, &security)?;
+            match output_format {
+                OutputFormat::Json => {
+                    use polars::prelude::{JsonFormat, JsonWriter, SerWriter};
+                    let mut out = std::io::stdout();
+                    JsonWriter::new(&mut out)
+                        .with_json_format(JsonFormat::Json)
+                        .finish(&mut df)
+                        .into_diagnostic()?;
+                    println!();
+                }
+                OutputFormat::Text => {
+                    // Show every requested row and column instead of Polars' elided preview
+                    for var in ["POLARS_FMT_MAX_ROWS", "POLARS_FMT_MAX_COLS"] {
+                        if std::env::var_os(var).is_none() {
+                            std::env::set_var(var, "-1");
+                        }
+                    }
+                    println!("{}", df);
+                }
+            }
+        }
+        Commands::Describe {
+            file,
+            format,
+            output_format,
+        } => {
+            let security =
+                mlprep::security::SecurityContext::new(mlprep::security::SecurityConfig {
+                    allowed_paths: cli.allowed_paths,
+                    mask_columns: cli.mask_columns,
+                })?;
+            let summary = mlprep::inspect::describe_file(file, format.as_deref(), &security)?;
+            match output_format {
+                OutputFormat::Json => {
+                    println!("{}", serde_json::to_string(&summary).into_diagnostic()?)
+                }
+                OutputFormat::Text => print!("{}", summary),
+            }
+        }
+        Commands::Init { dir, input, force } => {
+            let security =
+                mlprep::security::SecurityContext::new(mlprep::security::SecurityConfig {
+                    allowed_paths: cli.allowed_paths,
+                    mask_columns: cli.mask_columns,
+                })?;
+            for path in std::iter::once(dir).chain(input) {
+                security.validate_path(path)?;
+            }
+            let written = mlprep::scaffold::init(dir, input.as_deref(), *force)?;
+            for path in &written {
+                tracing::info!("Wrote {}", path.display());
+            }
+            println!(
+                "Scaffolded {}; run it with: cd {} && mlprep run pipeline.yaml",
+                dir.display(),
+                dir.display()
+            );
+        }
+        Commands::Completions { shell } => {
+            clap_complete::generate(
+                *shell,
+                &mut Cli::command(),
+                "mlprep",
+                &mut std::io::stdout(),
+            );
+        }
+        Commands::View {
+            file,
+            format,
+            columns,
+            rows,
+        } => {
+            use std::io::IsTerminal;
+            let security =
+                mlprep::security::SecurityContext::new(mlprep::security::SecurityConfig {
+                    allowed_paths: cli.allowed_paths,
+                    mask_columns: cli.mask_columns,
+                })?;
+            let mut viewer =
+                mlprep::view::Viewer::open(file, format.as_deref(), columns, *rows, &security)?;
+            if std::io::stdout().is_terminal() {
+                mlprep::view::run(&mut viewer)?;
+            } else {
+                for line in viewer.render(usize::MAX, false) {
+                    println!("{}", line);
+                }
             }
         }
     }
 
     Ok(())
 }
+
+fn export_state(
+    config: &PathBuf,
+    state: &PathBuf,
+    format: &ExportFormat,
+    output: &PathBuf,
+) -> mlprep::errors::MlPrepResult<()> {
+    use mlprep::errors::MlPrepError;
+    let feature_error = |e: anyhow::Error| MlPrepError::FeatureError(e.to_string());
+
+    let config = mlprep::features::FeatureConfig::load(config).map_err(feature_error)?;
+    let state = mlprep::features::FeatureState::load(state).map_err(feature_error)?;
+    let bytes = match format {
+        ExportFormat::Onnx => mlprep::onnx::export_onnx(&config, &state).map_err(feature_error)?,
+        ExportFormat::SklearnJson => mlprep::export::export_sklearn_json(&config, &state)
+            .map_err(feature_error)?
+            .into_bytes(),
+        ExportFormat::Pmml => mlprep::export::export_pmml(&config, &state)
+            .map_err(feature_error)?
+            .into_bytes(),
+    };
+    std::fs::write(output, bytes)?;
+    Ok(())
+}
+
+fn serve(
+    config: &PathBuf,
+    state: &PathBuf,
+    format: ServeFormat,
+    socket: Option<&PathBuf>,
+) -> mlprep::errors::MlPrepResult<()> {
+    use mlprep::errors::MlPrepError;
+    let feature_error = |e: anyhow::Error| MlPrepError::FeatureError(e.to_string());
+
+    let config = mlprep::features::FeatureConfig::load(config).map_err(feature_error)?;
+    let state = mlprep::features::FeatureState::load(state).map_err(feature_error)?;
+    let transformer = mlprep::serve::Transformer::new(config, state);
+    match socket {
+        #[cfg(unix)]
+        Some(path) => {
+            mlprep::serve::serve_unix_socket(std::sync::Arc::new(transformer), format.into(), path)
+                .map_err(feature_error)
+        }
+        #[cfg(not(unix))]
+        Some(_) => Err(MlPrepError::FeatureError(
+            "--socket is only supported on Unix".to_string(),
+        )),
+        None => mlprep::serve::serve_session(
+            &transformer,
+            format.into(),
+            std::io::stdin().lock(),
+            std::io::stdout().lock(),
+        )
+        .map_err(feature_error),
+    }
+}
+
+#[cfg(feature = "serve-http")]
+fn serve_http(
+    config: &PathBuf,
+    state: &PathBuf,
+    addr: &str,
+    workers: usize,
+) -> mlprep::errors::MlPrepResult<()> {
+    use mlprep::errors::MlPrepError;
+    let feature_error = |e: anyhow::Error| MlPrepError::FeatureError(e.to_string());
+
+    let config = mlprep::features::FeatureConfig::load(config).map_err(feature_error)?;
+    let state = mlprep::features::FeatureState::load(state).map_err(feature_error)?;
+    let transformer = std::sync::Arc::new(mlprep::serve::Transformer::new(config, state));
+    mlprep::serve::serve_http(transformer, addr, workers).map_err(feature_error)
+}
+
+#[cfg(not(feature = "serve-http"))]
+fn serve_http(
+    _config: &PathBuf,
+    _state: &PathBuf,
+    _addr: &str,
+    _workers: usize,
+) -> mlprep::errors::MlPrepResult<()> {
+    Err(mlprep::errors::MlPrepError::FeatureError(
+        "serve-http is unavailable: mlprep was built without the `serve-http` feature".to_string(),
+    ))
+}
+
+struct PipelineOutcome {
+    pipeline: PathBuf,
+    run_id: Uuid,
+    elapsed: Duration,
+    attempts: u32,
+    result: mlprep::errors::MlPrepResult<RunSummary>,
+}
+
+/// Execute pipelines with up to `jobs` worker threads, returning outcomes in input order.
+/// With a single job execution stops at the first failure, matching sequential semantics.
+fn run_pipelines(
+    pipelines: &[PathBuf],
+    jobs: usize,
+    security_config: &mlprep::security::SecurityConfig,
+    runtime_override: &mlprep::dsl::RuntimeConfig,
+) -> Vec<PipelineOutcome> {
+    let run_one = |pipeline: &PathBuf| {
+        let run_id = Uuid::new_v4();
+        let started = Instant::now();
+        let (result, attempts) = mlprep::runner::execute_with_retries(
+            pipeline,
+            run_id,
+            security_config.clone(),
+            Some(runtime_override.clone()),
+        );
+        PipelineOutcome {
+            pipeline: pipeline.clone(),
+            run_id,
+            elapsed: started.elapsed(),
+            attempts,
+            result,
+        }
+    };
+
+    if jobs == 1 {
+        let mut outcomes = Vec::new();
+        for pipeline in pipelines {
+            let outcome = run_one(pipeline);
+            let failed = outcome.result.is_err();
+            outcomes.push(outcome);
+            if failed {
+                break;
+            }
+        }
+        return outcomes;
+    }
+
+    let next = AtomicUsize::new(0);
+    let slots: Vec<Mutex<Option<PipelineOutcome>>> =
+        pipelines.iter().map(|_| Mutex::new(None)).collect();
+    let parent = tracing::Span::current();
+    std::thread::scope(|scope| {
+        for _ in 0..jobs.min(pipelines.len()) {
+            scope.spawn(|| {
+                // Worker logs stay tagged with the root span and their own pipeline span.
+                let _entered = parent.enter();
+                loop {
+                    let idx = next.fetch_add(1, Ordering::SeqCst);
+                    let Some(pipeline) = pipelines.get(idx) else {
+                        break;
+                    };
+                    let outcome = run_one(pipeline);
+                    *slots[idx].lock().unwrap_or_else(|e| e.into_inner()) = Some(outcome);
+                }
+            });
+        }
+    });
+    slots
+        .into_iter()
+        .filter_map(|slot| slot.into_inner().unwrap_or_else(|e| e.into_inner()))
+        .collect()
+}
@@ -0,0 +1,325 @@
+//! Manifest inputs: a CSV/JSON file listing the files to process, one per row. The other
+//! manifest columns are per-file metadata, added as constant columns to that file's rows.
+//! The files are concatenated into one input, or with `per_file: true` each one runs
+//! through the steps on its own and is written to its own output; finished files are
+//! recorded in a progress file so an interrupted run resumes where it stopped.
+
+use crate::dsl::{Input, Manifest, Output, RuntimeConfig};
+use crate::errors::{MlPrepError, MlPrepResult};
+use crate::io;
+use crate::security::SecurityContext;
+use polars::prelude::*;
+use serde::de::Error;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+fn invalid(msg: String) -> MlPrepError {
+    MlPrepError::ConfigError(serde_yaml::Error::custom(msg), None)
+}
+
+/// One file listed in a manifest.
+#[derive(Debug, Clone)]
+pub struct ManifestEntry {
+    pub path: PathBuf,
+    /// The row's other manifest columns
+    pub metadata: Vec<(String, Scalar)>,
+}
+
+impl ManifestEntry {
+    /// The file's rows with the metadata columns added.
+    pub fn scan(&self, manifest: &Manifest) -> MlPrepResult<LazyFrame> {
+        let lf = io::read_detected(&self.path, manifest.format.as_deref())?;
+        Ok(lf.with_columns(
+            self.metadata
+                .iter()
+                .map(|(name, value)| lit(value.clone()).alias(name.as_str()))
+                .collect::<Vec<_>>(),
+        ))
+    }
+
+    fn stem(&self) -> String {
+        self.path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_else(|| "part".to_string())
+    }
+
+    /// Where this file's output goes when processed `per_file`: `output.path` with
+    /// `{stem}` and `{<metadata column>}` filled in, or, without placeholders, a file
+    /// named after the input in the `output.path` directory.
+    pub fn output(&self, output: &Output) -> MlPrepResult<Output> {
+        let path = if let Some(template) = output.path.to_str().filter(|p| p.contains('{')) {
+            let mut path = template.replace("{stem}", &self.stem());
+            for (name, value) in &self.metadata {
+                let rendered = match value.value().get_str() {
+                    Some(s) => s.to_string(),
+                    None => value.value().to_string(),
+                };
+                path = path.replace(&format!("{{{}}}", name), &rendered);
+            }
+            if let Some(start) = path.find('{') {
+                return Err(invalid(format!(
+                    "Output path {} uses an unknown placeholder at '{}'",
+                    template,
+                    &path[start..]
+                )));
+            }
+            PathBuf::from(path)
+        } else {
+            let ext = output.format.as_deref().unwrap_or("parquet");
+            output.path.join(format!("{}.{}", self.stem(), ext))
+        };
+        Ok(Output {
+            path,
+            ..output.clone()
+        })
+    }
+}
+
+/// Read the manifest at `input.path`. Relative file paths are resolved against the
+/// manifest's directory.
+pub fn load(input: &Input, manifest: &Manifest) -> MlPrepResult<Vec<ManifestEntry>> {
+    let df = io::read_detected(&input.path, input.format.as_deref())?
+        .collect()
+        .map_err(MlPrepError::PolarsError)?;
+    let paths = df
+        .column(&manifest.path_column)
+        .map_err(|_| {
+            invalid(format!(
+                "Manifest {} has no '{}' column",
+                input.path.display(),
+                manifest.path_column
+            ))
+        })?
+        .cast(&DataType::String)
+        .map_err(MlPrepError::PolarsError)?;
+    let paths = paths.str().map_err(MlPrepError::PolarsError)?;
+    let base = input.path.parent().unwrap_or(Path::new(""));
+    let metadata: Vec<&Column> = df
+        .get_columns()
+        .iter()
+        .filter(|c| c.name().as_str() != manifest.path_column)
+        .collect();
+
+    let mut entries = Vec::with_capacity(df.height());
+    for (row, path) in paths.into_iter().enumerate() {
+        let path = path.ok_or_else(|| {
+            invalid(format!(
+                "Row {} of manifest {} has no path",
+                row + 1,
+                input.path.display()
+            ))
+        })?;
+        let path = if crate::io::is_cloud_uri(Path::new(path)) {
+            PathBuf::from(path)
+        } else {
+            base.join(path)
+        };
+        let metadata = metadata
+            .iter()
+            .map(|column| {
+                let value = column.get(row).map_err(MlPrepError::PolarsError)?;
+                Ok((
+                    column.name().to_string(),
+                    Scalar::new(column.dtype().clone(), value.into_static()),
+                ))
+            })
+            .collect::<MlPrepResult<Vec<_>>>()?;
+        entries.push(ManifestEntry { path, metadata });
+    }
+    if entries.is_empty() {
+        return Err(invalid(format!(
+            "Manifest {} lists no files",
+            input.path.display()
+        )));
+    }
+    Ok(entries)
+}
+
+/// Swap the listed remote files for their local copies (see [`crate::input_cache`]).
+pub fn localize(entries: &mut [ManifestEntry], runtime: &RuntimeConfig) -> MlPrepResult<()> {
+    for entry in entries {
+        entry.path = crate::input_cache::localize(&entry.path, runtime)?;
+    }
+    Ok(())
+}
+
+/// Check every listed file against the sandbox.
+pub fn validate(entries: &[ManifestEntry], security_context: &SecurityContext) -> MlPrepResult<()> {
+    entries
+        .iter()
+        .try_for_each(|entry| security_context.validate_path(&entry.path))
+}
+
+/// All listed files as one frame; columns missing from some files are filled with nulls.
+pub fn scan(entries: &[ManifestEntry], manifest: &Manifest) -> MlPrepResult<LazyFrame> {
+    let frames = entries
+        .iter()
+        .map(|entry| entry.scan(manifest))
+        .collect::<MlPrepResult<Vec<_>>>()?;
+    concat(
+        frames,
+        UnionArgs {
+            diagonal: true,
+            to_supertypes: true,
+            ..Default::default()
+        },
+    )
+    .map_err(MlPrepError::PolarsError)
+}
+
+/// Total rows of the listed files, counted without running any plan.
+pub fn count_rows(entries: &[ManifestEntry], manifest: &Manifest) -> MlPrepResult<usize> {
+    entries.iter().try_fold(0, |total, entry| {
+        let format = io::detect_format(&entry.path, manifest.format.as_deref())?;
+        Ok(total + io::count_rows(&entry.path, format)?)
+    })
+}
+
+/// A file finished by a `per_file` run.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FileDone {
+    /// SHA256 of the input when it was processed; a changed file is processed again
+    pub hash: String,
+    pub output: String,
+    pub rows_read: usize,
+    pub rows_written: usize,
+}
+
+/// Files finished so far by an interrupted `per_file` run, keyed by input path.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ManifestProgress {
+    pub done: BTreeMap<String, FileDone>,
+}
+
+impl ManifestProgress {
+    /// Where the progress of `pipeline`'s manifest run is kept: `state_path`, or
+    /// `<pipeline stem>.progress.json` next to the pipeline.
+    pub fn path(pipeline: &Path, manifest: &Manifest) -> PathBuf {
+        match &manifest.state_path {
+            Some(path) => path.clone(),
+            None => {
+                let stem = pipeline
+                    .file_stem()
+                    .map(|stem| stem.to_string_lossy().into_owned())
+                    .unwrap_or_else(|| "pipeline".to_string());
+                pipeline.with_file_name(format!("{}.progress.json", stem))
+            }
+        }
+    }
+
+    /// The recorded progress, or none when `path` does not exist.
+    pub fn load(path: &Path) -> MlPrepResult<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let file = std::fs::File::open(path)?;
+        serde_json::from_reader(std::io::BufReader::new(file)).map_err(|e| {
+            MlPrepError::TransformError(format!("{} is not a progress file: {}", path.display(), e))
+        })
+    }
+
+    pub fn save(&self, path: &Path) -> MlPrepResult<()> {
+        let json = serde_json::to_string_pretty(self)
+            .map_err(|e| MlPrepError::TransformError(e.to_string()))?;
+        io::write_atomic(path, |file| {
+            std::io::Write::write_all(file, json.as_bytes()).map_err(MlPrepError::IoError)
+        })
+    }
+
+    /// The earlier result for `path`, if the file is unchanged and its output still exists
+    /// where it would be written now.
+    pub fn finished(&self, path: &Path, hash: &str, output: &Output) -> Option<&FileDone> {
+        self.done
+            .get(path.to_string_lossy().as_ref())
+            .filter(|done| done.hash == hash && done.output == output.path.to_string_lossy())
+            .filter(|_| output.files().iter().any(|file| file.exists()))
+    }
+}
+
+/// Scan `input`: the file itself, or for a manifest all listed files concatenated.
+/// Remote files are read through the input cache configured by `runtime`.
+pub fn scan_input(
+    input: &Input,
+    security_context: &SecurityContext,
+    runtime: &RuntimeConfig,
+) -> MlPrepResult<LazyFrame> {
+    if let Some(options) = &input.kafka {
+        if let Some(schema) = &options.avro_schema {
+            security_context.validate_path(schema)?;
+        }
+        return crate::kafka::read(&input.path, options, input.format.as_deref())
+            .map(|df| df.lazy());
+    }
+    if let Some(table) = &input.iceberg {
+        let manifest = crate::iceberg::manifest();
+        let mut entries = crate::iceberg::data_files(input, table, &[], runtime)?;
+        validate(&entries, security_context)?;
+        localize(&mut entries, runtime)?;
+        return scan(&entries, &manifest);
+    }
+    match &input.manifest {
+        Some(manifest) => {
+            let mut entries = load(input, manifest)?;
+            validate(&entries, security_context)?;
+            localize(&mut entries, runtime)?;
+            scan(&entries, manifest)
+        }
+        None => io::read_detected(
+            crate::input_cache::localize(&input.path, runtime)?,
+            input.format.as_deref(),
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_manifest_entries() {
+        let dir = tempdir().unwrap();
+        std::fs::write(dir.path().join("a.csv"), "x\n1\n2\n").unwrap();
+        std::fs::write(dir.path().join("b.csv"), "x,y\n3,z\n").unwrap();
+        let manifest_path = dir.path().join("files.csv");
+        std::fs::write(
+            &manifest_path,
+            "file,day\na.csv,2024-01-01\nb.csv,2024-01-02\n",
+        )
+        .unwrap();
+        let input: Input = serde_yaml::from_str(&format!(
+            "path: \"{}\"\nmanifest:\n  path_column: file\n",
+            manifest_path.display()
+        ))
+        .unwrap();
+        let manifest = input.manifest.clone().unwrap();
+
+        let entries = load(&input, &manifest).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[1].path, dir.path().join("b.csv"));
+        assert_eq!(count_rows(&entries, &manifest).unwrap(), 3);
+
+        let df = scan(&entries, &manifest).unwrap().collect().unwrap();
+        assert_eq!(df.get_column_names(), ["x", "day", "y"]);
+        assert_eq!(
+            df.column("day").unwrap().str().unwrap().get(2),
+            Some("2024-01-02")
+        );
+        assert_eq!(df.column("y").unwrap().null_count(), 2);
+
+        let output: Output = serde_yaml::from_str("path: out/\nformat: csv\n").unwrap();
+        assert_eq!(
+            entries[0].output(&output).unwrap().path,
+            Path::new("out/a.csv")
+        );
+        let output: Output = serde_yaml::from_str("path: out/{day}/{stem}.parquet\n").unwrap();
+        assert_eq!(
+            entries[1].output(&output).unwrap().path,
+            Path::new("out/2024-01-02/b.parquet")
+        );
+        let output: Output = serde_yaml::from_str("path: out/{hour}.csv\n").unwrap();
+        assert!(entries[0].output(&output).is_err());
+    }
+}
@@ -0,0 +1,190 @@
+//! Memory estimates used to enforce `runtime.memory_limit`.
+//!
+//! Estimates only look at file metadata, so they are cheap enough to compute before
+//! every run: the uncompressed size recorded in a Parquet footer, or the file size for
+//! text formats (parsed CSV is rarely larger than its text).
+
+use crate::dsl::{Pipeline, Step};
+use crate::errors::{MlPrepError, MlPrepResult};
+use crate::io::{detect_format, FileFormat};
+use polars::prelude::{ParquetReader, SerReader};
+use std::path::Path;
+
+/// Estimated in-memory size of the data in the file at `path`. A remote object is not
+/// downloaded to estimate it: its stored size, from a HEAD request, stands in.
+pub fn estimate_file_bytes(path: &Path, format: FileFormat) -> MlPrepResult<u64> {
+    if crate::input_cache::is_remote(path) {
+        return Ok(crate::input_cache::head(path)?.size);
+    }
+    if format == FileFormat::Parquet {
+        let file = std::fs::File::open(path).map_err(MlPrepError::IoError)?;
+        let metadata = ParquetReader::new(file)
+            .get_metadata()
+            .map_err(MlPrepError::PolarsError)?
+            .clone();
+        Ok(metadata
+            .row_groups
+            .iter()
+            .map(|rg| rg.total_byte_size() as u64)
+            .sum())
+    } else {
+        Ok(std::fs::metadata(path).map_err(MlPrepError::IoError)?.len())
+    }
+}
+
+/// Estimated peak memory of running `pipeline` in memory: every input plus the right
+/// side of each join, all of which may be materialized at once. A manifest input counts
+/// all listed files, or only the largest when they are processed one at a time; an
+/// Iceberg input counts the data files its snapshot reads.
+pub fn estimate_pipeline_bytes(pipeline: &Pipeline) -> MlPrepResult<u64> {
+    let file_bytes =
+        |path: &Path, format: Option<&str>| estimate_file_bytes(path, detect_format(path, format)?);
+    let inputs = pipeline
+        .inputs
+        .iter()
+        .map(|input| match (&input.manifest, &input.iceberg) {
+            // Without the run's parameters, only unparameterized filters prune files
+            (None, Some(table)) => {
+                crate::iceberg::data_files(input, table, &pipeline.steps, &Default::default())?
+                    .iter()
+                    .map(|entry| file_bytes(&entry.path, Some("parquet")))
+                    .sum()
+            }
+            (Some(manifest), _) => {
+                let sizes = crate::manifest::load(input, manifest)?
+                    .iter()
+                    .map(|entry| file_bytes(&entry.path, manifest.format.as_deref()))
+                    .collect::<MlPrepResult<Vec<u64>>>()?;
+                Ok(if manifest.per_file {
+                    sizes.into_iter().max().unwrap_or_default()
+                } else {
+                    sizes.into_iter().sum()
+                })
+            }
+            (None, None) => file_bytes(&input.path, input.format.as_deref()),
+        });
+    let joins = pipeline.steps.iter().filter_map(|step| match step {
+        Step::Join(join) => Some(
+            join.right_input()
+                .and_then(|(path, format)| file_bytes(path, format)),
+        ),
+        _ => None,
+    });
+    inputs.chain(joins).sum::<MlPrepResult<u64>>()
+}
+
+/// Steps (1-based index, kind) that materialize the whole frame even in streaming mode.
+pub fn materializing_steps(pipeline: &Pipeline) -> Vec<(usize, &'static str)> {
+    pipeline
+        .steps
+        .iter()
+        .enumerate()
+        .filter(|(_, step)| matches!(step, Step::PythonUdf(_)))
+        .map(|(i, step)| (i + 1, step.kind()))
+        .collect()
+}
+
+/// Format a byte count for log messages, e.g. `1.5 GB`.
+pub fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KB", "MB", "GB", "TB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} B", bytes)
+    } else {
+        format!("{:.1} {}", value, UNITS[unit])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use polars::prelude::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_estimate_pipeline_bytes() {
+        let dir = tempdir().unwrap();
+        let csv = dir.path().join("input.csv");
+        std::fs::write(&csv, "a,b\n1,2\n3,4\n").unwrap();
+        let parquet = dir.path().join("right.parquet");
+        let df = df!("a" => (0..1000i64).collect::<Vec<_>>()).unwrap();
+        crate::io::write_parquet(df, &parquet).unwrap();
+
+        let pipeline = Pipeline::from_reader(
+            format!(
+                "inputs:\n  - path: \"{}\"\nsteps:\n  - type: join\n    right_path: \"{}\"\n    left_on: [a]\n    right_on: [a]\n  - type: python_udf\n    function: f\n",
+                csv.display(),
+                parquet.display()
+            )
+            .as_bytes(),
+        )
+        .unwrap();
+
+        let parquet_bytes = estimate_file_bytes(&parquet, FileFormat::Parquet).unwrap();
+        // 1000 i64 values, plus page headers.
+        assert!(parquet_bytes >= 8000, "{}", parquet_bytes);
+        assert_eq!(
+            estimate_pipeline_bytes(&pipeline).unwrap(),
+            12 + parquet_bytes
+        );
+        assert_eq!(materializing_steps(&pipeline), vec![(2, "python_udf")]);
+    }
+
+    #[test]
+    fn test_estimate_remote_bytes() {
+        use std::io::{BufRead, BufReader, Write};
+
+        // An S3 endpoint that reports every object as 4096 bytes; nothing is downloaded.
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let endpoint = format!("http://{}", listener.local_addr().unwrap());
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut stream = stream.unwrap();
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut request = String::new();
+                reader.read_line(&mut request).unwrap();
+                let status = if request.starts_with("HEAD") {
+                    "200 OK"
+                } else {
+                    "405 Method Not Allowed"
+                };
+                let mut line = String::new();
+                while reader.read_line(&mut line).unwrap() > 2 {
+                    line.clear();
+                }
+                let _ = write!(
+                    stream,
+                    "HTTP/1.1 {}\r\nContent-Length: 4096\r\nETag: \"v1\"\r\nLast-Modified: Tue, 15 Nov 1994 08:12:31 GMT\r\nConnection: close\r\n\r\n",
+                    status
+                );
+            }
+        });
+        for (key, value) in [
+            ("AWS_ENDPOINT_URL", endpoint.as_str()),
+            ("AWS_REGION", "us-east-1"),
+            ("AWS_ALLOW_HTTP", "true"),
+            ("AWS_ACCESS_KEY_ID", "test"),
+            ("AWS_SECRET_ACCESS_KEY", "test"),
+        ] {
+            std::env::set_var(key, value);
+        }
+
+        let pipeline = Pipeline::from_reader(
+            "inputs:\n  - path: s3://lake/events.csv\nsteps:\n  - type: join\n    right_path: s3://lake/users.parquet\n    left_on: [a]\n    right_on: [a]\n"
+                .as_bytes(),
+        )
+        .unwrap();
+        assert_eq!(estimate_pipeline_bytes(&pipeline).unwrap(), 2 * 4096);
+    }
+
+    #[test]
+    fn test_format_bytes() {
+        assert_eq!(format_bytes(512), "512 B");
+        assert_eq!(format_bytes(3 << 29), "1.5 GB");
+    }
+}
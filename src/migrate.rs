@@ -0,0 +1,398 @@
+//! `mlprep migrate`: rewrite a pipeline written for an older DSL version to the current
+//! one. The YAML text is edited in place, so comments and layout outside the rewritten
+//! blocks are kept; only if that edit does not load back as the same pipeline is the file
+//! re-serialized from scratch.
+//!
+//! Version 2 replaced the map form of `aggs` (column to `{func, alias}`, or to a list of
+//! them) with a list of `{column, func, alias}`.
+
+use crate::dsl::{Pipeline, DSL_VERSION};
+use crate::errors::{MlPrepError, MlPrepResult};
+use serde_yaml::{Mapping, Value};
+
+/// A migrated pipeline.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Migration {
+    /// The migrated YAML
+    pub yaml: String,
+    /// Version the pipeline declared before (1 when it had none)
+    pub from_version: u32,
+    /// What was rewritten, one entry per change
+    pub changes: Vec<String>,
+    /// The file had to be re-serialized, losing its comments and layout
+    pub reformatted: bool,
+}
+
+/// The list form of a map-form `aggs` value, or none if `value` is not one.
+fn agg_list(value: &Value) -> Option<Vec<Value>> {
+    let Value::Mapping(map) = value else {
+        return None;
+    };
+    if map.is_empty() {
+        return None;
+    }
+    let mut aggs = Vec::new();
+    for (column, funcs) in map {
+        let funcs = match funcs {
+            Value::Sequence(funcs) => funcs.clone(),
+            func => vec![func.clone()],
+        };
+        for func in funcs {
+            let Value::Mapping(func) = func else {
+                return None;
+            };
+            if !func.contains_key("func") {
+                return None;
+            }
+            let mut agg = Mapping::new();
+            agg.insert("column".into(), column.clone());
+            agg.extend(func);
+            aggs.push(Value::Mapping(agg));
+        }
+    }
+    Some(aggs)
+}
+
+/// A scalar as it is written inside a flow mapping.
+fn flow_scalar(value: &Value) -> Option<String> {
+    let rendered = serde_yaml::to_string(value).ok()?;
+    let rendered = rendered.trim_end();
+    if rendered.contains('\n') {
+        return None;
+    }
+    if rendered.contains([',', '[', ']', '{', '}', '#']) && !rendered.starts_with(['\'', '"']) {
+        return serde_json::to_string(value).ok();
+    }
+    Some(rendered.to_string())
+}
+
+/// `aggs` entries as YAML list items indented by `indent`: `{column, func, alias}` on one
+/// line each, or in block style when an entry has a structured `where`.
+fn render_aggs(aggs: &[Value], indent: usize) -> Option<String> {
+    let pad = " ".repeat(indent);
+    let mut out = String::new();
+    for agg in aggs {
+        let Value::Mapping(agg) = agg else {
+            return None;
+        };
+        let flow = agg
+            .iter()
+            .map(|(key, value)| Some(format!("{}: {}", flow_scalar(key)?, flow_scalar(value)?)))
+            .collect::<Option<Vec<_>>>()
+            .filter(|_| agg.values().all(|v| !v.is_mapping() && !v.is_sequence()));
+        match flow {
+            Some(fields) => out.push_str(&format!("{}- {{{}}}\n", pad, fields.join(", "))),
+            None => {
+                let block = serde_yaml::to_string(&vec![Value::Mapping(agg.clone())]).ok()?;
+                for line in block.lines() {
+                    out.push_str(&format!("{}{}\n", pad, line));
+                }
+            }
+        }
+    }
+    Some(out)
+}
+
+fn indent_of(line: &str) -> usize {
+    line.len() - line.trim_start_matches(' ').len()
+}
+
+/// Rewrite every map-form `aggs:` in the text as a list, keeping all other lines as they
+/// are. Comments inside a rewritten block are dropped and reported.
+fn rewrite_aggs(text: &str, changes: &mut Vec<String>) -> String {
+    let lines: Vec<&str> = text.lines().collect();
+    let mut out = String::with_capacity(text.len());
+    let mut i = 0;
+    while i < lines.len() {
+        let line = lines[i];
+        let body = line.trim_start_matches(' ');
+        let key = body.strip_prefix("- ").map(str::trim_start).unwrap_or(body);
+        let Some(rest) = key.strip_prefix("aggs:") else {
+            out.push_str(line);
+            out.push('\n');
+            i += 1;
+            continue;
+        };
+        let key_col = line.len() - key.len();
+
+        let inline = rest.trim();
+        let (value, end, dropped) = if inline.is_empty() || inline.starts_with('#') {
+            let mut end = i + 1;
+            let mut last = i;
+            while end < lines.len() {
+                let next = lines[end];
+                if !next.trim().is_empty() {
+                    if indent_of(next) <= key_col {
+                        break;
+                    }
+                    if !next.trim_start().starts_with('#') {
+                        last = end;
+                    }
+                }
+                end += 1;
+            }
+            let block = &lines[i + 1..=last];
+            let dropped = block
+                .iter()
+                .filter(|l| l.trim_start().starts_with('#') || l.contains(" #"))
+                .count();
+            let value = serde_yaml::from_str::<Value>(&block.join("\n")).ok();
+            (value, last + 1, dropped)
+        } else {
+            let value = serde_yaml::from_str::<Value>(inline).ok();
+            (value, i + 1, usize::from(inline.contains(" #")))
+        };
+
+        let rendered = value
+            .as_ref()
+            .and_then(agg_list)
+            .and_then(|aggs| render_aggs(&aggs, key_col + 2));
+        match rendered {
+            Some(rendered) => {
+                let head = if inline.is_empty() || inline.starts_with('#') {
+                    line.to_string()
+                } else {
+                    line[..line.len() - rest.len()].to_string()
+                };
+                out.push_str(&head);
+                out.push('\n');
+                out.push_str(&rendered);
+                let mut change = format!("line {}: rewrote the aggs map as a list", i + 1);
+                if dropped > 0 {
+                    change.push_str(&format!(" (dropped {} comment(s) inside it)", dropped));
+                }
+                changes.push(change);
+                i = end;
+            }
+            None => {
+                out.push_str(line);
+                out.push('\n');
+                i += 1;
+            }
+        }
+    }
+    out
+}
+
+/// Set the top-level `version:` line, or add one before the first key.
+fn set_version(text: &str) -> String {
+    let version = format!("version: {}", DSL_VERSION);
+    let mut lines: Vec<String> = text.lines().map(str::to_string).collect();
+    if let Some(line) = lines.iter_mut().find(|l| l.starts_with("version:")) {
+        *line = version;
+    } else {
+        let at = lines
+            .iter()
+            .position(|l| {
+                let l = l.trim();
+                !l.is_empty() && !l.starts_with('#') && l != "---"
+            })
+            .unwrap_or(lines.len());
+        lines.insert(at, version);
+    }
+    lines.join("\n") + "\n"
+}
+
+/// Rewrite map-form `aggs` anywhere in `value` as lists.
+fn migrate_value(value: &mut Value, path: &str, changes: &mut Vec<String>) {
+    match value {
+        Value::Mapping(map) => {
+            for (key, value) in map.iter_mut() {
+                let key = key.as_str().unwrap_or_default();
+                let path = if path.is_empty() {
+                    key.to_string()
+                } else {
+                    format!("{}.{}", path, key)
+                };
+                if key == "aggs" {
+                    if let Some(aggs) = agg_list(value) {
+                        *value = Value::Sequence(aggs);
+                        changes.push(format!("{}: rewrote the aggs map as a list", path));
+                        continue;
+                    }
+                }
+                migrate_value(value, &path, changes);
+            }
+        }
+        Value::Sequence(items) => {
+            for (idx, item) in items.iter_mut().enumerate() {
+                migrate_value(item, &format!("{}[{}]", path, idx), changes);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Whether `yaml` declares the current version and otherwise loads as `pipeline`.
+fn loads_as(yaml: &str, pipeline: &Pipeline) -> bool {
+    match Pipeline::from_reader(yaml.as_bytes()) {
+        Ok(mut migrated) if migrated.version == Some(DSL_VERSION) => {
+            migrated.version = pipeline.version;
+            migrated == *pipeline
+        }
+        _ => false,
+    }
+}
+
+/// Migrate pipeline YAML to the current DSL version.
+pub fn migrate(text: &str) -> MlPrepResult<Migration> {
+    let pipeline = Pipeline::from_reader(text.as_bytes())?;
+    let from_version = pipeline.version.unwrap_or(1);
+
+    let mut changes = Vec::new();
+    let mut yaml = rewrite_aggs(text, &mut changes);
+    if from_version != DSL_VERSION {
+        yaml = set_version(&yaml);
+        changes.push(format!(
+            "set version: {} (was {})",
+            DSL_VERSION, from_version
+        ));
+    }
+    if changes.is_empty() {
+        return Ok(Migration {
+            yaml: text.to_string(),
+            from_version,
+            changes,
+            reformatted: false,
+        });
+    }
+    if loads_as(&yaml, &pipeline) {
+        return Ok(Migration {
+            yaml,
+            from_version,
+            changes,
+            reformatted: false,
+        });
+    }
+
+    let mut value: Value =
+        serde_yaml::from_str(text).map_err(|e| MlPrepError::ConfigError(e, None))?;
+    let mut changes = Vec::new();
+    migrate_value(&mut value, "", &mut changes);
+    let Value::Mapping(map) = value else {
+        unreachable!("a pipeline is a mapping");
+    };
+    let mut versioned = Mapping::new();
+    versioned.insert("version".into(), DSL_VERSION.into());
+    versioned.extend(
+        map.into_iter()
+            .filter(|(key, _)| key.as_str() != Some("version")),
+    );
+    if from_version != DSL_VERSION {
+        changes.push(format!(
+            "set version: {} (was {})",
+            DSL_VERSION, from_version
+        ));
+    }
+    let yaml = serde_yaml::to_string(&Value::Mapping(versioned))
+        .map_err(|e| MlPrepError::ConfigError(e, None))?;
+    if !loads_as(&yaml, &pipeline) {
+        return Err(MlPrepError::TransformError(
+            "Migrated pipeline does not load as the original".to_string(),
+        ));
+    }
+    Ok(Migration {
+        yaml,
+        from_version,
+        changes,
+        reformatted: true,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LEGACY: &str = r#"# Daily revenue by category
+inputs:
+  - path: sales.csv
+
+steps:
+  - type: group_by
+    by: [category]   # one row per category
+    aggs:
+      # total first
+      amount: {func: sum, alias: total_amount}
+      price: [{func: min}, {func: max, where: "price > 0"}]
+  - type: sort
+    by: [total_amount]
+outputs:
+  - path: out.parquet
+"#;
+
+    #[test]
+    fn test_migrate_rewrites_aggs_and_keeps_comments() {
+        let migration = migrate(LEGACY).unwrap();
+        assert_eq!(migration.from_version, 1);
+        assert!(!migration.reformatted);
+        assert_eq!(
+            migration.changes,
+            [
+                "line 8: rewrote the aggs map as a list (dropped 1 comment(s) inside it)",
+                "set version: 2 (was 1)"
+            ]
+        );
+        assert!(
+            migration.yaml.starts_with(
+                "# Daily revenue by category\nversion: 2\ninputs:\n  - path: sales.csv\n"
+            ),
+            "{}",
+            migration.yaml
+        );
+        assert!(
+            migration.yaml.contains(
+                "    by: [category]   # one row per category\n    aggs:\n      \
+                 - {column: amount, func: sum, alias: total_amount}\n      \
+                 - {column: price, func: min}\n      \
+                 - {column: price, func: max, where: price > 0}\n  - type: sort\n"
+            ),
+            "{}",
+            migration.yaml
+        );
+        assert_eq!(
+            Pipeline::from_reader(migration.yaml.as_bytes()).unwrap(),
+            Pipeline {
+                version: Some(DSL_VERSION),
+                ..Pipeline::from_reader(LEGACY.as_bytes()).unwrap()
+            }
+        );
+
+        let again = migrate(&migration.yaml).unwrap();
+        assert_eq!(again.from_version, 2);
+        assert!(again.changes.is_empty());
+        assert_eq!(again.yaml, migration.yaml);
+    }
+
+    #[test]
+    fn test_migrate_structured_where_and_versions() {
+        let yaml = "version: 1\nsteps:\n  - type: resample\n    time_column: ts\n    every: 1h\n    aggs: {v: {func: sum, where: {column: v, op: gt, value: 0}}}\n";
+        let migration = migrate(yaml).unwrap();
+        assert!(!migration.reformatted);
+        assert!(
+            migration.yaml.starts_with("version: 2\nsteps:\n"),
+            "{}",
+            migration.yaml
+        );
+        assert!(
+            migration
+                .yaml
+                .contains("    aggs:\n      - column: v\n        func: sum\n        where:\n"),
+            "{}",
+            migration.yaml
+        );
+
+        let err = migrate("version: 3\nsteps: []\n").unwrap_err().to_string();
+        assert!(err.contains("Configuration error"), "{}", err);
+        assert!(Pipeline::from_reader("version: 0\nsteps: []\n".as_bytes()).is_err());
+    }
+
+    #[test]
+    fn test_migrate_value_fallback() {
+        let mut value: Value = serde_yaml::from_str(LEGACY).unwrap();
+        let mut changes = Vec::new();
+        migrate_value(&mut value, "", &mut changes);
+        assert_eq!(changes, ["steps[0].aggs: rewrote the aggs map as a list"]);
+        assert_eq!(value["steps"][0]["aggs"][2]["column"], "price");
+        assert_eq!(value["steps"][0]["aggs"][2]["func"], "max");
+    }
+}
@@ -0,0 +1,340 @@
+//! Run notifications (`runtime.notifications`): each webhook is POSTed a JSON or Slack
+//! message when a run fails, or finishes with validation violations or a quality score
+//! below its threshold, so bad data is heard of without scraping scheduler logs.
+
+use crate::dsl::{Notification, NotificationFormat};
+use crate::errors::{MlPrepError, MlPrepResult};
+use crate::observability::RunSummary;
+use chrono::Utc;
+use serde_json::json;
+use std::path::Path;
+use std::time::Duration;
+use tracing::{info, warn};
+use uuid::Uuid;
+
+/// Warnings listed in a Slack message; the JSON payload carries them all.
+const MAX_LISTED_WARNINGS: usize = 10;
+
+/// How long a webhook may take to accept the connection, and then to answer, before its
+/// delivery fails; an unresponsive endpoint must not hang the run.
+const WEBHOOK_CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+const WEBHOOK_READ_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// The HTTP agent that delivers webhooks: notifications and `on_failure` webhooks.
+pub(crate) fn webhook_agent() -> ureq::Agent {
+    agent(WEBHOOK_CONNECT_TIMEOUT, WEBHOOK_READ_TIMEOUT)
+}
+
+fn agent(connect: Duration, read: Duration) -> ureq::Agent {
+    ureq::AgentBuilder::new()
+        .timeout_connect(connect)
+        .timeout_read(read)
+        .timeout_write(read)
+        .build()
+}
+
+/// Why a run is notified.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Event {
+    Failed,
+    /// A strict-mode check failed the run
+    ValidationFailed,
+    Violations,
+    LowQuality,
+}
+
+impl Event {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Event::Failed => "failed",
+            Event::ValidationFailed => "validation_failed",
+            Event::Violations => "violations",
+            Event::LowQuality => "low_quality",
+        }
+    }
+}
+
+/// What `notification` reports about a run's outcome, if anything. A low quality score
+/// takes precedence over violations.
+pub fn event(notification: &Notification, outcome: &MlPrepResult<RunSummary>) -> Option<Event> {
+    match outcome {
+        Err(MlPrepError::ValidationError(_)) => Some(Event::ValidationFailed),
+        Err(_) => Some(Event::Failed),
+        Ok(summary) => {
+            let low_quality = match (notification.min_quality_score, summary.quality_score) {
+                (Some(min), Some(score)) => score < min,
+                _ => false,
+            };
+            if low_quality {
+                Some(Event::LowQuality)
+            } else if notification.on_violations && summary.violations > 0 {
+                Some(Event::Violations)
+            } else {
+                None
+            }
+        }
+    }
+}
+
+/// The body POSTed to `notification.url` for `event`. The JSON format is the run summary
+/// (or, for a failed run, the `on_failure` webhook payload) with `event` added.
+pub fn payload(
+    notification: &Notification,
+    event: Event,
+    pipeline: &Path,
+    run_id: Uuid,
+    outcome: &MlPrepResult<RunSummary>,
+) -> serde_json::Value {
+    match notification.format {
+        NotificationFormat::Json => {
+            let mut body = match outcome {
+                Ok(summary) => serde_json::to_value(summary).unwrap_or_default(),
+                Err(e) => json!({
+                    "run_id": run_id.to_string(),
+                    "pipeline": pipeline.display().to_string(),
+                    "status": "failed",
+                    "error": e.to_string(),
+                }),
+            };
+            body["event"] = json!(event.as_str());
+            body["timestamp"] = json!(Utc::now());
+            body
+        }
+        NotificationFormat::Slack => {
+            json!({ "text": slack_text(notification, event, pipeline, outcome) })
+        }
+    }
+}
+
+fn slack_text(
+    notification: &Notification,
+    event: Event,
+    pipeline: &Path,
+    outcome: &MlPrepResult<RunSummary>,
+) -> String {
+    let name = pipeline.display();
+    let summary = match outcome {
+        Err(MlPrepError::ValidationError(message)) => {
+            return format!(":x: mlprep: {} failed validation: {}", name, message)
+        }
+        Err(e) => return format!(":x: mlprep: {} failed: {}", name, e),
+        Ok(summary) => summary,
+    };
+    let mut text = match (event, summary.quality_score) {
+        (Event::LowQuality, Some(score)) => format!(
+            ":warning: mlprep: {} has quality score {:.3}, below {}",
+            name,
+            score,
+            notification.min_quality_score.unwrap_or_default()
+        ),
+        _ => format!(
+            ":warning: mlprep: {} finished with {} violations",
+            name, summary.violations
+        ),
+    };
+    for warning in summary.warnings.iter().take(MAX_LISTED_WARNINGS) {
+        text.push_str(&format!("\n• `{}` {}", warning.code, warning.message));
+    }
+    if summary.warnings.len() > MAX_LISTED_WARNINGS {
+        text.push_str(&format!(
+            "\n… and {} more",
+            summary.warnings.len() - MAX_LISTED_WARNINGS
+        ));
+    }
+    text
+}
+
+/// Send every notification that applies to the run's outcome. Delivery errors are
+/// logged as warnings and never change the outcome.
+pub fn notify(
+    notifications: &[Notification],
+    pipeline: &Path,
+    run_id: Uuid,
+    outcome: &MlPrepResult<RunSummary>,
+) {
+    let agent = webhook_agent();
+    for notification in notifications {
+        let Some(event) = event(notification, outcome) else {
+            continue;
+        };
+        let body = payload(notification, event, pipeline, run_id, outcome);
+        match agent.post(&notification.url).send_json(body) {
+            Ok(_) => info!(
+                "Notified {} of {}",
+                notification.url,
+                event.as_str().replace('_', " ")
+            ),
+            Err(e) => warn!("Notification to {} failed: {}", notification.url, e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::observability::{Metrics, RunWarning};
+    use std::io::{BufRead, BufReader, Read, Write};
+
+    fn summary(violations: usize, quality_score: Option<f64>) -> RunSummary {
+        let mut metrics = Metrics::new();
+        metrics.violations = violations;
+        metrics.quality_score = quality_score;
+        metrics.warnings = (0..12)
+            .map(|i| RunWarning {
+                step: 1,
+                kind: "validate".to_string(),
+                code: "validate.not_null".to_string(),
+                message: format!("Column 'c{}' has 1 null values", i),
+                count: 1,
+            })
+            .collect();
+        RunSummary::success(Uuid::nil(), Path::new("daily.yaml"), &metrics, Vec::new())
+    }
+
+    fn notification(yaml: &str) -> Notification {
+        serde_yaml::from_str(yaml).unwrap()
+    }
+
+    #[test]
+    fn test_events_and_payloads() {
+        let plain = notification("url: http://localhost/hook\n");
+        let quiet = notification(
+            "url: http://localhost/hook\non_violations: false\nmin_quality_score: 0.9\n",
+        );
+        let strict: MlPrepResult<RunSummary> = Err(MlPrepError::ValidationError(
+            "Validation failed with 3 violations".to_string(),
+        ));
+        let io: MlPrepResult<RunSummary> = Err(MlPrepError::IoError(std::io::Error::other("disk")));
+
+        assert_eq!(event(&plain, &Ok(summary(0, Some(1.0)))), None);
+        assert_eq!(
+            event(&plain, &Ok(summary(2, Some(0.5)))),
+            Some(Event::Violations)
+        );
+        assert_eq!(event(&quiet, &Ok(summary(2, Some(0.95)))), None);
+        assert_eq!(
+            event(&quiet, &Ok(summary(2, Some(0.5)))),
+            Some(Event::LowQuality)
+        );
+        assert_eq!(event(&quiet, &strict), Some(Event::ValidationFailed));
+        assert_eq!(event(&quiet, &io), Some(Event::Failed));
+
+        let pipeline = Path::new("daily.yaml");
+        let body = payload(
+            &plain,
+            Event::Violations,
+            pipeline,
+            Uuid::nil(),
+            &Ok(summary(2, Some(0.5))),
+        );
+        assert_eq!(body["event"], "violations");
+        assert_eq!(body["violations"], 2);
+        assert_eq!(body["warnings"].as_array().unwrap().len(), 12);
+        let body = payload(
+            &plain,
+            Event::ValidationFailed,
+            pipeline,
+            Uuid::nil(),
+            &strict,
+        );
+        assert_eq!(body["status"], "failed");
+        assert_eq!(
+            body["error"],
+            "Validation failed: Validation failed with 3 violations"
+        );
+
+        let slack =
+            notification("url: http://localhost/hook\nformat: slack\nmin_quality_score: 0.9\n");
+        let body = payload(
+            &slack,
+            Event::LowQuality,
+            pipeline,
+            Uuid::nil(),
+            &Ok(summary(2, Some(0.5))),
+        );
+        let text = body["text"].as_str().unwrap();
+        assert!(
+            text.starts_with(":warning: mlprep: daily.yaml has quality score 0.500, below 0.9\n• `validate.not_null` Column 'c0' has 1 null values"),
+            "{}",
+            text
+        );
+        assert!(text.ends_with("\n… and 2 more"), "{}", text);
+        let body = payload(
+            &slack,
+            Event::ValidationFailed,
+            pipeline,
+            Uuid::nil(),
+            &strict,
+        );
+        assert_eq!(
+            body["text"],
+            ":x: mlprep: daily.yaml failed validation: Validation failed with 3 violations"
+        );
+    }
+
+    #[test]
+    fn test_notify_posts_to_webhook() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/hook", listener.local_addr().unwrap());
+        let server = std::thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream);
+            let mut length = 0;
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                if let Some(value) = line.to_ascii_lowercase().strip_prefix("content-length:") {
+                    length = value.trim().parse().unwrap();
+                }
+                if line == "\r\n" {
+                    break;
+                }
+            }
+            let mut body = vec![0; length];
+            reader.read_exact(&mut body).unwrap();
+            reader
+                .get_mut()
+                .write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n")
+                .unwrap();
+            serde_json::from_slice::<serde_json::Value>(&body).unwrap()
+        });
+
+        let notifications = [
+            notification(&format!("url: {}\n", url)),
+            // Not notified: the run has no violations to report
+            notification("url: http://127.0.0.1:9/unused\nmin_quality_score: 0.1\n"),
+        ];
+        let io: MlPrepResult<RunSummary> = Err(MlPrepError::IoError(std::io::Error::other("disk")));
+        notify(
+            &notifications[..1],
+            Path::new("daily.yaml"),
+            Uuid::nil(),
+            &io,
+        );
+        notify(
+            &notifications[1..],
+            Path::new("daily.yaml"),
+            Uuid::nil(),
+            &Ok(summary(0, Some(1.0))),
+        );
+
+        let body = server.join().unwrap();
+        assert_eq!(body["event"], "failed");
+        assert_eq!(body["pipeline"], "daily.yaml");
+        assert!(body["error"].as_str().unwrap().contains("disk"));
+    }
+
+    #[test]
+    fn test_webhook_agent_times_out() {
+        // Accepts the connection but never answers
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/hook", listener.local_addr().unwrap());
+        let started = std::time::Instant::now();
+        let result = agent(Duration::from_secs(1), Duration::from_millis(200))
+            .post(&url)
+            .send_json(json!({}));
+        assert!(result.is_err());
+        assert!(started.elapsed() < Duration::from_secs(5));
+        drop(listener);
+    }
+}
@@ -1,11 +1,12 @@
 use chrono::{DateTime, Utc};
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
 use std::collections::HashMap;
 use std::fs::File;
 use std::io::{self, Read};
 use std::path::Path;
 use std::time::{Duration, Instant};
+use uuid::Uuid;
 
 #[derive(Debug, Serialize)]
 pub struct Metrics {
@@ -13,7 +14,62 @@ pub struct Metrics {
     start_time: Instant,
     pub rows_read: usize,
     pub rows_written: usize,
+    pub violations: usize,
+    /// 1 for a first-try success; incremented for each retry
+    pub attempts: u32,
     pub step_durations_ms: HashMap<String, u64>,
+    /// Runtime settings in effect for the run
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub runtime: Option<EffectiveRuntime>,
+    /// Problems that did not stop the run
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<RunWarning>,
+    /// Weighted pass rate of the validation checks, from 0 to 1; none without checks
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub quality_score: Option<f64>,
+    /// Sums behind `quality_score`: (weight × pass rate, weight)
+    #[serde(skip)]
+    quality: (f64, f64),
+    /// Step being planned, as (1-based index, kind); warnings are attributed to it
+    #[serde(skip)]
+    step: Option<(usize, &'static str)>,
+}
+
+/// A problem found while running a step that did not stop the run, e.g. rows failing
+/// a `warn`-mode check or a `warn_only` join cardinality expectation.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RunWarning {
+    /// Position of the step in `steps` (1-based); 0 when raised outside the steps
+    pub step: usize,
+    /// Kind of the step, e.g. `validate`
+    pub kind: String,
+    /// Stable identifier of the problem, e.g. `validate.not_null`
+    pub code: String,
+    pub message: String,
+    /// Rows or key values affected
+    pub count: usize,
+}
+
+/// Runtime settings actually applied to a run, after merging the `runtime` block with
+/// overrides and accounting for process-wide state (e.g. an already-started thread pool).
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct EffectiveRuntime {
+    /// Size of the Polars thread pool
+    pub threads: usize,
+    /// Common subplan/subexpression caching
+    pub cache: bool,
+    pub streaming: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub memory_limit_bytes: Option<u64>,
+    /// Estimated peak memory, computed when a memory limit is set
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub estimated_bytes: Option<u64>,
+    /// The estimate exceeded the limit, so the run was switched to streaming execution
+    /// and out-of-core writes
+    pub degraded: bool,
+    /// Seed for random operations; set, the run is deterministic
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub seed: Option<u64>,
 }
 
 impl Metrics {
@@ -22,7 +78,49 @@ impl Metrics {
             start_time: Instant::now(),
             rows_read: 0,
             rows_written: 0,
+            violations: 0,
+            attempts: 1,
             step_durations_ms: HashMap::new(),
+            runtime: None,
+            warnings: Vec::new(),
+            quality_score: None,
+            quality: (0.0, 0.0),
+            step: None,
+        }
+    }
+
+    /// Attribute warnings raised from here on to step `index` (1-based) of kind `kind`.
+    pub fn enter_step(&mut self, index: usize, kind: &'static str) {
+        self.step = Some((index, kind));
+    }
+
+    /// Log a warning and record it against the current step.
+    pub fn warn(&mut self, code: impl Into<String>, message: impl Into<String>, count: usize) {
+        let (step, kind) = self.step.unwrap_or((0, ""));
+        let warning = RunWarning {
+            step,
+            kind: kind.to_string(),
+            code: code.into(),
+            message: message.into(),
+            count,
+        };
+        tracing::warn!(
+            step = warning.step,
+            code = %warning.code,
+            count = warning.count,
+            "{}",
+            warning.message
+        );
+        self.warnings.push(warning);
+    }
+
+    /// Fold the weighted pass rate of a validation (see [`crate::validate::quality`]) into
+    /// the quality score.
+    pub fn record_quality(&mut self, (score, weight): (f64, f64)) {
+        self.quality.0 += score;
+        self.quality.1 += weight;
+        if self.quality.1 > 0.0 {
+            self.quality_score = Some(self.quality.0 / self.quality.1);
         }
     }
 
@@ -31,6 +129,19 @@ impl Metrics {
             .insert(step_name.to_string(), duration.as_millis() as u64);
     }
 
+    /// Add the rows, violations, step durations, warnings and quality of one part of a run,
+    /// e.g. one file of a per-file manifest.
+    pub fn add(&mut self, part: &Metrics) {
+        self.rows_read += part.rows_read;
+        self.rows_written += part.rows_written;
+        self.violations += part.violations;
+        for (step, ms) in &part.step_durations_ms {
+            *self.step_durations_ms.entry(step.clone()).or_default() += ms;
+        }
+        self.warnings.extend(part.warnings.iter().cloned());
+        self.record_quality(part.quality);
+    }
+
     pub fn total_duration(&self) -> Duration {
         self.start_time.elapsed()
     }
@@ -42,22 +153,211 @@ impl Default for Metrics {
     }
 }
 
-#[derive(Debug, Serialize)]
+/// Machine-readable outcome of a single pipeline run.
+#[derive(Debug, Clone, Serialize)]
+pub struct RunSummary {
+    pub run_id: String,
+    pub pipeline: String,
+    pub status: RunStatus,
+    pub rows_read: usize,
+    pub rows_written: usize,
+    pub violations: usize,
+    pub attempts: u32,
+    pub duration_ms: u64,
+    pub step_durations_ms: HashMap<String, u64>,
+    pub outputs: Vec<String>,
+    /// Scan pushdown report, filled in when `explain_io` is enabled.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub scans: Vec<crate::io::ScanPushdown>,
+    /// Lineage file written next to the pipeline, when outputs were produced.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub lineage: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub runtime: Option<EffectiveRuntime>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<RunWarning>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub quality_score: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RunStatus {
+    Success,
+    Failed,
+}
+
+impl RunSummary {
+    pub fn success(run_id: Uuid, pipeline: &Path, metrics: &Metrics, outputs: Vec<String>) -> Self {
+        Self {
+            run_id: run_id.to_string(),
+            pipeline: pipeline.display().to_string(),
+            status: RunStatus::Success,
+            rows_read: metrics.rows_read,
+            rows_written: metrics.rows_written,
+            violations: metrics.violations,
+            attempts: metrics.attempts,
+            duration_ms: metrics.total_duration().as_millis() as u64,
+            step_durations_ms: metrics.step_durations_ms.clone(),
+            outputs,
+            scans: Vec::new(),
+            lineage: None,
+            runtime: metrics.runtime.clone(),
+            warnings: metrics.warnings.clone(),
+            quality_score: metrics.quality_score,
+            error: None,
+        }
+    }
+
+    pub fn failed(
+        run_id: Uuid,
+        pipeline: &Path,
+        duration: Duration,
+        attempts: u32,
+        error: String,
+    ) -> Self {
+        Self {
+            run_id: run_id.to_string(),
+            pipeline: pipeline.display().to_string(),
+            status: RunStatus::Failed,
+            rows_read: 0,
+            rows_written: 0,
+            violations: 0,
+            attempts,
+            duration_ms: duration.as_millis() as u64,
+            step_durations_ms: HashMap::new(),
+            outputs: Vec::new(),
+            scans: Vec::new(),
+            lineage: None,
+            runtime: None,
+            warnings: Vec::new(),
+            quality_score: None,
+            error: Some(error),
+        }
+    }
+}
+
+/// Record of a run written next to the pipeline, enough to reproduce it or to compare
+/// it with another run (`mlprep diff-runs`).
+#[derive(Debug, Serialize, Deserialize)]
 pub struct Lineage {
     pub run_id: String,
     pub timestamp: DateTime<Utc>,
-    pub inputs: Vec<InputFileStats>,
-    // We could add output path here too
+    /// Lineage files written before retries were recorded count as one attempt
+    #[serde(default = "one_attempt")]
+    pub attempts: u32,
+    pub inputs: Vec<FileStats>,
     pub outputs: Vec<String>,
+    /// Size and hash of each output as written
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub output_files: Vec<FileStats>,
+    #[serde(default)]
+    pub rows_read: usize,
+    #[serde(default)]
+    pub rows_written: usize,
+    #[serde(default)]
+    pub violations: usize,
+    /// Weighted pass rate of the validation checks (see [`Metrics::quality_score`])
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub quality_score: Option<f64>,
+    /// Steps as configured for the run
+    #[serde(default)]
+    pub steps: Vec<crate::dsl::Step>,
+    /// Output columns and their dtypes
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub schema: Vec<SchemaField>,
+    /// Seed the run used, for reproducing it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub seed: Option<u64>,
+    /// Input columns each output column derives from
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub columns: Vec<crate::lineage::ColumnLineage>,
 }
 
-#[derive(Debug, Serialize)]
-pub struct InputFileStats {
+fn one_attempt() -> u32 {
+    1
+}
+
+impl Lineage {
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let path = path.as_ref();
+        let file = File::open(path)?;
+        serde_json::from_reader(io::BufReader::new(file)).map_err(|e| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{} is not a lineage file: {}", path.display(), e),
+            )
+        })
+    }
+}
+
+/// Size and content hash of an input or output.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FileStats {
     pub path: String,
     pub hash: String, // SHA256 hex
     pub size_bytes: u64,
 }
 
+impl FileStats {
+    /// Stats of a file, or of a dataset directory: its files' hashes combined in path
+    /// order, and their total size. A remote file read without the input cache is not
+    /// downloaded to hash it; its version (ETag) stands in for the hash.
+    pub fn of<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let path = path.as_ref();
+        if crate::input_cache::is_remote(path) {
+            let object = crate::input_cache::head(path).map_err(io::Error::other)?;
+            return Ok(Self {
+                path: path.display().to_string(),
+                hash: object.version,
+                size_bytes: object.size,
+            });
+        }
+        let (hash, size_bytes) = if path.is_dir() {
+            let mut files = Vec::new();
+            collect_files(path, &mut files)?;
+            files.sort();
+            let mut hasher = Sha256::new();
+            let mut size = 0;
+            for file in &files {
+                let relative = file.strip_prefix(path).unwrap_or(file);
+                hasher.update(relative.to_string_lossy().as_bytes());
+                hasher.update(compute_file_hash(file)?.as_bytes());
+                size += std::fs::metadata(file)?.len();
+            }
+            (format!("{:x}", hasher.finalize()), size)
+        } else {
+            (compute_file_hash(path)?, std::fs::metadata(path)?.len())
+        };
+        Ok(Self {
+            path: path.display().to_string(),
+            hash,
+            size_bytes,
+        })
+    }
+}
+
+fn collect_files(dir: &Path, files: &mut Vec<std::path::PathBuf>) -> io::Result<()> {
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            collect_files(&path, files)?;
+        } else {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// An output column and its Polars dtype.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SchemaField {
+    pub name: String,
+    pub dtype: String,
+}
+
 pub fn compute_file_hash<P: AsRef<Path>>(path: P) -> io::Result<String> {
     let mut file = File::open(path)?;
     let mut hasher = Sha256::new();
@@ -73,3 +373,51 @@ pub fn compute_file_hash<P: AsRef<Path>>(path: P) -> io::Result<String> {
 
     Ok(format!("{:x}", hasher.finalize()))
 }
+
+/// Keeps the OTLP tracer provider alive and flushes pending spans on drop.
+#[cfg(feature = "otel")]
+pub struct OtelGuard {
+    provider: opentelemetry_sdk::trace::TracerProvider,
+}
+
+#[cfg(feature = "otel")]
+impl Drop for OtelGuard {
+    fn drop(&mut self) {
+        if let Err(e) = self.provider.shutdown() {
+            eprintln!("Failed to flush OpenTelemetry spans: {}", e);
+        }
+    }
+}
+
+/// Build a tracing layer exporting spans to an OTLP/HTTP collector (e.g. `http://localhost:4318/v1/traces`).
+#[cfg(feature = "otel")]
+pub fn otel_layer<S>(
+    endpoint: &str,
+) -> crate::errors::MlPrepResult<(impl tracing_subscriber::Layer<S>, OtelGuard)>
+where
+    S: tracing::Subscriber + for<'span> tracing_subscriber::registry::LookupSpan<'span>,
+{
+    use opentelemetry::trace::TracerProvider as _;
+    use opentelemetry::KeyValue;
+    use opentelemetry_otlp::WithExportConfig;
+
+    let exporter = opentelemetry_otlp::SpanExporter::builder()
+        .with_http()
+        .with_endpoint(endpoint)
+        .build()
+        .map_err(|e| crate::errors::MlPrepError::Unknown(e.into()))?;
+
+    let provider = opentelemetry_sdk::trace::TracerProvider::builder()
+        .with_simple_exporter(exporter)
+        .with_resource(opentelemetry_sdk::Resource::new(vec![KeyValue::new(
+            "service.name",
+            "mlprep",
+        )]))
+        .build();
+    let tracer = provider.tracer("mlprep");
+
+    Ok((
+        tracing_opentelemetry::layer().with_tracer(tracer),
+        OtelGuard { provider },
+    ))
+}
@@ -0,0 +1,570 @@
+//! Export fitted feature transforms as an ONNX-ML preprocessing graph.
+//!
+//! Each source column becomes a `[N, 1]` graph input (float for numeric columns,
+//! string for encoded ones) and each produced feature a `[N, 1]` float output named
+//! like the column mlprep would produce. Only the subset of the ONNX protobuf
+//! schema needed to write such graphs is defined here.
+
+use crate::features::{FeatureConfig, FeatureState, FeatureStateEntry, FeatureTransform};
+use anyhow::{anyhow, Result};
+use prost::Message;
+use std::collections::HashSet;
+
+const IR_VERSION: i64 = 8;
+const ONNX_OPSET: i64 = 13;
+const ONNX_ML_OPSET: i64 = 2;
+const ML_DOMAIN: &str = "ai.onnx.ml";
+
+const FLOAT: i32 = 1;
+const INT64: i32 = 7;
+const STRING: i32 = 8;
+
+#[derive(Clone, PartialEq, Message)]
+pub struct ModelProto {
+    #[prost(int64, tag = "1")]
+    pub ir_version: i64,
+    #[prost(string, tag = "2")]
+    pub producer_name: String,
+    #[prost(string, tag = "3")]
+    pub producer_version: String,
+    #[prost(message, optional, tag = "7")]
+    pub graph: Option<GraphProto>,
+    #[prost(message, repeated, tag = "8")]
+    pub opset_import: Vec<OperatorSetIdProto>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct OperatorSetIdProto {
+    #[prost(string, tag = "1")]
+    pub domain: String,
+    #[prost(int64, tag = "2")]
+    pub version: i64,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct GraphProto {
+    #[prost(message, repeated, tag = "1")]
+    pub node: Vec<NodeProto>,
+    #[prost(string, tag = "2")]
+    pub name: String,
+    #[prost(message, repeated, tag = "5")]
+    pub initializer: Vec<TensorProto>,
+    #[prost(message, repeated, tag = "11")]
+    pub input: Vec<ValueInfoProto>,
+    #[prost(message, repeated, tag = "12")]
+    pub output: Vec<ValueInfoProto>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct NodeProto {
+    #[prost(string, repeated, tag = "1")]
+    pub input: Vec<String>,
+    #[prost(string, repeated, tag = "2")]
+    pub output: Vec<String>,
+    #[prost(string, tag = "3")]
+    pub name: String,
+    #[prost(string, tag = "4")]
+    pub op_type: String,
+    #[prost(message, repeated, tag = "5")]
+    pub attribute: Vec<AttributeProto>,
+    #[prost(string, tag = "7")]
+    pub domain: String,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct AttributeProto {
+    #[prost(string, tag = "1")]
+    pub name: String,
+    #[prost(float, tag = "2")]
+    pub f: f32,
+    #[prost(int64, tag = "3")]
+    pub i: i64,
+    #[prost(float, repeated, tag = "7")]
+    pub floats: Vec<f32>,
+    #[prost(bytes = "vec", repeated, tag = "9")]
+    pub strings: Vec<Vec<u8>>,
+    /// AttributeType: FLOAT = 1, INT = 2, FLOATS = 6, STRINGS = 8
+    #[prost(int32, tag = "20")]
+    pub r#type: i32,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct TensorProto {
+    #[prost(int64, repeated, tag = "1")]
+    pub dims: Vec<i64>,
+    #[prost(int32, tag = "2")]
+    pub data_type: i32,
+    #[prost(float, repeated, tag = "4")]
+    pub float_data: Vec<f32>,
+    #[prost(int64, repeated, tag = "7")]
+    pub int64_data: Vec<i64>,
+    #[prost(string, tag = "8")]
+    pub name: String,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct ValueInfoProto {
+    #[prost(string, tag = "1")]
+    pub name: String,
+    #[prost(message, optional, tag = "2")]
+    pub r#type: Option<TypeProto>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct TypeProto {
+    #[prost(message, optional, tag = "1")]
+    pub tensor_type: Option<TensorTypeProto>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct TensorTypeProto {
+    #[prost(int32, tag = "1")]
+    pub elem_type: i32,
+    #[prost(message, optional, tag = "2")]
+    pub shape: Option<TensorShapeProto>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct TensorShapeProto {
+    #[prost(message, repeated, tag = "1")]
+    pub dim: Vec<Dimension>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct Dimension {
+    #[prost(int64, optional, tag = "1")]
+    pub dim_value: Option<i64>,
+    #[prost(string, optional, tag = "2")]
+    pub dim_param: Option<String>,
+}
+
+fn column_value(name: &str, elem_type: i32) -> ValueInfoProto {
+    ValueInfoProto {
+        name: name.to_string(),
+        r#type: Some(TypeProto {
+            tensor_type: Some(TensorTypeProto {
+                elem_type,
+                shape: Some(TensorShapeProto {
+                    dim: vec![
+                        Dimension {
+                            dim_param: Some("N".to_string()),
+                            ..Default::default()
+                        },
+                        Dimension {
+                            dim_value: Some(1),
+                            ..Default::default()
+                        },
+                    ],
+                }),
+            }),
+        }),
+    }
+}
+
+fn floats_attr(name: &str, values: Vec<f32>) -> AttributeProto {
+    AttributeProto {
+        name: name.to_string(),
+        floats: values,
+        r#type: 6,
+        ..Default::default()
+    }
+}
+
+fn strings_attr(name: &str, values: impl IntoIterator<Item = String>) -> AttributeProto {
+    AttributeProto {
+        name: name.to_string(),
+        strings: values.into_iter().map(String::into_bytes).collect(),
+        r#type: 8,
+        ..Default::default()
+    }
+}
+
+fn int_attr(name: &str, value: i64) -> AttributeProto {
+    AttributeProto {
+        name: name.to_string(),
+        i: value,
+        r#type: 2,
+        ..Default::default()
+    }
+}
+
+fn float_attr(name: &str, value: f32) -> AttributeProto {
+    AttributeProto {
+        name: name.to_string(),
+        f: value,
+        r#type: 1,
+        ..Default::default()
+    }
+}
+
+/// Accumulates nodes, inputs and outputs while walking the feature specs.
+#[derive(Default)]
+struct GraphBuilder {
+    graph: GraphProto,
+    inputs: HashSet<String>,
+    outputs: HashSet<String>,
+    counter: usize,
+}
+
+impl GraphBuilder {
+    /// Declare `column` as a graph input (once) and return its value name.
+    fn input(&mut self, column: &str, elem_type: i32) -> String {
+        if self.inputs.insert(column.to_string()) {
+            self.graph.input.push(column_value(column, elem_type));
+        }
+        column.to_string()
+    }
+
+    fn temp(&mut self, hint: &str) -> String {
+        self.counter += 1;
+        format!("{}__{}", hint, self.counter)
+    }
+
+    /// Name for a produced feature; in-place outputs can't reuse the input's name.
+    fn output(&mut self, name: &str) -> Result<String> {
+        let name = if self.inputs.contains(name) {
+            format!("{}_out", name)
+        } else {
+            name.to_string()
+        };
+        if !self.outputs.insert(name.clone()) {
+            return Err(anyhow!("Two features produce the output '{}'", name));
+        }
+        self.graph.output.push(column_value(&name, FLOAT));
+        Ok(name)
+    }
+
+    fn node(
+        &mut self,
+        op_type: &str,
+        domain: &str,
+        input: Vec<String>,
+        output: Vec<String>,
+        attribute: Vec<AttributeProto>,
+    ) {
+        let name = format!("{}_{}", op_type, self.graph.node.len());
+        self.graph.node.push(NodeProto {
+            input,
+            output,
+            name,
+            op_type: op_type.to_string(),
+            attribute,
+            domain: domain.to_string(),
+        });
+    }
+
+    fn initializer(&mut self, hint: &str, dims: Vec<i64>, data: Vec<f32>) -> String {
+        let name = self.temp(hint);
+        self.graph.initializer.push(TensorProto {
+            dims,
+            data_type: FLOAT,
+            float_data: data,
+            name: name.clone(),
+            ..Default::default()
+        });
+        name
+    }
+
+    /// `(x - offset) * scale`, plus `bias` when non-zero (constant columns).
+    fn scaler(&mut self, input: String, output: &str, offset: f64, scale: f64, bias: f64) {
+        let attrs = vec![
+            floats_attr("offset", vec![offset as f32]),
+            floats_attr("scale", vec![scale as f32]),
+        ];
+        if bias == 0.0 {
+            self.node("Scaler", ML_DOMAIN, vec![input], vec![output.into()], attrs);
+        } else {
+            let scaled = self.temp(output);
+            self.node(
+                "Scaler",
+                ML_DOMAIN,
+                vec![input],
+                vec![scaled.clone()],
+                attrs,
+            );
+            let bias = self.initializer("bias", vec![], vec![bias as f32]);
+            self.node("Add", "", vec![scaled, bias], vec![output.into()], vec![]);
+        }
+    }
+
+    /// Split a `[N, k]` tensor into `k` named `[N, 1]` outputs.
+    fn split_columns(&mut self, matrix: String, names: &[String]) -> Result<()> {
+        let outputs = names
+            .iter()
+            .map(|n| self.output(n))
+            .collect::<Result<Vec<_>>>()?;
+        if outputs.len() == 1 {
+            self.node("Identity", "", vec![matrix], outputs, vec![]);
+            return Ok(());
+        }
+        let sizes = self.temp("split");
+        self.graph.initializer.push(TensorProto {
+            dims: vec![outputs.len() as i64],
+            data_type: INT64,
+            int64_data: vec![1; outputs.len()],
+            name: sizes.clone(),
+            ..Default::default()
+        });
+        self.node(
+            "Split",
+            "",
+            vec![matrix, sizes],
+            outputs,
+            vec![int_attr("axis", 1)],
+        );
+        Ok(())
+    }
+}
+
+/// Build the ONNX model for `config` using the fitted `state`.
+pub fn build_model(config: &FeatureConfig, state: &FeatureState) -> Result<ModelProto> {
+    let mut b = GraphBuilder::default();
+
+    for (spec, entry) in state.fitted_specs(config)? {
+        let prefix = spec.alias.as_deref().unwrap_or(&spec.column);
+
+        match entry {
+            FeatureStateEntry::MinMax { stats, .. } => {
+                let input = b.input(&spec.column, FLOAT);
+                let output = b.output(prefix)?;
+                let range = stats.max - stats.min;
+                if range.abs() < f64::EPSILON {
+                    b.scaler(input, &output, 0.0, 0.0, 0.5);
+                } else {
+                    b.scaler(input, &output, stats.min, 1.0 / range, 0.0);
+                }
+            }
+            FeatureStateEntry::Standard { stats, .. } => {
+                let input = b.input(&spec.column, FLOAT);
+                let output = b.output(prefix)?;
+                let scale = if stats.std.abs() < f64::EPSILON {
+                    0.0
+                } else {
+                    1.0 / stats.std
+                };
+                b.scaler(input, &output, stats.mean, scale, 0.0);
+            }
+            FeatureStateEntry::OneHot { vocab, .. } => {
+                let input = b.input(&spec.column, STRING);
+                let encoded = b.temp(prefix);
+                b.node(
+                    "OneHotEncoder",
+                    ML_DOMAIN,
+                    vec![input],
+                    vec![encoded.clone()],
+                    vec![
+                        strings_attr("cats_strings", vocab.categories.iter().cloned()),
+                        int_attr("zeros", 1),
+                    ],
+                );
+                // [N, 1, C] -> [N, C]
+                let flat = b.temp(prefix);
+                b.node(
+                    "Flatten",
+                    "",
+                    vec![encoded],
+                    vec![flat.clone()],
+                    vec![int_attr("axis", 1)],
+                );
+                let names: Vec<String> = vocab
+                    .categories
+                    .iter()
+                    .map(|c| format!("{}_{}", prefix, c))
+                    .collect();
+                b.split_columns(flat, &names)?;
+            }
+            FeatureStateEntry::Count { stats, .. } => {
+                let input = b.input(&spec.column, STRING);
+                let output = b.output(prefix)?;
+                let mut pairs: Vec<(&String, &u64)> = stats.counts.iter().collect();
+                pairs.sort();
+                let freq = |count: u64| {
+                    if stats.total == 0 {
+                        0.0
+                    } else {
+                        count as f32 / stats.total as f32
+                    }
+                };
+                b.node(
+                    "LabelEncoder",
+                    ML_DOMAIN,
+                    vec![input],
+                    vec![output],
+                    vec![
+                        strings_attr("keys_strings", pairs.iter().map(|(k, _)| (*k).clone())),
+                        floats_attr(
+                            "values_floats",
+                            pairs.iter().map(|(_, c)| freq(**c)).collect(),
+                        ),
+                        float_attr("default_float", 0.0),
+                    ],
+                );
+            }
+            FeatureStateEntry::Interactions { terms, .. } => {
+                for term in &terms.terms {
+                    let left = b.input(&term.left, FLOAT);
+                    let right = b.input(&term.right, FLOAT);
+                    let output = b.output(&term.name)?;
+                    b.node("Mul", "", vec![left, right], vec![output], vec![]);
+                }
+            }
+            FeatureStateEntry::Pca { stats, .. } => {
+                let inputs: Vec<String> = stats.inputs.iter().map(|c| b.input(c, FLOAT)).collect();
+                let n_inputs = inputs.len() as i64;
+                let stacked = b.temp(prefix);
+                b.node(
+                    "Concat",
+                    "",
+                    inputs,
+                    vec![stacked.clone()],
+                    vec![int_attr("axis", 1)],
+                );
+                let means = b.initializer(
+                    "means",
+                    vec![n_inputs],
+                    stats.means.iter().map(|m| *m as f32).collect(),
+                );
+                let centered = b.temp(prefix);
+                b.node(
+                    "Sub",
+                    "",
+                    vec![stacked, means],
+                    vec![centered.clone()],
+                    vec![],
+                );
+                // [inputs, components], row-major
+                let n_components = stats.components.len();
+                let weights: Vec<f32> = (0..stats.inputs.len())
+                    .flat_map(|j| stats.components.iter().map(move |c| c[j] as f32))
+                    .collect();
+                let weights =
+                    b.initializer("components", vec![n_inputs, n_components as i64], weights);
+                let projected = b.temp(prefix);
+                b.node(
+                    "MatMul",
+                    "",
+                    vec![centered, weights],
+                    vec![projected.clone()],
+                    vec![],
+                );
+                let names: Vec<String> = (1..=n_components)
+                    .map(|i| format!("{}_pc{}", prefix, i))
+                    .collect();
+                b.split_columns(projected, &names)?;
+            }
+            FeatureStateEntry::Cluster { .. } => {
+                return Err(anyhow!(
+                    "{:?} ('{}') cannot be exported to ONNX",
+                    FeatureTransform::ClusterEncode,
+                    spec.column
+                ));
+            }
+        }
+    }
+
+    b.graph.name = "mlprep_features".to_string();
+    Ok(ModelProto {
+        ir_version: IR_VERSION,
+        producer_name: "mlprep".to_string(),
+        producer_version: env!("CARGO_PKG_VERSION").to_string(),
+        graph: Some(b.graph),
+        opset_import: vec![
+            OperatorSetIdProto {
+                domain: String::new(),
+                version: ONNX_OPSET,
+            },
+            OperatorSetIdProto {
+                domain: ML_DOMAIN.to_string(),
+                version: ONNX_ML_OPSET,
+            },
+        ],
+    })
+}
+
+/// Serialize the ONNX model for `config` / `state` to protobuf bytes.
+pub fn export_onnx(config: &FeatureConfig, state: &FeatureState) -> Result<Vec<u8>> {
+    Ok(build_model(config, state)?.encode_to_vec())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::features::{fit_features, FeatureSpec};
+    use polars::prelude::*;
+
+    #[test]
+    fn test_export_onnx_graph() {
+        let df = df! {
+            "price" => &[10.0, 20.0, 30.0],
+            "city" => &["tokyo", "osaka", "tokyo"],
+            "x" => &[1.0, 2.0, 4.0],
+            "y" => &[2.0, 3.0, 1.0],
+        }
+        .unwrap();
+        let config = FeatureConfig {
+            features: vec![
+                FeatureSpec::new("price", FeatureTransform::MinMaxScale),
+                FeatureSpec::new("city", FeatureTransform::OneHotEncode),
+                FeatureSpec::new("city", FeatureTransform::CountEncode),
+                FeatureSpec {
+                    inputs: vec!["x".to_string(), "y".to_string()],
+                    n_components: Some(1),
+                    ..FeatureSpec::new("emb", FeatureTransform::Pca)
+                },
+            ],
+        };
+        let state = fit_features(&df, &config).unwrap();
+
+        let bytes = export_onnx(&config, &state).unwrap();
+        let model = ModelProto::decode(bytes.as_slice()).unwrap();
+        let graph = model.graph.unwrap();
+
+        let inputs: Vec<&str> = graph.input.iter().map(|v| v.name.as_str()).collect();
+        assert_eq!(inputs, vec!["price", "city", "x", "y"]);
+        let outputs: Vec<&str> = graph.output.iter().map(|v| v.name.as_str()).collect();
+        assert_eq!(
+            outputs,
+            vec![
+                "price_out",
+                "city_osaka",
+                "city_tokyo",
+                "city_out",
+                "emb_pc1"
+            ]
+        );
+
+        let ops: Vec<&str> = graph.node.iter().map(|n| n.op_type.as_str()).collect();
+        assert_eq!(
+            ops,
+            vec![
+                "Scaler",
+                "OneHotEncoder",
+                "Flatten",
+                "Split",
+                "LabelEncoder",
+                "Concat",
+                "Sub",
+                "MatMul",
+                "Identity"
+            ]
+        );
+        let scaler = &graph.node[0];
+        assert_eq!(scaler.domain, ML_DOMAIN);
+        assert_eq!(scaler.attribute[0].floats, vec![10.0]);
+        assert_eq!(scaler.attribute[1].floats, vec![0.05]);
+    }
+
+    #[test]
+    fn test_export_onnx_rejects_cluster_encode() {
+        let df = df! { "x" => &[0.0, 1.0, 10.0], "y" => &[0.0, 1.0, 10.0] }.unwrap();
+        let config = FeatureConfig {
+            features: vec![FeatureSpec {
+                inputs: vec!["x".to_string(), "y".to_string()],
+                k: Some(2),
+                ..FeatureSpec::new("seg", FeatureTransform::ClusterEncode)
+            }],
+        };
+        let state = fit_features(&df, &config).unwrap();
+        assert!(export_onnx(&config, &state).is_err());
+    }
+}
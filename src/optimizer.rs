@@ -0,0 +1,200 @@
+//! Plan-level rewrites applied to a pipeline's steps before they are lowered to Polars.
+//!
+//! Each step naively becomes one Polars call, so a long pipeline of casts and fills
+//! builds a plan hundreds of nodes deep. [`plan_stages`] groups the steps instead:
+//! adjacent Cast / FillNull / Select steps are lowered together into as few
+//! `with_columns` / `select` calls as their column dependencies allow (see
+//! [`Projection`]), and a filter directly after a join is a candidate to run on the
+//! left side first, which the lowering does when the filter only reads left columns.
+
+use crate::dsl::{Filter, Join, Step};
+use polars::prelude::*;
+
+/// One or more adjacent steps lowered together.
+#[derive(Debug)]
+pub(crate) enum Stage {
+    Step(Step),
+    /// Adjacent Cast / FillNull / Select steps.
+    Projection(Vec<Step>),
+    /// A join and the filter right after it.
+    JoinFilter(Join, Filter),
+}
+
+impl Stage {
+    /// Kinds of the original steps in this stage, in pipeline order.
+    pub(crate) fn kinds(&self) -> Vec<&'static str> {
+        match self {
+            Stage::Step(step) => vec![step.kind()],
+            Stage::Projection(steps) => steps.iter().map(Step::kind).collect(),
+            Stage::JoinFilter(..) => vec!["join", "filter"],
+        }
+    }
+
+    /// The original steps in this stage, in pipeline order.
+    pub(crate) fn steps(&self) -> Vec<Step> {
+        match self {
+            Stage::Step(step) => vec![step.clone()],
+            Stage::Projection(steps) => steps.clone(),
+            Stage::JoinFilter(join, filter) => {
+                vec![Step::Join(join.clone()), Step::Filter(filter.clone())]
+            }
+        }
+    }
+}
+
+/// Group `steps` into stages; lowering the stages in order is equivalent to lowering
+/// each step in order.
+pub(crate) fn plan_stages(steps: Vec<Step>) -> Vec<Stage> {
+    let mut stages: Vec<Stage> = Vec::new();
+    let mut steps = steps.into_iter().peekable();
+    while let Some(step) = steps.next() {
+        match step {
+            Step::Cast(_) | Step::FillNull(_) | Step::Select(_) => match stages.last_mut() {
+                Some(Stage::Projection(run)) => run.push(step),
+                _ => stages.push(Stage::Projection(vec![step])),
+            },
+            Step::Join(join) => match steps.next_if(|next| matches!(next, Step::Filter(_))) {
+                Some(Step::Filter(filter)) => stages.push(Stage::JoinFilter(join, filter)),
+                _ => stages.push(Stage::Step(Step::Join(join))),
+            },
+            step => stages.push(Stage::Step(step)),
+        }
+    }
+    stages
+}
+
+/// Columns a fusable step reads. Cast and FillNull only rewrite columns they read.
+pub(crate) fn fused_reads(step: &Step) -> Vec<&str> {
+    match step {
+        Step::Cast(cast) => cast.columns.keys().map(String::as_str).collect(),
+        Step::FillNull(fill) => fill
+            .columns
+            .iter()
+            .chain(&fill.over)
+            .chain(&fill.order_by)
+            .map(String::as_str)
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Column expressions pending for a single `with_columns` call, or a single `select`
+/// once a Select step has been folded in.
+///
+/// Every pending expression reads the frame as it was before the call, so an
+/// expression may only join if it reads no column an earlier one rewrote (or, after a
+/// select, dropped); otherwise the call is flushed first.
+#[derive(Debug, Default)]
+pub(crate) struct Projection {
+    /// Rewritten columns; after a select, every output column in order, with `None`
+    /// for those passed through unchanged.
+    columns: Vec<(String, Option<Expr>)>,
+    select: bool,
+}
+
+impl Projection {
+    /// Whether an expression reading `reads` can be evaluated in this call.
+    pub(crate) fn accepts(&self, reads: &[&str]) -> bool {
+        reads.iter().all(
+            |read| match self.columns.iter().find(|(name, _)| name == read) {
+                Some((_, expr)) => expr.is_none(),
+                None => !self.select,
+            },
+        )
+    }
+
+    /// Add an expression producing column `name`.
+    pub(crate) fn push(&mut self, name: String, expr: Expr) {
+        match self.columns.iter_mut().find(|(column, _)| *column == name) {
+            Some(entry) => entry.1 = Some(expr),
+            None => self.columns.push((name, Some(expr))),
+        }
+    }
+
+    /// Fold a Select of `names` into this call. Returns false, leaving the projection
+    /// unchanged, if a selected column was already dropped by an earlier select.
+    pub(crate) fn select(&mut self, names: &[String]) -> bool {
+        let mut columns = Vec::with_capacity(names.len());
+        for name in names {
+            match self.columns.iter().find(|(column, _)| column == name) {
+                Some((_, expr)) => columns.push((name.clone(), expr.clone())),
+                None if !self.select => columns.push((name.clone(), None)),
+                None => return false,
+            }
+        }
+        self.columns = columns;
+        self.select = true;
+        true
+    }
+
+    /// Apply the pending call to `lf` and start a new one.
+    pub(crate) fn flush(&mut self, lf: LazyFrame) -> LazyFrame {
+        let Projection { columns, select } = std::mem::take(self);
+        let exprs: Vec<Expr> = columns
+            .into_iter()
+            .map(|(name, expr)| match expr {
+                Some(expr) => expr.alias(name.as_str()),
+                None => col(name.as_str()),
+            })
+            .collect();
+        if select {
+            lf.select(exprs)
+        } else if exprs.is_empty() {
+            lf
+        } else {
+            lf.with_columns(exprs)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dsl::Pipeline;
+
+    #[test]
+    fn test_plan_stages() {
+        let pipeline = Pipeline::from_reader(
+            r#"
+steps:
+  - type: cast
+    columns: {a: Float64}
+  - type: fill_null
+    columns: [a]
+    strategy: zero
+  - type: select
+    columns: [a, id]
+  - type: join
+    right_path: right.csv
+    left_on: [id]
+    right_on: [id]
+  - type: filter
+    condition: "a > 0"
+  - type: filter
+    condition: "a < 10"
+  - type: join
+    right_path: right.csv
+    left_on: [id]
+    right_on: [id]
+  - type: cast
+    columns: {a: Int64}
+"#
+            .as_bytes(),
+        )
+        .unwrap();
+        let kinds: Vec<Vec<&str>> = plan_stages(pipeline.steps)
+            .iter()
+            .map(Stage::kinds)
+            .collect();
+        assert_eq!(
+            kinds,
+            vec![
+                vec!["cast", "fill_null", "select"],
+                vec!["join", "filter"],
+                vec!["filter"],
+                vec!["join"],
+                vec!["cast"],
+            ]
+        );
+    }
+}
@@ -0,0 +1,290 @@
+//! `mlprep repl`: build a pipeline interactively. Each line typed is a step in YAML flow
+//! form (`type: filter, condition: "price > 0"`), run against the input as soon as it is
+//! entered so its effect is previewed; lines starting with `:` are session commands.
+//! The assembled pipeline is dumped as YAML at the end.
+
+use crate::compute::apply_pipeline;
+use crate::dsl::{Input, Pipeline, RuntimeConfig, Step, DSL_VERSION};
+use crate::errors::{MlPrepError, MlPrepResult};
+use crate::security::SecurityContext;
+use polars::prelude::*;
+use std::io::{BufRead, Write};
+use std::path::Path;
+
+/// Rows previewed after each step unless `:head` changes it.
+pub const DEFAULT_PREVIEW_ROWS: usize = 10;
+
+const HELP: &str = "\
+Enter a step in YAML flow form, e.g.
+  type: filter, condition: \"price > 0\"
+  {type: select, columns: [city, price]}
+Commands:
+  :undo          remove the last step
+  :head N        preview N rows after each step
+  :schema        columns and dtypes after the last step
+  :yaml          print the pipeline so far
+  :save FILE     write the pipeline to FILE
+  :quit          end the session (also Ctrl-D)
+";
+
+/// What the session does after a line.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Flow {
+    Continue,
+    Quit,
+}
+
+/// An interactive session over one input.
+pub struct Repl {
+    input: Input,
+    source: LazyFrame,
+    steps: Vec<Step>,
+    rows: usize,
+    security_context: SecurityContext,
+}
+
+impl Repl {
+    pub fn new(input: Input, security_context: SecurityContext) -> MlPrepResult<Self> {
+        security_context.validate_path(&input.path)?;
+        let source = crate::manifest::scan_input(&input, &security_context, &Default::default())?;
+        Ok(Self {
+            input,
+            source,
+            steps: Vec::new(),
+            rows: DEFAULT_PREVIEW_ROWS,
+            security_context,
+        })
+    }
+
+    pub fn with_preview_rows(mut self, rows: usize) -> Self {
+        self.rows = rows;
+        self
+    }
+
+    /// The pipeline assembled so far: the input and every accepted step.
+    pub fn pipeline(&self) -> Pipeline {
+        Pipeline {
+            version: Some(DSL_VERSION),
+            inputs: vec![self.input.clone()],
+            steps: self.steps.clone(),
+            ..Default::default()
+        }
+    }
+
+    /// The result of the steps so far, with masked columns hidden.
+    fn result(&self) -> MlPrepResult<LazyFrame> {
+        let pipeline = Pipeline {
+            steps: self.steps.clone(),
+            ..Default::default()
+        };
+        let lf = apply_pipeline(
+            self.source.clone(),
+            pipeline,
+            &RuntimeConfig::default(),
+            &self.security_context,
+        )?;
+        self.security_context.masker().mask(lf)
+    }
+
+    fn preview<W: Write>(&self, out: &mut W) -> MlPrepResult<()> {
+        let df = self
+            .result()?
+            .limit(self.rows as IdxSize)
+            .collect()
+            .map_err(MlPrepError::PolarsError)?;
+        writeln!(out, "{}", df)?;
+        Ok(())
+    }
+
+    /// Parse `line` as a step, run it and keep it if it succeeds.
+    fn add_step<W: Write>(&mut self, line: &str, out: &mut W) -> MlPrepResult<()> {
+        let yaml = if line.starts_with('{') {
+            line.to_string()
+        } else {
+            format!("{{{}}}", line)
+        };
+        let step: Step =
+            serde_yaml::from_str(&yaml).map_err(|e| MlPrepError::ConfigError(e, None))?;
+        self.steps.push(step);
+        if let Err(e) = self.preview(out) {
+            self.steps.pop();
+            return Err(e);
+        }
+        let added = Pipeline {
+            steps: self.steps[self.steps.len() - 1..].to_vec(),
+            ..Default::default()
+        }
+        .to_yaml()?;
+        write!(
+            out,
+            "step {}:\n{}",
+            self.steps.len(),
+            added.trim_start_matches("steps:\n")
+        )?;
+        Ok(())
+    }
+
+    /// Handle one line of input.
+    pub fn handle<W: Write>(&mut self, line: &str, out: &mut W) -> MlPrepResult<Flow> {
+        let line = line.trim();
+        let Some(command) = line.strip_prefix(':') else {
+            if !line.is_empty() {
+                self.add_step(line, out)?;
+            }
+            return Ok(Flow::Continue);
+        };
+        let (command, arg) = command
+            .split_once(char::is_whitespace)
+            .map(|(c, a)| (c, a.trim()))
+            .unwrap_or((command, ""));
+        match command {
+            "undo" => match self.steps.pop() {
+                Some(_) => self.preview(out)?,
+                None => writeln!(out, "no steps to undo")?,
+            },
+            "head" => {
+                self.rows = arg.parse().map_err(|_| {
+                    MlPrepError::TransformError(format!(":head needs a row count, got '{}'", arg))
+                })?;
+                self.preview(out)?;
+            }
+            "schema" => {
+                let schema = self
+                    .result()?
+                    .collect_schema()
+                    .map_err(MlPrepError::PolarsError)?;
+                for (name, dtype) in schema.iter() {
+                    writeln!(out, "{}: {}", name, dtype)?;
+                }
+            }
+            "yaml" => write!(out, "{}", self.pipeline().to_yaml()?)?,
+            "save" => {
+                if arg.is_empty() {
+                    return Err(MlPrepError::TransformError(
+                        ":save needs a file name".to_string(),
+                    ));
+                }
+                let path = Path::new(arg);
+                self.security_context.validate_path(path)?;
+                std::fs::write(path, self.pipeline().to_yaml()?)?;
+                writeln!(
+                    out,
+                    "saved {} steps to {}",
+                    self.steps.len(),
+                    path.display()
+                )?;
+            }
+            "help" | "h" | "?" => write!(out, "{}", HELP)?,
+            "quit" | "q" | "exit" => return Ok(Flow::Quit),
+            other => {
+                return Err(MlPrepError::TransformError(format!(
+                    "unknown command ':{}' (try :help)",
+                    other
+                )))
+            }
+        }
+        Ok(Flow::Continue)
+    }
+
+    /// Read lines from `input` until `:quit` or end of input. A line that fails is
+    /// reported and leaves the pipeline unchanged.
+    pub fn session<R: BufRead, W: Write>(&mut self, mut input: R, mut out: W) -> MlPrepResult<()> {
+        writeln!(
+            out,
+            "mlprep repl on {} (:help for commands)",
+            self.input.path.display()
+        )?;
+        self.preview(&mut out)?;
+        let mut line = String::new();
+        loop {
+            write!(out, "[{}]> ", self.steps.len())?;
+            out.flush()?;
+            line.clear();
+            if input.read_line(&mut line)? == 0 {
+                writeln!(out)?;
+                break;
+            }
+            match self.handle(&line, &mut out) {
+                Ok(Flow::Continue) => {}
+                Ok(Flow::Quit) => break,
+                Err(e) => writeln!(out, "error: {}", e)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_repl_session() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("orders.csv");
+        std::fs::write(
+            &path,
+            "city,price,email\nosaka,3,a@x\ntokyo,-1,b@x\nkyoto,5,c@x\n",
+        )
+        .unwrap();
+        let input: Input =
+            serde_yaml::from_str(&format!("path: \"{}\"\n", path.display())).unwrap();
+        let security_context = SecurityContext::new(crate::security::SecurityConfig {
+            allowed_paths: None,
+            mask_columns: Some(vec!["email".to_string()]),
+        })
+        .unwrap();
+        let mut repl = Repl::new(input, security_context)
+            .unwrap()
+            .with_preview_rows(5);
+
+        let saved = dir.path().join("pipeline.yaml");
+        let lines = format!(
+            "type: filter, condition: \"price > 0\"\n\
+             type: select, columns: [city, missing]\n\
+             {{type: sort, by: [price], descending: [true]}}\n\
+             :undo\n\
+             type: sort, by: [city]\n\
+             :schema\n\
+             :bogus\n\
+             :save {}\n\
+             :quit\n\
+             type: limit, n: 1\n",
+            saved.display()
+        );
+        let mut out = Vec::new();
+        repl.session(lines.as_bytes(), &mut out).unwrap();
+        let out = String::from_utf8(out).unwrap();
+
+        assert!(
+            out.contains("step 1:\n- type: filter\n  condition: price > 0\n"),
+            "{}",
+            out
+        );
+        assert!(out.contains("error: "), "{}", out);
+        assert!(out.contains("step 2:\n- type: sort\n"), "{}", out);
+        assert!(
+            out.contains("city: str\nprice: i64\nemail: str\n"),
+            "{}",
+            out
+        );
+        assert!(
+            out.contains("error: Transformation error: unknown command ':bogus'"),
+            "{}",
+            out
+        );
+        assert!(out.contains("│ osaka ┆ 3     ┆ ***   │"), "{}", out);
+        assert!(!out.contains("a@x"), "{}", out);
+
+        let pipeline = repl.pipeline();
+        assert_eq!(pipeline.version, Some(DSL_VERSION));
+        assert_eq!(pipeline.steps.len(), 2);
+        assert_eq!(Pipeline::from_path(&saved).unwrap().steps, pipeline.steps);
+        let df = repl.result().unwrap().collect().unwrap();
+        assert_eq!(
+            df.column("city").unwrap().str().unwrap().get(0),
+            Some("kyoto")
+        );
+    }
+}
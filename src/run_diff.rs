@@ -0,0 +1,497 @@
+//! Compare two runs from their lineage files (`mlprep diff-runs`): which inputs
+//! changed, which step parameters changed, how row counts moved and how the output
+//! schema and files differ.
+
+use crate::errors::{MlPrepError, MlPrepResult};
+use crate::memory::format_bytes;
+use crate::observability::{FileStats, Lineage, SchemaField};
+use serde::Serialize;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// A file present in one run, the other, or both with different content.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "change", rename_all = "snake_case")]
+pub enum FileChange {
+    Added {
+        path: String,
+        hash: String,
+    },
+    Removed {
+        path: String,
+        hash: String,
+    },
+    Changed {
+        path: String,
+        hash_a: String,
+        hash_b: String,
+        size_a: u64,
+        size_b: u64,
+    },
+}
+
+/// A step parameter with different values in the two runs (`null` when unset).
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ParamChange {
+    pub param: String,
+    pub a: serde_json::Value,
+    pub b: serde_json::Value,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "change", rename_all = "snake_case")]
+pub enum StepChange {
+    Added {
+        index: usize,
+        kind: String,
+    },
+    Removed {
+        index: usize,
+        kind: String,
+    },
+    /// A different kind of step at the same position
+    Replaced {
+        index: usize,
+        kind_a: String,
+        kind_b: String,
+    },
+    Changed {
+        index: usize,
+        kind: String,
+        params: Vec<ParamChange>,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CountChange {
+    pub metric: String,
+    pub a: usize,
+    pub b: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "change", rename_all = "snake_case")]
+pub enum SchemaChange {
+    Added {
+        column: String,
+        dtype: String,
+    },
+    Removed {
+        column: String,
+        dtype: String,
+    },
+    Dtype {
+        column: String,
+        dtype_a: String,
+        dtype_b: String,
+    },
+}
+
+/// Differences between run A and run B; empty sections mean no change.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RunDiff {
+    pub run_a: String,
+    pub run_b: String,
+    pub inputs: Vec<FileChange>,
+    pub steps: Vec<StepChange>,
+    pub counts: Vec<CountChange>,
+    pub schema: Vec<SchemaChange>,
+    pub outputs: Vec<FileChange>,
+    /// Seeds of A and B, when they differ
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub seed: Option<(Option<u64>, Option<u64>)>,
+}
+
+impl RunDiff {
+    pub fn is_empty(&self) -> bool {
+        self.inputs.is_empty()
+            && self.steps.is_empty()
+            && self.counts.is_empty()
+            && self.schema.is_empty()
+            && self.outputs.is_empty()
+            && self.seed.is_none()
+    }
+}
+
+/// Load the lineage file at `path`, or the only `lineage_*.json` in directory `path`.
+pub fn load_run(path: &Path) -> MlPrepResult<Lineage> {
+    if !path.is_dir() {
+        return Ok(Lineage::load(path)?);
+    }
+    let mut found: Vec<PathBuf> = std::fs::read_dir(path)?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|p| {
+            p.file_name()
+                .and_then(|n| n.to_str())
+                .is_some_and(|n| n.starts_with("lineage_") && n.ends_with(".json"))
+        })
+        .collect();
+    match found.len() {
+        1 => Ok(Lineage::load(found.remove(0))?),
+        n => Err(MlPrepError::IoError(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!(
+                "{} contains {} lineage files; pass the lineage_<run_id>.json of the run to compare",
+                path.display(),
+                n
+            ),
+        ))),
+    }
+}
+
+pub fn diff_runs(a: &Lineage, b: &Lineage) -> RunDiff {
+    let counts = [
+        ("rows_read", a.rows_read, b.rows_read),
+        ("rows_written", a.rows_written, b.rows_written),
+        ("violations", a.violations, b.violations),
+    ]
+    .into_iter()
+    .filter(|(_, a, b)| a != b)
+    .map(|(metric, a, b)| CountChange {
+        metric: metric.to_string(),
+        a,
+        b,
+    })
+    .collect();
+
+    RunDiff {
+        run_a: a.run_id.clone(),
+        run_b: b.run_id.clone(),
+        inputs: diff_files(&a.inputs, &b.inputs),
+        steps: diff_steps(&a.steps, &b.steps),
+        counts,
+        schema: diff_schema(&a.schema, &b.schema),
+        outputs: diff_files(&a.output_files, &b.output_files),
+        seed: (a.seed != b.seed).then_some((a.seed, b.seed)),
+    }
+}
+
+/// Files are matched by path.
+fn diff_files(a: &[FileStats], b: &[FileStats]) -> Vec<FileChange> {
+    let mut changes = Vec::new();
+    for file in a {
+        match b.iter().find(|other| other.path == file.path) {
+            None => changes.push(FileChange::Removed {
+                path: file.path.clone(),
+                hash: file.hash.clone(),
+            }),
+            Some(other) if other.hash != file.hash => changes.push(FileChange::Changed {
+                path: file.path.clone(),
+                hash_a: file.hash.clone(),
+                hash_b: other.hash.clone(),
+                size_a: file.size_bytes,
+                size_b: other.size_bytes,
+            }),
+            Some(_) => {}
+        }
+    }
+    for file in b {
+        if !a.iter().any(|other| other.path == file.path) {
+            changes.push(FileChange::Added {
+                path: file.path.clone(),
+                hash: file.hash.clone(),
+            });
+        }
+    }
+    changes
+}
+
+/// Steps are matched by position; parameters are compared on their YAML keys.
+fn diff_steps(a: &[crate::dsl::Step], b: &[crate::dsl::Step]) -> Vec<StepChange> {
+    let params = |step: &crate::dsl::Step| match serde_json::to_value(step) {
+        Ok(serde_json::Value::Object(mut map)) => {
+            map.remove("type");
+            map
+        }
+        _ => serde_json::Map::new(),
+    };
+
+    let mut changes = Vec::new();
+    for index in 0..a.len().max(b.len()) {
+        match (a.get(index), b.get(index)) {
+            (Some(step), None) => changes.push(StepChange::Removed {
+                index,
+                kind: step.kind().to_string(),
+            }),
+            (None, Some(step)) => changes.push(StepChange::Added {
+                index,
+                kind: step.kind().to_string(),
+            }),
+            (Some(step_a), Some(step_b)) if step_a.kind() != step_b.kind() => {
+                changes.push(StepChange::Replaced {
+                    index,
+                    kind_a: step_a.kind().to_string(),
+                    kind_b: step_b.kind().to_string(),
+                })
+            }
+            (Some(step_a), Some(step_b)) => {
+                let (params_a, params_b) = (params(step_a), params(step_b));
+                let mut keys: Vec<&String> = params_a.keys().chain(params_b.keys()).collect();
+                keys.sort();
+                keys.dedup();
+                let changed: Vec<ParamChange> = keys
+                    .into_iter()
+                    .filter_map(|key| {
+                        let value_a = params_a.get(key).cloned().unwrap_or_default();
+                        let value_b = params_b.get(key).cloned().unwrap_or_default();
+                        (value_a != value_b).then(|| ParamChange {
+                            param: key.clone(),
+                            a: value_a,
+                            b: value_b,
+                        })
+                    })
+                    .collect();
+                if !changed.is_empty() {
+                    changes.push(StepChange::Changed {
+                        index,
+                        kind: step_a.kind().to_string(),
+                        params: changed,
+                    });
+                }
+            }
+            (None, None) => {}
+        }
+    }
+    changes
+}
+
+fn diff_schema(a: &[SchemaField], b: &[SchemaField]) -> Vec<SchemaChange> {
+    let mut changes = Vec::new();
+    for field in a {
+        match b.iter().find(|other| other.name == field.name) {
+            None => changes.push(SchemaChange::Removed {
+                column: field.name.clone(),
+                dtype: field.dtype.clone(),
+            }),
+            Some(other) if other.dtype != field.dtype => changes.push(SchemaChange::Dtype {
+                column: field.name.clone(),
+                dtype_a: field.dtype.clone(),
+                dtype_b: other.dtype.clone(),
+            }),
+            Some(_) => {}
+        }
+    }
+    for field in b {
+        if !a.iter().any(|other| other.name == field.name) {
+            changes.push(SchemaChange::Added {
+                column: field.name.clone(),
+                dtype: field.dtype.clone(),
+            });
+        }
+    }
+    changes
+}
+
+fn short(hash: &str) -> &str {
+    &hash[..hash.len().min(12)]
+}
+
+impl fmt::Display for FileChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FileChange::Added { path, hash } => write!(f, "+ {} ({})", path, short(hash)),
+            FileChange::Removed { path, hash } => write!(f, "- {} ({})", path, short(hash)),
+            FileChange::Changed {
+                path,
+                hash_a,
+                hash_b,
+                size_a,
+                size_b,
+            } => write!(
+                f,
+                "~ {}: {} -> {}, {} -> {}",
+                path,
+                short(hash_a),
+                short(hash_b),
+                format_bytes(*size_a),
+                format_bytes(*size_b)
+            ),
+        }
+    }
+}
+
+impl fmt::Display for StepChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StepChange::Added { index, kind } => write!(f, "+ step {} ({})", index, kind),
+            StepChange::Removed { index, kind } => write!(f, "- step {} ({})", index, kind),
+            StepChange::Replaced {
+                index,
+                kind_a,
+                kind_b,
+            } => write!(f, "~ step {}: {} -> {}", index, kind_a, kind_b),
+            StepChange::Changed {
+                index,
+                kind,
+                params,
+            } => {
+                write!(f, "~ step {} ({})", index, kind)?;
+                for param in params {
+                    write!(f, "\n      {}: {} -> {}", param.param, param.a, param.b)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl fmt::Display for CountChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let delta = self.b as i64 - self.a as i64;
+        write!(f, "{}: {} -> {} ({:+})", self.metric, self.a, self.b, delta)?;
+        if self.a > 0 {
+            write!(f, " {:+.1}%", delta as f64 * 100.0 / self.a as f64)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for SchemaChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SchemaChange::Added { column, dtype } => write!(f, "+ {}: {}", column, dtype),
+            SchemaChange::Removed { column, dtype } => write!(f, "- {}: {}", column, dtype),
+            SchemaChange::Dtype {
+                column,
+                dtype_a,
+                dtype_b,
+            } => write!(f, "~ {}: {} -> {}", column, dtype_a, dtype_b),
+        }
+    }
+}
+
+impl fmt::Display for RunDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Run A: {}", self.run_a)?;
+        writeln!(f, "Run B: {}", self.run_b)?;
+        if self.is_empty() {
+            return writeln!(f, "No differences");
+        }
+        fn section<T: fmt::Display>(
+            f: &mut fmt::Formatter<'_>,
+            title: &str,
+            items: &[T],
+        ) -> fmt::Result {
+            if !items.is_empty() {
+                writeln!(f, "{}:", title)?;
+                for item in items {
+                    writeln!(f, "  {}", item)?;
+                }
+            }
+            Ok(())
+        }
+        section(f, "Inputs", &self.inputs)?;
+        section(f, "Steps", &self.steps)?;
+        section(f, "Rows", &self.counts)?;
+        section(f, "Schema", &self.schema)?;
+        section(f, "Outputs", &self.outputs)?;
+        if let Some((a, b)) = self.seed {
+            let seed = |s: Option<u64>| s.map_or_else(|| "none".to_string(), |s| s.to_string());
+            writeln!(f, "Seed: {} -> {}", seed(a), seed(b))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dsl::Pipeline;
+
+    fn lineage(run_id: &str, input_hash: &str, steps: &str, rows: usize) -> Lineage {
+        Lineage {
+            run_id: run_id.to_string(),
+            timestamp: chrono::Utc::now(),
+            attempts: 1,
+            inputs: vec![FileStats {
+                path: "data.csv".to_string(),
+                hash: input_hash.to_string(),
+                size_bytes: 100,
+            }],
+            outputs: vec!["out.csv".to_string()],
+            output_files: Vec::new(),
+            rows_read: 10,
+            rows_written: rows,
+            violations: 0,
+            quality_score: None,
+            steps: Pipeline::from_reader(steps.as_bytes()).unwrap().steps,
+            schema: vec![SchemaField {
+                name: "a".to_string(),
+                dtype: "Int64".to_string(),
+            }],
+            seed: None,
+            columns: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_diff_runs() {
+        let steps = "steps:\n  - type: filter\n    condition: \"a > 1\"\n  - type: select\n    columns: [a]\n";
+        let a = lineage("run-a", "aaa", steps, 8);
+        assert!(diff_runs(&a, &lineage("run-a2", "aaa", steps, 8)).is_empty());
+
+        let mut b = lineage(
+            "run-b",
+            "bbb",
+            "steps:\n  - type: filter\n    condition: \"a > 2\"\n  - type: sort\n    by: [a]\n  - type: limit\n    n: 5\n",
+            5,
+        );
+        b.schema[0].dtype = "Float64".to_string();
+        let diff = diff_runs(&a, &b);
+
+        assert_eq!(
+            diff.inputs,
+            vec![FileChange::Changed {
+                path: "data.csv".to_string(),
+                hash_a: "aaa".to_string(),
+                hash_b: "bbb".to_string(),
+                size_a: 100,
+                size_b: 100,
+            }]
+        );
+        assert_eq!(
+            diff.steps,
+            vec![
+                StepChange::Changed {
+                    index: 0,
+                    kind: "filter".to_string(),
+                    params: vec![ParamChange {
+                        param: "condition".to_string(),
+                        a: "a > 1".into(),
+                        b: "a > 2".into(),
+                    }],
+                },
+                StepChange::Replaced {
+                    index: 1,
+                    kind_a: "select".to_string(),
+                    kind_b: "sort".to_string(),
+                },
+                StepChange::Added {
+                    index: 2,
+                    kind: "limit".to_string(),
+                },
+            ]
+        );
+        assert_eq!(diff.counts.len(), 1);
+        assert_eq!(
+            diff.counts[0].to_string(),
+            "rows_written: 8 -> 5 (-3) -37.5%"
+        );
+        assert_eq!(diff.schema[0].to_string(), "~ a: Int64 -> Float64");
+
+        let text = diff.to_string();
+        assert!(
+            text.contains("Steps:\n  ~ step 0 (filter)\n      condition: \"a > 1\" -> \"a > 2\"")
+        );
+    }
+
+    #[test]
+    fn test_load_lineage_without_attempts() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("lineage.json");
+        let mut json = serde_json::to_value(lineage("run-a", "aaa", "steps: []", 1)).unwrap();
+        json.as_object_mut().unwrap().remove("attempts");
+        std::fs::write(&path, json.to_string()).unwrap();
+        assert_eq!(Lineage::load(&path).unwrap().attempts, 1);
+    }
+}
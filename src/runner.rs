@@ -1,42 +1,216 @@
-use crate::dsl::Pipeline;
+use crate::chunked;
+use crate::contract;
+use crate::control::{self, Progress};
+use crate::dsl::{FailureHook, Manifest, Output, OutputMode, Pipeline, Step};
 use crate::engine::DataPipeline;
 use crate::errors::{MlPrepError, MlPrepResult};
+use crate::iceberg;
+use crate::input_cache;
 use crate::io;
-use crate::observability::{self, InputFileStats, Lineage, Metrics};
+use crate::kafka;
+use crate::lineage::{self, ColumnLineage};
+use crate::manifest::{self, FileDone, ManifestEntry, ManifestProgress};
+use crate::memory;
+use crate::observability::{
+    EffectiveRuntime, FileStats, Lineage, Metrics, RunSummary, SchemaField,
+};
 use chrono::Utc;
 use indicatif::{ProgressBar, ProgressStyle};
-use polars::prelude::*;
+use polars::prelude::{DataFrame, IdxSize, IntoLazy, LazyFrame};
 use serde::de::Error;
+use serde::Serialize;
 use std::env;
-use std::path::PathBuf;
+use std::io::IsTerminal;
+use std::path::{Path, PathBuf};
 use std::time::Instant;
-use tracing::{info, warn};
+use tracing::{info, info_span, warn};
 use uuid::Uuid;
 
-fn apply_runtime_env(runtime: &crate::dsl::RuntimeConfig) {
-    if let Some(ref threads) = runtime.threads {
-        if threads.parse::<usize>().is_err() {
+/// Apply the process-wide parts of `runtime` and report the settings in effect.
+///
+/// Every entry point (CLI, Python, [`crate::engine::run_in_memory`]) goes through this.
+/// `threads` sizes the Polars thread pool, which can only happen before the pool first
+/// starts; later runs in the same process keep the existing size and log a warning.
+/// `cache` is applied per plan by [`DataPipeline::with_runtime`].
+pub fn init_runtime(runtime: &crate::dsl::RuntimeConfig) -> MlPrepResult<EffectiveRuntime> {
+    let memory_limit_bytes = runtime.memory_limit_bytes()?;
+    let requested_threads = match runtime.threads.as_deref().map(str::parse::<usize>) {
+        Some(Ok(n)) if n > 0 => Some(n),
+        Some(_) => {
             warn!(
-                "Invalid threads value '{}'; skipping POLARS_MAX_THREADS override",
-                threads
+                "Invalid threads value '{}'; keeping the default thread pool size",
+                runtime.threads.as_deref().unwrap_or_default()
             );
-        } else {
-            let previous = env::var("POLARS_MAX_THREADS").ok();
-            env::set_var("POLARS_MAX_THREADS", threads);
-            match previous {
-                Some(prev) if prev != *threads => {
-                    info!("Overriding POLARS_MAX_THREADS from {} to {}", prev, threads);
-                }
-                None => info!("Setting POLARS_MAX_THREADS={}", threads),
-                _ => {}
+            None
+        }
+        None => None,
+    };
+
+    let pool_started = once_cell::sync::Lazy::get(&polars_core::POOL).is_some();
+    if let Some(n) = requested_threads {
+        if !pool_started {
+            env::set_var("POLARS_MAX_THREADS", n.to_string());
+        }
+    }
+    let threads = polars_core::POOL.current_num_threads();
+    if let Some(n) = requested_threads {
+        if n != threads {
+            warn!(
+                "Thread pool already started with {} threads; `threads: {}` is ignored",
+                threads, n
+            );
+        }
+    }
+
+    let effective = EffectiveRuntime {
+        threads,
+        cache: runtime.cache.unwrap_or(true),
+        streaming: runtime.streaming,
+        memory_limit_bytes,
+        estimated_bytes: None,
+        degraded: false,
+        seed: runtime.seed,
+    };
+    tracing::debug!(
+        threads = effective.threads,
+        cache = effective.cache,
+        streaming = effective.streaming,
+        memory_limit = runtime.memory_limit.as_deref().unwrap_or("none"),
+        "Runtime initialized"
+    );
+    Ok(effective)
+}
+
+fn new_progress_bar(enabled: bool, len: Option<u64>, template: &str) -> MlPrepResult<ProgressBar> {
+    if !enabled {
+        return Ok(ProgressBar::hidden());
+    }
+    let pb = match len {
+        Some(len) => ProgressBar::new(len),
+        None => ProgressBar::new_spinner(),
+    };
+    pb.set_style(
+        ProgressStyle::default_bar()
+            .template(template)
+            .map_err(|e| MlPrepError::Unknown(e.into()))? // Template error is rare/internal
+            .progress_chars("#>-"),
+    );
+    Ok(pb)
+}
+
+/// Whether filters or limits were pushed into a file scan of `lf`, so that fewer rows
+/// than the input holds come out of it and the input row count is no total to count
+/// towards.
+fn scans_filtered(lf: &LazyFrame) -> bool {
+    io::explain_scans(lf.clone()).map_or(true, |scans| {
+        scans
+            .iter()
+            .any(|scan| scan.predicate.is_some() || scan.slice.is_some())
+    })
+}
+
+/// Draw the input rows bar from here on, restarting its count: with a `total`, as a bar
+/// with an ETA, otherwise as a counter.
+fn show_input_bar(pb: &ProgressBar, total: Option<u64>) -> MlPrepResult<()> {
+    let template = match total {
+        Some(_) => "{spinner:.green} [{elapsed_precise}] [{bar:30}] {human_pos}/{human_len} input rows ({per_sec}, ETA {eta}) {msg}",
+        None => "{spinner:.green} [{elapsed_precise}] {human_pos} input rows ({per_sec}) {msg}",
+    };
+    pb.set_style(
+        ProgressStyle::default_bar()
+            .template(template)
+            .map_err(|e| MlPrepError::Unknown(e.into()))?
+            .progress_chars("#>-"),
+    );
+    if let Some(total) = total {
+        pb.set_length(total);
+    }
+    pb.reset();
+    pb.set_draw_target(indicatif::ProgressDrawTarget::stderr());
+    pb.enable_steady_tick(std::time::Duration::from_millis(100));
+    Ok(())
+}
+
+/// Merge the `checks_path` file of each validate step into its inline checks, after
+/// checking the file against the sandbox. Each file is read once: `checks_path` is
+/// cleared as it is loaded.
+pub(crate) fn load_check_files(
+    pipeline: &mut Pipeline,
+    security_context: &crate::security::SecurityContext,
+) -> MlPrepResult<()> {
+    for step in &mut pipeline.steps {
+        if let Step::Validate(validate) = step {
+            if let Some(checks_path) = validate.checks_path.take() {
+                security_context.validate_path(&checks_path)?;
+                let mut checks = crate::dsl::CheckConfig::load(&checks_path).map_err(|e| {
+                    MlPrepError::ConfigError(
+                        serde_yaml::Error::custom(format!(
+                            "Cannot load checks file {}: {}",
+                            checks_path.display(),
+                            e
+                        )),
+                        None,
+                    )
+                })?;
+                checks.extend(std::mem::take(&mut validate.checks));
+                validate.checks = checks;
             }
         }
     }
+    Ok(())
+}
 
-    if let Some(cache) = runtime.cache {
-        env::set_var("POLARS_CACHE", if cache { "1" } else { "0" });
-        info!("Plan cache {}", if cache { "enabled" } else { "disabled" });
+/// Merge the pipeline's `runtime` block with CLI/API overrides (overrides win when set).
+pub(crate) fn resolve_runtime(
+    pipeline: &Pipeline,
+    runtime_override: Option<crate::dsl::RuntimeConfig>,
+) -> crate::dsl::RuntimeConfig {
+    let mut runtime = pipeline.runtime.clone().unwrap_or_default();
+    // Each run pins its own cached inputs, released when its runtime is dropped
+    runtime.input_pins = Default::default();
+    // Parameters from the environment override the pipeline's, and the command line's both.
+    runtime.params.extend(crate::sql_condition::env_params());
+    if let Some(override_conf) = runtime_override {
+        runtime.params.extend(override_conf.params);
+        if override_conf.streaming {
+            runtime.streaming = true;
+        }
+        if override_conf.memory_limit.is_some() {
+            runtime.memory_limit = override_conf.memory_limit;
+        }
+        if override_conf.threads.is_some() {
+            runtime.threads = override_conf.threads;
+        }
+        if override_conf.cache.is_some() {
+            runtime.cache = override_conf.cache;
+        }
+        if override_conf.progress.is_some() {
+            runtime.progress = override_conf.progress;
+        }
+        if override_conf.retries.is_some() {
+            runtime.retries = override_conf.retries;
+        }
+        if override_conf.retry_backoff.is_some() {
+            runtime.retry_backoff = override_conf.retry_backoff;
+        }
+        if override_conf.explain_io.is_some() {
+            runtime.explain_io = override_conf.explain_io;
+        }
+        if override_conf.chunk_size.is_some() {
+            runtime.chunk_size = override_conf.chunk_size;
+        }
+        if override_conf.seed.is_some() {
+            runtime.seed = override_conf.seed;
+        }
+        if override_conf.restart {
+            runtime.restart = true;
+        }
+        if override_conf.no_input_cache {
+            runtime.no_input_cache = true;
+        }
+        runtime.udfs = override_conf.udfs;
     }
+    runtime
 }
 
 pub fn execution_pipeline(
@@ -44,8 +218,612 @@ pub fn execution_pipeline(
     run_id: Uuid,
     security_config: crate::security::SecurityConfig,
     runtime_override: Option<crate::dsl::RuntimeConfig>,
+) -> MlPrepResult<RunSummary> {
+    execute_with_retries(path, run_id, security_config, runtime_override).0
+}
+
+/// [`execution_pipeline`], also returning the number of attempts made, which the summary
+/// of a failed run reports.
+pub fn execute_with_retries(
+    path: &PathBuf,
+    run_id: Uuid,
+    security_config: crate::security::SecurityConfig,
+    runtime_override: Option<crate::dsl::RuntimeConfig>,
+) -> (MlPrepResult<RunSummary>, u32) {
+    let _span = info_span!("pipeline", run_id = %run_id, path = %path.display()).entered();
+
+    // The retry policy is resolved up front; a pipeline that fails to parse gets no retries
+    // from its own runtime block (CLI overrides still apply).
+    let parsed = Pipeline::from_path(path).ok();
+    let policy = match &parsed {
+        Some(pipeline) => resolve_runtime(pipeline, runtime_override.clone()),
+        None => runtime_override.clone().unwrap_or_default(),
+    };
+    let retries = policy.retries.unwrap_or(0);
+    let backoff = match policy.retry_backoff_duration() {
+        Ok(backoff) => backoff,
+        Err(e) => return (Err(e), 1),
+    };
+
+    let mut attempt: u32 = 1;
+    loop {
+        // Kafka batches are committed only once the run has written its outputs
+        let mut batches = Vec::new();
+        let mut written = Written::default();
+        let result = execute_attempt(
+            path,
+            run_id,
+            security_config.clone(),
+            runtime_override.clone(),
+            attempt,
+            &mut batches,
+            &mut written,
+        )
+        .and_then(|summary| {
+            batches.into_iter().try_for_each(kafka::Batch::commit)?;
+            Ok(summary)
+        })
+        .map_err(|e| e.with_pipeline_source(path));
+        // A retry would append the attempt's rows a second time.
+        let retry = |e: &MlPrepError| e.is_transient() && attempt <= retries && !written.appended;
+        if let (Err(e), Some(pipeline)) = (&result, &parsed) {
+            if !retry(e) {
+                if e.is_transient() && attempt <= retries {
+                    warn!("Not retrying: rows were already appended to an output");
+                }
+                run_failure_hooks(path, run_id, pipeline, &security_config, e, &written.files);
+            }
+        }
+        match result {
+            Err(e) if retry(&e) => {
+                let delay = backoff.saturating_mul(1 << (attempt - 1).min(16));
+                warn!(
+                    "Attempt {}/{} failed with a transient error: {}; retrying in {:?}",
+                    attempt,
+                    retries + 1,
+                    e,
+                    delay
+                );
+                std::thread::sleep(delay);
+                attempt += 1;
+            }
+            result => {
+                crate::notify::notify(&policy.notifications, path, run_id, &result);
+                return (result, attempt);
+            }
+        }
+    }
+}
+
+/// Outputs one attempt has written to.
+#[derive(Default)]
+struct Written {
+    /// Files moved into place, for the delete_outputs hook
+    files: Vec<PathBuf>,
+    /// Whether rows were appended to an output; such an attempt is not retried
+    appended: bool,
+}
+
+/// Apply the steps of `pipeline` to an in-memory frame and return the result.
+/// `inputs`, `outputs` and `on_failure` are ignored; nothing is read or written
+/// except files referenced by steps (e.g. a features `state_path` or a `checks_path`).
+pub fn execute_in_memory(
+    df: DataFrame,
+    mut pipeline: Pipeline,
+    security_config: crate::security::SecurityConfig,
+    runtime_override: Option<crate::dsl::RuntimeConfig>,
+) -> MlPrepResult<DataFrame> {
+    let security_context = crate::security::SecurityContext::new(security_config)?;
+    load_check_files(&mut pipeline, &security_context)?;
+    let runtime = resolve_runtime(&pipeline, runtime_override);
+    let effective = init_runtime(&runtime)?;
+    info!("Executing {} steps in memory...", pipeline.steps.len());
+    DataPipeline::new(df.lazy())
+        .apply_transforms(pipeline, &runtime, &security_context)?
+        .with_runtime(&effective)
+        .collect(runtime.streaming)
+}
+
+/// Run `path` on a fixture input: `input` when given, else the pipeline's first input.
+/// Outputs are not written; the result is returned for `mlprep test`.
+pub fn execute_fixture(
+    path: &Path,
+    input: Option<&Path>,
+    security_config: crate::security::SecurityConfig,
+) -> MlPrepResult<DataFrame> {
+    let security_context = crate::security::SecurityContext::new(security_config.clone())?;
+    security_context.validate_path(path)?;
+    let mut pipeline = Pipeline::from_path(path)?;
+    load_check_files(&mut pipeline, &security_context)?;
+    let lf = match (input, pipeline.inputs.first()) {
+        (Some(input), _) => {
+            security_context.validate_path(input)?;
+            info!("Running {} on fixture {}", path.display(), input.display());
+            io::read_detected(input, None)?
+        }
+        (None, Some(first)) => {
+            security_context.validate_path(&first.path)?;
+            info!(
+                "Running {} on fixture {}",
+                path.display(),
+                first.path.display()
+            );
+            manifest::scan_input(first, &security_context, &resolve_runtime(&pipeline, None))?
+        }
+        (None, None) => {
+            return Err(MlPrepError::ConfigError(
+                serde_yaml::Error::custom("No inputs specified in pipeline"),
+                None,
+            ))
+        }
+    };
+    let df = lf.collect().map_err(MlPrepError::PolarsError)?;
+    execute_in_memory(df, pipeline, security_config, None)
+}
+
+/// Execute `on_failure` hooks. Hook errors are logged and never mask the pipeline error.
+/// `written` lists the output files the failed attempt moved into place; only those are
+/// removed by `delete_outputs`, never files an earlier run left at the output paths.
+fn run_failure_hooks(
+    path: &Path,
+    run_id: Uuid,
+    pipeline: &Pipeline,
+    security_config: &crate::security::SecurityConfig,
+    error: &MlPrepError,
+    written: &[PathBuf],
+) {
+    if pipeline.on_failure.is_empty() {
+        return;
+    }
+    let security_context = match crate::security::SecurityContext::new(security_config.clone()) {
+        Ok(ctx) => ctx,
+        Err(e) => {
+            warn!("Skipping on_failure hooks: {}", e);
+            return;
+        }
+    };
+    let payload = serde_json::json!({
+        "run_id": run_id.to_string(),
+        "pipeline": path.display().to_string(),
+        "status": "failed",
+        "error": error.to_string(),
+        "timestamp": Utc::now(),
+    });
+
+    for hook in &pipeline.on_failure {
+        let outcome: MlPrepResult<()> = match hook {
+            FailureHook::WriteMarker { path: marker } => security_context
+                .validate_path(marker)
+                .and_then(|_| {
+                    let file = std::fs::File::create(marker).map_err(MlPrepError::IoError)?;
+                    serde_json::to_writer_pretty(file, &payload)
+                        .map_err(|e| MlPrepError::Unknown(e.into()))
+                })
+                .map(|_| info!("Failure marker written to {}", marker)),
+            FailureHook::DeleteOutputs => {
+                let mut result = Ok(());
+                for out_path in written.iter().filter(|p| p.is_file()) {
+                    result = security_context
+                        .validate_path(out_path)
+                        .and_then(|_| std::fs::remove_file(out_path).map_err(MlPrepError::IoError));
+                    if result.is_err() {
+                        break;
+                    }
+                    info!("Removed partial output {}", out_path.display());
+                }
+                result
+            }
+            FailureHook::Webhook { url } => crate::notify::webhook_agent()
+                .post(url)
+                .send_json(payload.clone())
+                .map(|_| info!("Failure webhook delivered to {}", url))
+                .map_err(|e| MlPrepError::Unknown(e.into())),
+        };
+        if let Err(e) = outcome {
+            warn!("on_failure hook {:?} failed: {}", hook, e);
+        }
+    }
+}
+
+/// Write `df` according to the output's mode. Every write goes through a temp file
+/// that is renamed into place (see `io::write_atomic`); the files moved into place are
+/// added to `written`, except a file appended to, which still holds earlier rows.
+/// Appending in either way marks `written` as appended.
+/// Parquet files also record the column `docs` from the pipeline's `schema` block.
+fn write_output(
+    df: DataFrame,
+    output: &Output,
+    run_id: Uuid,
+    docs: &io::ColumnDocs,
+    written: &mut Written,
+) -> MlPrepResult<()> {
+    if output.is_split() {
+        return write_parts(df, output, docs, written);
+    }
+    let path = output.path.as_path();
+    let is_dataset_dir = io::is_dataset_dir(path);
+
+    let (target, df, appended) = match output.mode {
+        OutputMode::Append if is_dataset_dir => {
+            // Appending to a dataset directory adds a new part file.
+            std::fs::create_dir_all(path).map_err(MlPrepError::IoError)?;
+            let ext = output.format.as_deref().unwrap_or("parquet");
+            (path.join(format!("part-{}.{}", run_id, ext)), df, false)
+        }
+        OutputMode::Append if path.exists() => {
+            let existing = if io::has_extension(path, "parquet") {
+                io::read_parquet(path)?
+            } else {
+                io::read_csv(path)?
+            }
+            .collect()
+            .map_err(MlPrepError::PolarsError)?;
+            let mut combined = existing;
+            combined.vstack_mut(&df).map_err(|e| {
+                MlPrepError::TransformError(format!(
+                    "Cannot append to {}: schema mismatch ({})",
+                    output.path.display(),
+                    e
+                ))
+            })?;
+            (path.to_path_buf(), combined, true)
+        }
+        _ => (path.to_path_buf(), df, false),
+    };
+
+    write_file(df, &target, output, docs)?;
+    written.appended |= output.mode == OutputMode::Append;
+    if !appended {
+        written.files.push(target);
+    }
+    Ok(())
+}
+
+fn write_file(
+    df: DataFrame,
+    target: &Path,
+    output: &Output,
+    docs: &io::ColumnDocs,
+) -> MlPrepResult<()> {
+    if io::has_extension(target, "parquet") {
+        io::write_parquet_documented(df, target, docs)
+    } else if io::has_extension(target, "csv") {
+        io::write_csv(df, target)
+    } else {
+        Err(MlPrepError::ConfigError(
+            serde_yaml::Error::custom(format!(
+                "Unsupported output format for file: {}",
+                output.path.display()
+            )),
+            None,
+        ))
+    }
+}
+
+/// Write `df` as the numbered part files of a split output (see [`Output::part_path`]).
+/// Parts hold at most `max_rows_per_file` rows; under `max_file_size` each part is sized
+/// by the bytes per row of the previous one and rewritten with fewer rows if it still
+/// comes out too large. Appending adds parts after the existing ones; otherwise parts
+/// left over from an earlier, larger result are removed.
+/// Parts are staged under temporary names and only moved into place, and added to
+/// `written`, once all of them are complete, so a failed write leaves the existing parts
+/// as they were.
+fn write_parts(
+    df: DataFrame,
+    output: &Output,
+    docs: &io::ColumnDocs,
+    written: &mut Written,
+) -> MlPrepResult<()> {
+    let max_rows = output.max_rows_per_file.unwrap_or(usize::MAX);
+    if max_rows == 0 {
+        return Err(MlPrepError::ConfigError(
+            serde_yaml::Error::custom("max_rows_per_file must be at least 1"),
+            None,
+        ));
+    }
+    let max_bytes = output.max_file_bytes()?;
+    if io::is_dataset_dir(&output.path) {
+        std::fs::create_dir_all(&output.path).map_err(MlPrepError::IoError)?;
+    }
+    let first = match output.mode {
+        OutputMode::Append => output.files().len(),
+        _ => 0,
+    };
+
+    let mut staged = Vec::new();
+    let result =
+        stage_parts(&df, output, docs, max_rows, max_bytes, first, &mut staged).and_then(|()| {
+            staged.iter().try_for_each(|(stage, part)| {
+                io::move_into_place(stage, part)?;
+                written.files.push(part.clone());
+                written.appended |= output.mode == OutputMode::Append;
+                Ok(())
+            })
+        });
+    if result.is_err() {
+        // Parts already moved into place are left for the delete_outputs hook.
+        for (stage, _) in &staged {
+            let _ = std::fs::remove_file(stage);
+        }
+    }
+    result?;
+    let count = staged.len();
+    info!(
+        "Wrote {} rows to {} part files of {}",
+        df.height(),
+        count,
+        output.path.display()
+    );
+
+    if output.mode != OutputMode::Append {
+        let stale = (first + count..)
+            .map(|index| output.part_path(index))
+            .take_while(|part| part.exists());
+        for part in stale {
+            std::fs::remove_file(&part).map_err(MlPrepError::IoError)?;
+        }
+    }
+    Ok(())
+}
+
+/// Write the parts of `df`, numbered from `first`, to staging files; each staging file
+/// and the part path it belongs at is added to `staged` as soon as it is written.
+fn stage_parts(
+    df: &DataFrame,
+    output: &Output,
+    docs: &io::ColumnDocs,
+    max_rows: usize,
+    max_bytes: Option<u64>,
+    first: usize,
+    staged: &mut Vec<(PathBuf, PathBuf)>,
 ) -> MlPrepResult<()> {
+    // Rows that fit in `max_bytes` at `bytes` per `rows` rows, with some headroom
+    let rows_within = |max: u64, bytes: u64, rows: usize| {
+        let fit = rows as u128 * max as u128 * 9 / 10 / bytes.max(1) as u128;
+        usize::try_from(fit)
+            .unwrap_or(usize::MAX)
+            .clamp(1, max_rows)
+    };
+    let mut rows = match max_bytes {
+        Some(max) => rows_within(max, df.estimated_size() as u64, df.height()),
+        None => max_rows,
+    };
+
+    let mut index = first;
+    let mut offset = 0;
+    loop {
+        let part = df.slice(offset as i64, rows);
+        let len = part.height();
+        let path = output.part_path(index);
+        let stage = io::staging_path_for(&path)?;
+        staged.push((stage.clone(), path.clone()));
+        write_file(part, &stage, output, docs)?;
+        if let Some(max) = max_bytes {
+            let size = std::fs::metadata(&stage)
+                .map_err(MlPrepError::IoError)?
+                .len();
+            if size > max && len > 1 {
+                std::fs::remove_file(&stage).map_err(MlPrepError::IoError)?;
+                staged.pop();
+                rows = rows_within(max, size, len).min(len - 1);
+                continue;
+            }
+            if size > max {
+                warn!(
+                    "{} is larger than max_file_size: a single row exceeds it",
+                    path.display()
+                );
+            }
+            rows = rows_within(max, size, len.max(1));
+        }
+        offset += len;
+        index += 1;
+        if offset >= df.height() {
+            return Ok(());
+        }
+    }
+}
+
+/// Whether `output` can be written by streaming the plan straight into the file.
+/// Appends need the existing contents and datasets get generated part names, so both
+/// go through [`write_output`], as do Parquet files carrying column docs.
+fn can_sink(output: &Output, docs: &io::ColumnDocs) -> bool {
+    output.mode != OutputMode::Append
+        && !output.is_split()
+        && (docs.is_empty() || !io::has_extension(&output.path, "parquet"))
+        && !Path::new(&output.path).is_dir()
+        && (io::has_extension(&output.path, "parquet") || io::has_extension(&output.path, "csv"))
+}
+
+/// Stream `lf` into `output` without materializing it. Returns `false`, having written
+/// nothing, when the plan cannot be streamed and must be collected instead.
+fn sink_output(lf: LazyFrame, output: &Output) -> MlPrepResult<bool> {
+    match io::sink_atomic(lf, &output.path) {
+        Ok(()) => Ok(true),
+        Err(e) if io::is_not_streamable(&e) => {
+            warn!(
+                "Pipeline cannot run in streaming order; materializing {} in memory",
+                output.path.display()
+            );
+            Ok(false)
+        }
+        Err(e) => Err(e),
+    }
+}
+
+/// Schema (and, when sampling, row count) after one pipeline stage.
+#[derive(Debug, Serialize)]
+pub struct DryRunStep {
+    pub index: usize,
+    pub kind: String,
+    pub columns: Vec<(String, String)>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sample_rows: Option<usize>,
+}
+
+/// Result of `mlprep run --dry-run`: the resolved schema per step and the outputs that would be written.
+#[derive(Debug, Serialize)]
+pub struct DryRunReport {
+    pub pipeline: String,
+    pub sample: Option<usize>,
+    pub steps: Vec<DryRunStep>,
+    pub outputs: Vec<String>,
+}
+
+impl std::fmt::Display for DryRunReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Dry run: {}", self.pipeline)?;
+        for step in &self.steps {
+            write!(f, "  [{}] {}", step.index, step.kind)?;
+            if let Some(rows) = step.sample_rows {
+                write!(f, " ({} sample rows)", rows)?;
+            }
+            writeln!(f)?;
+            for (name, dtype) in &step.columns {
+                writeln!(f, "      {}: {}", name, dtype)?;
+            }
+        }
+        for output in &self.outputs {
+            writeln!(f, "  would write: {}", output)?;
+        }
+        Ok(())
+    }
+}
+
+/// Build the plan step by step and resolve each step's output schema without writing outputs.
+/// With `sample`, every stage also runs on the first `sample` input rows to report row counts.
+/// Note that validate and features steps still compute their statistics while planning.
+pub fn dry_run(
+    path: &PathBuf,
+    security_config: crate::security::SecurityConfig,
+    runtime_override: Option<crate::dsl::RuntimeConfig>,
+    sample: Option<usize>,
+) -> MlPrepResult<DryRunReport> {
+    let security_context = crate::security::SecurityContext::new(security_config)?;
+    security_context.validate_path(path)?;
+    let mut pipeline = Pipeline::from_path(path)?;
+    load_check_files(&mut pipeline, &security_context)?;
+    let runtime = resolve_runtime(&pipeline, runtime_override);
+
+    let input_conf = pipeline.inputs.first().ok_or_else(|| {
+        MlPrepError::ConfigError(
+            serde_yaml::Error::custom("No inputs specified in pipeline"),
+            None,
+        )
+    })?;
+    security_context.validate_path(&input_conf.path)?;
+    // Without a sample no input rows are read: the contract, validation summaries and
+    // feature fitting all see an empty frame with the input's schema.
+    let mut lf = manifest::scan_input(input_conf, &security_context, &runtime)?
+        .limit(sample.unwrap_or(0) as IdxSize);
+    if let Some(contract) = &input_conf.contract {
+        security_context.validate_path(contract)?;
+        contract::enforce(lf.clone(), &input_conf.path, contract, runtime.streaming)?;
+    }
+
+    let describe = |index: usize, kind: &str, lf: &mut LazyFrame| -> MlPrepResult<DryRunStep> {
+        let schema = lf.collect_schema().map_err(MlPrepError::PolarsError)?;
+        let sample_rows = match sample {
+            Some(_) => Some(
+                lf.clone()
+                    .collect()
+                    .map_err(MlPrepError::PolarsError)?
+                    .height(),
+            ),
+            None => None,
+        };
+        Ok(DryRunStep {
+            index,
+            kind: kind.to_string(),
+            columns: schema
+                .iter()
+                .map(|(name, dtype)| (name.to_string(), dtype.to_string()))
+                .collect(),
+            sample_rows,
+        })
+    };
+
+    // Stage 0 is the input after the pipeline-level `schema` casts.
+    let schema_only = Pipeline {
+        schema: pipeline.schema.clone(),
+        ..Default::default()
+    };
+    lf = crate::compute::apply_pipeline(lf, schema_only, &runtime, &security_context)?;
+    let mut steps = vec![describe(0, "input", &mut lf)?];
+
+    for (idx, step) in pipeline.steps.iter().enumerate() {
+        let mut step = step.clone();
+        // Fit in memory rather than persisting a new feature state file.
+        let state_path = match &mut step {
+            Step::Features(features) => Some(&mut features.state_path),
+            Step::FeatureSelect(select) => Some(&mut select.state_path),
+            _ => None,
+        };
+        if let Some(state_path) = state_path {
+            if matches!(state_path, Some(p) if !Path::new(p).exists()) {
+                *state_path = None;
+            }
+        }
+        // A dry run writes no violation details either.
+        if let Step::Validate(validate) = &mut step {
+            validate.violations_path = None;
+        }
+        let kind = step.kind();
+        // Without a sample there are no rows to fit on: features get neutral statistics and
+        // feature_select keeps every column.
+        let unfitted = sample.is_none()
+            && match &step {
+                Step::Features(features) => features.state_path.is_none(),
+                Step::FeatureSelect(select) => select.state_path.is_none(),
+                _ => false,
+            };
+        lf = if unfitted {
+            describe_unfitted(lf, &step)
+        } else {
+            let single = Pipeline {
+                steps: vec![step],
+                ..Default::default()
+            };
+            crate::compute::apply_pipeline(lf, single, &runtime, &security_context)
+        }
+        .map_err(|e| e.at_step(idx + 1).with_pipeline_source(path))?;
+        steps.push(describe(idx + 1, kind, &mut lf)?);
+    }
+
+    Ok(DryRunReport {
+        pipeline: path.display().to_string(),
+        sample,
+        steps,
+        outputs: pipeline
+            .outputs
+            .iter()
+            .map(|o| o.path.display().to_string())
+            .collect(),
+    })
+}
+
+/// `lf` after a `features` or `feature_select` step that was not fitted: see [`dry_run`].
+fn describe_unfitted(mut lf: LazyFrame, step: &Step) -> MlPrepResult<LazyFrame> {
+    let Step::Features(features) = step else {
+        return Ok(lf);
+    };
+    let schema = lf.collect_schema().map_err(MlPrepError::PolarsError)?;
+    let state = crate::features::unfitted_features(&schema, &features.config)
+        .map_err(|e| MlPrepError::FeatureError(e.to_string()))?;
+    crate::compute::apply_feature_state(lf, features, &state)
+}
+
+fn execute_attempt(
+    path: &PathBuf,
+    run_id: Uuid,
+    security_config: crate::security::SecurityConfig,
+    runtime_override: Option<crate::dsl::RuntimeConfig>,
+    attempt: u32,
+    batches: &mut Vec<kafka::Batch>,
+    written: &mut Written,
+) -> MlPrepResult<RunSummary> {
     let mut metrics = Metrics::new();
+    metrics.attempts = attempt;
     info!("Loading pipeline from {:?}", path);
 
     // 0. Security Context
@@ -64,25 +842,15 @@ pub fn execution_pipeline(
         ))
     })?;
 
-    let pipeline = Pipeline::from_path(path)?;
+    let mut pipeline = Pipeline::from_path(path)?;
+    load_check_files(&mut pipeline, &security_context)?;
 
     // Determine runtime configuration (pipeline config + CLI overrides)
-    let mut runtime = pipeline.runtime.clone().unwrap_or_default();
-    if let Some(override_conf) = runtime_override {
-        if override_conf.streaming {
-            runtime.streaming = true;
-        }
-        if override_conf.memory_limit.is_some() {
-            runtime.memory_limit = override_conf.memory_limit;
-        }
-        if override_conf.threads.is_some() {
-            runtime.threads = override_conf.threads;
-        }
-        if override_conf.cache.is_some() {
-            runtime.cache = override_conf.cache;
-        }
-    }
-    apply_runtime_env(&runtime);
+    let mut runtime = resolve_runtime(&pipeline, runtime_override);
+    let show_progress = runtime
+        .progress
+        .unwrap_or_else(|| std::io::stderr().is_terminal());
+    let mut effective = init_runtime(&runtime)?;
 
     // 1. Inputs
     if pipeline.inputs.is_empty() {
@@ -94,7 +862,7 @@ pub fn execution_pipeline(
 
     // Capture Input Stats
     let mut input_stats = Vec::new();
-    for input in &pipeline.inputs {
+    for input in &mut pipeline.inputs {
         // Validate input path
         security_context.validate_path(&input.path).map_err(|e| {
             MlPrepError::IoError(std::io::Error::new(
@@ -103,121 +871,653 @@ pub fn execution_pipeline(
             ))
         })?;
 
-        let metadata = std::fs::metadata(&input.path).map_err(MlPrepError::IoError)?;
-        let hash = observability::compute_file_hash(&input.path).map_err(MlPrepError::IoError)?;
-        input_stats.push(InputFileStats {
-            path: input.path.clone(),
-            size_bytes: metadata.len(),
-            hash,
-        });
+        // A Kafka batch is read from a local copy like a remote file.
+        if let Some(options) = &input.kafka {
+            if let Some(schema) = &options.avro_schema {
+                security_context.validate_path(schema).map_err(|e| {
+                    MlPrepError::IoError(std::io::Error::new(
+                        std::io::ErrorKind::PermissionDenied,
+                        e.to_string(),
+                    ))
+                })?;
+            }
+            let batch = kafka::consume(
+                &input.path,
+                options,
+                input.format.as_deref(),
+                &std::env::temp_dir(),
+            )?;
+            let mut stats = FileStats::of(&batch.path).map_err(MlPrepError::IoError)?;
+            stats.path = input.path.display().to_string();
+            input_stats.push(stats);
+            input.path = batch.path.clone();
+            input.format = Some("parquet".to_string());
+            input.kafka = None;
+            batches.push(batch);
+            continue;
+        }
+
+        // An Iceberg table is recorded by its current metadata file, and its data files
+        // are listed below.
+        if input.iceberg.is_some() {
+            let metadata = iceberg::metadata_path(&input.path, &runtime)?;
+            let mut stats = FileStats::of(&metadata).map_err(MlPrepError::IoError)?;
+            stats.path = input.path.display().to_string();
+            input_stats.push(stats);
+            continue;
+        }
+
+        // Remote files are read from their local copy; manifests are read where they are,
+        // since their relative paths resolve against their own location.
+        let local = if input.manifest.is_none() {
+            input_cache::localize(&input.path, &runtime)?
+        } else {
+            input.path.clone()
+        };
+        let mut stats = FileStats::of(&local).map_err(MlPrepError::IoError)?;
+        stats.path = input.path.display().to_string();
+        input_stats.push(stats);
+        input.path = local;
     }
 
-    // For MVP, handle first input
-    let input_conf = &pipeline.inputs[0];
-    info!("Reading input: {:?}", input_conf.path);
-    let start_read = Instant::now();
+    // Enforce the memory limit: when the estimated peak would exceed it, plan for
+    // streaming (so eager fits and summaries stream too) and write outputs out of core.
+    if let Some(limit) = effective.memory_limit_bytes {
+        let estimated = memory::estimate_pipeline_bytes(&pipeline)?;
+        effective.estimated_bytes = Some(estimated);
+        if estimated > limit {
+            warn!(
+                "Estimated memory {} exceeds the {} memory limit; {}",
+                memory::format_bytes(estimated),
+                memory::format_bytes(limit),
+                if runtime.streaming {
+                    "writing outputs out of core"
+                } else {
+                    "switching to streaming execution"
+                }
+            );
+            effective.degraded = true;
+            effective.streaming = true;
+            runtime.streaming = true;
+            for (index, kind) in memory::materializing_steps(&pipeline) {
+                warn!(
+                    "Step {} ({}) materializes the whole frame and may still exceed the memory limit",
+                    index, kind
+                );
+            }
+        }
+    }
+    metrics.runtime = Some(effective.clone());
 
-    let lf = if input_conf.path.ends_with(".parquet") {
-        io::read_parquet(&input_conf.path)?
-    } else {
-        io::read_csv(&input_conf.path)?
+    let input_conf = &pipeline.inputs[0];
+    let run = Run {
+        run_id,
+        pipeline: &pipeline,
+        runtime: &runtime,
+        effective: &effective,
+        security_context: &security_context,
+        show_progress,
     };
-    metrics.record_step("read_input", start_read.elapsed());
 
-    let dp = DataPipeline::new(lf);
+    // The data files of an Iceberg table are read like the files of a manifest.
+    let iceberg_manifest = iceberg::manifest();
+    let manifest = match (&input_conf.manifest, &input_conf.iceberg) {
+        (Some(manifest), _) => manifest,
+        (None, Some(_)) => &iceberg_manifest,
+        (None, None) => {
+            let format = io::detect_format(&input_conf.path, input_conf.format.as_deref())?;
+            let source = Source::File {
+                path: &input_conf.path,
+                format,
+            };
+            let unit = run.execute(source, pipeline.outputs.first(), &mut metrics, written)?;
+            return finish_run(
+                path,
+                run_id,
+                &pipeline,
+                &metrics,
+                input_stats,
+                unit.scans,
+                unit.described,
+            );
+        }
+    };
 
-    // 2. Steps
-    info!("Executing {} steps...", pipeline.steps.len());
-    let pb = ProgressBar::new(1);
-    pb.set_style(
-        ProgressStyle::default_bar()
-            .template("{spinner:.green} [{elapsed_precise}] {msg}")
-            .map_err(|e| MlPrepError::Unknown(e.into()))? // Template error is rare/internal
-            .progress_chars("#>-"),
+    let mut entries = match &input_conf.iceberg {
+        Some(table) => iceberg::data_files(input_conf, table, &pipeline.steps, &runtime)?,
+        None => manifest::load(input_conf, manifest)?,
+    };
+    manifest::validate(&entries, &security_context)?;
+    let remote: Vec<String> = entries
+        .iter()
+        .map(|entry| entry.path.display().to_string())
+        .collect();
+    manifest::localize(&mut entries, &runtime)?;
+    let entry_stats = entries
+        .iter()
+        .zip(remote)
+        .map(|(entry, path)| FileStats::of(&entry.path).map(|stats| FileStats { path, ..stats }))
+        .collect::<std::io::Result<Vec<_>>>()
+        .map_err(MlPrepError::IoError)?;
+    input_stats.extend(entry_stats.iter().cloned());
+    info!(
+        "Manifest {} lists {} files",
+        input_conf.path.display(),
+        entries.len()
     );
+    if !manifest.per_file {
+        let source = Source::Manifest {
+            label: &input_conf.path,
+            entries: &entries,
+            manifest,
+        };
+        let unit = run.execute(source, pipeline.outputs.first(), &mut metrics, written)?;
+        return finish_run(
+            path,
+            run_id,
+            &pipeline,
+            &metrics,
+            input_stats,
+            unit.scans,
+            unit.described,
+        );
+    }
+
+    // Fan out: each listed file runs through the steps on its own and gets its own output.
+    // Finished files are recorded as they complete, so a rerun after an interruption
+    // skips them; the record is removed once every file is done.
+    let output = pipeline.outputs.first().ok_or_else(|| {
+        MlPrepError::ConfigError(
+            serde_yaml::Error::custom("A per_file manifest input needs an output"),
+            None,
+        )
+    })?;
+    let progress_path = ManifestProgress::path(path, manifest);
+    security_context.validate_path(&progress_path)?;
+    if runtime.restart && progress_path.exists() {
+        info!("Discarding progress in {}", progress_path.display());
+        std::fs::remove_file(&progress_path).map_err(MlPrepError::IoError)?;
+    }
+    let mut progress = ManifestProgress::load(&progress_path)?;
+    let mut outputs = Vec::with_capacity(entries.len());
+    let mut scans = Vec::new();
+    let mut described = None;
+    for (index, (entry, stats)) in entries.iter().zip(&entry_stats).enumerate() {
+        let output = entry.output(output)?;
+        if let Some(done) = progress.finished(&entry.path, &stats.hash, &output) {
+            info!(
+                "Skipping file {}/{}: {} (finished by an earlier run)",
+                index + 1,
+                entries.len(),
+                entry.path.display()
+            );
+            metrics.rows_read += done.rows_read;
+            metrics.rows_written += done.rows_written;
+            outputs.push(output);
+            continue;
+        }
+        check_output(&security_context, &output)?;
+        if let Some(dir) = output.path.parent() {
+            std::fs::create_dir_all(dir).map_err(MlPrepError::IoError)?;
+        }
+        info!(
+            "Processing file {}/{}: {} -> {}",
+            index + 1,
+            entries.len(),
+            entry.path.display(),
+            output.path.display()
+        );
+        let source = Source::Manifest {
+            label: &entry.path,
+            entries: std::slice::from_ref(entry),
+            manifest,
+        };
+        let mut file_metrics = Metrics::new();
+        let unit = run.execute(source, Some(&output), &mut file_metrics, written)?;
+        metrics.add(&file_metrics);
+        scans.extend(unit.scans);
+        described.get_or_insert(unit.described);
+        progress.done.insert(
+            entry.path.to_string_lossy().into_owned(),
+            FileDone {
+                hash: stats.hash.clone(),
+                output: output.path.to_string_lossy().into_owned(),
+                rows_read: file_metrics.rows_read,
+                rows_written: file_metrics.rows_written,
+            },
+        );
+        progress.save(&progress_path)?;
+        outputs.push(output);
+    }
+    let pipeline = Pipeline {
+        outputs,
+        ..pipeline.clone()
+    };
+    let summary = finish_run(
+        path,
+        run_id,
+        &pipeline,
+        &metrics,
+        input_stats,
+        scans,
+        described.unwrap_or_default(),
+    )?;
+    if progress_path.exists() {
+        std::fs::remove_file(&progress_path).map_err(MlPrepError::IoError)?;
+    }
+    Ok(summary)
+}
 
-    pb.set_message("Building execution graph...");
-    let start_build = Instant::now();
-    let processed_dp = dp.apply_transforms(pipeline.clone(), &runtime, &security_context)?;
-    metrics.record_step("build_graph", start_build.elapsed());
-    pb.finish_with_message("Execution graph built.");
+/// What one pass over the steps reads: a single file, or manifest entries concatenated.
+enum Source<'a> {
+    File {
+        path: &'a Path,
+        format: io::FileFormat,
+    },
+    Manifest {
+        /// The manifest, or the one file of a per-file pass
+        label: &'a Path,
+        entries: &'a [ManifestEntry],
+        manifest: &'a Manifest,
+    },
+}
 
-    // Log active configuration
-    if runtime.streaming {
-        info!("Execution mode: Streaming enabled");
+impl Source<'_> {
+    fn label(&self) -> &Path {
+        match self {
+            Source::File { path, .. } => path,
+            Source::Manifest { label, .. } => label,
+        }
     }
-    if let Some(ref threads) = runtime.threads {
-        info!("Thread limit: {}", threads);
+
+    fn read(&self) -> MlPrepResult<LazyFrame> {
+        match self {
+            Source::File { path, format } => io::read_input(path, *format),
+            Source::Manifest {
+                entries, manifest, ..
+            } => manifest::scan(entries, manifest),
+        }
     }
-    if let Some(cache) = runtime.cache {
-        info!("Plan cache: {}", if cache { "on" } else { "off" });
+
+    fn count_rows(&self) -> MlPrepResult<usize> {
+        match self {
+            Source::File { path, format } => io::count_rows(path, *format),
+            Source::Manifest {
+                entries, manifest, ..
+            } => manifest::count_rows(entries, manifest),
+        }
     }
-    if let Some(limit) = &runtime.memory_limit {
-        info!("Memory limit: {}", limit);
+}
+
+/// Settings shared by every pass of one run attempt.
+struct Run<'a> {
+    run_id: Uuid,
+    pipeline: &'a Pipeline,
+    runtime: &'a crate::dsl::RuntimeConfig,
+    effective: &'a EffectiveRuntime,
+    security_context: &'a crate::security::SecurityContext,
+    show_progress: bool,
+}
+
+/// What a pass leaves for the run summary and lineage.
+struct Pass {
+    scans: Vec<io::ScanPushdown>,
+    described: OutputDescription,
+}
+
+impl Run<'_> {
+    /// Read `source`, apply the steps and write `output`, recording into `metrics`.
+    fn execute(
+        &self,
+        source: Source,
+        output: Option<&Output>,
+        metrics: &mut Metrics,
+        written: &mut Written,
+    ) -> MlPrepResult<Pass> {
+        let pipeline = self.pipeline;
+        let runtime = self.runtime;
+        let security_context = self.security_context;
+        let input_path = source.label();
+        info!("Reading input: {:?}", input_path);
+        let start_read = Instant::now();
+
+        let lf =
+            info_span!("read_input", input = %input_path.display()).in_scope(|| source.read())?;
+        metrics.record_step("read_input", start_read.elapsed());
+
+        if let Some(contract) = &pipeline.inputs[0].contract {
+            security_context.validate_path(contract)?;
+            info!("Checking input against contract: {}", contract.display());
+            control::report(Progress::Stage("contract"));
+            let start_contract = Instant::now();
+            info_span!("contract").in_scope(|| {
+                contract::enforce(lf.clone(), input_path, contract, runtime.streaming)
+            })?;
+            metrics.record_step("contract", start_contract.elapsed());
+        }
+
+        if let Some(chunk_size) = runtime.chunk_size {
+            match chunked::unsupported_reason(pipeline) {
+                Some(reason) => warn!(
+                    "Chunked execution unavailable: {}; processing the whole frame",
+                    reason
+                ),
+                None => {
+                    if let Some(output) = output {
+                        check_output(security_context, output)?;
+                    }
+                    let described =
+                        describe_output(&input_path.to_string_lossy(), &lf, &pipeline.steps, None);
+                    info!(
+                        "Executing {} steps in chunks of {} rows...",
+                        pipeline.steps.len(),
+                        chunk_size
+                    );
+                    control::report(Progress::Stage("execution"));
+                    let start_exec = Instant::now();
+                    info_span!("execution", chunk_size)
+                        .in_scope(|| {
+                            chunked::execute_chunked(
+                                lf, pipeline, runtime, chunk_size, output, metrics,
+                            )
+                        })
+                        .map_err(control::or_cancelled)?;
+                    metrics.record_step("execution", start_exec.elapsed());
+                    info!("Done.");
+                    return Ok(Pass {
+                        scans: Vec::new(),
+                        described,
+                    });
+                }
+            }
+        }
+
+        let input_lf = lf.clone();
+        // Counts input rows as they are read; the bar is only drawn once execution starts,
+        // since steps that fit or validate eagerly also read the input while planning.
+        let input_pb = ProgressBar::hidden();
+        let dp = DataPipeline::new(lf).with_input_counter(input_pb.clone());
+
+        // 2. Steps. The plan is only built here; nothing runs until execution, so this
+        // bar counts steps planned.
+        info!("Executing {} steps...", pipeline.steps.len());
+        let pb = new_progress_bar(
+            self.show_progress,
+            Some(pipeline.steps.len() as u64),
+            "{spinner:.green} [{elapsed_precise}] [{bar:30}] {pos}/{len} steps planned {msg}",
+        )?;
+
+        pb.set_message("Building execution graph...");
+        control::report(Progress::Stage("build_graph"));
+        let start_build = Instant::now();
+        let processed_dp = info_span!("build_graph").in_scope(|| {
+            dp.apply_transforms_tracked(pipeline.clone(), runtime, security_context, &pb, metrics)
+        })?;
+        metrics.record_step("build_graph", start_build.elapsed());
+        pb.finish_with_message("Execution graph built.");
+
+        let described = if output.is_none() {
+            OutputDescription::default()
+        } else {
+            describe_output(
+                &input_path.to_string_lossy(),
+                &input_lf,
+                &pipeline.steps,
+                Some(processed_dp.get_df()),
+            )
+        };
+
+        let scans = if runtime.explain_io.unwrap_or(false) {
+            let scans = io::explain_scans(processed_dp.get_df().clone())?;
+            for scan in &scans {
+                info!("Pushdown: {}", scan);
+            }
+            scans
+        } else {
+            Vec::new()
+        };
+
+        // Input rows come from the Parquet footer or a count-only CSV scan, never from
+        // running the plan; they are also the expected total for the ETA.
+        let input_rows = match source.count_rows() {
+            Ok(rows) => Some(rows),
+            Err(e) => {
+                warn!("Could not count input rows: {}", e);
+                None
+            }
+        };
+        if self.show_progress {
+            let total = input_rows.filter(|_| !scans_filtered(processed_dp.get_df()));
+            show_input_bar(&input_pb, total.map(|n| n as u64))?;
+        }
+        // Output rows are counted at the end of the plan, for the summary of a sunk run and
+        // the `rows` events of the Python module.
+        let rows_pb = ProgressBar::hidden();
+        let processed_dp = processed_dp
+            .with_row_counter(rows_pb.clone())
+            .with_runtime(self.effective);
+
+        // Log active configuration
+        if runtime.streaming {
+            info!("Execution mode: Streaming enabled");
+        }
+        info!("Thread pool: {} threads", self.effective.threads);
+        info!(
+            "Plan cache: {}",
+            if self.effective.cache { "on" } else { "off" }
+        );
+        if let Some(limit) = &runtime.memory_limit {
+            info!("Memory limit: {}", limit);
+        }
+
+        // 3. Execution & Output
+        control::check_cancelled()?;
+        control::report(Progress::Stage("execution"));
+        let start_exec = Instant::now();
+        metrics.rows_read = input_rows.unwrap_or_default();
+        let Some(output_conf) = output else {
+            info!("No outputs specified, executing pipeline without output...");
+            info_span!("execution")
+                .in_scope(|| processed_dp.collect(runtime.streaming))
+                .map_err(control::or_cancelled)?;
+            input_pb.finish_with_message("done");
+            metrics.record_step("execution", start_exec.elapsed());
+            metrics.rows_written = 0;
+            info!("Done.");
+            return Ok(Pass { scans, described });
+        };
+
+        check_output(security_context, output_conf)?;
+        info!(
+            "Executing pipeline and writing output to: {:?}",
+            output_conf.path
+        );
+
+        let docs = pipeline.column_docs();
+        let sunk = if self.effective.degraded && can_sink(output_conf, &docs) {
+            info_span!("execution", out_of_core = true)
+                .in_scope(|| sink_output(processed_dp.get_df().clone(), output_conf))
+                .map_err(control::or_cancelled)?
+        } else {
+            false
+        };
+        if sunk {
+            written.files.push(output_conf.path.clone());
+            input_pb.finish_with_message("done");
+            metrics.record_step("execution", start_exec.elapsed());
+            metrics.rows_written = rows_pb.position() as usize;
+        } else {
+            let final_df = info_span!("execution")
+                .in_scope(|| processed_dp.collect(runtime.streaming))
+                .map_err(control::or_cancelled)?;
+            input_pb.finish_with_message("done");
+            metrics.record_step("execution", start_exec.elapsed());
+            metrics.rows_written = final_df.height();
+
+            control::check_cancelled()?;
+            control::report(Progress::Stage("write_output"));
+            let start_write = Instant::now();
+            let write_span =
+                info_span!("write_output", path = %output_conf.path.display()).entered();
+            write_output(final_df, output_conf, self.run_id, &docs, written)?;
+            write_span.exit();
+            metrics.record_step("write_output", start_write.elapsed());
+        }
+        Ok(Pass { scans, described })
     }
+}
 
-    // 3. Execution & Output
-    let start_exec = Instant::now();
-    if pipeline.outputs.is_empty() {
-        info!("No outputs specified, executing pipeline without output...");
-        let df = processed_dp.collect(runtime.streaming)?;
-        metrics.record_step("execution", start_exec.elapsed());
-        metrics.rows_read = df.height(); // Approx since we executed
-        metrics.rows_written = 0;
-        info!("Done.");
-        return Ok(()); // Should we write lineage here too? Probably yes.
-    }
-
-    let output_conf = &pipeline.outputs[0];
-    security_context
-        .validate_path(&output_conf.path)
-        .map_err(|e| {
+/// Output schema and column-level lineage recorded in the lineage file.
+#[derive(Default)]
+struct OutputDescription {
+    schema: Vec<SchemaField>,
+    columns: Vec<ColumnLineage>,
+}
+
+/// Describe the output of `steps` on the input: `output` is the built plan, whose schema
+/// names fitted feature columns. Failing to derive it only loses the description, not
+/// the run.
+fn describe_output(
+    input: &str,
+    input_lf: &LazyFrame,
+    steps: &[Step],
+    output: Option<&LazyFrame>,
+) -> OutputDescription {
+    let fields = |lf: &LazyFrame| -> MlPrepResult<Vec<SchemaField>> {
+        let schema = lf
+            .clone()
+            .collect_schema()
+            .map_err(MlPrepError::PolarsError)?;
+        Ok(schema
+            .iter()
+            .map(|(name, dtype)| SchemaField {
+                name: name.to_string(),
+                dtype: format!("{:?}", dtype),
+            })
+            .collect())
+    };
+    let names = |fields: &[SchemaField]| -> Vec<String> {
+        fields.iter().map(|field| field.name.clone()).collect()
+    };
+    let described = fields(input_lf).and_then(|input_fields| {
+        let schema = output.map(fields).transpose()?.unwrap_or_default();
+        let output_columns = output.map(|_| names(&schema));
+        let columns = lineage::column_lineage(
+            input,
+            &names(&input_fields),
+            steps,
+            output_columns.as_deref(),
+        )?;
+        Ok(OutputDescription { schema, columns })
+    });
+    described.unwrap_or_else(|e| {
+        warn!("Could not describe the output schema and lineage: {}", e);
+        OutputDescription::default()
+    })
+}
+
+/// Refuse outputs outside the allowed paths, that exist with `error_if_exists`, or that
+/// use a mode other than `overwrite` in an object store.
+fn check_output(
+    security_context: &crate::security::SecurityContext,
+    output: &Output,
+) -> MlPrepResult<()> {
+    security_context.validate_path(&output.path).map_err(|e| {
+        MlPrepError::IoError(std::io::Error::new(
+            std::io::ErrorKind::PermissionDenied,
+            e.to_string(),
+        ))
+    })?;
+    if let Some(feast) = &output.feast {
+        let definition = crate::feast::definition_path(output, feast);
+        security_context.validate_path(&definition).map_err(|e| {
             MlPrepError::IoError(std::io::Error::new(
                 std::io::ErrorKind::PermissionDenied,
                 e.to_string(),
             ))
         })?;
+    }
 
-    info!(
-        "Executing pipeline and writing output to: {:?}",
-        output_conf.path
-    );
+    if io::is_cloud_uri(&output.path) && output.mode != OutputMode::Overwrite {
+        return Err(MlPrepError::ConfigError(
+            serde_yaml::Error::custom(format!(
+                "Output {} is in an object store, where only mode: overwrite is supported",
+                output.path.display()
+            )),
+            None,
+        ));
+    }
+    if io::is_cloud_uri(&output.path) && output.max_file_size.is_some() {
+        return Err(MlPrepError::ConfigError(
+            serde_yaml::Error::custom(format!(
+                "Output {} is in an object store, where max_file_size is not supported; use max_rows_per_file",
+                output.path.display()
+            )),
+            None,
+        ));
+    }
+    if output.mode == OutputMode::ErrorIfExists && output.files().iter().any(|f| f.exists()) {
+        return Err(MlPrepError::IoError(std::io::Error::new(
+            std::io::ErrorKind::AlreadyExists,
+            format!(
+                "Output {} already exists and mode is error_if_exists",
+                output.path.display()
+            ),
+        )));
+    }
+    Ok(())
+}
 
-    let mut final_df = processed_dp.collect(runtime.streaming)?;
-    metrics.record_step("execution", start_exec.elapsed());
-    metrics.rows_written = final_df.height();
-    // In lazy exec, we might not verify rows_read easily without scanning input separately
-    // metrics.rows_read = ???
+/// Write the lineage file next to the pipeline and build the run summary.
+fn finish_run(
+    path: &Path,
+    run_id: Uuid,
+    pipeline: &Pipeline,
+    metrics: &Metrics,
+    input_stats: Vec<FileStats>,
+    scans: Vec<io::ScanPushdown>,
+    described: OutputDescription,
+) -> MlPrepResult<RunSummary> {
+    if pipeline.outputs.is_empty() {
+        let mut summary = RunSummary::success(run_id, path, metrics, Vec::new());
+        summary.scans = scans;
+        return Ok(summary);
+    }
 
-    let start_write = Instant::now();
-    if output_conf.path.ends_with(".parquet") {
-        io::write_parquet(final_df.clone(), &output_conf.path)?;
-    } else {
-        // Fallback for CSV
-        if output_conf.path.ends_with(".csv") {
-            let mut file =
-                std::fs::File::create(&output_conf.path).map_err(MlPrepError::IoError)?;
-            CsvWriter::new(&mut file)
-                .finish(&mut final_df)
-                .map_err(MlPrepError::PolarsError)?;
-        } else {
-            return Err(MlPrepError::ConfigError(
-                serde_yaml::Error::custom(format!(
-                    "Unsupported output format for file: {}",
-                    output_conf.path
-                )),
-                None,
-            ));
+    let mut output_files = Vec::new();
+    for output in &pipeline.outputs {
+        for file in output.files() {
+            match FileStats::of(&file) {
+                Ok(stats) => output_files.push(stats),
+                Err(e) => warn!("Could not hash output {}: {}", file.display(), e),
+            }
+        }
+        if let Some(feast) = &output.feast {
+            let definition = crate::feast::write_definition(output, feast)?;
+            info!("Feast definition written to {}", definition.display());
         }
     }
-    metrics.record_step("write_output", start_write.elapsed());
 
     // Generate Lineage
     let lineage = Lineage {
         run_id: run_id.to_string(),
         timestamp: Utc::now(),
+        attempts: metrics.attempts,
         inputs: input_stats,
-        outputs: pipeline.outputs.iter().map(|o| o.path.clone()).collect(),
+        outputs: pipeline
+            .outputs
+            .iter()
+            .map(|o| o.path.display().to_string())
+            .collect(),
+        output_files,
+        rows_read: metrics.rows_read,
+        rows_written: metrics.rows_written,
+        violations: metrics.violations,
+        quality_score: metrics.quality_score,
+        steps: pipeline.steps.clone(),
+        schema: described.schema,
+        seed: metrics.runtime.as_ref().and_then(|r| r.seed),
+        columns: described.columns,
     };
 
     // Write lineage.json
@@ -231,12 +1531,24 @@ pub fn execution_pipeline(
         .map_err(|e| MlPrepError::Unknown(e.into()))?;
 
     info!("Lineage written to {}", lineage_path.display());
-    if let Ok(m_json) = serde_json::to_string(&metrics) {
+    if let Ok(m_json) = serde_json::to_string(metrics) {
         info!("Metrics: {}", m_json);
     }
 
     info!("Pipeline completed successfully.");
-    Ok(())
+    let mut summary = RunSummary::success(
+        run_id,
+        path,
+        metrics,
+        pipeline
+            .outputs
+            .iter()
+            .map(|o| o.path.display().to_string())
+            .collect(),
+    );
+    summary.scans = scans;
+    summary.lineage = Some(lineage_path.display().to_string());
+    Ok(summary)
 }
 
 #[cfg(test)]
@@ -285,4 +1597,295 @@ mod tests {
         let non_existent_restricted = restricted_dir.join("output.parquet");
         assert!(context.validate_path(&non_existent_restricted).is_err());
     }
+
+    #[test]
+    fn test_write_output_records_appends() {
+        use super::{write_output, Written};
+        use crate::dsl::{Output, OutputMode};
+        use polars::prelude::*;
+
+        let dir = tempdir().unwrap();
+        let output = |mode| Output {
+            path: dir.path().join("out.csv"),
+            format: None,
+            compression: None,
+            partition_by: None,
+            mode,
+            feast: None,
+            max_rows_per_file: None,
+            max_file_size: None,
+        };
+        let write = |mode| {
+            let mut written = Written::default();
+            let df = df!("a" => [1i64]).unwrap();
+            write_output(
+                df,
+                &output(mode),
+                uuid::Uuid::new_v4(),
+                &Default::default(),
+                &mut written,
+            )
+            .unwrap();
+            written
+        };
+        let created = write(OutputMode::Overwrite);
+        assert_eq!(created.files, [dir.path().join("out.csv")]);
+        assert!(!created.appended);
+        // Retrying after this would add the row again, and the file is not ours to delete.
+        let appended = write(OutputMode::Append);
+        assert!(appended.files.is_empty());
+        assert!(appended.appended);
+    }
+
+    #[test]
+    fn test_input_counter_and_filtered_scans() {
+        use crate::engine::DataPipeline;
+        use indicatif::ProgressBar;
+        use polars::prelude::*;
+
+        let dir = tempdir().unwrap();
+        let csv = dir.path().join("in.csv");
+        std::fs::write(&csv, "a\n1\n2\n3\n4\n").unwrap();
+        let pb = ProgressBar::hidden();
+        let counted =
+            DataPipeline::new(crate::io::read_csv(&csv).unwrap()).with_input_counter(pb.clone());
+        assert!(!super::scans_filtered(counted.get_df()));
+        assert_eq!(counted.get_df().clone().collect().unwrap().height(), 4);
+        assert_eq!(pb.position(), 4);
+
+        // The filter is pushed into the scan past the counter, so 4 is no longer a total.
+        let filtered = counted.get_df().clone().filter(col("a").gt(lit(2)));
+        assert!(super::scans_filtered(&filtered));
+    }
+
+    #[test]
+    fn test_execute_in_memory() {
+        use polars::prelude::*;
+
+        let df = df!("a" => [1, 2, 3], "b" => [10, 20, 30]).unwrap();
+        let pipeline = crate::dsl::Pipeline::from_reader(
+            "steps:\n  - type: filter\n    condition: \"a > 1\"\n  - type: select\n    columns: [b]\n"
+                .as_bytes(),
+        )
+        .unwrap();
+        let config = SecurityConfig {
+            allowed_paths: None,
+            mask_columns: None,
+        };
+        let out = super::execute_in_memory(df, pipeline, config, None).unwrap();
+        assert_eq!(out.get_column_names(), vec!["b"]);
+        assert_eq!(
+            out.column("b").unwrap().i32().unwrap().to_vec(),
+            vec![Some(20), Some(30)]
+        );
+    }
+
+    #[test]
+    fn test_init_runtime_reports_effective_settings() {
+        let runtime = crate::dsl::RuntimeConfig {
+            memory_limit: Some("512MB".to_string()),
+            cache: Some(false),
+            ..Default::default()
+        };
+        let effective = super::init_runtime(&runtime).unwrap();
+        assert_eq!(effective.threads, polars_core::POOL.current_num_threads());
+        assert!(!effective.cache);
+        assert!(!effective.streaming);
+        assert_eq!(effective.memory_limit_bytes, Some(512 << 20));
+
+        let defaults = super::init_runtime(&Default::default()).unwrap();
+        assert!(defaults.cache);
+        assert_eq!(defaults.memory_limit_bytes, None);
+
+        let invalid = crate::dsl::RuntimeConfig {
+            memory_limit: Some("plenty".to_string()),
+            ..Default::default()
+        };
+        assert!(super::init_runtime(&invalid).is_err());
+    }
+
+    fn run_with_memory_limit(
+        dir: &std::path::Path,
+        steps: &str,
+        limit: &str,
+    ) -> crate::observability::RunSummary {
+        let input = dir.join("input.csv");
+        std::fs::write(&input, "a,b\n1,10\n1,20\n2,30\n").unwrap();
+        let pipeline = dir.join("pipeline.yaml");
+        std::fs::write(
+            &pipeline,
+            format!(
+                "inputs:\n  - path: \"{}\"\nsteps:\n{}outputs:\n  - path: \"{}\"\n",
+                input.display(),
+                steps,
+                dir.join("out.parquet").display()
+            ),
+        )
+        .unwrap();
+        let runtime = crate::dsl::RuntimeConfig {
+            memory_limit: Some(limit.to_string()),
+            progress: Some(false),
+            ..Default::default()
+        };
+        let config = SecurityConfig {
+            allowed_paths: None,
+            mask_columns: None,
+        };
+        super::execution_pipeline(&pipeline, uuid::Uuid::new_v4(), config, Some(runtime)).unwrap()
+    }
+
+    #[test]
+    fn test_memory_limit_degrades_to_streaming_sink() {
+        let dir = tempdir().unwrap();
+        let steps = "  - type: filter\n    condition: \"b > 10\"\n";
+
+        let summary = run_with_memory_limit(dir.path(), steps, "1GB");
+        assert_eq!((summary.rows_read, summary.rows_written), (3, 2));
+        let runtime = summary.runtime.unwrap();
+        assert!(!runtime.degraded);
+        assert!(!runtime.streaming);
+        assert!(runtime.estimated_bytes.unwrap() > 0);
+
+        let summary = run_with_memory_limit(dir.path(), steps, "8B");
+        let runtime = summary.runtime.unwrap();
+        assert!(runtime.degraded);
+        assert!(runtime.streaming);
+        assert_eq!(summary.rows_read, 3);
+        assert_eq!(summary.rows_written, 2);
+        assert!(!summary.step_durations_ms.contains_key("write_output"));
+        let out = crate::io::read_parquet(dir.path().join("out.parquet"))
+            .unwrap()
+            .collect()
+            .unwrap();
+        assert_eq!(out.height(), 2);
+    }
+
+    #[test]
+    fn test_memory_limit_falls_back_for_unstreamable_plans() {
+        let dir = tempdir().unwrap();
+        let steps = "  - type: window\n    partition_by: [a]\n    ops:\n      - column: b\n        func: sum\n        alias: total\n";
+
+        let summary = run_with_memory_limit(dir.path(), steps, "8B");
+        assert!(summary.runtime.unwrap().degraded);
+        assert_eq!(summary.rows_read, 3);
+        assert_eq!(summary.rows_written, 3);
+        assert!(summary.step_durations_ms.contains_key("write_output"));
+        let out = crate::io::read_parquet(dir.path().join("out.parquet"))
+            .unwrap()
+            .collect()
+            .unwrap();
+        assert_eq!(out.column("total").unwrap().len(), 3);
+    }
+
+    #[test]
+    fn test_seeded_runs_are_byte_identical() {
+        let dir = tempdir().unwrap();
+        let input = dir.path().join("input.csv");
+        let rows: Vec<String> = (0..500)
+            .map(|i| format!("k{},{}", (i * 7919) % 97, i % 13))
+            .collect();
+        std::fs::write(&input, format!("key,b\n{}\n", rows.join("\n"))).unwrap();
+
+        let run = |name: &str| {
+            let output = dir.path().join(name);
+            let pipeline = dir.path().join("pipeline.yaml");
+            std::fs::write(
+                &pipeline,
+                format!(
+                    r#"
+inputs:
+  - path: "{}"
+steps:
+  - type: features
+    config:
+      features:
+        - {{column: seg, transform: cluster_encode, inputs: [b], k: 3}}
+  - type: group_by
+    by: [key]
+    aggs:
+      seg_cluster: {{func: max}}
+outputs:
+  - path: "{}"
+runtime:
+  seed: 7
+  progress: false
+"#,
+                    input.display(),
+                    output.display()
+                ),
+            )
+            .unwrap();
+            let config = SecurityConfig {
+                allowed_paths: None,
+                mask_columns: None,
+            };
+            let summary =
+                super::execution_pipeline(&pipeline, uuid::Uuid::new_v4(), config, None).unwrap();
+            assert_eq!(summary.runtime.unwrap().seed, Some(7));
+            let lineage: serde_json::Value =
+                serde_json::from_str(&std::fs::read_to_string(summary.lineage.unwrap()).unwrap())
+                    .unwrap();
+            assert_eq!(lineage["seed"], 7);
+            std::fs::read(output).unwrap()
+        };
+
+        let first = run("first.csv");
+        assert_eq!(first, run("second.csv"));
+        // Groups come out in order of first appearance.
+        assert!(String::from_utf8(first)
+            .unwrap()
+            .starts_with("key,seg_cluster\nk0,"));
+    }
+
+    fn write_pipeline(dir: &std::path::Path) -> std::path::PathBuf {
+        let input = dir.join("input.csv");
+        std::fs::write(&input, "a,b\n1,10\n2,20\n").unwrap();
+        let pipeline = dir.join("pipeline.yaml");
+        std::fs::write(
+            &pipeline,
+            format!(
+                "inputs:\n  - path: \"{}\"\nsteps:\n  - type: select\n    columns: [a]\noutputs:\n  - path: \"{}\"\n",
+                input.display(),
+                dir.join("out.csv").display()
+            ),
+        )
+        .unwrap();
+        pipeline
+    }
+
+    #[test]
+    fn test_run_control_progress_and_cancel() {
+        use crate::control::{self, Progress, RunControl};
+        use crate::errors::MlPrepError;
+
+        let dir = tempdir().unwrap();
+        let pipeline = write_pipeline(dir.path());
+        let config = SecurityConfig {
+            allowed_paths: None,
+            mask_columns: None,
+        };
+
+        let (tx, rx) = std::sync::mpsc::channel();
+        {
+            let _guard = control::install(RunControl::new(Some(tx)));
+            super::execution_pipeline(&pipeline, uuid::Uuid::new_v4(), config.clone(), None)
+                .unwrap();
+        }
+        let events: Vec<Progress> = rx.try_iter().collect();
+        assert!(events.contains(&Progress::Step {
+            index: 1,
+            total: 1,
+            kind: "select"
+        }));
+        assert!(events.contains(&Progress::Stage("write_output")));
+        assert!(events.contains(&Progress::Rows(2)));
+
+        std::fs::remove_file(dir.path().join("out.csv")).unwrap();
+        let cancelled = RunControl::new(None);
+        cancelled.cancel();
+        let _guard = control::install(cancelled);
+        let result = super::execution_pipeline(&pipeline, uuid::Uuid::new_v4(), config, None);
+        assert!(matches!(result, Err(MlPrepError::Cancelled)));
+        assert!(!dir.path().join("out.csv").exists());
+    }
 }
@@ -0,0 +1,198 @@
+//! `mlprep init`: a starter project — `pipeline.yaml`, `checks.yaml`, `data/` and
+//! `output/` — so a new user starts from a pipeline that runs. Given a sample input, the
+//! pipeline reads it and the checks describe its columns as they are.
+
+use crate::contract::Contract;
+use crate::errors::{MlPrepError, MlPrepResult};
+use crate::io;
+use serde::de::Error;
+use std::path::{Path, PathBuf};
+
+/// Input path of a project scaffolded without a sample.
+const DEFAULT_INPUT: &str = "data/input.csv";
+
+const GITIGNORE: &str = "output/\n";
+
+const EXAMPLE_CHECKS: &str = "\
+# Data quality checks, run by the pipeline's validate step.
+# See docs/wiki/validation.md for all checks.
+columns: []
+#  - name: id
+#    not_null: true
+#    unique: true
+#  - name: price
+#    range: [0, 1000]
+#    severity: high
+";
+
+/// A column name as a YAML scalar.
+fn yaml_str(value: &str) -> String {
+    serde_yaml::to_string(value)
+        .map(|s| s.trim_end().to_string())
+        .unwrap_or_else(|_| format!("{:?}", value))
+}
+
+fn pipeline_yaml(input: &str, output: &str) -> String {
+    format!(
+        "\
+# mlprep pipeline; run from this directory with `mlprep run pipeline.yaml`.
+# See docs/wiki/pipeline-reference.md for every step.
+version: {version}
+
+inputs:
+  - path: {input}
+
+steps:
+  # Add transformations here, e.g.
+  # - type: filter
+  #   condition: \"price > 0\"
+  # - type: select
+  #   columns: [id, price]
+  - type: validate
+    checks_path: checks.yaml
+    mode: warn
+
+outputs:
+  - path: {output}
+",
+        version = crate::dsl::DSL_VERSION,
+        input = yaml_str(input),
+        output = yaml_str(output),
+    )
+}
+
+/// Checks describing `sample` as it is: `not_null` for columns without nulls and the
+/// observed range of numeric columns.
+fn sample_checks(sample: &Path) -> MlPrepResult<String> {
+    let contract = Contract::generate(io::read_detected(sample, None)?, false)?;
+    let mut yaml = format!(
+        "# Checks generated by `mlprep init` from {}; tighten or relax as needed.\n\
+         # See docs/wiki/validation.md for all checks.\ncolumns:\n",
+        sample.display()
+    );
+    for column in &contract.columns {
+        yaml.push_str(&format!("  - name: {}\n", yaml_str(&column.name)));
+        if !column.nullable {
+            yaml.push_str("    not_null: true\n");
+        }
+        if let (Some(min), Some(max)) = (column.min, column.max) {
+            yaml.push_str(&format!("    range: [{}, {}]\n", min, max));
+        }
+    }
+    Ok(yaml)
+}
+
+/// The files of a project in `dir`, relative to it. The pipeline runs from `dir`, so
+/// `sample` is referenced relative to it when inside it, else by its absolute path.
+pub fn files(dir: &Path, sample: Option<&Path>) -> MlPrepResult<Vec<(PathBuf, String)>> {
+    let (input, stem, checks) = match sample {
+        Some(sample) => {
+            let stem = sample
+                .file_stem()
+                .map(|s| s.to_string_lossy().into_owned())
+                .unwrap_or_else(|| "result".to_string());
+            let absolute = std::path::absolute(sample)?;
+            let input = absolute
+                .strip_prefix(std::path::absolute(dir)?)
+                .unwrap_or(&absolute)
+                .to_string_lossy()
+                .into_owned();
+            (input, stem, sample_checks(sample)?)
+        }
+        None => (
+            DEFAULT_INPUT.to_string(),
+            "result".to_string(),
+            EXAMPLE_CHECKS.to_string(),
+        ),
+    };
+    Ok(vec![
+        (
+            PathBuf::from("pipeline.yaml"),
+            pipeline_yaml(&input, &format!("output/{}.parquet", stem)),
+        ),
+        (PathBuf::from("checks.yaml"), checks),
+        (PathBuf::from(".gitignore"), GITIGNORE.to_string()),
+    ])
+}
+
+/// Scaffold a project in `dir`; returns the files written. Existing files are left alone
+/// unless `force` is set.
+pub fn init(dir: &Path, sample: Option<&Path>, force: bool) -> MlPrepResult<Vec<PathBuf>> {
+    let files = files(dir, sample)?;
+    let existing: Vec<String> = files
+        .iter()
+        .map(|(path, _)| dir.join(path))
+        .filter(|path| path.exists())
+        .map(|path| path.display().to_string())
+        .collect();
+    if !existing.is_empty() && !force {
+        return Err(MlPrepError::ConfigError(
+            serde_yaml::Error::custom(format!(
+                "{} already exist; pass --force to overwrite",
+                existing.join(", ")
+            )),
+            None,
+        ));
+    }
+    for sub in ["data", "output"] {
+        std::fs::create_dir_all(dir.join(sub))?;
+    }
+    let mut written = Vec::with_capacity(files.len());
+    for (path, content) in files {
+        let path = dir.join(path);
+        std::fs::write(&path, content)?;
+        written.push(path);
+    }
+    Ok(written)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dsl::{CheckConfig, Pipeline, Step};
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_init_scaffolds_a_loadable_project() {
+        let dir = tempdir().unwrap();
+        let project = dir.path().join("churn");
+        let written = init(&project, None, false).unwrap();
+        assert_eq!(written.len(), 3);
+        assert!(project.join("data").is_dir() && project.join("output").is_dir());
+
+        let pipeline = Pipeline::from_path(project.join("pipeline.yaml")).unwrap();
+        assert_eq!(pipeline.inputs[0].path, Path::new(DEFAULT_INPUT));
+        assert_eq!(pipeline.outputs[0].path, Path::new("output/result.parquet"));
+        let Step::Validate(validate) = &pipeline.steps[0] else {
+            panic!("expected a validate step");
+        };
+        assert_eq!(
+            validate.checks_path.as_deref(),
+            Some(project.join("checks.yaml").as_path())
+        );
+        assert!(CheckConfig::load(project.join("checks.yaml"))
+            .unwrap()
+            .columns
+            .is_empty());
+
+        let err = init(&project, None, false).unwrap_err().to_string();
+        assert!(err.contains("pass --force to overwrite"), "{}", err);
+        assert!(init(&project, None, true).is_ok());
+
+        let sample = project.join("data/orders.csv");
+        std::fs::write(&sample, "order id,amount,note\n1,2.5,\n2,10,rush\n").unwrap();
+        let scaffolded = files(&project, Some(&sample)).unwrap();
+        let checks: CheckConfig = serde_yaml::from_str(&scaffolded[1].1).unwrap();
+        assert_eq!(checks.columns.len(), 3);
+        assert_eq!(checks.columns[0].name, "order id");
+        assert!(checks.columns[0].not_null);
+        assert_eq!(checks.columns[1].range, Some((2.5, 10.0)));
+        assert!(!checks.columns[2].not_null);
+        let pipeline = Pipeline::from_reader(scaffolded[0].1.as_bytes()).unwrap();
+        assert_eq!(pipeline.inputs[0].path, Path::new("data/orders.csv"));
+        assert_eq!(pipeline.outputs[0].path, Path::new("output/orders.parquet"));
+        let elsewhere = files(&dir.path().join("other"), Some(&sample)).unwrap();
+        let pipeline = Pipeline::from_reader(elsewhere[0].1.as_bytes()).unwrap();
+        assert_eq!(pipeline.inputs[0].path, sample);
+    }
+}
@@ -14,11 +14,22 @@ pub struct SecurityContext {
     masker: Masker,
 }
 
+/// Object store, HTTP(S) and Kafka URIs, which are allowed by URI prefix rather than by
+/// directory.
+fn is_uri(path: &Path) -> bool {
+    crate::input_cache::is_remote(path) || crate::kafka::is_kafka_uri(path)
+}
+
 impl SecurityContext {
     pub fn new(config: SecurityConfig) -> MlPrepResult<Self> {
         let allowed_paths = if let Some(paths) = config.allowed_paths {
             let mut canonical_paths = Vec::new();
             for p in paths {
+                // Object store prefixes such as `s3://bucket/raw/` are matched as written.
+                if is_uri(&p) {
+                    canonical_paths.push(p);
+                    continue;
+                }
                 // If path doesn't exist, this might fail. CLI args should ideally be existing paths?
                 // Or we canonicalize as much as possible. For now, strict check: must exist to be an allowed root.
                 if let Ok(canonical) = p.canonicalize() {
@@ -45,6 +56,26 @@ impl SecurityContext {
     pub fn validate_path<P: AsRef<Path>>(&self, path: P) -> MlPrepResult<()> {
         if let Some(allowed) = &self.allowed_paths {
             let path_ref = path.as_ref();
+            if is_uri(path_ref) {
+                let uri = path_ref.to_string_lossy();
+                // A `..` segment would climb out of the prefix once the server resolves it.
+                let climbs = uri.split(['/', '?', '#']).any(|segment| segment == "..");
+                let is_allowed = !climbs
+                    && allowed.iter().any(|allowed_base| {
+                        let base = allowed_base.to_string_lossy();
+                        let base = base.trim_end_matches('/');
+                        is_uri(allowed_base)
+                            && (uri == base || uri.starts_with(&format!("{}/", base)))
+                    });
+                if !is_allowed {
+                    return Err(MlPrepError::Unknown(anyhow::anyhow!(
+                        "Access denied: Path {:?} is not in allowed paths {:?}",
+                        path_ref,
+                        allowed
+                    )));
+                }
+                return Ok(());
+            }
 
             // Attempt to resolve target path
             let target = if path_ref.exists() {
@@ -76,9 +107,10 @@ impl SecurityContext {
                 }
             };
 
+            let target_cmp = strip_verbatim(&target);
             let is_allowed = allowed
                 .iter()
-                .any(|allowed_base| target.starts_with(allowed_base));
+                .any(|allowed_base| target_cmp.starts_with(strip_verbatim(allowed_base)));
 
             if !is_allowed {
                 return Err(MlPrepError::Unknown(anyhow::anyhow!(
@@ -96,6 +128,35 @@ impl SecurityContext {
     }
 }
 
+/// `path` without a Windows verbatim prefix: `\\?\C:\data` becomes `C:\data` and
+/// `\\?\UNC\server\share\data` becomes `\\server\share\data`. `canonicalize` returns
+/// verbatim paths on Windows (they are what lets long paths work), while allowed roots or
+/// parents that failed to resolve may not be, so both sides are compared in this form.
+/// Elsewhere paths have no prefix and are returned unchanged.
+fn strip_verbatim(path: &Path) -> PathBuf {
+    use std::ffi::OsString;
+    use std::path::{Component, Prefix};
+
+    let mut components = path.components();
+    let root = match components.next() {
+        Some(Component::Prefix(prefix)) => match prefix.kind() {
+            Prefix::VerbatimDisk(disk) => OsString::from(format!("{}:", disk as char)),
+            Prefix::VerbatimUNC(server, share) => {
+                let mut root = OsString::from(r"\\");
+                root.push(server);
+                root.push(r"\");
+                root.push(share);
+                root
+            }
+            _ => return path.to_path_buf(),
+        },
+        _ => return path.to_path_buf(),
+    };
+    let mut stripped = PathBuf::from(root);
+    stripped.extend(components);
+    stripped
+}
+
 #[derive(Debug, Clone)]
 pub struct Masker {
     columns: HashSet<String>,
@@ -119,4 +180,95 @@ impl Masker {
             value.to_string()
         }
     }
+
+    /// `lf` with the values of masked columns replaced by `***`, for showing data.
+    pub fn mask(
+        &self,
+        mut lf: polars::prelude::LazyFrame,
+    ) -> MlPrepResult<polars::prelude::LazyFrame> {
+        use polars::prelude::{lit, Expr};
+        let schema = lf.collect_schema().map_err(MlPrepError::PolarsError)?;
+        let masked: Vec<Expr> = schema
+            .iter_names()
+            .filter(|name| self.is_masked(name))
+            .map(|name| lit("***").alias(name.clone()))
+            .collect();
+        Ok(if masked.is_empty() {
+            lf
+        } else {
+            lf.with_columns(masked)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(windows)]
+    #[test]
+    fn test_strip_verbatim() {
+        assert_eq!(
+            strip_verbatim(Path::new(r"\\?\C:\data\in.csv")),
+            Path::new(r"C:\data\in.csv")
+        );
+        assert_eq!(
+            strip_verbatim(Path::new(r"\\?\UNC\server\share\data")),
+            Path::new(r"\\server\share\data")
+        );
+        assert_eq!(strip_verbatim(Path::new(r"C:\data")), Path::new(r"C:\data"));
+    }
+
+    #[test]
+    fn test_cloud_allowed_paths() {
+        let context = SecurityContext::new(SecurityConfig {
+            allowed_paths: Some(vec![PathBuf::from("s3://lake/raw/")]),
+            mask_columns: None,
+        })
+        .unwrap();
+        assert!(context
+            .validate_path("s3://lake/raw/events.parquet")
+            .is_ok());
+        assert!(context.validate_path("s3://lake/raw").is_ok());
+        assert!(context
+            .validate_path("s3://lake/raw-archive/a.csv")
+            .is_err());
+        assert!(context
+            .validate_path("gs://lake/raw/events.parquet")
+            .is_err());
+        // Local paths are not allowed by an object store prefix
+        assert!(context.validate_path("raw/events.parquet").is_err());
+
+        let context = SecurityContext::new(SecurityConfig {
+            allowed_paths: Some(vec![PathBuf::from("kafka://broker:9092")]),
+            mask_columns: None,
+        })
+        .unwrap();
+        assert!(context.validate_path("kafka://broker:9092/orders").is_ok());
+        assert!(context.validate_path("kafka://other:9092/orders").is_err());
+
+        let context = SecurityContext::new(SecurityConfig {
+            allowed_paths: Some(vec![PathBuf::from("https://data.example.com/exports")]),
+            mask_columns: None,
+        })
+        .unwrap();
+        assert!(context
+            .validate_path("https://data.example.com/exports/day.csv")
+            .is_ok());
+        assert!(context
+            .validate_path("https://data.example.com/exports/../private/day.csv")
+            .is_err());
+        assert!(context
+            .validate_path("https://data.example.com.evil.io/exports/day.csv")
+            .is_err());
+        assert!(context
+            .validate_path("http://data.example.com/exports/day.csv")
+            .is_err());
+    }
+
+    #[test]
+    fn test_strip_verbatim_keeps_plain_paths() {
+        let path = Path::new("data/in.csv");
+        assert_eq!(strip_verbatim(path), path);
+    }
 }
@@ -0,0 +1,378 @@
+//! Long-running transform mode: apply a fitted feature state to record batches
+//! streamed over stdin/stdout or a Unix socket, or posted to an HTTP endpoint
+//! (`serve-http` feature), for online preprocessing with the exact state used offline.
+
+use crate::features::{transform_features, FeatureConfig, FeatureState};
+use anyhow::{anyhow, Result};
+use polars::prelude::*;
+use std::io::{BufRead, Cursor, Read, Write};
+
+/// Wire format of the record batches exchanged in a serve session.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ServeFormat {
+    /// One JSON value per line: an object is a single record, an array is a batch.
+    /// Each input line produces exactly one output line of the same shape; failures
+    /// are reported as `{"error": "..."}` and the session continues.
+    Ndjson,
+    /// Back-to-back Arrow IPC streams, one stream per batch. Each input stream is
+    /// answered with one transformed stream; a failing batch ends the session.
+    Ipc,
+}
+
+/// A feature config and its fitted state, ready to transform incoming batches.
+pub struct Transformer {
+    config: FeatureConfig,
+    state: FeatureState,
+    schema: Schema,
+}
+
+impl Transformer {
+    pub fn new(config: FeatureConfig, state: FeatureState) -> Self {
+        // Fitted columns are parsed with their fitted dtypes so a batch with only
+        // nulls (or integral floats) in a column still matches the state.
+        let schema = state.fitted_schema();
+        Self {
+            config,
+            state,
+            schema,
+        }
+    }
+
+    pub fn transform(&self, df: &DataFrame) -> Result<DataFrame> {
+        transform_features(df, &self.config, &self.state)
+    }
+
+    /// Transform a JSON array of rows (answered with an array) or newline-delimited
+    /// JSON objects (answered with NDJSON).
+    pub fn transform_json(&self, body: &[u8]) -> Result<Vec<u8>> {
+        let batch = body.trim_ascii_start().starts_with(b"[");
+        let format = || {
+            if batch {
+                JsonFormat::Json
+            } else {
+                JsonFormat::JsonLines
+            }
+        };
+        let df = JsonReader::new(Cursor::new(body))
+            .with_json_format(format())
+            .with_schema_overwrite(&self.schema)
+            .finish()?;
+        let mut out = self.transform(&df)?;
+        let mut buf = Vec::new();
+        JsonWriter::new(&mut buf)
+            .with_json_format(format())
+            .finish(&mut out)?;
+        if buf.last() != Some(&b'\n') {
+            buf.push(b'\n');
+        }
+        Ok(buf)
+    }
+
+    /// Transform one Arrow IPC stream read from `input` into a new IPC stream.
+    pub fn transform_ipc<R: Read>(&self, input: R) -> Result<Vec<u8>> {
+        let df = IpcStreamReader::new(input)
+            .finish()
+            .map_err(|e| anyhow!("Failed to read Arrow IPC batch: {}", e))?;
+        let mut out = self.transform(&df)?;
+        let mut buf = Vec::new();
+        IpcStreamWriter::new(&mut buf).finish(&mut out)?;
+        Ok(buf)
+    }
+}
+
+/// Serve one session: read batches from `input` until EOF and write each
+/// transformed batch to `output`, flushing after every batch.
+pub fn serve_session<R: BufRead, W: Write>(
+    transformer: &Transformer,
+    format: ServeFormat,
+    mut input: R,
+    mut output: W,
+) -> Result<()> {
+    match format {
+        ServeFormat::Ndjson => {
+            let mut line = String::new();
+            loop {
+                line.clear();
+                if input.read_line(&mut line)? == 0 {
+                    break;
+                }
+                let trimmed = line.trim();
+                if trimmed.is_empty() {
+                    continue;
+                }
+                match transformer.transform_json(trimmed.as_bytes()) {
+                    Ok(bytes) => output.write_all(&bytes)?,
+                    Err(e) => {
+                        let error = serde_json::json!({ "error": e.to_string() });
+                        writeln!(output, "{}", error)?;
+                    }
+                }
+                output.flush()?;
+            }
+        }
+        ServeFormat::Ipc => {
+            while !input.fill_buf()?.is_empty() {
+                let bytes = transformer.transform_ipc(&mut input)?;
+                output.write_all(&bytes)?;
+                output.flush()?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Accept connections on a Unix socket at `path`, serving each one on its own
+/// thread until the process is stopped.
+#[cfg(unix)]
+pub fn serve_unix_socket(
+    transformer: std::sync::Arc<Transformer>,
+    format: ServeFormat,
+    path: &std::path::Path,
+) -> Result<()> {
+    use std::os::unix::net::UnixListener;
+
+    if path.exists() {
+        std::fs::remove_file(path)?;
+    }
+    let listener = UnixListener::bind(path)
+        .map_err(|e| anyhow!("Failed to bind socket {}: {}", path.display(), e))?;
+    tracing::info!("Serving feature transforms on {}", path.display());
+    for stream in listener.incoming() {
+        let stream = stream?;
+        let transformer = transformer.clone();
+        std::thread::spawn(move || {
+            let result = stream
+                .try_clone()
+                .map_err(anyhow::Error::from)
+                .and_then(|w| {
+                    serve_session(&transformer, format, std::io::BufReader::new(stream), w)
+                });
+            if let Err(e) = result {
+                tracing::error!("Serve connection failed: {}", e);
+            }
+        });
+    }
+    Ok(())
+}
+
+/// Content type of Arrow IPC stream request and response bodies.
+pub const ARROW_STREAM_CONTENT_TYPE: &str = "application/vnd.apache.arrow.stream";
+
+/// Serve `POST /transform` and `GET /healthz` on `addr` with `workers` threads until
+/// the process is stopped.
+///
+/// `/transform` takes Arrow IPC (`Content-Type: application/vnd.apache.arrow.stream`)
+/// and answers in kind; any other body is read as JSON rows (see
+/// [`Transformer::transform_json`]). Bad batches are answered with `400` and
+/// `{"error": "..."}`.
+#[cfg(feature = "serve-http")]
+pub fn serve_http(
+    transformer: std::sync::Arc<Transformer>,
+    addr: &str,
+    workers: usize,
+) -> Result<()> {
+    let server = std::sync::Arc::new(
+        tiny_http::Server::http(addr).map_err(|e| anyhow!("Failed to bind {}: {}", addr, e))?,
+    );
+    tracing::info!(
+        "Serving feature transforms on http://{}",
+        server.server_addr()
+    );
+    let handles: Vec<_> = (0..workers.max(1))
+        .map(|_| {
+            let server = server.clone();
+            let transformer = transformer.clone();
+            std::thread::spawn(move || {
+                for request in server.incoming_requests() {
+                    if let Err(e) = handle_http(&transformer, request) {
+                        tracing::error!("Failed to answer request: {}", e);
+                    }
+                }
+            })
+        })
+        .collect();
+    for handle in handles {
+        let _ = handle.join();
+    }
+    Ok(())
+}
+
+#[cfg(feature = "serve-http")]
+fn handle_http(transformer: &Transformer, mut request: tiny_http::Request) -> Result<()> {
+    use tiny_http::{Header, Method, Response};
+
+    let header = |value: &str| Header::from_bytes("Content-Type", value).expect("valid header");
+    let json = |status: u16, body: serde_json::Value| {
+        Response::from_string(body.to_string())
+            .with_status_code(status)
+            .with_header(header("application/json"))
+    };
+
+    let path = request
+        .url()
+        .split('?')
+        .next()
+        .unwrap_or_default()
+        .to_string();
+    let response = match (request.method(), path.as_str()) {
+        (Method::Get, "/healthz") => json(200, serde_json::json!({ "status": "ok" })),
+        (Method::Post, "/transform") => {
+            let arrow = request.headers().iter().any(|h| {
+                h.field.equiv("Content-Type")
+                    && h.value.as_str().starts_with(ARROW_STREAM_CONTENT_TYPE)
+            });
+            let mut body = Vec::new();
+            request.as_reader().read_to_end(&mut body)?;
+            let result = if arrow {
+                transformer.transform_ipc(body.as_slice())
+            } else {
+                transformer.transform_json(&body)
+            };
+            match result {
+                Ok(bytes) => {
+                    let content_type = if arrow {
+                        ARROW_STREAM_CONTENT_TYPE
+                    } else if body.trim_ascii_start().starts_with(b"[") {
+                        "application/json"
+                    } else {
+                        "application/x-ndjson"
+                    };
+                    Response::from_data(bytes).with_header(header(content_type))
+                }
+                Err(e) => json(400, serde_json::json!({ "error": e.to_string() })),
+            }
+        }
+        (_, "/healthz" | "/transform") => {
+            json(405, serde_json::json!({ "error": "method not allowed" }))
+        }
+        _ => json(404, serde_json::json!({ "error": "not found" })),
+    };
+    request.respond(response)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::features::{fit_features, FeatureSpec, FeatureTransform};
+
+    fn transformer() -> Transformer {
+        let df = df!(
+            "price" => [10.0, 20.0, 30.0],
+            "city" => ["a", "b", "a"],
+        )
+        .unwrap();
+        let config = FeatureConfig {
+            features: vec![
+                FeatureSpec::new("price", FeatureTransform::MinMaxScale),
+                FeatureSpec::new("city", FeatureTransform::CountEncode),
+            ],
+        };
+        let state = fit_features(&df, &config).unwrap();
+        Transformer::new(config, state)
+    }
+
+    #[test]
+    fn test_serve_ndjson_records_and_batches() {
+        let input = "{\"price\": 20, \"city\": \"a\"}\n\
+                     \n\
+                     [{\"price\": 30.0, \"city\": \"b\"}, {\"price\": null, \"city\": \"z\"}]\n\
+                     {\"city\": \"a\"}\n";
+        let mut out = Vec::new();
+        serve_session(
+            &transformer(),
+            ServeFormat::Ndjson,
+            input.as_bytes(),
+            &mut out,
+        )
+        .unwrap();
+        let lines: Vec<serde_json::Value> = String::from_utf8(out)
+            .unwrap()
+            .lines()
+            .map(|l| serde_json::from_str(l).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[0]["price"], 0.5);
+        assert!((lines[0]["city"].as_f64().unwrap() - 2.0 / 3.0).abs() < 1e-9);
+        assert_eq!(lines[1][0]["price"], 1.0);
+        assert_eq!(lines[1][1]["price"], serde_json::Value::Null);
+        assert_eq!(lines[1][1]["city"], 0.0);
+        assert!(lines[2]["error"].as_str().unwrap().contains("price"));
+    }
+
+    #[test]
+    fn test_serve_ipc_streams() {
+        let mut input = Vec::new();
+        for price in [10.0, 30.0] {
+            let mut df = df!("price" => [price], "city" => ["b"]).unwrap();
+            IpcStreamWriter::new(&mut input).finish(&mut df).unwrap();
+        }
+        let mut out = Vec::new();
+        serve_session(&transformer(), ServeFormat::Ipc, input.as_slice(), &mut out).unwrap();
+
+        let mut reader = out.as_slice();
+        let mut prices = Vec::new();
+        while !reader.is_empty() {
+            let df = IpcStreamReader::new(&mut reader).finish().unwrap();
+            prices.push(df.column("price").unwrap().f64().unwrap().get(0).unwrap());
+        }
+        assert_eq!(prices, vec![0.0, 1.0]);
+    }
+
+    #[cfg(feature = "serve-http")]
+    #[test]
+    fn test_serve_http_transform_and_healthz() {
+        let addr = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .to_string();
+        let transformer = std::sync::Arc::new(transformer());
+        {
+            let addr = addr.clone();
+            std::thread::spawn(move || serve_http(transformer, &addr, 1));
+        }
+        let base = format!("http://{}", addr);
+        let mut health = None;
+        for _ in 0..50 {
+            if let Ok(resp) = ureq::get(&format!("{}/healthz", base)).call() {
+                health = Some(resp.into_json::<serde_json::Value>().unwrap());
+                break;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(20));
+        }
+        assert_eq!(health.unwrap()["status"], "ok");
+
+        let rows: serde_json::Value = ureq::post(&format!("{}/transform", base))
+            .send_string(r#"[{"price": 20, "city": "a"}, {"price": 30, "city": "b"}]"#)
+            .unwrap()
+            .into_json()
+            .unwrap();
+        assert_eq!(rows[0]["price"], 0.5);
+        assert_eq!(rows[1]["price"], 1.0);
+
+        let mut body = Vec::new();
+        let mut df = df!("price" => [10.0], "city" => ["a"]).unwrap();
+        IpcStreamWriter::new(&mut body).finish(&mut df).unwrap();
+        let resp = ureq::post(&format!("{}/transform", base))
+            .set("Content-Type", ARROW_STREAM_CONTENT_TYPE)
+            .send_bytes(&body)
+            .unwrap();
+        assert_eq!(resp.content_type(), ARROW_STREAM_CONTENT_TYPE);
+        let mut bytes = Vec::new();
+        resp.into_reader().read_to_end(&mut bytes).unwrap();
+        let out = IpcStreamReader::new(Cursor::new(bytes)).finish().unwrap();
+        assert_eq!(
+            out.column("price").unwrap().f64().unwrap().get(0),
+            Some(0.0)
+        );
+
+        match ureq::post(&format!("{}/transform", base)).send_string(r#"[{"city": "a"}]"#) {
+            Err(ureq::Error::Status(400, resp)) => {
+                let body: serde_json::Value = resp.into_json().unwrap();
+                assert!(body["error"].as_str().unwrap().contains("price"));
+            }
+            other => panic!("expected 400, got {:?}", other.map(|r| r.status())),
+        }
+    }
+}
@@ -0,0 +1,198 @@
+//! Output snapshots for `mlprep test --snapshot`: a pipeline runs on a small fixture
+//! input, and its output's schema, row count and content hash are stored in a snapshot
+//! file. Later runs fail when the output drifts from the stored snapshot.
+
+use crate::errors::{MlPrepError, MlPrepResult};
+use crate::observability::SchemaField;
+use polars::prelude::*;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// Canonical description of a pipeline output.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub rows: usize,
+    pub schema: Vec<SchemaField>,
+    /// SHA256 of the rendered rows, sorted so that row order does not matter
+    pub hash: String,
+}
+
+impl Snapshot {
+    pub fn of(df: &DataFrame) -> Self {
+        let schema = df
+            .schema()
+            .iter()
+            .map(|(name, dtype)| SchemaField {
+                name: name.to_string(),
+                dtype: format!("{:?}", dtype),
+            })
+            .collect();
+        let mut rows: Vec<String> = (0..df.height())
+            .filter_map(|i| df.get(i))
+            .map(|row| format!("{:?}", row))
+            .collect();
+        rows.sort_unstable();
+        let mut hasher = Sha256::new();
+        for row in &rows {
+            hasher.update(row.as_bytes());
+            hasher.update(b"\n");
+        }
+        Self {
+            rows: df.height(),
+            schema,
+            hash: format!("{:x}", hasher.finalize()),
+        }
+    }
+
+    pub fn load(path: &Path) -> MlPrepResult<Self> {
+        let file = std::fs::File::open(path)?;
+        serde_json::from_reader(std::io::BufReader::new(file)).map_err(|e| {
+            MlPrepError::TransformError(format!("{} is not a snapshot file: {}", path.display(), e))
+        })
+    }
+
+    pub fn save(&self, path: &Path) -> MlPrepResult<()> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let json = serde_json::to_string_pretty(self)
+            .map_err(|e| MlPrepError::TransformError(e.to_string()))?;
+        std::fs::write(path, json + "\n")?;
+        Ok(())
+    }
+
+    /// Differences from `expected`, one per line; empty when the outputs match.
+    pub fn drift(&self, expected: &Snapshot) -> Vec<String> {
+        let mut drift = Vec::new();
+        if self.rows != expected.rows {
+            drift.push(format!("rows: {} -> {}", expected.rows, self.rows));
+        }
+        for field in &expected.schema {
+            match self.schema.iter().find(|f| f.name == field.name) {
+                None => drift.push(format!("- {}: {}", field.name, field.dtype)),
+                Some(f) if f.dtype != field.dtype => {
+                    drift.push(format!("~ {}: {} -> {}", field.name, field.dtype, f.dtype))
+                }
+                Some(_) => {}
+            }
+        }
+        for field in &self.schema {
+            if !expected.schema.iter().any(|f| f.name == field.name) {
+                drift.push(format!("+ {}: {}", field.name, field.dtype));
+            }
+        }
+        let names = |schema: &[SchemaField]| -> Vec<String> {
+            schema.iter().map(|f| f.name.clone()).collect()
+        };
+        if drift.is_empty() && names(&self.schema) != names(&expected.schema) {
+            drift.push(format!(
+                "column order: [{}] -> [{}]",
+                names(&expected.schema).join(", "),
+                names(&self.schema).join(", ")
+            ));
+        }
+        if self.hash != expected.hash {
+            drift.push(format!(
+                "content: {} -> {}",
+                &expected.hash[..12.min(expected.hash.len())],
+                &self.hash[..12]
+            ));
+        }
+        drift
+    }
+}
+
+/// Where the snapshot of `pipeline` is stored in `dir`: `<pipeline file stem>.snap.json`.
+pub fn snapshot_path(dir: &Path, pipeline: &Path) -> PathBuf {
+    let stem = pipeline
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "pipeline".to_string());
+    dir.join(format!("{}.snap.json", stem))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SnapshotStatus {
+    /// No snapshot existed; one was written
+    Created,
+    /// The output matched the stored snapshot
+    Matched,
+    /// The stored snapshot was overwritten (`--update`)
+    Updated,
+}
+
+impl fmt::Display for SnapshotStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            SnapshotStatus::Created => "created",
+            SnapshotStatus::Matched => "matches",
+            SnapshotStatus::Updated => "updated",
+        })
+    }
+}
+
+/// Compare `output` against the snapshot at `path`, writing it when it does not exist
+/// yet or when `update` is set. Drift fails with [`MlPrepError::TestFailure`].
+pub fn check(output: &DataFrame, path: &Path, update: bool) -> MlPrepResult<SnapshotStatus> {
+    let actual = Snapshot::of(output);
+    if !path.exists() {
+        actual.save(path)?;
+        return Ok(SnapshotStatus::Created);
+    }
+    if update {
+        actual.save(path)?;
+        return Ok(SnapshotStatus::Updated);
+    }
+    let drift = actual.drift(&Snapshot::load(path)?);
+    if drift.is_empty() {
+        Ok(SnapshotStatus::Matched)
+    } else {
+        Err(MlPrepError::TestFailure(format!(
+            "output drifted from snapshot {}:\n  {}",
+            path.display(),
+            drift.join("\n  ")
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_snapshot_check() {
+        let dir = tempdir().unwrap();
+        let path = snapshot_path(dir.path(), Path::new("pipelines/orders.yaml"));
+        assert!(path.ends_with("orders.snap.json"));
+
+        let df = df!("a" => [1i64, 2], "b" => ["x", "y"]).unwrap();
+        assert_eq!(check(&df, &path, false).unwrap(), SnapshotStatus::Created);
+        assert_eq!(check(&df, &path, false).unwrap(), SnapshotStatus::Matched);
+
+        // Row order does not matter
+        let reordered = df!("a" => [2i64, 1], "b" => ["y", "x"]).unwrap();
+        assert_eq!(
+            check(&reordered, &path, false).unwrap(),
+            SnapshotStatus::Matched
+        );
+
+        let drifted = df!("a" => [1.0f64, 2.0], "c" => ["x", "z"]).unwrap();
+        let err = check(&drifted, &path, false).unwrap_err().to_string();
+        assert!(err.contains("~ a: Int64 -> Float64"), "{}", err);
+        assert!(err.contains("- b: String"), "{}", err);
+        assert!(err.contains("+ c: String"), "{}", err);
+        assert!(err.contains("content:"), "{}", err);
+
+        assert_eq!(
+            check(&drifted, &path, true).unwrap(),
+            SnapshotStatus::Updated
+        );
+        assert_eq!(
+            check(&drifted, &path, false).unwrap(),
+            SnapshotStatus::Matched
+        );
+    }
+}
@@ -0,0 +1,203 @@
+//! SQL filter conditions: binding `:name` parameters and checking that a condition is a
+//! single expression, so a templated pipeline cannot be turned into a different query by
+//! the values passed to it.
+
+use crate::dsl::ScalarValue;
+use crate::errors::{MlPrepError, MlPrepResult};
+use serde::de::Error;
+use sqlparser::dialect::GenericDialect;
+use sqlparser::keywords::Keyword;
+use sqlparser::parser::Parser;
+use sqlparser::tokenizer::{Location, Token, Tokenizer};
+use std::collections::BTreeMap;
+
+/// Prefix of the environment variables setting parameters: `MLPREP_PARAM_THRESHOLD`
+/// sets `:threshold`.
+pub const ENV_PREFIX: &str = "MLPREP_PARAM_";
+
+/// Parameters set by `MLPREP_PARAM_<NAME>` environment variables, named in lowercase.
+pub fn env_params() -> BTreeMap<String, ScalarValue> {
+    std::env::vars()
+        .filter_map(|(key, value)| {
+            let name = key.strip_prefix(ENV_PREFIX)?.to_lowercase();
+            Some((name, param_value(&value)))
+        })
+        .collect()
+}
+
+/// Parse a `--param name=value` argument.
+pub fn parse_param(arg: &str) -> Result<(String, ScalarValue), String> {
+    let (name, value) = arg
+        .split_once('=')
+        .ok_or_else(|| format!("expected NAME=VALUE, got '{}'", arg))?;
+    if name.is_empty() {
+        return Err(format!("missing parameter name in '{}'", arg));
+    }
+    Ok((name.to_string(), param_value(value)))
+}
+
+/// A parameter value given as text, typed as the YAML scalar it reads as: `100` is an
+/// integer and `true` a boolean. Anything else is a string.
+fn param_value(text: &str) -> ScalarValue {
+    serde_yaml::from_str(text).unwrap_or_else(|_| ScalarValue::String(text.to_string()))
+}
+
+/// `condition` with each `:name` placeholder replaced by the SQL literal of
+/// `params[name]`. Fails unless the result is one expression: no `;`, no trailing
+/// clause and no subquery.
+pub fn bind(condition: &str, params: &BTreeMap<String, ScalarValue>) -> MlPrepResult<String> {
+    let invalid = |reason: String| {
+        MlPrepError::ConfigError(
+            serde_yaml::Error::custom(format!(
+                "Invalid filter condition '{}': {}",
+                condition, reason
+            )),
+            None,
+        )
+    };
+    let dialect = GenericDialect {};
+    let tokens = Tokenizer::new(&dialect, condition)
+        .tokenize_with_location()
+        .map_err(|e| invalid(e.to_string()))?;
+
+    let mut bound = String::with_capacity(condition.len());
+    let mut copied = 0;
+    for (i, token) in tokens.iter().enumerate() {
+        match &token.token {
+            Token::SemiColon => return Err(invalid("';' is not allowed".to_string())),
+            Token::Word(word)
+                if word.quote_style.is_none()
+                    && matches!(
+                        word.keyword,
+                        Keyword::SELECT | Keyword::WITH | Keyword::VALUES
+                    ) =>
+            {
+                return Err(invalid("subqueries are not allowed".to_string()));
+            }
+            Token::Colon => {
+                let Some(Token::Word(word)) = tokens.get(i + 1).map(|t| &t.token) else {
+                    continue;
+                };
+                if word.quote_style.is_some() {
+                    continue;
+                }
+                let value = params.get(&word.value).ok_or_else(|| {
+                    invalid(format!(
+                        "parameter ':{}' is not set; pass --param {}=<value> or set {}{}",
+                        word.value,
+                        word.value,
+                        ENV_PREFIX,
+                        word.value.to_uppercase()
+                    ))
+                })?;
+                let start = byte_offset(condition, token.span.start);
+                bound.push_str(&condition[copied..start]);
+                bound.push_str(&sql_literal(value).map_err(invalid)?);
+                copied = start + 1 + word.value.len();
+            }
+            _ => {}
+        }
+    }
+    bound.push_str(&condition[copied..]);
+
+    let mut parser = Parser::new(&dialect)
+        .try_with_sql(&bound)
+        .map_err(|e| invalid(e.to_string()))?;
+    parser.parse_expr().map_err(|e| invalid(e.to_string()))?;
+    let rest = parser.peek_token().token;
+    if rest != Token::EOF {
+        return Err(invalid(format!(
+            "expected a single expression, found '{}' after it",
+            rest
+        )));
+    }
+    Ok(bound)
+}
+
+/// `value` as a SQL literal.
+fn sql_literal(value: &ScalarValue) -> Result<String, String> {
+    Ok(match value {
+        ScalarValue::Null => "NULL".to_string(),
+        ScalarValue::Bool(b) => b.to_string().to_uppercase(),
+        ScalarValue::Int(i) if *i < 0 => format!("({})", i),
+        ScalarValue::Int(i) => i.to_string(),
+        ScalarValue::Float(f) if !f.is_finite() => {
+            return Err(format!("parameter value {} is not a finite number", f));
+        }
+        ScalarValue::Float(f) if *f < 0.0 => format!("({:?})", f),
+        ScalarValue::Float(f) => format!("{:?}", f),
+        ScalarValue::Date { date } => format!("CAST('{}' AS DATE)", date),
+        ScalarValue::String(s) => format!("'{}'", s.replace('\'', "''")),
+    })
+}
+
+/// Byte offset in `text` of a 1-based line and column, counted in characters.
+fn byte_offset(text: &str, location: Location) -> usize {
+    let line_start: usize = text
+        .split_inclusive('\n')
+        .take(location.line.saturating_sub(1) as usize)
+        .map(str::len)
+        .sum();
+    let column: usize = text[line_start..]
+        .chars()
+        .take(location.column.saturating_sub(1) as usize)
+        .map(char::len_utf8)
+        .sum();
+    line_start + column
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bind() {
+        let params: BTreeMap<String, ScalarValue> = [
+            ("threshold".to_string(), ScalarValue::Int(100)),
+            ("low".to_string(), ScalarValue::Float(-0.5)),
+            ("city".to_string(), "O'Hare".into()),
+        ]
+        .into();
+        assert_eq!(
+            bind("amount > :threshold AND score >= :low", &params).unwrap(),
+            "amount > 100 AND score >= (-0.5)"
+        );
+        // Quotes in a value stay inside the string literal
+        assert_eq!(
+            bind("city = :city OR note = 'it''s :city'", &params).unwrap(),
+            "city = 'O''Hare' OR note = 'it''s :city'"
+        );
+        assert_eq!(
+            bind("name = 'Zoë'\n  AND amount > :threshold", &params).unwrap(),
+            "name = 'Zoë'\n  AND amount > 100"
+        );
+        assert_eq!(bind("a::int > 1", &params).unwrap(), "a::int > 1");
+
+        for (condition, reason) in [
+            ("amount > :missing", "parameter ':missing' is not set"),
+            ("amount > 0; DROP TABLE df", "';' is not allowed"),
+            (
+                "amount > 0 UNION ALL SELECT 1",
+                "subqueries are not allowed",
+            ),
+            ("id IN (SELECT id FROM other)", "subqueries are not allowed"),
+            ("amount > 0 ORDER BY amount", "expected a single expression"),
+        ] {
+            let err = bind(condition, &params).unwrap_err().to_string();
+            assert!(err.contains(reason), "{}: {}", condition, err);
+        }
+    }
+
+    #[test]
+    fn test_parse_param() {
+        assert_eq!(
+            parse_param("threshold=100").unwrap(),
+            ("threshold".to_string(), ScalarValue::Int(100))
+        );
+        assert_eq!(
+            parse_param("city=New York").unwrap(),
+            ("city".to_string(), "New York".into())
+        );
+        assert!(parse_param("threshold").is_err());
+    }
+}
@@ -0,0 +1,216 @@
+//! `sql` step queries: checking that a query reads only the current frame, `df`. Polars
+//! SQL can read files with table functions such as `read_csv('/path')`, which would go
+//! around `--allowed-paths`, so every table a query names must be `df` or one of its own
+//! common table expressions.
+
+use crate::errors::{MlPrepError, MlPrepResult};
+use serde::de::Error;
+use sqlparser::ast::{
+    Expr, ObjectName, Query, SelectItem, SetExpr, Statement, TableFactor, TableWithJoins,
+};
+use sqlparser::dialect::GenericDialect;
+use sqlparser::keywords::Keyword;
+use sqlparser::parser::Parser;
+use sqlparser::tokenizer::{Token, Tokenizer};
+
+/// Name the current frame is registered under.
+pub const FRAME: &str = "df";
+
+/// Fail unless `query` is a single SELECT whose tables are all `df` or CTEs defined in
+/// it. Subqueries are allowed in FROM, and as `IN`, `EXISTS` or scalar subqueries in
+/// WHERE, HAVING and the select list.
+pub fn check(query: &str) -> MlPrepResult<()> {
+    let invalid = |reason: String| {
+        MlPrepError::ConfigError(
+            serde_yaml::Error::custom(format!("Invalid sql query: {}", reason)),
+            None,
+        )
+    };
+    let dialect = GenericDialect {};
+    let statements = Parser::parse_sql(&dialect, query).map_err(|e| invalid(e.to_string()))?;
+    let [Statement::Query(parsed)] = statements.as_slice() else {
+        return Err(invalid("expected a single SELECT statement".to_string()));
+    };
+    let mut scope = Scope::default();
+    scope.query(parsed).map_err(invalid)?;
+
+    // Every SELECT in the text must have been reached above; one that was not sits in an
+    // expression this check does not walk, so its tables were never looked at.
+    let selects = Tokenizer::new(&dialect, query)
+        .tokenize()
+        .map_err(|e| invalid(e.to_string()))?
+        .iter()
+        .filter(|token| matches!(token, Token::Word(w) if w.keyword == Keyword::SELECT && w.quote_style.is_none()))
+        .count();
+    if selects > scope.selects {
+        return Err(invalid(
+            "subqueries are only supported in FROM, or as IN, EXISTS or scalar subqueries"
+                .to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// The CTE names visible at the current point of the walk, and the SELECTs checked so far.
+#[derive(Default)]
+struct Scope {
+    ctes: Vec<String>,
+    selects: usize,
+}
+
+impl Scope {
+    fn query(&mut self, query: &Query) -> Result<(), String> {
+        let outer = self.ctes.len();
+        if let Some(with) = &query.with {
+            for cte in &with.cte_tables {
+                // Pushed first so a recursive CTE can name itself.
+                self.ctes.push(cte.alias.name.value.clone());
+                self.query(&cte.query)?;
+            }
+        }
+        let result = self.set_expr(&query.body);
+        self.ctes.truncate(outer);
+        result
+    }
+
+    fn set_expr(&mut self, body: &SetExpr) -> Result<(), String> {
+        match body {
+            SetExpr::Select(select) => {
+                self.selects += 1;
+                for from in &select.from {
+                    self.table_with_joins(from)?;
+                }
+                for item in &select.projection {
+                    match item {
+                        SelectItem::UnnamedExpr(e) | SelectItem::ExprWithAlias { expr: e, .. } => {
+                            self.expr(e)?
+                        }
+                        _ => {}
+                    }
+                }
+                for e in [&select.selection, &select.having].into_iter().flatten() {
+                    self.expr(e)?;
+                }
+                Ok(())
+            }
+            SetExpr::Query(query) => self.query(query),
+            SetExpr::SetOperation { left, right, .. } => {
+                self.set_expr(left)?;
+                self.set_expr(right)
+            }
+            SetExpr::Values(_) => Ok(()),
+            SetExpr::Table(table) => match &table.table_name {
+                Some(name) if table.schema_name.is_none() && self.is_table(name) => Ok(()),
+                _ => Err(format!("table '{}' is not allowed; use {}", table, FRAME)),
+            },
+            _ => Err("expected a SELECT".to_string()),
+        }
+    }
+
+    fn table_with_joins(&mut self, from: &TableWithJoins) -> Result<(), String> {
+        self.table_factor(&from.relation)?;
+        for join in &from.joins {
+            self.table_factor(&join.relation)?;
+        }
+        Ok(())
+    }
+
+    fn table_factor(&mut self, factor: &TableFactor) -> Result<(), String> {
+        match factor {
+            TableFactor::Table {
+                name, args: None, ..
+            } => {
+                if self.is_name(name) {
+                    Ok(())
+                } else {
+                    Err(format!("table '{}' is not allowed; use {}", name, FRAME))
+                }
+            }
+            TableFactor::Table { name, .. } => {
+                Err(format!("table function '{}' is not allowed", name))
+            }
+            TableFactor::Derived { subquery, .. } => self.query(subquery),
+            TableFactor::NestedJoin {
+                table_with_joins, ..
+            } => self.table_with_joins(table_with_joins),
+            other => Err(format!("'{}' is not allowed as a table", other)),
+        }
+    }
+
+    fn expr(&mut self, expr: &Expr) -> Result<(), String> {
+        match expr {
+            Expr::Subquery(query)
+            | Expr::Exists {
+                subquery: query, ..
+            } => self.query(query),
+            Expr::InSubquery { expr, subquery, .. } => {
+                self.expr(expr)?;
+                self.query(subquery)
+            }
+            Expr::BinaryOp { left, right, .. } => {
+                self.expr(left)?;
+                self.expr(right)
+            }
+            Expr::UnaryOp { expr, .. } | Expr::Nested(expr) => self.expr(expr),
+            _ => Ok(()),
+        }
+    }
+
+    fn is_name(&self, name: &ObjectName) -> bool {
+        match name.0.as_slice() {
+            [ident] => self.is_table(&ident.value),
+            _ => false,
+        }
+    }
+
+    fn is_table(&self, name: &str) -> bool {
+        name == FRAME || self.ctes.iter().any(|cte| cte == name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check() {
+        for ok in [
+            "SELECT g, SUM(v) AS total FROM df GROUP BY g ORDER BY g",
+            "SELECT a.x FROM df a JOIN df b ON a.x = b.x",
+            "WITH t AS (SELECT * FROM df WHERE x > 1) SELECT * FROM t UNION ALL SELECT * FROM df",
+            "SELECT * FROM (SELECT x FROM df) s WHERE x IN (SELECT x FROM df WHERE x > 0)",
+            "SELECT 'select' AS word FROM df",
+        ] {
+            assert!(check(ok).is_ok(), "{}", ok);
+        }
+        for (bad, reason) in [
+            ("SELECT * FROM read_csv('/etc/passwd')", "table function"),
+            (
+                "SELECT * FROM df JOIN read_parquet('x.parquet') p ON true",
+                "table function",
+            ),
+            ("SELECT * FROM other", "table 'other'"),
+            (
+                "SELECT * FROM (SELECT * FROM read_ipc('x'))",
+                "table function",
+            ),
+            (
+                "SELECT * FROM df WHERE x IN (SELECT x FROM read_json('x'))",
+                "table function",
+            ),
+            (
+                "SELECT * FROM df WHERE x = 1 OR x = (SELECT 1 FROM read_csv('x'))",
+                "table function",
+            ),
+            (
+                "SELECT COALESCE((SELECT 1 FROM read_csv('x')), 0) FROM df",
+                "subqueries",
+            ),
+            ("SELECT * FROM df; SELECT * FROM df", "single SELECT"),
+            ("DROP TABLE df", "single SELECT"),
+        ] {
+            let err = check(bad).unwrap_err().to_string();
+            assert!(err.contains(reason), "{}: {}", bad, err);
+        }
+    }
+}
@@ -0,0 +1,293 @@
+//! `mlprep synth`: fake data shaped like a schema contract or a checks file, for demos
+//! and tests that must not touch real data. Each column gets its dtype, `min`/`max` or
+//! `range`, `enum` values and null rate; the same seed always yields the same data.
+
+use crate::bench::{Rng, FIRST_DAY};
+use crate::errors::{MlPrepError, MlPrepResult};
+use polars::prelude::*;
+use serde::de::Error;
+use serde::Deserialize;
+use std::path::Path;
+use tracing::warn;
+
+/// Null rate of columns declared `nullable: true` without a `null_rate`.
+const DEFAULT_NULL_RATE: f64 = 0.05;
+
+/// Distinct strings generated for a `String` column without `enum` values.
+const STRING_CARDINALITY: u64 = 100;
+
+/// A column as described by a contract (`dtype`, `nullable`, `min`, `max`) or a checks
+/// file (`not_null`, `unique`, `range`, `regex`, `enum`). Other keys are ignored.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct SynthColumn {
+    pub name: String,
+    /// Polars dtype name; inferred from `enum` and the bounds when omitted
+    #[serde(default)]
+    pub dtype: Option<String>,
+    #[serde(default)]
+    pub nullable: Option<bool>,
+    #[serde(default)]
+    pub not_null: bool,
+    /// Fraction of nulls; defaults to 5% for `nullable: true` columns, else none
+    #[serde(default)]
+    pub null_rate: Option<f64>,
+    #[serde(default)]
+    pub unique: bool,
+    #[serde(default)]
+    pub min: Option<f64>,
+    #[serde(default)]
+    pub max: Option<f64>,
+    #[serde(default)]
+    pub range: Option<(f64, f64)>,
+    #[serde(default)]
+    pub regex: Option<String>,
+    #[serde(default, rename = "enum")]
+    pub allowed_values: Option<Vec<String>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct SynthSchema {
+    pub columns: Vec<SynthColumn>,
+}
+
+impl SynthSchema {
+    pub fn load<P: AsRef<Path>>(path: P) -> MlPrepResult<Self> {
+        let file = std::fs::File::open(path.as_ref())?;
+        serde_yaml::from_reader(std::io::BufReader::new(file))
+            .map_err(|e| MlPrepError::ConfigError(e, None))
+    }
+}
+
+impl SynthColumn {
+    fn bounds(&self) -> (Option<f64>, Option<f64>) {
+        match self.range {
+            Some((min, max)) => (Some(min), Some(max)),
+            None => (self.min, self.max),
+        }
+    }
+
+    fn dtype(&self) -> MlPrepResult<DataType> {
+        if let Some(name) = &self.dtype {
+            return crate::compute::parse_dtype(name);
+        }
+        if self.allowed_values.is_some() {
+            return Ok(DataType::String);
+        }
+        Ok(match self.bounds() {
+            (None, None) => DataType::String,
+            (min, max) if [min, max].iter().flatten().all(|b| b.fract() == 0.0) => DataType::Int64,
+            _ => DataType::Float64,
+        })
+    }
+
+    fn null_rate(&self) -> f64 {
+        if self.not_null || self.nullable == Some(false) {
+            return 0.0;
+        }
+        self.null_rate.unwrap_or(if self.nullable == Some(true) {
+            DEFAULT_NULL_RATE
+        } else {
+            0.0
+        })
+    }
+}
+
+/// Values of one column before nulls are applied, in the dtype they are generated in.
+fn values(
+    column: &SynthColumn,
+    dtype: &DataType,
+    rows: usize,
+    rng: &mut Rng,
+) -> MlPrepResult<Series> {
+    let name = column.name.as_str().into();
+    let invalid = |msg: String| MlPrepError::ConfigError(serde_yaml::Error::custom(msg), None);
+    let (min, max) = column.bounds();
+
+    if let Some(allowed) = &column.allowed_values {
+        if allowed.is_empty() {
+            return Err(invalid(format!(
+                "Column '{}' has an empty enum",
+                column.name
+            )));
+        }
+        if column.unique && rows > allowed.len() {
+            return Err(invalid(format!(
+                "Column '{}' is unique but has only {} enum values for {} rows",
+                column.name,
+                allowed.len(),
+                rows
+            )));
+        }
+        let picked: Vec<&str> = if column.unique {
+            allowed.iter().take(rows).map(String::as_str).collect()
+        } else {
+            (0..rows)
+                .map(|_| allowed[rng.below(allowed.len() as u64) as usize].as_str())
+                .collect()
+        };
+        return Ok(Series::new(name, picked));
+    }
+
+    Ok(if dtype.is_integer() {
+        let low = min.unwrap_or(0.0).ceil() as i64;
+        let high = max.unwrap_or(low as f64 + 1000.0).floor() as i64;
+        if high < low {
+            return Err(invalid(format!(
+                "Column '{}' has an empty range",
+                column.name
+            )));
+        }
+        let span = (high - low) as u64 + 1;
+        if column.unique && rows as u64 > span {
+            return Err(invalid(format!(
+                "Column '{}' is unique but its range holds only {} values for {} rows",
+                column.name, span, rows
+            )));
+        }
+        let values: Vec<i64> = if column.unique {
+            (0..rows as i64).map(|i| low + i).collect()
+        } else {
+            (0..rows).map(|_| low + rng.below(span) as i64).collect()
+        };
+        Series::new(name, values)
+    } else if dtype.is_float() {
+        let low = min.unwrap_or(0.0);
+        let high = max.unwrap_or(low + 1000.0);
+        Series::new(
+            name,
+            (0..rows)
+                .map(|_| low + rng.unit() * (high - low))
+                .collect::<Vec<_>>(),
+        )
+    } else {
+        match dtype {
+            DataType::Boolean => Series::new(
+                name,
+                (0..rows).map(|_| rng.next() & 1 == 1).collect::<Vec<_>>(),
+            ),
+            DataType::Date => Series::new(
+                name,
+                (0..rows)
+                    .map(|_| FIRST_DAY + rng.below(5 * 365) as i32)
+                    .collect::<Vec<_>>(),
+            ),
+            DataType::Datetime(_, _) => Series::new(
+                name,
+                (0..rows)
+                    .map(|_| {
+                        (FIRST_DAY as i64 * 86_400 + rng.below(5 * 365 * 86_400) as i64) * 1_000_000
+                    })
+                    .collect::<Vec<_>>(),
+            ),
+            DataType::String | DataType::Categorical(_, _) => {
+                if column.regex.is_some() {
+                    warn!(
+                        "Column '{}': regex is not used when generating values",
+                        column.name
+                    );
+                }
+                let values: Vec<String> = if column.unique {
+                    (0..rows)
+                        .map(|i| format!("{}_{}", column.name, i))
+                        .collect()
+                } else {
+                    (0..rows)
+                        .map(|_| format!("{}_{}", column.name, rng.below(STRING_CARDINALITY)))
+                        .collect()
+                };
+                Series::new(name, values)
+            }
+            other => {
+                return Err(invalid(format!(
+                    "Cannot generate values for column '{}' of type {:?}",
+                    column.name, other
+                )))
+            }
+        }
+    })
+}
+
+/// Generate `rows` rows shaped like `schema`.
+pub fn synthesize(schema: &SynthSchema, rows: usize, seed: u64) -> MlPrepResult<DataFrame> {
+    let mut rng = Rng(seed);
+    let columns = schema
+        .columns
+        .iter()
+        .map(|column| {
+            let dtype = column.dtype()?;
+            let null_rate = column.null_rate();
+            let values = values(column, &dtype, rows, &mut rng)?;
+            let keep: BooleanChunked = (0..rows)
+                .map(|_| rng.unit() >= null_rate)
+                .collect::<BooleanChunked>();
+            let values = if null_rate > 0.0 {
+                values
+                    .zip_with(&keep, &Series::full_null("".into(), rows, values.dtype()))
+                    .map_err(MlPrepError::PolarsError)?
+            } else {
+                values
+            };
+            let values = values.cast(&dtype).map_err(MlPrepError::PolarsError)?;
+            Ok(values.with_name(column.name.as_str().into()).into())
+        })
+        .collect::<MlPrepResult<Vec<Column>>>()?;
+    DataFrame::new(columns).map_err(MlPrepError::PolarsError)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_synthesize_respects_schema() {
+        let schema: SynthSchema = serde_yaml::from_str(
+            r#"
+columns:
+  - name: id
+    dtype: Int64
+    nullable: false
+    unique: true
+    min: 100
+  - name: age
+    range: [18, 65]
+    not_null: true
+  - name: price
+    dtype: Float64
+    nullable: true
+    null_rate: 0.5
+    min: 1.5
+    max: 2.5
+  - name: country
+    enum: [jp, us]
+  - name: signup
+    dtype: Date
+"#,
+        )
+        .unwrap();
+        let df = synthesize(&schema, 200, 7).unwrap();
+        assert_eq!(df.height(), 200);
+        assert_eq!(
+            df.dtypes(),
+            vec![
+                DataType::Int64,
+                DataType::Int64,
+                DataType::Float64,
+                DataType::String,
+                DataType::Date
+            ]
+        );
+
+        let id = df.column("id").unwrap().i64().unwrap();
+        assert_eq!((id.min(), id.max()), (Some(100), Some(299)));
+        let age = df.column("age").unwrap().i64().unwrap();
+        assert!(age.min().unwrap() >= 18 && age.max().unwrap() <= 65);
+        assert_eq!(age.null_count(), 0);
+        let price = df.column("price").unwrap().f64().unwrap();
+        assert!(price.min().unwrap() >= 1.5 && price.max().unwrap() < 2.5);
+        assert!((50..150).contains(&price.null_count()));
+        let country = df.column("country").unwrap().str().unwrap();
+        assert!(country.into_iter().all(|c| matches!(c, Some("jp" | "us"))));
+
+        assert!(synthesize(&schema, 200, 7).unwrap().equals_missing(&df));
+    }
+}
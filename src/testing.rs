@@ -0,0 +1,222 @@
+//! Inline pipeline test cases (the `tests:` block) run by `mlprep test`: each case's
+//! `given` rows stand in for the first input, and the output must equal its `expect` rows.
+
+use crate::dsl::{Pipeline, TestCase};
+use crate::errors::{MlPrepError, MlPrepResult};
+use polars::prelude::*;
+use std::fmt;
+use std::io::Cursor;
+
+/// Mismatched rows listed per failing case.
+const MAX_REPORTED_ROWS: usize = 5;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CaseOutcome {
+    pub name: String,
+    /// Why the case failed; empty when it passed
+    pub failures: Vec<String>,
+}
+
+impl CaseOutcome {
+    pub fn passed(&self) -> bool {
+        self.failures.is_empty()
+    }
+}
+
+impl fmt::Display for CaseOutcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {}",
+            if self.passed() { "PASS" } else { "FAIL" },
+            self.name
+        )?;
+        for failure in &self.failures {
+            write!(f, "\n    {}", failure)?;
+        }
+        Ok(())
+    }
+}
+
+/// Build a frame from inline YAML rows; dtypes are inferred as for a JSON input.
+fn rows_frame(rows: &[serde_yaml::Mapping]) -> MlPrepResult<DataFrame> {
+    let json = serde_json::to_vec(rows).map_err(|e| {
+        MlPrepError::TransformError(format!("test rows are not plain records: {}", e))
+    })?;
+    JsonReader::new(Cursor::new(json))
+        .finish()
+        .map_err(MlPrepError::PolarsError)
+}
+
+fn render_rows(df: &DataFrame) -> Vec<String> {
+    let names = df.get_column_names();
+    (0..df.height())
+        .filter_map(|i| df.get(i))
+        .map(|row| {
+            let fields: Vec<String> = names
+                .iter()
+                .zip(row)
+                .map(|(name, value)| format!("{}: {}", name, value))
+                .collect();
+            format!("{{{}}}", fields.join(", "))
+        })
+        .collect()
+}
+
+/// Compare `actual` with `expected` on the expected columns, casting expected values
+/// to the output dtypes. Returns the differences.
+fn compare(actual: &DataFrame, expected: &DataFrame, ignore_order: bool) -> Vec<String> {
+    let mut failures = Vec::new();
+    let mut columns = Vec::with_capacity(expected.width());
+    let mut cast = Vec::with_capacity(expected.width());
+    for column in expected.get_columns() {
+        match actual.column(column.name()) {
+            Ok(output) => match column.cast(output.dtype()) {
+                Ok(values) => {
+                    columns.push(output.clone());
+                    cast.push(values);
+                }
+                Err(e) => failures.push(format!("column {}: {}", column.name(), e)),
+            },
+            Err(_) => failures.push(format!("missing column {}", column.name())),
+        }
+    }
+    if !failures.is_empty() {
+        return failures;
+    }
+    let (Ok(actual), Ok(expected)) = (DataFrame::new(columns), DataFrame::new(cast)) else {
+        return vec!["expected rows could not be aligned with the output".to_string()];
+    };
+    if actual.height() != expected.height() {
+        failures.push(format!(
+            "expected {} rows, got {}",
+            expected.height(),
+            actual.height()
+        ));
+    }
+    let mut actual = render_rows(&actual);
+    let mut expected = render_rows(&expected);
+    if ignore_order {
+        actual.sort_unstable();
+        expected.sort_unstable();
+        let mut missing = Vec::new();
+        for row in expected {
+            match actual.iter().position(|a| *a == row) {
+                Some(i) => {
+                    actual.remove(i);
+                }
+                None => missing.push(row),
+            }
+        }
+        failures.extend(
+            missing
+                .into_iter()
+                .map(|row| format!("- {}", row))
+                .chain(actual.into_iter().map(|row| format!("+ {}", row)))
+                .take(MAX_REPORTED_ROWS),
+        );
+    } else {
+        let missing = "(no row)".to_string();
+        failures.extend(
+            (0..expected.len().max(actual.len()))
+                .filter_map(|i| {
+                    let e = expected.get(i).unwrap_or(&missing);
+                    let a = actual.get(i).unwrap_or(&missing);
+                    (e != a).then(|| format!("row {}: expected {}, got {}", i, e, a))
+                })
+                .take(MAX_REPORTED_ROWS),
+        );
+    }
+    failures
+}
+
+/// Run one case through the pipeline's steps in memory.
+pub fn run_case(
+    pipeline: &Pipeline,
+    case: &TestCase,
+    security_config: crate::security::SecurityConfig,
+) -> MlPrepResult<CaseOutcome> {
+    if case.given.is_empty() {
+        return Err(MlPrepError::TransformError(format!(
+            "test case '{}' has no given rows",
+            case.name
+        )));
+    }
+    let given = rows_frame(&case.given)?;
+    let failures =
+        match crate::runner::execute_in_memory(given, pipeline.clone(), security_config, None) {
+            Err(e) => vec![format!("pipeline failed: {}", e)],
+            Ok(actual) if case.expect.is_empty() => match actual.height() {
+                0 => Vec::new(),
+                rows => vec![format!("expected 0 rows, got {}", rows)],
+            },
+            Ok(actual) => compare(&actual, &rows_frame(&case.expect)?, case.ignore_order),
+        };
+    Ok(CaseOutcome {
+        name: case.name.clone(),
+        failures,
+    })
+}
+
+/// Run every case of the pipeline's `tests:` block.
+pub fn run_cases(
+    pipeline: &Pipeline,
+    security_config: crate::security::SecurityConfig,
+) -> MlPrepResult<Vec<CaseOutcome>> {
+    pipeline
+        .tests
+        .iter()
+        .map(|case| run_case(pipeline, case, security_config.clone()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_run_cases() {
+        let pipeline = Pipeline::from_reader(
+            r#"
+steps:
+  - type: filter
+    condition: "amount > 0"
+  - type: cast
+    columns: {amount: Float64}
+tests:
+  - name: drops refunds
+    given:
+      - {id: 1, amount: 10, city: tokyo}
+      - {id: 2, amount: -5, city: osaka}
+      - {id: 3, amount: 7, city: ~}
+    expect:
+      - {id: 1, amount: 10}
+      - {id: 3, amount: 7}
+  - name: wrong order
+    ignore_order: true
+    given:
+      - {id: 1, amount: 1}
+      - {id: 2, amount: 2}
+    expect:
+      - {id: 2, amount: 2}
+      - {id: 1, amount: 1.5}
+  - name: everything filtered
+    given:
+      - {id: 1, amount: -1}
+    expect: []
+"#
+            .as_bytes(),
+        )
+        .unwrap();
+
+        let outcomes = run_cases(&pipeline, Default::default()).unwrap();
+        assert_eq!(outcomes.len(), 3);
+        assert!(outcomes[0].passed(), "{}", outcomes[0]);
+        assert!(outcomes[2].passed(), "{}", outcomes[2]);
+        assert_eq!(
+            outcomes[1].failures,
+            vec!["- {id: 1, amount: 1.5}", "+ {id: 1, amount: 1.0}"]
+        );
+        assert!(outcomes[1].to_string().starts_with("FAIL wrong order"));
+    }
+}
@@ -0,0 +1,80 @@
+//! User-defined functions referenced by `python_udf` steps.
+//!
+//! Pipelines only name a function; the callables are passed in by the host (the Python
+//! module) with each run and travel with that run's `RuntimeConfig`, so runs going at
+//! the same time never see each other's functions.
+
+use crate::errors::{MlPrepError, MlPrepResult};
+use polars::prelude::DataFrame;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+
+pub type UdfFn = Arc<dyn Fn(DataFrame) -> MlPrepResult<DataFrame> + Send + Sync>;
+
+/// The functions available to the `python_udf` steps of one run, by name.
+#[derive(Clone, Default)]
+pub struct Udfs(HashMap<String, UdfFn>);
+
+impl Udfs {
+    pub fn insert(&mut self, name: impl Into<String>, f: UdfFn) {
+        self.0.insert(name.into(), f);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn lookup(&self, name: &str) -> MlPrepResult<UdfFn> {
+        self.0.get(name).cloned().ok_or_else(|| {
+            MlPrepError::TransformError(format!(
+                "python_udf '{}' is not registered (python_udf steps are only available \
+                 when the pipeline is run from the Python module with udfs={{...}})",
+                name
+            ))
+        })
+    }
+}
+
+impl fmt::Debug for Udfs {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut names: Vec<_> = self.0.keys().collect();
+        names.sort();
+        f.debug_tuple("Udfs").field(&names).finish()
+    }
+}
+
+/// Equal when both hold the same callables under the same names.
+impl PartialEq for Udfs {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.len() == other.0.len()
+            && self
+                .0
+                .iter()
+                .all(|(name, f)| other.0.get(name).is_some_and(|g| Arc::ptr_eq(f, g)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_udfs_are_per_map() {
+        let mut first = Udfs::default();
+        first.insert("udf_test", Arc::new(Ok));
+        let mut second = Udfs::default();
+        second.insert("udf_test", Arc::new(|df: DataFrame| Ok(df.head(Some(0)))));
+        let df = polars::df!("x" => [1, 2]).unwrap();
+        assert_eq!(
+            first.lookup("udf_test").unwrap()(df.clone())
+                .unwrap()
+                .height(),
+            2
+        );
+        assert_eq!(second.lookup("udf_test").unwrap()(df).unwrap().height(), 0);
+        assert!(Udfs::default().lookup("udf_test").is_err());
+        assert_ne!(first, second);
+        assert_eq!(first.clone(), first);
+    }
+}
@@ -1,9 +1,14 @@
 //! Validation engine for data quality checks
 //!
-//! Implements NotNull, Unique, Range, Regex, and Enum checks with
-//! strict, warn, and quarantine execution modes.
-
-use crate::dsl::{CheckConfig, ColumnCheck, ValidationMode};
+//! Implements NotNull, Unique, Range, Regex, and Enum checks, plus conditional checks
+//! across columns and expected column statistics, with strict, warn, and quarantine
+//! execution modes.
+
+use crate::dsl::{
+    CheckConfig, ColumnCheck, ConditionalCheck, ScalarValue, Severity, StatExpectation, StatsCheck,
+    Validate, ValidationMode,
+};
+use crate::errors::{column_not_found, find_column};
 use anyhow::{anyhow, Result};
 use polars::prelude::*;
 
@@ -29,6 +34,10 @@ pub struct ValidationReport {
     pub results: Vec<ValidationResult>,
     pub total_violations: usize,
     pub passed: bool,
+    /// Rows the checks ran over
+    pub rows: usize,
+    /// How the rows were sampled, when the checks ran on a sample
+    pub sampling: Option<Sampling>,
 }
 
 impl ValidationReport {
@@ -37,6 +46,8 @@ impl ValidationReport {
             results: Vec::new(),
             total_violations: 0,
             passed: true,
+            rows: 0,
+            sampling: None,
         }
     }
 
@@ -55,16 +66,104 @@ impl Default for ValidationReport {
     }
 }
 
+/// A uniform random sample of the rows, drawn for a validate step with
+/// `sample_fraction` or `sample_rows`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Sampling {
+    /// The option the sample was drawn by, e.g. `sample_rows: 1000`
+    pub strategy: String,
+    /// Rows in the sample
+    pub rows: usize,
+    /// Rows sampled from
+    pub total: usize,
+    pub seed: u64,
+}
+
+impl std::fmt::Display for Sampling {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "random sample of {} of {} rows ({}, seed {})",
+            self.rows, self.total, self.strategy, self.seed
+        )
+    }
+}
+
+/// The rows of `lf` that `validate` checks: a sample drawn without replacement with
+/// `seed` when it sets `sample_fraction` or `sample_rows`, else `None`. The same seed
+/// and row count always pick the same rows.
+pub fn sample(
+    lf: LazyFrame,
+    validate: &Validate,
+    seed: u64,
+) -> Result<Option<(LazyFrame, Sampling)>> {
+    let strategy = match (validate.sample_fraction, validate.sample_rows) {
+        (None, None) => return Ok(None),
+        (Some(_), Some(_)) => {
+            return Err(anyhow!("Set sample_fraction or sample_rows, not both"));
+        }
+        (Some(fraction), None) if !(fraction > 0.0 && fraction <= 1.0) => {
+            return Err(anyhow!(
+                "sample_fraction must be in (0, 1], got {}",
+                fraction
+            ));
+        }
+        (Some(fraction), None) => format!("sample_fraction: {}", fraction),
+        (None, Some(rows)) => format!("sample_rows: {}", rows),
+    };
+    if validate.mode == ValidationMode::Quarantine {
+        return Err(anyhow!(
+            "Quarantine mode checks every row and cannot run on a sample"
+        ));
+    }
+
+    let counted = lf
+        .clone()
+        .select([len().cast(DataType::UInt64)])
+        .collect()
+        .map_err(|e| anyhow!("Failed to count rows to sample: {}", e))?;
+    let total = counted.get_columns()[0].u64()?.get(0).unwrap_or(0) as usize;
+    let rows = match (validate.sample_fraction, validate.sample_rows) {
+        (Some(fraction), _) => (fraction * total as f64).round() as usize,
+        (_, rows) => rows.unwrap_or(total).min(total),
+    };
+
+    // Floyd's algorithm: each subset of `rows` positions is equally likely.
+    let mut rng = crate::bench::Rng(seed);
+    let mut picked = std::collections::HashSet::with_capacity(rows);
+    for j in total - rows..total {
+        let t = rng.below(j as u64 + 1) as IdxSize;
+        if !picked.insert(t) {
+            picked.insert(j as IdxSize);
+        }
+    }
+    let mut positions: Vec<IdxSize> = picked.into_iter().collect();
+    positions.sort_unstable();
+
+    let sampled = lf
+        .with_row_index("__sample_row", None)
+        .filter(col("__sample_row").is_in(lit(Series::new("positions".into(), positions))))
+        .drop(["__sample_row"]);
+    let sampling = Sampling {
+        strategy,
+        rows,
+        total,
+        seed,
+    };
+    Ok(Some((sampled, sampling)))
+}
+
 fn check_label(check: &ColumnCheck) -> String {
     format!("{}:{}", check.name, check_label_suffix(check))
 }
 
-fn check_label_suffix(check: &ColumnCheck) -> &'static str {
+/// Short name of the check kind (`not_null`, `unique`, `range`, `regex` or `enum`).
+pub(crate) fn check_label_suffix(check: &ColumnCheck) -> &'static str {
     if check.not_null {
         "not_null"
     } else if check.unique {
         "unique"
-    } else if check.range.is_some() {
+    } else if check.bounds().is_some() {
         "range"
     } else if check.regex.is_some() {
         "regex"
@@ -75,60 +174,99 @@ fn check_label_suffix(check: &ColumnCheck) -> &'static str {
     }
 }
 
-/// Build a violation expression for a single column check.
-/// The expression evaluates to `true` for rows that violate the check.
-pub fn build_violation_expr(check: &ColumnCheck) -> Result<Expr> {
-    let mut parts: Vec<Expr> = Vec::new();
-
-    if check.not_null {
-        parts.push(col(&check.name).is_null());
-    }
-
-    if check.unique {
-        let dup_mask = col(&check.name)
-            .count()
-            .over([col(&check.name)])
-            .gt(lit(1u32));
-        parts.push(dup_mask);
-    }
+/// Whether `column` is one of `allowed`. Values compare as typed, except that a list of
+/// strings compares against the column as text.
+fn allowed_expr(column: Expr, allowed: &[ScalarValue]) -> Result<Expr> {
+    let allowed = crate::compute::scalar_series("allowed", allowed)?;
+    Ok(if allowed.dtype().is_string() {
+        column.cast(DataType::String).is_in(lit(allowed))
+    } else {
+        column.is_in(lit(allowed))
+    })
+}
 
-    if let Some((min, max)) = check.range {
-        let col_expr = col(&check.name).cast(DataType::Float64);
-        parts.push(col_expr.clone().lt(lit(min)).or(col_expr.gt(lit(max))));
-    }
+/// `[a, b, c]`, for messages.
+fn value_list(values: &[ScalarValue]) -> String {
+    let values: Vec<String> = values.iter().map(ToString::to_string).collect();
+    format!("[{}]", values.join(", "))
+}
 
-    if let Some(ref pattern) = check.regex {
-        // Validate regex upfront for early erroring
-        regex::Regex::new(pattern)?;
-        let regex_miss = col(&check.name)
-            .cast(DataType::String)
-            .str()
-            .contains(lit(pattern.clone()), false)
-            .not()
-            .fill_null(false);
-        parts.push(regex_miss);
+/// Rows of `column` outside `bounds`, compared as floats; an open side never fails.
+fn out_of_range_expr(column: Expr, bounds: (Option<f64>, Option<f64>)) -> Expr {
+    let value = column.cast(DataType::Float64);
+    match bounds {
+        (Some(min), Some(max)) => value.clone().lt(lit(min)).or(value.gt(lit(max))),
+        (Some(min), None) => value.lt(lit(min)),
+        (None, Some(max)) => value.gt(lit(max)),
+        (None, None) => lit(false),
     }
+}
 
-    if let Some(ref allowed) = check.allowed_values {
-        let series = Series::new("allowed".into(), allowed.clone());
-        let not_allowed = col(&check.name)
-            .cast(DataType::String)
-            .is_in(lit(series))
-            .not()
-            .fill_null(false);
-        parts.push(not_allowed);
+fn range_message(column: &str, count: usize, bounds: (Option<f64>, Option<f64>)) -> String {
+    match bounds {
+        (Some(min), None) => format!("Column '{}' has {} values below {}", column, count, min),
+        (None, Some(max)) => format!("Column '{}' has {} values above {}", column, count, max),
+        (min, max) => format!(
+            "Column '{}' has {} values outside range [{}, {}]",
+            column,
+            count,
+            min.unwrap_or(f64::NEG_INFINITY),
+            max.unwrap_or(f64::INFINITY)
+        ),
     }
+}
 
-    if parts.is_empty() {
-        // No-op check, never matches violations
-        return Ok(lit(false));
-    }
+/// Build a violation expression for a single column check.
+/// The expression evaluates to `true` for rows that violate the check.
+pub fn build_violation_expr(check: &ColumnCheck) -> Result<Expr> {
+    let parts = check_kinds(check)
+        .into_iter()
+        .map(|kind| kind_violation_expr(check, kind))
+        .collect::<Result<Vec<_>>>()?;
+    // No-op check, never matches violations
+    Ok(parts
+        .into_iter()
+        .reduce(|acc, expr| acc.or(expr))
+        .unwrap_or_else(|| lit(false)))
+}
 
-    let mut iter = parts.into_iter();
-    let first = iter
-        .next()
-        .ok_or_else(|| anyhow!("Empty validation expression"))?;
-    Ok(iter.fold(first, |acc, expr| acc.or(expr)))
+/// Row mask of the rows violating one check kind of `check`.
+fn kind_violation_expr(check: &ColumnCheck, kind: &str) -> Result<Expr> {
+    let column = col(&check.name);
+    let mask = match kind {
+        "not_null" => return Ok(column.is_null()),
+        "unique" => {
+            // `len` counts the null group like any other value; `count` leaves it at zero.
+            let group_size = if check.unique_nulls_fail() {
+                len()
+            } else {
+                column.clone().count()
+            };
+            return Ok(group_size.over([column]).gt(lit(1u32)));
+        }
+        "range" => out_of_range_expr(column, check.bounds().unwrap_or_default()),
+        "regex" => {
+            let pattern = check.regex.clone().unwrap_or_default();
+            // Validate regex upfront for early erroring
+            regex::Regex::new(&pattern)?;
+            column
+                .cast(DataType::String)
+                .str()
+                .contains(lit(pattern), false)
+                .not()
+        }
+        "enum" => {
+            let allowed = check.allowed_values.as_deref().unwrap_or_default();
+            allowed_expr(column, allowed)?.not()
+        }
+        _ => return Err(anyhow!("Unknown check kind '{}'", kind)),
+    };
+    let mask = mask.fill_null(false);
+    Ok(if check.nulls_fail() {
+        col(&check.name).is_null().or(mask)
+    } else {
+        mask
+    })
 }
 
 /// Build a combined violation mask for all column checks.
@@ -138,6 +276,12 @@ pub fn violation_mask_expr(config: &CheckConfig) -> Result<Option<Expr>> {
     for check in &config.columns {
         exprs.push(build_violation_expr(check)?);
     }
+    for keys in &config.unique_keys {
+        exprs.push(repeated_key_expr(keys)?);
+    }
+    for check in &config.conditions {
+        exprs.push(condition_violation_expr(check)?);
+    }
 
     if exprs.is_empty() {
         return Ok(None);
@@ -148,6 +292,102 @@ pub fn violation_mask_expr(config: &CheckConfig) -> Result<Option<Expr>> {
     Ok(Some(iter.fold(first, |acc, expr| acc.or(expr))))
 }
 
+/// One row per violation of a column check kind, conditional check or composite key:
+/// the row's position in `lf` (`row_index`), the `column` (the condition or the key
+/// columns for those), the `check` that failed and the row's `value` as text, `***` for
+/// columns masked by `masker`. Conditions and keys span columns and have no value.
+/// Statistics describe whole columns and are not listed.
+pub fn violation_details(
+    lf: LazyFrame,
+    config: &CheckConfig,
+    masker: &crate::security::Masker,
+) -> Result<LazyFrame> {
+    let indexed = lf.with_row_index("__row_index", None);
+    let detail = |mask: Expr, column: &str, check: &str, value: Expr| {
+        indexed.clone().filter(mask).select([
+            col("__row_index").alias("row_index"),
+            lit(column.to_string()).alias("column"),
+            lit(check.to_string()).alias("check"),
+            value.alias("value"),
+        ])
+    };
+    let no_value = || lit(NULL).cast(DataType::String);
+
+    let mut parts = Vec::new();
+    for check in &config.columns {
+        let value = if masker.is_masked(&check.name) {
+            lit("***")
+        } else {
+            col(&check.name).cast(DataType::String)
+        };
+        for kind in check_kinds(check) {
+            let mask = kind_violation_expr(check, kind)?;
+            parts.push(detail(mask, &check.name, kind, value.clone()));
+        }
+    }
+    for check in &config.conditions {
+        let mask = condition_violation_expr(check)?;
+        parts.push(detail(mask, check.label(), "condition", no_value()));
+    }
+    for keys in &config.unique_keys {
+        let mask = repeated_key_expr(keys)?;
+        parts.push(detail(mask, &keys.join(", "), "unique_keys", no_value()));
+    }
+
+    if parts.is_empty() {
+        let empty = DataFrame::new(vec![
+            Column::new_empty("row_index".into(), &IDX_DTYPE),
+            Column::new_empty("column".into(), &DataType::String),
+            Column::new_empty("check".into(), &DataType::String),
+            Column::new_empty("value".into(), &DataType::String),
+        ])?;
+        return Ok(empty.lazy());
+    }
+    Ok(concat(parts, UnionArgs::default())?.sort(
+        ["row_index"],
+        SortMultipleOptions::default().with_maintain_order(true),
+    ))
+}
+
+/// Rows matching `when` that do not satisfy `require`. A `require` evaluating to null
+/// is not satisfied.
+fn condition_violation_expr(check: &ConditionalCheck) -> Result<Expr> {
+    let parse = |sql: &str| {
+        polars::sql::sql_expr(sql).map_err(|e| {
+            anyhow!(
+                "Invalid condition '{}' of check '{}': {}",
+                sql,
+                check.label(),
+                e
+            )
+        })
+    };
+    let when = parse(&check.when)?;
+    let require = parse(&check.require)?;
+    Ok(when.fill_null(false).and(require.fill_null(false).not()))
+}
+
+fn condition_result(check: &ConditionalCheck, count: usize) -> ValidationResult {
+    if count == 0 {
+        return ValidationResult {
+            passed: true,
+            violations: vec![],
+        };
+    }
+    ValidationResult {
+        passed: false,
+        violations: vec![Violation {
+            column: check.label().to_string(),
+            check_type: "condition".to_string(),
+            message: format!(
+                "{} rows where {} do not satisfy {}",
+                count, check.when, check.require
+            ),
+            count,
+        }],
+    }
+}
+
 fn violation_from_count(check: &ColumnCheck, count: usize) -> Option<Violation> {
     if count == 0 {
         return None;
@@ -157,11 +397,8 @@ fn violation_from_count(check: &ColumnCheck, count: usize) -> Option<Violation>
         format!("Column '{}' has {} null values", check.name, count)
     } else if check.unique {
         format!("Column '{}' has {} duplicate values", check.name, count)
-    } else if let Some((min, max)) = check.range {
-        format!(
-            "Column '{}' has {} values outside range [{}, {}]",
-            check.name, count, min, max
-        )
+    } else if let Some(bounds) = check.bounds() {
+        range_message(&check.name, count, bounds)
     } else if let Some(ref pattern) = check.regex {
         format!(
             "Column '{}' has {} values not matching pattern '{}'",
@@ -169,8 +406,10 @@ fn violation_from_count(check: &ColumnCheck, count: usize) -> Option<Violation>
         )
     } else if let Some(ref allowed) = check.allowed_values {
         format!(
-            "Column '{}' has {} values not in allowed set {:?}",
-            check.name, count, allowed
+            "Column '{}' has {} values not in allowed set {}",
+            check.name,
+            count,
+            value_list(allowed)
         )
     } else {
         format!("Column '{}' failed validation {} times", check.name, count)
@@ -184,39 +423,276 @@ fn violation_from_count(check: &ColumnCheck, count: usize) -> Option<Violation>
     })
 }
 
-/// Summarize violations lazily by aggregating violation counts per check.
+/// Check that every column named by `config` is in `schema`, so a misspelt check fails
+/// with a suggestion rather than an error from the query engine. Conditional checks are
+/// SQL expressions and are left to the engine.
+pub fn check_columns(config: &CheckConfig, schema: &Schema) -> Result<()> {
+    let named = config
+        .columns
+        .iter()
+        .map(|check| &check.name)
+        .chain(config.stats.iter().map(|check| &check.name))
+        .chain(config.unique_keys.iter().flatten());
+    for name in named {
+        if !schema.contains(name) {
+            return Err(anyhow!(column_not_found(
+                name,
+                schema.iter_names().map(|n| n.as_str())
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Summarize violations lazily by aggregating violation counts per check. Column
+/// checks, conditional checks and expected statistics are computed in a single pass,
+/// and each of `unique_keys` by grouping on its columns. The report lists the column
+/// checks first, then the conditional checks, each statistic of [`stat_checks`], and
+/// the key sets.
 pub fn summarize_violations_lazy(
-    lf: LazyFrame,
+    mut lf: LazyFrame,
     config: &CheckConfig,
     streaming: bool,
 ) -> Result<ValidationReport> {
-    let mut agg_exprs: Vec<Expr> = Vec::new();
-    for (idx, check) in config.columns.iter().enumerate() {
-        let mask_expr = build_violation_expr(check)?;
-        let alias = format!("check{}_{}", idx, check_label(check));
-        agg_exprs.push(mask_expr.cast(DataType::UInt64).sum().alias(&alias));
+    check_columns(config, &*lf.collect_schema()?)?;
+    let mut agg_exprs = count_exprs(config)?;
+    agg_exprs.extend(stat_exprs(config));
+    let summary = collect_summary(lf.clone(), agg_exprs, streaming)?;
+
+    let mut report = report_from_counts(config, &counts_from(&summary, config));
+    for result in stat_results(&summary, config) {
+        report.add_result(result);
+    }
+    for result in unique_key_results(lf, config, streaming)? {
+        report.add_result(result);
     }
+    Ok(report)
+}
 
-    if agg_exprs.is_empty() {
-        return Ok(ValidationReport::new());
+/// Row mask of the rows sharing their combination of `keys` with another row.
+fn repeated_key_expr(keys: &[String]) -> Result<Expr> {
+    if keys.is_empty() {
+        return Err(anyhow!("unique_keys entries need at least one column"));
     }
+    Ok(len()
+        .over(keys.iter().map(col).collect::<Vec<_>>())
+        .gt(lit(1u32)))
+}
 
-    let counts_df = lf
+/// Rows beyond the first of each combination of `keys`, counted by grouping on them.
+fn count_repeated_keys(lf: LazyFrame, keys: &[String], streaming: bool) -> Result<usize> {
+    if keys.is_empty() {
+        return Err(anyhow!("unique_keys entries need at least one column"));
+    }
+    let counted = lf
         .with_streaming(streaming)
+        .group_by(keys.iter().map(col).collect::<Vec<_>>())
+        .agg([len().alias("__rows")])
+        .select([(col("__rows").cast(DataType::UInt64) - lit(1u64))
+            .sum()
+            .alias("__repeated")])
+        .collect()
+        .map_err(|e| anyhow!("Failed to count key repeats: {}", e))?;
+    Ok(counted
+        .column("__repeated")
+        .ok()
+        .and_then(|c| c.u64().ok())
+        .and_then(|ca| ca.get(0))
+        .unwrap_or(0) as usize)
+}
+
+/// Results of the `unique_keys` checks, in order.
+fn unique_key_results(
+    lf: LazyFrame,
+    config: &CheckConfig,
+    streaming: bool,
+) -> Result<Vec<ValidationResult>> {
+    config
+        .unique_keys
+        .iter()
+        .map(|keys| {
+            let count = count_repeated_keys(lf.clone(), keys, streaming)?;
+            if count == 0 {
+                return Ok(ValidationResult {
+                    passed: true,
+                    violations: vec![],
+                });
+            }
+            Ok(ValidationResult {
+                passed: false,
+                violations: vec![Violation {
+                    column: keys.join(", "),
+                    check_type: "unique_keys".to_string(),
+                    message: format!(
+                        "Columns [{}] have {} duplicate keys",
+                        keys.join(", "),
+                        count
+                    ),
+                    count,
+                }],
+            })
+        })
+        .collect()
+}
+
+/// Number of violating rows for each of `config.columns`, then each of
+/// `config.conditions`, in order, followed by the number of rows checked. Counts from
+/// separate chunks of a frame can be summed and passed to [`report_from_counts`].
+pub fn count_violations_lazy(
+    mut lf: LazyFrame,
+    config: &CheckConfig,
+    streaming: bool,
+) -> Result<Vec<usize>> {
+    check_columns(config, &*lf.collect_schema()?)?;
+    let agg_exprs = count_exprs(config)?;
+    let counts_df = collect_summary(lf, agg_exprs, streaming)?;
+    Ok(counts_from(&counts_df, config))
+}
+
+fn collect_summary(lf: LazyFrame, agg_exprs: Vec<Expr>, streaming: bool) -> Result<DataFrame> {
+    lf.with_streaming(streaming)
         .select(agg_exprs)
         .collect()
-        .map_err(|e| anyhow!("Failed to collect validation summary: {}", e))?;
+        .map_err(|e| anyhow!("Failed to collect validation summary: {}", e))
+}
 
-    let mut report = ValidationReport::new();
+/// Aggregations counting the violating rows of each column and conditional check, and
+/// the rows checked.
+fn count_exprs(config: &CheckConfig) -> Result<Vec<Expr>> {
+    let mut agg_exprs: Vec<Expr> = Vec::new();
     for (idx, check) in config.columns.iter().enumerate() {
-        let col_name = format!("check{}_{}", idx, check_label(check));
-        let count = counts_df
-            .column(&col_name)
+        let mask_expr = build_violation_expr(check)?;
+        let alias = format!("check{}_{}", idx, check_label(check));
+        agg_exprs.push(mask_expr.cast(DataType::UInt64).sum().alias(&alias));
+    }
+    for (idx, check) in config.conditions.iter().enumerate() {
+        let mask_expr = condition_violation_expr(check)?;
+        let alias = format!("condition{}", idx);
+        agg_exprs.push(mask_expr.cast(DataType::UInt64).sum().alias(&alias));
+    }
+    agg_exprs.push(len().cast(DataType::UInt64).alias("__rows"));
+    Ok(agg_exprs)
+}
+
+fn counts_from(counts_df: &DataFrame, config: &CheckConfig) -> Vec<usize> {
+    let count = |col_name: &str| {
+        counts_df
+            .column(col_name)
             .ok()
             .and_then(|c| c.u64().ok())
             .and_then(|ca| ca.get(0))
-            .unwrap_or(0) as usize;
+            .unwrap_or(0) as usize
+    };
+    config
+        .columns
+        .iter()
+        .enumerate()
+        .map(|(idx, check)| count(&format!("check{}_{}", idx, check_label(check))))
+        .chain((0..config.conditions.len()).map(|idx| count(&format!("condition{}", idx))))
+        .chain([count("__rows")])
+        .collect()
+}
+
+/// Each expected statistic of `config.stats` with its column and name (`mean`, `std`,
+/// `min` or `max`), in report order.
+pub fn stat_checks(config: &CheckConfig) -> Vec<(&StatsCheck, &'static str, &StatExpectation)> {
+    config
+        .stats
+        .iter()
+        .flat_map(|check| {
+            [
+                ("mean", &check.mean),
+                ("std", &check.std),
+                ("min", &check.min),
+                ("max", &check.max),
+            ]
+            .into_iter()
+            .filter_map(move |(stat, expectation)| Some((check, stat, expectation.as_ref()?)))
+        })
+        .collect()
+}
+
+/// Aggregations computing each statistic of [`stat_checks`] as a Float64.
+fn stat_exprs(config: &CheckConfig) -> Vec<Expr> {
+    stat_checks(config)
+        .into_iter()
+        .enumerate()
+        .map(|(i, (check, stat, _))| {
+            let column = col(&check.name).cast(DataType::Float64);
+            let value = match stat {
+                "mean" => column.mean(),
+                "std" => column.std(1),
+                "min" => column.min(),
+                _ => column.max(),
+            };
+            value
+                .cast(DataType::Float64)
+                .alias(stat_alias(i, check, stat))
+        })
+        .collect()
+}
+
+fn stat_alias(i: usize, check: &StatsCheck, stat: &str) -> String {
+    format!("stat{}_{}_{}", i, check.name, stat)
+}
+
+/// Results of the statistics of [`stat_checks`], read from a frame holding
+/// [`stat_exprs`].
+fn stat_results(summary: &DataFrame, config: &CheckConfig) -> Vec<ValidationResult> {
+    stat_checks(config)
+        .into_iter()
+        .enumerate()
+        .map(|(i, (check, stat, expectation))| {
+            let value = summary
+                .column(&stat_alias(i, check, stat))
+                .ok()
+                .and_then(|c| c.f64().ok())
+                .and_then(|ca| ca.get(0));
+            stat_result(check, stat, expectation, value)
+        })
+        .collect()
+}
+
+/// Compare a computed statistic with its expectation. A failed expectation counts as
+/// one violation; so does a column without non-null values to compute it from.
+fn stat_result(
+    check: &StatsCheck,
+    stat: &str,
+    expectation: &StatExpectation,
+    value: Option<f64>,
+) -> ValidationResult {
+    let tolerance = expectation.tolerance();
+    let message = match value {
+        Some(value) if (value - expectation.expected).abs() <= tolerance => {
+            return ValidationResult {
+                passed: true,
+                violations: vec![],
+            }
+        }
+        Some(value) => format!(
+            "Column '{}' has {} {}, expected {} ± {}",
+            check.name, stat, value, expectation.expected, tolerance
+        ),
+        None => format!(
+            "Column '{}' has no values to compute its {} from",
+            check.name, stat
+        ),
+    };
+    ValidationResult {
+        passed: false,
+        violations: vec![Violation {
+            column: check.name.clone(),
+            check_type: format!("stats.{}", stat),
+            message,
+            count: 1,
+        }],
+    }
+}
 
+/// Build a report from per-check violation counts (see [`count_violations_lazy`]).
+pub fn report_from_counts(config: &CheckConfig, counts: &[usize]) -> ValidationReport {
+    let mut report = ValidationReport::new();
+    for (check, &count) in config.columns.iter().zip(counts) {
         let violation = violation_from_count(check, count);
         let passed = violation.is_none();
         report.add_result(ValidationResult {
@@ -224,15 +700,60 @@ pub fn summarize_violations_lazy(
             violations: violation.into_iter().collect(),
         });
     }
+    let condition_counts = counts.iter().skip(config.columns.len());
+    for (check, &count) in config.conditions.iter().zip(condition_counts) {
+        report.add_result(condition_result(check, count));
+    }
+    let rows_at = config.columns.len() + config.conditions.len();
+    report.rows = counts.get(rows_at).copied().unwrap_or(0);
+    report
+}
 
-    Ok(report)
+/// Weighted pass rate of a report built by [`summarize_violations_lazy`] or
+/// [`report_from_counts`], as (sum of weight × pass rate, sum of weights). A check's
+/// weight is that of its severity. Its pass rate is the share of rows not violating it,
+/// or 1 or 0 for a statistic, which holds for the whole column.
+pub fn quality(config: &CheckConfig, report: &ValidationReport) -> (f64, f64) {
+    let weight = |severity: Option<Severity>| severity.unwrap_or_default().weight();
+    let checks = config
+        .columns
+        .iter()
+        .map(|check| (weight(check.severity), true))
+        .chain(
+            config
+                .conditions
+                .iter()
+                .map(|check| (weight(check.severity), true)),
+        )
+        .chain(
+            stat_checks(config)
+                .into_iter()
+                .map(|(check, _, _)| (weight(check.severity), false)),
+        )
+        .chain(
+            config
+                .unique_keys
+                .iter()
+                .map(|_| (Severity::default().weight(), true)),
+        );
+    checks
+        .zip(&report.results)
+        .fold((0.0, 0.0), |(score, total), ((weight, per_row), result)| {
+            let count: usize = result.violations.iter().map(|v| v.count).sum();
+            let rate = if result.passed {
+                1.0
+            } else if per_row && report.rows > 0 {
+                1.0 - count.min(report.rows) as f64 / report.rows as f64
+            } else {
+                0.0
+            };
+            (score + weight * rate, total + weight)
+        })
 }
 
 /// Validate that a column has no null values
 pub fn validate_not_null(df: &DataFrame, column: &str) -> Result<ValidationResult> {
-    let col = df
-        .column(column)
-        .map_err(|e| anyhow!("Column '{}' not found: {}", column, e))?;
+    let col = find_column(df, column)?;
     let null_count = col.null_count();
 
     if null_count == 0 {
@@ -255,9 +776,7 @@ pub fn validate_not_null(df: &DataFrame, column: &str) -> Result<ValidationResul
 
 /// Validate that a column has unique values
 pub fn validate_unique(df: &DataFrame, column: &str) -> Result<ValidationResult> {
-    let col = df
-        .column(column)
-        .map_err(|e| anyhow!("Column '{}' not found: {}", column, e))?;
+    let col = find_column(df, column)?;
 
     let total = col.len();
     let unique = col
@@ -286,16 +805,14 @@ pub fn validate_unique(df: &DataFrame, column: &str) -> Result<ValidationResult>
     }
 }
 
-/// Validate that column values are within a range
+/// Validate that column values are within a range; `None` leaves that side open
 pub fn validate_range(
     df: &DataFrame,
     column: &str,
-    min: f64,
-    max: f64,
+    min: Option<f64>,
+    max: Option<f64>,
 ) -> Result<ValidationResult> {
-    let col = df
-        .column(column)
-        .map_err(|e| anyhow!("Column '{}' not found: {}", column, e))?;
+    let col = find_column(df, column)?;
 
     // Cast to f64 for comparison
     let float_col = col
@@ -308,7 +825,7 @@ pub fn validate_range(
 
     let mut out_of_range_count = 0;
     for val in ca.into_iter().flatten() {
-        if val < min || val > max {
+        if min.is_some_and(|min| val < min) || max.is_some_and(|max| val > max) {
             out_of_range_count += 1;
         }
     }
@@ -324,10 +841,7 @@ pub fn validate_range(
             violations: vec![Violation {
                 column: column.to_string(),
                 check_type: "range".to_string(),
-                message: format!(
-                    "Column '{}' has {} values outside range [{}, {}]",
-                    column, out_of_range_count, min, max
-                ),
+                message: range_message(column, out_of_range_count, (min, max)),
                 count: out_of_range_count,
             }],
         })
@@ -336,9 +850,7 @@ pub fn validate_range(
 
 /// Validate that column values match a regex pattern
 pub fn validate_regex(df: &DataFrame, column: &str, pattern: &str) -> Result<ValidationResult> {
-    let col = df
-        .column(column)
-        .map_err(|e| anyhow!("Column '{}' not found: {}", column, e))?;
+    let col = find_column(df, column)?;
 
     let str_col = col
         .str()
@@ -377,9 +889,7 @@ pub fn validate_regex(df: &DataFrame, column: &str, pattern: &str) -> Result<Val
 
 /// Validate that column values are in an allowed set
 pub fn validate_enum(df: &DataFrame, column: &str, allowed: &[String]) -> Result<ValidationResult> {
-    let col = df
-        .column(column)
-        .map_err(|e| anyhow!("Column '{}' not found: {}", column, e))?;
+    let col = find_column(df, column)?;
 
     let str_col = col
         .str()
@@ -404,8 +914,10 @@ pub fn validate_enum(df: &DataFrame, column: &str, allowed: &[String]) -> Result
                 column: column.to_string(),
                 check_type: "enum".to_string(),
                 message: format!(
-                    "Column '{}' has {} values not in allowed set {:?}",
-                    column, invalid_count, allowed
+                    "Column '{}' has {} values not in allowed set [{}]",
+                    column,
+                    invalid_count,
+                    allowed.join(", ")
                 ),
                 count: invalid_count,
             }],
@@ -413,68 +925,115 @@ pub fn validate_enum(df: &DataFrame, column: &str, allowed: &[String]) -> Result
     }
 }
 
-/// Build a boolean mask for rows that pass all column checks
-fn build_violation_mask(df: &DataFrame, check: &ColumnCheck) -> Result<BooleanChunked> {
-    let n_rows = df.height();
-    let mut mask = BooleanChunked::from_iter(std::iter::repeat_n(Some(false), n_rows));
-
-    // Check not_null
+/// Check kinds set on `check`, in the order [`run_validation`] reports them.
+fn check_kinds(check: &ColumnCheck) -> Vec<&'static str> {
+    let mut kinds = Vec::new();
     if check.not_null {
-        let col = df.column(&check.name)?;
-        let nulls = col.is_null();
-        mask = mask | nulls;
-    }
-
-    // Check range
-    if let Some((min, max)) = check.range {
-        let col = df.column(&check.name)?;
-        let float_col = col.cast(&DataType::Float64)?;
-        let ca = float_col.f64()?;
-
-        let below_min = ca.lt(min);
-        let above_max = ca.gt(max);
-        let out_of_range = below_min | above_max;
-        mask = mask | out_of_range;
-    }
-
-    // Check regex
-    if let Some(ref pattern) = check.regex {
-        let col = df.column(&check.name)?;
-        if let Ok(str_col) = col.str() {
-            let regex = regex::Regex::new(pattern)?;
-            let mut non_match = Vec::with_capacity(n_rows);
-            for opt_val in str_col.into_iter() {
-                match opt_val {
-                    Some(val) => non_match.push(Some(!regex.is_match(val))),
-                    None => non_match.push(Some(false)), // null values don't violate regex
-                }
-            }
-            let non_match_ca = BooleanChunked::from_iter(non_match);
-            mask = mask | non_match_ca;
-        }
+        kinds.push("not_null");
+    }
+    if check.unique {
+        kinds.push("unique");
+    }
+    if check.bounds().is_some() {
+        kinds.push("range");
     }
+    if check.regex.is_some() {
+        kinds.push("regex");
+    }
+    if check.allowed_values.is_some() {
+        kinds.push("enum");
+    }
+    kinds
+}
 
-    // Check enum
-    if let Some(ref allowed) = check.allowed_values {
-        let col = df.column(&check.name)?;
-        if let Ok(str_col) = col.str() {
-            let mut not_allowed = Vec::with_capacity(n_rows);
-            for opt_val in str_col.into_iter() {
-                match opt_val {
-                    Some(val) => not_allowed.push(Some(!allowed.iter().any(|a| a == val))),
-                    None => not_allowed.push(Some(false)), // null values don't violate enum
-                }
-            }
-            let not_allowed_ca = BooleanChunked::from_iter(not_allowed);
-            mask = mask | not_allowed_ca;
+/// Aggregation counting the violations of one check kind, with the semantics of the
+/// matching `validate_*` function: nulls only violate `not_null`, and `unique` counts
+/// rows beyond the first of each value. The `nulls` and `unique_nulls` options of the
+/// check override how nulls are treated.
+fn violation_count_expr(check: &ColumnCheck, kind: &str) -> Result<Expr> {
+    let column = col(&check.name);
+    let violations = match kind {
+        "not_null" => column.is_null(),
+        "unique" => {
+            let values = if check.unique_nulls_fail() {
+                column
+            } else {
+                column.drop_nulls()
+            };
+            return Ok((values.clone().len() - values.n_unique()).cast(DataType::UInt64));
         }
-    }
+        "range" => out_of_range_expr(column, check.bounds().unwrap_or_default()),
+        "regex" => {
+            let pattern = check.regex.clone().unwrap_or_default();
+            regex::Regex::new(&pattern)
+                .map_err(|e| anyhow!("Invalid regex pattern '{}': {}", pattern, e))?;
+            column.str().contains(lit(pattern), true).not()
+        }
+        "enum" => {
+            let allowed = check.allowed_values.as_deref().unwrap_or_default();
+            column
+                .clone()
+                .is_not_null()
+                .and(allowed_expr(column, allowed)?.not())
+        }
+        _ => return Err(anyhow!("Unknown check kind '{}'", kind)),
+    };
+    let violations = if kind != "not_null" && check.nulls_fail() {
+        col(&check.name).is_null().or(violations)
+    } else {
+        violations
+    };
+    Ok(violations.fill_null(false).cast(DataType::UInt64).sum())
+}
 
-    Ok(mask)
+/// Result for one check kind, worded like the matching `validate_*` function.
+fn kind_result(check: &ColumnCheck, kind: &str, count: usize, rows: usize) -> ValidationResult {
+    if count == 0 {
+        return ValidationResult {
+            passed: true,
+            violations: vec![],
+        };
+    }
+    let column = &check.name;
+    let message = match kind {
+        "not_null" => format!("Column '{}' has {} null values", column, count),
+        "unique" => format!(
+            "Column '{}' has {} duplicate values ({} total, {} unique)",
+            column,
+            count,
+            rows,
+            rows - count
+        ),
+        "range" => range_message(column, count, check.bounds().unwrap_or_default()),
+        "regex" => format!(
+            "Column '{}' has {} values not matching pattern '{}'",
+            column,
+            count,
+            check.regex.as_deref().unwrap_or_default()
+        ),
+        _ => format!(
+            "Column '{}' has {} values not in allowed set {}",
+            column,
+            count,
+            value_list(check.allowed_values.as_deref().unwrap_or_default())
+        ),
+    };
+    ValidationResult {
+        passed: false,
+        violations: vec![Violation {
+            column: column.clone(),
+            check_type: kind.to_string(),
+            message,
+            count,
+        }],
+    }
 }
 
 /// Run validation on a DataFrame with the given configuration and mode
 ///
+/// Every check is counted, and in quarantine mode the combined violation mask is
+/// built, in a single lazy pass over `df`.
+///
 /// Returns:
 /// - Valid DataFrame (rows that passed all checks, or all rows in strict/warn mode)
 /// - Optional quarantine DataFrame (rows that failed checks, only in quarantine mode)
@@ -485,34 +1044,79 @@ pub fn run_validation(
     mode: &ValidationMode,
     _masker: &crate::security::Masker,
 ) -> Result<(DataFrame, Option<DataFrame>, ValidationReport)> {
-    let mut report = ValidationReport::new();
-
-    // Run all column checks and collect results
-    for check in &config.columns {
-        if check.not_null {
-            let result = validate_not_null(&df, &check.name)?;
-            report.add_result(result);
-        }
-
-        if check.unique {
-            let result = validate_unique(&df, &check.name)?;
-            report.add_result(result);
-        }
-
-        if let Some((min, max)) = check.range {
-            let result = validate_range(&df, &check.name, min, max)?;
-            report.add_result(result);
-        }
-
-        if let Some(ref pattern) = check.regex {
-            let result = validate_regex(&df, &check.name, pattern)?;
-            report.add_result(result);
-        }
+    check_columns(config, df.schema())?;
+    let checks: Vec<(&ColumnCheck, &str)> = config
+        .columns
+        .iter()
+        .flat_map(|check| {
+            check_kinds(check)
+                .into_iter()
+                .map(move |kind| (check, kind))
+        })
+        .collect();
+    let mut exprs = checks
+        .iter()
+        .enumerate()
+        .map(|(i, (check, kind))| {
+            Ok(violation_count_expr(check, kind)?.alias(format!("__count{}", i)))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    for (i, check) in config.conditions.iter().enumerate() {
+        exprs.push(
+            condition_violation_expr(check)?
+                .cast(DataType::UInt64)
+                .sum()
+                .alias(format!("__condition{}", i)),
+        );
+    }
+    exprs.extend(stat_exprs(config));
+    // Counts broadcast next to the row mask; common subexpressions are evaluated once.
+    let mask = match mode {
+        ValidationMode::Quarantine => violation_mask_expr(config)?,
+        _ => None,
+    };
+    if let Some(mask) = mask {
+        exprs.push(mask.fill_null(false).alias("__violation"));
+    }
+    if exprs.is_empty() && config.unique_keys.is_empty() {
+        return Ok((df, None, ValidationReport::new()));
+    }
 
-        if let Some(ref allowed) = check.allowed_values {
-            let result = validate_enum(&df, &check.name, allowed)?;
-            report.add_result(result);
-        }
+    let evaluated = if exprs.is_empty() {
+        DataFrame::empty()
+    } else {
+        df.clone()
+            .lazy()
+            .select(exprs)
+            .collect()
+            .map_err(|e| anyhow!("Validation failed to evaluate: {}", e))?
+    };
+    let mut report = ValidationReport::new();
+    report.rows = df.height();
+    for (i, (check, kind)) in checks.iter().enumerate() {
+        // An empty frame has no rows to read counts from, and no violations.
+        let count = evaluated
+            .column(&format!("__count{}", i))
+            .ok()
+            .and_then(|c| c.u64().ok())
+            .and_then(|ca| ca.get(0))
+            .unwrap_or(0) as usize;
+        report.add_result(kind_result(check, kind, count, df.height()));
+    }
+    for (i, check) in config.conditions.iter().enumerate() {
+        let count = evaluated
+            .column(&format!("__condition{}", i))
+            .ok()
+            .and_then(|c| c.u64().ok())
+            .and_then(|ca| ca.get(0))
+            .unwrap_or(0) as usize;
+        report.add_result(condition_result(check, count));
+    }
+    for result in stat_results(&evaluated, config) {
+        report.add_result(result);
+    }
+    for result in unique_key_results(df.clone().lazy(), config, false)? {
+        report.add_result(result);
     }
 
     // Handle based on mode
@@ -531,16 +1135,10 @@ pub fn run_validation(
             Ok((df, None, report))
         }
         ValidationMode::Quarantine => {
-            if !report.passed {
-                // Build combined violation mask
-                let n_rows = df.height();
-                let mut combined_mask =
-                    BooleanChunked::from_iter(std::iter::repeat_n(Some(false), n_rows));
-
-                for check in &config.columns {
-                    let check_mask = build_violation_mask(&df, check)?;
-                    combined_mask = combined_mask | check_mask;
-                }
+            // Without column checks only statistics failed, and no row is to blame.
+            let mask = evaluated.column("__violation").ok();
+            if let (false, Some(mask)) = (report.passed, mask) {
+                let combined_mask = mask.bool()?.clone();
 
                 // Split into valid and quarantine DataFrames
                 let valid_mask = !combined_mask.clone();
@@ -616,7 +1214,7 @@ mod tests {
     #[test]
     fn test_validate_range_pass() {
         let df = create_test_df();
-        let result = validate_range(&df, "age", 0.0, 100.0).unwrap();
+        let result = validate_range(&df, "age", Some(0.0), Some(100.0)).unwrap();
         assert!(result.passed);
         assert!(result.violations.is_empty());
     }
@@ -628,7 +1226,7 @@ mod tests {
         }
         .unwrap();
 
-        let result = validate_range(&df, "age", 0.0, 120.0).unwrap();
+        let result = validate_range(&df, "age", Some(0.0), Some(120.0)).unwrap();
         assert!(!result.passed);
         assert_eq!(result.violations.len(), 1);
         assert_eq!(result.violations[0].count, 2); // 150 and -5 are out of range
@@ -703,10 +1301,19 @@ mod tests {
                 not_null: false,
                 unique: false,
                 range: Some((0.0, 120.0)),
+                min: None,
+                max: None,
                 regex: None,
                 allowed_values: None,
+                nulls: None,
+                unique_nulls: None,
+                on_violation: None,
+                severity: None,
             }],
             dataset: None,
+            stats: Vec::new(),
+            unique_keys: Vec::new(),
+            conditions: Vec::new(),
         };
 
         let masker = crate::security::Masker::new(vec![]);
@@ -719,6 +1326,276 @@ mod tests {
         assert_eq!(quarantine_df.unwrap().height(), 2); // rows with age 150, -5
     }
 
+    #[test]
+    fn test_run_validation_matches_per_check_functions() {
+        let df = df! {
+            "id" => &[Some(1), Some(2), Some(2), None, None, Some(6)],
+            "age" => &[Some(25), None, Some(150), Some(-5), Some(45), Some(30)],
+            "email" => &[Some("a@x.io"), Some("bad"), None, Some("b@y.io"), Some("nope"), Some("c@z.io")],
+            "status" => &[Some("active"), Some("gone"), None, Some("active"), Some("pending"), Some("x")]
+        }
+        .unwrap();
+        let check = |name: &str| ColumnCheck {
+            name: name.to_string(),
+            not_null: false,
+            unique: false,
+            range: None,
+            min: None,
+            max: None,
+            regex: None,
+            allowed_values: None,
+            nulls: None,
+            unique_nulls: None,
+            on_violation: None,
+            severity: None,
+        };
+        let allowed = vec!["active".to_string(), "pending".to_string()];
+        let pattern = r"^[a-z]+@[a-z]+\.[a-z]+$";
+        let config = CheckConfig {
+            columns: vec![
+                ColumnCheck {
+                    not_null: true,
+                    unique: true,
+                    ..check("id")
+                },
+                ColumnCheck {
+                    range: Some((0.0, 120.0)),
+                    ..check("age")
+                },
+                ColumnCheck {
+                    regex: Some(pattern.to_string()),
+                    ..check("email")
+                },
+                ColumnCheck {
+                    allowed_values: Some(allowed.iter().map(|s| s.as_str().into()).collect()),
+                    nulls: None,
+                    unique_nulls: None,
+                    on_violation: None,
+                    ..check("status")
+                },
+            ],
+            dataset: None,
+            stats: Vec::new(),
+            unique_keys: Vec::new(),
+            conditions: Vec::new(),
+        };
+
+        let masker = crate::security::Masker::new(vec![]);
+        let (_, _, report) =
+            run_validation(df.clone(), &config, &ValidationMode::Warn, &masker).unwrap();
+        let expected = [
+            validate_not_null(&df, "id").unwrap(),
+            validate_unique(&df, "id").unwrap(),
+            validate_range(&df, "age", Some(0.0), Some(120.0)).unwrap(),
+            validate_regex(&df, "email", pattern).unwrap(),
+            validate_enum(&df, "status", &allowed).unwrap(),
+        ];
+        assert_eq!(report.results.len(), expected.len());
+        for (got, want) in report.results.iter().zip(&expected) {
+            assert_eq!(got.passed, want.passed);
+            assert_eq!(got.violations, want.violations);
+        }
+        assert_eq!(report.total_violations, 2 + 2 + 2 + 2 + 2);
+
+        // Quarantine splits the frame; rows whose only problem is a null in a range,
+        // regex or enum column stay valid.
+        let (valid, quarantine, _) =
+            run_validation(df.clone(), &config, &ValidationMode::Quarantine, &masker).unwrap();
+        let quarantine = quarantine.unwrap();
+        assert_eq!(valid.height() + quarantine.height(), df.height());
+        assert_eq!(valid.height(), 1);
+    }
+
+    #[test]
+    fn test_stats_checks() {
+        let df = df! {
+            "amount" => &[Some(200.0), Some(250.0), None, Some(300.0)],
+            "empty" => &[None::<f64>, None, None, None]
+        }
+        .unwrap();
+        let config: CheckConfig = serde_yaml::from_str(
+            r#"
+stats:
+  - name: amount
+    mean: {expected: 260, rel_tolerance: 0.1}
+    std: {expected: 40, abs_tolerance: 5}
+    min: {expected: 200}
+    max: {expected: 250, abs_tolerance: 10}
+  - name: empty
+    mean: {expected: 0, abs_tolerance: 1}
+"#,
+        )
+        .unwrap();
+        assert_eq!(
+            stat_checks(&config)
+                .iter()
+                .map(|(check, stat, _)| (check.name.as_str(), *stat))
+                .collect::<Vec<_>>(),
+            [
+                ("amount", "mean"),
+                ("amount", "std"),
+                ("amount", "min"),
+                ("amount", "max"),
+                ("empty", "mean")
+            ]
+        );
+
+        let report = summarize_violations_lazy(df.clone().lazy(), &config, false).unwrap();
+        let passed: Vec<bool> = report.results.iter().map(|r| r.passed).collect();
+        // std is 50, outside 40 ± 5; max is 300, outside 250 ± 10
+        assert_eq!(passed, [true, false, true, false, false]);
+        assert_eq!(report.total_violations, 3);
+        let max = &report.results[3].violations[0];
+        assert_eq!(max.check_type, "stats.max");
+        assert_eq!(
+            max.message,
+            "Column 'amount' has max 300, expected 250 ± 10"
+        );
+        assert_eq!(
+            report.results[4].violations[0].message,
+            "Column 'empty' has no values to compute its mean from"
+        );
+
+        // Statistics never split rows off in quarantine mode
+        let masker = crate::security::Masker::new(vec![]);
+        let (valid, quarantine, eager) =
+            run_validation(df.clone(), &config, &ValidationMode::Quarantine, &masker).unwrap();
+        assert_eq!(valid.height(), df.height());
+        assert!(quarantine.is_none());
+        assert_eq!(eager.total_violations, 3);
+    }
+
+    #[test]
+    fn test_unique_keys_check() {
+        let df = df! {
+            "user_id" => &[1, 1, 2, 1, 2],
+            "date" => &["2024-01-01", "2024-01-02", "2024-01-01", "2024-01-01", "2024-01-02"],
+            "amount" => &[1, 2, 3, 4, 5]
+        }
+        .unwrap();
+        let config: CheckConfig = serde_yaml::from_str(
+            r#"
+unique_keys:
+  - [user_id, date]
+  - [user_id, date, amount]
+"#,
+        )
+        .unwrap();
+
+        let report = summarize_violations_lazy(df.clone().lazy(), &config, false).unwrap();
+        let passed: Vec<bool> = report.results.iter().map(|r| r.passed).collect();
+        assert_eq!(passed, [false, true]);
+        let violation = &report.results[0].violations[0];
+        assert_eq!(violation.column, "user_id, date");
+        assert_eq!(violation.check_type, "unique_keys");
+        assert_eq!(violation.count, 1);
+        assert_eq!(
+            violation.message,
+            "Columns [user_id, date] have 1 duplicate keys"
+        );
+
+        // Every row of a repeated key is quarantined, not only the later ones
+        let masker = crate::security::Masker::new(vec![]);
+        let (valid, quarantine, eager) =
+            run_validation(df.clone(), &config, &ValidationMode::Quarantine, &masker).unwrap();
+        assert_eq!(valid.height(), 3);
+        assert_eq!(quarantine.unwrap().height(), 2);
+        assert_eq!(eager.total_violations, 1);
+
+        let (_, _, eager) = run_validation(df, &config, &ValidationMode::Warn, &masker).unwrap();
+        assert_eq!(eager.total_violations, 1);
+
+        let empty: CheckConfig = serde_yaml::from_str("unique_keys: [[]]").unwrap();
+        assert!(violation_mask_expr(&empty).is_err());
+    }
+
+    #[test]
+    fn test_conditional_checks() {
+        let df = df! {
+            "status" => &[Some("closed"), Some("closed"), Some("open"), None, Some("closed")],
+            "closed_at" => &[Some("2024-01-02"), None, None, None, None],
+            "amount" => &[Some(10), Some(5), Some(-1), Some(3), None]
+        }
+        .unwrap();
+        let config: CheckConfig = serde_yaml::from_str(
+            r#"
+conditions:
+  - name: closed_has_date
+    when: "status == 'closed'"
+    require: "closed_at IS NOT NULL"
+  - when: "status = 'closed'"
+    require: "amount > 0"
+"#,
+        )
+        .unwrap();
+
+        let report = summarize_violations_lazy(df.clone().lazy(), &config, false).unwrap();
+        let counts: Vec<usize> = report
+            .results
+            .iter()
+            .map(|r| r.violations.first().map_or(0, |v| v.count))
+            .collect();
+        // A null status never matches `when`; a null amount does not satisfy `require`
+        assert_eq!(counts, [2, 1]);
+        let violation = &report.results[0].violations[0];
+        assert_eq!(violation.column, "closed_has_date");
+        assert_eq!(violation.check_type, "condition");
+        assert_eq!(
+            violation.message,
+            "2 rows where status == 'closed' do not satisfy closed_at IS NOT NULL"
+        );
+        assert_eq!(report.results[1].violations[0].column, "amount > 0");
+        assert_eq!(
+            count_violations_lazy(df.clone().lazy(), &config, false).unwrap(),
+            [2, 1, 5]
+        );
+
+        let masker = crate::security::Masker::new(vec![]);
+        let (valid, quarantine, eager) =
+            run_validation(df, &config, &ValidationMode::Quarantine, &masker).unwrap();
+        assert_eq!(valid.height(), 3);
+        assert_eq!(quarantine.unwrap().height(), 2);
+        assert_eq!(eager.total_violations, 3);
+
+        let bad: CheckConfig =
+            serde_yaml::from_str("conditions: [{when: \"status ==\", require: \"true\"}]").unwrap();
+        let err = violation_mask_expr(&bad).unwrap_err().to_string();
+        assert!(err.contains("Invalid condition 'status =='"), "{}", err);
+    }
+
+    #[test]
+    fn test_quality_score() {
+        let df = df! {
+            "amount" => &[Some(10.0), Some(-1.0), None, Some(30.0)],
+            "status" => &["open", "closed", "closed", "open"]
+        }
+        .unwrap();
+        let config: CheckConfig = serde_yaml::from_str(
+            r#"
+columns:
+  - name: amount
+    not_null: true
+    severity: critical
+conditions:
+  - when: "status = 'closed'"
+    require: "amount > 0"
+    severity: low
+stats:
+  - name: amount
+    max: {expected: 100}
+unique_keys:
+  - [status]
+"#,
+        )
+        .unwrap();
+        let report = summarize_violations_lazy(df.lazy(), &config, false).unwrap();
+        assert_eq!(report.rows, 4);
+        let (score, weight) = quality(&config, &report);
+        // not_null: 3/4 × 8, condition: 2/4 × 1, max: 0 × 2, unique_keys: 2/4 × 2
+        assert_eq!(weight, 13.0);
+        assert!((score - 7.5).abs() < 1e-9, "{}", score);
+    }
+
     #[test]
     fn test_strict_mode_fail() {
         let df = df! {
@@ -732,10 +1609,19 @@ mod tests {
                 not_null: true,
                 unique: false,
                 range: None,
+                min: None,
+                max: None,
                 regex: None,
                 allowed_values: None,
+                nulls: None,
+                unique_nulls: None,
+                on_violation: None,
+                severity: None,
             }],
             dataset: None,
+            stats: Vec::new(),
+            unique_keys: Vec::new(),
+            conditions: Vec::new(),
         };
 
         let masker = crate::security::Masker::new(vec![]);
@@ -756,10 +1642,19 @@ mod tests {
                 not_null: true,
                 unique: false,
                 range: None,
+                min: None,
+                max: None,
                 regex: None,
                 allowed_values: None,
+                nulls: None,
+                unique_nulls: None,
+                on_violation: None,
+                severity: None,
             }],
             dataset: None,
+            stats: Vec::new(),
+            unique_keys: Vec::new(),
+            conditions: Vec::new(),
         };
 
         let masker = crate::security::Masker::new(vec![]);
@@ -770,4 +1665,226 @@ mod tests {
         assert_eq!(valid_df.height(), 3); // but all rows are kept
         assert!(quarantine_df.is_none()); // no quarantine in warn mode
     }
+
+    #[test]
+    fn test_misspelt_check_column_suggests_a_name() {
+        let df = df! {
+            "amount" => [1.5, 2.5],
+            "user_id" => [1i64, 2],
+        }
+        .unwrap();
+        let config: CheckConfig = serde_yaml::from_str(
+            "columns:\n  - name: amout\n    not_null: true\nunique_keys: [[user_id]]\n",
+        )
+        .unwrap();
+
+        let err = summarize_violations_lazy(df.clone().lazy(), &config, false).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Column 'amout' not found; did you mean 'amount'?"
+        );
+        let masker = crate::security::Masker::new(vec![]);
+        let err = run_validation(df.clone(), &config, &ValidationMode::Warn, &masker).unwrap_err();
+        assert!(
+            err.to_string().contains("did you mean 'amount'?"),
+            "{}",
+            err
+        );
+
+        let err = validate_unique(&df, "userid").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Column 'userid' not found; did you mean 'user_id'?"
+        );
+        // Nothing close enough to suggest
+        let err = validate_unique(&df, "revenue").unwrap_err();
+        assert_eq!(err.to_string(), "Column 'revenue' not found");
+    }
+
+    #[test]
+    fn test_open_ended_range() {
+        let df = df! {
+            "age" => [Some(-1i64), Some(30), None, Some(200)],
+        }
+        .unwrap();
+        let config: CheckConfig =
+            serde_yaml::from_str("columns:\n  - name: age\n    min: 0\n").unwrap();
+        assert_eq!(config.columns[0].bounds(), Some((Some(0.0), None)));
+
+        let report = summarize_violations_lazy(df.clone().lazy(), &config, false).unwrap();
+        let violation = &report.results[0].violations[0];
+        assert_eq!(violation.check_type, "range");
+        assert_eq!(violation.message, "Column 'age' has 1 values below 0");
+        assert_eq!(
+            validate_range(&df, "age", Some(0.0), None)
+                .unwrap()
+                .violations,
+            report.results[0].violations
+        );
+
+        let masker = crate::security::Masker::new(vec![]);
+        let config: CheckConfig =
+            serde_yaml::from_str("columns:\n  - name: age\n    max: 120\n").unwrap();
+        let (valid, quarantine, report) =
+            run_validation(df.clone(), &config, &ValidationMode::Quarantine, &masker).unwrap();
+        assert_eq!(
+            report.results[0].violations[0].message,
+            "Column 'age' has 1 values above 120"
+        );
+        assert_eq!((valid.height(), quarantine.unwrap().height()), (3, 1));
+
+        // `range` takes precedence over `min` / `max`
+        let config: CheckConfig =
+            serde_yaml::from_str("columns:\n  - name: age\n    range: [0, 100]\n    min: -5\n")
+                .unwrap();
+        assert_eq!(config.columns[0].bounds(), Some((Some(0.0), Some(100.0))));
+        let report = summarize_violations_lazy(df.lazy(), &config, false).unwrap();
+        assert_eq!(report.total_violations, 2);
+    }
+
+    #[test]
+    fn test_null_semantics() {
+        let df = df! {
+            "id" => [Some(1i64), None, None, Some(2)],
+            "code" => [Some("a"), None, Some("zz"), Some("b")],
+        }
+        .unwrap();
+        let masker = crate::security::Masker::new(vec![]);
+        let run = |yaml: &str| {
+            let config: CheckConfig = serde_yaml::from_str(yaml).unwrap();
+            let (valid, _, report) =
+                run_validation(df.clone(), &config, &ValidationMode::Quarantine, &masker).unwrap();
+            let lazy = count_violations_lazy(df.clone().lazy(), &config, false).unwrap();
+            (report.total_violations, valid.height(), lazy[0])
+        };
+
+        // By default repeated nulls are duplicates, and both null rows are quarantined
+        assert_eq!(run("columns:\n  - name: id\n    unique: true\n"), (1, 2, 2));
+        assert_eq!(
+            run("columns:\n  - name: id\n    unique: true\n    unique_nulls: pass\n"),
+            (0, 4, 0)
+        );
+
+        // By default a null passes the enum check
+        let allowed = "columns:\n  - name: code\n    enum: [a, b]\n";
+        assert_eq!(run(allowed), (1, 3, 1));
+        assert_eq!(run(&format!("{}    nulls: fail\n", allowed)), (2, 2, 2));
+        assert_eq!(
+            run("columns:\n  - name: id\n    min: 0\n    nulls: fail\n"),
+            (2, 2, 2)
+        );
+    }
+
+    #[test]
+    fn test_sample() {
+        let lf = df! { "id" => (0..1000i64).collect::<Vec<_>>() }
+            .unwrap()
+            .lazy();
+        let validate = |yaml: &str| -> Validate { serde_yaml::from_str(yaml).unwrap() };
+        let draw = |v: &Validate, seed: u64| {
+            let (sampled, sampling) = sample(lf.clone(), v, seed).unwrap().unwrap();
+            (sampled.collect().unwrap(), sampling)
+        };
+
+        let rows = validate("mode: warn\nsample_rows: 100\n");
+        let (first, sampling) = draw(&rows, 7);
+        assert_eq!(first.height(), 100);
+        assert_eq!(
+            sampling.to_string(),
+            "random sample of 100 of 1000 rows (sample_rows: 100, seed 7)"
+        );
+        assert!(first.equals(&draw(&rows, 7).0));
+        assert!(!first.equals(&draw(&rows, 8).0));
+        // Rows keep their order, and none is drawn twice
+        let ids = first.column("id").unwrap().i64().unwrap();
+        assert!(ids.into_no_null_iter().is_sorted_by(|a, b| a < b));
+
+        let (all, _) = draw(&validate("sample_rows: 5000\n"), 0);
+        assert_eq!(all.height(), 1000);
+        let (quarter, _) = draw(&validate("sample_fraction: 0.25\n"), 0);
+        assert_eq!(quarter.height(), 250);
+
+        assert!(sample(lf.clone(), &validate("mode: warn\n"), 0)
+            .unwrap()
+            .is_none());
+        for invalid in [
+            "sample_fraction: 0\n",
+            "sample_fraction: 0.5\nsample_rows: 10\n",
+            "mode: quarantine\nsample_rows: 10\n",
+        ] {
+            assert!(
+                sample(lf.clone(), &validate(invalid), 0).is_err(),
+                "{}",
+                invalid
+            );
+        }
+    }
+
+    #[test]
+    fn test_violation_details() {
+        let df = df! {
+            "user_id" => [1i64, 1, 2],
+            "email" => ["a@x.io", "bad", "c@x.io"],
+            "status" => ["open", "closed", "closed"],
+            "closed_at" => [None, None, Some("2024-01-02")],
+        }
+        .unwrap();
+        let config: CheckConfig = serde_yaml::from_str(
+            r#"
+columns:
+  - name: email
+    regex: "@"
+conditions:
+  - name: closed_at set
+    when: "status = 'closed'"
+    require: "closed_at IS NOT NULL"
+unique_keys:
+  - [user_id]
+"#,
+        )
+        .unwrap();
+        let masker = crate::security::Masker::new(vec!["email".to_string()]);
+        let details = violation_details(df.lazy(), &config, &masker)
+            .unwrap()
+            .collect()
+            .unwrap();
+        let text = |name: &str| -> Vec<Option<String>> {
+            let column = details.column(name).unwrap().str().unwrap().clone();
+            column.into_iter().map(|v| v.map(String::from)).collect()
+        };
+        assert_eq!(
+            details
+                .column("row_index")
+                .unwrap()
+                .idx()
+                .unwrap()
+                .into_no_null_iter()
+                .collect::<Vec<_>>(),
+            [0, 1, 1, 1]
+        );
+        assert_eq!(
+            text("check"),
+            ["unique_keys", "regex", "condition", "unique_keys"].map(|c| Some(c.to_string()))
+        );
+        assert_eq!(
+            text("column"),
+            ["user_id", "email", "closed_at set", "user_id"].map(|c| Some(c.to_string()))
+        );
+        // The masked email is not written out
+        assert_eq!(text("value"), [None, Some("***".to_string()), None, None]);
+
+        let empty = violation_details(
+            df! { "a" => [1] }.unwrap().lazy(),
+            &CheckConfig::default(),
+            &masker,
+        )
+        .unwrap()
+        .collect()
+        .unwrap();
+        assert_eq!(empty.height(), 0);
+        assert_eq!(
+            empty.get_column_names(),
+            ["row_index", "column", "check", "value"]
+        );
+    }
 }
@@ -0,0 +1,472 @@
+//! `mlprep view`: page through a data file in the terminal. Rows are read a page at a
+//! time, so large files open instantly; columns can be hidden and sorted on, and each
+//! header shows the column's dtype. Without a terminal the first page is printed.
+
+use crate::errors::{MlPrepError, MlPrepResult};
+use crate::io;
+use crate::security::SecurityContext;
+use polars::prelude::*;
+use std::io::Write;
+use std::path::Path;
+
+/// Characters shown of a cell before it is cut off with `…`.
+const MAX_CELL_WIDTH: usize = 30;
+
+/// Lines taken by the title, the header, the dtypes, the rule and the key help.
+const CHROME_LINES: usize = 5;
+
+const KEY_HELP: &str =
+    "q quit  ↑↓ row  PgUp/PgDn page  g/G first/last  ←→ column  s/S sort  x hide  a show all";
+
+/// A viewer command, bound to keys by the terminal loop.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    Down,
+    Up,
+    NextPage,
+    PrevPage,
+    First,
+    Last,
+    Left,
+    Right,
+    /// Hide the selected column
+    Hide,
+    ShowAll,
+    SortAscending,
+    SortDescending,
+    Quit,
+}
+
+/// The state of one viewed file.
+pub struct Viewer {
+    name: String,
+    source: LazyFrame,
+    schema: Schema,
+    total: usize,
+    visible: Vec<bool>,
+    /// Selected column, an index into `schema`
+    selected: usize,
+    offset: usize,
+    page_rows: usize,
+    sort: Option<(String, bool)>,
+    page: DataFrame,
+    status: Option<String>,
+}
+
+impl Viewer {
+    /// Open `path`, showing only `columns` when given. Masked columns read as `***`.
+    pub fn open(
+        path: &Path,
+        format: Option<&str>,
+        columns: &[String],
+        page_rows: usize,
+        security_context: &SecurityContext,
+    ) -> MlPrepResult<Self> {
+        security_context.validate_path(path)?;
+        let detected = io::detect_format(path, format)?;
+        let total = io::count_rows(path, detected)?;
+        let source = security_context
+            .masker()
+            .mask(io::read_detected(path, format)?)?;
+        let schema = source
+            .clone()
+            .collect_schema()
+            .map_err(MlPrepError::PolarsError)?
+            .as_ref()
+            .clone();
+        for column in columns {
+            if schema.get(column).is_none() {
+                return Err(MlPrepError::TransformError(format!(
+                    "{} has no column '{}'",
+                    path.display(),
+                    column
+                )));
+            }
+        }
+        let visible: Vec<bool> = schema
+            .iter_names()
+            .map(|name| columns.is_empty() || columns.iter().any(|c| c == name.as_str()))
+            .collect();
+        let selected = visible.iter().position(|v| *v).unwrap_or(0);
+        let mut viewer = Self {
+            name: path.display().to_string(),
+            source,
+            schema,
+            total,
+            visible,
+            selected,
+            offset: 0,
+            page_rows: page_rows.max(1),
+            sort: None,
+            page: DataFrame::empty(),
+            status: None,
+        };
+        viewer.load()?;
+        Ok(viewer)
+    }
+
+    /// Read the rows of the current page.
+    fn load(&mut self) -> MlPrepResult<()> {
+        let mut lf = self.source.clone();
+        if let Some((column, descending)) = &self.sort {
+            lf = lf.sort(
+                [column.as_str()],
+                SortMultipleOptions::default()
+                    .with_order_descending(*descending)
+                    .with_nulls_last(true),
+            );
+        }
+        let columns: Vec<Expr> = self
+            .schema
+            .iter_names()
+            .zip(&self.visible)
+            .filter(|(_, visible)| **visible)
+            .map(|(name, _)| col(name.clone()))
+            .collect();
+        self.page = lf
+            .select(columns)
+            .slice(self.offset as i64, self.page_rows as IdxSize)
+            .collect()
+            .map_err(MlPrepError::PolarsError)?;
+        Ok(())
+    }
+
+    /// Fit pages to a terminal of `height` lines.
+    pub fn resize(&mut self, height: usize) -> MlPrepResult<()> {
+        let rows = height.saturating_sub(CHROME_LINES).max(1);
+        if rows != self.page_rows {
+            self.page_rows = rows;
+            self.load()?;
+        }
+        Ok(())
+    }
+
+    fn last_offset(&self) -> usize {
+        self.total.saturating_sub(self.page_rows)
+    }
+
+    fn move_selection(&mut self, forward: bool) {
+        let mut idx = self.selected;
+        loop {
+            idx = match (forward, idx) {
+                (true, i) if i + 1 < self.visible.len() => i + 1,
+                (false, i) if i > 0 => i - 1,
+                _ => return,
+            };
+            if self.visible[idx] {
+                self.selected = idx;
+                return;
+            }
+        }
+    }
+
+    /// Apply `action`; returns false when the viewer should close.
+    pub fn apply(&mut self, action: Action) -> MlPrepResult<bool> {
+        self.status = None;
+        let offset = self.offset;
+        match action {
+            Action::Quit => return Ok(false),
+            Action::Down => self.offset = (self.offset + 1).min(self.last_offset()),
+            Action::Up => self.offset = self.offset.saturating_sub(1),
+            Action::NextPage => {
+                self.offset = (self.offset + self.page_rows).min(self.last_offset())
+            }
+            Action::PrevPage => self.offset = self.offset.saturating_sub(self.page_rows),
+            Action::First => self.offset = 0,
+            Action::Last => self.offset = self.last_offset(),
+            Action::Left => self.move_selection(false),
+            Action::Right => self.move_selection(true),
+            Action::Hide => {
+                if self.visible.iter().filter(|v| **v).count() == 1 {
+                    self.status = Some("cannot hide the last column".to_string());
+                    return Ok(true);
+                }
+                self.visible[self.selected] = false;
+                let hidden = self.selected;
+                self.move_selection(true);
+                if self.selected == hidden {
+                    self.move_selection(false);
+                }
+                self.load()?;
+            }
+            Action::ShowAll => {
+                self.visible.iter_mut().for_each(|v| *v = true);
+                self.load()?;
+            }
+            Action::SortAscending | Action::SortDescending => {
+                let column = self
+                    .schema
+                    .get_at_index(self.selected)
+                    .map(|(n, _)| n.to_string());
+                let previous = self.sort.take();
+                self.sort = column.map(|c| (c, action == Action::SortDescending));
+                self.offset = 0;
+                if let Err(e) = self.load() {
+                    self.sort = previous;
+                    self.offset = offset;
+                    self.load()?;
+                    self.status = Some(format!("cannot sort: {}", e));
+                }
+                return Ok(true);
+            }
+        }
+        if self.offset != offset {
+            self.load()?;
+        }
+        Ok(true)
+    }
+
+    fn title(&self) -> String {
+        let mut title = if self.total == 0 {
+            format!("{}  no rows", self.name)
+        } else {
+            format!(
+                "{}  rows {}-{} of {}",
+                self.name,
+                self.offset + 1,
+                self.offset + self.page.height(),
+                self.total
+            )
+        };
+        let hidden = self.visible.iter().filter(|v| !**v).count();
+        if hidden > 0 {
+            title.push_str(&format!("  {} columns hidden", hidden));
+        }
+        if let Some((column, descending)) = &self.sort {
+            title.push_str(&format!(
+                "  sorted by {} {}",
+                column,
+                if *descending { "↓" } else { "↑" }
+            ));
+        }
+        if let Some(status) = &self.status {
+            title.push_str(&format!("  ({})", status));
+        }
+        title
+    }
+
+    /// The screen as lines at most `width` characters wide: title, column names (the
+    /// selected one in brackets), dtypes, a rule, the page's rows and, with `help`, the
+    /// key bindings.
+    pub fn render(&self, width: usize, help: bool) -> Vec<String> {
+        let shown: Vec<usize> = (0..self.visible.len())
+            .filter(|idx| self.visible[*idx])
+            .collect();
+        let columns: Vec<(usize, Vec<String>)> = shown
+            .iter()
+            .zip(self.page.get_columns())
+            .map(|(idx, column)| {
+                let (name, dtype) = self.schema.get_at_index(*idx).unwrap();
+                let name = if *idx == self.selected {
+                    format!("[{}]", name)
+                } else {
+                    name.to_string()
+                };
+                let mut cells = vec![name, dtype.to_string()];
+                cells.extend((0..self.page.height()).map(|row| cell(column, row)));
+                (*idx, cells)
+            })
+            .collect();
+        let widths: Vec<usize> = columns
+            .iter()
+            .map(|(_, cells)| cells.iter().map(|c| c.chars().count()).max().unwrap_or(0))
+            .collect();
+
+        // Scroll sideways so the selected column is on screen
+        let selected = columns
+            .iter()
+            .position(|(idx, _)| *idx == self.selected)
+            .unwrap_or(0);
+        let mut first = 0;
+        while first < selected
+            && widths[first..=selected].iter().sum::<usize>() + 3 * (selected - first) > width
+        {
+            first += 1;
+        }
+
+        let drawn: Vec<(&Vec<String>, usize)> = columns
+            .iter()
+            .map(|(_, cells)| cells)
+            .zip(widths.iter().copied())
+            .skip(first)
+            .collect();
+        let row = |line: usize| {
+            drawn
+                .iter()
+                .map(|(cells, width)| format!("{:<width$}", cells[line], width = width))
+                .collect::<Vec<_>>()
+                .join(" │ ")
+        };
+        let mut lines = vec![self.title(), row(0), row(1)];
+        lines.push(
+            drawn
+                .iter()
+                .map(|(_, width)| "─".repeat(*width))
+                .collect::<Vec<_>>()
+                .join("─┼─"),
+        );
+        lines.extend((0..self.page.height()).map(|line| row(line + 2)));
+        if help {
+            lines.push(KEY_HELP.to_string());
+        }
+        lines
+            .into_iter()
+            .map(|line| {
+                let line = line.trim_end();
+                if line.chars().count() > width {
+                    line.chars()
+                        .take(width.saturating_sub(1))
+                        .chain(['…'])
+                        .collect()
+                } else {
+                    line.to_string()
+                }
+            })
+            .collect()
+    }
+}
+
+fn cell(column: &Column, row: usize) -> String {
+    let text = match column.get(row) {
+        Ok(AnyValue::Null) | Err(_) => "null".to_string(),
+        Ok(value) => match value.get_str() {
+            Some(s) => s.to_string(),
+            None => value.to_string(),
+        },
+    };
+    let text = text.replace(['\n', '\r', '\t'], " ");
+    if text.chars().count() > MAX_CELL_WIDTH {
+        text.chars().take(MAX_CELL_WIDTH - 1).chain(['…']).collect()
+    } else {
+        text
+    }
+}
+
+/// The viewer command bound to a key, if any.
+pub fn action_for(code: crossterm::event::KeyCode) -> Option<Action> {
+    use crossterm::event::KeyCode;
+    Some(match code {
+        KeyCode::Char('q') | KeyCode::Esc => Action::Quit,
+        KeyCode::Down | KeyCode::Char('j') => Action::Down,
+        KeyCode::Up | KeyCode::Char('k') => Action::Up,
+        KeyCode::PageDown | KeyCode::Char(' ') | KeyCode::Char('n') => Action::NextPage,
+        KeyCode::PageUp | KeyCode::Char('b') | KeyCode::Char('p') => Action::PrevPage,
+        KeyCode::Home | KeyCode::Char('g') => Action::First,
+        KeyCode::End | KeyCode::Char('G') => Action::Last,
+        KeyCode::Left | KeyCode::Char('h') => Action::Left,
+        KeyCode::Right | KeyCode::Char('l') => Action::Right,
+        KeyCode::Char('x') => Action::Hide,
+        KeyCode::Char('a') => Action::ShowAll,
+        KeyCode::Char('s') => Action::SortAscending,
+        KeyCode::Char('S') => Action::SortDescending,
+        _ => return None,
+    })
+}
+
+/// Run the viewer full screen until the user quits, restoring the terminal afterwards.
+pub fn run(viewer: &mut Viewer) -> MlPrepResult<()> {
+    use crossterm::event::{self, Event, KeyEventKind};
+    use crossterm::{cursor, execute, queue, style, terminal};
+
+    let mut out = std::io::stdout();
+    terminal::enable_raw_mode()?;
+    execute!(out, terminal::EnterAlternateScreen, cursor::Hide)?;
+    let result = (|| -> MlPrepResult<()> {
+        loop {
+            let (width, height) = terminal::size()?;
+            viewer.resize(height as usize)?;
+            queue!(out, terminal::Clear(terminal::ClearType::All))?;
+            for (row, line) in viewer.render(width as usize, true).iter().enumerate() {
+                queue!(out, cursor::MoveTo(0, row as u16), style::Print(line))?;
+            }
+            out.flush()?;
+            if let Event::Key(key) = event::read()? {
+                if key.kind != KeyEventKind::Press {
+                    continue;
+                }
+                if let Some(action) = action_for(key.code) {
+                    if !viewer.apply(action)? {
+                        return Ok(());
+                    }
+                }
+            }
+        }
+    })();
+    execute!(out, cursor::Show, terminal::LeaveAlternateScreen)?;
+    terminal::disable_raw_mode()?;
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_viewer_pages_sorts_and_hides() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("orders.csv");
+        let mut csv = "id,city,price,email\n".to_string();
+        for i in 0..25 {
+            csv.push_str(&format!("{},city{},{}.5,u{}@x\n", i, i % 3, 100 - i, i));
+        }
+        std::fs::write(&path, csv).unwrap();
+        let security_context = SecurityContext::new(crate::security::SecurityConfig {
+            allowed_paths: None,
+            mask_columns: Some(vec!["email".to_string()]),
+        })
+        .unwrap();
+        let mut viewer = Viewer::open(&path, None, &[], 10, &security_context).unwrap();
+
+        let lines = viewer.render(200, false);
+        assert!(
+            lines[0].ends_with("orders.csv  rows 1-10 of 25"),
+            "{}",
+            lines[0]
+        );
+        assert_eq!(lines[1], "[id] │ city  │ price │ email");
+        assert_eq!(lines[2], "i64  │ str   │ f64   │ str");
+        assert_eq!(lines[3], "─────┼───────┼───────┼──────");
+        assert_eq!(lines[4], "0    │ city0 │ 100.5 │ ***");
+        assert_eq!(lines.len(), 14);
+
+        viewer.apply(Action::Last).unwrap();
+        assert!(viewer.render(200, false)[0].ends_with("rows 16-25 of 25"));
+        viewer.apply(Action::NextPage).unwrap();
+        viewer.apply(Action::Up).unwrap();
+        assert!(viewer.render(200, false)[0].ends_with("rows 15-24 of 25"));
+
+        viewer.apply(Action::Right).unwrap();
+        viewer.apply(Action::Right).unwrap();
+        viewer.apply(Action::SortAscending).unwrap();
+        let lines = viewer.render(200, false);
+        assert!(
+            lines[0].ends_with("rows 1-10 of 25  sorted by price ↑"),
+            "{}",
+            lines[0]
+        );
+        assert_eq!(lines[4], "24  │ city0 │ 76.5    │ ***");
+
+        viewer.apply(Action::Hide).unwrap();
+        viewer.apply(Action::Left).unwrap();
+        viewer.apply(Action::Hide).unwrap();
+        let lines = viewer.render(200, false);
+        assert!(lines[0].contains("2 columns hidden"), "{}", lines[0]);
+        assert_eq!(lines[1], "id  │ [email]");
+
+        // Narrow terminals scroll to the selected column and cut off what does not fit
+        viewer.apply(Action::ShowAll).unwrap();
+        viewer.apply(Action::Right).unwrap();
+        viewer.apply(Action::Right).unwrap();
+        viewer.apply(Action::Right).unwrap();
+        let lines = viewer.render(16, true);
+        assert_eq!(lines[1], "price │ [email]");
+        assert_eq!(lines[14], "q quit  ↑↓ row …");
+        assert!(!viewer.apply(Action::Quit).unwrap());
+
+        let viewer =
+            Viewer::open(&path, None, &["city".to_string()], 5, &security_context).unwrap();
+        assert_eq!(viewer.render(80, false)[1], "[city]");
+        assert!(Viewer::open(&path, None, &["nope".to_string()], 5, &security_context).is_err());
+    }
+}
@@ -0,0 +1,126 @@
+//! Watch mode support
+//!
+//! Polls the pipeline YAML and the files it reads for modification-time
+//! changes so `mlprep run --watch` can re-execute on every save.
+
+use crate::dsl::{Pipeline, Step};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+/// Files whose changes should trigger a re-run: the pipeline itself, its inputs, join
+/// sources and checks files.
+pub fn watched_files(pipeline_path: &Path) -> Vec<PathBuf> {
+    let mut files = vec![pipeline_path.to_path_buf()];
+    // A YAML that currently fails to parse is still watched so fixing it triggers a run.
+    if let Ok(pipeline) = Pipeline::from_path(pipeline_path) {
+        for input in &pipeline.inputs {
+            files.push(input.path.clone());
+            files.extend(input.contract.clone());
+            if let Some(manifest) = &input.manifest {
+                let entries = crate::manifest::load(input, manifest).unwrap_or_default();
+                files.extend(entries.into_iter().map(|entry| entry.path));
+            }
+        }
+        for step in &pipeline.steps {
+            match step {
+                Step::Join(join) => {
+                    files.extend(join.right_input().ok().map(|(path, _)| path.to_path_buf()));
+                }
+                Step::Validate(validate) => files.extend(validate.checks_path.clone()),
+                _ => {}
+            }
+        }
+    }
+    files.sort();
+    files.dedup();
+    files
+}
+
+/// Modification times of `paths`; missing files are recorded as `None`.
+pub fn snapshot(paths: &[PathBuf]) -> HashMap<PathBuf, Option<SystemTime>> {
+    paths
+        .iter()
+        .map(|p| {
+            let modified = std::fs::metadata(p).and_then(|m| m.modified()).ok();
+            (p.clone(), modified)
+        })
+        .collect()
+}
+
+/// Block until any of `paths` is created, removed or modified. Returns the changed paths.
+pub fn wait_for_change(paths: &[PathBuf], interval: Duration) -> Vec<PathBuf> {
+    let before = snapshot(paths);
+    loop {
+        std::thread::sleep(interval);
+        let after = snapshot(paths);
+        let changed: Vec<PathBuf> = paths
+            .iter()
+            .filter(|p| before.get(*p) != after.get(*p))
+            .cloned()
+            .collect();
+        if !changed.is_empty() {
+            return changed;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_watched_files_include_inputs_and_joins() {
+        let dir = tempdir().unwrap();
+        let pipeline_path = dir.path().join("pipeline.yaml");
+        fs::write(
+            &pipeline_path,
+            r#"
+inputs:
+  - path: "data.csv"
+    contract: "data.contract.yaml"
+steps:
+  - type: join
+    right_path: "lookup.csv"
+    left_on: ["id"]
+    right_on: ["id"]
+  - type: validate
+    checks_path: "shared/checks.yaml"
+"#,
+        )
+        .unwrap();
+
+        let files = watched_files(&pipeline_path);
+        assert!(files.contains(&pipeline_path));
+        assert!(files.contains(&PathBuf::from("data.csv")));
+        assert!(files.contains(&PathBuf::from("data.contract.yaml")));
+        assert!(files.contains(&PathBuf::from("lookup.csv")));
+        // Checks files are relative to the pipeline, unlike data paths
+        assert!(files.contains(&dir.path().join("shared/checks.yaml")));
+    }
+
+    #[test]
+    fn test_wait_for_change_detects_modification() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("input.csv");
+        fs::write(&path, "a\n1").unwrap();
+        let paths = vec![path.clone()];
+
+        let writer_path = path.clone();
+        let handle = std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(50));
+            let file = fs::OpenOptions::new()
+                .write(true)
+                .open(&writer_path)
+                .unwrap();
+            file.set_modified(SystemTime::now() + Duration::from_secs(5))
+                .unwrap();
+        });
+
+        let changed = wait_for_change(&paths, Duration::from_millis(10));
+        handle.join().unwrap();
+        assert_eq!(changed, paths);
+    }
+}
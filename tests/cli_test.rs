@@ -46,3 +46,2549 @@ outputs:
     let metadata = fs::metadata(&output_path).unwrap();
     assert!(metadata.len() > 0);
 }
+
+#[test]
+fn test_cli_run_json_summary() {
+    let dir = tempdir().unwrap();
+    let input_path = dir.path().join("input.csv");
+    let output_path = dir.path().join("output.csv");
+    let config_path = dir.path().join("pipeline.yaml");
+
+    fs::write(&input_path, "a,b\n1,10\n2,20\n3,30").unwrap();
+    let yaml = format!(
+        r#"
+inputs:
+  - path: "{input}"
+steps:
+  - type: filter
+    condition: "a >= 2"
+outputs:
+  - path: "{output}"
+"#,
+        input = input_path.to_str().unwrap(),
+        output = output_path.to_str().unwrap()
+    );
+    fs::write(&config_path, yaml).unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_mlprep"))
+        .args([
+            "run",
+            config_path.to_str().unwrap(),
+            "--output-format",
+            "json",
+        ])
+        .output()
+        .expect("Failed to run mlprep");
+    assert!(output.status.success());
+
+    // stdout carries only the summary; logs go to stderr
+    let summary: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(summary["status"], "success");
+    assert_eq!(summary["rows_written"], 2);
+    assert_eq!(summary["outputs"][0], output_path.to_str().unwrap());
+    let lineage = summary["lineage"].as_str().unwrap();
+    let lineage: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(lineage).unwrap()).unwrap();
+    assert_eq!(lineage["columns"][1]["column"], "b");
+    assert_eq!(
+        lineage["columns"][1]["sources"][0]["input"],
+        input_path.to_str().unwrap()
+    );
+    assert_eq!(lineage["columns"][1]["sources"][0]["column"], "b");
+    assert!(summary["runtime"]["threads"].as_u64().unwrap() > 0);
+    assert_eq!(summary["runtime"]["cache"], true);
+}
+
+#[test]
+fn test_cli_run_shared_checks_file() {
+    let dir = tempdir().unwrap();
+    let project = dir.path().join("project");
+    fs::create_dir(&project).unwrap();
+    fs::create_dir(dir.path().join("shared")).unwrap();
+    let input_path = project.join("input.csv");
+    let output_path = project.join("output.csv");
+    let config_path = project.join("pipeline.yaml");
+
+    fs::write(&input_path, "a,b\n1,10\n2,\n3,30\n").unwrap();
+    fs::write(
+        dir.path().join("shared/checks.yaml"),
+        "columns:\n  - name: b\n    not_null: true\n",
+    )
+    .unwrap();
+    let yaml = format!(
+        r#"
+inputs:
+  - path: "{input}"
+steps:
+  - type: validate
+    mode: warn
+    checks_path: "../shared/checks.yaml"
+    checks:
+      columns:
+        - name: a
+          range: [0, 2]
+outputs:
+  - path: "{output}"
+"#,
+        input = input_path.to_str().unwrap(),
+        output = output_path.to_str().unwrap()
+    );
+    fs::write(&config_path, yaml).unwrap();
+
+    let run = |allowed: &std::path::Path| {
+        Command::new(env!("CARGO_BIN_EXE_mlprep"))
+            .args([
+                "run",
+                config_path.to_str().unwrap(),
+                "--output-format",
+                "json",
+                "--allowed-paths",
+                allowed.to_str().unwrap(),
+            ])
+            .output()
+            .expect("Failed to run mlprep")
+    };
+
+    let output = run(dir.path());
+    assert!(output.status.success());
+    let summary: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let codes: Vec<&str> = summary["warnings"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|w| w["code"].as_str().unwrap())
+        .collect();
+    // The shared checks run before the inline ones
+    assert_eq!(
+        codes,
+        ["validate.not_null", "validate.range"],
+        "{}",
+        summary
+    );
+
+    // The checks file is read through the sandbox too
+    fs::remove_file(&output_path).unwrap();
+    let output = run(&project);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("checks.yaml"), "{}", stderr);
+    assert!(!output_path.exists());
+}
+
+#[test]
+fn test_cli_run_json_summary_warnings() {
+    let dir = tempdir().unwrap();
+    let input_path = dir.path().join("input.csv");
+    let output_path = dir.path().join("output.csv");
+    let config_path = dir.path().join("pipeline.yaml");
+
+    fs::write(&input_path, "a,b\n1,10\n2,\n3,30\n4,\n").unwrap();
+    let yaml = format!(
+        r#"
+inputs:
+  - path: "{input}"
+steps:
+  - type: filter
+    condition: "a >= 2"
+  - type: validate
+    mode: warn
+    checks:
+      columns:
+        - name: a
+          range: [0, 3]
+        - name: b
+          not_null: true
+outputs:
+  - path: "{output}"
+"#,
+        input = input_path.to_str().unwrap(),
+        output = output_path.to_str().unwrap()
+    );
+    fs::write(&config_path, yaml).unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_mlprep"))
+        .args([
+            "run",
+            config_path.to_str().unwrap(),
+            "--output-format",
+            "json",
+        ])
+        .output()
+        .expect("Failed to run mlprep");
+    assert!(output.status.success());
+
+    let summary: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(summary["violations"], 3);
+    // Each check fails some of the three remaining rows: (2/3 + 1/3) / 2
+    assert_eq!(summary["quality_score"], 0.5);
+    let warnings = summary["warnings"].as_array().unwrap();
+    let codes: Vec<(&str, u64)> = warnings
+        .iter()
+        .map(|w| (w["code"].as_str().unwrap(), w["count"].as_u64().unwrap()))
+        .collect();
+    assert_eq!(
+        codes,
+        [("validate.range", 1), ("validate.not_null", 2)],
+        "{}",
+        summary
+    );
+    for warning in warnings {
+        assert_eq!(warning["step"], 2);
+        assert_eq!(warning["kind"], "validate");
+        assert!(warning["message"].as_str().unwrap().starts_with("Column"));
+    }
+}
+
+#[test]
+fn test_cli_run_detects_extensionless_inputs() {
+    let dir = tempdir().unwrap();
+    // Object-store style names: the content decides the format.
+    let input_path = dir.path().join("events-000");
+    let lookup_path = dir.path().join("lookup-000");
+    let output_path = dir.path().join("output.csv");
+    let config_path = dir.path().join("pipeline.yaml");
+
+    fs::write(
+        &input_path,
+        "{\"id\": 1, \"v\": 10}\n{\"id\": 2, \"v\": 20}\n{\"id\": 3, \"v\": 30}\n",
+    )
+    .unwrap();
+    fs::write(&lookup_path, "id,name\n1,a\n2,b\n").unwrap();
+    let yaml = format!(
+        r#"
+inputs:
+  - path: "{input}"
+steps:
+  - type: join
+    right_path: "{lookup}"
+    left_on: [id]
+    right_on: [id]
+  - type: sort
+    by: [id]
+outputs:
+  - path: "{output}"
+"#,
+        input = input_path.to_str().unwrap(),
+        lookup = lookup_path.to_str().unwrap(),
+        output = output_path.to_str().unwrap()
+    );
+    fs::write(&config_path, yaml).unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_mlprep"))
+        .args([
+            "run",
+            config_path.to_str().unwrap(),
+            "--output-format",
+            "json",
+        ])
+        .output()
+        .expect("Failed to run mlprep");
+    assert!(output.status.success());
+
+    let summary: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(summary["rows_read"], 3);
+    assert_eq!(summary["rows_written"], 2);
+    let text = fs::read_to_string(&output_path).unwrap();
+    assert_eq!(text, "id,v,name\n1,10,a\n2,20,b\n");
+}
+
+#[test]
+fn test_cli_contract_generate_and_enforce() {
+    let dir = tempdir().unwrap();
+    let input_path = dir.path().join("input.csv");
+    let drifted_path = dir.path().join("drifted.csv");
+    let contract_path = dir.path().join("contract.yaml");
+    let output_path = dir.path().join("output.csv");
+
+    fs::write(&input_path, "id,price\n1,9.5\n2,20.0\n").unwrap();
+    fs::write(&drifted_path, "id,price,note\n1,-3.0,x\n,4.0,y\n").unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_mlprep"))
+        .args([
+            "contract",
+            "generate",
+            input_path.to_str().unwrap(),
+            "-o",
+            contract_path.to_str().unwrap(),
+        ])
+        .output()
+        .expect("Failed to run mlprep");
+    assert!(output.status.success());
+    let contract = fs::read_to_string(&contract_path).unwrap();
+    assert!(contract.contains("name: price"));
+    assert!(contract.contains("dtype: Float64"));
+
+    let run = |input: &std::path::Path| {
+        let config_path = dir.path().join("pipeline.yaml");
+        let yaml = format!(
+            r#"
+inputs:
+  - path: "{input}"
+    contract: "{contract}"
+steps: []
+outputs:
+  - path: "{output}"
+"#,
+            input = input.to_str().unwrap(),
+            contract = contract_path.to_str().unwrap(),
+            output = output_path.to_str().unwrap()
+        );
+        fs::write(&config_path, yaml).unwrap();
+        Command::new(env!("CARGO_BIN_EXE_mlprep"))
+            .args(["run", config_path.to_str().unwrap()])
+            .output()
+            .expect("Failed to run mlprep")
+    };
+
+    assert!(run(&input_path).status.success());
+    assert!(output_path.exists());
+    fs::remove_file(&output_path).unwrap();
+
+    let output = run(&drifted_path);
+    assert!(!output.status.success());
+    assert!(!output_path.exists());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("MLPREP-008"), "stderr: {}", stderr);
+    assert!(stderr.contains("+ note"), "stderr: {}", stderr);
+}
+
+#[test]
+fn test_cli_run_chunked() {
+    let dir = tempdir().unwrap();
+    let input_path = dir.path().join("input.csv");
+    let output_path = dir.path().join("output.csv");
+    let config_path = dir.path().join("pipeline.yaml");
+
+    fs::write(&input_path, "a,b\n1,10\n2,\n3,30\n4,40\n5,").unwrap();
+    let yaml = format!(
+        r#"
+inputs:
+  - path: "{input}"
+steps:
+  - type: filter
+    condition: "a >= 2"
+  - type: validate
+    mode: warn
+    checks:
+      columns:
+        - name: b
+          not_null: true
+outputs:
+  - path: "{output}"
+"#,
+        input = input_path.to_str().unwrap(),
+        output = output_path.to_str().unwrap()
+    );
+    fs::write(&config_path, yaml).unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_mlprep"))
+        .args([
+            "run",
+            config_path.to_str().unwrap(),
+            "--chunk-size",
+            "2",
+            "--output-format",
+            "json",
+        ])
+        .output()
+        .expect("Failed to run mlprep");
+    assert!(output.status.success());
+
+    let summary: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(summary["rows_read"], 5);
+    assert_eq!(summary["rows_written"], 4);
+    assert_eq!(summary["violations"], 2);
+    let text = fs::read_to_string(&output_path).unwrap();
+    assert_eq!(text, "a,b\n2,\n3,30\n4,40\n5,\n");
+}
+
+#[test]
+fn test_cli_bench_generated_data() {
+    let dir = tempdir().unwrap();
+    let data_path = dir.path().join("data.parquet");
+    let output_path = dir.path().join("output.parquet");
+    let config_path = dir.path().join("pipeline.yaml");
+    let yaml = format!(
+        r#"
+inputs:
+  - path: "{input}"
+steps:
+  - type: filter
+    condition: "price < 100"
+outputs:
+  - path: "{output}"
+"#,
+        input = dir.path().join("missing.csv").to_str().unwrap(),
+        output = output_path.to_str().unwrap()
+    );
+    fs::write(&config_path, yaml).unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_mlprep"))
+        .args([
+            "bench",
+            config_path.to_str().unwrap(),
+            "--generate",
+            data_path.to_str().unwrap(),
+            "--rows",
+            "5000",
+            "--iterations",
+            "2",
+            "--output-format",
+            "json",
+        ])
+        .output()
+        .expect("Failed to run mlprep");
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(report["input"], data_path.to_str().unwrap());
+    let phases = report["phases"].as_array().unwrap();
+    assert_eq!(phases.len(), 3);
+    assert_eq!(phases[0]["phase"], "read");
+    assert_eq!(phases[0]["rows"], 5000);
+    assert!(phases[1]["rows"].as_u64().unwrap() < 5000);
+    assert!(phases[2]["rows_per_sec"].as_f64().unwrap() > 0.0);
+    // The benchmark never writes the pipeline's own outputs.
+    assert!(!output_path.exists());
+
+    // Without a pipeline, only the data is generated.
+    let csv_path = dir.path().join("data.csv");
+    let status = Command::new(env!("CARGO_BIN_EXE_mlprep"))
+        .args([
+            "bench",
+            "--generate",
+            csv_path.to_str().unwrap(),
+            "--rows",
+            "10",
+            "--schema",
+            "id:id,city:str:3",
+        ])
+        .status()
+        .expect("Failed to run mlprep");
+    assert!(status.success());
+    let text = fs::read_to_string(&csv_path).unwrap();
+    assert_eq!(text.lines().next(), Some("id,city"));
+    assert_eq!(text.lines().count(), 11);
+}
+
+#[test]
+fn test_cli_run_parallel_jobs_aggregates_failures() {
+    let dir = tempdir().unwrap();
+    let input_path = dir.path().join("input.csv");
+    fs::write(&input_path, "a,b\n1,10\n2,20\n3,30").unwrap();
+
+    let good_output = dir.path().join("good.csv");
+    let good_config = dir.path().join("good.yaml");
+    fs::write(
+        &good_config,
+        format!(
+            "inputs:\n  - path: \"{}\"\nsteps: []\noutputs:\n  - path: \"{}\"\n",
+            input_path.to_str().unwrap(),
+            good_output.to_str().unwrap()
+        ),
+    )
+    .unwrap();
+    let bad_config = dir.path().join("bad.yaml");
+    fs::write(
+        &bad_config,
+        "inputs:\n  - path: \"missing.csv\"\nsteps: []\n",
+    )
+    .unwrap();
+
+    // The failing pipeline comes first; the other one must still run.
+    let output = Command::new(env!("CARGO_BIN_EXE_mlprep"))
+        .args([
+            "run",
+            bad_config.to_str().unwrap(),
+            good_config.to_str().unwrap(),
+            "--jobs",
+            "2",
+            "--output-format",
+            "json",
+        ])
+        .output()
+        .expect("Failed to run mlprep");
+
+    assert!(!output.status.success());
+    assert!(good_output.exists());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let statuses: Vec<String> = stdout
+        .lines()
+        .map(|l| serde_json::from_str::<serde_json::Value>(l).unwrap()["status"].to_string())
+        .collect();
+    assert_eq!(statuses, vec!["\"failed\"", "\"success\""]);
+}
+
+#[test]
+fn test_cli_run_retries_transient_io_errors() {
+    use std::io::{BufRead, BufReader, Write};
+
+    let dir = tempdir().unwrap();
+    let config_path = dir.path().join("pipeline.yaml");
+    let write_config = |input: &str| {
+        fs::write(
+            &config_path,
+            format!(
+                "inputs:\n  - path: \"{}\"\nsteps: []\noutputs:\n  - path: \"{}\"\n",
+                input,
+                dir.path().join("out.csv").to_str().unwrap()
+            ),
+        )
+        .unwrap();
+    };
+    let run = |retries: &str, backoff: &str| {
+        Command::new(env!("CARGO_BIN_EXE_mlprep"))
+            .args([
+                "run",
+                config_path.to_str().unwrap(),
+                "--retries",
+                retries,
+                "--retry-backoff",
+                backoff,
+                "--output-format",
+                "json",
+            ])
+            .env("XDG_CACHE_HOME", dir.path().join("cache"))
+            .stdout(std::process::Stdio::piped())
+            .stderr(std::process::Stdio::null())
+            .spawn()
+            .expect("Failed to run mlprep")
+    };
+    let attempts = |output: &std::process::Output| {
+        let summary: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+        summary["attempts"].as_u64().unwrap()
+    };
+
+    // A missing file is not transient: no retries, and no backoff
+    write_config(dir.path().join("missing.csv").to_str().unwrap());
+    let started = std::time::Instant::now();
+    let output = run("3", "10s").wait_with_output().unwrap();
+    assert!(!output.status.success());
+    assert_eq!(attempts(&output), 1);
+    assert!(started.elapsed() < std::time::Duration::from_secs(5));
+
+    // A server that cannot be reached is retried, and a failed run reports every attempt
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    drop(listener);
+    write_config(&format!("http://{}/late.csv", addr));
+    let output = run("2", "50ms").wait_with_output().unwrap();
+    assert!(!output.status.success());
+    assert_eq!(attempts(&output), 3);
+
+    // The server comes up while the runner is backing off after the first attempt.
+    let child = run("3", "500ms");
+    std::thread::sleep(std::time::Duration::from_millis(200));
+    let listener = std::net::TcpListener::bind(addr).unwrap();
+    std::thread::spawn(move || {
+        let body = "a\n1\n2\n";
+        for stream in listener.incoming() {
+            let mut stream = stream.unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut request = String::new();
+            reader.read_line(&mut request).unwrap();
+            let mut line = String::new();
+            while reader.read_line(&mut line).unwrap() > 2 {
+                line.clear();
+            }
+            let _ = write!(
+                stream,
+                "HTTP/1.1 200 OK\r\nETag: \"v1\"\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                body.len(),
+                if request.starts_with("GET") { body } else { "" }
+            );
+        }
+    });
+    let output = child.wait_with_output().unwrap();
+    assert!(output.status.success());
+    assert_eq!(attempts(&output), 2);
+}
+
+#[test]
+fn test_cli_on_failure_hooks() {
+    let dir = tempdir().unwrap();
+    let input_path = dir.path().join("input.csv");
+    let output_path = dir.path().join("output.csv");
+    let marker_path = dir.path().join("_FAILED");
+    let config_path = dir.path().join("pipeline.yaml");
+
+    fs::write(&input_path, "a\n1\n\n3").unwrap();
+    // Output of an earlier run: the failed run never replaced it, so it is kept.
+    fs::write(&output_path, "a\n1").unwrap();
+    let yaml = format!(
+        r#"
+inputs:
+  - path: "{input}"
+steps:
+  - type: validate
+    checks:
+      columns:
+        - name: a
+          not_null: true
+outputs:
+  - path: "{output}"
+on_failure:
+  - type: write_marker
+    path: "{marker}"
+  - type: delete_outputs
+"#,
+        input = input_path.to_str().unwrap(),
+        output = output_path.to_str().unwrap(),
+        marker = marker_path.to_str().unwrap()
+    );
+    fs::write(&config_path, yaml).unwrap();
+
+    let status = Command::new(env!("CARGO_BIN_EXE_mlprep"))
+        .args(["run", config_path.to_str().unwrap()])
+        .status()
+        .expect("Failed to run mlprep");
+
+    assert!(!status.success());
+    assert_eq!(fs::read_to_string(&output_path).unwrap(), "a\n1");
+    let marker: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(&marker_path).unwrap()).unwrap();
+    assert_eq!(marker["status"], "failed");
+
+    // A run that fails after writing its output removes what it wrote: the Feast
+    // definition names a column the output lacks.
+    let yaml = format!(
+        r#"
+inputs:
+  - path: "{input}"
+steps: []
+outputs:
+  - path: "{output}"
+    feast:
+      entities: [a]
+      timestamp_field: missing
+on_failure:
+  - type: delete_outputs
+"#,
+        input = input_path.to_str().unwrap(),
+        output = output_path.to_str().unwrap(),
+    );
+    fs::write(&config_path, yaml).unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_mlprep"))
+        .args(["run", config_path.to_str().unwrap()])
+        .output()
+        .expect("Failed to run mlprep");
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("timestamp_field"), "{}", stderr);
+    assert!(!output_path.exists());
+}
+
+#[test]
+fn test_cli_output_modes() {
+    let dir = tempdir().unwrap();
+    let input_path = dir.path().join("input.csv");
+    fs::write(&input_path, "a,b\n1,10\n2,20").unwrap();
+    let write_config = |name: &str, output: &str, mode: &str| {
+        let config_path = dir.path().join(name);
+        fs::write(
+            &config_path,
+            format!(
+                "inputs:\n  - path: \"{}\"\nsteps: []\noutputs:\n  - path: \"{}\"\n    mode: {}\n",
+                input_path.to_str().unwrap(),
+                output,
+                mode
+            ),
+        )
+        .unwrap();
+        config_path
+    };
+    let run = |config: &std::path::Path| {
+        Command::new(env!("CARGO_BIN_EXE_mlprep"))
+            .args(["run", config.to_str().unwrap()])
+            .status()
+            .expect("Failed to run mlprep")
+            .success()
+    };
+
+    // Appending to a CSV file adds rows
+    let csv_out = dir.path().join("out.csv");
+    let append_csv = write_config("append_csv.yaml", csv_out.to_str().unwrap(), "append");
+    assert!(run(&append_csv));
+    assert!(run(&append_csv));
+    assert_eq!(fs::read_to_string(&csv_out).unwrap().lines().count(), 5);
+
+    // Appending to a dataset directory adds a part file per run
+    let dataset = dir.path().join("dataset");
+    let append_dir = write_config(
+        "append_dir.yaml",
+        &format!("{}/", dataset.to_str().unwrap()),
+        "append",
+    );
+    assert!(run(&append_dir));
+    assert!(run(&append_dir));
+    assert_eq!(fs::read_dir(&dataset).unwrap().count(), 2);
+
+    // error_if_exists refuses to clobber
+    let guarded = write_config("guarded.yaml", csv_out.to_str().unwrap(), "error_if_exists");
+    assert!(!run(&guarded));
+    assert_eq!(fs::read_to_string(&csv_out).unwrap().lines().count(), 5);
+}
+
+#[test]
+fn test_cli_output_modes_with_delete_outputs() {
+    let dir = tempdir().unwrap();
+    let input_path = dir.path().join("input.csv");
+    fs::write(&input_path, "a,b\n1,10\n2,20").unwrap();
+    // Fails before writing (a violated check) or after (a Feast definition naming a
+    // column the output lacks); either way delete_outputs must keep earlier outputs.
+    let run = |output: &str, mode: &str, after_write: bool| {
+        let config_path = dir.path().join("pipeline.yaml");
+        let (steps, feast) = if after_write {
+            (
+                "[]",
+                "\n    feast:\n      entities: [a]\n      timestamp_field: missing",
+            )
+        } else {
+            ("\n  - type: validate\n    checks:\n      columns:\n        - name: a\n          range: [5, 9]", "")
+        };
+        fs::write(
+            &config_path,
+            format!(
+                "inputs:\n  - path: \"{}\"\nsteps: {}\noutputs:\n  - path: \"{}\"\n    mode: {}{}\non_failure:\n  - type: delete_outputs\n",
+                input_path.to_str().unwrap(),
+                steps,
+                output,
+                mode,
+                feast
+            ),
+        )
+        .unwrap();
+        let output = Command::new(env!("CARGO_BIN_EXE_mlprep"))
+            .args(["run", config_path.to_str().unwrap()])
+            .output()
+            .expect("Failed to run mlprep");
+        assert!(!output.status.success());
+    };
+
+    // error_if_exists: the refused target is not this run's output
+    let prod = dir.path().join("prod.csv");
+    fs::write(&prod, "a,b\n7,70\n").unwrap();
+    run(prod.to_str().unwrap(), "error_if_exists", false);
+    run(prod.to_str().unwrap(), "error_if_exists", true);
+    assert_eq!(fs::read_to_string(&prod).unwrap(), "a,b\n7,70\n");
+
+    // append to a file: the file still holds the earlier rows, so it is kept
+    run(prod.to_str().unwrap(), "append", false);
+    assert_eq!(fs::read_to_string(&prod).unwrap(), "a,b\n7,70\n");
+    run(prod.to_str().unwrap(), "append", true);
+    assert!(fs::read_to_string(&prod)
+        .unwrap()
+        .starts_with("a,b\n7,70\n"));
+
+    // append to a dataset directory: only the part this run added is removed
+    let dataset = dir.path().join("ds");
+    fs::create_dir_all(&dataset).unwrap();
+    fs::write(dataset.join("part-0.csv"), "a,b\n7,70\n").unwrap();
+    let dataset_path = format!("{}/", dataset.to_str().unwrap());
+    let with_format = |mode: &str| format!("{}\n    format: csv", mode);
+    run(&dataset_path, &with_format("append"), false);
+    run(&dataset_path, &with_format("append"), true);
+    let parts: Vec<_> = fs::read_dir(&dataset)
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name())
+        .collect();
+    assert_eq!(parts, ["part-0.csv"]);
+}
+
+#[test]
+fn test_cli_split_outputs() {
+    use polars::prelude::{ParquetReader, SerReader};
+
+    let dir = tempdir().unwrap();
+    let input_path = dir.path().join("input.csv");
+    let rows: String = (0..2000)
+        .map(|i| format!("{},name-{}\n", i, i * 7))
+        .collect();
+    fs::write(&input_path, format!("id,name\n{}", rows)).unwrap();
+    let config_path = dir.path().join("pipeline.yaml");
+    let run = |output: &str| {
+        fs::write(
+            &config_path,
+            format!(
+                "inputs:\n  - path: \"{}\"\nsteps: []\noutputs:\n{}",
+                input_path.display(),
+                output
+            ),
+        )
+        .unwrap();
+        let output = Command::new(env!("CARGO_BIN_EXE_mlprep"))
+            .args(["run", config_path.to_str().unwrap()])
+            .output()
+            .expect("Failed to run mlprep");
+        assert!(
+            output.status.success(),
+            "{}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+    };
+    let lines = |name: &str| {
+        fs::read_to_string(dir.path().join(name))
+            .unwrap()
+            .lines()
+            .count()
+    };
+
+    // Row-limited parts next to the output path, each with its own header
+    let csv_out = dir.path().join("out.csv");
+    run(&format!(
+        "  - path: \"{}\"\n    max_rows_per_file: 800\n",
+        csv_out.display()
+    ));
+    assert!(!csv_out.exists());
+    assert_eq!(lines("out-00000.csv"), 801);
+    assert_eq!(lines("out-00001.csv"), 801);
+    assert_eq!(lines("out-00002.csv"), 401);
+
+    // A rerun with fewer parts removes the stale ones
+    run(&format!(
+        "  - path: \"{}\"\n    max_rows_per_file: 1000\n",
+        csv_out.display()
+    ));
+    assert_eq!(lines("out-00001.csv"), 1001);
+    assert!(!dir.path().join("out-00002.csv").exists());
+
+    // Size-limited Parquet parts in a dataset directory
+    let dataset = dir.path().join("dataset");
+    let output = format!(
+        "  - path: \"{}/\"\n    max_file_size: 4KB\n    mode: append\n",
+        dataset.display()
+    );
+    run(&output);
+    let parts = || {
+        let mut parts: Vec<_> = fs::read_dir(&dataset)
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .collect();
+        parts.sort();
+        parts
+    };
+    let first = parts();
+    assert!(first.len() > 1);
+    let mut total = 0;
+    for part in &first {
+        assert!(fs::metadata(part).unwrap().len() <= 4096, "{:?}", part);
+        let file = fs::File::open(part).unwrap();
+        total += ParquetReader::new(file).finish().unwrap().height();
+    }
+    assert_eq!(total, 2000);
+    assert!(first[0].ends_with("part-00000.parquet"));
+
+    // Appending continues the numbering
+    run(&output);
+    let appended = parts();
+    assert_eq!(appended.len(), 2 * first.len());
+
+    // A failed append removes only the parts it added (the Feast definition names a
+    // column the output lacks, so the run fails after writing)
+    fs::write(
+        &config_path,
+        format!(
+            "inputs:\n  - path: \"{}\"\nsteps: []\noutputs:\n{}    feast:\n      entities: [id]\n      timestamp_field: missing\non_failure:\n  - type: delete_outputs\n",
+            input_path.display(),
+            output
+        ),
+    )
+    .unwrap();
+    let status = Command::new(env!("CARGO_BIN_EXE_mlprep"))
+        .args(["run", config_path.to_str().unwrap()])
+        .status()
+        .expect("Failed to run mlprep");
+    assert!(!status.success());
+    assert_eq!(parts(), appended);
+}
+
+#[test]
+fn test_cli_dry_run_reports_schema_without_writing() {
+    let dir = tempdir().unwrap();
+    let input_path = dir.path().join("input.csv");
+    let output_path = dir.path().join("output.parquet");
+    let config_path = dir.path().join("pipeline.yaml");
+
+    fs::write(&input_path, "a,b\n1,10\n2,20\n3,30").unwrap();
+    let yaml = format!(
+        r#"
+inputs:
+  - path: "{input}"
+steps:
+  - type: filter
+    condition: "a >= 2"
+  - type: cast
+    columns:
+      b: "Float64"
+  - type: select
+    columns: ["b"]
+outputs:
+  - path: "{output}"
+"#,
+        input = input_path.to_str().unwrap(),
+        output = output_path.to_str().unwrap()
+    );
+    fs::write(&config_path, yaml).unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_mlprep"))
+        .args([
+            "run",
+            config_path.to_str().unwrap(),
+            "--dry-run",
+            "--sample",
+            "2",
+            "--output-format",
+            "json",
+        ])
+        .output()
+        .expect("Failed to run mlprep");
+
+    assert!(output.status.success());
+    assert!(!output_path.exists());
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let steps = report["steps"].as_array().unwrap();
+    assert_eq!(steps.len(), 4);
+    assert_eq!(steps[0]["sample_rows"], 2);
+    assert_eq!(steps[1]["sample_rows"], 1);
+    assert_eq!(steps[3]["columns"], serde_json::json!([["b", "f64"]]));
+}
+
+#[test]
+fn test_cli_dry_run_without_sample_reads_no_rows() {
+    let dir = tempdir().unwrap();
+    let input_path = dir.path().join("input.csv");
+    let config_path = dir.path().join("pipeline.yaml");
+
+    // A null the strict check would fail on, if the dry run read it
+    fs::write(&input_path, "a,b\n1,10\n,20\n3,30").unwrap();
+    let yaml = format!(
+        r#"
+inputs:
+  - path: "{input}"
+steps:
+  - type: validate
+    checks:
+      columns:
+        - name: a
+          not_null: true
+  - type: features
+    config:
+      features:
+        - column: b
+          transform: standard_scale
+outputs:
+  - path: "{output}"
+"#,
+        input = input_path.to_str().unwrap(),
+        output = dir.path().join("output.csv").to_str().unwrap()
+    );
+    fs::write(&config_path, yaml).unwrap();
+
+    let dry_run = |sample: &[&str]| {
+        Command::new(env!("CARGO_BIN_EXE_mlprep"))
+            .args(["run", config_path.to_str().unwrap(), "--dry-run"])
+            .args(sample)
+            .args(["--output-format", "json"])
+            .output()
+            .expect("Failed to run mlprep")
+    };
+    let output = dry_run(&[]);
+    assert!(output.status.success());
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(report["steps"].as_array().unwrap().len(), 3);
+    assert!(!dry_run(&["--sample", "3"]).status.success());
+}
+
+#[test]
+fn test_cli_explain_io_reports_pushdown() {
+    let dir = tempdir().unwrap();
+    let input_path = dir.path().join("input.csv");
+    let output_path = dir.path().join("output.csv");
+    let config_path = dir.path().join("pipeline.yaml");
+
+    fs::write(&input_path, "a,b,c\n1,10,x\n2,20,y\n3,30,z").unwrap();
+    let yaml = format!(
+        r#"
+inputs:
+  - path: "{input}"
+steps:
+  - type: filter
+    condition: "a >= 2"
+  - type: select
+    columns: ["b"]
+outputs:
+  - path: "{output}"
+"#,
+        input = input_path.to_str().unwrap(),
+        output = output_path.to_str().unwrap()
+    );
+    fs::write(&config_path, yaml).unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_mlprep"))
+        .args([
+            "run",
+            config_path.to_str().unwrap(),
+            "--explain-io",
+            "--output-format",
+            "json",
+        ])
+        .output()
+        .expect("Failed to run mlprep");
+    assert!(output.status.success());
+
+    let summary: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let scans = summary["scans"].as_array().unwrap();
+    assert_eq!(scans.len(), 1);
+    assert_eq!(scans[0]["format"], "csv");
+    assert_eq!(scans[0]["total_columns"], 3);
+    let mut columns: Vec<String> = scans[0]["columns"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|c| c.as_str().unwrap().to_string())
+        .collect();
+    columns.sort();
+    assert_eq!(columns, vec!["a", "b"]);
+    assert!(scans[0]["predicate"].is_string());
+}
+
+#[test]
+fn test_cli_export_state_onnx() {
+    let dir = tempdir().unwrap();
+    let input_path = dir.path().join("input.csv");
+    let pipeline_path = dir.path().join("pipeline.yaml");
+    let features_path = dir.path().join("features.yaml");
+    let state_path = dir.path().join("feature_state.json");
+    let onnx_path = dir.path().join("preprocess.onnx");
+
+    fs::write(&input_path, "price,city\n10,tokyo\n20,osaka\n30,tokyo").unwrap();
+    let features = r#"
+features:
+  - column: price
+    transform: standard_scale
+  - column: city
+    transform: one_hot_encode
+"#;
+    fs::write(&features_path, features).unwrap();
+    let indented: String = features.lines().map(|l| format!("      {}\n", l)).collect();
+    let yaml = format!(
+        r#"
+inputs:
+  - path: "{input}"
+steps:
+  - type: features
+    state_path: "{state}"
+    config:
+{indented}
+outputs:
+  - path: "{output}"
+"#,
+        input = input_path.to_str().unwrap(),
+        state = state_path.to_str().unwrap(),
+        output = dir.path().join("out.csv").to_str().unwrap(),
+    );
+    fs::write(&pipeline_path, yaml).unwrap();
+    let status = Command::new(env!("CARGO_BIN_EXE_mlprep"))
+        .args(["run", pipeline_path.to_str().unwrap()])
+        .status()
+        .expect("Failed to run mlprep");
+    assert!(status.success());
+
+    let status = Command::new(env!("CARGO_BIN_EXE_mlprep"))
+        .args([
+            "export-state",
+            "--config",
+            features_path.to_str().unwrap(),
+            "--state",
+            state_path.to_str().unwrap(),
+            "--format",
+            "onnx",
+            "--output",
+            onnx_path.to_str().unwrap(),
+        ])
+        .status()
+        .expect("Failed to run mlprep");
+    assert!(status.success());
+
+    let bytes = fs::read(&onnx_path).unwrap();
+    let text = String::from_utf8_lossy(&bytes);
+    assert!(text.contains("OneHotEncoder"));
+    assert!(text.contains("city_tokyo"));
+}
+
+#[test]
+fn test_cli_serve_ndjson_stdin() {
+    use std::io::Write;
+    use std::process::Stdio;
+
+    let dir = tempdir().unwrap();
+    let input_path = dir.path().join("input.csv");
+    let pipeline_path = dir.path().join("pipeline.yaml");
+    let features_path = dir.path().join("features.yaml");
+    let state_path = dir.path().join("feature_state.json");
+
+    fs::write(&input_path, "price,city\n10,tokyo\n20,osaka\n30,tokyo").unwrap();
+    let features = r#"
+features:
+  - column: price
+    transform: min_max_scale
+  - column: city
+    transform: one_hot_encode
+"#;
+    fs::write(&features_path, features).unwrap();
+    let indented: String = features.lines().map(|l| format!("      {}\n", l)).collect();
+    let yaml = format!(
+        r#"
+inputs:
+  - path: "{input}"
+steps:
+  - type: features
+    state_path: "{state}"
+    config:
+{indented}
+outputs:
+  - path: "{output}"
+"#,
+        input = input_path.to_str().unwrap(),
+        state = state_path.to_str().unwrap(),
+        output = dir.path().join("out.csv").to_str().unwrap(),
+    );
+    fs::write(&pipeline_path, yaml).unwrap();
+    let status = Command::new(env!("CARGO_BIN_EXE_mlprep"))
+        .args(["run", pipeline_path.to_str().unwrap()])
+        .status()
+        .expect("Failed to run mlprep");
+    assert!(status.success());
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_mlprep"))
+        .args([
+            "serve",
+            "--config",
+            features_path.to_str().unwrap(),
+            "--state",
+            state_path.to_str().unwrap(),
+        ])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .expect("Failed to run mlprep");
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(
+            b"{\"price\": 20, \"city\": \"osaka\"}\n[{\"price\": 30, \"city\": \"tokyo\"}]\n",
+        )
+        .unwrap();
+    let output = child.wait_with_output().unwrap();
+    assert!(output.status.success());
+
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let lines: Vec<serde_json::Value> = stdout
+        .lines()
+        .map(|l| serde_json::from_str(l).unwrap())
+        .collect();
+    assert_eq!(lines.len(), 2);
+    assert_eq!(lines[0]["price"], 0.5);
+    assert_eq!(lines[0]["city_osaka"], 1);
+    assert_eq!(lines[1][0]["price"], 1.0);
+    assert_eq!(lines[1][0]["city_tokyo"], 1);
+}
+
+#[test]
+fn test_cli_diff_runs() {
+    let dir = tempdir().unwrap();
+    let input_path = dir.path().join("input.csv");
+    let output_path = dir.path().join("output.csv");
+
+    let run = |name: &str, condition: &str| {
+        let run_dir = dir.path().join(name);
+        fs::create_dir(&run_dir).unwrap();
+        let config_path = run_dir.join("pipeline.yaml");
+        let yaml = format!(
+            r#"
+inputs:
+  - path: "{input}"
+steps:
+  - type: filter
+    condition: "{condition}"
+  - type: cast
+    columns: {{b: Float64}}
+outputs:
+  - path: "{output}"
+"#,
+            input = input_path.to_str().unwrap(),
+            output = output_path.to_str().unwrap()
+        );
+        fs::write(&config_path, yaml).unwrap();
+        let status = Command::new(env!("CARGO_BIN_EXE_mlprep"))
+            .args(["run", config_path.to_str().unwrap()])
+            .status()
+            .expect("Failed to run mlprep");
+        assert!(status.success());
+        run_dir
+    };
+
+    fs::write(&input_path, "a,b\n1,10\n2,20\n3,30\n").unwrap();
+    let run_a = run("a", "a >= 2");
+    fs::write(&input_path, "a,b\n1,10\n2,20\n3,30\n4,40\n").unwrap();
+    let run_b = run("b", "a >= 3");
+
+    let output = Command::new(env!("CARGO_BIN_EXE_mlprep"))
+        .args([
+            "diff-runs",
+            run_a.to_str().unwrap(),
+            run_b.to_str().unwrap(),
+            "--output-format",
+            "json",
+        ])
+        .output()
+        .expect("Failed to run mlprep");
+    assert!(output.status.success());
+    let diff: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(diff["inputs"][0]["change"], "changed");
+    assert_eq!(diff["steps"].as_array().unwrap().len(), 1);
+    assert_eq!(diff["steps"][0]["params"][0]["param"], "condition");
+    assert_eq!(diff["steps"][0]["params"][0]["b"], "a >= 3");
+    let counts = diff["counts"].as_array().unwrap();
+    assert_eq!(counts[0]["metric"], "rows_read");
+    assert_eq!(
+        (counts[0]["a"].as_u64(), counts[0]["b"].as_u64()),
+        (Some(3), Some(4))
+    );
+    assert!(diff["schema"].as_array().unwrap().is_empty());
+    assert_eq!(diff["outputs"][0]["change"], "changed");
+
+    let output = Command::new(env!("CARGO_BIN_EXE_mlprep"))
+        .args([
+            "diff-runs",
+            run_b.to_str().unwrap(),
+            run_b.to_str().unwrap(),
+        ])
+        .output()
+        .expect("Failed to run mlprep");
+    assert!(String::from_utf8_lossy(&output.stdout).contains("No differences"));
+}
+
+#[test]
+fn test_cli_diff() {
+    let dir = tempdir().unwrap();
+    let a_path = dir.path().join("a.csv");
+    let b_path = dir.path().join("b.csv");
+    fs::write(&a_path, "id,price,city\n1,1.0,x\n2,2.0,y\n3,3.0,z\n").unwrap();
+    fs::write(&b_path, "id,price,city\n2,2.5,y\n3,3.0,z\n4,4.0,w\n").unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_mlprep"))
+        .args([
+            "diff",
+            a_path.to_str().unwrap(),
+            b_path.to_str().unwrap(),
+            "--keys",
+            "id",
+            "--output-format",
+            "json",
+        ])
+        .output()
+        .expect("Failed to run mlprep");
+    assert!(output.status.success());
+    let diff: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(diff["added"], 1);
+    assert_eq!(diff["removed"], 1);
+    assert_eq!(diff["changed"], 1);
+    assert_eq!(diff["changed_keys"][0], "2");
+    assert_eq!(diff["columns"][0]["column"], "price");
+    assert_eq!(diff["columns"][0]["max_abs_diff"], 0.5);
+
+    let status = Command::new(env!("CARGO_BIN_EXE_mlprep"))
+        .args([
+            "diff",
+            a_path.to_str().unwrap(),
+            b_path.to_str().unwrap(),
+            "--keys",
+            "id",
+            "--exit-code",
+        ])
+        .status()
+        .expect("Failed to run mlprep");
+    assert_eq!(status.code(), Some(1));
+
+    let output = Command::new(env!("CARGO_BIN_EXE_mlprep"))
+        .args([
+            "diff",
+            a_path.to_str().unwrap(),
+            a_path.to_str().unwrap(),
+            "--keys",
+            "id",
+            "--exit-code",
+        ])
+        .output()
+        .expect("Failed to run mlprep");
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("Datasets are equivalent"));
+}
+
+#[test]
+fn test_cli_test_snapshot() {
+    let dir = tempdir().unwrap();
+    let input_path = dir.path().join("fixture.csv");
+    let config_path = dir.path().join("orders.yaml");
+    let snapshot_dir = dir.path().join("snapshots");
+    fs::write(&input_path, "id,amount\n1,10\n2,20\n3,30\n").unwrap();
+    let write_pipeline = |condition: &str| {
+        let yaml = format!(
+            r#"
+inputs:
+  - path: "{input}"
+steps:
+  - type: filter
+    condition: "{condition}"
+outputs:
+  - path: "{output}"
+"#,
+            input = input_path.to_str().unwrap(),
+            output = dir.path().join("out.csv").to_str().unwrap()
+        );
+        fs::write(&config_path, yaml).unwrap();
+    };
+    let test = |extra: &[&str]| {
+        Command::new(env!("CARGO_BIN_EXE_mlprep"))
+            .args([
+                "test",
+                config_path.to_str().unwrap(),
+                "--snapshot",
+                snapshot_dir.to_str().unwrap(),
+            ])
+            .args(extra)
+            .output()
+            .expect("Failed to run mlprep")
+    };
+
+    write_pipeline("amount > 10");
+    let output = test(&[]);
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("snapshot created"));
+    assert!(snapshot_dir.join("orders.snap.json").exists());
+    assert!(!dir.path().join("out.csv").exists());
+    assert!(test(&[]).status.success());
+
+    write_pipeline("amount > 20");
+    let output = test(&[]);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("rows: 2 -> 1"), "{}", stderr);
+
+    assert!(test(&["--update"]).status.success());
+    assert!(test(&[]).status.success());
+}
+
+#[test]
+fn test_cli_test_cases() {
+    let dir = tempdir().unwrap();
+    let config_path = dir.path().join("pipeline.yaml");
+    let write_pipeline = |expected_amount: u32| {
+        let yaml = format!(
+            r#"
+steps:
+  - type: filter
+    condition: "amount > 0"
+tests:
+  - name: drops refunds
+    given:
+      - {{id: 1, amount: 10}}
+      - {{id: 2, amount: -5}}
+    expect:
+      - {{id: 1, amount: {expected_amount}}}
+"#
+        );
+        fs::write(&config_path, yaml).unwrap();
+    };
+    let test = || {
+        Command::new(env!("CARGO_BIN_EXE_mlprep"))
+            .args(["test", config_path.to_str().unwrap()])
+            .output()
+            .expect("Failed to run mlprep")
+    };
+
+    write_pipeline(10);
+    let output = test();
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("PASS drops refunds"));
+
+    write_pipeline(11);
+    let output = test();
+    assert!(!output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("FAIL drops refunds"), "{}", stdout);
+    assert!(
+        stdout.contains("row 0: expected {id: 1, amount: 11}"),
+        "{}",
+        stdout
+    );
+    assert!(String::from_utf8_lossy(&output.stderr).contains("1 of 1 test cases failed"));
+}
+
+#[test]
+fn test_cli_synth() {
+    let dir = tempdir().unwrap();
+    let schema_path = dir.path().join("checks.yaml");
+    let output_path = dir.path().join("fake.csv");
+    fs::write(
+        &schema_path,
+        r#"
+columns:
+  - name: id
+    dtype: Int64
+    unique: true
+  - name: age
+    range: [18, 65]
+  - name: country
+    enum: [jp, us]
+"#,
+    )
+    .unwrap();
+
+    let synth = |extra: &[&str]| {
+        Command::new(env!("CARGO_BIN_EXE_mlprep"))
+            .args([
+                "synth",
+                "--schema",
+                schema_path.to_str().unwrap(),
+                "--rows",
+                "50",
+            ])
+            .args(extra)
+            .output()
+            .expect("Failed to run mlprep")
+    };
+
+    let output = synth(&["-o", output_path.to_str().unwrap()]);
+    assert!(output.status.success());
+    let content = fs::read_to_string(&output_path).unwrap();
+    let lines: Vec<&str> = content.lines().collect();
+    assert_eq!(lines[0], "id,age,country");
+    assert_eq!(lines.len(), 51);
+
+    let stdout = synth(&[]).stdout;
+    assert_eq!(String::from_utf8_lossy(&stdout), content);
+}
+
+#[test]
+fn test_cli_schema_show_column_docs() {
+    let dir = tempdir().unwrap();
+    let input_path = dir.path().join("input.csv");
+    let output_path = dir.path().join("output.parquet");
+    let pipeline_path = dir.path().join("pipeline.yaml");
+    fs::write(&input_path, "id,price\n1,1.5\n2,2.5\n").unwrap();
+    fs::write(
+        &pipeline_path,
+        format!(
+            r#"
+inputs:
+  - path: "{}"
+schema:
+  price: {{dtype: Float64, description: "Unit price", unit: EUR}}
+steps: []
+outputs:
+  - path: "{}"
+"#,
+            input_path.display(),
+            output_path.display()
+        ),
+    )
+    .unwrap();
+
+    let status = Command::new(env!("CARGO_BIN_EXE_mlprep"))
+        .args(["run", pipeline_path.to_str().unwrap()])
+        .status()
+        .expect("Failed to run mlprep");
+    assert!(status.success());
+
+    let output = Command::new(env!("CARGO_BIN_EXE_mlprep"))
+        .args([
+            "schema",
+            "show",
+            output_path.to_str().unwrap(),
+            "--output-format",
+            "json",
+        ])
+        .output()
+        .expect("Failed to run mlprep");
+    assert!(output.status.success());
+    let schema: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(schema["columns"][1]["name"], "price");
+    assert_eq!(schema["columns"][1]["description"], "Unit price");
+    assert_eq!(schema["columns"][1]["unit"], "EUR");
+    assert!(schema["columns"][0].get("description").is_none());
+}
+
+#[test]
+fn test_cli_docs() {
+    let dir = tempdir().unwrap();
+    let input_path = dir.path().join("orders.csv");
+    let pipeline_path = dir.path().join("pipeline.yaml");
+    let docs_path = dir.path().join("features.md");
+    fs::write(&input_path, "user,amount\na,1\na,2\nb,5\n").unwrap();
+    fs::write(
+        &pipeline_path,
+        format!(
+            r#"
+inputs:
+  - path: "{}"
+schema:
+  amount: {{dtype: Float64, description: "Order amount"}}
+steps:
+  - type: group_by
+    by: [user]
+    aggs:
+      - {{column: amount, func: sum, alias: total}}
+"#,
+            input_path.display()
+        ),
+    )
+    .unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_mlprep"))
+        .args([
+            "docs",
+            pipeline_path.to_str().unwrap(),
+            "-o",
+            docs_path.to_str().unwrap(),
+        ])
+        .output()
+        .expect("Failed to run mlprep");
+    assert!(output.status.success());
+    let md = fs::read_to_string(&docs_path).unwrap();
+    assert!(md.contains("## total"), "{}", md);
+    assert!(
+        md.contains("- **Transforms:** sum(amount) by [user]"),
+        "{}",
+        md
+    );
+    assert!(
+        md.contains(&format!("- **Sources:** `{}:amount`", input_path.display())),
+        "{}",
+        md
+    );
+}
+
+#[test]
+fn test_cli_run_writes_feast_definition() {
+    let dir = tempdir().unwrap();
+    let input_path = dir.path().join("events.csv");
+    let output_path = dir.path().join("user_stats.parquet");
+    let pipeline_path = dir.path().join("pipeline.yaml");
+    fs::write(
+        &input_path,
+        "user_id,event_date,spend\n1,2024-01-01,1.5\n2,2024-01-02,2.5\n",
+    )
+    .unwrap();
+    fs::write(
+        &pipeline_path,
+        format!(
+            r#"
+inputs:
+  - path: "{}"
+schema:
+  event_date: Date
+steps: []
+outputs:
+  - path: "{}"
+    feast:
+      entities: [user_id]
+      timestamp_field: event_date
+      ttl: 24h
+"#,
+            input_path.display(),
+            output_path.display()
+        ),
+    )
+    .unwrap();
+
+    let status = Command::new(env!("CARGO_BIN_EXE_mlprep"))
+        .args(["run", pipeline_path.to_str().unwrap()])
+        .status()
+        .expect("Failed to run mlprep");
+    assert!(status.success());
+
+    let py = fs::read_to_string(dir.path().join("user_stats_feast.py")).unwrap();
+    assert!(py.contains("user_stats = FeatureView("), "{}", py);
+    assert!(py.contains("timestamp_field=\"event_date\""), "{}", py);
+    assert!(
+        py.contains("Field(name=\"spend\", dtype=Float64)"),
+        "{}",
+        py
+    );
+    assert!(
+        py.contains(&format!("path={:?}", output_path.display().to_string())),
+        "{}",
+        py
+    );
+}
+
+#[test]
+fn test_cli_run_manifest_input() {
+    let dir = tempdir().unwrap();
+    fs::create_dir(dir.path().join("daily")).unwrap();
+    fs::write(dir.path().join("daily/d1.csv"), "id,amount\n1,10\n2,-1\n").unwrap();
+    fs::write(dir.path().join("daily/d2.csv"), "id,amount\n3,30\n").unwrap();
+    let manifest_path = dir.path().join("files.csv");
+    fs::write(
+        &manifest_path,
+        "path,day\ndaily/d1.csv,2024-01-01\ndaily/d2.csv,2024-01-02\n",
+    )
+    .unwrap();
+
+    let run = |per_file: bool, output: &str| {
+        let pipeline_path = dir.path().join("pipeline.yaml");
+        fs::write(
+            &pipeline_path,
+            format!(
+                r#"
+inputs:
+  - path: "{}"
+    manifest:
+      per_file: {}
+steps:
+  - type: filter
+    condition: "amount > 0"
+outputs:
+  - path: "{}"
+"#,
+                manifest_path.display(),
+                per_file,
+                dir.path().join(output).display()
+            ),
+        )
+        .unwrap();
+        let output = Command::new(env!("CARGO_BIN_EXE_mlprep"))
+            .args(["run", pipeline_path.to_str().unwrap()])
+            .output()
+            .expect("Failed to run mlprep");
+        assert!(
+            output.status.success(),
+            "{}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+    };
+
+    run(false, "all.csv");
+    let all = fs::read_to_string(dir.path().join("all.csv")).unwrap();
+    assert_eq!(all, "id,amount,day\n1,10,2024-01-01\n3,30,2024-01-02\n");
+
+    run(true, "out/{day}.csv");
+    let first = fs::read_to_string(dir.path().join("out/2024-01-01.csv")).unwrap();
+    assert_eq!(first, "id,amount,day\n1,10,2024-01-01\n");
+    let second = fs::read_to_string(dir.path().join("out/2024-01-02.csv")).unwrap();
+    assert_eq!(second, "id,amount,day\n3,30,2024-01-02\n");
+}
+
+#[test]
+fn test_cli_run_manifest_resumes_per_file() {
+    let dir = tempdir().unwrap();
+    fs::write(dir.path().join("d1.csv"), "id,amount\n1,10\n").unwrap();
+    // Missing the filtered column: the run fails on this file.
+    fs::write(dir.path().join("d2.csv"), "id\n2\n").unwrap();
+    fs::write(dir.path().join("files.csv"), "path\nd1.csv\nd2.csv\n").unwrap();
+    let pipeline_path = dir.path().join("daily.yaml");
+    fs::write(
+        &pipeline_path,
+        format!(
+            r#"
+inputs:
+  - path: "{}"
+    manifest:
+      per_file: true
+steps:
+  - type: filter
+    condition: "amount > 0"
+outputs:
+  - path: "{}"
+    format: csv
+"#,
+            dir.path().join("files.csv").display(),
+            dir.path().join("out").display()
+        ),
+    )
+    .unwrap();
+    let run = |extra: &[&str]| {
+        Command::new(env!("CARGO_BIN_EXE_mlprep"))
+            .arg("run")
+            .arg(&pipeline_path)
+            .args(extra)
+            .output()
+            .expect("Failed to run mlprep")
+    };
+    let progress_path = dir.path().join("daily.progress.json");
+    let first_output = dir.path().join("out/d1.csv");
+
+    assert!(!run(&[]).status.success());
+    let progress: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(&progress_path).unwrap()).unwrap();
+    assert_eq!(progress["done"].as_object().unwrap().len(), 1);
+
+    // The rerun skips the finished file and only processes the fixed one.
+    fs::write(&first_output, "kept\n").unwrap();
+    fs::write(dir.path().join("d2.csv"), "id,amount\n2,20\n").unwrap();
+    let output = run(&[]);
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert_eq!(fs::read_to_string(&first_output).unwrap(), "kept\n");
+    assert_eq!(
+        fs::read_to_string(dir.path().join("out/d2.csv")).unwrap(),
+        "id,amount\n2,20\n"
+    );
+    assert!(!progress_path.exists());
+
+    // --restart processes every file again.
+    fs::write(dir.path().join("d2.csv"), "id\n2\n").unwrap();
+    assert!(!run(&[]).status.success());
+    fs::write(&first_output, "kept\n").unwrap();
+    fs::write(dir.path().join("d2.csv"), "id,amount\n2,20\n").unwrap();
+    assert!(run(&["--restart"]).status.success());
+    assert_eq!(
+        fs::read_to_string(&first_output).unwrap(),
+        "id,amount\n1,10\n"
+    );
+}
+
+#[test]
+fn test_cli_migrate() {
+    let dir = tempdir().unwrap();
+    let input_path = dir.path().join("orders.csv");
+    let pipeline_path = dir.path().join("pipeline.yaml");
+    let output_path = dir.path().join("out.csv");
+    fs::write(&input_path, "user,amount\na,1\na,2\nb,5\n").unwrap();
+    fs::write(
+        &pipeline_path,
+        format!(
+            r#"# Spend per user
+inputs:
+  - path: "{}"
+steps:
+  - type: group_by
+    by: [user]
+    aggs:
+      amount: {{func: sum, alias: total}}
+    maintain_order: true
+outputs:
+  - path: "{}"
+"#,
+            input_path.display(),
+            output_path.display()
+        ),
+    )
+    .unwrap();
+
+    let migrate = |extra: &[&str]| {
+        Command::new(env!("CARGO_BIN_EXE_mlprep"))
+            .args(["migrate", pipeline_path.to_str().unwrap()])
+            .args(extra)
+            .output()
+            .expect("Failed to run mlprep")
+    };
+
+    let output = migrate(&[]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(
+        stdout.starts_with("# Spend per user\nversion: 2\n"),
+        "{}",
+        stdout
+    );
+    assert!(stdout.contains(
+        "    aggs:\n      - {column: amount, func: sum, alias: total}\n    maintain_order: true\n"
+    ));
+    assert!(fs::read_to_string(&pipeline_path)
+        .unwrap()
+        .contains("amount: {func: sum"));
+
+    let output = migrate(&["--in-place"]);
+    assert!(output.status.success());
+    assert_eq!(fs::read_to_string(&pipeline_path).unwrap(), stdout);
+
+    let output = Command::new(env!("CARGO_BIN_EXE_mlprep"))
+        .args(["run", pipeline_path.to_str().unwrap()])
+        .output()
+        .expect("Failed to run mlprep");
+    assert!(output.status.success());
+    assert_eq!(
+        fs::read_to_string(&output_path).unwrap(),
+        "user,total\na,3\nb,5\n"
+    );
+
+    fs::write(&pipeline_path, "version: 3\nsteps: []\n").unwrap();
+    let output = migrate(&[]);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(
+        stderr.contains("Pipeline version 3 is not supported"),
+        "{}",
+        stderr
+    );
+}
+
+#[test]
+fn test_cli_repl() {
+    use std::io::Write;
+    use std::process::Stdio;
+
+    let dir = tempdir().unwrap();
+    let input_path = dir.path().join("orders.csv");
+    let pipeline_path = dir.path().join("pipeline.yaml");
+    let output_path = dir.path().join("out.csv");
+    fs::write(&input_path, "city,price\nosaka,3\ntokyo,-1\nkyoto,5\n").unwrap();
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_mlprep"))
+        .args([
+            "repl",
+            input_path.to_str().unwrap(),
+            "--rows",
+            "2",
+            "-o",
+            pipeline_path.to_str().unwrap(),
+        ])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .expect("Failed to run mlprep");
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(b"type: filter, condition: \"price > 0\"\ntype: nope\ntype: sort, by: [city]\n")
+        .unwrap();
+    let output = child.wait_with_output().unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("shape: (2, 2)"), "{}", stdout);
+    assert!(
+        stdout.contains("[1]> error: Configuration error"),
+        "{}",
+        stdout
+    );
+    assert!(stdout.contains("step 2:\n- type: sort\n"), "{}", stdout);
+
+    // The dumped pipeline runs as is once an output is added
+    let mut yaml = fs::read_to_string(&pipeline_path).unwrap();
+    assert!(yaml.starts_with("version: 2\n"), "{}", yaml);
+    yaml.push_str(&format!(
+        "outputs:\n- path: \"{}\"\n",
+        output_path.display()
+    ));
+    fs::write(&pipeline_path, yaml).unwrap();
+    let status = Command::new(env!("CARGO_BIN_EXE_mlprep"))
+        .args(["run", pipeline_path.to_str().unwrap()])
+        .status()
+        .expect("Failed to run mlprep");
+    assert!(status.success());
+    assert_eq!(
+        fs::read_to_string(&output_path).unwrap(),
+        "city,price\nkyoto,5\nosaka,3\n"
+    );
+}
+
+#[test]
+fn test_cli_view_without_terminal() {
+    let dir = tempdir().unwrap();
+    let input_path = dir.path().join("users.csv");
+    fs::write(&input_path, "id,name,ssn\n1,ann,111\n2,bob,222\n3,cy,333\n").unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_mlprep"))
+        .args([
+            "--mask-columns",
+            "ssn",
+            "view",
+            input_path.to_str().unwrap(),
+            "--columns",
+            "id,ssn",
+            "--rows",
+            "2",
+        ])
+        .output()
+        .expect("Failed to run mlprep");
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert!(
+        lines[0].ends_with("users.csv  rows 1-2 of 3  1 columns hidden"),
+        "{}",
+        stdout
+    );
+    assert_eq!(
+        &lines[1..],
+        [
+            "[id] │ ssn",
+            "i64  │ str",
+            "─────┼────",
+            "1    │ ***",
+            "2    │ ***"
+        ]
+    );
+}
+
+#[test]
+fn test_cli_head_and_describe() {
+    let dir = tempdir().unwrap();
+    let input_path = dir.path().join("users.csv");
+    let mut csv = "id,name,ssn\n".to_string();
+    for i in 0..30 {
+        csv.push_str(&format!("{},user{},{}\n", i, i % 4, 1000 + i));
+    }
+    fs::write(&input_path, csv).unwrap();
+    let mlprep = |args: &[&str]| {
+        Command::new(env!("CARGO_BIN_EXE_mlprep"))
+            .args(["--mask-columns", "ssn"])
+            .args(args)
+            .output()
+            .expect("Failed to run mlprep")
+    };
+
+    let output = mlprep(&["head", input_path.to_str().unwrap(), "-n", "20"]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("shape: (20, 3)"), "{}", stdout);
+    assert!(stdout.contains("│ 19  ┆ user3 ┆ ***"), "{}", stdout);
+    assert!(!stdout.contains('…'), "{}", stdout);
+
+    let output = mlprep(&[
+        "head",
+        input_path.to_str().unwrap(),
+        "-n",
+        "2",
+        "--output-format",
+        "json",
+    ]);
+    assert!(output.status.success());
+    let rows: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(
+        rows,
+        serde_json::json!([
+            {"id": 0, "name": "user0", "ssn": "***"},
+            {"id": 1, "name": "user1", "ssn": "***"}
+        ])
+    );
+
+    let output = mlprep(&["describe", input_path.to_str().unwrap()]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.starts_with("rows: 30\n"), "{}", stdout);
+    assert!(
+        stdout.contains("\nid      Int64   0      30      14.5  8.8034  0      29\n"),
+        "{}",
+        stdout
+    );
+    assert!(!stdout.contains("1029"), "{}", stdout);
+
+    let output = mlprep(&[
+        "describe",
+        input_path.to_str().unwrap(),
+        "--output-format",
+        "json",
+    ]);
+    assert!(output.status.success());
+    let summary: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(summary["columns"][1]["unique"], 4);
+    assert_eq!(summary["columns"][1]["max"], "user3");
+    assert!(summary["columns"][2].get("max").is_none());
+
+    let output = Command::new(env!("CARGO_BIN_EXE_mlprep"))
+        .args([
+            "--allowed-paths",
+            dir.path().join("elsewhere").to_str().unwrap(),
+            "head",
+            input_path.to_str().unwrap(),
+        ])
+        .output()
+        .expect("Failed to run mlprep");
+    assert!(!output.status.success());
+}
+
+#[test]
+fn test_cli_init_and_completions() {
+    let dir = tempdir().unwrap();
+    let project = dir.path().join("churn");
+    fs::create_dir_all(project.join("data")).unwrap();
+    fs::write(project.join("data/users.csv"), "id,age\n1,30\n2,41\n").unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_mlprep"))
+        .args([
+            "init",
+            project.to_str().unwrap(),
+            "--input",
+            project.join("data/users.csv").to_str().unwrap(),
+        ])
+        .output()
+        .expect("Failed to run mlprep");
+    assert!(output.status.success());
+    let checks = fs::read_to_string(project.join("checks.yaml")).unwrap();
+    assert!(
+        checks.contains("  - name: age\n    not_null: true\n    range: [30, 41]\n"),
+        "{}",
+        checks
+    );
+
+    // The scaffolded pipeline runs from the project directory as is
+    let output = Command::new(env!("CARGO_BIN_EXE_mlprep"))
+        .current_dir(&project)
+        .args(["run", "pipeline.yaml"])
+        .output()
+        .expect("Failed to run mlprep");
+    assert!(output.status.success());
+    assert!(project.join("output/users.parquet").exists());
+
+    let output = Command::new(env!("CARGO_BIN_EXE_mlprep"))
+        .args(["init", project.to_str().unwrap()])
+        .output()
+        .expect("Failed to run mlprep");
+    assert!(!output.status.success());
+
+    let output = Command::new(env!("CARGO_BIN_EXE_mlprep"))
+        .args(["completions", "bash"])
+        .output()
+        .expect("Failed to run mlprep");
+    assert!(output.status.success());
+    let script = String::from_utf8(output.stdout).unwrap();
+    assert!(script.contains("_mlprep()"), "{}", &script[..200]);
+    assert!(script.contains("completions"));
+}
+
+#[test]
+fn test_cli_run_reports_failing_step() {
+    let dir = tempdir().unwrap();
+    let input_path = dir.path().join("orders.csv");
+    let config_path = dir.path().join("pipeline.yaml");
+    fs::write(&input_path, "amount,city\n1,osaka\n2,kyoto\n").unwrap();
+    fs::write(
+        &config_path,
+        format!(
+            r#"inputs:
+  - path: "{input}"
+steps:
+  - type: filter
+    condition: "amount > 0"
+  - type: select
+    columns: [city, amonut]
+outputs:
+  - path: "{output}"
+"#,
+            input = input_path.display(),
+            output = dir.path().join("out.parquet").display()
+        ),
+    )
+    .unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_mlprep"))
+        .args(["run", config_path.to_str().unwrap()])
+        .env("NO_COLOR", "1")
+        .output()
+        .expect("Failed to run mlprep");
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr
+            .contains("Step 2 (select) failed: column 'amonut' not found; did you mean 'amount'?"),
+        "{}",
+        stderr
+    );
+    assert!(stderr.contains("MLPREP-010"), "{}", stderr);
+    assert!(
+        stderr.contains("available columns: amount (i64), city (str)"),
+        "{}",
+        stderr
+    );
+    // The report quotes the step from the pipeline file
+    assert!(stderr.contains("columns: [city, amonut]"), "{}", stderr);
+}
+
+#[test]
+fn test_cli_run_binds_filter_params() {
+    let dir = tempdir().unwrap();
+    let input_path = dir.path().join("orders.csv");
+    let output_path = dir.path().join("out.csv");
+    let config_path = dir.path().join("pipeline.yaml");
+    fs::write(&input_path, "amount,city\n1,osaka\n5,kyoto\n9,osaka\n").unwrap();
+    fs::write(
+        &config_path,
+        format!(
+            r#"inputs:
+  - path: "{input}"
+steps:
+  - type: filter
+    condition: "amount > :min_amount AND city = :city"
+outputs:
+  - path: "{output}"
+runtime:
+  params:
+    min_amount: 0
+"#,
+            input = input_path.display(),
+            output = output_path.display()
+        ),
+    )
+    .unwrap();
+    let run = |args: &[&str]| {
+        Command::new(env!("CARGO_BIN_EXE_mlprep"))
+            .args(["run", config_path.to_str().unwrap()])
+            .args(args)
+            .env("MLPREP_PARAM_CITY", "osaka")
+            .env("NO_COLOR", "1")
+            .output()
+            .expect("Failed to run mlprep")
+    };
+
+    // The pipeline's default, with the city from the environment
+    assert!(run(&[]).status.success());
+    assert_eq!(
+        fs::read_to_string(&output_path).unwrap(),
+        "amount,city\n1,osaka\n9,osaka\n"
+    );
+
+    // The command line overrides both
+    let output = run(&["--param", "min_amount=4", "--param", "city=kyoto"]);
+    assert!(output.status.success());
+    assert_eq!(
+        fs::read_to_string(&output_path).unwrap(),
+        "amount,city\n5,kyoto\n"
+    );
+
+    let output = run(&["--param", "city=x'; DROP TABLE df; --"]);
+    assert!(output.status.success());
+    assert_eq!(fs::read_to_string(&output_path).unwrap(), "amount,city\n");
+}
+
+#[test]
+fn test_cli_run_caches_remote_inputs() {
+    use std::io::{BufRead, BufReader, Write};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    const BODY: &str = "id,v\n1,a\n2,b\n";
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}/events.csv", listener.local_addr().unwrap());
+    let downloads = Arc::new(AtomicUsize::new(0));
+    let counter = downloads.clone();
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            let mut stream = stream.unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut request = String::new();
+            reader.read_line(&mut request).unwrap();
+            let mut line = String::new();
+            while reader.read_line(&mut line).unwrap() > 2 {
+                line.clear();
+            }
+            let is_get = request.starts_with("GET");
+            if is_get {
+                counter.fetch_add(1, Ordering::SeqCst);
+            }
+            let _ = write!(
+                stream,
+                "HTTP/1.1 200 OK\r\nETag: \"1\"\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                BODY.len(),
+                if is_get { BODY } else { "" }
+            );
+        }
+    });
+
+    let dir = tempdir().unwrap();
+    let cache_dir = dir.path().join("cache");
+    let output_path = dir.path().join("out.csv");
+    let config_path = dir.path().join("pipeline.yaml");
+    fs::write(
+        &config_path,
+        format!(
+            r#"inputs:
+  - path: "{url}"
+steps: []
+outputs:
+  - path: "{output}"
+runtime:
+  input_cache_dir: "{cache}"
+"#,
+            url = url,
+            output = output_path.display(),
+            cache = cache_dir.display()
+        ),
+    )
+    .unwrap();
+    let run = |args: &[&str]| {
+        Command::new(env!("CARGO_BIN_EXE_mlprep"))
+            .args(["run", config_path.to_str().unwrap()])
+            .args(args)
+            .env("NO_COLOR", "1")
+            .output()
+            .expect("Failed to run mlprep")
+    };
+
+    for _ in 0..2 {
+        let output = run(&[]);
+        assert!(
+            output.status.success(),
+            "{}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+        assert_eq!(fs::read_to_string(&output_path).unwrap(), BODY);
+    }
+    // The second run read the cached copy
+    assert_eq!(downloads.load(Ordering::SeqCst), 1);
+    assert!(cache_dir.join("index.json").exists());
+
+    let output = run(&["--no-cache"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("--no-cache"));
+}
+
+#[test]
+fn test_cli_run_iceberg_table() {
+    use polars::io::avro::AvroWriter;
+    use polars::prelude::*;
+
+    let dir = tempdir().unwrap();
+    let table = dir.path().join("orders");
+    let metadata = table.join("metadata");
+    fs::create_dir_all(&metadata).unwrap();
+    let write_avro = |path: &std::path::Path, mut df: DataFrame| {
+        AvroWriter::new(fs::File::create(path).unwrap())
+            .finish(&mut df)
+            .unwrap();
+    };
+    let bound = |value: i64| {
+        let key = Series::new("key".into(), [1i32]);
+        let value = Series::new("value".into(), [value.to_le_bytes().to_vec()]);
+        StructChunked::from_series("entry".into(), 1, [key, value].iter())
+            .unwrap()
+            .into_series()
+    };
+
+    // Two data files, with amounts 1..=3 and 10..=12
+    let mut files = Vec::new();
+    for (name, amounts) in [("a", [1i64, 2, 3]), ("b", [10, 11, 12])] {
+        let path = table.join(format!("{}.parquet", name));
+        let mut df = df!("amount" => amounts).unwrap();
+        ParquetWriter::new(fs::File::create(&path).unwrap())
+            .finish(&mut df)
+            .unwrap();
+        files.push((path.display().to_string(), amounts[0], amounts[2]));
+    }
+    let fields = [
+        Series::new(
+            "file_path".into(),
+            files.iter().map(|f| f.0.clone()).collect::<Vec<_>>(),
+        ),
+        Series::new("file_format".into(), ["PARQUET", "PARQUET"]),
+        Series::new(
+            "lower_bounds".into(),
+            files.iter().map(|f| bound(f.1)).collect::<Vec<_>>(),
+        ),
+        Series::new(
+            "upper_bounds".into(),
+            files.iter().map(|f| bound(f.2)).collect::<Vec<_>>(),
+        ),
+    ];
+    let data_file = StructChunked::from_series("data_file".into(), 2, fields.iter())
+        .unwrap()
+        .into_series();
+    let manifest = metadata.join("manifest.avro");
+    write_avro(
+        &manifest,
+        DataFrame::new(vec![
+            Series::new("status".into(), [1i32, 1]).into(),
+            data_file.into(),
+        ])
+        .unwrap(),
+    );
+    let manifest_list = metadata.join("snap-7.avro");
+    write_avro(
+        &manifest_list,
+        df!("manifest_path" => [manifest.display().to_string()]).unwrap(),
+    );
+    fs::write(
+        metadata.join("v1.metadata.json"),
+        format!(
+            r#"{{"format-version": 2, "current-snapshot-id": 7, "current-schema-id": 0,
+"schemas": [{{"schema-id": 0, "type": "struct", "fields": [{{"id": 1, "name": "amount", "required": true, "type": "long"}}]}}],
+"snapshots": [{{"snapshot-id": 7, "timestamp-ms": 1000, "manifest-list": "{}"}}]}}"#,
+            manifest_list.display()
+        ),
+    )
+    .unwrap();
+    fs::write(metadata.join("version-hint.text"), "1").unwrap();
+
+    let output_path = dir.path().join("out.csv");
+    let config_path = dir.path().join("pipeline.yaml");
+    fs::write(
+        &config_path,
+        format!(
+            r#"inputs:
+  - path: "{table}"
+    iceberg: {{}}
+steps:
+  - type: filter
+    condition: "amount > 10"
+outputs:
+  - path: "{output}"
+"#,
+            table = table.display(),
+            output = output_path.display()
+        ),
+    )
+    .unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_mlprep"))
+        .args(["run", config_path.to_str().unwrap()])
+        .env("NO_COLOR", "1")
+        .output()
+        .expect("Failed to run mlprep");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "{}", stderr);
+    assert_eq!(
+        fs::read_to_string(&output_path).unwrap(),
+        "amount\n11\n12\n"
+    );
+    // The file with amounts up to 3 was never opened
+    assert!(
+        stderr.contains("reading 1 data files, 1 skipped"),
+        "{}",
+        stderr
+    );
+}
+
+#[cfg(not(feature = "kafka"))]
+#[test]
+fn test_cli_run_kafka_input_needs_feature() {
+    let dir = tempdir().unwrap();
+    let config_path = dir.path().join("pipeline.yaml");
+    fs::write(
+        &config_path,
+        format!(
+            r#"inputs:
+  - path: "kafka://localhost:9092/orders"
+    kafka:
+      max_messages: 10
+steps: []
+outputs:
+  - path: "{}"
+"#,
+            dir.path().join("out.parquet").display()
+        ),
+    )
+    .unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_mlprep"))
+        .args(["run", config_path.to_str().unwrap()])
+        .env("NO_COLOR", "1")
+        .output()
+        .expect("Failed to run mlprep");
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("without the `kafka` feature"));
+}
+
+#[test]
+fn test_cli_kafka_avro_schema_stays_in_sandbox() {
+    let dir = tempdir().unwrap();
+    let allowed = dir.path().join("allowed");
+    fs::create_dir_all(&allowed).unwrap();
+    let schema_path = dir.path().join("orders.avsc");
+    fs::write(
+        &schema_path,
+        r#"{"type": "record", "name": "o", "fields": []}"#,
+    )
+    .unwrap();
+    let config_path = allowed.join("pipeline.yaml");
+    fs::write(
+        &config_path,
+        format!(
+            r#"inputs:
+  - path: "kafka://localhost:9092/orders"
+    format: avro
+    kafka:
+      avro_schema: "{}"
+steps: []
+outputs:
+  - path: "{}"
+"#,
+            schema_path.display(),
+            allowed.join("out.parquet").display()
+        ),
+    )
+    .unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_mlprep"))
+        .args([
+            "run",
+            config_path.to_str().unwrap(),
+            "--allowed-paths",
+            allowed.to_str().unwrap(),
+            "--allowed-paths",
+            "kafka://localhost:9092",
+        ])
+        .env("NO_COLOR", "1")
+        .output()
+        .expect("Failed to run mlprep");
+    assert!(!output.status.success());
+    // Refused before connecting, with or without the `kafka` feature
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("Access denied"), "{}", stderr);
+}
+
+#[test]
+fn test_cli_sql_step_stays_in_sandbox() {
+    let dir = tempdir().unwrap();
+    let allowed = dir.path().join("allowed");
+    fs::create_dir_all(&allowed).unwrap();
+    let secret_path = dir.path().join("secret.csv");
+    fs::write(&secret_path, "token\nhunter2\n").unwrap();
+    fs::write(allowed.join("in.csv"), "a\n1\n2\n").unwrap();
+    let output_path = allowed.join("out.csv");
+
+    let run = |query: &str| {
+        let config_path = allowed.join("pipeline.yaml");
+        let yaml = format!(
+            "inputs:\n  - path: \"{}\"\nsteps:\n  - type: sql\n    query: \"{}\"\noutputs:\n  - path: \"{}\"\n",
+            allowed.join("in.csv").to_str().unwrap(),
+            query,
+            output_path.to_str().unwrap()
+        );
+        fs::write(&config_path, yaml).unwrap();
+        Command::new(env!("CARGO_BIN_EXE_mlprep"))
+            .args([
+                "run",
+                config_path.to_str().unwrap(),
+                "--allowed-paths",
+                allowed.to_str().unwrap(),
+            ])
+            .output()
+            .expect("Failed to run mlprep")
+    };
+
+    for query in [
+        format!("SELECT * FROM read_csv('{}')", secret_path.display()),
+        format!(
+            "SELECT a FROM df WHERE a IN (SELECT 1 FROM read_csv('{}'))",
+            secret_path.display()
+        ),
+    ] {
+        let output = run(&query);
+        assert!(!output.status.success());
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert!(stderr.contains("is not allowed"), "{}", stderr);
+        assert!(!output_path.exists());
+    }
+
+    let output = run("SELECT a * 10 AS a FROM df WHERE a > 1");
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert_eq!(fs::read_to_string(&output_path).unwrap(), "a\n20\n");
+}
+
+/// Serve `body` as every object of a minimal S3 endpoint: HEAD and (ranged) GET requests.
+fn serve_s3(body: &'static [u8]) -> String {
+    use std::io::{BufRead, BufReader, Write};
+
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let endpoint = format!("http://{}", listener.local_addr().unwrap());
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            let mut stream = stream.unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut request = String::new();
+            reader.read_line(&mut request).unwrap();
+            let mut range = None;
+            let mut line = String::new();
+            while reader.read_line(&mut line).unwrap() > 2 {
+                if let Some(value) = line.to_ascii_lowercase().strip_prefix("range: bytes=") {
+                    let (start, end) = value.trim().split_once('-').unwrap();
+                    let start: usize = start.parse().unwrap();
+                    let end = end.parse().map_or(body.len(), |end: usize| end + 1);
+                    range = Some(start..end.min(body.len()));
+                }
+                line.clear();
+            }
+            let (status, content) = match &range {
+                Some(range) => ("206 Partial Content", &body[range.clone()]),
+                None => ("200 OK", body),
+            };
+            let mut response = format!(
+                "HTTP/1.1 {}\r\nContent-Length: {}\r\nETag: \"v1\"\r\nLast-Modified: Tue, 15 Nov 1994 08:12:31 GMT\r\nConnection: close\r\n",
+                status,
+                content.len()
+            );
+            if let Some(range) = &range {
+                response.push_str(&format!(
+                    "Content-Range: bytes {}-{}/{}\r\n",
+                    range.start,
+                    range.end - 1,
+                    body.len()
+                ));
+            }
+            response.push_str("\r\n");
+            let _ = stream.write_all(response.as_bytes());
+            if !request.starts_with("HEAD") {
+                let _ = stream.write_all(content);
+            }
+        }
+    });
+    endpoint
+}
+
+#[test]
+fn test_cli_run_object_store_input() {
+    let dir = tempdir().unwrap();
+    let endpoint = serve_s3(b"id,v\n1,a\n2,b\n3,c\n");
+    let config_path = dir.path().join("pipeline.yaml");
+    let output_path = dir.path().join("out.csv");
+    fs::write(
+        &config_path,
+        format!(
+            "inputs:\n  - path: s3://lake/events.csv\nsteps:\n  - type: filter\n    condition: \"id > 1\"\noutputs:\n  - path: \"{}\"\n",
+            output_path.display()
+        ),
+    )
+    .unwrap();
+
+    // Scanned in place, and with the memory limit estimated from a HEAD request
+    for args in [
+        &["--no-cache"][..],
+        &["--no-cache", "--memory-limit", "1GB"],
+    ] {
+        let output = Command::new(env!("CARGO_BIN_EXE_mlprep"))
+            .arg("run")
+            .arg(&config_path)
+            .args(args)
+            .args(["--output-format", "json"])
+            .env("AWS_ENDPOINT_URL", &endpoint)
+            .env("AWS_REGION", "us-east-1")
+            .env("AWS_ALLOW_HTTP", "true")
+            .env("AWS_ACCESS_KEY_ID", "test")
+            .env("AWS_SECRET_ACCESS_KEY", "test")
+            .output()
+            .expect("Failed to run mlprep");
+        assert!(
+            output.status.success(),
+            "{:?}: {}",
+            args,
+            String::from_utf8_lossy(&output.stderr)
+        );
+        assert_eq!(
+            fs::read_to_string(&output_path).unwrap(),
+            "id,v\n2,b\n3,c\n"
+        );
+
+        // The object's ETag stands in for its hash
+        let summary: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+        assert_eq!(summary["attempts"], 1);
+        let lineage_path = dir.path().join(summary["lineage"].as_str().unwrap());
+        let lineage: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(lineage_path).unwrap()).unwrap();
+        assert_eq!(lineage["inputs"][0]["path"], "s3://lake/events.csv");
+        assert_eq!(lineage["inputs"][0]["hash"], "\"v1\"");
+        assert_eq!(lineage["inputs"][0]["size_bytes"], 17);
+    }
+}
@@ -95,6 +95,140 @@ steps:
     Ok(())
 }
 
+/// Test GroupBy with several aggregations of one column
+#[test]
+fn test_groupby_multiple_aggs_per_column_integration() -> Result<()> {
+    let df = df! {
+        "region" => ["east", "west", "east", "west", "east"],
+        "sales" => [100, 200, 150, 300, 250],
+    }?;
+    let lf = df.lazy();
+
+    let yaml = r#"
+steps:
+  - type: group_by
+    by: ["region"]
+    aggs:
+      - {column: sales, func: sum}
+      - {column: sales, func: mean, alias: avg_sales}
+      - {column: sales, func: max}
+"#;
+
+    let pipeline: Pipeline = serde_yaml::from_str(yaml)?;
+    let runtime = mlprep::dsl::RuntimeConfig::default();
+    let result_df = DataPipeline::new(lf)
+        .apply_transforms(
+            pipeline,
+            &runtime,
+            &mlprep::security::SecurityContext::new(Default::default()).unwrap(),
+        )?
+        .collect(false)?
+        .sort(["region"], Default::default())?;
+
+    assert_eq!(
+        result_df.get_column_names(),
+        &["region", "sales_sum", "avg_sales", "sales_max"]
+    );
+    assert_eq!(result_df.column("sales_sum")?.i32()?.get(0), Some(500));
+    assert_eq!(result_df.column("avg_sales")?.f64()?.get(1), Some(250.0));
+    assert_eq!(result_df.column("sales_max")?.i32()?.get(0), Some(250));
+
+    Ok(())
+}
+
+/// Test GroupBy aggregations restricted by a `where` condition
+#[test]
+fn test_groupby_filtered_aggs_integration() -> Result<()> {
+    let df = df! {
+        "host" => ["a", "a", "a", "b", "b"],
+        "status" => ["error", "ok", "error", "ok", "ok"],
+        "latency" => [100, 20, 300, 10, 30],
+    }?;
+    let lf = df.lazy();
+
+    let yaml = r#"
+steps:
+  - type: group_by
+    by: [host]
+    aggs:
+      - {column: status, func: count, alias: requests}
+      - {column: status, func: count, alias: errors, where: "status = 'error'"}
+      - column: latency
+        func: max
+        alias: max_ok_latency
+        where: {column: status, op: "==", value: ok}
+"#;
+
+    let pipeline: Pipeline = serde_yaml::from_str(yaml)?;
+    let runtime = mlprep::dsl::RuntimeConfig::default();
+    let result_df = DataPipeline::new(lf)
+        .apply_transforms(
+            pipeline,
+            &runtime,
+            &mlprep::security::SecurityContext::new(Default::default()).unwrap(),
+        )?
+        .collect(false)?
+        .sort(["host"], Default::default())?;
+
+    let requests = result_df.column("requests")?.u32()?;
+    let errors = result_df.column("errors")?.u32()?;
+    let max_ok = result_df.column("max_ok_latency")?.i32()?;
+    assert_eq!((requests.get(0), requests.get(1)), (Some(3), Some(2)));
+    assert_eq!((errors.get(0), errors.get(1)), (Some(2), Some(0)));
+    assert_eq!((max_ok.get(0), max_ok.get(1)), (Some(20), Some(30)));
+
+    Ok(())
+}
+
+/// Test GroupBy `having` filter applied after aggregation
+#[test]
+fn test_groupby_having_integration() -> Result<()> {
+    let df = df! {
+        "user" => ["a", "a", "a", "b", "c", "c"],
+        "amount" => [10, 20, 30, 5, 1, 2],
+    }?;
+    let users_having = |having: &str| -> Result<Vec<Option<String>>> {
+        let yaml = format!(
+            r#"
+steps:
+  - type: group_by
+    by: [user]
+    aggs:
+      - {{column: amount, func: count, alias: orders}}
+      - {{column: amount, func: sum, alias: total}}
+    having: {having}
+"#
+        );
+        let pipeline: Pipeline = serde_yaml::from_str(&yaml)?;
+        let runtime = mlprep::dsl::RuntimeConfig::default();
+        let result_df = DataPipeline::new(df.clone().lazy())
+            .apply_transforms(
+                pipeline,
+                &runtime,
+                &mlprep::security::SecurityContext::new(Default::default()).unwrap(),
+            )?
+            .collect(false)?
+            .sort(["user"], Default::default())?;
+        Ok(result_df
+            .column("user")?
+            .str()?
+            .into_iter()
+            .map(|u| u.map(str::to_string))
+            .collect())
+    };
+
+    assert_eq!(
+        users_having(r#""orders >= 2 AND total > 5""#)?,
+        vec![Some("a".to_string())]
+    );
+    assert_eq!(
+        users_having(r#"{column: orders, op: ">=", value: 2}"#)?,
+        vec![Some("a".to_string()), Some("c".to_string())]
+    );
+
+    Ok(())
+}
+
 /// Test Window function with partition
 #[test]
 fn test_window_integration() -> Result<()> {
@@ -200,6 +334,61 @@ steps:
     Ok(())
 }
 
+/// Test Join whose right side is an inline pipeline
+#[test]
+fn test_join_inline_right_integration() -> Result<()> {
+    let mut orders_file = NamedTempFile::new()?;
+    writeln!(orders_file, "user_id,amount,status")?;
+    writeln!(orders_file, "1,10,paid")?;
+    writeln!(orders_file, "1,5,refunded")?;
+    writeln!(orders_file, "1,7,paid")?;
+    writeln!(orders_file, "2,3,paid")?;
+    orders_file.flush()?;
+
+    let df = df! {
+        "id" => [1i64, 2, 3],
+        "name" => ["Alice", "Bob", "Carol"],
+    }?;
+
+    let yaml = format!(
+        r#"
+steps:
+  - type: join
+    right:
+      input: {{path: "{}", format: csv}}
+      steps:
+        - type: filter
+          condition: "status = 'paid'"
+        - type: group_by
+          by: [user_id]
+          aggs:
+            - {{column: amount, func: sum, alias: paid}}
+    left_on: ["id"]
+    right_on: ["user_id"]
+    how: "left"
+  - type: sort
+    by: ["id"]
+"#,
+        orders_file.path().display()
+    );
+
+    let pipeline: Pipeline = serde_yaml::from_str(&yaml)?;
+    let runtime = mlprep::dsl::RuntimeConfig::default();
+    let result_df = DataPipeline::new(df.lazy())
+        .apply_transforms(
+            pipeline,
+            &runtime,
+            &mlprep::security::SecurityContext::new(Default::default()).unwrap(),
+        )?
+        .collect(false)?;
+
+    let paid: Vec<Option<i64>> = result_df.column("paid")?.i64()?.into_iter().collect();
+    assert_eq!(paid, vec![Some(17), Some(3), None]);
+    assert!(result_df.column("status").is_err());
+
+    Ok(())
+}
+
 /// Test complex pipeline combining filter, groupby, and sort
 #[test]
 fn test_complex_pipeline_integration() -> Result<()> {
@@ -293,3 +482,108 @@ steps:
 
     Ok(())
 }
+
+/// Test a strict validate step with expected column statistics catching a unit change
+#[test]
+fn test_validate_stats_integration() -> Result<()> {
+    let yaml = r#"
+steps:
+  - type: validate
+    mode: strict
+    checks:
+      stats:
+        - name: amount
+          mean: {expected: 250, rel_tolerance: 0.1}
+          max: {expected: 400, abs_tolerance: 100}
+"#;
+    let pipeline: Pipeline = serde_yaml::from_str(yaml)?;
+
+    let euros = df! { "amount" => [200.0, 240.0, 310.0] }?;
+    let result_df = mlprep::engine::run_in_memory(euros.lazy(), &pipeline)?;
+    assert_eq!(result_df.height(), 3);
+
+    // The same amounts delivered in cents
+    let cents = df! { "amount" => [20000.0, 24000.0, 31000.0] }?;
+    let err = mlprep::engine::run_in_memory(cents.lazy(), &pipeline)
+        .unwrap_err()
+        .to_string();
+    assert!(
+        err.contains("Validation failed with 2 violations"),
+        "{}",
+        err
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_validate_unique_keys_integration() -> Result<()> {
+    let yaml = r#"
+steps:
+  - type: validate
+    mode: quarantine
+    checks:
+      unique_keys:
+        - [user_id, date]
+"#;
+    let pipeline: Pipeline = serde_yaml::from_str(yaml)?;
+
+    let df = df! {
+        "user_id" => [1, 1, 2, 2],
+        "date" => ["2024-01-01", "2024-01-02", "2024-01-01", "2024-01-01"],
+    }?;
+    let result_df = mlprep::engine::run_in_memory(df.lazy(), &pipeline)?;
+    assert_eq!(result_df.height(), 2);
+    assert_eq!(
+        result_df
+            .column("user_id")?
+            .i32()?
+            .into_no_null_iter()
+            .collect::<Vec<_>>(),
+        [1, 1]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_validate_violations_path_integration() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    let details_path = dir.path().join("violations.csv");
+    let yaml = format!(
+        r#"
+steps:
+  - type: validate
+    mode: quarantine
+    violations_path: "{}"
+    checks:
+      columns:
+        - name: id
+          unique: true
+        - name: status
+          enum: [active]
+          nulls: fail
+"#,
+        details_path.display()
+    );
+    let pipeline: Pipeline = serde_yaml::from_str(&yaml)?;
+
+    let df = df! {
+        "id" => [1, 2, 2, 4],
+        "status" => [Some("active"), Some("lost"), None, Some("active")],
+    }?;
+    let result_df = mlprep::engine::run_in_memory(df.lazy(), &pipeline)?;
+    assert_eq!(result_df.height(), 2);
+
+    // One row per failed check, ordered by the position of the row
+    assert_eq!(
+        std::fs::read_to_string(&details_path)?,
+        "row_index,column,check,value\n\
+         1,id,unique,2\n\
+         1,status,enum,lost\n\
+         2,id,unique,2\n\
+         2,status,enum,\n"
+    );
+
+    Ok(())
+}